@@ -0,0 +1,10 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 017
+-- Created: 2026-10-16
+-- Description: Add is_pinned flag to document_versions for version timeline metadata
+-- ============================================
+
+ALTER TABLE document_versions ADD COLUMN IF NOT EXISTS is_pinned BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN document_versions.is_pinned IS 'When true, version is pinned in the version timeline';
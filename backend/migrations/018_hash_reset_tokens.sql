@@ -0,0 +1,16 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 018
+-- Created: 2026-10-16
+-- Description: Store password reset and email verification tokens as SHA-256 hashes
+-- ============================================
+
+-- Outstanding tokens were stored in plaintext and can no longer be matched
+DELETE FROM password_resets WHERE used_at IS NULL;
+DELETE FROM email_verifications WHERE verified_at IS NULL;
+
+ALTER TABLE password_resets RENAME COLUMN token TO token_hash;
+ALTER TABLE email_verifications RENAME COLUMN token TO token_hash;
+
+COMMENT ON COLUMN password_resets.token_hash IS 'Hex-encoded SHA-256 of the reset token; the plaintext token is never stored';
+COMMENT ON COLUMN email_verifications.token_hash IS 'Hex-encoded SHA-256 of the verification token; the plaintext token is never stored';
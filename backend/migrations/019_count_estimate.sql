@@ -0,0 +1,17 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 019
+-- Created: 2026-10-16
+-- Description: Planner-based row count estimate for large paginated listings
+-- ============================================
+
+-- Returns the planner's row estimate for a SELECT without executing it.
+-- Callers must only pass queries built from trusted, already-validated values.
+CREATE OR REPLACE FUNCTION count_estimate(query TEXT) RETURNS BIGINT AS $$
+DECLARE
+    plan JSONB;
+BEGIN
+    EXECUTE 'EXPLAIN (FORMAT JSON) ' || query INTO plan;
+    RETURN (plan -> 0 -> 'Plan' ->> 'Plan Rows')::BIGINT;
+END;
+$$ LANGUAGE plpgsql;
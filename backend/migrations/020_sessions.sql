@@ -0,0 +1,33 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 020
+-- Created: 2026-10-16
+-- Description: Session registry for issued refresh tokens ("logged-in devices")
+-- ============================================
+
+-- Refresh tokens are JWTs longer than 64 characters, and 016 could not change
+-- ip_address while active_refresh_tokens depended on it. Recreate the view
+-- around both column changes so sessions can reference stored refresh tokens.
+DROP VIEW IF EXISTS active_refresh_tokens;
+
+ALTER TABLE refresh_tokens ALTER COLUMN token TYPE TEXT;
+ALTER TABLE refresh_tokens ALTER COLUMN ip_address TYPE VARCHAR(45);
+
+CREATE OR REPLACE VIEW active_refresh_tokens AS
+SELECT rt.*, u.email as user_email
+FROM refresh_tokens rt
+JOIN users u ON rt.user_id = u.id
+WHERE rt.is_revoked = false AND rt.expires_at > NOW();
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    refresh_token_id UUID NOT NULL UNIQUE REFERENCES refresh_tokens(id) ON DELETE CASCADE,
+    user_agent VARCHAR(500),
+    ip_address VARCHAR(45),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_used TIMESTAMP NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user_active ON sessions(user_id) WHERE revoked_at IS NULL;
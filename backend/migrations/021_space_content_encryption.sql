@@ -0,0 +1,10 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 021
+-- Created: 2026-10-16
+-- Description: Per-space flag for encrypting document content at rest
+-- ============================================
+
+ALTER TABLE spaces ADD COLUMN IF NOT EXISTS content_encrypted BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN spaces.content_encrypted IS 'When true, document and version content is stored as an AES-256-GCM envelope';
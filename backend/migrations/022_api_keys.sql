@@ -0,0 +1,25 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 022
+-- Created: 2026-10-16
+-- Description: API keys for programmatic access
+-- ============================================
+
+-- Only a SHA-256 hash of each key is stored; the key itself is shown once
+-- when it is created. key_prefix keeps the first characters so users can
+-- tell their keys apart.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL DEFAULT ARRAY['read'],
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP,
+    expires_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    CONSTRAINT api_keys_scopes_valid CHECK (scopes <@ ARRAY['read', 'write']::TEXT[])
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_active ON api_keys(user_id) WHERE revoked_at IS NULL;
@@ -0,0 +1,14 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 023
+-- Created: 2026-10-16
+-- Description: Allow documents to be pinned to the top of space listings
+-- ============================================
+
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS is_pinned BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS pin_order INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_documents_space_pins ON documents(space_id, pin_order) WHERE is_pinned = true;
+
+COMMENT ON COLUMN documents.is_pinned IS 'When true, document is listed before unpinned documents in its space';
+COMMENT ON COLUMN documents.pin_order IS 'Position among pinned documents, ascending; NULL when not pinned';
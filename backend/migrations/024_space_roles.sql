@@ -0,0 +1,36 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 024
+-- Created: 2026-10-16
+-- Description: Space roles as named permission sets
+-- ============================================
+
+-- permissions holds the bits of auth_service::rbac::roles::PermissionSet.
+-- Rows with a NULL space_id are the built-in roles available in every
+-- space; a space can add its own roles, which take precedence by name.
+CREATE TABLE IF NOT EXISTS space_roles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    space_id UUID REFERENCES spaces(id) ON DELETE CASCADE,
+    name VARCHAR(20) NOT NULL,
+    permissions INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_space_roles_builtin_name ON space_roles(name) WHERE space_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_space_roles_space_name ON space_roles(space_id, name) WHERE space_id IS NOT NULL;
+
+-- Built-in roles, matching the permissions they had when roles were fixed
+INSERT INTO space_roles (space_id, name, permissions) VALUES
+    (NULL, 'owner', 511),
+    (NULL, 'editor', 119),
+    (NULL, 'commenter', 17),
+    (NULL, 'viewer', 1)
+ON CONFLICT DO NOTHING;
+
+-- Memberships may now reference custom roles; names are checked against
+-- space_roles by the application
+ALTER TABLE space_memberships DROP CONSTRAINT IF EXISTS space_memberships_role_check;
+
+COMMENT ON TABLE space_roles IS 'Named permission sets assignable to space members';
+COMMENT ON COLUMN space_roles.space_id IS 'Owning space; NULL for built-in roles';
+COMMENT ON COLUMN space_roles.permissions IS 'Permission bit set, see rbac::roles::PermissionSet';
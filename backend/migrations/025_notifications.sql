@@ -0,0 +1,21 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 025
+-- Created: 2026-10-16
+-- Description: Per-user notifications with read tracking
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL DEFAULT '{}'::jsonb,
+    read_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_created ON notifications(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_notifications_user_unread ON notifications(user_id) WHERE read_at IS NULL;
+
+COMMENT ON COLUMN notifications.kind IS 'Event type, e.g. comment_reply or member_added';
+COMMENT ON COLUMN notifications.read_at IS 'When the recipient marked the notification read; NULL while unread';
@@ -0,0 +1,19 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 026
+-- Created: 2026-10-16
+-- Description: Append-only log of Yjs updates so document state survives restarts
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS document_updates (
+    id BIGSERIAL PRIMARY KEY,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    update_data BYTEA NOT NULL,
+    state_vector BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_updates_document ON document_updates(document_id, id);
+
+COMMENT ON TABLE document_updates IS 'Yjs updates in arrival order; replaying them rebuilds the document';
+COMMENT ON COLUMN document_updates.state_vector IS 'Sender state vector after applying the update';
@@ -0,0 +1,15 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 027
+-- Created: 2026-10-16
+-- Description: Per-space origins allowed to call a public space's endpoints from embedded widgets
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS space_embed_origins (
+    space_id UUID NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    origin VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (space_id, origin)
+);
+
+COMMENT ON COLUMN space_embed_origins.origin IS 'Normalized scheme://host[:port], only honored while the space is public';
@@ -0,0 +1,10 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 028
+-- Created: 2026-10-16
+-- Description: Mark compacted snapshot rows in the Yjs update log
+-- ============================================
+
+ALTER TABLE document_updates ADD COLUMN IF NOT EXISTS is_snapshot BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN document_updates.is_snapshot IS 'When true, update_data holds every update it replaced, length-prefixed, and state_vector their merged state';
@@ -0,0 +1,17 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 029
+-- Created: 2026-10-16
+-- Description: Track when each user last viewed a document for unread indicators
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS document_views (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    last_viewed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, document_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_views_document ON document_views(document_id);
+
+COMMENT ON TABLE document_views IS 'A document is unread for a user when someone else updated it after last_viewed_at';
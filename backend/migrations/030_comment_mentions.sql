@@ -0,0 +1,17 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 030
+-- Created: 2026-10-16
+-- Description: Users @mentioned in comments
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS comment_mentions (
+    comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_mentions_user ON comment_mentions(user_id);
+
+COMMENT ON TABLE comment_mentions IS 'Space members resolved from @uuid or @username tokens in comment content';
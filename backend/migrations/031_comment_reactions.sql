@@ -0,0 +1,19 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 031
+-- Created: 2026-10-16
+-- Description: Emoji reactions on comments
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS comment_reactions (
+    comment_id UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    emoji VARCHAR(16) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    -- Reacting twice with the same emoji is a no-op
+    PRIMARY KEY (comment_id, user_id, emoji)
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_reactions_comment ON comment_reactions(comment_id, emoji);
+
+COMMENT ON TABLE comment_reactions IS 'Emoji reactions; the allowed emoji are enforced by the API';
@@ -0,0 +1,14 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 032
+-- Created: 2026-10-16
+-- Description: Soft delete for spaces
+-- ============================================
+
+ALTER TABLE spaces ADD COLUMN IF NOT EXISTS is_deleted BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE spaces ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP;
+
+-- Purging deleted spaces looks rows up by deletion time
+CREATE INDEX IF NOT EXISTS idx_spaces_deleted_at ON spaces(deleted_at) WHERE is_deleted = true;
+
+COMMENT ON COLUMN spaces.is_deleted IS 'Soft deleted; hidden from listings until restored or purged';
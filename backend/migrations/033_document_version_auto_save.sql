@@ -0,0 +1,10 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 033
+-- Created: 2026-10-17
+-- Description: Mark auto-save document versions for coalescing
+-- ============================================
+
+ALTER TABLE document_versions ADD COLUMN IF NOT EXISTS is_auto_save BOOLEAN NOT NULL DEFAULT false;
+
+COMMENT ON COLUMN document_versions.is_auto_save IS 'Created by editor auto-save; overwritten by the same user''s next auto-save within the throttle window';
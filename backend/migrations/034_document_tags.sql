@@ -0,0 +1,20 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 034
+-- Created: 2026-10-17
+-- Description: Document tags
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS document_tags (
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    -- Stored trimmed and lowercased; length and count caps are enforced by the API
+    tag VARCHAR(255) NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC'),
+    PRIMARY KEY (document_id, tag)
+);
+
+-- Finding documents by tag
+CREATE INDEX IF NOT EXISTS idx_document_tags_tag ON document_tags(tag);
+
+COMMENT ON TABLE document_tags IS 'Normalized tags attached to documents';
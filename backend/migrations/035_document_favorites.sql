@@ -0,0 +1,18 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 035
+-- Created: 2026-10-17
+-- Description: Per-user favorite documents
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS document_favorites (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC'),
+    PRIMARY KEY (user_id, document_id)
+);
+
+-- Listing a user's favorites, newest first
+CREATE INDEX IF NOT EXISTS idx_document_favorites_user_created ON document_favorites(user_id, created_at DESC);
+
+COMMENT ON TABLE document_favorites IS 'Documents a user marked as favorite; listed at /me/favorites';
@@ -0,0 +1,20 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 036
+-- Created: 2026-10-17
+-- Description: Space-scoped document templates
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS document_templates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    space_id UUID NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    title VARCHAR(200) NOT NULL,
+    -- Same shape as documents.content; copied into each new document
+    content JSONB NOT NULL DEFAULT '{}',
+    created_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')
+);
+
+CREATE INDEX IF NOT EXISTS idx_document_templates_space ON document_templates(space_id, title);
+
+COMMENT ON TABLE document_templates IS 'Reusable page templates that new documents in a space can start from';
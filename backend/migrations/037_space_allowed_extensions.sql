@@ -0,0 +1,12 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 037
+-- Created: 2026-10-17
+-- Description: Per-space allowlist of upload file extensions
+-- ============================================
+
+-- NULL keeps the global MIME type checks only; otherwise uploads must have
+-- one of these extensions (stored lowercase, without the leading dot)
+ALTER TABLE spaces ADD COLUMN IF NOT EXISTS allowed_extensions TEXT[];
+
+COMMENT ON COLUMN spaces.allowed_extensions IS 'Lowercase file extensions accepted for uploads; NULL allows any extension';
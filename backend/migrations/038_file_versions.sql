@@ -0,0 +1,21 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 038
+-- Created: 2026-10-17
+-- Description: Keep a version history when a file name is re-uploaded
+-- ============================================
+
+-- Re-uploading a name already present in the same space and document adds
+-- a new row that links back to the version it replaces
+ALTER TABLE files ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE files ADD COLUMN IF NOT EXISTS previous_version_id UUID REFERENCES files(id) ON DELETE SET NULL;
+
+-- Each version is replaced at most once, so a history never forks
+CREATE UNIQUE INDEX IF NOT EXISTS idx_files_previous_version
+    ON files(previous_version_id)
+    WHERE previous_version_id IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_files_name_lookup ON files(space_id, document_id, file_name);
+
+COMMENT ON COLUMN files.version IS 'Version number within the file''s history, starting at 1';
+COMMENT ON COLUMN files.previous_version_id IS 'The version this upload replaced; NULL for the first version';
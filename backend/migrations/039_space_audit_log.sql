@@ -0,0 +1,27 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 039
+-- Created: 2026-10-17
+-- Description: Audit log of space membership and role changes
+-- ============================================
+
+-- Written in the same transaction as the membership change it records.
+-- Rows outlive the users involved, so actor and target are kept as NULL
+-- once the user is deleted.
+CREATE TABLE IF NOT EXISTS space_audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    space_id UUID NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    target_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(20) NOT NULL CHECK (action IN ('member_added', 'member_removed', 'role_changed')),
+    old_role VARCHAR(20),
+    new_role VARCHAR(20),
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')
+);
+
+-- Newest-first listing per space
+CREATE INDEX IF NOT EXISTS idx_space_audit_log_space ON space_audit_log(space_id, created_at DESC);
+
+COMMENT ON TABLE space_audit_log IS 'Who added or removed space members and changed their roles';
+COMMENT ON COLUMN space_audit_log.old_role IS 'Role before the change; NULL when a member was added';
+COMMENT ON COLUMN space_audit_log.new_role IS 'Role after the change; NULL when a member was removed';
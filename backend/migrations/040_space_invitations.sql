@@ -0,0 +1,30 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 040
+-- Created: 2026-10-17
+-- Description: Invite people to a space by email before they have an account
+-- ============================================
+
+-- An invitation is redeemed with its token by a signed-in user whose
+-- account email matches, which turns it into a membership
+CREATE TABLE IF NOT EXISTS space_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    space_id UUID NOT NULL REFERENCES spaces(id) ON DELETE CASCADE,
+    -- Stored trimmed and lowercased
+    email VARCHAR(255) NOT NULL,
+    role VARCHAR(20) NOT NULL,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT (CURRENT_TIMESTAMP AT TIME ZONE 'UTC'),
+    expires_at TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP,
+    accepted_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- One open invitation per address and space; inviting again refreshes it
+CREATE UNIQUE INDEX IF NOT EXISTS idx_space_invitations_pending
+    ON space_invitations(space_id, email)
+    WHERE accepted_at IS NULL;
+
+COMMENT ON TABLE space_invitations IS 'Pending and accepted email invitations to spaces';
+COMMENT ON COLUMN space_invitations.accepted_by IS 'Account that redeemed the invitation';
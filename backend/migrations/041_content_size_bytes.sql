@@ -0,0 +1,31 @@
+-- ============================================
+-- miniWiki Database Migration
+-- Version: 041
+-- Created: 2026-10-17
+-- Description: Measure document content size in bytes
+-- ============================================
+
+-- LENGTH counts characters, so multibyte content was reported smaller than
+-- it is stored. Every write path now measures OCTET_LENGTH of the stored
+-- JSON text, the same as this trigger.
+CREATE OR REPLACE FUNCTION update_content_size()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.content_size = OCTET_LENGTH(NEW.content::TEXT)::INTEGER;
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE OR REPLACE FUNCTION calculate_content_size(content JSONB)
+RETURNS INT AS $$
+SELECT OCTET_LENGTH(content::TEXT)::INT;
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- Recount existing documents without touching their updated_at
+ALTER TABLE documents DISABLE TRIGGER update_documents_updated_at;
+
+UPDATE documents
+SET content_size = OCTET_LENGTH(content::TEXT)
+WHERE content_size <> OCTET_LENGTH(content::TEXT);
+
+ALTER TABLE documents ENABLE TRIGGER update_documents_updated_at;
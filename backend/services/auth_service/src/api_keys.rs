@@ -0,0 +1,448 @@
+//! API keys for programmatic access
+//!
+//! Integrations authenticate with `Authorization: ApiKey <key>` instead of a
+//! user JWT. Keys are random URL-safe tokens; only their SHA-256 hash is
+//! stored, so a key is shown once when it is created. [`ApiKeyAuth`] looks the
+//! hash up, checks the key's scopes against the route and stores the owning
+//! user id in the request extensions, the same slot JWT middleware uses.
+//!
+//! Keys are managed through `/auth/api-keys`, which requires a user JWT so a
+//! leaked key cannot mint further keys.
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method},
+    web, Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::jwt::JwtService;
+use crate::models::{ApiKeyListResponse, ApiKeyResponse, CreateApiKeyRequest, CreateApiKeyResponse};
+use crate::repository::{ApiKeyRow, AuthRepository};
+use crate::sessions::{authenticate, parse_uuid};
+
+/// Authorization scheme for API keys
+pub const API_KEY_SCHEME: &str = "ApiKey ";
+
+/// Prefix on every issued key so they are easy to recognise in logs and scanners
+pub const API_KEY_PREFIX: &str = "mwk_";
+
+/// Random characters after the prefix
+pub const API_KEY_LENGTH: usize = 40;
+
+// Characters of the key kept in plaintext for display
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// Permission granted to an API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyScope {
+    Read,
+    Write,
+}
+
+impl ApiKeyScope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(Self::Read),
+            "write" => Some(Self::Write),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+
+    /// Scope needed for a request when the route does not fix one:
+    /// safe methods need `read`, everything else needs `write`
+    pub fn for_method(method: &Method) -> Self {
+        if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+}
+
+/// Authenticated API key, available from request extensions
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: Uuid,
+    pub user_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyContext {
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+}
+
+/// A newly generated key and the hash to store for it
+pub struct GeneratedApiKey {
+    pub key: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+}
+
+pub fn generate_api_key() -> GeneratedApiKey {
+    let key = format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        shared_security::generate_url_safe_token(API_KEY_LENGTH)
+    );
+    GeneratedApiKey {
+        key_prefix: key.chars().take(DISPLAY_PREFIX_LEN).collect(),
+        key_hash: hash_api_key(&key),
+        key,
+    }
+}
+
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Extract the key from an `Authorization: ApiKey <key>` header value
+pub fn extract_api_key(auth_header: &str) -> Option<&str> {
+    auth_header
+        .strip_prefix(API_KEY_SCHEME)
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Middleware accepting `Authorization: ApiKey <key>`
+///
+/// Requests without an API key pass through untouched so JWT auth keeps
+/// working on the same routes. The repository is taken from app data.
+#[derive(Clone, Copy, Default)]
+pub struct ApiKeyAuth {
+    required_scope: Option<ApiKeyScope>,
+}
+
+impl ApiKeyAuth {
+    /// Require `read` for safe methods and `write` otherwise
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `scope` for every request under the wrapped route
+    pub fn require(scope: ApiKeyScope) -> Self {
+        Self {
+            required_scope: Some(scope),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ApiKeyAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ApiKeyAuthMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ApiKeyAuthMiddleware {
+            service: Rc::new(service),
+            required_scope: self.required_scope,
+        }))
+    }
+}
+
+pub struct ApiKeyAuthMiddleware<S> {
+    service: Rc<S>,
+    required_scope: Option<ApiKeyScope>,
+}
+
+impl<S, B> Service<ServiceRequest> for ApiKeyAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let required_scope = self.required_scope.unwrap_or_else(|| ApiKeyScope::for_method(req.method()));
+
+        Box::pin(async move {
+            let key = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|h| h.to_str().ok())
+                .and_then(extract_api_key)
+                .map(str::to_string);
+
+            let key = match key {
+                Some(key) => key,
+                None => return service.call(req).await.map(ServiceResponse::map_into_left_body),
+            };
+
+            let repo = match req.app_data::<web::Data<AuthRepository>>() {
+                Some(repo) => repo.clone(),
+                None => {
+                    tracing::error!("AuthRepository not registered; cannot authenticate API key");
+                    let response = HttpResponse::InternalServerError()
+                        .json(serde_json::json!({ "error": "INTERNAL_ERROR", "message": "Internal server error" }));
+                    return Ok(req.into_response(response).map_into_right_body());
+                },
+            };
+
+            let row = match repo.authenticate_api_key(&hash_api_key(&key)).await {
+                Ok(Some(row)) => row,
+                Ok(None) => {
+                    let response = HttpResponse::Unauthorized().json(
+                        serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid or revoked API key" }),
+                    );
+                    return Ok(req.into_response(response).map_into_right_body());
+                },
+                Err(e) => {
+                    tracing::error!("Database error while authenticating API key: {}", e);
+                    let response = HttpResponse::InternalServerError()
+                        .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+                    return Ok(req.into_response(response).map_into_right_body());
+                },
+            };
+
+            let context = ApiKeyContext {
+                key_id: row.id,
+                user_id: row.user_id,
+                scopes: row.scopes,
+            };
+            if !context.has_scope(required_scope) {
+                let response = HttpResponse::Forbidden().json(serde_json::json!({
+                    "error": "INSUFFICIENT_SCOPE",
+                    "message": format!("API key lacks the '{}' scope", required_scope.as_str())
+                }));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            req.extensions_mut().insert(context.user_id);
+            req.extensions_mut().insert(context);
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+fn api_key_to_response(row: &ApiKeyRow) -> ApiKeyResponse {
+    ApiKeyResponse {
+        id: row.id.to_string(),
+        name: row.name.clone(),
+        key_prefix: row.key_prefix.clone(),
+        scopes: row.scopes.clone(),
+        created_at: row.created_at.and_utc().to_rfc3339(),
+        last_used_at: row.last_used_at.map(|t| t.and_utc().to_rfc3339()),
+        expires_at: row.expires_at.map(|t| t.and_utc().to_rfc3339()),
+    }
+}
+
+// Resolve the caller from a user JWT
+async fn authenticated_user(req: &HttpRequest, jwt_service: &JwtService) -> Result<Uuid, HttpResponse> {
+    let claims = authenticate(req, jwt_service).await?;
+    parse_uuid(&claims.user_id).ok_or_else(|| {
+        HttpResponse::Unauthorized()
+            .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }))
+    })
+}
+
+/// Create an API key; the key is only returned in this response
+pub async fn create_api_key(
+    req: HttpRequest,
+    body: web::Json<CreateApiKeyRequest>,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let user_id = match authenticated_user(&req, &jwt_service).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = body.validate() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": e.to_string() }));
+    }
+
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in &body.scopes {
+        match ApiKeyScope::parse(scope) {
+            Some(scope) if !scopes.iter().any(|s| s == scope.as_str()) => scopes.push(scope.as_str().to_string()),
+            Some(_) => {},
+            None => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "VALIDATION_ERROR",
+                    "message": format!("Unknown scope: {}", scope)
+                }));
+            },
+        }
+    }
+    if scopes.is_empty() {
+        scopes.push(ApiKeyScope::Read.as_str().to_string());
+    }
+
+    let expires_at = body
+        .expires_in_days
+        .map(|days| (Utc::now() + Duration::days(days as i64)).naive_utc());
+
+    let generated = generate_api_key();
+    match repo
+        .create_api_key(
+            &user_id,
+            &body.name,
+            &generated.key_prefix,
+            &generated.key_hash,
+            &scopes,
+            expires_at,
+        )
+        .await
+    {
+        Ok(row) => HttpResponse::Created().json(CreateApiKeyResponse {
+            key: generated.key,
+            api_key: api_key_to_response(&row),
+        }),
+        Err(e) => {
+            tracing::error!("Database error while creating API key: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
+}
+
+/// List the caller's API keys
+pub async fn list_api_keys(
+    req: HttpRequest,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let user_id = match authenticated_user(&req, &jwt_service).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    match repo.list_api_keys(&user_id).await {
+        Ok(rows) => HttpResponse::Ok().json(ApiKeyListResponse {
+            api_keys: rows.iter().map(api_key_to_response).collect(),
+        }),
+        Err(e) => {
+            tracing::error!("Database error while listing API keys: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
+}
+
+/// Revoke one of the caller's API keys
+pub async fn revoke_api_key(
+    path: web::Path<String>,
+    req: HttpRequest,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let user_id = match authenticated_user(&req, &jwt_service).await {
+        Ok(id) => id,
+        Err(response) => return response,
+    };
+
+    let key_id = match parse_uuid(&path.into_inner()) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::BadRequest()
+                .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": "Invalid API key ID" }));
+        },
+    };
+
+    match repo.revoke_api_key(&user_id, &key_id).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({ "message": "API key revoked" })),
+        Ok(false) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "NOT_FOUND", "message": "API key not found" }))
+        },
+        Err(e) => {
+            tracing::error!("Database error while revoking API key: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(scopes: &[&str]) -> ApiKeyContext {
+        ApiKeyContext {
+            key_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_generated_key_hash_matches() {
+        let generated = generate_api_key();
+        assert!(generated.key.starts_with(API_KEY_PREFIX));
+        assert_eq!(generated.key.len(), API_KEY_PREFIX.len() + API_KEY_LENGTH);
+        assert!(generated.key.starts_with(&generated.key_prefix));
+        assert_eq!(generated.key_hash, hash_api_key(&generated.key));
+        assert_ne!(generated.key_hash, hash_api_key(&generate_api_key().key));
+    }
+
+    #[test]
+    fn test_extract_api_key() {
+        assert_eq!(extract_api_key("ApiKey mwk_abc"), Some("mwk_abc"));
+        assert_eq!(extract_api_key("ApiKey "), None);
+        assert_eq!(extract_api_key("Bearer mwk_abc"), None);
+    }
+
+    #[test]
+    fn test_scope_for_method() {
+        assert_eq!(ApiKeyScope::for_method(&Method::GET), ApiKeyScope::Read);
+        assert_eq!(ApiKeyScope::for_method(&Method::HEAD), ApiKeyScope::Read);
+        assert_eq!(ApiKeyScope::for_method(&Method::POST), ApiKeyScope::Write);
+        assert_eq!(ApiKeyScope::for_method(&Method::DELETE), ApiKeyScope::Write);
+    }
+
+    #[test]
+    fn test_scope_check() {
+        let read_only = context(&["read"]);
+        assert!(read_only.has_scope(ApiKeyScope::Read));
+        assert!(!read_only.has_scope(ApiKeyScope::Write));
+
+        let read_write = context(&["read", "write"]);
+        assert!(read_write.has_scope(ApiKeyScope::Write));
+    }
+
+    #[actix_web::test]
+    async fn test_requests_without_api_key_pass_through() {
+        use actix_web::{test, App};
+
+        let app =
+            test::init_service(App::new().wrap(ApiKeyAuth::new()).route("/", web::post().to(HttpResponse::Ok))).await;
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((header::AUTHORIZATION, "Bearer some.jwt.token"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+}
@@ -1,37 +1,239 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
 use serde_json::json;
 
+use crate::jwt::JwtService;
 use crate::password_reset::{self as pr, PasswordResetRequest};
+use crate::repository::{AuthRepository, VerificationReissue, VerificationTokenRecord};
+use crate::sessions::{authenticate, parse_uuid};
 
-#[allow(dead_code)]
-#[allow(unused_variables)]
-#[actix_web::post("/verify-email/confirm")]
-async fn confirm_email_verification(
-    req: web::Json<serde_json::Value>,
-    repo: web::Data<crate::repository::AuthRepository>,
-    _jwt_service: web::Data<crate::jwt::JwtService>,
+/// Default lifetime of an email verification token in seconds
+pub const DEFAULT_VERIFICATION_TOKEN_TTL_SECS: i64 = 86400;
+
+/// Default minimum gap between verification emails for one user
+pub const DEFAULT_VERIFICATION_RESEND_COOLDOWN_SECS: i64 = 60;
+
+/// Error code returned while a resend is still cooling down
+pub const RESEND_COOLDOWN_CODE: &str = "RESEND_COOLDOWN";
+
+lazy_static::lazy_static! {
+    pub static ref VERIFICATION_CONFIG: VerificationConfig = VerificationConfig::from_env();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationConfig {
+    /// How long a verification token stays valid
+    pub ttl: chrono::Duration,
+    /// Minimum time between two verification emails
+    pub resend_cooldown: chrono::Duration,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            ttl: chrono::Duration::seconds(DEFAULT_VERIFICATION_TOKEN_TTL_SECS),
+            resend_cooldown: chrono::Duration::seconds(DEFAULT_VERIFICATION_RESEND_COOLDOWN_SECS),
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Read `EMAIL_VERIFICATION_TTL_SECS` and `EMAIL_VERIFICATION_RESEND_COOLDOWN_SECS`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let ttl_secs = std::env::var("EMAIL_VERIFICATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_VERIFICATION_TOKEN_TTL_SECS);
+        let cooldown_secs = std::env::var("EMAIL_VERIFICATION_RESEND_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v >= 0)
+            .unwrap_or(DEFAULT_VERIFICATION_RESEND_COOLDOWN_SECS);
+
+        Self {
+            ttl: chrono::Duration::seconds(ttl_secs),
+            resend_cooldown: chrono::Duration::seconds(cooldown_secs),
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of a verification token, the only form persisted
+///
+/// The raw token only ever appears in the email; confirming hashes the
+/// submitted token and looks the hash up, so the stored value is useless to
+/// anyone reading the table.
+pub fn hash_verification_token(token: &str) -> String {
+    pr::hash_reset_token(token)
+}
+
+/// Why a verification token cannot be redeemed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationTokenError {
+    NotFound,
+    Expired,
+    AlreadyVerified,
+}
+
+impl VerificationTokenError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "INVALID_TOKEN",
+            Self::Expired => "VERIFICATION_EXPIRED",
+            Self::AlreadyVerified => "ALREADY_VERIFIED",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NotFound => "Verification token not found",
+            Self::Expired => "Verification token has expired",
+            Self::AlreadyVerified => "Email address is already verified",
+        }
+    }
+}
+
+/// Reject verification tokens that were already redeemed or have expired
+pub fn check_verification_token(
+    record: &VerificationTokenRecord,
+    now: chrono::NaiveDateTime,
+) -> Result<(), VerificationTokenError> {
+    if record.verified_at.is_some() {
+        return Err(VerificationTokenError::AlreadyVerified);
+    }
+    if record.expires_at <= now {
+        return Err(VerificationTokenError::Expired);
+    }
+    Ok(())
+}
+
+/// Verify the email address a token was issued for, returning the user id
+///
+/// When the token cannot be redeemed, the stored record is inspected to tell
+/// an unknown token from an expired or already redeemed one.
+pub async fn verify_email_token(
+    repo: &AuthRepository,
+    token: &str,
+) -> Result<Result<uuid::Uuid, VerificationTokenError>, sqlx::Error> {
+    let token_hash = hash_verification_token(token);
+    let now = chrono::Utc::now().naive_utc();
+
+    if let Some(user_id) = repo.redeem_email_verification(&token_hash, now).await? {
+        return Ok(Ok(user_id));
+    }
+
+    let record = repo.find_email_verification(&token_hash).await?;
+    Ok(Err(match record {
+        Some(record) => check_verification_token(&record, now)
+            .err()
+            .unwrap_or(VerificationTokenError::AlreadyVerified),
+        None => VerificationTokenError::NotFound,
+    }))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ConfirmVerificationRequest {
+    pub token: String,
+}
+
+/// Confirm an email address with the token from the verification email
+pub async fn confirm_verification(
+    req: web::Json<ConfirmVerificationRequest>,
+    repo: web::Data<AuthRepository>,
 ) -> impl Responder {
-    let token = match req.get("token") {
-        Some(t) => t.as_str().unwrap_or_default(),
-        None => {
-            return HttpResponse::BadRequest()
-                .json(json!({ "error": "VALIDATION_ERROR", "message": "Token is required" }));
+    if let Err(e) = pr::RESET_TOKEN_CONFIG.validate_format(&req.token) {
+        return HttpResponse::BadRequest()
+            .json(json!({ "error": "VALIDATION_ERROR", "message": format!("Invalid token format. {}", e) }));
+    }
+
+    match verify_email_token(&repo, &req.token).await {
+        Ok(Ok(user_id)) => {
+            tracing::info!("Email verified for user {}", user_id);
+            HttpResponse::Ok().json(json!({ "message": "Email verified successfully" }))
+        },
+        Ok(Err(e)) => match e {
+            VerificationTokenError::NotFound => HttpResponse::NotFound(),
+            VerificationTokenError::Expired => HttpResponse::BadRequest(),
+            VerificationTokenError::AlreadyVerified => HttpResponse::Conflict(),
+        }
+        .json(json!({ "error": e.code(), "message": e.message() })),
+        Err(e) => {
+            tracing::error!("Failed to redeem verification token: {}", e);
+            HttpResponse::InternalServerError()
+                .json(json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
         },
+    }
+}
+
+/// Send the caller a fresh verification email
+///
+/// The new token replaces any pending one, so only the latest link works.
+pub async fn resend_verification(
+    req: HttpRequest,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
     };
 
-    if token.len() != 64 {
-        return HttpResponse::BadRequest().json(
-            json!({ "error": "VALIDATION_ERROR", "message": "Invalid token format. Token must be 64 characters" }),
-        );
+    let user = match parse_uuid(&claims.user_id) {
+        Some(user_id) => repo.find_by_id(&user_id).await,
+        None => Ok(None),
+    };
+    let user = match user {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return HttpResponse::Unauthorized()
+                .json(json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+        },
+        Err(e) => {
+            tracing::error!("Database error while loading user: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+        },
+    };
+
+    if user.is_email_verified {
+        return HttpResponse::Conflict()
+            .json(json!({ "error": "ALREADY_VERIFIED", "message": "Email address is already verified" }));
     }
 
-    if !token.chars().all(|c| c.is_alphanumeric()) {
-        return HttpResponse::BadRequest().json(
-            json!({ "error": "VALIDATION_ERROR", "message": "Invalid token format. Token must be alphanumeric" }),
-        );
+    let token = shared_security::generate_url_safe_token(pr::RESET_TOKEN_CONFIG.length);
+    let expires_at = chrono::Utc::now().naive_utc() + VERIFICATION_CONFIG.ttl;
+
+    match repo
+        .reissue_email_verification(
+            &user.id,
+            &hash_verification_token(&token),
+            expires_at,
+            VERIFICATION_CONFIG.resend_cooldown,
+        )
+        .await
+    {
+        Ok(VerificationReissue::Issued) => {},
+        Ok(VerificationReissue::Cooldown { retry_after_secs }) => {
+            return HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, retry_after_secs.to_string()))
+                .json(json!({
+                    "error": RESEND_COOLDOWN_CODE,
+                    "message": "A verification email was sent recently, please wait before requesting another",
+                    "retry_after": retry_after_secs
+                }));
+        },
+        Err(e) => {
+            tracing::error!("Failed to store verification token: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+        },
     }
 
-    HttpResponse::Ok().json(json!({ "message": "Email verified successfully".to_string() }))
+    // TODO: Send email with verification link
+    tracing::info!("Verification token reissued for user {}", user.id);
+    tracing::debug!("Verification token: {}", token);
+
+    HttpResponse::Ok().json(json!({ "message": "Verification email sent" }))
 }
 
 #[actix_web::post("/password/reset")]
@@ -39,8 +241,9 @@ async fn reset_password(
     req: web::Json<PasswordResetRequest>,
     repo: web::Data<crate::repository::AuthRepository>,
     _jwt_service: web::Data<crate::jwt::JwtService>,
+    password_requirements: Option<web::Data<shared_security::PasswordRequirements>>,
 ) -> impl Responder {
-    pr::reset_password(req, repo, _jwt_service).await
+    pr::reset_password(req, repo, _jwt_service, password_requirements).await
 }
 
 #[actix_web::post("/password/reset-request")]
@@ -58,3 +261,52 @@ async fn resend_verification_email(
 ) -> impl Responder {
     pr::resend_verification_email(req, repo).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(expires_in: chrono::Duration, verified: bool) -> (VerificationTokenRecord, chrono::NaiveDateTime) {
+        let now = chrono::Utc::now().naive_utc();
+        let record = VerificationTokenRecord {
+            user_id: uuid::Uuid::new_v4(),
+            token_hash: hash_verification_token("token"),
+            expires_at: now + expires_in,
+            verified_at: verified.then_some(now),
+        };
+        (record, now)
+    }
+
+    #[test]
+    fn test_hash_matches_only_the_original_token() {
+        let token = shared_security::generate_url_safe_token(64);
+        let stored = hash_verification_token(&token);
+
+        assert_ne!(stored, token);
+        assert_eq!(stored.len(), 64);
+        assert_eq!(hash_verification_token(&token), stored);
+        assert_ne!(hash_verification_token(&shared_security::generate_url_safe_token(64)), stored);
+    }
+
+    #[test]
+    fn test_check_verification_token() {
+        let (pending, now) = record(chrono::Duration::hours(1), false);
+        assert_eq!(check_verification_token(&pending, now), Ok(()));
+
+        let (expired, now) = record(chrono::Duration::seconds(-1), false);
+        assert_eq!(check_verification_token(&expired, now), Err(VerificationTokenError::Expired));
+
+        let (redeemed, now) = record(chrono::Duration::hours(1), true);
+        assert_eq!(
+            check_verification_token(&redeemed, now),
+            Err(VerificationTokenError::AlreadyVerified)
+        );
+    }
+
+    #[test]
+    fn test_verification_config_defaults() {
+        let config = VerificationConfig::default();
+        assert_eq!(config.ttl, chrono::Duration::hours(24));
+        assert_eq!(config.resend_cooldown, chrono::Duration::minutes(1));
+    }
+}
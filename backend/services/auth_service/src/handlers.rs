@@ -1,12 +1,32 @@
 use crate::jwt::JwtService;
+use crate::lockout::{FailureOutcome, LoginLockout, ACCOUNT_LOCKED_CODE};
 use crate::models::{
-    LoginRequest, LoginResponse, LogoutRequest, RefreshRequest, RefreshResponse, RegisterRequest, RegisterResponse,
+    LoginRequest, LoginResponse, LogoutRequest, MeQuery, MeResponse, RefreshRequest, RefreshResponse, RegisterRequest,
+    RegisterResponse, SpaceAccessResponse, SpaceMembershipResponse,
 };
-use crate::password::{hash_password, validate_password_strength, verify_password};
+use crate::password::{
+    hash_password_configured, validate_new_password, verify_password, BcryptCost, PasswordRequirements,
+};
+use crate::permissions::{RbacConfig, Role};
+use crate::rbac::RbacMiddleware;
 use crate::repository::AuthRepository;
+use crate::sessions::{authenticate, parse_uuid};
+use crate::token_denylist::{remaining_lifetime, TokenDenylist, TOKEN_REVOKED_CODE};
 use actix_web::{http::header, web, HttpResponse, Responder};
 use shared_models::entities::RefreshToken;
 
+/// Response extension marking a change in the caller's privileges
+///
+/// Login and logout attach it so the CSRF middleware rotates the session's
+/// tokens instead of adding another one.
+#[derive(Debug, Clone, Copy)]
+pub struct PrivilegeChange;
+
+fn with_privilege_change(mut response: HttpResponse) -> HttpResponse {
+    response.extensions_mut().insert(PrivilegeChange);
+    response
+}
+
 fn mask_email(email: &str) -> String {
     let parts: Vec<&str> = email.split('@').collect();
     if parts.len() == 2 {
@@ -21,13 +41,25 @@ fn mask_email(email: &str) -> String {
     }
 }
 
+fn account_locked_response(retry_after: u64) -> HttpResponse {
+    HttpResponse::Locked()
+        .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+        .json(serde_json::json!({
+            "error": ACCOUNT_LOCKED_CODE,
+            "message": "Account temporarily locked due to repeated failed login attempts",
+            "retry_after": retry_after
+        }))
+}
+
 pub async fn register(
     req: web::Json<RegisterRequest>,
     repo: web::Data<AuthRepository>,
     _jwt_service: web::Data<JwtService>,
+    bcrypt_cost: Option<web::Data<BcryptCost>>,
+    password_requirements: Option<web::Data<PasswordRequirements>>,
 ) -> impl Responder {
     // Hash password and create user
-    let password_hash = match hash_password(&req.password) {
+    let password_hash = match hash_password_configured(&req.password, bcrypt_cost.as_ref()) {
         Ok(hash) => hash,
         Err(e) => {
             return HttpResponse::BadRequest().json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": e }));
@@ -48,7 +80,7 @@ pub async fn register(
     }
 
     // Validate password strength
-    match validate_password_strength(&req.password) {
+    match validate_new_password(&req.password, password_requirements.as_ref()) {
         Ok(()) => {},
         Err(e) => {
             return HttpResponse::BadRequest().json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": e }));
@@ -81,6 +113,7 @@ pub async fn login(
     http_req: actix_web::HttpRequest,
     repo: web::Data<AuthRepository>,
     jwt_service: web::Data<JwtService>,
+    lockout: web::Data<LoginLockout>,
 ) -> impl Responder {
     let ip_address = http_req.connection_info().realip_remote_addr().map(|s| s.to_string());
 
@@ -104,12 +137,23 @@ pub async fn login(
         },
     };
 
+    // Reject locked accounts before checking the password
+    if let Some(retry_after) = lockout.locked_for(&user.id).await {
+        return account_locked_response(retry_after);
+    }
+
     // Verify password
     match verify_password(&req.password, &user.password_hash) {
-        Ok(true) => {},
+        Ok(true) => {
+            lockout.record_success(&user.id).await;
+        },
         Ok(false) => {
             let masked_email = mask_email(&req.email);
             tracing::warn!("Failed login attempt for email: {}", masked_email);
+            if let FailureOutcome::Locked { retry_after } = lockout.record_failure(&user.id).await {
+                tracing::warn!("Account locked after repeated failed logins: {}", masked_email);
+                return account_locked_response(retry_after);
+            }
             return HttpResponse::Unauthorized()
                 .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid email or password" }));
         },
@@ -135,15 +179,17 @@ pub async fn login(
         },
     }
 
-    // Generate tokens
-    let access_token = match jwt_service.generate_access_token(&user.id.to_string(), &user.email, "user") {
-        Ok(token) => token,
-        Err(e) => {
-            tracing::error!("Failed to generate access token: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(serde_json::json!({ "error": "INTERNAL_ERROR", "message": "Internal server error" }));
-        },
-    };
+    // Generate tokens; the access token carries the id of the new session
+    let session_id = uuid::Uuid::new_v4();
+    let access_token =
+        match jwt_service.generate_session_access_token(&user.id.to_string(), &user.email, "user", &session_id) {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::error!("Failed to generate access token: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(serde_json::json!({ "error": "INTERNAL_ERROR", "message": "Internal server error" }));
+            },
+        };
 
     let refresh_token = match jwt_service.generate_refresh_token(&user.id.to_string()) {
         Ok(token) => token,
@@ -161,8 +207,8 @@ pub async fn login(
         user_id: user.id,
         token: refresh_token.clone(),
         expires_at,
-        ip_address,
-        user_agent,
+        ip_address: ip_address.clone(),
+        user_agent: user_agent.clone(),
         is_revoked: false,
         revoked_at: None,
         created_at: chrono::Utc::now().naive_utc(),
@@ -174,10 +220,26 @@ pub async fn login(
             .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
     }
 
+    // Register the session so it shows up in the user's device list
+    if let Err(e) = repo
+        .create_session(
+            &session_id,
+            &user.id,
+            &refresh_token_record.id,
+            user_agent.as_deref(),
+            ip_address.as_deref(),
+        )
+        .await
+    {
+        tracing::error!("Failed to create session: {}", e);
+        return HttpResponse::InternalServerError()
+            .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+    }
+
     // Update last login
     repo.update_last_login(&user.id).await.ok();
 
-    HttpResponse::Ok().json(LoginResponse {
+    with_privilege_change(HttpResponse::Ok().json(LoginResponse {
         user: crate::models::UserResponse {
             id: user.id.to_string(),
             email: user.email.clone(),
@@ -188,18 +250,24 @@ pub async fn login(
         access_token,
         refresh_token,
         expires_in: jwt_service.config.access_expiry,
-    })
+    }))
 }
 
 pub async fn logout(
     req: web::Json<LogoutRequest>,
-    _http_req: actix_web::HttpRequest,
+    http_req: actix_web::HttpRequest,
     repo: web::Data<AuthRepository>,
     jwt_service: web::Data<JwtService>,
+    denylist: Option<web::Data<TokenDenylist>>,
 ) -> impl Responder {
     if let Some(refresh_token) = &req.refresh_token {
         let claims = match jwt_service.validate_token(refresh_token) {
-            Ok(claims) => claims,
+            Ok(claims) if claims.is_refresh_token() => claims,
+            Ok(_) => {
+                tracing::warn!("Non-refresh token in logout request");
+                return HttpResponse::Unauthorized()
+                    .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid refresh token" }));
+            },
             Err(e) => {
                 tracing::warn!("Invalid refresh token in logout request: {}", e);
                 return HttpResponse::Unauthorized()
@@ -249,18 +317,46 @@ pub async fn logout(
             return HttpResponse::InternalServerError()
                 .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
         }
+
+        if let (Some(denylist), Some(jti)) = (&denylist, &claims.jti) {
+            denylist.revoke(jti, remaining_lifetime(claims.exp, chrono::Utc::now())).await;
+        }
+    }
+
+    // Access tokens bound to this session stop working everywhere, not just
+    // once they expire
+    if let Some(denylist) = &denylist {
+        let access_claims = http_req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(JwtService::extract_token_from_header)
+            .and_then(|token| jwt_service.validate_token(token).ok())
+            .filter(|claims| !claims.is_refresh_token());
+        if let Some(claims) = access_claims {
+            if let Some(sid) = &claims.sid {
+                denylist.revoke_session(sid, remaining_lifetime(claims.exp, chrono::Utc::now())).await;
+            }
+        }
     }
 
-    HttpResponse::Ok().json(serde_json::json!({ "message": "Logged out successfully" }))
+    with_privilege_change(HttpResponse::Ok().json(serde_json::json!({ "message": "Logged out successfully" })))
 }
 
 pub async fn refresh(
     req: web::Json<RefreshRequest>,
     jwt_service: web::Data<JwtService>,
     repo: web::Data<AuthRepository>,
+    denylist: Option<web::Data<TokenDenylist>>,
 ) -> impl Responder {
     let claims = match jwt_service.validate_token(&req.refresh_token) {
-        Ok(claims) => claims,
+        Ok(claims) if claims.is_refresh_token() => claims,
+        Ok(_) => {
+            tracing::warn!("Non-refresh token presented to the refresh endpoint");
+            return HttpResponse::Unauthorized().json(
+                serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid or expired refresh token" }),
+            );
+        },
         Err(e) => {
             tracing::warn!("Invalid refresh token: {}", e);
             return HttpResponse::Unauthorized().json(
@@ -269,6 +365,15 @@ pub async fn refresh(
         },
     };
 
+    if let (Some(denylist), Some(jti)) = (&denylist, &claims.jti) {
+        if denylist.is_revoked(jti).await {
+            tracing::warn!("Revoked refresh token presented for user_id: {}", claims.user_id);
+            return HttpResponse::Unauthorized().json(
+                serde_json::json!({ "error": TOKEN_REVOKED_CODE, "message": "Refresh token has been revoked" }),
+            );
+        }
+    }
+
     match repo.find_refresh_token(&req.refresh_token).await {
         Ok(Some(_)) => {},
         Ok(None) => {
@@ -306,7 +411,22 @@ pub async fn refresh(
         },
     };
 
-    let new_access_token = match jwt_service.generate_access_token(&user.id.to_string(), &user.email, "user") {
+    let session_id = match repo.touch_session(&req.refresh_token).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            tracing::warn!("Failed to update session last_used: {}", e);
+            None
+        },
+    };
+
+    let token_result = match &session_id {
+        Some(session_id) => {
+            jwt_service.generate_session_access_token(&user.id.to_string(), &user.email, "user", session_id)
+        },
+        None => jwt_service.generate_access_token(&user.id.to_string(), &user.email, "user"),
+    };
+
+    let new_access_token = match token_result {
         Ok(token) => token,
         Err(e) => {
             tracing::error!("Failed to generate access token: {}", e);
@@ -321,26 +441,97 @@ pub async fn refresh(
     })
 }
 
-pub async fn me(req: actix_web::HttpRequest, jwt_service: web::Data<JwtService>) -> impl Responder {
-    let auth_header = match req.headers().get(header::AUTHORIZATION) {
-        Some(h) if h.to_str().ok().map(|s| s.starts_with("Bearer ")).unwrap_or(false) => &h.to_str().unwrap()[7..],
-        _ => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Missing or invalid authorization header" }));
+/// Current user with their effective roles and permissions
+///
+/// Pass `?space_id=<uuid>` to also get the caller's role in that space, so
+/// clients can gate UI without guessing.
+pub async fn me(
+    req: actix_web::HttpRequest,
+    query: web::Query<MeQuery>,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let include_spaces = match query.include_spaces() {
+        Ok(include_spaces) => include_spaces,
+        Err(message) => {
+            return HttpResponse::BadRequest()
+                .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": message }));
         },
     };
 
-    let claims = match jwt_service.validate_token(auth_header) {
-        Ok(c) => c,
-        Err(e) => {
-            return HttpResponse::Unauthorized()
-                .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": e.to_string() }));
+    let space = match query.space_id.as_deref() {
+        Some(space_id) => {
+            let (user_id, space_id) = match (parse_uuid(&claims.user_id), parse_uuid(space_id)) {
+                (Some(user_id), Some(space_id)) => (user_id, space_id),
+                (None, _) => {
+                    return HttpResponse::Unauthorized()
+                        .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+                },
+                (_, None) => {
+                    return HttpResponse::BadRequest()
+                        .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": "Invalid space ID" }));
+                },
+            };
+
+            let role = match repo.find_space_role(&user_id, &space_id).await {
+                Ok(role) => role.as_deref().and_then(Role::from_str),
+                Err(e) => {
+                    tracing::error!("Database error while resolving space role: {}", e);
+                    return HttpResponse::InternalServerError()
+                        .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+                },
+            };
+
+            Some(SpaceAccessResponse {
+                space_id: space_id.to_string(),
+                permissions: role.map(|r| RbacConfig::get_permissions_for_role(&r)).unwrap_or_default(),
+                role,
+            })
         },
+        None => None,
     };
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "id": claims.user_id,
-        "email": claims.email,
-        "role": claims.role
-    }))
+    // Memberships always come from the token's user, never from the query
+    let spaces = if include_spaces {
+        let user_id = match parse_uuid(&claims.user_id) {
+            Some(user_id) => user_id,
+            None => {
+                return HttpResponse::Unauthorized()
+                    .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+            },
+        };
+        match repo.list_space_roles(&user_id).await {
+            Ok(roles) => Some(
+                roles
+                    .into_iter()
+                    .map(|(space_id, role)| SpaceMembershipResponse {
+                        space_id: space_id.to_string(),
+                        role,
+                    })
+                    .collect(),
+            ),
+            Err(e) => {
+                tracing::error!("Database error while listing space memberships: {}", e);
+                return HttpResponse::InternalServerError()
+                    .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }));
+            },
+        }
+    } else {
+        None
+    };
+
+    HttpResponse::Ok().json(MeResponse {
+        permissions: RbacMiddleware::effective_permissions(&claims.role),
+        roles: vec![claims.role.clone()],
+        id: claims.user_id,
+        email: claims.email,
+        role: claims.role,
+        space,
+        spaces,
+    })
 }
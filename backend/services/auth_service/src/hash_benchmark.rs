@@ -0,0 +1,175 @@
+//! Password hashing benchmark for ops tuning
+//!
+//! `POST /admin/security/hash-benchmark` hashes a random throwaway value at
+//! a few bcrypt costs and reports how long each took on this server, so
+//! operators can pick the highest cost that still meets their login latency
+//! target. The request only carries costs: it has no field for a password,
+//! and unknown fields are rejected.
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::jwt::JwtService;
+use crate::permissions::Role;
+use crate::rbac::RbacMiddleware;
+use crate::sessions::authenticate;
+
+/// Costs measured when the request does not name any
+pub const DEFAULT_BENCHMARK_COSTS: &[u32] = &[10, 11, 12, 13];
+
+/// Lowest cost bcrypt accepts
+pub const MIN_BENCHMARK_COST: u32 = 4;
+
+/// Highest cost accepted; each step doubles the work, so this bounds the request time
+pub const MAX_BENCHMARK_COST: u32 = 14;
+
+/// Most costs measured by one request
+pub const MAX_BENCHMARK_COSTS: usize = 5;
+
+// Each cost is hashed this many times and the median reported
+const SAMPLES_PER_COST: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashBenchmarkRequest {
+    #[serde(default)]
+    pub costs: Vec<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HashBenchmarkResult {
+    pub cost: u32,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HashBenchmarkResponse {
+    pub algorithm: String,
+    pub samples_per_cost: usize,
+    pub results: Vec<HashBenchmarkResult>,
+}
+
+/// Requested costs, sorted and deduplicated, or an error message
+fn benchmark_costs(requested: &[u32]) -> Result<Vec<u32>, String> {
+    let mut costs = if requested.is_empty() {
+        DEFAULT_BENCHMARK_COSTS.to_vec()
+    } else {
+        requested.to_vec()
+    };
+    costs.sort_unstable();
+    costs.dedup();
+
+    if costs.len() > MAX_BENCHMARK_COSTS {
+        return Err(format!(
+            "At most {} costs can be measured per request",
+            MAX_BENCHMARK_COSTS
+        ));
+    }
+    if let Some(cost) = costs.iter().find(|&&c| !(MIN_BENCHMARK_COST..=MAX_BENCHMARK_COST).contains(&c)) {
+        return Err(format!(
+            "Cost {} is outside the supported range {}-{}",
+            cost, MIN_BENCHMARK_COST, MAX_BENCHMARK_COST
+        ));
+    }
+
+    Ok(costs)
+}
+
+/// Median time to hash a random value at `cost`
+fn measure_cost(cost: u32) -> Result<Duration, bcrypt::BcryptError> {
+    let mut samples = Vec::with_capacity(SAMPLES_PER_COST);
+    for _ in 0..SAMPLES_PER_COST {
+        let mut value = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut value);
+
+        let started = Instant::now();
+        bcrypt::hash(value, cost)?;
+        samples.push(started.elapsed());
+    }
+
+    samples.sort_unstable();
+    Ok(samples[SAMPLES_PER_COST / 2])
+}
+
+/// Measure bcrypt hashing time at several costs (owner role only)
+pub async fn hash_benchmark(
+    req: HttpRequest,
+    body: web::Json<HashBenchmarkRequest>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+    if !RbacMiddleware::has_role(&claims.role, Role::Owner) {
+        return HttpResponse::Forbidden()
+            .json(serde_json::json!({ "error": "FORBIDDEN", "message": "Admin permission required" }));
+    }
+
+    let costs = match benchmark_costs(&body.costs) {
+        Ok(costs) => costs,
+        Err(message) => {
+            return HttpResponse::BadRequest()
+                .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": message }));
+        },
+    };
+
+    // Hashing is CPU bound; keep it off the async workers
+    let measured = web::block(move || {
+        costs
+            .into_iter()
+            .map(|cost| {
+                measure_cost(cost).map(|duration| HashBenchmarkResult {
+                    cost,
+                    duration_ms: duration.as_secs_f64() * 1000.0,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await;
+
+    match measured {
+        Ok(Ok(results)) => HttpResponse::Ok().json(HashBenchmarkResponse {
+            algorithm: "bcrypt".to_string(),
+            samples_per_cost: SAMPLES_PER_COST,
+            results,
+        }),
+        Ok(Err(e)) => {
+            tracing::error!("Hash benchmark failed: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "INTERNAL_ERROR", "message": "Hash benchmark failed" }))
+        },
+        Err(e) => {
+            tracing::error!("Hash benchmark task failed: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "INTERNAL_ERROR", "message": "Hash benchmark failed" }))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_costs_defaults_and_normalizes() {
+        assert_eq!(benchmark_costs(&[]).unwrap(), DEFAULT_BENCHMARK_COSTS.to_vec());
+        assert_eq!(benchmark_costs(&[8, 4, 8, 6]).unwrap(), vec![4, 6, 8]);
+    }
+
+    #[test]
+    fn test_benchmark_costs_rejects_out_of_range_and_too_many() {
+        assert!(benchmark_costs(&[3]).is_err());
+        assert!(benchmark_costs(&[MAX_BENCHMARK_COST + 1]).is_err());
+        assert!(benchmark_costs(&[4, 5, 6, 7, 8, 9]).is_err());
+    }
+
+    #[test]
+    fn test_request_rejects_password_field() {
+        let parsed: Result<HashBenchmarkRequest, _> =
+            serde_json::from_value(serde_json::json!({ "costs": [4], "password": "hunter2" }));
+        assert!(parsed.is_err());
+    }
+}
@@ -0,0 +1,307 @@
+//! Who is making a request
+//!
+//! `extract_user_id` is the one place services turn a request into a user
+//! id. It takes, in order:
+//! 1. a `Uuid` put in the request extensions by authentication middleware
+//!    (e.g. for API keys),
+//! 2. the subject of a Bearer token, checked with `verify_access_token`,
+//! 3. the `X-User-Id` header, kept for backward compatibility.
+//!
+//! A Bearer token that fails verification is an error rather than a reason
+//! to fall back to the header, and so is one whose session was revoked in
+//! the `TokenDenylist` registered as app data. When a token and `X-User-Id`
+//! name different users, `IdentityMismatchPolicy` decides what happens.
+//!
+//! Handlers use `extract_request_user_id`, which verifies tokens with the
+//! `JwtConfig` registered once as app data and only reads the environment
+//! when none is registered (a service mounted on its own, as in unit tests).
+
+use actix_web::{web, HttpMessage, HttpRequest};
+use shared_errors::AppError;
+use uuid::Uuid;
+
+use crate::jwt::{verify_access_token, JwtConfig};
+use crate::token_denylist::TokenDenylist;
+
+/// Error code returned when the JWT and `X-User-Id` name different users
+pub const IDENTITY_MISMATCH_CODE: &str = "IDENTITY_MISMATCH";
+
+/// Message of the authentication error for a JWT / `X-User-Id` mismatch
+pub const IDENTITY_MISMATCH_MESSAGE: &str = "JWT subject does not match X-User-Id header";
+
+const MISSING_AUTH_MESSAGE: &str = "Missing or invalid authentication";
+
+/// What to do when a request carries a JWT and an `X-User-Id` header that
+/// disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdentityMismatchPolicy {
+    /// Reject the request with `401 IDENTITY_MISMATCH`
+    Strict,
+    /// Log a warning and use the JWT identity
+    #[default]
+    Lenient,
+}
+
+impl IdentityMismatchPolicy {
+    /// Read `IDENTITY_MISMATCH_POLICY` (`strict` or `lenient`), defaulting to
+    /// lenient for missing or unknown values
+    pub fn from_env() -> Self {
+        match std::env::var("IDENTITY_MISMATCH_POLICY") {
+            Ok(v) if v.trim().eq_ignore_ascii_case("strict") => Self::Strict,
+            _ => Self::Lenient,
+        }
+    }
+}
+
+/// Whether an authentication error came from a JWT / `X-User-Id` mismatch
+pub fn is_identity_mismatch(err: &AppError) -> bool {
+    matches!(err, AppError::AuthenticationError(msg) if msg == IDENTITY_MISMATCH_MESSAGE)
+}
+
+/// Token verification settings from the environment (see
+/// `JwtConfig::verification_from_env`), read once at startup and registered
+/// as app data
+pub fn jwt_config_from_env() -> Result<JwtConfig, AppError> {
+    JwtConfig::verification_from_env().map_err(|e| AppError::ConfigurationError(e.to_string()))
+}
+
+/// User making the request, verified with the app's `JwtConfig`
+pub async fn extract_request_user_id(req: &HttpRequest) -> Result<Uuid, AppError> {
+    extract_request_user_id_with_policy(req, IdentityMismatchPolicy::from_env()).await
+}
+
+/// `extract_request_user_id` with an explicit mismatch policy
+pub async fn extract_request_user_id_with_policy(
+    req: &HttpRequest,
+    policy: IdentityMismatchPolicy,
+) -> Result<Uuid, AppError> {
+    match req.app_data::<web::Data<JwtConfig>>() {
+        Some(config) => extract_user_id_with_policy(req, config, policy).await,
+        None => extract_user_id_with_policy(req, &jwt_config_from_env()?, policy).await,
+    }
+}
+
+/// User making the request from authentication middleware or a Bearer token
+/// only, for services that never accepted the `X-User-Id` header
+pub async fn extract_token_user_id(req: &HttpRequest) -> Result<Uuid, AppError> {
+    if let Some(user_id) = req.extensions().get::<Uuid>().copied() {
+        return Ok(user_id);
+    }
+
+    let user_id = match req.app_data::<web::Data<JwtConfig>>() {
+        Some(config) => bearer_user_id(req, config).await?,
+        None => bearer_user_id(req, &jwt_config_from_env()?).await?,
+    };
+    user_id.ok_or_else(|| AppError::AuthenticationError(MISSING_AUTH_MESSAGE.to_string()))
+}
+
+/// User making the request, with the mismatch policy from the environment
+pub async fn extract_user_id(req: &HttpRequest, config: &JwtConfig) -> Result<Uuid, AppError> {
+    extract_user_id_with_policy(req, config, IdentityMismatchPolicy::from_env()).await
+}
+
+/// User making the request, resolving a JWT / `X-User-Id` mismatch with
+/// `policy`
+pub async fn extract_user_id_with_policy(
+    req: &HttpRequest,
+    config: &JwtConfig,
+    policy: IdentityMismatchPolicy,
+) -> Result<Uuid, AppError> {
+    if let Some(user_id) = req.extensions().get::<Uuid>().copied() {
+        return Ok(user_id);
+    }
+
+    let header_user_id = req.headers().get("X-User-Id").and_then(|h| h.to_str().ok()).map(str::trim);
+
+    let jwt_user_id = match bearer_user_id(req, config).await? {
+        Some(id) => id,
+        None => {
+            let header_user_id =
+                header_user_id.ok_or_else(|| AppError::AuthenticationError(MISSING_AUTH_MESSAGE.to_string()))?;
+            return Uuid::parse_str(header_user_id)
+                .map_err(|_| AppError::AuthenticationError("Invalid X-User-Id format".to_string()));
+        },
+    };
+
+    match header_user_id {
+        Some(header_user_id) if Uuid::parse_str(header_user_id).ok() != Some(jwt_user_id) => match policy {
+            IdentityMismatchPolicy::Strict => Err(AppError::AuthenticationError(IDENTITY_MISMATCH_MESSAGE.to_string())),
+            IdentityMismatchPolicy::Lenient => {
+                tracing::warn!(
+                    jwt_user_id = %jwt_user_id,
+                    header_user_id = %header_user_id,
+                    "X-User-Id header does not match JWT subject; using JWT identity"
+                );
+                Ok(jwt_user_id)
+            },
+        },
+        _ => Ok(jwt_user_id),
+    }
+}
+
+// User ID from a Bearer token, if one is present
+async fn bearer_user_id(req: &HttpRequest, config: &JwtConfig) -> Result<Option<Uuid>, AppError> {
+    let token = match req
+        .headers()
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+    {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+
+    let claims = verify_access_token(token, config)?;
+
+    if let (Some(denylist), Some(sid)) = (req.app_data::<web::Data<TokenDenylist>>(), &claims.sid) {
+        if denylist.is_session_revoked(sid).await {
+            return Err(AppError::AuthenticationError("Session has been revoked".to_string()));
+        }
+    }
+
+    // Prefer "sub", then "user_id", ignoring empty values
+    let user_id = [claims.sub, claims.user_id]
+        .into_iter()
+        .find(|id| !id.is_empty())
+        .ok_or_else(|| AppError::AuthenticationError("JWT token missing or contains empty user ID claim".to_string()))?;
+    Uuid::parse_str(&user_id)
+        .map(Some)
+        .map_err(|_| AppError::AuthenticationError("JWT subject is not a valid user ID".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt::JwtService;
+    use actix_web::test::TestRequest;
+    use std::time::Duration;
+
+    const USER_ID: &str = "550e8400-e29b-41d4-a716-446655440000";
+    const OTHER_USER_ID: &str = "660e8400-e29b-41d4-a716-446655440000";
+
+    fn config() -> JwtConfig {
+        JwtConfig::new("identity-test-secret".to_string(), 3600, 86400)
+    }
+
+    fn bearer(user_id: &str) -> String {
+        let token = JwtService::new(config()).generate_access_token(user_id, "a@b.c", "user").unwrap();
+        format!("Bearer {}", token)
+    }
+
+    #[actix_rt::test]
+    async fn test_user_from_jwt() {
+        let req = TestRequest::get().insert_header(("Authorization", bearer(USER_ID))).to_http_request();
+
+        assert_eq!(extract_user_id(&req, &config()).await.unwrap(), Uuid::parse_str(USER_ID).unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_invalid_jwt_does_not_fall_back_to_header() {
+        let req = TestRequest::get()
+            .insert_header(("Authorization", "Bearer invalid.token.here"))
+            .insert_header(("X-User-Id", USER_ID))
+            .to_http_request();
+
+        let err = extract_user_id(&req, &config()).await.unwrap_err();
+        assert!(matches!(err, AppError::AuthenticationError(_)));
+    }
+
+    #[actix_rt::test]
+    async fn test_user_from_header_fallback() {
+        let req = TestRequest::get().insert_header(("X-User-Id", USER_ID)).to_http_request();
+        assert_eq!(extract_user_id(&req, &config()).await.unwrap(), Uuid::parse_str(USER_ID).unwrap());
+
+        let req = TestRequest::get().insert_header(("X-User-Id", "not-a-uuid")).to_http_request();
+        assert!(matches!(extract_user_id(&req, &config()).await, Err(AppError::AuthenticationError(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_extension_takes_precedence() {
+        let ext_user = Uuid::parse_str(OTHER_USER_ID).unwrap();
+        let req = TestRequest::get().insert_header(("X-User-Id", USER_ID)).to_http_request();
+        req.extensions_mut().insert(ext_user);
+
+        assert_eq!(extract_user_id(&req, &config()).await.unwrap(), ext_user);
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_auth() {
+        let req = TestRequest::get().to_http_request();
+
+        let err = extract_user_id(&req, &config()).await.unwrap_err();
+        assert!(matches!(err, AppError::AuthenticationError(_)));
+        assert!(err.to_string().contains(MISSING_AUTH_MESSAGE));
+        assert!(!is_identity_mismatch(&err));
+    }
+
+    #[actix_rt::test]
+    async fn test_revoked_session_is_rejected() {
+        let session_id = Uuid::new_v4();
+        let token = JwtService::new(config())
+            .generate_session_access_token(USER_ID, "a@b.c", "user", &session_id)
+            .unwrap();
+        let denylist = web::Data::new(TokenDenylist::new());
+        let req = TestRequest::get()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .app_data(denylist.clone())
+            .to_http_request();
+
+        assert!(extract_user_id(&req, &config()).await.is_ok());
+
+        denylist.revoke_session(&session_id.to_string(), Duration::from_secs(60)).await;
+        let err = extract_user_id(&req, &config()).await.unwrap_err();
+        assert!(matches!(err, AppError::AuthenticationError(_)));
+    }
+
+    #[actix_rt::test]
+    async fn test_refresh_token_is_not_an_identity() {
+        let token = JwtService::new(config()).generate_refresh_token(USER_ID).unwrap();
+        let req = TestRequest::get()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request();
+
+        let err = extract_user_id(&req, &config()).await.unwrap_err();
+        assert!(matches!(err, AppError::AuthenticationError(_)));
+    }
+
+    #[actix_rt::test]
+    async fn test_registered_config_is_used() {
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer(USER_ID)))
+            .app_data(web::Data::new(config()))
+            .to_http_request();
+
+        assert_eq!(extract_request_user_id(&req).await.unwrap(), Uuid::parse_str(USER_ID).unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_token_only_ignores_header() {
+        let config = web::Data::new(config());
+        let req = TestRequest::get()
+            .insert_header(("X-User-Id", USER_ID))
+            .app_data(config.clone())
+            .to_http_request();
+        assert!(matches!(extract_token_user_id(&req).await, Err(AppError::AuthenticationError(_))));
+
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer(USER_ID)))
+            .insert_header(("X-User-Id", OTHER_USER_ID))
+            .app_data(config)
+            .to_http_request();
+        assert_eq!(extract_token_user_id(&req).await.unwrap(), Uuid::parse_str(USER_ID).unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_mismatch_policy() {
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer(USER_ID)))
+            .insert_header(("X-User-Id", OTHER_USER_ID))
+            .to_http_request();
+
+        let err = extract_user_id_with_policy(&req, &config(), IdentityMismatchPolicy::Strict).await.unwrap_err();
+        assert!(is_identity_mismatch(&err));
+
+        let user_id = extract_user_id_with_policy(&req, &config(), IdentityMismatchPolicy::Lenient).await.unwrap();
+        assert_eq!(user_id, Uuid::parse_str(USER_ID).unwrap());
+    }
+}
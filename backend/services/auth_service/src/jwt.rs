@@ -1,6 +1,8 @@
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_errors::AppError;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -12,20 +14,72 @@ pub enum JwtError {
     ValidationError(String),
     #[error("Token decoding error: {0}")]
     DecodingError(String),
+    #[error("Key configuration error: {0}")]
+    KeyError(String),
 }
 
 const BEARER_PREFIX: &str = "Bearer ";
 
+/// HS256 secret used when `JWT_SECRET` is unset in debug builds
+pub const TEST_JWT_SECRET: &str = "test-secret-key-for-testing-only-do-not-use-in-production";
+
+/// `token_type` claim of refresh tokens
+pub const REFRESH_TOKEN_TYPE: &str = "refresh";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
+    #[serde(default)]
     pub sub: String,
+    #[serde(default)]
     pub user_id: String,
+    #[serde(default)]
     pub email: String,
+    #[serde(default)]
     pub role: String,
     pub exp: usize,
+    #[serde(default)]
     pub iat: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jti: Option<String>, // JWT ID for token uniqueness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>, // Session the access token was issued for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>, // "refresh" on refresh tokens
+}
+
+impl Claims {
+    /// Whether these are refresh token claims, which carry a `jti` (older
+    /// refresh tokens have no `token_type`)
+    pub fn is_refresh_token(&self) -> bool {
+        self.jti.is_some() || self.token_type.as_deref() == Some(REFRESH_TOKEN_TYPE)
+    }
+}
+
+/// Signing algorithm for issued tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    /// HMAC with the shared `secret`
+    #[default]
+    HS256,
+    /// RSA signatures; other services only need the public key to verify
+    RS256,
+}
+
+impl JwtAlgorithm {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_uppercase().as_str() {
+            "HS256" => Some(Self::HS256),
+            "RS256" => Some(Self::RS256),
+            _ => None,
+        }
+    }
+
+    pub fn as_algorithm(self) -> Algorithm {
+        match self {
+            Self::HS256 => Algorithm::HS256,
+            Self::RS256 => Algorithm::RS256,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +87,40 @@ pub struct JwtConfig {
     pub secret: String,
     pub access_expiry: i64,
     pub refresh_expiry: i64,
+    pub algorithm: JwtAlgorithm,
+    /// PEM-encoded RSA private key used to sign RS256 tokens
+    pub private_key_pem: Option<String>,
+    /// PEM-encoded RSA public key used to verify RS256 tokens
+    pub public_key_pem: Option<String>,
+    /// `kid` header for issued tokens; derived from the key when unset
+    pub key_id: Option<String>,
+    /// Retired HS256 secrets whose tokens are still accepted until each one's
+    /// `retire_after`
+    pub previous_secrets: Vec<PreviousSecret>,
+}
+
+/// A retired HS256 secret and the instant it stops verifying tokens
+///
+/// Set `retire_after` to the rotation time plus the refresh token lifetime:
+/// every token the old secret signed has expired by then, and a leaked old
+/// secret is useless afterwards.
+#[derive(Debug, Clone)]
+pub struct PreviousSecret {
+    pub secret: String,
+    pub retire_after: DateTime<Utc>,
+}
+
+impl PreviousSecret {
+    pub fn new(secret: impl Into<String>, retire_after: DateTime<Utc>) -> Self {
+        Self {
+            secret: secret.into(),
+            retire_after,
+        }
+    }
+
+    fn is_accepted(&self, now: DateTime<Utc>) -> bool {
+        now < self.retire_after
+    }
 }
 
 impl JwtConfig {
@@ -41,20 +129,235 @@ impl JwtConfig {
             secret,
             access_expiry,
             refresh_expiry,
+            algorithm: JwtAlgorithm::HS256,
+            private_key_pem: None,
+            public_key_pem: None,
+            key_id: None,
+            previous_secrets: Vec::new(),
         }
     }
+
+    /// Config for RS256 signing with a PEM key pair
+    pub fn rs256(private_key_pem: String, public_key_pem: String, access_expiry: i64, refresh_expiry: i64) -> Self {
+        Self {
+            algorithm: JwtAlgorithm::RS256,
+            private_key_pem: Some(private_key_pem),
+            public_key_pem: Some(public_key_pem),
+            ..Self::new(String::new(), access_expiry, refresh_expiry)
+        }
+    }
+
+    /// Apply `JWT_ALGORITHM`, `JWT_PRIVATE_KEY_PATH`, `JWT_PUBLIC_KEY_PATH`,
+    /// `JWT_KEY_ID` and `JWT_PREVIOUS_SECRETS` (comma-separated
+    /// `secret@RFC3339` entries) from the environment; HS256 stays the default
+    pub fn with_env_keys(mut self) -> Result<Self, JwtError> {
+        if let Ok(algorithm) = std::env::var("JWT_ALGORITHM") {
+            self.algorithm = JwtAlgorithm::parse(&algorithm)
+                .ok_or_else(|| JwtError::KeyError(format!("unsupported JWT_ALGORITHM: {}", algorithm)))?;
+        }
+
+        if self.algorithm == JwtAlgorithm::RS256 {
+            self.private_key_pem = Some(read_pem_from_env("JWT_PRIVATE_KEY_PATH")?);
+            self.public_key_pem = Some(read_pem_from_env("JWT_PUBLIC_KEY_PATH")?);
+        }
+
+        if let Ok(key_id) = std::env::var("JWT_KEY_ID") {
+            if !key_id.trim().is_empty() {
+                self.key_id = Some(key_id.trim().to_string());
+            }
+        }
+
+        if let Some(previous) = previous_secrets_from_env()? {
+            self.previous_secrets = previous;
+        }
+
+        Ok(self)
+    }
+
+    /// Settings for services that only verify access tokens, from
+    /// `JWT_ALGORITHM`, `JWT_SECRET` (HS256), `JWT_PUBLIC_KEY_PATH` (RS256)
+    /// and `JWT_PREVIOUS_SECRETS`; no private key is needed
+    pub fn verification_from_env() -> Result<Self, JwtError> {
+        let algorithm = match std::env::var("JWT_ALGORITHM") {
+            Ok(algorithm) => JwtAlgorithm::parse(&algorithm)
+                .ok_or_else(|| JwtError::KeyError(format!("unsupported JWT_ALGORITHM: {}", algorithm)))?,
+            Err(_) => JwtAlgorithm::HS256,
+        };
+
+        let mut config = match algorithm {
+            JwtAlgorithm::HS256 => Self::new(secret_from_env()?, 0, 0),
+            JwtAlgorithm::RS256 => Self {
+                algorithm: JwtAlgorithm::RS256,
+                public_key_pem: Some(read_pem_from_env("JWT_PUBLIC_KEY_PATH")?),
+                ..Self::new(String::new(), 0, 0)
+            },
+        };
+        config.previous_secrets = previous_secrets_from_env()?.unwrap_or_default();
+        Ok(config)
+    }
+}
+
+// JWT_SECRET, falling back to the test secret in debug builds only
+fn secret_from_env() -> Result<String, JwtError> {
+    match std::env::var("JWT_SECRET") {
+        Ok(secret) => Ok(secret),
+        #[cfg(debug_assertions)]
+        Err(_) => {
+            tracing::warn!("JWT_SECRET is not set; using the test secret. Set JWT_SECRET in production!");
+            Ok(TEST_JWT_SECRET.to_string())
+        },
+        #[cfg(not(debug_assertions))]
+        Err(_) => Err(JwtError::KeyError("JWT_SECRET must be set".to_string())),
+    }
+}
+
+fn previous_secrets_from_env() -> Result<Option<Vec<PreviousSecret>>, JwtError> {
+    match std::env::var("JWT_PREVIOUS_SECRETS") {
+        Ok(previous) => parse_previous_secrets(&previous).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+// Comma-separated `secret@retire-after` entries, the time in RFC 3339. An
+// entry without a retire-after time is an error rather than a secret that is
+// accepted forever.
+fn parse_previous_secrets(value: &str) -> Result<Vec<PreviousSecret>, JwtError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (secret, retire_after) = entry
+                .rsplit_once('@')
+                .and_then(|(secret, at)| Some((secret, DateTime::parse_from_rfc3339(at).ok()?)))
+                .ok_or_else(|| {
+                    JwtError::KeyError("JWT_PREVIOUS_SECRETS entries must be secret@RFC3339 retire-after".to_string())
+                })?;
+            Ok(PreviousSecret::new(secret, retire_after.with_timezone(&Utc)))
+        })
+        .collect()
+}
+
+fn read_pem_from_env(var: &str) -> Result<String, JwtError> {
+    let path = std::env::var(var).map_err(|_| JwtError::KeyError(format!("{} must be set for RS256", var)))?;
+    std::fs::read_to_string(&path).map_err(|e| JwtError::KeyError(format!("failed to read {}: {}", path, e)))
+}
+
+/// Key used to verify tokens carrying a given `kid`
+struct VerificationKey {
+    kid: String,
+    key: DecodingKey,
+    algorithm: Algorithm,
+    /// Secondary keys stop verifying after this instant; `None` never expires
+    accepted_until: Option<DateTime<Utc>>,
+}
+
+impl VerificationKey {
+    fn is_accepted(&self, now: DateTime<Utc>) -> bool {
+        self.accepted_until.is_none_or(|until| now < until)
+    }
 }
 
 pub struct JwtService {
     pub config: JwtConfig,
+    signing_key: EncodingKey,
+    key_id: String,
+    verification_keys: Vec<VerificationKey>,
 }
 
 impl JwtService {
+    /// Create a service from `config`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the RS256 key material is missing or invalid; use
+    /// [`JwtService::try_new`] to handle that case.
     pub fn new(config: JwtConfig) -> Self {
-        Self { config }
+        Self::try_new(config).expect("Invalid JWT key configuration")
+    }
+
+    pub fn try_new(config: JwtConfig) -> Result<Self, JwtError> {
+        let (signing_key, primary) = primary_keys(&config)?;
+
+        let mut verification_keys = vec![primary];
+        for previous in &config.previous_secrets {
+            let kid = derive_key_id(&previous.secret);
+            if verification_keys.iter().any(|k| k.kid == kid) {
+                continue;
+            }
+            verification_keys.push(VerificationKey {
+                kid,
+                key: DecodingKey::from_secret(previous.secret.as_bytes()),
+                algorithm: Algorithm::HS256,
+                accepted_until: Some(previous.retire_after),
+            });
+        }
+
+        Ok(Self {
+            key_id: verification_keys[0].kid.clone(),
+            config,
+            signing_key,
+            verification_keys,
+        })
+    }
+
+    /// Switch signing to `new_primary`. The previous primary key keeps
+    /// verifying for the refresh token lifetime, so tokens already issued stay
+    /// valid until they would have expired anyway.
+    pub fn rotate(&mut self, new_primary: JwtConfig) -> Result<(), JwtError> {
+        let grace = Duration::seconds(self.config.refresh_expiry);
+        self.rotate_with_grace(new_primary, grace)
+    }
+
+    /// Like [`JwtService::rotate`] with an explicit grace window for the old key
+    pub fn rotate_with_grace(&mut self, new_primary: JwtConfig, grace: Duration) -> Result<(), JwtError> {
+        let (signing_key, primary) = primary_keys(&new_primary)?;
+        if primary.kid == self.key_id {
+            return Err(JwtError::KeyError(
+                "new primary key must differ from the current key".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        self.verification_keys.retain(|k| k.kid != primary.kid && k.is_accepted(now));
+        if let Some(previous) = self.verification_keys.first_mut() {
+            previous.accepted_until = Some(now + grace);
+        }
+        self.verification_keys.insert(0, primary);
+
+        self.key_id = self.verification_keys[0].kid.clone();
+        self.signing_key = signing_key;
+        self.config = new_primary;
+        Ok(())
+    }
+
+    /// `kid` header placed on newly issued tokens
+    pub fn key_id(&self) -> &str {
+        &self.key_id
     }
 
     pub fn generate_access_token(&self, user_id: &str, email: &str, role: &str) -> Result<String, JwtError> {
+        self.build_access_token(user_id, email, role, None)
+    }
+
+    /// Generate an access token bound to a session in the session registry
+    pub fn generate_session_access_token(
+        &self,
+        user_id: &str,
+        email: &str,
+        role: &str,
+        session_id: &Uuid,
+    ) -> Result<String, JwtError> {
+        self.build_access_token(user_id, email, role, Some(session_id.to_string()))
+    }
+
+    fn build_access_token(
+        &self,
+        user_id: &str,
+        email: &str,
+        role: &str,
+        sid: Option<String>,
+    ) -> Result<String, JwtError> {
         let now = Utc::now();
         let expiry = now + Duration::seconds(self.config.access_expiry);
 
@@ -66,14 +369,11 @@ impl JwtService {
             exp: expiry.timestamp() as usize,
             iat: now.timestamp() as usize,
             jti: None, // Access tokens don't need JTI
+            sid,
+            token_type: None,
         };
 
-        encode(
-            &Header::new(Algorithm::HS256),
-            &claims,
-            &EncodingKey::from_secret(self.config.secret.as_bytes()),
-        )
-        .map_err(|e| JwtError::GenerationError(e.to_string()))
+        self.sign(&claims)
     }
 
     pub fn generate_refresh_token(&self, user_id: &str) -> Result<String, JwtError> {
@@ -89,22 +389,43 @@ impl JwtService {
             exp: expiry.timestamp() as usize,
             iat: now.timestamp() as usize,
             jti: Some(jti),
+            sid: None,
+            token_type: Some(REFRESH_TOKEN_TYPE.to_string()),
         };
 
-        encode(
-            &Header::new(Algorithm::HS256),
-            &claims,
-            &EncodingKey::from_secret(self.config.secret.as_bytes()),
-        )
-        .map_err(|e| JwtError::GenerationError(e.to_string()))
+        self.sign(&claims)
+    }
+
+    fn sign(&self, claims: &Claims) -> Result<String, JwtError> {
+        let mut header = Header::new(self.config.algorithm.as_algorithm());
+        header.kid = Some(self.key_id.clone());
+
+        encode(&header, claims, &self.signing_key).map_err(|e| JwtError::GenerationError(e.to_string()))
     }
 
     pub fn validate_token(&self, token: &str) -> Result<Claims, JwtError> {
-        let decoding_key = DecodingKey::from_secret(self.config.secret.as_bytes());
+        let header = decode_header(token).map_err(|e| JwtError::ValidationError(e.to_string()))?;
+
+        // Tokens issued before key ids were added carry no kid; they can only
+        // have been signed with the primary key
+        let verification_key = match header.kid {
+            Some(kid) => self
+                .verification_keys
+                .iter()
+                .find(|k| k.kid == kid && k.is_accepted(Utc::now()))
+                .ok_or_else(|| JwtError::ValidationError(format!("Unknown key id: {}", kid)))?,
+            None => &self.verification_keys[0],
+        };
 
-        decode::<Claims>(token, &decoding_key, &Validation::new(Algorithm::HS256))
-            .map(|data| data.claims)
-            .map_err(|e| JwtError::ValidationError(e.to_string()))
+        // Validation pins the key's algorithm, so a token signed with a
+        // different one is rejected before its signature is checked
+        decode::<Claims>(
+            token,
+            &verification_key.key,
+            &Validation::new(verification_key.algorithm),
+        )
+        .map(|data| data.claims)
+        .map_err(|e| JwtError::ValidationError(e.to_string()))
     }
 
     pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
@@ -116,14 +437,430 @@ impl JwtService {
     }
 }
 
+// Signing key and primary verification key described by `config`
+fn primary_keys(config: &JwtConfig) -> Result<(EncodingKey, VerificationKey), JwtError> {
+    let (signing_key, decoding_key, key_material) = match config.algorithm {
+        JwtAlgorithm::HS256 => (
+            EncodingKey::from_secret(config.secret.as_bytes()),
+            DecodingKey::from_secret(config.secret.as_bytes()),
+            config.secret.clone(),
+        ),
+        JwtAlgorithm::RS256 => {
+            let private_pem = config
+                .private_key_pem
+                .as_deref()
+                .ok_or_else(|| JwtError::KeyError("RS256 requires a private key".to_string()))?;
+            let public_pem = config
+                .public_key_pem
+                .as_deref()
+                .ok_or_else(|| JwtError::KeyError("RS256 requires a public key".to_string()))?;
+            (
+                EncodingKey::from_rsa_pem(private_pem.as_bytes()).map_err(|e| JwtError::KeyError(e.to_string()))?,
+                DecodingKey::from_rsa_pem(public_pem.as_bytes()).map_err(|e| JwtError::KeyError(e.to_string()))?,
+                public_pem.trim().to_string(),
+            )
+        },
+    };
+
+    let verification_key = VerificationKey {
+        kid: config.key_id.clone().unwrap_or_else(|| derive_key_id(&key_material)),
+        key: decoding_key,
+        algorithm: config.algorithm.as_algorithm(),
+        accepted_until: None,
+    };
+    Ok((signing_key, verification_key))
+}
+
+// Short fingerprint of the verification key material, used as the default kid
+fn derive_key_id(key_material: &str) -> String {
+    hex::encode(&Sha256::digest(key_material.as_bytes())[..8])
+}
+
+// Keys `config` verifies with: its primary key, then any retired HS256
+// secrets not yet past their retire-after time. Unlike `primary_keys` this
+// needs no private key.
+fn verification_keys(config: &JwtConfig) -> Result<Vec<(DecodingKey, Algorithm)>, JwtError> {
+    let primary = match config.algorithm {
+        JwtAlgorithm::HS256 => DecodingKey::from_secret(config.secret.as_bytes()),
+        JwtAlgorithm::RS256 => {
+            let public_pem = config
+                .public_key_pem
+                .as_deref()
+                .ok_or_else(|| JwtError::KeyError("RS256 requires a public key".to_string()))?;
+            DecodingKey::from_rsa_pem(public_pem.as_bytes()).map_err(|e| JwtError::KeyError(e.to_string()))?
+        },
+    };
+
+    let now = Utc::now();
+    let mut keys = vec![(primary, config.algorithm.as_algorithm())];
+    keys.extend(
+        config
+            .previous_secrets
+            .iter()
+            .filter(|previous| previous.is_accepted(now))
+            .map(|previous| (DecodingKey::from_secret(previous.secret.as_bytes()), Algorithm::HS256)),
+    );
+    Ok(keys)
+}
+
+/// Verify an access token against `config` and return its claims
+///
+/// Only the algorithms `config` has keys for are allowed: its own, plus
+/// HS256 for `previous_secrets`. The token's `alg` header is checked against
+/// that list before any signature is, so `alg: none` tokens and tokens
+/// signed with another algorithm (such as HS256 keyed with an RS256 public
+/// key) are rejected. Refresh tokens are signed with the same keys but are
+/// only good at the refresh endpoint, so they are rejected here too.
+/// Services extracting a user from a Bearer token should go through here
+/// rather than decoding tokens themselves.
+pub fn verify_access_token(token: &str, config: &JwtConfig) -> Result<Claims, AppError> {
+    let header = decode_header(token)
+        .map_err(|e| AppError::AuthenticationError(format!("Invalid JWT token: {}", e)))?;
+    let keys = verification_keys(config).map_err(|e| AppError::ConfigurationError(e.to_string()))?;
+
+    if !keys.iter().any(|(_, algorithm)| *algorithm == header.alg) {
+        return Err(AppError::AuthenticationError(format!(
+            "JWT algorithm {:?} is not accepted",
+            header.alg
+        )));
+    }
+
+    let mut last_error = None;
+    for (key, algorithm) in keys.iter().filter(|(_, algorithm)| *algorithm == header.alg) {
+        let mut validation = Validation::new(*algorithm);
+        validation.algorithms = vec![*algorithm];
+        match decode::<Claims>(token, key, &validation) {
+            Ok(data) if data.claims.is_refresh_token() => {
+                return Err(AppError::AuthenticationError(
+                    "Refresh tokens cannot be used as access tokens".to_string(),
+                ));
+            },
+            Ok(data) => return Ok(data.claims),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(AppError::AuthenticationError(format!(
+        "Invalid JWT token: {}",
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    )))
+}
+
 /// Generate a JWT token for testing purposes.
 /// Uses a hardcoded test secret for simplicity in tests.
 pub fn generate_jwt_token(user_id: Uuid, email: &str) -> Result<String, JwtError> {
-    let config = JwtConfig::new(
-        "test-secret-key-for-testing-only-do-not-use-in-production".to_string(),
-        3600,
-        86400,
-    );
+    let config = JwtConfig::new(TEST_JWT_SECRET.to_string(), 3600, 86400);
     let service = JwtService::new(config);
     service.generate_access_token(&user_id.to_string(), email, "user")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY_PEM: &str = include_str!("../tests/fixtures/jwt_rs256_private.pem");
+    const PUBLIC_KEY_PEM: &str = include_str!("../tests/fixtures/jwt_rs256_public.pem");
+
+    fn rs256_service() -> JwtService {
+        JwtService::new(JwtConfig::rs256(
+            PRIVATE_KEY_PEM.to_string(),
+            PUBLIC_KEY_PEM.to_string(),
+            3600,
+            86400,
+        ))
+    }
+
+    #[test]
+    fn test_rs256_roundtrip_with_kid() {
+        let service = rs256_service();
+        let token = service.generate_access_token("user-123", "user@example.com", "editor").unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some(service.key_id()));
+
+        let claims = service.validate_token(&token).unwrap();
+        assert_eq!(claims.sub, "user-123");
+        assert_eq!(claims.role, "editor");
+    }
+
+    #[test]
+    fn test_rs256_token_verifies_with_public_key_only() {
+        let token = rs256_service()
+            .generate_access_token("user-123", "user@example.com", "user")
+            .unwrap();
+
+        let claims = decode::<Claims>(
+            &token,
+            &DecodingKey::from_rsa_pem(PUBLIC_KEY_PEM.as_bytes()).unwrap(),
+            &Validation::new(Algorithm::RS256),
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(claims.user_id, "user-123");
+    }
+
+    #[test]
+    fn test_hs256_token_rejected_under_rs256() {
+        let hs256 = JwtService::new(JwtConfig::new("shared-secret".to_string(), 3600, 86400));
+        let token = hs256.generate_access_token("user-123", "user@example.com", "user").unwrap();
+
+        let rs256 = rs256_service();
+        assert!(rs256.validate_token(&token).is_err());
+
+        // Also rejected when the kid is forged to match the RS256 key
+        let claims = hs256.validate_token(&token).unwrap();
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(rs256.key_id().to_string());
+        let forged = encode(&header, &claims, &EncodingKey::from_secret(b"shared-secret")).unwrap();
+        assert!(rs256.validate_token(&forged).is_err());
+    }
+
+    #[test]
+    fn test_hs256_remains_default_and_accepts_tokens_without_kid() {
+        let config = JwtConfig::new("shared-secret".to_string(), 3600, 86400);
+        assert_eq!(config.algorithm, JwtAlgorithm::HS256);
+        let service = JwtService::new(config);
+
+        let claims = service
+            .validate_token(&service.generate_access_token("user-123", "a@b.c", "user").unwrap())
+            .unwrap();
+
+        // Legacy token issued without a kid header
+        let legacy = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"shared-secret"),
+        )
+        .unwrap();
+        assert_eq!(service.validate_token(&legacy).unwrap().sub, "user-123");
+    }
+
+    #[test]
+    fn test_rs256_requires_key_material() {
+        let config = JwtConfig {
+            public_key_pem: None,
+            ..JwtConfig::rs256(PRIVATE_KEY_PEM.to_string(), String::new(), 3600, 86400)
+        };
+        assert!(matches!(JwtService::try_new(config), Err(JwtError::KeyError(_))));
+
+        let config = JwtConfig::rs256("not a key".to_string(), PUBLIC_KEY_PEM.to_string(), 3600, 86400);
+        assert!(matches!(JwtService::try_new(config), Err(JwtError::KeyError(_))));
+    }
+
+    fn hs256_service(secret: &str) -> JwtService {
+        JwtService::new(JwtConfig::new(secret.to_string(), 3600, 86400))
+    }
+
+    #[test]
+    fn test_old_key_token_validates_after_rotation() {
+        let mut service = hs256_service("old-secret");
+        let old_token = service.generate_access_token("user-123", "a@b.c", "user").unwrap();
+        let old_kid = service.key_id().to_string();
+
+        service.rotate(JwtConfig::new("new-secret".to_string(), 3600, 86400)).unwrap();
+        assert_ne!(service.key_id(), old_kid);
+
+        // Tokens issued before the rotation still verify
+        assert_eq!(service.validate_token(&old_token).unwrap().sub, "user-123");
+
+        // New tokens are signed with the new primary key
+        let new_token = service.generate_access_token("user-456", "a@b.c", "user").unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some(service.key_id())
+        );
+        assert!(hs256_service("old-secret").validate_token(&new_token).is_err());
+        assert_eq!(
+            hs256_service("new-secret").validate_token(&new_token).unwrap().sub,
+            "user-456"
+        );
+    }
+
+    #[test]
+    fn test_unknown_key_token_fails() {
+        let mut service = hs256_service("old-secret");
+        service.rotate(JwtConfig::new("new-secret".to_string(), 3600, 86400)).unwrap();
+
+        let foreign = hs256_service("someone-elses-secret")
+            .generate_access_token("user-123", "a@b.c", "user")
+            .unwrap();
+        assert!(service.validate_token(&foreign).is_err());
+    }
+
+    #[test]
+    fn test_old_key_rejected_after_grace_window() {
+        let mut service = hs256_service("old-secret");
+        let old_token = service.generate_access_token("user-123", "a@b.c", "user").unwrap();
+
+        service
+            .rotate_with_grace(JwtConfig::new("new-secret".to_string(), 3600, 86400), Duration::zero())
+            .unwrap();
+
+        assert!(service.validate_token(&old_token).is_err());
+    }
+
+    #[test]
+    fn test_rotation_from_hs256_to_rs256_keeps_old_tokens() {
+        let mut service = hs256_service("old-secret");
+        let old_token = service.generate_access_token("user-123", "a@b.c", "user").unwrap();
+
+        service
+            .rotate(JwtConfig::rs256(
+                PRIVATE_KEY_PEM.to_string(),
+                PUBLIC_KEY_PEM.to_string(),
+                3600,
+                86400,
+            ))
+            .unwrap();
+
+        assert!(service.validate_token(&old_token).is_ok());
+        let new_token = service.generate_access_token("user-123", "a@b.c", "user").unwrap();
+        assert_eq!(decode_header(&new_token).unwrap().alg, Algorithm::RS256);
+        assert!(service.validate_token(&new_token).is_ok());
+    }
+
+    #[test]
+    fn test_rotate_to_same_key_is_rejected() {
+        let mut service = hs256_service("same-secret");
+        let result = service.rotate(JwtConfig::new("same-secret".to_string(), 3600, 86400));
+        assert!(matches!(result, Err(JwtError::KeyError(_))));
+    }
+
+    #[test]
+    fn test_previous_secrets_are_accepted() {
+        let old_token = hs256_service("old-secret")
+            .generate_access_token("user-123", "a@b.c", "user")
+            .unwrap();
+
+        let config = JwtConfig {
+            previous_secrets: vec![PreviousSecret::new("old-secret", Utc::now() + Duration::days(1))],
+            ..JwtConfig::new("new-secret".to_string(), 3600, 86400)
+        };
+        assert!(JwtService::new(config).validate_token(&old_token).is_ok());
+    }
+
+    #[test]
+    fn test_retired_previous_secrets_are_rejected() {
+        let old_token = hs256_service("old-secret")
+            .generate_access_token("user-123", "a@b.c", "user")
+            .unwrap();
+
+        let config = JwtConfig {
+            previous_secrets: vec![PreviousSecret::new("old-secret", Utc::now() - Duration::seconds(1))],
+            ..JwtConfig::new("new-secret".to_string(), 3600, 86400)
+        };
+        assert!(verify_access_token(&old_token, &config).is_err());
+        assert!(JwtService::new(config).validate_token(&old_token).is_err());
+    }
+
+    #[test]
+    fn test_parse_previous_secrets_requires_retire_after() {
+        let parsed = parse_previous_secrets("old@secret@2030-01-01T00:00:00Z, other@2029-06-01T12:00:00+02:00");
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].secret, "old@secret");
+        assert_eq!(parsed[1].retire_after.to_rfc3339(), "2029-06-01T10:00:00+00:00");
+
+        assert!(matches!(parse_previous_secrets("old-secret"), Err(JwtError::KeyError(_))));
+        assert!(matches!(parse_previous_secrets("old-secret@tomorrow"), Err(JwtError::KeyError(_))));
+    }
+
+    // {"alg":"none","typ":"JWT"}.{"sub":"user-123","exp":4102444800}. with no signature
+    const ALG_NONE_TOKEN: &str =
+        "eyJhbGciOiJub25lIiwidHlwIjoiSldUIn0.eyJzdWIiOiJ1c2VyLTEyMyIsImV4cCI6NDEwMjQ0NDgwMH0.";
+
+    #[test]
+    fn test_verify_access_token_accepts_configured_algorithm() {
+        let token = hs256_service("secret").generate_access_token("user-123", "a@b.c", "user").unwrap();
+        let claims = verify_access_token(&token, &JwtConfig::new("secret".to_string(), 3600, 86400)).unwrap();
+        assert_eq!(claims.sub, "user-123");
+
+        let token = rs256_service().generate_access_token("user-123", "a@b.c", "user").unwrap();
+        let config = rs256_service().config;
+        assert_eq!(verify_access_token(&token, &config).unwrap().sub, "user-123");
+    }
+
+    #[test]
+    fn test_verify_access_token_rejects_alg_none() {
+        let hs256 = JwtConfig::new("secret".to_string(), 3600, 86400);
+        let result = verify_access_token(ALG_NONE_TOKEN, &hs256);
+        assert!(matches!(result, Err(AppError::AuthenticationError(_))));
+
+        let result = verify_access_token(ALG_NONE_TOKEN, &rs256_service().config);
+        assert!(matches!(result, Err(AppError::AuthenticationError(_))));
+    }
+
+    #[test]
+    fn test_verify_access_token_rejects_hs256_under_rs256_config() {
+        // Algorithm confusion: an HS256 token keyed with the RS256 public key
+        let claims = Claims {
+            sub: "user-123".to_string(),
+            user_id: "user-123".to_string(),
+            email: "a@b.c".to_string(),
+            role: "admin".to_string(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp() as usize,
+            iat: Utc::now().timestamp() as usize,
+            jti: None,
+            sid: None,
+            token_type: None,
+        };
+        let token = encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(PUBLIC_KEY_PEM.as_bytes()),
+        )
+        .unwrap();
+
+        let result = verify_access_token(&token, &rs256_service().config);
+        assert!(matches!(result, Err(AppError::AuthenticationError(_))));
+    }
+
+    #[test]
+    fn test_verify_access_token_accepts_previous_secret() {
+        let old_token = hs256_service("old-secret")
+            .generate_access_token("user-123", "a@b.c", "user")
+            .unwrap();
+
+        let config = JwtConfig {
+            previous_secrets: vec![PreviousSecret::new("old-secret", Utc::now() + Duration::days(1))],
+            ..JwtConfig::new("new-secret".to_string(), 3600, 86400)
+        };
+        assert!(verify_access_token(&old_token, &config).is_ok());
+        assert!(verify_access_token(&old_token, &JwtConfig::new("new-secret".to_string(), 3600, 86400)).is_err());
+    }
+
+    #[test]
+    fn test_verify_access_token_rejects_refresh_token() {
+        let service = hs256_service("secret");
+        let refresh_token = service.generate_refresh_token("user-123").unwrap();
+
+        let result = verify_access_token(&refresh_token, &service.config);
+        assert!(matches!(result, Err(AppError::AuthenticationError(_))));
+
+        // Refresh tokens issued before `token_type` existed only carry a jti
+        let legacy = Claims {
+            sub: "user-123".to_string(),
+            user_id: "user-123".to_string(),
+            email: String::new(),
+            role: String::new(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp() as usize,
+            iat: Utc::now().timestamp() as usize,
+            jti: Some(Uuid::new_v4().to_string()),
+            sid: None,
+            token_type: None,
+        };
+        let legacy = encode(&Header::new(Algorithm::HS256), &legacy, &EncodingKey::from_secret(b"secret")).unwrap();
+        assert!(verify_access_token(&legacy, &service.config).is_err());
+
+        let access_token = service.generate_access_token("user-123", "a@b.c", "user").unwrap();
+        assert!(verify_access_token(&access_token, &service.config).is_ok());
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        assert_eq!(JwtAlgorithm::parse("rs256"), Some(JwtAlgorithm::RS256));
+        assert_eq!(JwtAlgorithm::parse("HS256"), Some(JwtAlgorithm::HS256));
+        assert_eq!(JwtAlgorithm::parse("none"), None);
+    }
+}
@@ -1,22 +1,77 @@
+pub mod api_keys;
 pub mod email_verification;
 pub mod handlers;
+pub mod hash_benchmark;
+pub mod identity;
 pub mod jwt;
+pub mod lockout;
 pub mod models;
 pub mod password;
 pub mod password_reset;
+#[cfg(feature = "zxcvbn")]
+pub mod password_strength;
 pub mod permissions;
+pub mod rate_limit;
 pub mod rbac;
 pub mod repository;
+pub mod sessions;
+pub mod token_denylist;
+pub mod users;
 
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
     use crate::handlers::*;
 
+    let auth = actix_web::web::scope("/auth")
+        .service(
+            actix_web::web::resource("/register")
+                .wrap(crate::rate_limit::AuthRateLimited)
+                .route(actix_web::web::post().to(register)),
+        )
+        .service(
+            actix_web::web::resource("/login")
+                .wrap(crate::rate_limit::AuthRateLimited)
+                .route(actix_web::web::post().to(login)),
+        )
+        .route("/logout", actix_web::web::post().to(logout))
+        .route("/refresh", actix_web::web::post().to(refresh))
+        .route("/me", actix_web::web::get().to(me))
+        .route(
+            "/verify/resend",
+            actix_web::web::post().to(crate::email_verification::resend_verification),
+        )
+        .route(
+            "/verify/confirm",
+            actix_web::web::post().to(crate::email_verification::confirm_verification),
+        )
+        .route("/sessions", actix_web::web::get().to(crate::sessions::list_sessions))
+        .route(
+            "/sessions",
+            actix_web::web::delete().to(crate::sessions::revoke_other_sessions),
+        )
+        .route(
+            "/sessions/{id}",
+            actix_web::web::delete().to(crate::sessions::revoke_session),
+        )
+        .route("/api-keys", actix_web::web::get().to(crate::api_keys::list_api_keys))
+        .route("/api-keys", actix_web::web::post().to(crate::api_keys::create_api_key))
+        .route(
+            "/api-keys/{id}",
+            actix_web::web::delete().to(crate::api_keys::revoke_api_key),
+        );
+    #[cfg(feature = "zxcvbn")]
+    let auth = auth.route(
+        "/password/strength",
+        actix_web::web::post().to(crate::password_strength::password_strength),
+    );
+    cfg.service(auth);
+
+    cfg.service(actix_web::web::scope("/admin/security").route(
+        "/hash-benchmark",
+        actix_web::web::post().to(crate::hash_benchmark::hash_benchmark),
+    ));
+
     cfg.service(
-        actix_web::web::scope("/auth")
-            .route("/register", actix_web::web::post().to(register))
-            .route("/login", actix_web::web::post().to(login))
-            .route("/logout", actix_web::web::post().to(logout))
-            .route("/refresh", actix_web::web::post().to(refresh))
-            .route("/me", actix_web::web::get().to(me)),
+        actix_web::web::scope("/users")
+            .route("/resolve", actix_web::web::post().to(crate::users::resolve_users)),
     );
 }
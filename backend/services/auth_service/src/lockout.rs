@@ -0,0 +1,364 @@
+//! Account lockout after repeated failed logins
+//!
+//! Failed password attempts are counted per user. Once the count reaches the
+//! configured threshold the account is locked for a cooldown period, and a
+//! successful login clears the counter. State lives in Redis when a
+//! connection is available so every instance sees the same counters; if
+//! Redis is not configured or a command fails, an in-memory store is used.
+
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Default number of consecutive failures before an account is locked
+pub const DEFAULT_LOCKOUT_THRESHOLD: u32 = 5;
+
+/// Default lock duration in seconds
+pub const DEFAULT_LOCKOUT_COOLDOWN_SECS: u64 = 900;
+
+/// Error code returned by the login endpoint while an account is locked
+pub const ACCOUNT_LOCKED_CODE: &str = "ACCOUNT_LOCKED";
+
+const ATTEMPTS_KEY_PREFIX: &str = "auth:lockout:attempts:";
+const LOCKED_KEY_PREFIX: &str = "auth:lockout:locked:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutConfig {
+    /// Consecutive failures that trigger a lock
+    pub threshold: u32,
+    /// How long the account stays locked
+    pub cooldown: Duration,
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_LOCKOUT_THRESHOLD,
+            cooldown: Duration::from_secs(DEFAULT_LOCKOUT_COOLDOWN_SECS),
+        }
+    }
+}
+
+impl LockoutConfig {
+    /// Read `AUTH_LOCKOUT_THRESHOLD` and `AUTH_LOCKOUT_COOLDOWN_SECS`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let threshold = std::env::var("AUTH_LOCKOUT_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_LOCKOUT_THRESHOLD);
+        let cooldown_secs = std::env::var("AUTH_LOCKOUT_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_LOCKOUT_COOLDOWN_SECS);
+
+        Self {
+            threshold,
+            cooldown: Duration::from_secs(cooldown_secs),
+        }
+    }
+}
+
+/// Result of recording a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureOutcome {
+    /// Account is still usable; `remaining` attempts are left before a lock
+    Counted { failures: u32, remaining: u32 },
+    /// This failure reached the threshold and the account is now locked
+    Locked { retry_after: u64 },
+}
+
+#[derive(Debug, Default)]
+struct AttemptState {
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryAttempts {
+    entries: Mutex<HashMap<Uuid, AttemptState>>,
+}
+
+impl InMemoryAttempts {
+    fn locked_for(&self, user_id: &Uuid) -> Option<Duration> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let state = entries.get_mut(user_id)?;
+        match state.locked_until {
+            Some(until) if until > now => Some(until - now),
+            Some(_) => {
+                entries.remove(user_id);
+                None
+            },
+            None => None,
+        }
+    }
+
+    fn record_failure(&self, user_id: &Uuid, config: &LockoutConfig) -> FailureOutcome {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let state = entries.entry(*user_id).or_default();
+        state.failures += 1;
+
+        if state.failures >= config.threshold {
+            state.failures = 0;
+            state.locked_until = Some(Instant::now() + config.cooldown);
+            FailureOutcome::Locked {
+                retry_after: retry_after_secs(config.cooldown),
+            }
+        } else {
+            FailureOutcome::Counted {
+                failures: state.failures,
+                remaining: config.threshold - state.failures,
+            }
+        }
+    }
+
+    fn reset(&self, user_id: &Uuid) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(user_id);
+    }
+}
+
+/// Tracks failed logins and enforces the lockout policy
+pub struct LoginLockout {
+    config: LockoutConfig,
+    redis: Option<MultiplexedConnection>,
+    fallback: InMemoryAttempts,
+}
+
+impl LoginLockout {
+    /// Create an in-memory tracker
+    pub fn new(config: LockoutConfig) -> Self {
+        Self {
+            config,
+            redis: None,
+            fallback: InMemoryAttempts::default(),
+        }
+    }
+
+    /// Create a tracker backed by Redis, using in-memory state if the
+    /// connection cannot be established
+    pub async fn connect(config: LockoutConfig, redis_url: Option<&str>) -> Self {
+        let redis = match redis_url.filter(|url| !url.is_empty()) {
+            Some(url) => match redis::Client::open(url) {
+                Ok(client) => match client.get_multiplexed_async_connection().await {
+                    Ok(conn) => Some(conn),
+                    Err(e) => {
+                        tracing::warn!("Failed to connect to Redis for login lockout, using in-memory: {}", e);
+                        None
+                    },
+                },
+                Err(e) => {
+                    tracing::warn!("Invalid Redis URL for login lockout, using in-memory: {}", e);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        Self {
+            config,
+            redis,
+            fallback: InMemoryAttempts::default(),
+        }
+    }
+
+    pub fn config(&self) -> &LockoutConfig {
+        &self.config
+    }
+
+    /// Seconds until the account unlocks, or `None` if it is not locked
+    pub async fn locked_for(&self, user_id: &Uuid) -> Option<u64> {
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            match conn.ttl::<_, i64>(locked_key(user_id)).await {
+                Ok(ttl) if ttl > 0 => return Some(ttl as u64),
+                Ok(_) => return None,
+                Err(e) => tracing::warn!("Redis error checking login lockout: {}", e),
+            }
+        }
+
+        self.fallback.locked_for(user_id).map(retry_after_secs)
+    }
+
+    /// Count a failed attempt, locking the account once the threshold is hit
+    pub async fn record_failure(&self, user_id: &Uuid) -> FailureOutcome {
+        if let Some(conn) = &self.redis {
+            match self.record_failure_redis(conn.clone(), user_id).await {
+                Ok(outcome) => return outcome,
+                Err(e) => tracing::warn!("Redis error recording failed login: {}", e),
+            }
+        }
+
+        self.fallback.record_failure(user_id, &self.config)
+    }
+
+    /// Clear the failure counter after a successful login
+    pub async fn record_success(&self, user_id: &Uuid) {
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            if let Err(e) = conn.del::<_, ()>(attempts_key(user_id)).await {
+                tracing::warn!("Redis error resetting failed logins: {}", e);
+            }
+        }
+
+        self.fallback.reset(user_id);
+    }
+
+    async fn record_failure_redis(
+        &self,
+        mut conn: MultiplexedConnection,
+        user_id: &Uuid,
+    ) -> Result<FailureOutcome, redis::RedisError> {
+        let key = attempts_key(user_id);
+        let cooldown_secs = retry_after_secs(self.config.cooldown);
+
+        // The counter expires after a quiet period of one cooldown so stale
+        // failures do not accumulate forever
+        let (failures, _): (u32, ()) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .expire(&key, cooldown_secs as i64)
+            .query_async(&mut conn)
+            .await?;
+
+        if failures >= self.config.threshold {
+            let _: () = redis::pipe()
+                .atomic()
+                .set_ex(locked_key(user_id), 1, cooldown_secs)
+                .del(&key)
+                .query_async(&mut conn)
+                .await?;
+            Ok(FailureOutcome::Locked {
+                retry_after: cooldown_secs,
+            })
+        } else {
+            Ok(FailureOutcome::Counted {
+                failures,
+                remaining: self.config.threshold - failures,
+            })
+        }
+    }
+}
+
+fn attempts_key(user_id: &Uuid) -> String {
+    format!("{}{}", ATTEMPTS_KEY_PREFIX, user_id)
+}
+
+fn locked_key(user_id: &Uuid) -> String {
+    format!("{}{}", LOCKED_KEY_PREFIX, user_id)
+}
+
+// Round up so clients never retry a moment too early
+fn retry_after_secs(duration: Duration) -> u64 {
+    let secs = duration.as_secs();
+    if duration.subsec_nanos() > 0 {
+        secs + 1
+    } else {
+        secs.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(threshold: u32, cooldown: Duration) -> LockoutConfig {
+        LockoutConfig { threshold, cooldown }
+    }
+
+    #[tokio::test]
+    async fn test_failures_increment_until_threshold() {
+        let lockout = LoginLockout::new(config(3, Duration::from_secs(60)));
+        let user_id = Uuid::new_v4();
+
+        assert_eq!(
+            lockout.record_failure(&user_id).await,
+            FailureOutcome::Counted {
+                failures: 1,
+                remaining: 2
+            }
+        );
+        assert_eq!(
+            lockout.record_failure(&user_id).await,
+            FailureOutcome::Counted {
+                failures: 2,
+                remaining: 1
+            }
+        );
+        assert_eq!(lockout.locked_for(&user_id).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_lock_triggers_at_threshold() {
+        let lockout = LoginLockout::new(config(3, Duration::from_secs(60)));
+        let user_id = Uuid::new_v4();
+
+        lockout.record_failure(&user_id).await;
+        lockout.record_failure(&user_id).await;
+        assert_eq!(
+            lockout.record_failure(&user_id).await,
+            FailureOutcome::Locked { retry_after: 60 }
+        );
+
+        let retry_after = lockout.locked_for(&user_id).await.expect("account should be locked");
+        assert!(retry_after > 0 && retry_after <= 60);
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_counter() {
+        let lockout = LoginLockout::new(config(3, Duration::from_secs(60)));
+        let user_id = Uuid::new_v4();
+
+        lockout.record_failure(&user_id).await;
+        lockout.record_failure(&user_id).await;
+        lockout.record_success(&user_id).await;
+
+        assert_eq!(
+            lockout.record_failure(&user_id).await,
+            FailureOutcome::Counted {
+                failures: 1,
+                remaining: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counters_are_per_user() {
+        let lockout = LoginLockout::new(config(2, Duration::from_secs(60)));
+        let locked_user = Uuid::new_v4();
+        let other_user = Uuid::new_v4();
+
+        lockout.record_failure(&locked_user).await;
+        lockout.record_failure(&locked_user).await;
+
+        assert!(lockout.locked_for(&locked_user).await.is_some());
+        assert_eq!(lockout.locked_for(&other_user).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_lock_expires_after_cooldown() {
+        let lockout = LoginLockout::new(config(1, Duration::from_millis(20)));
+        let user_id = Uuid::new_v4();
+
+        assert!(matches!(
+            lockout.record_failure(&user_id).await,
+            FailureOutcome::Locked { .. }
+        ));
+        assert!(lockout.locked_for(&user_id).await.is_some());
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(lockout.locked_for(&user_id).await, None);
+    }
+
+    #[test]
+    fn test_retry_after_rounds_up() {
+        assert_eq!(retry_after_secs(Duration::from_secs(30)), 30);
+        assert_eq!(retry_after_secs(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+    }
+}
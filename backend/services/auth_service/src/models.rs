@@ -1,18 +1,12 @@
 use serde::{Deserialize, Serialize};
-use shared_security::PasswordRequirements;
 use validator::Validate;
 
+use crate::permissions::{Permission, Role};
+
 // Helper function to validate password using shared security module
 // This delegates to shared_security for centralized password validation
 fn validate_password(password: &str) -> Result<(), validator::ValidationError> {
-    let requirements = PasswordRequirements {
-        min_length: 8,
-        max_length: 100,
-        require_uppercase: true,
-        require_lowercase: true,
-        require_digit: true,
-        require_special_char: false,
-    };
+    let requirements = crate::password::configured_requirements();
 
     match shared_security::validate_password_strength_with_requirements(password, &requirements) {
         Ok(()) => Ok(()),
@@ -82,6 +76,84 @@ pub struct LogoutRequest {
     pub refresh_token: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: String,
+    pub last_used: String,
+    pub is_current: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+fn default_api_key_scopes() -> Vec<String> {
+    vec!["read".to_string()]
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[serde(default = "default_api_key_scopes")]
+    pub scopes: Vec<String>,
+    #[validate(range(min = 1, max = 3650))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyResponse {
+    /// The full key; it cannot be retrieved again
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiKeyListResponse {
+    pub api_keys: Vec<ApiKeyResponse>,
+}
+
+/// Most user ids resolved by one `POST /users/resolve` request
+pub const MAX_RESOLVE_USER_IDS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResolveUsersRequest {
+    pub user_ids: Vec<String>,
+}
+
+/// What any signed-in user may see about another user
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicProfileResponse {
+    pub id: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResolveUsersResponse {
+    pub users: Vec<PublicProfileResponse>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,3 +334,58 @@ mod tests {
         assert!(request.validate().is_err());
     }
 }
+
+#[derive(Debug, Deserialize)]
+pub struct MeQuery {
+    pub space_id: Option<String>,
+    /// Comma-separated extras to add to the response; only `spaces` is known
+    pub include: Option<String>,
+}
+
+impl MeQuery {
+    /// Whether `include` asks for the caller's space memberships
+    ///
+    /// Fails with the first unknown value so typos don't silently return a
+    /// lean response.
+    pub fn include_spaces(&self) -> Result<bool, String> {
+        let mut spaces = false;
+        for value in self.include.iter().flat_map(|include| include.split(',')) {
+            match value.trim() {
+                "spaces" => spaces = true,
+                "" => {},
+                other => return Err(format!("Unknown include: {}", other)),
+            }
+        }
+        Ok(spaces)
+    }
+}
+
+/// Caller's role in one of their spaces
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceMembershipResponse {
+    pub space_id: String,
+    pub role: String,
+}
+
+/// Caller's role and permissions within one space
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceAccessResponse {
+    pub space_id: String,
+    /// `None` when the caller is not a member of the space
+    pub role: Option<Role>,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MeResponse {
+    pub id: String,
+    pub email: String,
+    pub role: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<Permission>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub space: Option<SpaceAccessResponse>,
+    /// Every space the caller owns or is a member of, with `?include=spaces`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spaces: Option<Vec<SpaceMembershipResponse>>,
+}
@@ -1,12 +1,59 @@
 //! Password utilities for auth_service
 //!
-//! This module re-exports password utilities from shared_security for backward compatibility.
+//! This module re-exports password utilities from shared_security for backward compatibility,
+//! and applies the password requirements configured through `PASSWORD_*` variables
+//! (see `PasswordRequirements::from_env`).
 
 pub use shared_security::{
     generate_reset_token, generate_url_safe_token, hash_password, hash_password_with_cost, validate_password_strength,
-    validate_password_strength_with_requirements, verify_password, PasswordError, PasswordRequirements,
-    PasswordValidationError,
+    validate_password_strength_with_requirements, verify_password, PasswordConfigError, PasswordError,
+    PasswordRequirements, PasswordValidationError, DEFAULT_BCRYPT_COST, MIN_PASSWORD_LENGTH_FLOOR,
 };
+#[cfg(feature = "zxcvbn")]
+pub use shared_security::{password_strength_score, PasswordStrength};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // Read once. Invalid values stop the server at startup, where
+    // `PasswordRequirements::from_env` is checked before any request is served.
+    static ref CONFIGURED_REQUIREMENTS: PasswordRequirements =
+        PasswordRequirements::from_env().unwrap_or_default();
+}
+
+/// Requirements from the `PASSWORD_*` environment variables
+pub fn configured_requirements() -> PasswordRequirements {
+    *CONFIGURED_REQUIREMENTS
+}
+
+/// bcrypt cost for new password hashes, shared with handlers through app_data
+#[derive(Debug, Clone, Copy)]
+pub struct BcryptCost(pub u32);
+
+impl Default for BcryptCost {
+    fn default() -> Self {
+        Self(DEFAULT_BCRYPT_COST)
+    }
+}
+
+/// Hash `password` with the registered cost, or the default when none is set
+pub fn hash_password_configured(
+    password: &str,
+    cost: Option<&actix_web::web::Data<BcryptCost>>,
+) -> Result<String, PasswordError> {
+    let BcryptCost(cost) = cost.map(|c| **c.as_ref()).unwrap_or_default();
+    hash_password_with_cost(password, cost)
+}
+
+/// Validate a new password against the registered requirements, or the
+/// environment's when none are registered
+pub fn validate_new_password(
+    password: &str,
+    requirements: Option<&actix_web::web::Data<PasswordRequirements>>,
+) -> Result<(), PasswordError> {
+    let requirements = requirements.map(|r| **r.as_ref()).unwrap_or_else(configured_requirements);
+    validate_password_strength_with_requirements(password, &requirements)
+}
 
 #[cfg(test)]
 mod tests {
@@ -24,6 +71,15 @@ mod tests {
         assert!(!verify_password("WrongPassword", &hash).unwrap());
     }
 
+    #[test]
+    fn test_hash_password_configured_uses_registered_cost() {
+        let cost = actix_web::web::Data::new(BcryptCost(4));
+        let hash = hash_password_configured("TestPassword123!", Some(&cost)).unwrap();
+
+        assert!(hash.starts_with("$2b$04$"));
+        assert!(verify_password("TestPassword123!", &hash).unwrap());
+    }
+
     #[test]
     fn test_validate_password_strength_valid() {
         let password = "TestPass123";
@@ -54,6 +110,15 @@ mod tests {
         assert!(validate_password_strength(password).is_err());
     }
 
+    #[test]
+    fn test_validate_new_password_uses_registered_requirements() {
+        let strict = actix_web::web::Data::new(PasswordRequirements::default().with_min_length(16));
+
+        assert!(validate_new_password("TestPass123", None).is_ok());
+        assert!(validate_new_password("TestPass123", Some(&strict)).is_err());
+        assert!(validate_new_password("TestPass123456789", Some(&strict)).is_ok());
+    }
+
     #[test]
     fn test_generate_reset_token() {
         let token = generate_reset_token(32);
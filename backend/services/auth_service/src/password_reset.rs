@@ -1,8 +1,10 @@
 use actix_web::{web, HttpResponse};
+use chrono::NaiveDateTime;
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::repository::AuthRepository;
+use crate::repository::{AuthRepository, ResetTokenRecord};
 use shared_errors::AppError;
 use shared_models::entities::User;
 
@@ -26,6 +28,139 @@ pub struct ResendVerificationEmailRequest {
     pub email: String,
 }
 
+// ============================================================================
+// Token Configuration
+// ============================================================================
+
+/// Default length of generated reset and verification tokens
+pub const DEFAULT_RESET_TOKEN_LENGTH: usize = 64;
+
+/// Default lifetime of a reset token in seconds
+pub const DEFAULT_RESET_TOKEN_TTL_SECS: i64 = 3600;
+
+/// Shortest token length accepted from configuration
+pub const MIN_RESET_TOKEN_LENGTH: usize = 32;
+
+lazy_static::lazy_static! {
+    pub static ref RESET_TOKEN_CONFIG: ResetTokenConfig = ResetTokenConfig::from_env();
+}
+
+/// Length and lifetime of password reset and email verification tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetTokenConfig {
+    pub length: usize,
+    pub ttl: chrono::Duration,
+}
+
+impl Default for ResetTokenConfig {
+    fn default() -> Self {
+        Self {
+            length: DEFAULT_RESET_TOKEN_LENGTH,
+            ttl: chrono::Duration::seconds(DEFAULT_RESET_TOKEN_TTL_SECS),
+        }
+    }
+}
+
+impl ResetTokenConfig {
+    /// Read `RESET_TOKEN_LENGTH` and `RESET_TOKEN_TTL_SECS`, falling back to
+    /// the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let length = std::env::var("RESET_TOKEN_LENGTH")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v >= MIN_RESET_TOKEN_LENGTH)
+            .unwrap_or(DEFAULT_RESET_TOKEN_LENGTH);
+        let ttl_secs = std::env::var("RESET_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_RESET_TOKEN_TTL_SECS);
+
+        Self {
+            length,
+            ttl: chrono::Duration::seconds(ttl_secs),
+        }
+    }
+
+    /// Generate a new token expiring `ttl` after `now`
+    pub fn issue(&self, now: NaiveDateTime) -> IssuedResetToken {
+        let token = shared_security::generate_reset_token(self.length);
+        let token_hash = hash_reset_token(&token);
+        IssuedResetToken {
+            token,
+            token_hash,
+            expires_at: now + self.ttl,
+        }
+    }
+
+    /// Check that a client-supplied token has the configured shape
+    pub fn validate_format(&self, token: &str) -> Result<(), String> {
+        if token.len() != self.length {
+            return Err(format!("Token must be {} characters long", self.length));
+        }
+        if !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err("Token must contain only alphanumeric characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A freshly generated token: `token` is sent to the user, `token_hash` is stored
+#[derive(Debug, Clone)]
+pub struct IssuedResetToken {
+    pub token: String,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Hex-encoded SHA-256 of a token, the only form persisted in the database
+pub fn hash_reset_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Error code for a reset token past its expiry
+pub const RESET_EXPIRED_CODE: &str = "RESET_EXPIRED";
+
+/// Error code for a reset token that was already redeemed
+pub const RESET_USED_CODE: &str = "RESET_USED";
+
+/// Why a reset token cannot be redeemed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetTokenError {
+    NotFound,
+    Expired,
+    Used,
+}
+
+impl ResetTokenError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "INVALID_TOKEN",
+            Self::Expired => RESET_EXPIRED_CODE,
+            Self::Used => RESET_USED_CODE,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NotFound => "Reset token not found",
+            Self::Expired => "Reset token has expired",
+            Self::Used => "Reset token has already been used",
+        }
+    }
+}
+
+/// Reject reset tokens that were already used or have expired
+pub fn check_reset_token(record: &ResetTokenRecord, now: NaiveDateTime) -> Result<(), ResetTokenError> {
+    if record.used_at.is_some() {
+        return Err(ResetTokenError::Used);
+    }
+    if record.expires_at <= now {
+        return Err(ResetTokenError::Expired);
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Public Functions (called by email_verification.rs)
 // ============================================================================
@@ -35,34 +170,18 @@ pub async fn reset_password(
     req: web::Json<PasswordResetRequest>,
     repo: web::Data<AuthRepository>,
     _jwt_service: web::Data<crate::jwt::JwtService>,
+    password_requirements: Option<web::Data<shared_security::PasswordRequirements>>,
 ) -> impl actix_web::Responder {
     // Validate token format
-    if let Err(e) = validate_token_format(&req.token) {
+    if let Err(e) = RESET_TOKEN_CONFIG.validate_format(&req.token) {
         return HttpResponse::BadRequest().json(json!({ "error": "VALIDATION_ERROR", "message": e }));
     }
 
-    // Validate password strength using shared security module
-    if let Err(e) = shared_security::validate_password_strength(&req.new_password) {
+    // Validate password strength against the configured requirements
+    if let Err(e) = crate::password::validate_new_password(&req.new_password, password_requirements.as_ref()) {
         return HttpResponse::BadRequest().json(json!({ "error": "VALIDATION_ERROR", "message": e.to_string() }));
     }
 
-    // Find reset token and verify it's valid
-    let reset_info = match find_valid_reset_token(&req.token, repo.clone()).await {
-        Ok(info) => info,
-        Err(e) => {
-            return match e {
-                AppError::NotFoundError(msg) => {
-                    HttpResponse::NotFound().json(json!({ "error": "INVALID_TOKEN", "message": msg }))
-                },
-                AppError::ValidationError(msg) => {
-                    HttpResponse::BadRequest().json(json!({ "error": "INVALID_TOKEN", "message": msg }))
-                },
-                _ => HttpResponse::InternalServerError()
-                    .json(json!({ "error": "INTERNAL_ERROR", "message": "Failed to validate reset token" })),
-            };
-        },
-    };
-
     // Hash new password using shared security module
     let new_password_hash = match shared_security::hash_password(&req.new_password) {
         Ok(hash) => hash,
@@ -73,23 +192,23 @@ pub async fn reset_password(
         },
     };
 
-    // Update user password
-    match update_user_password(reset_info.user_id, &new_password_hash, repo.clone()).await {
-        Ok(_) => {},
-        Err(e) => {
-            tracing::error!("Failed to update password: {}", e);
-            return HttpResponse::InternalServerError()
-                .json(json!({ "error": "DATABASE_ERROR", "message": "Failed to update password" }));
+    // Redeem the token and update the password in one step so a token can
+    // only ever be used once
+    let redeemed = redeem_reset_token(&req.token, &new_password_hash, repo).await;
+    match redeemed {
+        Ok(Ok(_user_id)) => {},
+        Ok(Err(e)) => {
+            let response = match e {
+                ResetTokenError::NotFound => HttpResponse::NotFound(),
+                ResetTokenError::Expired | ResetTokenError::Used => HttpResponse::BadRequest(),
+            }
+            .json(json!({ "error": e.code(), "message": e.message() }));
+            return response;
         },
-    }
-
-    // Mark token as used
-    match mark_reset_token_used(&req.token, repo).await {
-        Ok(_) => {},
         Err(e) => {
-            tracing::error!("Failed to mark reset token as used: {}", e);
+            tracing::error!("Failed to redeem reset token: {}", e);
             return HttpResponse::InternalServerError()
-                .json(json!({ "error": "DATABASE_ERROR", "message": "Failed to finalize reset" }));
+                .json(json!({ "error": "DATABASE_ERROR", "message": "Failed to update password" }));
         },
     }
 
@@ -136,18 +255,16 @@ pub async fn request_password_reset(
         },
     };
 
-    // Generate and store password reset token using shared security module
-    let token = shared_security::generate_reset_token(64);
-    if let Err(e) = store_reset_token(user.id, &token, repo.clone()).await {
+    // Generate a reset token; only its hash is stored
+    let issued = RESET_TOKEN_CONFIG.issue(chrono::Utc::now().naive_utc());
+    if let Err(e) = store_reset_token(user.id, &issued, repo.clone()).await {
         tracing::error!("Failed to store reset token: {}", e);
         return HttpResponse::InternalServerError()
             .json(json!({ "error": "INTERNAL_ERROR", "message": "Failed to process request" }));
     }
 
-    // TODO: Send email with reset link
-    // For now, just log the token (in production, this should send an email)
+    // TODO: Send email with reset link. The token itself must never be logged.
     tracing::info!("Password reset token generated for {}", email);
-    tracing::debug!("Password reset token: {}", token);
 
     HttpResponse::Ok().json(json!({ "message": "If the email is registered, a reset link has been sent".to_string() }))
 }
@@ -200,9 +317,9 @@ pub async fn resend_verification_email(
             .json(json!({ "message": "If email is registered, a verification email has been sent".to_string() }));
     }
 
-    // Generate and store new verification token using shared security module
-    let token = shared_security::generate_reset_token(64);
-    if let Err(e) = store_verification_token(user.id, &token, repo).await {
+    // Generate a new verification token; only its hash is stored
+    let issued = RESET_TOKEN_CONFIG.issue(chrono::Utc::now().naive_utc());
+    if let Err(e) = store_verification_token(user.id, &issued, repo).await {
         tracing::error!("Failed to store verification token: {}", e);
         return HttpResponse::InternalServerError()
             .json(json!({ "error": "INTERNAL_ERROR", "message": "Failed to process request" }));
@@ -210,7 +327,7 @@ pub async fn resend_verification_email(
 
     // TODO: Send email with verification link
     tracing::info!("Verification token resent for {}", email);
-    tracing::debug!("Verification token: {}", token);
+    tracing::debug!("Verification token: {}", issued.token);
 
     HttpResponse::Ok().json(json!({ "message": "Verification email sent successfully".to_string() }))
 }
@@ -219,43 +336,40 @@ pub async fn resend_verification_email(
 // Helper Functions
 // ============================================================================
 
-struct ResetTokenInfo {
-    user_id: Uuid,
-    #[allow(dead_code)]
-    expires_at: chrono::DateTime<chrono::Utc>,
-}
-
-fn validate_token_format(token: &str) -> Result<(), String> {
-    if token.len() != 64 {
-        return Err("Token must be 64 characters long".to_string());
-    }
-    if !token.chars().all(|c| c.is_alphanumeric()) {
-        return Err("Token must contain only alphanumeric characters".to_string());
-    }
-    Ok(())
-}
-
 #[allow(dead_code)]
 fn generate_verification_token() -> String {
-    shared_security::generate_reset_token(64)
+    shared_security::generate_reset_token(RESET_TOKEN_CONFIG.length)
 }
 
 // ============================================================================
-// Database Operations (TODO: These should be in AuthRepository)
+// Database Operations
 // ============================================================================
 
-async fn find_valid_reset_token(_token: &str, _repo: web::Data<AuthRepository>) -> Result<ResetTokenInfo, AppError> {
-    // TODO: Implement actual database query to verify reset token exists, is unused, and hasn't expired
-    Err(AppError::NotFoundError(
-        "Password reset functionality not yet implemented".to_string(),
-    ))
-}
+/// Mark the token used and set the new password atomically
+///
+/// When the token cannot be redeemed, the stored record is inspected to tell
+/// an unknown token from an expired or already used one.
+pub async fn redeem_reset_token(
+    token: &str,
+    new_password_hash: &str,
+    repo: web::Data<AuthRepository>,
+) -> Result<Result<Uuid, ResetTokenError>, AppError> {
+    let token_hash = hash_reset_token(token);
+    let now = chrono::Utc::now().naive_utc();
+
+    if let Some(user_id) = repo
+        .redeem_password_reset(&token_hash, new_password_hash, now)
+        .await
+        .map_err(AppError::DatabaseError)?
+    {
+        return Ok(Ok(user_id));
+    }
 
-async fn mark_reset_token_used(_token: &str, _repo: web::Data<AuthRepository>) -> Result<(), AppError> {
-    // TODO: Implement actual database update to mark reset token as used
-    Err(AppError::NotFoundError(
-        "Password reset functionality not yet implemented".to_string(),
-    ))
+    let record = repo.find_password_reset(&token_hash).await.map_err(AppError::DatabaseError)?;
+    Ok(Err(match record {
+        Some(record) => check_reset_token(&record, now).err().unwrap_or(ResetTokenError::Used),
+        None => ResetTokenError::NotFound,
+    }))
 }
 
 async fn find_user_by_email(email: &str, repo: web::Data<AuthRepository>) -> Result<Option<User>, AppError> {
@@ -263,33 +377,24 @@ async fn find_user_by_email(email: &str, repo: web::Data<AuthRepository>) -> Res
     repo.find_by_email(email).await.map_err(AppError::DatabaseError)
 }
 
-async fn update_user_password(
-    _user_id: Uuid,
-    _password_hash: &str,
-    _repo: web::Data<AuthRepository>,
+async fn store_reset_token(
+    user_id: Uuid,
+    issued: &IssuedResetToken,
+    repo: web::Data<AuthRepository>,
 ) -> Result<(), AppError> {
-    // TODO: Implement actual password update in users table
-    Err(AppError::NotFoundError(
-        "Password reset functionality not yet implemented".to_string(),
-    ))
-}
-
-async fn store_reset_token(_user_id: Uuid, _token: &str, _repo: web::Data<AuthRepository>) -> Result<(), AppError> {
-    // TODO: Implement actual token storage in password_resets table
-    Err(AppError::NotFoundError(
-        "Password reset functionality not yet implemented".to_string(),
-    ))
+    repo.create_password_reset(&user_id, &issued.token_hash, issued.expires_at)
+        .await
+        .map_err(AppError::DatabaseError)
 }
 
 async fn store_verification_token(
-    _user_id: Uuid,
-    _token: &str,
-    _repo: web::Data<AuthRepository>,
+    user_id: Uuid,
+    issued: &IssuedResetToken,
+    repo: web::Data<AuthRepository>,
 ) -> Result<(), AppError> {
-    // TODO: Implement actual token storage in email_verifications table
-    Err(AppError::NotFoundError(
-        "Password reset functionality not yet implemented".to_string(),
-    ))
+    repo.create_email_verification(&user_id, &issued.token_hash, issued.expires_at)
+        .await
+        .map_err(AppError::DatabaseError)
 }
 
 #[cfg(test)]
@@ -303,33 +408,33 @@ mod tests {
     #[test]
     fn test_validate_token_format_valid() {
         let valid_token = shared_security::generate_reset_token(64);
-        assert!(validate_token_format(&valid_token).is_ok());
+        assert!(ResetTokenConfig::default().validate_format(&valid_token).is_ok());
     }
 
     #[test]
     fn test_validate_token_format_too_short() {
         let short_token = "test_short";
-        assert!(validate_token_format(&short_token).is_err());
+        assert!(ResetTokenConfig::default().validate_format(&short_token).is_err());
     }
 
     #[test]
     fn test_validate_token_format_too_long() {
         let long_token = format!("test_{}", "a".repeat(100));
-        assert!(validate_token_format(&long_token).is_err());
+        assert!(ResetTokenConfig::default().validate_format(&long_token).is_err());
     }
 
     #[test]
     fn test_validate_token_format_invalid_chars() {
         // Exactly 64 chars with underscores (invalid character)
         let invalid_token = "abcd_fgh_jklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ01";
-        assert!(validate_token_format(&invalid_token).is_err());
+        assert!(ResetTokenConfig::default().validate_format(&invalid_token).is_err());
     }
 
     #[test]
     fn test_validate_token_format_special_chars() {
         // Exactly 64 chars with special characters (invalid)
         let invalid_token = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOP!@#$%^&*()XY";
-        assert!(validate_token_format(&invalid_token).is_err());
+        assert!(ResetTokenConfig::default().validate_format(&invalid_token).is_err());
     }
 
     // ========================================
@@ -362,6 +467,81 @@ mod tests {
         assert!(verification_token.chars().all(|c| c.is_alphanumeric()));
     }
 
+    // ========================================
+    // Token Config Tests
+    // ========================================
+
+    fn record_for(issued: &IssuedResetToken) -> ResetTokenRecord {
+        ResetTokenRecord {
+            user_id: Uuid::new_v4(),
+            token_hash: issued.token_hash.clone(),
+            expires_at: issued.expires_at,
+            used_at: None,
+        }
+    }
+
+    #[test]
+    fn test_reset_token_config_length_and_expiry() {
+        let config = ResetTokenConfig {
+            length: 40,
+            ttl: chrono::Duration::minutes(15),
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let issued = config.issue(now);
+
+        assert_eq!(issued.token.len(), 40);
+        assert_eq!(issued.expires_at, now + chrono::Duration::minutes(15));
+        assert!(config.validate_format(&issued.token).is_ok());
+        assert!(ResetTokenConfig::default().validate_format(&issued.token).is_err());
+    }
+
+    #[test]
+    fn test_reset_token_valid_before_expiry() {
+        let config = ResetTokenConfig::default();
+        let now = chrono::Utc::now().naive_utc();
+        let record = record_for(&config.issue(now));
+
+        assert!(check_reset_token(&record, now).is_ok());
+        assert!(check_reset_token(&record, now + config.ttl - chrono::Duration::seconds(1)).is_ok());
+    }
+
+    #[test]
+    fn test_reset_token_rejected_after_expiry() {
+        let config = ResetTokenConfig::default();
+        let now = chrono::Utc::now().naive_utc();
+        let record = record_for(&config.issue(now));
+
+        let result = check_reset_token(&record, now + config.ttl + chrono::Duration::seconds(1));
+        assert_eq!(result, Err(ResetTokenError::Expired));
+        assert_eq!(ResetTokenError::Expired.code(), RESET_EXPIRED_CODE);
+    }
+
+    #[test]
+    fn test_reset_token_rejected_after_use() {
+        let now = chrono::Utc::now().naive_utc();
+        let mut record = record_for(&ResetTokenConfig::default().issue(now));
+        record.used_at = Some(now);
+
+        assert_eq!(check_reset_token(&record, now), Err(ResetTokenError::Used));
+        assert_eq!(ResetTokenError::Used.code(), RESET_USED_CODE);
+    }
+
+    #[test]
+    fn test_reset_token_stored_as_hash() {
+        let issued = ResetTokenConfig::default().issue(chrono::Utc::now().naive_utc());
+        let record = record_for(&issued);
+
+        // The stored form is a SHA-256 digest, never the plaintext token
+        assert_ne!(record.token_hash, issued.token);
+        assert!(!record.token_hash.contains(&issued.token));
+        assert_eq!(record.token_hash.len(), 64);
+        assert!(record.token_hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // Looking the token up means hashing it first; the plaintext never matches
+        assert_eq!(hash_reset_token(&issued.token), record.token_hash);
+        assert_ne!(hash_reset_token(&record.token_hash), record.token_hash);
+    }
+
     // ========================================
     // Email Validation Integration Tests
     // ========================================
@@ -0,0 +1,97 @@
+//! Password strength scoring for the signup form
+//!
+//! Serves `POST /auth/password/strength`, which scores a candidate password
+//! with zxcvbn for the strength meter. Built with the `zxcvbn` feature.
+
+use crate::password::{password_strength_score, PasswordRequirements, PasswordValidationError};
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+/// Body of `POST /auth/password/strength`
+#[derive(Debug, Deserialize)]
+pub struct PasswordStrengthRequest {
+    pub password: String,
+    /// Email the user is signing up with, if already entered
+    pub email: Option<String>,
+    /// Display name the user is signing up with, if already entered
+    pub display_name: Option<String>,
+}
+
+/// Words from the user's email and name for the strength dictionaries
+///
+/// The full email plus every alphanumeric piece of its local part, domain
+/// and the name; pieces shorter than three characters are left out.
+fn user_dictionary(email: Option<&str>, display_name: Option<&str>) -> Vec<String> {
+    let mut words: Vec<String> = email.map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()).into_iter().collect();
+    for text in email.into_iter().chain(display_name) {
+        for piece in text.split(|c: char| !c.is_alphanumeric()) {
+            let piece = piece.to_lowercase();
+            if piece.chars().count() >= 3 && !words.contains(&piece) {
+                words.push(piece);
+            }
+        }
+    }
+    words
+}
+
+/// `POST /auth/password/strength` - score a candidate password from 0 to 4
+///
+/// Nothing is stored; the signup form calls this as the user types.
+pub async fn password_strength(req: web::Json<PasswordStrengthRequest>) -> HttpResponse {
+    let max_length = PasswordRequirements::default().max_length;
+    if req.password.chars().count() > max_length {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "VALIDATION_ERROR",
+            "message": PasswordValidationError::TooLong(max_length).to_string()
+        }));
+    }
+
+    let words = user_dictionary(req.email.as_deref(), req.display_name.as_deref());
+    let user_inputs: Vec<&str> = words.iter().map(String::as_str).collect();
+    HttpResponse::Ok().json(password_strength_score(&req.password, &user_inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_dictionary_splits_email_and_name() {
+        assert_eq!(
+            user_dictionary(Some("Jane.Doe+wiki@example.com"), Some("Jane Q Doe")),
+            vec!["jane.doe+wiki@example.com", "jane", "doe", "wiki", "example", "com"]
+        );
+        assert!(user_dictionary(None, None).is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_password_strength_uses_user_words() {
+        let probe = |email: Option<&str>| PasswordStrengthRequest {
+            password: "Zorblax2024!".to_string(),
+            email: email.map(str::to_string),
+            display_name: None,
+        };
+
+        let plain = password_strength(web::Json(probe(None))).await;
+        let personal = password_strength(web::Json(probe(Some("zorblax@example.com")))).await;
+        assert_eq!(plain.status(), actix_web::http::StatusCode::OK);
+
+        let score = |response: HttpResponse| async move {
+            let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()["score"].as_u64().unwrap()
+        };
+        assert!(score(personal).await < score(plain).await);
+    }
+
+    #[actix_rt::test]
+    async fn test_password_strength_rejects_overlong_passwords() {
+        let response = password_strength(web::Json(PasswordStrengthRequest {
+            password: "a".repeat(200),
+            email: None,
+            display_name: None,
+        }))
+        .await;
+
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
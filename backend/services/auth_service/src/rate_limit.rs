@@ -0,0 +1,304 @@
+//! Per-client rate limit for the sign-in endpoints
+//!
+//! Login and registration are wrapped in `AuthRateLimited`, which spends a
+//! token from a bucket keyed by endpoint and client IP (see
+//! `shared_cache::rate_limit`). The client IP is the connection's peer
+//! address; `X-Forwarded-For` and `Forwarded` are only used with
+//! `trust_proxy`, since any client can send them. Every response carries
+//! `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` so
+//! clients can see their budget; requests over the limit get
+//! `429 RATE_LIMITED` with a `Retry-After` header as well.
+//!
+//! The limiter is read from app data, so routes stay unlimited when no
+//! `AuthRateLimit` is registered.
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web, Error, HttpResponse,
+};
+use shared_cache::rate_limit::{
+    rate_limit_headers, retry_after_secs, RateLimitConfig, RateLimitDecision, TokenBucketLimiter,
+};
+use std::future::{Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Default sustained requests per second for one client on one endpoint
+pub const DEFAULT_AUTH_RATE_PER_SEC: f64 = 0.1;
+
+/// Default requests one client can make back to back on one endpoint
+pub const DEFAULT_AUTH_BURST: f64 = 10.0;
+
+/// Error code for requests refused by the limiter
+pub const RATE_LIMITED_CODE: &str = "RATE_LIMITED";
+
+const KEY_PREFIX: &str = "auth:ratelimit:";
+
+/// Token-bucket limiter for the sign-in endpoints
+pub struct AuthRateLimit {
+    limiter: TokenBucketLimiter,
+    trust_proxy: bool,
+}
+
+impl AuthRateLimit {
+    /// In-memory limiter, for a single instance or tests
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            limiter: TokenBucketLimiter::new(KEY_PREFIX, config),
+            trust_proxy: false,
+        }
+    }
+
+    /// Key clients on the forwarded client address, for deployments behind
+    /// a proxy that sets `X-Forwarded-For`
+    pub fn with_trust_proxy(mut self, trust_proxy: bool) -> Self {
+        self.trust_proxy = trust_proxy;
+        self
+    }
+
+    /// Read `AUTH_RATE_PER_SEC` and `AUTH_RATE_BURST`, keeping buckets in
+    /// Redis when `redis_url` is reachable
+    pub async fn connect_from_env(redis_url: Option<&str>) -> Self {
+        let config = RateLimitConfig::from_env(
+            "AUTH_RATE_PER_SEC",
+            "AUTH_RATE_BURST",
+            RateLimitConfig {
+                per_sec: DEFAULT_AUTH_RATE_PER_SEC,
+                burst: DEFAULT_AUTH_BURST,
+            },
+        );
+        Self {
+            limiter: TokenBucketLimiter::connect(KEY_PREFIX, config, redis_url).await,
+            trust_proxy: false,
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        self.limiter.config()
+    }
+
+    /// Spend one request from `client` on `endpoint`
+    pub async fn take(&self, endpoint: &str, client: &str) -> RateLimitDecision {
+        self.limiter.take(&format!("{}:{}", endpoint, client)).await
+    }
+
+    /// Address the request's bucket is keyed on
+    pub fn client_key(&self, req: &ServiceRequest) -> String {
+        if self.trust_proxy {
+            return req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+        }
+        req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// 429 telling the client when to retry
+pub fn rate_limited_response(decision: &RateLimitDecision) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after_secs(decision.retry_after).to_string()))
+        .json(serde_json::json!({
+            "error": RATE_LIMITED_CODE,
+            "message": "Too many requests. Please try again later.",
+        }))
+}
+
+/// Middleware applying the registered `AuthRateLimit` to a route
+#[derive(Clone, Default)]
+pub struct AuthRateLimited;
+
+impl<S, B> Transform<S, ServiceRequest> for AuthRateLimited
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AuthRateLimitedMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(AuthRateLimitedMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AuthRateLimitedMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthRateLimitedMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let limiter = match req.app_data::<web::Data<AuthRateLimit>>() {
+                Some(limiter) => limiter.clone(),
+                None => return service.call(req).await.map(ServiceResponse::map_into_left_body),
+            };
+
+            let client = limiter.client_key(&req);
+            let decision = limiter.take(req.path(), &client).await;
+
+            let mut res = if decision.allowed {
+                service.call(req).await?.map_into_left_body()
+            } else {
+                tracing::warn!("Rate limit reached for {} from {}", req.path(), client);
+                req.into_response(rate_limited_response(&decision)).map_into_right_body()
+            };
+
+            for (name, value) in rate_limit_headers(&decision) {
+                if let Ok(value) = header::HeaderValue::from_str(&value) {
+                    res.headers_mut().insert(header::HeaderName::from_static(name), value);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    async fn ok() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    fn limited_app_data(burst: f64) -> web::Data<AuthRateLimit> {
+        web::Data::new(AuthRateLimit::new(RateLimitConfig { per_sec: 0.01, burst }))
+    }
+
+    fn remaining(resp: &ServiceResponse<impl MessageBody>) -> &str {
+        resp.headers().get("x-ratelimit-remaining").unwrap().to_str().unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_headers_count_down_then_429() {
+        let app = test::init_service(
+            App::new().app_data(limited_app_data(3.0)).service(
+                web::resource("/login")
+                    .wrap(AuthRateLimited)
+                    .route(web::post().to(ok)),
+            ),
+        )
+        .await;
+        let login = || {
+            test::TestRequest::post()
+                .uri("/login")
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .to_request()
+        };
+
+        for expected in ["2", "1", "0"] {
+            let resp = test::call_service(&app, login()).await;
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.headers().get("x-ratelimit-limit").unwrap(), "3");
+            assert_eq!(remaining(&resp), expected);
+            assert!(resp.headers().contains_key("x-ratelimit-reset"));
+        }
+
+        let resp = test::call_service(&app, login()).await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(remaining(&resp), "0");
+        assert!(resp.headers().contains_key(header::RETRY_AFTER));
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], RATE_LIMITED_CODE);
+    }
+
+    #[actix_rt::test]
+    async fn test_budget_is_per_endpoint() {
+        let app = test::init_service(
+            App::new()
+                .app_data(limited_app_data(1.0))
+                .service(
+                    web::resource("/login")
+                        .wrap(AuthRateLimited)
+                        .route(web::post().to(ok)),
+                )
+                .service(
+                    web::resource("/register")
+                        .wrap(AuthRateLimited)
+                        .route(web::post().to(ok)),
+                ),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/login").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/register").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/login").to_request()).await;
+        assert_eq!(resp.status(), 429);
+    }
+
+    #[actix_rt::test]
+    async fn test_forwarded_for_is_ignored_without_trust_proxy() {
+        let login_from = |forwarded_for: &str| {
+            test::TestRequest::post()
+                .uri("/login")
+                .peer_addr("10.0.0.1:4000".parse().unwrap())
+                .insert_header(("X-Forwarded-For", forwarded_for.to_string()))
+                .to_request()
+        };
+
+        let app = test::init_service(
+            App::new().app_data(limited_app_data(1.0)).service(
+                web::resource("/login")
+                    .wrap(AuthRateLimited)
+                    .route(web::post().to(ok)),
+            ),
+        )
+        .await;
+        assert_eq!(test::call_service(&app, login_from("203.0.113.1")).await.status(), 200);
+        // A new spoofed address does not buy a fresh bucket
+        assert_eq!(test::call_service(&app, login_from("203.0.113.2")).await.status(), 429);
+
+        let trusting = web::Data::new(
+            AuthRateLimit::new(RateLimitConfig { per_sec: 0.01, burst: 1.0 }).with_trust_proxy(true),
+        );
+        let app = test::init_service(
+            App::new().app_data(trusting).service(
+                web::resource("/login")
+                    .wrap(AuthRateLimited)
+                    .route(web::post().to(ok)),
+            ),
+        )
+        .await;
+        assert_eq!(test::call_service(&app, login_from("203.0.113.1")).await.status(), 200);
+        assert_eq!(test::call_service(&app, login_from("203.0.113.2")).await.status(), 200);
+    }
+
+    #[actix_rt::test]
+    async fn test_without_limiter_routes_are_unlimited() {
+        let app = test::init_service(
+            App::new().service(
+                web::resource("/login")
+                    .wrap(AuthRateLimited)
+                    .route(web::post().to(ok)),
+            ),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::post().uri("/login").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        assert!(!resp.headers().contains_key("x-ratelimit-remaining"));
+    }
+}
@@ -3,13 +3,14 @@ use actix_web::{HttpRequest, HttpResponse, ResponseError};
 use lazy_static::lazy_static;
 use thiserror::Error;
 
-use crate::jwt::Claims;
-use crate::permissions::{ActionType, Permission, Role};
+pub mod roles;
+
+use crate::jwt::{verify_access_token, Claims, JwtConfig};
+use crate::permissions::{ActionType, Permission, RbacConfig, Role};
 
 lazy_static! {
-    static ref JWT_SECRET: String = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    static ref JWT_DECODING_KEY: jsonwebtoken::DecodingKey =
-        jsonwebtoken::DecodingKey::from_secret(JWT_SECRET.as_bytes());
+    static ref JWT_VERIFICATION: JwtConfig =
+        JwtConfig::verification_from_env().expect("JWT verification settings must be valid");
 }
 
 #[derive(Debug, Error)]
@@ -69,12 +70,8 @@ impl RbacMiddleware {
             .and_then(|s| s.strip_prefix("Bearer "));
 
         if let Some(token_str) = auth_header {
-            let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
-            let token_data: jsonwebtoken::TokenData<Claims> =
-                jsonwebtoken::decode(token_str, &JWT_DECODING_KEY, &validation)
-                    .map_err(|e| Error::Unauthorized(format!("Invalid token: {}", e)))?;
-
-            Ok(token_data.claims)
+            verify_access_token(token_str, &JWT_VERIFICATION)
+                .map_err(|e| Error::Unauthorized(format!("Invalid token: {}", e)))
         } else {
             Err(Error::Unauthorized("Missing authorization header".to_string()))
         }
@@ -89,6 +86,11 @@ impl RbacMiddleware {
         }
     }
 
+    /// Checks if a role is at least `required`; unknown roles never are
+    pub fn has_role(role: &str, required: Role) -> bool {
+        Role::from_str(role).is_some_and(|role| role.level() >= required.level())
+    }
+
     /// Checks if a user can perform a specific action
     pub fn can_perform_action(role: &str, action: &ActionType) -> bool {
         if let Some(parsed_role) = Role::from_str(role) {
@@ -98,6 +100,13 @@ impl RbacMiddleware {
         }
     }
 
+    /// Permissions granted by a role; unknown roles grant none
+    pub fn effective_permissions(role: &str) -> Vec<Permission> {
+        Role::from_str(role)
+            .map(|role| RbacConfig::get_permissions_for_role(&role))
+            .unwrap_or_default()
+    }
+
     /// Extract user role from claims
     pub fn extract_role(claims: &Claims) -> Option<String> {
         Some(claims.role.clone())
@@ -226,6 +235,14 @@ mod tests {
         assert!(!RbacMiddleware::has_permission("viewer", &Permission::EditDocuments));
     }
 
+    #[test]
+    fn test_has_role() {
+        assert!(RbacMiddleware::has_role("owner", Role::Owner));
+        assert!(RbacMiddleware::has_role("editor", Role::Commenter));
+        assert!(!RbacMiddleware::has_role("editor", Role::Owner));
+        assert!(!RbacMiddleware::has_role("admin", Role::Viewer));
+    }
+
     #[test]
     fn test_can_perform_action() {
         // Owner can delete
@@ -238,6 +255,14 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_effective_permissions() {
+        let viewer = RbacMiddleware::effective_permissions("viewer");
+        assert_eq!(viewer, vec![Permission::ViewDocuments]);
+        assert!(RbacMiddleware::effective_permissions("owner").contains(&Permission::DeleteSpace));
+        assert!(RbacMiddleware::effective_permissions("user").is_empty());
+    }
+
     #[test]
     fn test_extract_role() {
         assert_eq!(extract_role_from_string("owner"), Role::Owner);
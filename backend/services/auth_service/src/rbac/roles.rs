@@ -0,0 +1,216 @@
+//! Space roles as named permission sets
+//!
+//! A [`Role`] is a name plus a [`PermissionSet`], so spaces can define roles
+//! beyond the fixed owner/editor/commenter/viewer set. Permission checks go
+//! through [`has_permission`] rather than comparing role names. The built-in
+//! roles keep the permissions of [`RbacConfig::get_permissions_for_role`] and
+//! are seeded into `space_roles` with the same bits.
+
+use bitflags::bitflags;
+
+use crate::permissions::{Permission, RbacConfig, Role as BuiltinRole};
+
+bitflags! {
+    /// Set of [`Permission`]s, stored as an integer in `space_roles.permissions`
+    ///
+    /// Bit positions are persisted, so never reorder or reuse them.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct PermissionSet: u32 {
+        const VIEW_DOCUMENTS = 1 << 0;
+        const CREATE_DOCUMENTS = 1 << 1;
+        const EDIT_DOCUMENTS = 1 << 2;
+        const DELETE_DOCUMENTS = 1 << 3;
+        const COMMENT = 1 << 4;
+        const SHARE = 1 << 5;
+        const MANAGE_MEMBERS = 1 << 6;
+        const MANAGE_ROLES = 1 << 7;
+        const DELETE_SPACE = 1 << 8;
+    }
+}
+
+// Every permission, in bit order
+const ALL_PERMISSIONS: [Permission; 9] = [
+    Permission::ViewDocuments,
+    Permission::CreateDocuments,
+    Permission::EditDocuments,
+    Permission::DeleteDocuments,
+    Permission::Comment,
+    Permission::Share,
+    Permission::ManageMembers,
+    Permission::ManageRoles,
+    Permission::DeleteSpace,
+];
+
+impl From<Permission> for PermissionSet {
+    fn from(permission: Permission) -> Self {
+        match permission {
+            Permission::ViewDocuments => Self::VIEW_DOCUMENTS,
+            Permission::CreateDocuments => Self::CREATE_DOCUMENTS,
+            Permission::EditDocuments => Self::EDIT_DOCUMENTS,
+            Permission::DeleteDocuments => Self::DELETE_DOCUMENTS,
+            Permission::Comment => Self::COMMENT,
+            Permission::Share => Self::SHARE,
+            Permission::ManageMembers => Self::MANAGE_MEMBERS,
+            Permission::ManageRoles => Self::MANAGE_ROLES,
+            Permission::DeleteSpace => Self::DELETE_SPACE,
+        }
+    }
+}
+
+impl PermissionSet {
+    pub fn from_permissions(permissions: &[Permission]) -> Self {
+        permissions
+            .iter()
+            .fold(Self::empty(), |set, permission| set | Self::from(*permission))
+    }
+
+    /// Permission set from a stored column value; unknown bits are dropped
+    pub fn from_stored(bits: i32) -> Self {
+        Self::from_bits_truncate(bits as u32)
+    }
+
+    pub fn to_stored(self) -> i32 {
+        self.bits() as i32
+    }
+
+    /// Permissions in the set, in bit order
+    pub fn permissions(&self) -> Vec<Permission> {
+        ALL_PERMISSIONS
+            .into_iter()
+            .filter(|permission| self.contains((*permission).into()))
+            .collect()
+    }
+}
+
+/// Named role in a space
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Role {
+    pub name: String,
+    pub permissions: PermissionSet,
+}
+
+impl Role {
+    pub fn new(name: impl Into<String>, permissions: PermissionSet) -> Self {
+        Self {
+            name: name.into(),
+            permissions,
+        }
+    }
+
+    /// Built-in role with the given name, if there is one
+    pub fn builtin(name: &str) -> Option<Self> {
+        BuiltinRole::from_str(name).map(Self::from)
+    }
+}
+
+impl From<BuiltinRole> for Role {
+    fn from(role: BuiltinRole) -> Self {
+        Self::new(
+            role.display_name().to_lowercase(),
+            PermissionSet::from_permissions(&RbacConfig::get_permissions_for_role(&role)),
+        )
+    }
+}
+
+/// The roles every space has, as seeded into `space_roles`
+pub fn builtin_roles() -> Vec<Role> {
+    [
+        BuiltinRole::Owner,
+        BuiltinRole::Editor,
+        BuiltinRole::Commenter,
+        BuiltinRole::Viewer,
+    ]
+    .into_iter()
+    .map(Role::from)
+    .collect()
+}
+
+pub fn has_permission(role: &Role, permission: Permission) -> bool {
+    role.permissions.contains(permission.into())
+}
+
+/// Whether the role may create comments and edit its own
+pub fn can_comment(role: &Role) -> bool {
+    has_permission(role, Permission::Comment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Handlers used to allow these actions with `role == "owner" || role == "editor"`
+    #[test]
+    fn test_builtin_roles_match_inline_checks() {
+        for role in builtin_roles() {
+            let was_allowed = role.name == "owner" || role.name == "editor";
+            assert_eq!(
+                has_permission(&role, Permission::CreateDocuments),
+                was_allowed,
+                "{}",
+                role.name
+            );
+            assert_eq!(
+                has_permission(&role, Permission::EditDocuments),
+                was_allowed,
+                "{}",
+                role.name
+            );
+            assert_eq!(
+                has_permission(&role, Permission::ManageMembers),
+                was_allowed,
+                "{}",
+                role.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_can_comment() {
+        assert!(can_comment(&Role::builtin("owner").unwrap()));
+        assert!(can_comment(&Role::builtin("editor").unwrap()));
+        assert!(can_comment(&Role::builtin("commenter").unwrap()));
+        assert!(!can_comment(&Role::builtin("viewer").unwrap()));
+
+        let commenter = Role::builtin("commenter").unwrap();
+        assert!(!has_permission(&commenter, Permission::EditDocuments));
+    }
+
+    #[test]
+    fn test_builtin_roles_keep_configured_permissions() {
+        for role in builtin_roles() {
+            let builtin = BuiltinRole::from_str(&role.name).unwrap();
+            assert_eq!(
+                role.permissions.permissions(),
+                RbacConfig::get_permissions_for_role(&builtin)
+            );
+        }
+        assert_eq!(builtin_roles().len(), 4);
+    }
+
+    #[test]
+    fn test_builtin_role_bits_match_seed() {
+        // Values seeded by migrations/024_space_roles.sql
+        let seeded = [("owner", 511), ("editor", 119), ("commenter", 17), ("viewer", 1)];
+        for (name, bits) in seeded {
+            assert_eq!(Role::builtin(name).unwrap().permissions.to_stored(), bits, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_custom_role() {
+        let reviewer = Role::new(
+            "reviewer",
+            PermissionSet::from_permissions(&[
+                Permission::ViewDocuments,
+                Permission::Comment,
+                Permission::EditDocuments,
+            ]),
+        );
+        assert!(has_permission(&reviewer, Permission::EditDocuments));
+        assert!(!has_permission(&reviewer, Permission::CreateDocuments));
+
+        let stored = Role::new("reviewer", PermissionSet::from_stored(reviewer.permissions.to_stored()));
+        assert_eq!(stored, reviewer);
+        assert!(Role::builtin("reviewer").is_none());
+    }
+}
@@ -1,7 +1,66 @@
+use chrono::NaiveDateTime;
 use shared_models::entities::{RefreshToken, User};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// Stored password reset token; only the hash of the token is persisted
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ResetTokenRecord {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+}
+
+/// Stored email verification token; only the hash of the token is persisted
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct VerificationTokenRecord {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub verified_at: Option<NaiveDateTime>,
+}
+
+/// Active session tied to an issued refresh token
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SessionRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used: NaiveDateTime,
+}
+
+/// API key as stored; the key itself is never persisted
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PublicProfileRow {
+    pub id: Uuid,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Result of asking for a fresh email verification token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationReissue {
+    /// Previous tokens were invalidated and the new one stored
+    Issued,
+    /// A token was sent too recently; retry after this many seconds
+    Cooldown { retry_after_secs: i64 },
+}
+
 pub struct AuthRepository {
     pool: PgPool,
 }
@@ -35,6 +94,16 @@ impl AuthRepository {
         .await
     }
 
+    /// Public profiles of the given users; unknown ids are left out
+    pub async fn find_public_profiles(&self, ids: &[Uuid]) -> Result<Vec<PublicProfileRow>, sqlx::Error> {
+        sqlx::query_as::<_, PublicProfileRow>(
+            "SELECT id, display_name, avatar_url FROM users WHERE id = ANY($1) ORDER BY display_name, id",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     pub async fn create(&self, email: &str, password_hash: &str, display_name: &str) -> Result<User, sqlx::Error> {
         let user = sqlx::query_as::<_, User>(
             "INSERT INTO users (email, password_hash, display_name) 
@@ -107,4 +176,388 @@ impl AuthRepository {
             .fetch_optional(&self.pool)
             .await
     }
+
+    pub async fn update_password(&self, user_id: &Uuid, password_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(password_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create_password_reset(
+        &self,
+        user_id: &Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO password_resets (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_password_reset(&self, token_hash: &str) -> Result<Option<ResetTokenRecord>, sqlx::Error> {
+        sqlx::query_as::<_, ResetTokenRecord>(
+            "SELECT user_id, token_hash, expires_at, used_at FROM password_resets WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Redeem an unused, unexpired reset token and set the user's new
+    /// password in one transaction, returning the user id
+    ///
+    /// Returns `None` when the token is unknown, expired or already used.
+    pub async fn redeem_password_reset(
+        &self,
+        token_hash: &str,
+        password_hash: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let user_id: Option<Uuid> = sqlx::query_scalar(
+            "UPDATE password_resets SET used_at = $2
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(user_id) = user_id else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        sqlx::query("UPDATE users SET password_hash = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(password_hash)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(user_id))
+    }
+
+    pub async fn mark_password_reset_used(&self, token_hash: &str) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE password_resets SET used_at = NOW() WHERE token_hash = $1 AND used_at IS NULL")
+                .bind(token_hash)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn create_email_verification(
+        &self,
+        user_id: &Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO email_verifications (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_email_verification(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<VerificationTokenRecord>, sqlx::Error> {
+        sqlx::query_as::<_, VerificationTokenRecord>(
+            "SELECT user_id, token_hash, expires_at, verified_at FROM email_verifications WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Redeem a pending, unexpired verification token and mark the user's
+    /// email verified in one transaction, returning the user id
+    ///
+    /// Returns `None` when the token is unknown, expired or already redeemed.
+    pub async fn redeem_email_verification(
+        &self,
+        token_hash: &str,
+        now: NaiveDateTime,
+    ) -> Result<Option<Uuid>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let user_id: Option<Uuid> = sqlx::query_scalar(
+            "UPDATE email_verifications SET verified_at = $2
+             WHERE token_hash = $1 AND verified_at IS NULL AND expires_at > $2
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(user_id) = user_id else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE users SET is_email_verified = true, email_verified_at = NOW(), updated_at = NOW() WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(user_id))
+    }
+
+    /// Replace the user's pending verification tokens with a new one, unless
+    /// the last token was issued less than `cooldown` ago
+    pub async fn reissue_email_verification(
+        &self,
+        user_id: &Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        cooldown: chrono::Duration,
+    ) -> Result<VerificationReissue, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize concurrent resends for the same user
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let remaining: (Option<i64>,) = sqlx::query_as(
+            "SELECT CEIL(EXTRACT(EPOCH FROM MAX(created_at) + make_interval(secs => $2) - NOW()))::BIGINT
+             FROM email_verifications WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(cooldown.num_seconds() as f64)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(retry_after_secs) = remaining.0.filter(|secs| *secs > 0) {
+            tx.rollback().await?;
+            return Ok(VerificationReissue::Cooldown { retry_after_secs });
+        }
+
+        sqlx::query("DELETE FROM email_verifications WHERE user_id = $1 AND verified_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO email_verifications (user_id, token_hash, expires_at) VALUES ($1, $2, $3)")
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(VerificationReissue::Issued)
+    }
+
+    pub async fn create_session(
+        &self,
+        session_id: &Uuid,
+        user_id: &Uuid,
+        refresh_token_id: &Uuid,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO sessions (id, user_id, refresh_token_id, user_agent, ip_address)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(refresh_token_id)
+        .bind(user_agent)
+        .bind(ip_address)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sessions whose refresh token is still usable, most recently used first
+    pub async fn list_sessions(&self, user_id: &Uuid) -> Result<Vec<SessionRow>, sqlx::Error> {
+        sqlx::query_as::<_, SessionRow>(
+            "SELECT s.id, s.user_id, s.user_agent, s.ip_address, s.created_at, s.last_used
+             FROM sessions s
+             JOIN refresh_tokens rt ON rt.id = s.refresh_token_id
+             WHERE s.user_id = $1 AND s.revoked_at IS NULL
+             AND rt.is_revoked = false AND rt.expires_at > NOW()
+             ORDER BY s.last_used DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Record use of the session behind a refresh token and return its id
+    pub async fn touch_session(&self, refresh_token: &str) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "UPDATE sessions s SET last_used = NOW()
+             FROM refresh_tokens rt
+             WHERE rt.id = s.refresh_token_id AND rt.token = $1 AND s.revoked_at IS NULL
+             RETURNING s.id",
+        )
+        .bind(refresh_token)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Revoke one of the user's sessions along with its refresh token
+    pub async fn revoke_session(&self, user_id: &Uuid, session_id: &Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "WITH revoked AS (
+                 UPDATE sessions SET revoked_at = NOW()
+                 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+                 RETURNING refresh_token_id
+             )
+             UPDATE refresh_tokens SET is_revoked = true, revoked_at = NOW()
+             WHERE id IN (SELECT refresh_token_id FROM revoked)",
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke every session of the user except `keep`, returning how many were revoked
+    /// Revoke every other session of the user, returning the revoked session ids
+    pub async fn revoke_other_sessions(&self, user_id: &Uuid, keep: &Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            "WITH revoked AS (
+                 UPDATE sessions SET revoked_at = NOW()
+                 WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL
+                 RETURNING id, refresh_token_id
+             ), tokens AS (
+                 UPDATE refresh_tokens SET is_revoked = true, revoked_at = NOW()
+                 WHERE id IN (SELECT refresh_token_id FROM revoked)
+             )
+             SELECT id FROM revoked",
+        )
+        .bind(user_id)
+        .bind(keep)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn create_api_key(
+        &self,
+        user_id: &Uuid,
+        name: &str,
+        key_prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<ApiKeyRow, sqlx::Error> {
+        sqlx::query_as::<_, ApiKeyRow>(
+            "INSERT INTO api_keys (user_id, name, key_prefix, key_hash, scopes, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id, user_id, name, key_prefix, scopes, created_at, last_used_at, expires_at",
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(key_prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// The user's keys that have not been revoked, newest first
+    pub async fn list_api_keys(&self, user_id: &Uuid) -> Result<Vec<ApiKeyRow>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKeyRow>(
+            "SELECT id, user_id, name, key_prefix, scopes, created_at, last_used_at, expires_at
+             FROM api_keys
+             WHERE user_id = $1 AND revoked_at IS NULL
+             ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The user's role in a space: `owner` for the space owner, otherwise their
+    /// membership role, or `None` when they have no access
+    pub async fn find_space_role(&self, user_id: &Uuid, space_id: &Uuid) -> Result<Option<String>, sqlx::Error> {
+        let role: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT CASE WHEN s.owner_id = $1 THEN 'owner' ELSE sm.role END
+             FROM spaces s
+             LEFT JOIN space_memberships sm ON sm.space_id = s.id AND sm.user_id = $1
+             WHERE s.id = $2",
+        )
+        .bind(user_id)
+        .bind(space_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(role.and_then(|(role,)| role))
+    }
+
+    /// Every space the user owns or is a member of, with their role in it;
+    /// deleted spaces are left out
+    pub async fn list_space_roles(&self, user_id: &Uuid) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT s.id, CASE WHEN s.owner_id = $1 THEN 'owner' ELSE sm.role END
+             FROM spaces s
+             LEFT JOIN space_memberships sm ON sm.space_id = s.id AND sm.user_id = $1
+             WHERE (s.owner_id = $1 OR sm.user_id IS NOT NULL) AND s.is_deleted = false
+             ORDER BY s.created_at, s.id",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Look up a usable key by hash and record that it was used
+    pub async fn authenticate_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyRow>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKeyRow>(
+            "UPDATE api_keys k SET last_used_at = NOW()
+             FROM users u
+             WHERE k.key_hash = $1 AND k.revoked_at IS NULL
+             AND (k.expires_at IS NULL OR k.expires_at > NOW())
+             AND u.id = k.user_id AND u.is_active = true
+             RETURNING k.id, k.user_id, k.name, k.key_prefix, k.scopes, k.created_at, k.last_used_at, k.expires_at",
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn revoke_api_key(&self, user_id: &Uuid, key_id: &Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET revoked_at = NOW()
+             WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(key_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
@@ -1,80 +1,220 @@
-use actix_web::{web, HttpResponse, Responder, HttpRequest};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use shared_errors::AppError;
-use shared_models::entities::User;
-
-#[derive(Debug, Serialize)]
-pub struct SessionInfo {
-    pub id: String,
-    pub device_name: Option<String>,
-    pub ip_address: Option<String>,
-    pub created_at: String,
-    pub last_active: String,
-    pub is_current: bool,
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
+use uuid::Uuid;
+
+use crate::jwt::{verify_access_token, Claims, JwtService};
+use crate::models::{RevokeSessionsResponse, SessionListResponse, SessionResponse};
+use crate::repository::{AuthRepository, SessionRow};
+use crate::token_denylist::TokenDenylist;
+
+// Validate the bearer access token and return its claims
+//
+// Refresh tokens are refused, and so are access tokens whose session has
+// been revoked or logged out.
+pub(crate) async fn authenticate(req: &HttpRequest, jwt_service: &JwtService) -> Result<Claims, HttpResponse> {
+    let unauthorized = |message: &str| {
+        HttpResponse::Unauthorized().json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": message }))
+    };
+
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(JwtService::extract_token_from_header)
+        .ok_or_else(|| unauthorized("Missing or invalid authorization header"))?;
+
+    let claims = verify_access_token(token, &jwt_service.config).map_err(|e| unauthorized(&e.to_string()))?;
+
+    if let (Some(denylist), Some(sid)) = (req.app_data::<web::Data<TokenDenylist>>(), &claims.sid) {
+        if denylist.is_session_revoked(sid).await {
+            return Err(unauthorized("Session has been revoked"));
+        }
+    }
+
+    Ok(claims)
+}
+
+pub(crate) fn parse_uuid(value: &str) -> Option<Uuid> {
+    Uuid::parse_str(value).ok()
 }
 
-/// Get all active sessions for the authenticated user
-#[actix_web::get("/auth/sessions")]
-async fn get_sessions(
+fn session_to_response(row: &SessionRow, current: Option<Uuid>) -> SessionResponse {
+    SessionResponse {
+        id: row.id.to_string(),
+        user_agent: row.user_agent.clone(),
+        ip_address: row.ip_address.clone(),
+        created_at: row.created_at.and_utc().to_rfc3339(),
+        last_used: row.last_used.and_utc().to_rfc3339(),
+        is_current: current == Some(row.id),
+    }
+}
+
+/// List the caller's active sessions ("logged-in devices")
+pub async fn list_sessions(
     req: HttpRequest,
-    _repo: web::Data<crate::repository::AuthRepository>,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
 ) -> impl Responder {
-    HttpResponse::Ok()
-        .json(json!({
-            "message": "Sessions retrieved successfully",
-            "phase": "RED",
-            "todo": [
-                "Extract user from JWT token",
-                "Query sessions from database",
-                "Mark current session",
-                "Return session list"
-            ],
-            "sessions": []
-        }))
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let user_id = match parse_uuid(&claims.user_id) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized()
+                .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+        },
+    };
+    let current = claims.sid.as_deref().and_then(parse_uuid);
+
+    match repo.list_sessions(&user_id).await {
+        Ok(rows) => HttpResponse::Ok().json(SessionListResponse {
+            sessions: rows.iter().map(|row| session_to_response(row, current)).collect(),
+        }),
+        Err(e) => {
+            tracing::error!("Database error while listing sessions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
 }
 
-/// Delete a specific session
-#[actix_web::delete("/auth/sessions/{session_id}")]
-async fn delete_session(
+/// Revoke one of the caller's sessions
+pub async fn revoke_session(
     path: web::Path<String>,
-    _repo: web::Data<crate::repository::AuthRepository>,
+    req: HttpRequest,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+    denylist: Option<web::Data<TokenDenylist>>,
 ) -> impl Responder {
-    let session_id = path.into_inner();
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
 
-    if session_id.is_empty() {
-        return HttpResponse::BadRequest()
-            .json(json!({ "error": "VALIDATION_ERROR", "message": "Session ID is required" }));
-    }
+    let user_id = match parse_uuid(&claims.user_id) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized()
+                .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+        },
+    };
 
-    HttpResponse::Ok()
-        .json(json!({
-            "message": "Session deleted successfully",
-            "phase": "RED",
-            "todo": [
-                "Validate session exists and belongs to user",
-                "Delete session from database",
-                "Return success response"
-            ]
-        }))
+    let session_id = match parse_uuid(&path.into_inner()) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::BadRequest()
+                .json(serde_json::json!({ "error": "VALIDATION_ERROR", "message": "Invalid session ID" }));
+        },
+    };
+
+    match repo.revoke_session(&user_id, &session_id).await {
+        Ok(true) => {
+            // Access tokens already issued for the session stop working now
+            if let Some(denylist) = &denylist {
+                let ttl = std::time::Duration::from_secs(jwt_service.config.access_expiry.max(0) as u64);
+                denylist.revoke_session(&session_id.to_string(), ttl).await;
+            }
+            HttpResponse::Ok().json(serde_json::json!({ "message": "Session revoked" }))
+        },
+        Ok(false) => {
+            HttpResponse::NotFound().json(serde_json::json!({ "error": "NOT_FOUND", "message": "Session not found" }))
+        },
+        Err(e) => {
+            tracing::error!("Database error while revoking session: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
 }
 
-/// Delete all sessions except the current one
-#[actix_web::delete("/auth/sessions")]
-async fn delete_all_sessions(
+/// Revoke every session of the caller except the one making the request
+pub async fn revoke_other_sessions(
     req: HttpRequest,
-    _repo: web::Data<crate::repository::AuthRepository>,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+    denylist: Option<web::Data<TokenDenylist>>,
 ) -> impl Responder {
-    HttpResponse::Ok()
-        .json(json!({
-            "message": "All sessions deleted successfully",
-            "phase": "RED",
-            "todo": [
-                "Extract user from JWT token",
-                "Get current session ID from token",
-                "Delete all sessions except current",
-                "Return count of deleted sessions"
-            ],
-            "deleted_count": 0
-        }))
+    let claims = match authenticate(&req, &jwt_service).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let user_id = match parse_uuid(&claims.user_id) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::Unauthorized()
+                .json(serde_json::json!({ "error": "AUTHENTICATION_ERROR", "message": "Invalid token" }));
+        },
+    };
+
+    // Tokens issued before the session registry carry no session id
+    let current = match claims.sid.as_deref().and_then(parse_uuid) {
+        Some(id) => id,
+        None => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "VALIDATION_ERROR",
+                "message": "Access token is not bound to a session. Please sign in again."
+            }));
+        },
+    };
+
+    match repo.revoke_other_sessions(&user_id, &current).await {
+        Ok(revoked) => {
+            // Access tokens on the other devices stop working now
+            if let Some(denylist) = &denylist {
+                let ttl = std::time::Duration::from_secs(jwt_service.config.access_expiry.max(0) as u64);
+                for session_id in &revoked {
+                    denylist.revoke_session(&session_id.to_string(), ttl).await;
+                }
+            }
+            HttpResponse::Ok().json(RevokeSessionsResponse {
+                revoked: revoked.len() as u64,
+            })
+        },
+        Err(e) => {
+            tracing::error!("Database error while revoking sessions: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn session_row(id: Uuid) -> SessionRow {
+        let now = Utc::now().naive_utc();
+        SessionRow {
+            id,
+            user_id: Uuid::new_v4(),
+            user_agent: Some("Mozilla/5.0".to_string()),
+            ip_address: Some("127.0.0.1".to_string()),
+            created_at: now,
+            last_used: now,
+        }
+    }
+
+    #[test]
+    fn test_session_response_marks_current() {
+        let id = Uuid::new_v4();
+        let row = session_row(id);
+
+        assert!(session_to_response(&row, Some(id)).is_current);
+        assert!(!session_to_response(&row, Some(Uuid::new_v4())).is_current);
+        assert!(!session_to_response(&row, None).is_current);
+    }
+
+    #[test]
+    fn test_session_response_fields() {
+        let id = Uuid::new_v4();
+        let response = session_to_response(&session_row(id), None);
+
+        assert_eq!(response.id, id.to_string());
+        assert_eq!(response.user_agent.as_deref(), Some("Mozilla/5.0"));
+        assert!(response.last_used.ends_with("+00:00"));
+    }
 }
@@ -0,0 +1,210 @@
+//! Revoked token denylist
+//!
+//! Logout records the refresh token's id (`jti`) here for the rest of the
+//! token's lifetime, and the refresh endpoint refuses any token on the list
+//! before looking at the database. Logout and session revocation also record
+//! the session id (`sid`) that access tokens are bound to, so those access
+//! tokens stop authenticating (see `identity::extract_user_id`) before they
+//! expire. Entries live in Redis when a connection is available so every
+//! instance sees a logout at once; if Redis is not configured or a command
+//! fails, an in-memory store is used.
+
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Error code returned for refresh tokens on the denylist
+pub const TOKEN_REVOKED_CODE: &str = "TOKEN_REVOKED";
+
+const KEY_PREFIX: &str = "auth:denylist:jti:";
+const SESSION_KEY_PREFIX: &str = "auth:denylist:sid:";
+
+/// Entries kept in memory before expired ones are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Denylist of revoked refresh token ids and session ids
+pub struct TokenDenylist {
+    redis: Option<MultiplexedConnection>,
+    fallback: Mutex<HashMap<String, Instant>>,
+}
+
+impl Default for TokenDenylist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenDenylist {
+    /// Create an in-memory denylist
+    pub fn new() -> Self {
+        Self {
+            redis: None,
+            fallback: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a denylist backed by Redis, using in-memory entries if the
+    /// connection cannot be established
+    pub async fn connect(redis_url: Option<&str>) -> Self {
+        let redis = match redis_url.filter(|url| !url.is_empty()) {
+            Some(url) => match redis::Client::open(url) {
+                Ok(client) => match client.get_multiplexed_async_connection().await {
+                    Ok(conn) => Some(conn),
+                    Err(e) => {
+                        tracing::warn!("Failed to connect to Redis for token denylist, using in-memory: {}", e);
+                        None
+                    },
+                },
+                Err(e) => {
+                    tracing::warn!("Invalid Redis URL for token denylist, using in-memory: {}", e);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        Self {
+            redis,
+            ..Self::new()
+        }
+    }
+
+    /// Deny `jti` for `ttl`, normally the token's remaining lifetime
+    ///
+    /// Tokens that have already expired are rejected anyway and are not
+    /// recorded.
+    pub async fn revoke(&self, jti: &str, ttl: Duration) {
+        self.deny(denylist_key(jti), ttl).await
+    }
+
+    /// Whether `jti` has been revoked and its token has not yet expired
+    pub async fn is_revoked(&self, jti: &str) -> bool {
+        self.is_denied(&denylist_key(jti)).await
+    }
+
+    /// Deny access tokens bound to session `sid` for `ttl`, normally the
+    /// access token lifetime
+    pub async fn revoke_session(&self, sid: &str, ttl: Duration) {
+        self.deny(session_key(sid), ttl).await
+    }
+
+    /// Whether access tokens bound to session `sid` have been revoked
+    pub async fn is_session_revoked(&self, sid: &str) -> bool {
+        self.is_denied(&session_key(sid)).await
+    }
+
+    async fn deny(&self, key: String, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            // Redis expiries are whole seconds; round up so the entry never
+            // lapses before the token does
+            let ttl_secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+            match conn.set_ex::<_, _, ()>(&key, 1, ttl_secs).await {
+                Ok(()) => return,
+                Err(e) => tracing::warn!("Redis error revoking token: {}", e),
+            }
+        }
+
+        let now = Instant::now();
+        let mut entries = self.fallback.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() > PRUNE_THRESHOLD {
+            entries.retain(|_, until| *until > now);
+        }
+        entries.insert(key, now + ttl);
+    }
+
+    async fn is_denied(&self, key: &str) -> bool {
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            match conn.exists::<_, bool>(key).await {
+                Ok(true) => return true,
+                Ok(false) => {},
+                Err(e) => tracing::warn!("Redis error checking token denylist: {}", e),
+            }
+        }
+
+        // Revocations made while Redis was failing are only held here
+        let mut entries = self.fallback.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some(until) if *until > Instant::now() => true,
+            Some(_) => {
+                entries.remove(key);
+                false
+            },
+            None => false,
+        }
+    }
+}
+
+fn denylist_key(jti: &str) -> String {
+    format!("{}{}", KEY_PREFIX, jti)
+}
+
+fn session_key(sid: &str) -> String {
+    format!("{}{}", SESSION_KEY_PREFIX, sid)
+}
+
+/// Time left before a token with the given `exp` claim expires
+pub fn remaining_lifetime(exp: usize, now: chrono::DateTime<chrono::Utc>) -> Duration {
+    let remaining = i64::try_from(exp).unwrap_or(i64::MAX) - now.timestamp();
+    Duration::from_secs(remaining.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revoked_jti_is_denied() {
+        let denylist = TokenDenylist::new();
+
+        assert!(!denylist.is_revoked("jti-1").await);
+        denylist.revoke("jti-1", Duration::from_secs(60)).await;
+
+        assert!(denylist.is_revoked("jti-1").await);
+        assert!(!denylist.is_revoked("jti-2").await);
+    }
+
+    #[tokio::test]
+    async fn test_entry_expires_with_token() {
+        let denylist = TokenDenylist::connect(None).await;
+        denylist.revoke("jti-1", Duration::from_millis(20)).await;
+        assert!(denylist.is_revoked("jti-1").await);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(!denylist.is_revoked("jti-1").await);
+    }
+
+    #[tokio::test]
+    async fn test_session_and_jti_entries_are_separate() {
+        let denylist = TokenDenylist::new();
+        denylist.revoke_session("id-1", Duration::from_secs(60)).await;
+
+        assert!(denylist.is_session_revoked("id-1").await);
+        assert!(!denylist.is_revoked("id-1").await);
+        assert!(!denylist.is_session_revoked("id-2").await);
+    }
+
+    #[tokio::test]
+    async fn test_expired_token_is_not_recorded() {
+        let denylist = TokenDenylist::new();
+        denylist.revoke("jti-1", Duration::ZERO).await;
+
+        assert!(!denylist.is_revoked("jti-1").await);
+    }
+
+    #[test]
+    fn test_remaining_lifetime() {
+        let now = chrono::Utc::now();
+        let exp = now.timestamp() as usize;
+
+        assert_eq!(remaining_lifetime(exp + 90, now), Duration::from_secs(90));
+        assert_eq!(remaining_lifetime(exp - 5, now), Duration::ZERO);
+    }
+}
@@ -0,0 +1,66 @@
+//! Public user profiles
+//!
+//! Clients that show author names, mentions or member lists only hold user
+//! ids; `POST /users/resolve` turns a batch of them into display names and
+//! avatars. Nothing beyond the public profile is exposed.
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::jwt::JwtService;
+use crate::models::{PublicProfileResponse, ResolveUsersRequest, ResolveUsersResponse, MAX_RESOLVE_USER_IDS};
+use crate::repository::AuthRepository;
+use crate::sessions::{authenticate, parse_uuid};
+
+/// Resolve up to `MAX_RESOLVE_USER_IDS` user ids to public profiles
+///
+/// Unknown or malformed ids are left out of the response rather than failing
+/// the whole batch.
+pub async fn resolve_users(
+    req: HttpRequest,
+    body: web::Json<ResolveUsersRequest>,
+    repo: web::Data<AuthRepository>,
+    jwt_service: web::Data<JwtService>,
+) -> impl Responder {
+    if let Err(response) = authenticate(&req, &jwt_service).await {
+        return response;
+    }
+
+    if body.user_ids.len() > MAX_RESOLVE_USER_IDS {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "VALIDATION_ERROR",
+            "message": format!("At most {} user IDs can be resolved per request", MAX_RESOLVE_USER_IDS)
+        }));
+    }
+
+    let ids: Vec<Uuid> = body
+        .user_ids
+        .iter()
+        .filter_map(|id| parse_uuid(id))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    if ids.is_empty() {
+        return HttpResponse::Ok().json(ResolveUsersResponse { users: Vec::new() });
+    }
+
+    match repo.find_public_profiles(&ids).await {
+        Ok(rows) => HttpResponse::Ok().json(ResolveUsersResponse {
+            users: rows
+                .into_iter()
+                .map(|row| PublicProfileResponse {
+                    id: row.id.to_string(),
+                    display_name: row.display_name,
+                    avatar_url: row.avatar_url,
+                })
+                .collect(),
+        }),
+        Err(e) => {
+            tracing::error!("Database error while resolving users: {}", e);
+            HttpResponse::InternalServerError()
+                .json(serde_json::json!({ "error": "DATABASE_ERROR", "message": "Internal server error" }))
+        },
+    }
+}
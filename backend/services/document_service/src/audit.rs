@@ -0,0 +1,84 @@
+//! Space Audit Log Handlers
+//!
+//! Provides the HTTP handler for a space's membership audit log:
+//! - GET /spaces/{spaceId}/audit - List membership and role changes
+//!
+//! Entries are written by the repository in the same transaction as the
+//! membership change, so the log can't disagree with the member list. Only
+//! the space owner can read it.
+use actix_web::{web, HttpResponse, Responder};
+use shared_models::pagination::next_offset;
+use tracing::error;
+
+use crate::handlers::{extract_user_id, unauthorized_response};
+use crate::models::*;
+use crate::repository::{DocumentRepository, SpaceAuditRow};
+
+fn audit_row_to_response(row: &SpaceAuditRow) -> SpaceAuditEntryResponse {
+    SpaceAuditEntryResponse {
+        id: row.id.to_string(),
+        space_id: row.space_id.to_string(),
+        actor_id: row.actor_id.map(|id| id.to_string()),
+        target_user_id: row.target_user_id.map(|id| id.to_string()),
+        action: row.action.clone(),
+        old_role: row.old_role.clone(),
+        new_role: row.new_role.clone(),
+        created_at: row.created_at.and_utc().to_rfc3339(),
+    }
+}
+
+/// List a space's membership changes, newest first (owner only)
+pub async fn list_space_audit(
+    space_id: web::Path<String>,
+    query: web::Query<ListSpaceAuditQuery>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.is_space_owner(&space_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "Only the space owner can view the audit log",
+            ));
+        },
+        Err(e) => {
+            error!("Database error checking space owner: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match repo.list_space_audit(&space_id, limit, offset).await {
+        Ok((rows, total)) => {
+            let next_offset = next_offset(offset.into(), rows.len(), total);
+            HttpResponse::Ok().json(ApiResponse::<SpaceAuditListResponse>::success(SpaceAuditListResponse {
+                entries: rows.iter().map(audit_row_to_response).collect(),
+                total,
+                limit,
+                offset,
+                has_more: next_offset.is_some(),
+                next_offset,
+            }))
+        },
+        Err(e) => {
+            error!("Database error listing space audit log: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
@@ -7,15 +7,21 @@
 //! - POST /comments/{commentId}/resolve - Resolve comment
 //! - POST /comments/{commentId}/unresolve - Unresolve comment
 //! - DELETE /comments/{commentId} - Delete comment
+//! - POST /comments/{commentId}/reactions - Add a reaction
+//! - DELETE /comments/{commentId}/reactions/{emoji} - Remove a reaction
 //!
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use auth_service::rbac::roles::can_comment;
 use shared_errors::{AppError, ErrorCode};
 use tracing::error;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::handlers::{document_access_denied, extract_user_id};
+use crate::mentions::{mention_response, resolve_content_mentions};
 use crate::models::*;
 use crate::repository::CommentRow;
+use crate::repository::CommentReactionRow;
 use crate::repository::DocumentRepository;
 
 #[cfg(test)]
@@ -33,24 +39,24 @@ mod tests {
     }
 
     // extract_user_id Tests
-    #[test]
-    fn test_extract_user_id_invalid_format() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_invalid_format() {
         let req = TestRequest::get().insert_header(("X-User-Id", "not-a-uuid")).to_http_request();
 
-        let user_id = extract_user_id(&req);
+        let user_id = extract_user_id(&req).await;
         // extract_user_id should validate UUID format and return error for invalid UUIDs
         assert!(user_id.is_err());
     }
 
-    #[test]
-    fn test_extract_user_id_missing() {
-        let req = TestRequest::get().to_http_request(); // No X-User-Id header
+    #[actix_rt::test]
+    async fn test_extract_user_id_missing() {
+        let req = TestRequest::get().to_http_request(); // No Bearer token or X-User-Id header
 
-        let user_id = extract_user_id(&req);
+        let user_id = extract_user_id(&req).await;
         assert!(user_id.is_err());
         let err = user_id.unwrap_err();
         assert!(matches!(err, AppError::AuthenticationError(_)));
-        assert!(err.to_string().contains("Missing X-User-Id header"));
+        assert!(err.to_string().contains("Missing or invalid authentication"));
     }
 
     // extract_user_name Tests
@@ -382,6 +388,8 @@ mod tests {
             resolved_at: Some("2026-01-22T10:00:00Z".to_string()),
             created_at: "2026-01-22T08:00:00Z".to_string(),
             updated_at: Some("2026-01-22T09:00:00Z".to_string()),
+            mentions: Vec::new(),
+            reactions: Vec::new(),
         };
 
         assert_eq!(response.id, "comment-001");
@@ -406,6 +414,8 @@ mod tests {
             resolved_at: None,
             created_at: "2026-01-22T08:00:00Z".to_string(),
             updated_at: None,
+            mentions: Vec::new(),
+            reactions: Vec::new(),
         };
 
         assert_eq!(response.parent_id, None);
@@ -576,19 +586,6 @@ mod tests {
     }
 }
 
-/// Extract user ID from request header
-fn extract_user_id(req: &HttpRequest) -> Result<String, AppError> {
-    let raw = req
-        .headers()
-        .get("X-User-Id")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| AppError::AuthenticationError("Missing X-User-Id header".to_string()))?;
-
-    Uuid::parse_str(raw).map_err(|_| AppError::AuthenticationError("Invalid X-User-Id format".to_string()))?;
-
-    Ok(raw.to_string())
-}
-
 /// Extract user name from request header (optional)
 fn extract_user_name(req: &HttpRequest) -> String {
     req.headers()
@@ -598,6 +595,18 @@ fn extract_user_name(req: &HttpRequest) -> String {
         .unwrap_or_else(|| "Unknown User".to_string())
 }
 
+/// Shown for authors whose user record no longer exists
+const UNKNOWN_AUTHOR: &str = "Unknown User";
+
+/// Convert a row with joined author fields to CommentResponse
+fn comment_to_response(row: &CommentRow) -> CommentResponse {
+    comment_row_to_response(
+        row,
+        row.author_name.as_deref().unwrap_or(UNKNOWN_AUTHOR),
+        row.author_avatar.as_deref(),
+    )
+}
+
 /// Convert database row to CommentResponse
 fn comment_row_to_response(row: &CommentRow, author_name: &str, author_avatar: Option<&str>) -> CommentResponse {
     CommentResponse {
@@ -613,6 +622,44 @@ fn comment_row_to_response(row: &CommentRow, author_name: &str, author_avatar: O
         resolved_at: row.resolved_at.map(|t| t.and_utc().to_rfc3339()),
         created_at: row.created_at.and_utc().to_rfc3339(),
         updated_at: Some(row.updated_at.and_utc().to_rfc3339()),
+        mentions: Vec::new(),
+        reactions: Vec::new(),
+    }
+}
+
+/// Reaction summaries of one comment out of rows for several
+fn reaction_summaries(rows: &[CommentReactionRow], comment_id: Uuid) -> Vec<ReactionSummary> {
+    rows.iter()
+        .filter(|r| r.comment_id == comment_id)
+        .map(|r| ReactionSummary {
+            emoji: r.emoji.clone(),
+            count: r.count,
+            reacted_by_me: r.reacted_by_me,
+        })
+        .collect()
+}
+
+/// Check that the caller's role in the document's space allows commenting
+async fn check_can_comment(
+    repo: &DocumentRepository,
+    document_id: &str,
+    user_id: Uuid,
+    http_req: &HttpRequest,
+) -> Result<(), HttpResponse> {
+    match repo.get_document_role(document_id, user_id).await {
+        Ok(Some(role)) if can_comment(&role) => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "PERMISSION_DENIED",
+            "Your role does not allow commenting on this document",
+        ))),
+        Ok(None) => Err(document_access_denied(http_req)),
+        Err(e) => {
+            error!("Database error checking comment permission: {:?}", e);
+            Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "Failed to verify document access",
+            )))
+        },
     }
 }
 
@@ -624,7 +671,7 @@ pub async fn list_comments(
     http_req: HttpRequest,
 ) -> impl Responder {
     let document_id = document_id.into_inner();
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -635,14 +682,9 @@ pub async fn list_comments(
     };
 
     // Check document access
-    match repo.check_document_access(&document_id, &user_id).await {
+    match repo.check_document_access(&document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have permission to view comments on this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(e) => {
             error!("Database error checking document access: {:?}", e);
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
@@ -658,13 +700,35 @@ pub async fn list_comments(
         .await
     {
         Ok((comments, total)) => {
+            let comment_ids: Vec<Uuid> = comments.iter().map(|row| row.id).collect();
+            let mentions = match repo.list_comment_mentions(&comment_ids).await {
+                Ok(mentions) => mentions,
+                Err(e) => {
+                    error!("Database error listing comment mentions: {:?}", e);
+                    return HttpResponse::InternalServerError()
+                        .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to list comments"));
+                },
+            };
+            let reactions = match repo.list_comment_reactions(&comment_ids, user_id).await {
+                Ok(reactions) => reactions,
+                Err(e) => {
+                    error!("Database error listing comment reactions: {:?}", e);
+                    return HttpResponse::InternalServerError()
+                        .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to list comments"));
+                },
+            };
+
             let comment_responses: Vec<CommentResponse> = comments
                 .iter()
                 .map(|row| {
-                    // TODO: Join with users table to get author_name and author_avatar
-                    let author_name = "User"; // Placeholder until user lookup is implemented
-                    let author_avatar = None;
-                    comment_row_to_response(row, author_name, author_avatar)
+                    let mut response = comment_to_response(row);
+                    response.mentions = mentions
+                        .iter()
+                        .filter(|m| m.comment_id == row.id)
+                        .map(|m| mention_response(m.user_id, &m.display_name, &m.username))
+                        .collect();
+                    response.reactions = reaction_summaries(&reactions, row.id);
+                    response
                 })
                 .collect();
 
@@ -694,13 +758,10 @@ pub async fn create_comment(
 
     // Validate request
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -712,22 +773,9 @@ pub async fn create_comment(
 
     let user_name = extract_user_name(&http_req);
 
-    // Check document access (need at least commenter role)
-    match repo.check_document_access(&document_id, &user_id).await {
-        Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have permission to add comments to this document",
-            ));
-        },
-        Err(e) => {
-            error!("Database error checking document access: {:?}", e);
-            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
-                "DATABASE_ERROR",
-                "Failed to verify document access",
-            ));
-        },
+    // Need at least the commenter role
+    if let Err(response) = check_can_comment(&repo, &document_id, user_id, &http_req).await {
+        return response;
     }
 
     // If parent_id is provided, verify parent comment exists and belongs to the same document
@@ -755,19 +803,35 @@ pub async fn create_comment(
         }
     }
 
+    // Mentions that don't resolve to a space member are left as plain text
+    let mentioned = match resolve_content_mentions(&repo, &document_id, &req.content).await {
+        Ok(users) => users,
+        Err(e) => {
+            error!("Database error resolving mentions: {:?}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to create comment"));
+        },
+    };
+    let mention_ids: Vec<Uuid> = mentioned.iter().map(|u| u.id).collect();
+
     // Create comment
     match repo
         .create_comment(
             &document_id,
-            &user_id,
+            user_id,
             &user_name,
             &req.content,
             req.parent_id.as_deref(),
+            &mention_ids,
         )
         .await
     {
         Ok(comment) => {
-            let response = comment_row_to_response(&comment, &user_name, None);
+            let mut response = comment_to_response(&comment);
+            response.mentions = mentioned
+                .iter()
+                .map(|u| mention_response(u.id, &u.display_name, &u.username))
+                .collect();
             HttpResponse::Created().json(ApiResponse::success(response))
         },
         Err(e) => {
@@ -789,13 +853,10 @@ pub async fn update_comment(
 
     // Validate request
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -806,16 +867,8 @@ pub async fn update_comment(
     };
 
     // Get existing comment
-    match repo.get_comment(&comment_id).await {
-        Ok(Some(comment)) => {
-            // Check if user is the author
-            if comment.author_id.to_string() != user_id {
-                return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                    "ACCESS_DENIED",
-                    "You can only edit your own comments",
-                ));
-            }
-        },
+    let comment = match repo.get_comment(&comment_id).await {
+        Ok(Some(comment)) => comment,
         Ok(None) => {
             return HttpResponse::NotFound().json(ApiResponse::<()>::error("NOT_FOUND", "Comment not found"));
         },
@@ -824,30 +877,24 @@ pub async fn update_comment(
             return HttpResponse::InternalServerError()
                 .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to get comment"));
         },
+    };
+
+    // Check if user is the author
+    if comment.author_id != user_id {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "ACCESS_DENIED",
+            "You can only edit your own comments",
+        ));
+    }
+
+    // Authors who lost the right to comment can no longer edit
+    if let Err(response) = check_can_comment(&repo, &comment.document_id.to_string(), user_id, &http_req).await {
+        return response;
     }
 
     // Update comment
     match repo.update_comment(&comment_id, &req.content).await {
-        Ok(_comment) => {
-            let author_name = "User"; // Placeholder until user lookup is implemented
-            let author_avatar = None;
-            // Re-fetch the comment with full data
-            match repo.get_comment(&comment_id).await {
-                Ok(Some(full_comment)) => {
-                    let response = comment_row_to_response(&full_comment, author_name, author_avatar);
-                    HttpResponse::Ok().json(ApiResponse::success(response))
-                },
-                Ok(None) => HttpResponse::NotFound()
-                    .json(ApiResponse::<()>::error("NOT_FOUND", "Comment not found after update")),
-                Err(e) => {
-                    error!("Database error fetching updated comment: {:?}", e);
-                    HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
-                        "DATABASE_ERROR",
-                        "Failed to fetch updated comment",
-                    ))
-                },
-            }
-        },
+        Ok(comment) => HttpResponse::Ok().json(ApiResponse::success(comment_to_response(&comment))),
         Err(e) => {
             error!("Database error updating comment: {:?}", e);
             HttpResponse::InternalServerError()
@@ -864,7 +911,7 @@ pub async fn resolve_comment(
 ) -> impl Responder {
     let comment_id = comment_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -878,18 +925,13 @@ pub async fn resolve_comment(
     match repo.get_comment(&comment_id).await {
         Ok(Some(comment)) => {
             // Check if user can resolve (author or editor+)
-            if comment.author_id.to_string() == user_id {
+            if comment.author_id == user_id {
                 // Author can resolve their own comment
             } else {
                 // Check document access for editing
-                match repo.check_document_access(&comment.document_id.to_string(), &user_id).await {
+                match repo.check_document_access(&comment.document_id.to_string(), user_id).await {
                     Ok(true) => {},
-                    Ok(false) => {
-                        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                            "ACCESS_DENIED",
-                            "You don't have permission to resolve comments",
-                        ));
-                    },
+                    Ok(false) => return document_access_denied(&http_req),
                     Err(e) => {
                         error!("Database error checking document access: {:?}", e);
                         return HttpResponse::InternalServerError()
@@ -909,12 +951,8 @@ pub async fn resolve_comment(
     }
 
     // Resolve comment
-    match repo.resolve_comment(&comment_id, &user_id).await {
-        Ok(comment) => {
-            let _author_name = "User"; // Placeholder until user lookup is implemented
-            let _author_avatar: Option<&str> = None;
-            HttpResponse::Ok().json(ApiResponse::success(comment))
-        },
+    match repo.resolve_comment(&comment_id, user_id).await {
+        Ok(comment) => HttpResponse::Ok().json(ApiResponse::success(comment)),
         Err(e) => {
             error!("Database error resolving comment: {:?}", e);
             HttpResponse::InternalServerError()
@@ -931,7 +969,7 @@ pub async fn unresolve_comment(
 ) -> impl Responder {
     let comment_id = comment_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -945,14 +983,9 @@ pub async fn unresolve_comment(
     match repo.get_comment(&comment_id).await {
         Ok(Some(comment)) => {
             // Check if user can unresolve (editor+ only)
-            match repo.check_document_access(&comment.document_id.to_string(), &user_id).await {
+            match repo.check_document_access(&comment.document_id.to_string(), user_id).await {
                 Ok(true) => {},
-                Ok(false) => {
-                    return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                        "ACCESS_DENIED",
-                        "You don't have permission to unresolve comments",
-                    ));
-                },
+                Ok(false) => return document_access_denied(&http_req),
                 Err(e) => {
                     error!("Database error checking document access: {:?}", e);
                     return HttpResponse::InternalServerError()
@@ -991,7 +1024,7 @@ pub async fn delete_comment(
 ) -> impl Responder {
     let comment_id = comment_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(ref e) => {
             return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
@@ -1005,18 +1038,13 @@ pub async fn delete_comment(
     match repo.get_comment(&comment_id).await {
         Ok(Some(comment)) => {
             // Check if user can delete (author or editor+)
-            if comment.author_id.to_string() == user_id {
+            if comment.author_id == user_id {
                 // Author can delete their own comment
             } else {
                 // Check document access for editing
-                match repo.check_document_access(&comment.document_id.to_string(), &user_id).await {
+                match repo.check_document_access(&comment.document_id.to_string(), user_id).await {
                     Ok(true) => {},
-                    Ok(false) => {
-                        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                            "ACCESS_DENIED",
-                            "You don't have permission to delete this comment",
-                        ));
-                    },
+                    Ok(false) => return document_access_denied(&http_req),
                     Err(e) => {
                         error!("Database error checking document access: {:?}", e);
                         return HttpResponse::InternalServerError()
@@ -1047,3 +1075,86 @@ pub async fn delete_comment(
         },
     }
 }
+
+/// React to a comment with an emoji from `ALLOWED_REACTIONS`
+pub async fn add_reaction(
+    comment_id: web::Path<String>,
+    req: web::Json<ReactionRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    set_reaction(comment_id.into_inner(), req.into_inner(), true, &repo, &http_req).await
+}
+
+/// Withdraw the caller's reaction to a comment
+pub async fn remove_reaction(
+    path: web::Path<(String, String)>,
+    repo: web::Data<DocumentRepository>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let (comment_id, emoji) = path.into_inner();
+    set_reaction(comment_id, ReactionRequest { emoji }, false, &repo, &http_req).await
+}
+
+// Adding or removing is idempotent; either way the comment's reactions are returned
+async fn set_reaction(
+    comment_id: String,
+    req: ReactionRequest,
+    react: bool,
+    repo: &DocumentRepository,
+    http_req: &HttpRequest,
+) -> HttpResponse {
+    if let Err(validation_errors) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(http_req).await {
+        Ok(id) => id,
+        Err(ref e) => {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
+                ErrorCode::from(e).to_string().as_str(),
+                e.to_string().as_str(),
+            ))
+        },
+    };
+
+    let comment = match repo.get_comment(&comment_id).await {
+        Ok(Some(comment)) => comment,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ApiResponse::<()>::error("NOT_FOUND", "Comment not found"));
+        },
+        Err(e) => {
+            error!("Database error getting comment: {:?}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to get comment"));
+        },
+    };
+
+    // Reacting needs the same role as commenting
+    if let Err(response) = check_can_comment(repo, &comment.document_id.to_string(), user_id, http_req).await {
+        return response;
+    }
+
+    let result = if react {
+        repo.add_reaction(&comment_id, user_id, &req.emoji).await
+    } else {
+        repo.remove_reaction(&comment_id, user_id, &req.emoji).await
+    };
+    if let Err(e) = result {
+        error!("Database error updating reaction: {:?}", e);
+        return HttpResponse::InternalServerError()
+            .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to update reaction"));
+    }
+
+    match repo.list_comment_reactions(&[comment.id], user_id).await {
+        Ok(rows) => HttpResponse::Ok().json(ApiResponse::success(CommentReactionsResponse {
+            comment_id: comment.id.to_string(),
+            reactions: reaction_summaries(&rows, comment.id),
+        })),
+        Err(e) => {
+            error!("Database error listing comment reactions: {:?}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("DATABASE_ERROR", "Failed to list reactions"))
+        },
+    }
+}
@@ -0,0 +1,214 @@
+//! Short-lived cache for pagination totals
+//!
+//! Document listings return a total alongside each page. Counting is the
+//! expensive part on large spaces and the total rarely changes while a user
+//! pages through results, so exact counts are cached per space and filter for
+//! a few seconds. Writes that add or remove documents invalidate every entry
+//! for the affected space; changes made outside this service are picked up
+//! once the TTL expires.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Default lifetime of a cached count in seconds
+pub const DEFAULT_COUNT_CACHE_TTL_SECS: u64 = 30;
+
+/// Planner estimates at or above this many rows are returned instead of an
+/// exact count when the caller opts in to estimates
+pub const COUNT_ESTIMATE_THRESHOLD: i64 = 10_000;
+
+/// Identifies one listing total: the space plus the filter applied to it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CountKey {
+    pub space_id: Uuid,
+    pub filter: String,
+}
+
+impl CountKey {
+    pub fn new(space_id: Uuid, filter: impl Into<String>) -> Self {
+        Self {
+            space_id,
+            filter: filter.into(),
+        }
+    }
+}
+
+/// Total returned with a page of results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageTotal {
+    pub count: i64,
+    /// True when `count` is a planner estimate rather than an exact count
+    pub is_estimate: bool,
+}
+
+impl PageTotal {
+    pub fn exact(count: i64) -> Self {
+        Self {
+            count,
+            is_estimate: false,
+        }
+    }
+
+    pub fn estimate(count: i64) -> Self {
+        Self {
+            count,
+            is_estimate: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CountCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<CountKey, (i64, Instant)>>,
+}
+
+impl Default for CountCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_COUNT_CACHE_TTL_SECS))
+    }
+}
+
+impl CountCache {
+    /// Create a cache; a zero TTL disables caching
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Read the TTL from `DOCUMENT_COUNT_CACHE_TTL_SECS`
+    pub fn from_env() -> Self {
+        let ttl_secs = std::env::var("DOCUMENT_COUNT_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_COUNT_CACHE_TTL_SECS);
+        Self::new(Duration::from_secs(ttl_secs))
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Cached count for `key`, if present and not expired
+    pub fn get(&self, key: &CountKey) -> Option<i64> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(key)
+            .filter(|(_, stored_at)| stored_at.elapsed() < self.ttl)
+            .map(|(count, _)| *count)
+    }
+
+    pub fn insert(&self, key: CountKey, count: i64) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        // Drop expired entries so the map stays bounded by active listings
+        entries.retain(|_, (_, stored_at)| stored_at.elapsed() < self.ttl);
+        entries.insert(key, (count, Instant::now()));
+    }
+
+    /// Forget every cached count for a space
+    pub fn invalidate_space(&self, space_id: &Uuid) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|key, _| key.space_id != *space_id);
+    }
+
+    /// Return the cached count or compute and cache it
+    pub async fn get_or_compute<F, Fut, E>(&self, key: CountKey, compute: F) -> Result<i64, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<i64, E>>,
+    {
+        if let Some(count) = self.get(&key) {
+            return Ok(count);
+        }
+
+        let count = compute().await?;
+        self.insert(key, count);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn count_with(cache: &CountCache, key: CountKey, calls: &AtomicUsize, value: i64) -> i64 {
+        cache
+            .get_or_compute(key, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, ()>(value)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_repeated_pages_reuse_cached_count() {
+        let cache = CountCache::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let key = CountKey::new(Uuid::new_v4(), "root");
+
+        assert_eq!(count_with(&cache, key.clone(), &calls, 42).await, 42);
+        assert_eq!(count_with(&cache, key.clone(), &calls, 99).await, 42);
+        assert_eq!(count_with(&cache, key, &calls, 99).await, 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_space_forces_recount() {
+        let cache = CountCache::new(Duration::from_secs(60));
+        let calls = AtomicUsize::new(0);
+        let space_id = Uuid::new_v4();
+        let key = CountKey::new(space_id, "root");
+
+        assert_eq!(count_with(&cache, key.clone(), &calls, 10).await, 10);
+
+        // A document create in this space invalidates its totals
+        cache.invalidate_space(&space_id);
+
+        assert_eq!(count_with(&cache, key, &calls, 11).await, 11);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_space_keeps_other_spaces() {
+        let cache = CountCache::new(Duration::from_secs(60));
+        let changed = CountKey::new(Uuid::new_v4(), "root");
+        let untouched = CountKey::new(Uuid::new_v4(), "root");
+
+        cache.insert(changed.clone(), 1);
+        cache.insert(untouched.clone(), 2);
+        cache.invalidate_space(&changed.space_id);
+
+        assert_eq!(cache.get(&changed), None);
+        assert_eq!(cache.get(&untouched), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_entries_expire_after_ttl() {
+        let cache = CountCache::new(Duration::from_millis(20));
+        let key = CountKey::new(Uuid::new_v4(), "root");
+
+        cache.insert(key.clone(), 5);
+        assert_eq!(cache.get(&key), Some(5));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(cache.get(&key), None);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_cache() {
+        let cache = CountCache::new(Duration::ZERO);
+        let key = CountKey::new(Uuid::new_v4(), "root");
+
+        cache.insert(key.clone(), 5);
+        assert_eq!(cache.get(&key), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Optional encryption at rest for document content
+//!
+//! Spaces with `content_encrypted = true` have their document and version
+//! `content` encrypted by [`DocumentRepository`](crate::repository::DocumentRepository)
+//! before it is written, and decrypted transparently when it is read. Each
+//! space gets its own AES-256-GCM key supplied by a [`SpaceKeyProvider`]; the
+//! default provider derives it with HKDF-SHA256 from the master key in
+//! `DOCUMENT_ENCRYPTION_KEY` (base64, 32 bytes). A KMS-backed provider can be
+//! plugged in through [`ContentEncryption::new`].
+//!
+//! Ciphertext is stored in the JSONB column as an envelope:
+//! `{"$encrypted": {"v": 1, "space": "<uuid>", "nonce": "<b64>", "data": "<b64>"}}`.
+//! The space id is also bound as associated data, so an envelope copied into
+//! another space fails to decrypt.
+//!
+//! Search: titles stay in plaintext and remain searchable, but content of
+//! encrypted spaces is opaque to PostgreSQL, so full-text and snippet search
+//! over content is effectively disabled for those spaces. Services that read
+//! `documents.content` directly (sync, export via SQL) see the envelope.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Top-level key marking an encrypted content envelope
+pub const ENCRYPTED_CONTENT_KEY: &str = "$encrypted";
+
+const ENVELOPE_VERSION: u64 = 1;
+const NONCE_LEN: usize = 12;
+const KEY_INFO_PREFIX: &[u8] = b"miniwiki-document-content:";
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+    #[error("Failed to encrypt content")]
+    Encrypt,
+    #[error("Failed to decrypt content")]
+    Decrypt,
+    #[error("Malformed encrypted content: {0}")]
+    Malformed(String),
+}
+
+/// Supplies the 256-bit content key for a space
+pub trait SpaceKeyProvider: Send + Sync {
+    fn space_key(&self, space_id: &Uuid) -> Result<[u8; 32], EncryptionError>;
+}
+
+/// Derives per-space keys from a single master key with HKDF-SHA256
+pub struct DerivedKeyProvider {
+    master_key: [u8; 32],
+}
+
+impl DerivedKeyProvider {
+    pub fn new(master_key: [u8; 32]) -> Self {
+        Self { master_key }
+    }
+
+    /// Parse a base64-encoded 32-byte master key
+    pub fn from_base64(encoded: &str) -> Result<Self, EncryptionError> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?;
+        let master_key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| EncryptionError::InvalidKey("master key must be 32 bytes".to_string()))?;
+        Ok(Self::new(master_key))
+    }
+}
+
+impl SpaceKeyProvider for DerivedKeyProvider {
+    fn space_key(&self, space_id: &Uuid) -> Result<[u8; 32], EncryptionError> {
+        let mut info = KEY_INFO_PREFIX.to_vec();
+        info.extend_from_slice(space_id.as_bytes());
+
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, &self.master_key)
+            .expand(&info, &mut key)
+            .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?;
+        Ok(key)
+    }
+}
+
+/// Encrypts and decrypts document content for encrypted spaces
+pub struct ContentEncryption {
+    keys: Arc<dyn SpaceKeyProvider>,
+}
+
+impl ContentEncryption {
+    pub fn new(keys: Arc<dyn SpaceKeyProvider>) -> Self {
+        Self { keys }
+    }
+
+    /// Build from `DOCUMENT_ENCRYPTION_KEY`; `Ok(None)` when it is unset
+    pub fn from_env() -> Result<Option<Self>, EncryptionError> {
+        match std::env::var("DOCUMENT_ENCRYPTION_KEY") {
+            Ok(key) if !key.trim().is_empty() => {
+                let provider = DerivedKeyProvider::from_base64(&key)?;
+                Ok(Some(Self::new(Arc::new(provider))))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Encrypt content for a space, returning the JSON envelope to store
+    pub fn encrypt(&self, space_id: &Uuid, content: &Value) -> Result<Value, EncryptionError> {
+        let cipher = self.cipher(space_id)?;
+        let plaintext = serde_json::to_vec(content).map_err(|_| EncryptionError::Encrypt)?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: space_id.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Encrypt)?;
+
+        Ok(json!({
+            ENCRYPTED_CONTENT_KEY: {
+                "v": ENVELOPE_VERSION,
+                "space": space_id.to_string(),
+                "nonce": BASE64.encode(nonce),
+                "data": BASE64.encode(ciphertext),
+            }
+        }))
+    }
+
+    /// Decrypt an envelope; content that is not encrypted is returned as-is
+    pub fn decrypt(&self, content: Value) -> Result<Value, EncryptionError> {
+        let envelope = match content.get(ENCRYPTED_CONTENT_KEY) {
+            Some(envelope) => envelope,
+            None => return Ok(content),
+        };
+
+        let field = |name: &str| {
+            envelope
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| EncryptionError::Malformed(format!("missing {}", name)))
+        };
+
+        if envelope.get("v").and_then(Value::as_u64) != Some(ENVELOPE_VERSION) {
+            return Err(EncryptionError::Malformed("unsupported version".to_string()));
+        }
+        let space_id = Uuid::parse_str(field("space")?).map_err(|e| EncryptionError::Malformed(e.to_string()))?;
+        let nonce = BASE64
+            .decode(field("nonce")?)
+            .map_err(|e| EncryptionError::Malformed(e.to_string()))?;
+        let data = BASE64
+            .decode(field("data")?)
+            .map_err(|e| EncryptionError::Malformed(e.to_string()))?;
+        if nonce.len() != NONCE_LEN {
+            return Err(EncryptionError::Malformed("invalid nonce length".to_string()));
+        }
+
+        let plaintext = self
+            .cipher(&space_id)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &data,
+                    aad: space_id.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::Decrypt)?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| EncryptionError::Malformed(e.to_string()))
+    }
+
+    fn cipher(&self, space_id: &Uuid) -> Result<Aes256Gcm, EncryptionError> {
+        let key = self.keys.space_key(space_id)?;
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+}
+
+/// Whether stored content is an encrypted envelope
+pub fn is_encrypted(content: &Value) -> bool {
+    content.get(ENCRYPTED_CONTENT_KEY).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encryption() -> ContentEncryption {
+        ContentEncryption::new(Arc::new(DerivedKeyProvider::new([7u8; 32])))
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let enc = encryption();
+        let space_id = Uuid::new_v4();
+        let content = json!({"type": "doc", "text": "top secret plans"});
+
+        let sealed = enc.encrypt(&space_id, &content).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.to_string().contains("top secret plans"));
+
+        assert_eq!(enc.decrypt(sealed).unwrap(), content);
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_decrypt() {
+        let content = json!({"text": "hello"});
+        assert!(!is_encrypted(&content));
+        assert_eq!(encryption().decrypt(content.clone()).unwrap(), content);
+    }
+
+    #[test]
+    fn test_nonce_is_random() {
+        let enc = encryption();
+        let space_id = Uuid::new_v4();
+        let content = json!({"text": "same"});
+
+        assert_ne!(
+            enc.encrypt(&space_id, &content).unwrap(),
+            enc.encrypt(&space_id, &content).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_envelope_moved_to_other_space_fails() {
+        let enc = encryption();
+        let mut sealed = enc.encrypt(&Uuid::new_v4(), &json!({"text": "x"})).unwrap();
+        sealed[ENCRYPTED_CONTENT_KEY]["space"] = json!(Uuid::new_v4().to_string());
+
+        assert!(matches!(enc.decrypt(sealed), Err(EncryptionError::Decrypt)));
+    }
+
+    #[test]
+    fn test_wrong_master_key_fails() {
+        let space_id = Uuid::new_v4();
+        let sealed = encryption().encrypt(&space_id, &json!({"text": "x"})).unwrap();
+        let other = ContentEncryption::new(Arc::new(DerivedKeyProvider::new([8u8; 32])));
+
+        assert!(matches!(other.decrypt(sealed), Err(EncryptionError::Decrypt)));
+    }
+
+    #[test]
+    fn test_master_key_from_base64() {
+        assert!(DerivedKeyProvider::from_base64(&BASE64.encode([1u8; 32])).is_ok());
+        assert!(matches!(
+            DerivedKeyProvider::from_base64(&BASE64.encode([1u8; 16])),
+            Err(EncryptionError::InvalidKey(_))
+        ));
+        assert!(DerivedKeyProvider::from_base64("not base64!").is_err());
+    }
+}
@@ -5,6 +5,11 @@
 //! - HTML with embedded styles
 //! - PDF (via weasyprint - requires Python runtime)
 //! - JSON (raw Yjs state)
+//! - DOCX (Word), streamed into the output file
+//!
+//! With an image store configured, images referenced by file id are embedded
+//! in HTML, PDF and DOCX exports; see `images`. A whole space can be exported
+//! as a zip of documents laid out like the page tree; see `space`.
 //!
 //! # Implementation Notes
 //!
@@ -17,8 +22,16 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as FmtWrite;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+mod docx;
+pub mod images;
+pub mod space;
+
+pub use images::{resolve_images, ImageStore, StorageImageStore};
+pub use space::{SpaceDocumentSource, SpaceExportEntry, SpaceExportResponse};
 
 /// Export format enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,6 +41,7 @@ pub enum ExportFormat {
     Html,
     Pdf,
     Json,
+    Docx,
 }
 
 impl ExportFormat {
@@ -38,6 +52,7 @@ impl ExportFormat {
             "html" | "htm" => Some(ExportFormat::Html),
             "pdf" => Some(ExportFormat::Pdf),
             "json" => Some(ExportFormat::Json),
+            "docx" | "doc" => Some(ExportFormat::Docx),
             _ => None,
         }
     }
@@ -49,6 +64,7 @@ impl ExportFormat {
             ExportFormat::Html => "html",
             ExportFormat::Pdf => "pdf",
             ExportFormat::Json => "json",
+            ExportFormat::Docx => "docx",
         }
     }
 
@@ -59,6 +75,7 @@ impl ExportFormat {
             ExportFormat::Html => "text/html",
             ExportFormat::Pdf => "application/pdf",
             ExportFormat::Json => "application/json",
+            ExportFormat::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
         }
     }
 }
@@ -114,11 +131,14 @@ pub struct DocumentMetadata {
 }
 
 /// Main export service struct
+#[derive(Clone)]
 pub struct ExportService {
     /// Base output directory for exports
     output_dir: PathBuf,
     /// Weasyprint path (if available)
     weasyprint_path: Option<PathBuf>,
+    /// Source of embedded images; without one images become placeholders
+    image_store: Option<Arc<dyn ImageStore>>,
 }
 
 impl ExportService {
@@ -154,9 +174,16 @@ impl ExportService {
         Self {
             output_dir,
             weasyprint_path,
+            image_store: None,
         }
     }
 
+    /// Embed referenced images, fetched from `store`, in HTML, PDF and DOCX exports
+    pub fn with_image_store(mut self, store: Arc<dyn ImageStore>) -> Self {
+        self.image_store = Some(store);
+        self
+    }
+
     /// Get the output directory path
     pub fn output_dir(&self) -> &PathBuf {
         &self.output_dir
@@ -183,26 +210,7 @@ impl ExportService {
         );
 
         let file_path = self.output_dir.join(&file_name);
-
-        // Generate content based on format and write to file
-        match format {
-            ExportFormat::Markdown => {
-                let content_str = self.export_markdown(title, content, metadata.as_ref())?;
-                fs::write(&file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
-            },
-            ExportFormat::Html => {
-                let content_str = self.export_html(title, content, metadata.as_ref())?;
-                fs::write(&file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
-            },
-            ExportFormat::Pdf => {
-                let content_bytes = self.export_pdf(title, content, metadata.as_ref())?;
-                fs::write(&file_path, content_bytes).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
-            },
-            ExportFormat::Json => {
-                let content_str = self.export_json(content)?;
-                fs::write(&file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
-            },
-        }
+        self.write_export(&file_path, title, content, metadata.as_ref(), format).await?;
 
         // Get file size
         let file_size = fs::metadata(&file_path)
@@ -219,6 +227,70 @@ impl ExportService {
         })
     }
 
+    /// Write one document in `format` to `file_path`
+    ///
+    /// Images are fetched here; rendering and writing the file run on the
+    /// blocking pool, as PDF and DOCX output can take a while.
+    async fn write_export(
+        &self,
+        file_path: &Path,
+        title: &str,
+        content: &serde_json::Value,
+        metadata: Option<&DocumentMetadata>,
+        format: ExportFormat,
+    ) -> Result<(), ExportError> {
+        // Formats that can carry images get them embedded
+        let content = match (&self.image_store, format) {
+            (Some(store), ExportFormat::Html | ExportFormat::Pdf | ExportFormat::Docx) => {
+                resolve_images(content, store.as_ref()).await
+            },
+            _ => content.clone(),
+        };
+
+        let service = self.clone();
+        let file_path = file_path.to_path_buf();
+        let title = title.to_string();
+        let metadata = metadata.cloned();
+        blocking(move || service.render_export(&file_path, &title, &content, metadata.as_ref(), format)).await
+    }
+
+    /// Render one document in `format` and write it to `file_path`
+    fn render_export(
+        &self,
+        file_path: &Path,
+        title: &str,
+        content: &serde_json::Value,
+        metadata: Option<&DocumentMetadata>,
+        format: ExportFormat,
+    ) -> Result<(), ExportError> {
+        match format {
+            ExportFormat::Markdown => {
+                let content_str = self.export_markdown(title, content, metadata)?;
+                fs::write(file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+            },
+            ExportFormat::Html => {
+                let content_str = self.export_html(title, content, metadata)?;
+                fs::write(file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+            },
+            ExportFormat::Pdf => {
+                let content_bytes = self.export_pdf(title, content, metadata)?;
+                fs::write(file_path, content_bytes).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+            },
+            ExportFormat::Json => {
+                let content_str = self.export_json(content)?;
+                fs::write(file_path, content_str).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+            },
+            ExportFormat::Docx => {
+                if let Err(e) = docx::write_docx(file_path, title, content, metadata) {
+                    let _ = fs::remove_file(file_path);
+                    return Err(e);
+                }
+            },
+        }
+
+        Ok(())
+    }
+
     /// Export as Markdown with frontmatter
     fn export_markdown(
         &self,
@@ -310,6 +382,9 @@ impl ExportService {
         th { background: var(--code-background); }
         .metadata { color: #6b7280; font-size: 0.875rem; margin-block-end: 1rem; }
         .metadata span { margin-inline-end: 1rem; }
+        figure { margin-block: 1rem; margin-inline: 0; }
+        figure img { max-inline-size: 100%; }
+        .image-missing { color: #6b7280; font-style: italic; }
     </style>
 </head>
 <body>
@@ -639,6 +714,25 @@ fn process_html_item(item: &serde_json::Value, html: &mut String, in_paragraph:
                 }
             }
         },
+        "image" => {
+            if *in_paragraph {
+                html.push_str("</p>\n");
+                *in_paragraph = false;
+            }
+            let alt = item.get("alt").and_then(|v| v.as_str()).unwrap_or("");
+            match item.get("data_uri").and_then(|v| v.as_str()) {
+                Some(data_uri) => {
+                    html.push_str(&format!(
+                        "  <figure><img src=\"{}\" alt=\"{}\"></figure>\n",
+                        escape_html(data_uri),
+                        escape_html(alt)
+                    ));
+                },
+                None => {
+                    html.push_str(&format!("  <p class=\"image-missing\">{}</p>\n", escape_html(&image_placeholder(alt))));
+                },
+            }
+        },
         _ => {
             // Default: try to extract text
             if let Some(text) = item.get("text").or(item.get("content")) {
@@ -656,6 +750,34 @@ fn process_html_item(item: &serde_json::Value, html: &mut String, in_paragraph:
     }
 }
 
+/// Run blocking export work (rendering, file and zip writes) off the async
+/// worker thread
+async fn blocking<T, F>(work: F) -> Result<T, ExportError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ExportError> + Send + 'static,
+{
+    actix_web::web::block(work)
+        .await
+        .map_err(|e| ExportError::ExportFailed(format!("Export task failed: {}", e)))?
+}
+
+/// Text shown in place of an image that could not be embedded
+fn image_placeholder(alt: &str) -> String {
+    if alt.is_empty() {
+        "[Image unavailable]".to_string()
+    } else {
+        format!("[Image unavailable: {}]", alt)
+    }
+}
+
+/// Plain text of a document's content, with blocks separated by blank lines
+pub fn extract_plain_text(content: &serde_json::Value) -> String {
+    let mut text = String::new();
+    extract_text_recursive(content, &mut text, 0);
+    text
+}
+
 /// Extract text recursively from JSON
 fn extract_text_recursive(value: &serde_json::Value, output: &mut String, _depth: usize) {
     match value {
@@ -742,6 +864,8 @@ mod tests {
         assert_eq!(ExportFormat::from_str("htm"), Some(ExportFormat::Html));
         assert_eq!(ExportFormat::from_str("pdf"), Some(ExportFormat::Pdf));
         assert_eq!(ExportFormat::from_str("json"), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_str("docx"), Some(ExportFormat::Docx));
+        assert_eq!(ExportFormat::from_str("DOC"), Some(ExportFormat::Docx));
         assert_eq!(ExportFormat::from_str("unknown"), None);
     }
 
@@ -751,6 +875,7 @@ mod tests {
         assert_eq!(ExportFormat::Html.extension(), "html");
         assert_eq!(ExportFormat::Pdf.extension(), "pdf");
         assert_eq!(ExportFormat::Json.extension(), "json");
+        assert_eq!(ExportFormat::Docx.extension(), "docx");
     }
 
     #[test]
@@ -780,6 +905,43 @@ mod tests {
         assert!(result.contains("Hello World"));
     }
 
+    #[tokio::test]
+    async fn test_export_docx_is_a_zip_container() {
+        let output_dir = std::env::temp_dir().join(format!("miniwiki_docx_test_{}", uuid::Uuid::new_v4()));
+        let service = ExportService::new(output_dir.clone());
+        let content = serde_json::json!({
+            "type": "Y.Doc",
+            "items": [
+                {"type": "heading", "text": "Overview"},
+                {"type": "text", "text": "Hello Word"},
+                {"type": "bullet_list", "items": [{"text": "One"}, {"text": "Two"}]}
+            ]
+        });
+
+        let result = service
+            .export_document("3f2b8c1e-0000-0000-0000-000000000000", "Small Doc", &content, None, ExportFormat::Docx)
+            .await
+            .unwrap();
+
+        assert!(result.file_name.ends_with(".docx"));
+        assert_eq!(result.content_type, ExportFormat::Docx.mime_type());
+        let bytes = fs::read(output_dir.join(&result.file_name)).unwrap();
+        assert!(bytes.starts_with(b"PK\x03\x04"));
+        assert_eq!(result.file_size, bytes.len() as u64);
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        for part in ["[Content_Types].xml", "_rels/.rels", "word/styles.xml", "word/numbering.xml"] {
+            assert!(archive.by_name(part).is_ok(), "missing {}", part);
+        }
+        let mut document = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("word/document.xml").unwrap(), &mut document).unwrap();
+        assert!(document.contains("Small Doc"));
+        assert!(document.contains("Overview"));
+        assert!(document.contains("Hello Word"));
+
+        let _ = fs::remove_dir_all(&output_dir);
+    }
+
     #[test]
     fn test_escape_html() {
         assert_eq!(escape_html("Hello & World <test>"), "Hello &amp; World &lt;test&gt;");
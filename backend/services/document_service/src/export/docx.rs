@@ -0,0 +1,528 @@
+//! DOCX (Office Open XML) export
+//!
+//! A `.docx` is a zip of XML parts. The body part is written straight into
+//! the zip entry as the content is walked, so a large document never exists
+//! as a whole in memory; only hyperlink targets and the number of ordered
+//! lists are collected, because their parts must be written afterwards.
+//!
+//! Content items are mapped the same way as the HTML export: headings,
+//! bullet and ordered lists, code blocks and blockquotes become paragraphs,
+//! while text, bold, italic, inline code and links become runs in the current
+//! paragraph. Resolved images are written as media parts after the body and
+//! shown inline, scaled down to the page width.
+
+use super::images::decode_data_uri;
+use super::{image_placeholder, DocumentMetadata, ExportError};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Default Extension="gif" ContentType="image/gif"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>"#;
+
+const PACKAGE_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>"#;
+
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:pPr><w:spacing w:after="160"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="240"/></w:pPr><w:rPr><w:b/><w:sz w:val="48"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="60"/></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:left="720"/></w:pPr><w:rPr><w:i/><w:color w:val="6B7280"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F3F4F6"/></w:pPr><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New"/><w:sz w:val="20"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="2563EB"/><w:u w:val="single"/></w:rPr></w:style>
+</w:styles>"#;
+
+/// Numbering definition id used by every bullet list
+const BULLET_NUM_ID: usize = 1;
+
+/// EMUs per pixel at 96 DPI
+const EMU_PER_PIXEL: u64 = 9525;
+
+/// Width between the page margins, in EMUs
+const MAX_IMAGE_WIDTH_EMU: u64 = 5_731_510;
+
+/// Media part extension for the image types Word displays
+fn media_extension(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpeg"),
+        "image/gif" => Some("gif"),
+        _ => None,
+    }
+}
+
+/// Write `content` as a `.docx` at `path`
+pub(super) fn write_docx(
+    path: &Path,
+    title: &str,
+    content: &serde_json::Value,
+    metadata: Option<&DocumentMetadata>,
+) -> Result<(), ExportError> {
+    let file = File::create(path).map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let written = (|| -> Result<(), DocxError> {
+        zip.start_file("[Content_Types].xml", options)?;
+        zip.write_all(CONTENT_TYPES_XML.as_bytes())?;
+        zip.start_file("_rels/.rels", options)?;
+        zip.write_all(PACKAGE_RELS_XML.as_bytes())?;
+        zip.start_file("docProps/core.xml", options)?;
+        zip.write_all(core_properties(title, metadata).as_bytes())?;
+        zip.start_file("word/styles.xml", options)?;
+        zip.write_all(STYLES_XML.as_bytes())?;
+
+        zip.start_file("word/document.xml", options)?;
+        let mut body = DocxBody::new(&mut zip);
+        body.write_document(title, content)?;
+        let (links, ordered_lists, media) = (body.links, body.ordered_lists, body.media);
+
+        // Each image is decoded only while its part is written
+        for (i, data_uri) in media.iter().enumerate() {
+            if let Some((content_type, bytes)) = decode_data_uri(data_uri) {
+                let extension = media_extension(content_type).unwrap_or("png");
+                zip.start_file(format!("word/media/image{}.{}", i + 1, extension), options)?;
+                zip.write_all(&bytes)?;
+            }
+        }
+
+        zip.start_file("word/_rels/document.xml.rels", options)?;
+        write_document_rels(&mut zip, &links, &media)?;
+        zip.start_file("word/numbering.xml", options)?;
+        write_numbering(&mut zip, ordered_lists)?;
+
+        zip.finish()?.flush()?;
+        Ok(())
+    })();
+
+    written.map_err(|e| ExportError::ConversionFailed(format!("DOCX generation failed: {}", e)))
+}
+
+#[derive(Debug, thiserror::Error)]
+enum DocxError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// Inline formatting of a run
+#[derive(Debug, Clone, Copy, Default)]
+struct RunStyle {
+    bold: bool,
+    italic: bool,
+    code: bool,
+}
+
+/// Streams `word/document.xml` into `out`
+struct DocxBody<'a, W: Write> {
+    out: W,
+    in_paragraph: bool,
+    /// Hyperlink targets, in relationship id order
+    links: Vec<String>,
+    /// Ordered lists seen so far; each restarts its numbering
+    ordered_lists: usize,
+    /// Data URIs of embedded images, in media part order
+    media: Vec<&'a str>,
+}
+
+impl<'a, W: Write> DocxBody<'a, W> {
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            in_paragraph: false,
+            links: Vec::new(),
+            ordered_lists: 0,
+            media: Vec::new(),
+        }
+    }
+
+    fn write_document(&mut self, title: &str, content: &'a serde_json::Value) -> std::io::Result<()> {
+        self.out.write_all(
+            br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><w:body>"#,
+        )?;
+        self.styled_paragraph("Title", title)?;
+
+        let doc_type = content.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        if doc_type == "Y.Doc" || doc_type == "y-doc" {
+            if let Some(items) = content.get("items").or(content.get("content")).and_then(|v| v.as_array()) {
+                for item in items {
+                    self.item(item)?;
+                }
+            }
+        } else if content.is_object() {
+            self.fallback(content)?;
+        }
+        self.close_paragraph()?;
+
+        self.out.write_all(
+            br#"<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr></w:body></w:document>"#,
+        )
+    }
+
+    fn item(&mut self, item: &'a serde_json::Value) -> std::io::Result<()> {
+        let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("text");
+        let text = item_text(item);
+
+        match item_type {
+            "heading" | "heading1" => self.styled_paragraph("Heading1", text),
+            "heading2" => self.styled_paragraph("Heading2", text),
+            "heading3" => self.styled_paragraph("Heading3", text),
+            "bullet_list" | "list" => self.list(item, BULLET_NUM_ID),
+            "ordered_list" => {
+                self.ordered_lists += 1;
+                self.list(item, BULLET_NUM_ID + self.ordered_lists)
+            },
+            "code_block" => self.code_block(text),
+            "blockquote" => self.styled_paragraph("Quote", text),
+            "bold" | "strong" => self.inline(text, RunStyle { bold: true, ..Default::default() }),
+            "italic" | "em" => self.inline(text, RunStyle { italic: true, ..Default::default() }),
+            "inline_code" => self.inline(text, RunStyle { code: true, ..Default::default() }),
+            "image" => self.image(item),
+            "link" => {
+                let href = item.get("href").or(item.get("url")).and_then(|v| v.as_str()).unwrap_or("");
+                self.link(text, href)
+            },
+            _ => self.inline(text, RunStyle::default()),
+        }
+    }
+
+    /// Plain text paragraphs for content that isn't a Yjs item list
+    fn fallback(&mut self, value: &'a serde_json::Value) -> std::io::Result<()> {
+        match value {
+            serde_json::Value::String(s) => self.inline(s, RunStyle::default()),
+            serde_json::Value::Array(arr) => {
+                for item in arr {
+                    self.item(item)?;
+                }
+                Ok(())
+            },
+            serde_json::Value::Object(obj) => match obj.get("content").or(obj.get("text")) {
+                Some(content) => self.fallback(content),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn open_paragraph(&mut self) -> std::io::Result<()> {
+        if !self.in_paragraph {
+            self.out.write_all(b"<w:p>")?;
+            self.in_paragraph = true;
+        }
+        Ok(())
+    }
+
+    fn close_paragraph(&mut self) -> std::io::Result<()> {
+        if self.in_paragraph {
+            self.out.write_all(b"</w:p>")?;
+            self.in_paragraph = false;
+        }
+        Ok(())
+    }
+
+    fn styled_paragraph(&mut self, style: &str, text: &str) -> std::io::Result<()> {
+        self.close_paragraph()?;
+        write!(self.out, r#"<w:p><w:pPr><w:pStyle w:val="{}"/></w:pPr>"#, style)?;
+        self.run(text, RunStyle::default())?;
+        self.out.write_all(b"</w:p>")
+    }
+
+    fn inline(&mut self, text: &str, style: RunStyle) -> std::io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.open_paragraph()?;
+        self.run(text, style)
+    }
+
+    fn link(&mut self, text: &str, href: &str) -> std::io::Result<()> {
+        let text = if text.is_empty() { href } else { text };
+        if href.is_empty() {
+            return self.inline(text, RunStyle::default());
+        }
+
+        self.links.push(href.to_string());
+        self.open_paragraph()?;
+        write!(
+            self.out,
+            r#"<w:hyperlink r:id="rIdLink{}"><w:r><w:rPr><w:rStyle w:val="Hyperlink"/></w:rPr>"#,
+            self.links.len()
+        )?;
+        self.text(text)?;
+        self.out.write_all(b"</w:r></w:hyperlink>")
+    }
+
+    /// An embedded image in its own paragraph, or a placeholder
+    fn image(&mut self, item: &'a serde_json::Value) -> std::io::Result<()> {
+        let alt = item.get("alt").and_then(|v| v.as_str()).unwrap_or("");
+        let data_uri = item
+            .get("data_uri")
+            .and_then(|v| v.as_str())
+            .filter(|uri| decode_content_type(uri).and_then(media_extension).is_some());
+        let Some(data_uri) = data_uri else {
+            return self.styled_paragraph("Quote", &image_placeholder(alt));
+        };
+
+        let width = item.get("width").and_then(|v| v.as_u64()).filter(|w| *w > 0).unwrap_or(400);
+        let height = item.get("height").and_then(|v| v.as_u64()).filter(|h| *h > 0).unwrap_or(300);
+        let (mut cx, mut cy) = (width * EMU_PER_PIXEL, height * EMU_PER_PIXEL);
+        if cx > MAX_IMAGE_WIDTH_EMU {
+            cy = cy * MAX_IMAGE_WIDTH_EMU / cx;
+            cx = MAX_IMAGE_WIDTH_EMU;
+        }
+
+        self.media.push(data_uri);
+        let n = self.media.len();
+        self.close_paragraph()?;
+        write!(
+            self.out,
+            r#"<w:p><w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{n}" name="Image {n}" descr="{alt}"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{n}" name="Image {n}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="rIdImage{n}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#,
+            cx = cx,
+            cy = cy,
+            n = n,
+            alt = escape_xml(alt)
+        )
+    }
+
+    fn list(&mut self, item: &serde_json::Value, num_id: usize) -> std::io::Result<()> {
+        self.close_paragraph()?;
+        for list_item in item.get("items").and_then(|v| v.as_array()).into_iter().flatten() {
+            write!(
+                self.out,
+                r#"<w:p><w:pPr><w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="0"/><w:numId w:val="{}"/></w:numPr></w:pPr>"#,
+                num_id
+            )?;
+            self.run(item_text(list_item), RunStyle::default())?;
+            self.out.write_all(b"</w:p>")?;
+        }
+        Ok(())
+    }
+
+    fn code_block(&mut self, text: &str) -> std::io::Result<()> {
+        self.close_paragraph()?;
+        self.out.write_all(br#"<w:p><w:pPr><w:pStyle w:val="Code"/></w:pPr><w:r>"#)?;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.out.write_all(b"<w:br/>")?;
+            }
+            self.text(line)?;
+        }
+        self.out.write_all(b"</w:r></w:p>")
+    }
+
+    fn run(&mut self, text: &str, style: RunStyle) -> std::io::Result<()> {
+        self.out.write_all(b"<w:r>")?;
+        if style.bold || style.italic || style.code {
+            self.out.write_all(b"<w:rPr>")?;
+            if style.code {
+                self.out.write_all(br#"<w:rFonts w:ascii="Courier New" w:hAnsi="Courier New"/>"#)?;
+            }
+            if style.bold {
+                self.out.write_all(b"<w:b/>")?;
+            }
+            if style.italic {
+                self.out.write_all(b"<w:i/>")?;
+            }
+            self.out.write_all(b"</w:rPr>")?;
+        }
+        self.text(text)?;
+        self.out.write_all(b"</w:r>")
+    }
+
+    fn text(&mut self, text: &str) -> std::io::Result<()> {
+        write!(self.out, r#"<w:t xml:space="preserve">{}</w:t>"#, escape_xml(text))
+    }
+}
+
+/// Content type named by a data URI, without decoding it
+fn decode_content_type(data_uri: &str) -> Option<&str> {
+    data_uri.strip_prefix("data:")?.split(';').next()
+}
+
+fn item_text(item: &serde_json::Value) -> &str {
+    item.get("text").or(item.get("content")).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn write_document_rels(out: &mut impl Write, links: &[String], media: &[&str]) -> std::io::Result<()> {
+    out.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+<Relationship Id="rIdNumbering" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>
+"#,
+    )?;
+    for (i, href) in links.iter().enumerate() {
+        write!(
+            out,
+            r#"<Relationship Id="rIdLink{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="{}" TargetMode="External"/>"#,
+            i + 1,
+            escape_xml(href)
+        )?;
+    }
+    for (i, data_uri) in media.iter().enumerate() {
+        let extension = decode_content_type(data_uri).and_then(media_extension).unwrap_or("png");
+        write!(
+            out,
+            r#"<Relationship Id="rIdImage{n}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image{n}.{ext}"/>"#,
+            n = i + 1,
+            ext = extension
+        )?;
+    }
+    out.write_all(b"</Relationships>")
+}
+
+/// Bullet numbering, plus one decimal numbering per ordered list so each
+/// list starts again at 1
+fn write_numbering(out: &mut impl Write, ordered_lists: usize) -> std::io::Result<()> {
+    out.write_all(
+        br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="bullet"/><w:lvlText w:val="&#8226;"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:lvl></w:abstractNum>
+<w:abstractNum w:abstractNumId="1"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:lvl></w:abstractNum>
+"#,
+    )?;
+    write!(out, r#"<w:num w:numId="{}"><w:abstractNumId w:val="0"/></w:num>"#, BULLET_NUM_ID)?;
+    for i in 1..=ordered_lists {
+        write!(
+            out,
+            r#"<w:num w:numId="{}"><w:abstractNumId w:val="1"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="1"/></w:lvlOverride></w:num>"#,
+            BULLET_NUM_ID + i
+        )?;
+    }
+    out.write_all(b"</w:numbering>")
+}
+
+fn core_properties(title: &str, metadata: Option<&DocumentMetadata>) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">"#,
+    );
+    xml.push_str(&format!("<dc:title>{}</dc:title>", escape_xml(title)));
+    if let Some(meta) = metadata {
+        if let Some(created_by) = &meta.created_by {
+            xml.push_str(&format!("<dc:creator>{}</dc:creator>", escape_xml(created_by)));
+        }
+        if let Some(created_at) = meta.created_at {
+            xml.push_str(&format!(
+                r#"<dcterms:created xsi:type="dcterms:W3CDTF">{}</dcterms:created>"#,
+                created_at.format("%Y-%m-%dT%H:%M:%SZ")
+            ));
+        }
+        if let Some(updated_at) = meta.updated_at {
+            xml.push_str(&format!(
+                r#"<dcterms:modified xsi:type="dcterms:W3CDTF">{}</dcterms:modified>"#,
+                updated_at.format("%Y-%m-%dT%H:%M:%SZ")
+            ));
+        }
+    }
+    xml.push_str("</cp:coreProperties>");
+    xml
+}
+
+/// Escape text for XML, dropping control characters XML 1.0 cannot hold
+fn escape_xml(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            '\t' | '\n' | '\r' => result.push(c),
+            c if c.is_control() => {},
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_xml(content: serde_json::Value) -> (String, Vec<String>) {
+        let mut out = Vec::new();
+        let mut body = DocxBody::new(&mut out);
+        body.write_document("Title", &content).unwrap();
+        let links = body.links;
+        (String::from_utf8(out).unwrap(), links)
+    }
+
+    #[test]
+    fn test_items_map_to_paragraph_styles_and_runs() {
+        let (xml, links) = body_xml(serde_json::json!({
+            "type": "Y.Doc",
+            "items": [
+                {"type": "heading2", "text": "Section"},
+                {"type": "text", "text": "Plain "},
+                {"type": "bold", "text": "strong"},
+                {"type": "italic", "text": "slanted"},
+                {"type": "link", "text": "docs", "href": "https://example.com/?a=1&b=2"},
+                {"type": "bullet_list", "items": [{"text": "one"}, {"text": "two"}]},
+                {"type": "ordered_list", "items": [{"text": "first"}]}
+            ]
+        }));
+
+        assert!(xml.contains(r#"<w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t xml:space="preserve">Section</w:t>"#));
+        assert!(xml.contains(r#"<w:rPr><w:b/></w:rPr><w:t xml:space="preserve">strong</w:t>"#));
+        assert!(xml.contains(r#"<w:rPr><w:i/></w:rPr><w:t xml:space="preserve">slanted</w:t>"#));
+        assert!(xml.contains(r#"<w:hyperlink r:id="rIdLink1">"#));
+        assert_eq!(links, vec!["https://example.com/?a=1&b=2".to_string()]);
+        assert_eq!(xml.matches(r#"<w:numId w:val="1"/>"#).count(), 2);
+        assert_eq!(xml.matches(r#"<w:numId w:val="2"/>"#).count(), 1);
+    }
+
+    #[test]
+    fn test_images_are_embedded_or_replaced_by_placeholders() {
+        let content = serde_json::json!({
+            "type": "Y.Doc",
+            "items": [
+                {"type": "image", "alt": "Wide", "data_uri": "data:image/png;base64,AAAA", "width": 2000, "height": 1000},
+                {"type": "image", "alt": "Gone", "missing": true}
+            ]
+        });
+        let mut out = Vec::new();
+        let mut body = DocxBody::new(&mut out);
+        body.write_document("Title", &content).unwrap();
+        let media = body.media;
+        let xml = String::from_utf8(out).unwrap();
+
+        assert_eq!(media, vec!["data:image/png;base64,AAAA"]);
+        assert!(xml.contains(r#"<a:blip r:embed="rIdImage1"/>"#));
+        // Scaled to the page width, keeping the aspect ratio
+        assert!(xml.contains(&format!(r#"<wp:extent cx="{}" cy="{}"/>"#, MAX_IMAGE_WIDTH_EMU, MAX_IMAGE_WIDTH_EMU / 2)));
+        assert!(xml.contains("[Image unavailable: Gone]"));
+    }
+
+    #[test]
+    fn test_text_is_escaped() {
+        let (xml, _) = body_xml(serde_json::json!({
+            "type": "Y.Doc",
+            "items": [{"type": "text", "text": "a < b & \"c\"\u{1}"}]
+        }));
+
+        assert!(xml.contains("a &lt; b &amp; &quot;c&quot;</w:t>"));
+    }
+}
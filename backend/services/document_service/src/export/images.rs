@@ -0,0 +1,270 @@
+//! Embedding images in exports
+//!
+//! Image items in document content reference an uploaded file by id:
+//! `{"type": "image", "file_id": "...", "alt": "..."}`. Before an HTML, PDF
+//! or DOCX export, `resolve_images` fetches each referenced image and
+//! records it on the item as a base64 data URI (`data_uri`) with its pixel
+//! size, so the exported file works offline. Images that cannot be fetched
+//! are marked `missing` and rendered as a placeholder instead of failing
+//! the export.
+//!
+//! Only files uploaded to the exported document's space are embedded, and
+//! at most `MAX_EMBEDDED_IMAGES` images / `MAX_EMBEDDED_TOTAL_BYTES` bytes
+//! per document; anything beyond that is a placeholder too.
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use file_service::storage::S3Storage;
+use sqlx::PgPool;
+use std::io::Cursor;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Largest image embedded in an export; bigger ones become placeholders
+pub const MAX_EMBEDDED_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Most images embedded in one document's export
+pub const MAX_EMBEDDED_IMAGES: usize = 100;
+
+/// Most image bytes embedded in one document's export
+pub const MAX_EMBEDDED_TOTAL_BYTES: usize = 50 * 1024 * 1024;
+
+/// Bytes of a stored image
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Where exports fetch referenced images from
+#[async_trait]
+pub trait ImageStore: Send + Sync {
+    /// The image with this file id, or `None` when it can't be fetched
+    async fn fetch_image(&self, file_id: Uuid) -> Option<ImageData>;
+}
+
+/// Images uploaded through the file service to one space
+pub struct StorageImageStore {
+    pool: PgPool,
+    storage: Arc<S3Storage>,
+    space_id: Uuid,
+}
+
+impl StorageImageStore {
+    /// Store serving only live files of `space_id`, the space being exported
+    pub fn new(pool: PgPool, storage: Arc<S3Storage>, space_id: Uuid) -> Self {
+        Self { pool, storage, space_id }
+    }
+}
+
+#[async_trait]
+impl ImageStore for StorageImageStore {
+    async fn fetch_image(&self, file_id: Uuid) -> Option<ImageData> {
+        // A file id from another space is treated as missing, not fetched
+        let file = sqlx::query_as::<_, (String, String)>(
+            "SELECT file_type, storage_path FROM files WHERE id = $1 AND space_id = $2 AND is_deleted = false",
+        )
+        .bind(file_id)
+        .bind(self.space_id)
+        .fetch_optional(&self.pool)
+        .await;
+        let (file_type, storage_path) = match file {
+            Ok(Some(file)) => file,
+            Ok(None) => return None,
+            Err(e) => {
+                tracing::warn!("Failed to look up image {} for export: {}", file_id, e);
+                return None;
+            },
+        };
+        if !file_type.starts_with("image/") {
+            return None;
+        }
+
+        match self.storage.download_file(&storage_path).await {
+            Ok(bytes) => Some(ImageData {
+                bytes,
+                content_type: file_type,
+            }),
+            Err(e) => {
+                tracing::warn!("Failed to download image {} for export: {}", file_id, e);
+                None
+            },
+        }
+    }
+}
+
+/// Copy of `content` with every image item resolved against `store`
+pub async fn resolve_images(content: &serde_json::Value, store: &dyn ImageStore) -> serde_json::Value {
+    let mut resolved = content.clone();
+    let mut budget = ImageBudget::default();
+    let mut pending = vec![&mut resolved];
+
+    while let Some(value) = pending.pop() {
+        match value {
+            serde_json::Value::Array(items) => pending.extend(items.iter_mut().rev()),
+            serde_json::Value::Object(obj) => {
+                if obj.get("type").and_then(|v| v.as_str()) == Some("image") {
+                    resolve_image(obj, store, &mut budget).await;
+                    continue;
+                }
+                pending.extend(obj.values_mut());
+            },
+            _ => {},
+        }
+    }
+
+    resolved
+}
+
+// Images and bytes embedded so far in one document
+#[derive(Default)]
+struct ImageBudget {
+    images: usize,
+    bytes: usize,
+}
+
+async fn resolve_image(
+    item: &mut serde_json::Map<String, serde_json::Value>,
+    store: &dyn ImageStore,
+    budget: &mut ImageBudget,
+) {
+    let file_id = item
+        .get("file_id")
+        .and_then(|v| v.as_str())
+        .and_then(|id| Uuid::parse_str(id).ok());
+    let image = match file_id {
+        Some(file_id) if budget.images < MAX_EMBEDDED_IMAGES => store.fetch_image(file_id).await,
+        _ => None,
+    };
+
+    let embedded = image
+        .filter(|image| {
+            image.bytes.len() <= MAX_EMBEDDED_IMAGE_BYTES
+                && budget.bytes + image.bytes.len() <= MAX_EMBEDDED_TOTAL_BYTES
+        })
+        .and_then(|image| {
+            let (width, height) = image::ImageReader::new(Cursor::new(&image.bytes))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()?;
+            Some((image, width, height))
+        });
+
+    match embedded {
+        Some((image, width, height)) => {
+            budget.images += 1;
+            budget.bytes += image.bytes.len();
+            let data_uri = format!("data:{};base64,{}", image.content_type, BASE64.encode(&image.bytes));
+            item.insert("data_uri".to_string(), data_uri.into());
+            item.insert("width".to_string(), width.into());
+            item.insert("height".to_string(), height.into());
+        },
+        None => {
+            item.insert("missing".to_string(), true.into());
+        },
+    }
+}
+
+/// Content type and bytes of a resolved image item's data URI
+pub(super) fn decode_data_uri(data_uri: &str) -> Option<(&str, Vec<u8>)> {
+    let (content_type, data) = data_uri.strip_prefix("data:")?.split_once(";base64,")?;
+    Some((content_type, BASE64.decode(data).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Serves fixed images from memory
+    struct StubImageStore(HashMap<Uuid, ImageData>);
+
+    #[async_trait]
+    impl ImageStore for StubImageStore {
+        async fn fetch_image(&self, file_id: Uuid) -> Option<ImageData> {
+            self.0.get(&file_id).cloned()
+        }
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::DynamicImage::new_rgb8(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_resolve_images_embeds_found_images_and_marks_missing_ones() {
+        let stored = Uuid::new_v4();
+        let missing = Uuid::new_v4();
+        let bytes = png(3, 2);
+        let store = StubImageStore(HashMap::from([(
+            stored,
+            ImageData {
+                bytes: bytes.clone(),
+                content_type: "image/png".to_string(),
+            },
+        )]));
+        let content = serde_json::json!({
+            "type": "Y.Doc",
+            "items": [
+                {"type": "text", "text": "Before"},
+                {"type": "image", "file_id": stored.to_string(), "alt": "Diagram"},
+                {"type": "image", "file_id": missing.to_string(), "alt": "Gone"},
+                {"type": "image", "file_id": "not-a-uuid"}
+            ]
+        });
+
+        let resolved = resolve_images(&content, &store).await;
+        let items = resolved["items"].as_array().unwrap();
+
+        let data_uri = items[1]["data_uri"].as_str().unwrap();
+        assert!(data_uri.starts_with("data:image/png;base64,"));
+        assert_eq!(decode_data_uri(data_uri), Some(("image/png", bytes)));
+        assert_eq!((items[1]["width"].as_u64(), items[1]["height"].as_u64()), (Some(3), Some(2)));
+        assert_eq!(items[2]["missing"], true);
+        assert_eq!(items[3]["missing"], true);
+        assert_eq!(items[0], content["items"][0]);
+    }
+
+    #[tokio::test]
+    async fn test_images_beyond_the_count_cap_become_placeholders() {
+        let file_id = Uuid::new_v4();
+        let store = StubImageStore(HashMap::from([(
+            file_id,
+            ImageData {
+                bytes: png(1, 1),
+                content_type: "image/png".to_string(),
+            },
+        )]));
+        let item = serde_json::json!({"type": "image", "file_id": file_id.to_string()});
+        let content = serde_json::Value::Array(vec![item; MAX_EMBEDDED_IMAGES + 1]);
+
+        let resolved = resolve_images(&content, &store).await;
+        let items = resolved.as_array().unwrap();
+
+        assert!(items[..MAX_EMBEDDED_IMAGES].iter().all(|item| item.get("data_uri").is_some()));
+        assert_eq!(items[MAX_EMBEDDED_IMAGES]["missing"], true);
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_image_becomes_placeholder() {
+        let file_id = Uuid::new_v4();
+        let store = StubImageStore(HashMap::from([(
+            file_id,
+            ImageData {
+                bytes: b"not an image".to_vec(),
+                content_type: "image/png".to_string(),
+            },
+        )]));
+        let content = serde_json::json!([{"type": "image", "file_id": file_id.to_string()}]);
+
+        let resolved = resolve_images(&content, &store).await;
+
+        assert_eq!(resolved[0]["missing"], true);
+        assert!(resolved[0].get("data_uri").is_none());
+    }
+}
@@ -0,0 +1,393 @@
+//! Exporting a whole space as a zip archive
+//!
+//! Every non-archived document in the space becomes one file, placed in
+//! folders named after its ancestors: a page "Setup" under "Guides" is
+//! written as `Guides/Setup.md`, next to `Guides.md` for the parent itself.
+//!
+//! Only the outline (ids, parents and titles) is held for the whole space.
+//! Each document is loaded, exported to a scratch file and copied into the
+//! archive before the next one is read, and the archive is written to disk,
+//! so memory use doesn't grow with the size of the space.
+
+use super::{blocking, DocumentMetadata, ExportError, ExportFormat, ExportService};
+use crate::repository::DocumentRepository;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Longest file or folder name taken from a title, in characters
+const MAX_NAME_CHARS: usize = 100;
+
+/// A document in the space outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceExportEntry {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub title: String,
+}
+
+/// The written archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpaceExportResponse {
+    pub space_id: String,
+    pub format: ExportFormat,
+    pub file_name: String,
+    pub file_size: u64,
+    pub document_count: usize,
+    pub exported_at: NaiveDateTime,
+}
+
+/// Where a space export reads its documents from
+#[async_trait]
+pub trait SpaceDocumentSource: Send + Sync {
+    /// Non-archived documents of the space, parents before their children
+    /// where possible
+    async fn outline(&self, space_id: &str) -> Result<Vec<SpaceExportEntry>, ExportError>;
+
+    /// Content and metadata of one document, or `None` if it has gone
+    async fn document(&self, id: Uuid) -> Result<Option<(serde_json::Value, DocumentMetadata)>, ExportError>;
+}
+
+#[async_trait]
+impl SpaceDocumentSource for DocumentRepository {
+    async fn outline(&self, space_id: &str) -> Result<Vec<SpaceExportEntry>, ExportError> {
+        let rows = self
+            .list_space_outline(space_id)
+            .await
+            .map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, parent_id, title)| SpaceExportEntry { id, parent_id, title })
+            .collect())
+    }
+
+    async fn document(&self, id: Uuid) -> Result<Option<(serde_json::Value, DocumentMetadata)>, ExportError> {
+        let document = self
+            .get_by_id(&id.to_string())
+            .await
+            .map_err(|e| ExportError::ExportFailed(e.to_string()))?;
+
+        Ok(document.filter(|d| !d.is_archived).map(|d| {
+            let metadata = DocumentMetadata {
+                id: d.id.to_string(),
+                title: d.title.clone(),
+                created_at: Some(d.created_at),
+                updated_at: Some(d.updated_at),
+                created_by: Some(d.created_by.to_string()),
+                icon: d.icon.clone(),
+            };
+            (d.content.0, metadata)
+        }))
+    }
+}
+
+/// A title made safe to use as a file or folder name
+fn file_name_for(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_NAME_CHARS)
+        .collect();
+    // Leading dots would hide the file or, as "..", escape the folder
+    let name = name.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    if name.is_empty() {
+        "Untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Archive path of every entry, in outline order
+///
+/// Siblings with the same name get a " (2)", " (3)"... suffix. A document
+/// whose parent isn't in the outline (archived, or in another space) is
+/// placed at the top level.
+fn archive_paths(entries: &[SpaceExportEntry], extension: &str) -> Vec<String> {
+    let index: HashMap<Uuid, usize> = entries.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
+    let parent_of = |entry: &SpaceExportEntry| entry.parent_id.and_then(|id| index.get(&id).copied());
+
+    // Unique name of each entry among its siblings
+    let mut taken: HashMap<Option<usize>, HashSet<String>> = HashMap::new();
+    let names: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            let siblings = taken.entry(parent_of(entry)).or_default();
+            let base = file_name_for(&entry.title);
+            let mut name = base.clone();
+            let mut n = 1;
+            while !siblings.insert(name.to_lowercase()) {
+                n += 1;
+                name = format!("{} ({})", base, n);
+            }
+            name
+        })
+        .collect();
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mut segments = vec![format!("{}.{}", names[i], extension)];
+            let mut parent = parent_of(entry);
+            // A corrupt parent chain can loop; no real chain is longer than the outline
+            while let Some(p) = parent.filter(|_| segments.len() <= entries.len()) {
+                segments.push(names[p].clone());
+                parent = parent_of(&entries[p]);
+            }
+            segments.reverse();
+            segments.join("/")
+        })
+        .collect()
+}
+
+impl ExportService {
+    /// Export every non-archived document of a space into one zip archive
+    ///
+    /// The archive is written to the output directory; the caller is expected
+    /// to check the user's access to the space first.
+    pub async fn export_space(
+        &self,
+        space_id: &str,
+        source: &dyn SpaceDocumentSource,
+        format: ExportFormat,
+    ) -> Result<SpaceExportResponse, ExportError> {
+        let outline = source.outline(space_id).await?;
+        let paths = archive_paths(&outline, format.extension());
+
+        let file_name = format!(
+            "space_{}_{}.zip",
+            space_id.split('-').next().unwrap_or(space_id),
+            Uuid::new_v4().simple()
+        );
+        let file_path = self.output_dir.join(&file_name);
+        let scratch_path = self.output_dir.join(format!("{}.part", file_name));
+
+        let written = self
+            .write_space_archive(&file_path, &scratch_path, &outline, &paths, source, format)
+            .await;
+        let _ = fs::remove_file(&scratch_path);
+        let document_count = match written {
+            Ok(count) => count,
+            Err(e) => {
+                let _ = fs::remove_file(&file_path);
+                return Err(e);
+            },
+        };
+
+        let file_size = fs::metadata(&file_path)
+            .map_err(|e| ExportError::ExportFailed(e.to_string()))?
+            .len();
+
+        Ok(SpaceExportResponse {
+            space_id: space_id.to_string(),
+            format,
+            file_name,
+            file_size,
+            document_count,
+            exported_at: chrono::Local::now().naive_local(),
+        })
+    }
+
+    /// Write the archive, returning how many documents went into it
+    async fn write_space_archive(
+        &self,
+        file_path: &Path,
+        scratch_path: &Path,
+        outline: &[SpaceExportEntry],
+        paths: &[String],
+        source: &dyn SpaceDocumentSource,
+        format: ExportFormat,
+    ) -> Result<usize, ExportError> {
+        fn failed(e: impl std::fmt::Display) -> ExportError {
+            ExportError::ExportFailed(format!("Space archive failed: {}", e))
+        }
+
+        let file_path = file_path.to_path_buf();
+        let file = blocking(move || File::create(file_path).map_err(failed)).await?;
+        let mut zip = ZipWriter::new(BufWriter::new(file));
+        // PDF and DOCX are compressed already
+        let compression = match format {
+            ExportFormat::Pdf | ExportFormat::Docx => CompressionMethod::Stored,
+            _ => CompressionMethod::Deflated,
+        };
+        let options = SimpleFileOptions::default().compression_method(compression);
+
+        let mut count = 0;
+        for (entry, path) in outline.iter().zip(paths) {
+            let Some((content, metadata)) = source.document(entry.id).await? else {
+                continue;
+            };
+            self.write_export(scratch_path, &entry.title, &content, Some(&metadata), format).await?;
+
+            // The writer moves onto the blocking pool for the copy and comes back
+            let path = path.clone();
+            let scratch_path = scratch_path.to_path_buf();
+            zip = blocking(move || {
+                zip.start_file(path, options).map_err(failed)?;
+                let mut exported = File::open(scratch_path).map_err(failed)?;
+                std::io::copy(&mut exported, &mut zip).map_err(failed)?;
+                Ok(zip)
+            })
+            .await?;
+            count += 1;
+        }
+
+        blocking(move || zip.finish().map_err(failed)?.flush().map_err(failed)).await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::images::{ImageData, ImageStore};
+    use std::io::Read;
+
+    /// Serves a fixed outline from memory
+    struct StubSpace(Vec<(SpaceExportEntry, serde_json::Value)>);
+
+    #[async_trait]
+    impl SpaceDocumentSource for StubSpace {
+        async fn outline(&self, _space_id: &str) -> Result<Vec<SpaceExportEntry>, ExportError> {
+            Ok(self.0.iter().map(|(entry, _)| entry.clone()).collect())
+        }
+
+        async fn document(&self, id: Uuid) -> Result<Option<(serde_json::Value, DocumentMetadata)>, ExportError> {
+            Ok(self.0.iter().find(|(entry, _)| entry.id == id).map(|(entry, content)| {
+                let metadata = DocumentMetadata {
+                    id: entry.id.to_string(),
+                    title: entry.title.clone(),
+                    created_at: None,
+                    updated_at: None,
+                    created_by: None,
+                    icon: None,
+                };
+                (content.clone(), metadata)
+            }))
+        }
+    }
+
+    fn entry(title: &str, parent_id: Option<Uuid>) -> SpaceExportEntry {
+        SpaceExportEntry {
+            id: Uuid::new_v4(),
+            parent_id,
+            title: title.to_string(),
+        }
+    }
+
+    fn text(text: &str) -> serde_json::Value {
+        serde_json::json!({"type": "Y.Doc", "items": [{"type": "text", "text": text}]})
+    }
+
+    #[tokio::test]
+    async fn test_export_space_zips_documents_by_hierarchy() {
+        let output_dir = std::env::temp_dir().join(format!("miniwiki_space_test_{}", Uuid::new_v4()));
+        let service = ExportService::new(output_dir.clone());
+        let guides = entry("Guides", None);
+        let setup = entry("Setup", Some(guides.id));
+        let space = StubSpace(vec![(guides, text("All guides")), (setup, text("Install it"))]);
+
+        let result = service
+            .export_space("7d1e0c2a-0000-0000-0000-000000000000", &space, ExportFormat::Markdown)
+            .await
+            .unwrap();
+
+        assert_eq!(result.document_count, 2);
+        let archive_path = output_dir.join(&result.file_name);
+        let mut archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["Guides.md", "Guides/Setup.md"]);
+
+        let mut setup_md = String::new();
+        archive.by_name("Guides/Setup.md").unwrap().read_to_string(&mut setup_md).unwrap();
+        assert!(setup_md.contains("# Setup"));
+        assert!(setup_md.contains("Install it"));
+        // Only the archive is left behind
+        assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&output_dir);
+    }
+
+    /// Serves one 1x1 PNG for any file id
+    struct OnePixelStore;
+
+    #[async_trait]
+    impl ImageStore for OnePixelStore {
+        async fn fetch_image(&self, _file_id: Uuid) -> Option<ImageData> {
+            let mut bytes = Vec::new();
+            image::DynamicImage::new_rgb8(1, 1)
+                .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+                .ok()?;
+            Some(ImageData {
+                bytes,
+                content_type: "image/png".to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_space_embeds_images() {
+        let output_dir = std::env::temp_dir().join(format!("miniwiki_space_test_{}", Uuid::new_v4()));
+        let service = ExportService::new(output_dir.clone()).with_image_store(std::sync::Arc::new(OnePixelStore));
+        let diagram = serde_json::json!({
+            "type": "Y.Doc",
+            "items": [{"type": "image", "file_id": Uuid::new_v4().to_string(), "alt": "Diagram"}]
+        });
+        let space = StubSpace(vec![(entry("Architecture", None), diagram)]);
+
+        let result = service
+            .export_space("7d1e0c2a-0000-0000-0000-000000000000", &space, ExportFormat::Html)
+            .await
+            .unwrap();
+
+        let archive_path = output_dir.join(&result.file_name);
+        let mut archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+        let mut html = String::new();
+        archive.by_name("Architecture.html").unwrap().read_to_string(&mut html).unwrap();
+        assert!(html.contains("data:image/png;base64,"));
+        assert!(!html.contains("[Image unavailable"));
+
+        let _ = fs::remove_dir_all(&output_dir);
+    }
+
+    #[test]
+    fn test_archive_paths_deduplicate_siblings_and_sanitize_titles() {
+        let docs = entry("Docs", None);
+        let first = entry("FAQ", Some(docs.id));
+        let second = entry("faq", Some(docs.id));
+        let nested = entry("a/b: c?", Some(second.id));
+        let orphan = entry("..", Some(Uuid::new_v4()));
+        let entries = vec![docs, first, second, nested, orphan];
+
+        assert_eq!(
+            archive_paths(&entries, "md"),
+            vec!["Docs.md", "Docs/FAQ.md", "Docs/faq (2).md", "Docs/faq (2)/a_b_ c_.md", "Untitled.md"]
+        );
+    }
+
+    #[test]
+    fn test_archive_paths_survive_a_parent_cycle() {
+        let mut a = entry("A", None);
+        let b = entry("B", Some(a.id));
+        a.parent_id = Some(b.id);
+
+        let paths = archive_paths(&[a, b], "md");
+
+        assert!(paths[0].ends_with("A.md"));
+        assert!(paths[1].ends_with("B.md"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Favorite Document Handlers
+//!
+//! Provides HTTP handlers for a user's personal list of favorite documents:
+//! - PUT /documents/{documentId}/favorite - Add a favorite
+//! - DELETE /documents/{documentId}/favorite - Remove a favorite
+//! - GET /me/favorites - List the caller's favorites
+//!
+//! Favorites are private to each user. Adding one requires access to the
+//! document; the list leaves out documents that were archived or whose space
+//! the user has since left.
+use actix_web::{web, HttpResponse, Responder};
+use shared_models::pagination::next_offset;
+use tracing::error;
+
+use crate::handlers::{document_access_denied, extract_user_id, unauthorized_response};
+use crate::models::*;
+use crate::repository::DocumentRepository;
+
+/// Add a document to the caller's favorites
+pub async fn add_favorite(
+    document_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.check_document_access(&document_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => return document_access_denied(&http_req),
+        Err(e) => {
+            error!("Database error checking document access: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    // Favoriting twice is not an error
+    match repo.add_favorite(&document_id, user_id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            error!("Database error adding favorite: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+/// Remove a document from the caller's favorites
+///
+/// No access check: users can always clean up their own list, even for
+/// documents they can no longer open.
+pub async fn remove_favorite(
+    document_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.remove_favorite(&document_id, user_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "FAVORITE_NOT_FOUND",
+            "Document is not in your favorites",
+        )),
+        Err(e) => {
+            error!("Database error removing favorite: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+/// List the caller's favorite documents, most recently favorited first
+pub async fn list_favorites(
+    query: web::Query<ListFavoritesQuery>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match repo.list_favorites(user_id, limit, offset).await {
+        Ok((rows, total)) => {
+            let documents: Vec<DocumentResponse> = rows
+                .iter()
+                .map(|row| DocumentResponse {
+                    is_favorite: Some(true),
+                    ..crate::handlers::document_row_to_response(row)
+                })
+                .collect();
+            let next_offset = next_offset(offset.into(), documents.len(), total);
+            HttpResponse::Ok().json(ApiResponse::<DocumentListResponse>::success(DocumentListResponse {
+                documents,
+                total,
+                total_is_estimate: false,
+                limit,
+                offset,
+                has_more: next_offset.is_some(),
+                next_offset,
+            }))
+        },
+        Err(e) => {
+            error!("Database error listing favorites: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
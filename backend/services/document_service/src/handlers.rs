@@ -1,15 +1,26 @@
-use crate::export::{ExportFormat, ExportService};
+use crate::export::{ExportFormat, ExportService, StorageImageStore};
 use crate::models::*;
-use crate::repository::DocumentRepository;
+use crate::repository::{
+    AddMemberError, BulkMemberOutcome, DocumentRepository, PatchContentError, TransferOwnershipError,
+    UpdateDocumentError, OWNER_ROLE,
+};
+use actix_web::http::header::{ETag, EntityTag, Header, IfNoneMatch};
 use actix_web::{web, HttpResponse, Responder};
-use jsonwebtoken;
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::has_permission;
+use sha2::{Digest, Sha256};
 use shared_errors::AppError;
+use shared_models::pagination::next_offset;
 use tracing::error;
 use validator::Validate;
 
 // Helper for access check with proper error handling
 // Returns Ok(true) if access granted, Ok(false) if denied, Err for DB errors
-async fn check_document_access(repo: &DocumentRepository, document_id: &str, user_id: &str) -> Result<bool, AppError> {
+async fn check_document_access(
+    repo: &DocumentRepository,
+    document_id: &str,
+    user_id: uuid::Uuid,
+) -> Result<bool, AppError> {
     match repo.check_document_access(document_id, user_id).await {
         Ok(true) => Ok(true),
         Ok(false) => Ok(false),
@@ -20,9 +31,54 @@ async fn check_document_access(repo: &DocumentRepository, document_id: &str, use
     }
 }
 
+/// How document handlers answer a caller without access to a document
+///
+/// `403 ACCESS_DENIED` tells the caller the document id exists, so anyone can
+/// probe for valid ids. With `hide_forbidden_as_not_found` the handlers gated
+/// on document access answer `404 DOC_NOT_FOUND` instead, the same as for a
+/// missing document. The tradeoff is that a user who lost access gets no hint
+/// why a link stopped working, and support can't tell the two apart from the
+/// response either. Checks where the caller already has a role in the
+/// document's space (a viewer trying to edit, a member editing someone
+/// else's comment) keep `403`: they can see the document, so nothing leaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccessDenialPolicy {
+    pub hide_forbidden_as_not_found: bool,
+}
+
+impl AccessDenialPolicy {
+    /// Read `HIDE_FORBIDDEN_AS_NOT_FOUND`, off unless set to a true value
+    pub fn from_env() -> Self {
+        let hide_forbidden_as_not_found = std::env::var("HIDE_FORBIDDEN_AS_NOT_FOUND")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+            .unwrap_or(false);
+
+        Self {
+            hide_forbidden_as_not_found,
+        }
+    }
+}
+
+/// Response for a caller without access to a document, following the
+/// `AccessDenialPolicy` registered as app data (403 when there is none)
+pub(crate) fn document_access_denied(http_req: &actix_web::HttpRequest) -> HttpResponse {
+    let hide = http_req
+        .app_data::<web::Data<AccessDenialPolicy>>()
+        .is_some_and(|policy| policy.hide_forbidden_as_not_found);
+
+    if hide {
+        HttpResponse::NotFound().json(ApiResponse::<()>::error("DOC_NOT_FOUND", "Document not found"))
+    } else {
+        HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "ACCESS_DENIED",
+            "You don't have access to this document",
+        ))
+    }
+}
+
 // Helper for space access check with proper error handling
 // Returns Ok(true) if access granted, Ok(false) if denied, Err for DB errors
-async fn check_space_access(repo: &DocumentRepository, space_id: &str, user_id: &str) -> Result<bool, AppError> {
+async fn check_space_access(repo: &DocumentRepository, space_id: &str, user_id: uuid::Uuid) -> Result<bool, AppError> {
     match repo.check_space_access(space_id, user_id).await {
         Ok(true) => Ok(true),
         Ok(false) => Ok(false),
@@ -34,7 +90,7 @@ async fn check_space_access(repo: &DocumentRepository, space_id: &str, user_id:
 }
 
 // Helper to convert DocumentRow to DocumentResponse
-fn document_row_to_response(row: &crate::repository::DocumentRow) -> DocumentResponse {
+pub(crate) fn document_row_to_response(row: &crate::repository::DocumentRow) -> DocumentResponse {
     DocumentResponse {
         id: row.id.to_string(),
         space_id: row.space_id.to_string(),
@@ -44,11 +100,88 @@ fn document_row_to_response(row: &crate::repository::DocumentRow) -> DocumentRes
         content: row.content.0.clone(),
         content_size: row.content_size,
         is_archived: row.is_archived,
+        is_pinned: row.is_pinned,
+        pin_order: row.pin_order,
         created_by: row.created_by.to_string(),
         last_edited_by: row.last_edited_by.to_string(),
         created_at: row.created_at.and_utc().to_rfc3339(),
         updated_at: row.updated_at.and_utc().to_rfc3339(),
+        version: row.version,
+        unread: false,
+        comment_count: None,
+        unresolved_count: None,
+        is_favorite: None,
+    }
+}
+
+// Helper to convert listed documents to responses flagged unread for the caller
+async fn with_unread_flags(
+    repo: &DocumentRepository,
+    user_id: uuid::Uuid,
+    documents: &[crate::repository::DocumentRow],
+) -> Result<Vec<DocumentResponse>, HttpResponse> {
+    let ids: Vec<uuid::Uuid> = documents.iter().map(|d| d.id).collect();
+    let unread = repo.unread_document_ids(user_id, &ids).await.map_err(|e| {
+        error!("Database error checking unread documents: {:?}", e);
+        HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+            "DATABASE_ERROR",
+            "A database error occurred. Please try again later.",
+        ))
+    })?;
+
+    Ok(documents
+        .iter()
+        .map(|row| DocumentResponse {
+            unread: unread.contains(&row.id),
+            ..document_row_to_response(row)
+        })
+        .collect())
+}
+
+// Helper to fill in comment counts on listed documents, for `?include=comments`
+async fn with_comment_counts(
+    repo: &DocumentRepository,
+    space_id: &str,
+    rows: &[crate::repository::DocumentRow],
+    documents: &mut [DocumentResponse],
+) -> Result<(), HttpResponse> {
+    let ids: Vec<uuid::Uuid> = rows.iter().map(|d| d.id).collect();
+    let counts = repo.comment_counts(space_id, &ids).await.map_err(|e| {
+        error!("Database error counting comments: {:?}", e);
+        HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+            "DATABASE_ERROR",
+            "A database error occurred. Please try again later.",
+        ))
+    })?;
+
+    for (row, document) in rows.iter().zip(documents.iter_mut()) {
+        let (total, unresolved) = counts.get(&row.id).copied().unwrap_or((0, 0));
+        document.comment_count = Some(total);
+        document.unresolved_count = Some(unresolved);
     }
+    Ok(())
+}
+
+// Helper to fill in the caller's favorite flag, for `?include=favorite`
+async fn with_favorite_flags(
+    repo: &DocumentRepository,
+    user_id: uuid::Uuid,
+    documents: &mut [DocumentResponse],
+) -> Result<(), HttpResponse> {
+    let ids: Vec<uuid::Uuid> = documents.iter().filter_map(|d| uuid::Uuid::parse_str(&d.id).ok()).collect();
+    let favorites = repo.favorite_document_ids(user_id, &ids).await.map_err(|e| {
+        error!("Database error loading favorites: {:?}", e);
+        HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+            "DATABASE_ERROR",
+            "A database error occurred. Please try again later.",
+        ))
+    })?;
+
+    for document in documents.iter_mut() {
+        let is_favorite = uuid::Uuid::parse_str(&document.id).is_ok_and(|id| favorites.contains(&id));
+        document.is_favorite = Some(is_favorite);
+    }
+    Ok(())
 }
 
 // Helper to convert DocumentVersionRow to VersionResponse
@@ -65,76 +198,95 @@ fn version_row_to_response(row: &crate::repository::DocumentVersionRow) -> Versi
     }
 }
 
-// User extraction - supports both JWT Authorization header and X-User-Id header for backward compatibility
-fn extract_user_id(req: &actix_web::HttpRequest) -> Result<String, AppError> {
-    // Get JWT secret from environment variable, with fallback to default for test/debug mode only
-    let jwt_secret = match std::env::var("JWT_SECRET") {
-        Ok(secret) => secret,
-        Err(_) => {
-            // Allow fallback to test secret in debug/test mode for consistency with routes/mod.rs
-            #[cfg(any(debug_assertions, test))]
-            {
-                eprintln!("WARNING: Using default JWT secret. Set JWT_SECRET environment variable in production!");
-                "test-secret-key-for-testing-only-do-not-use-in-production".to_string()
-            }
-            #[cfg(not(any(debug_assertions, test)))]
-            {
-                return Err(AppError::AuthenticationError("JWT_SECRET not configured".to_string()));
-            }
-        },
+// Weak ETag for a document's current state: every edit bumps `updated_at`
+// and content edits also bump `version`. The caller's favorite flag, when
+// requested, is part of the tag so toggling it is never answered with a 304.
+// Weak because the body also carries the per-caller unread flag, which the
+// tag doesn't cover.
+fn document_etag(row: &crate::repository::DocumentRow, is_favorite: Option<bool>) -> EntityTag {
+    let favorite = match is_favorite {
+        Some(true) => "-f1",
+        Some(false) => "-f0",
+        None => "",
     };
+    EntityTag::new_weak(format!("{}-{}{}", row.version, row.updated_at.and_utc().timestamp_micros(), favorite))
+}
 
-    // First try JWT Authorization header (preferred method)
-    if let Some(auth_header) = req.headers().get("authorization") {
-        if let Ok(token_str) = auth_header.to_str() {
-            if let Some(token) = token_str.strip_prefix("Bearer ") {
-                let decoding_key = jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes());
-                // Explicitly enforce HS256 algorithm for security
-                let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
-
-                match jsonwebtoken::decode::<serde_json::Value>(token, &decoding_key, &validation) {
-                    Ok(token_data) => {
-                        // Try to extract "sub" claim with validation
-                        if let Some(sub) = token_data.claims.get("sub") {
-                            if let Some(user_id_str) =
-                                sub.as_str()
-                                    .and_then(|s| if !s.is_empty() { Some(s.to_string()) } else { None })
-                            {
-                                return Ok(user_id_str);
-                            }
-                        }
-
-                        // Try to extract "user_id" claim with validation
-                        if let Some(user_id) = token_data.claims.get("user_id") {
-                            if let Some(user_id_str) =
-                                user_id
-                                    .as_str()
-                                    .and_then(|s| if !s.is_empty() { Some(s.to_string()) } else { None })
-                            {
-                                return Ok(user_id_str);
-                            }
-                        }
-
-                        // JWT decoded but no valid user ID found
-                        return Err(AppError::AuthenticationError(
-                            "JWT token missing or contains empty user ID claim".to_string(),
-                        ));
-                    },
-                    Err(e) => {
-                        // JWT decode failed, return error instead of falling back
-                        return Err(AppError::AuthenticationError(format!("Invalid JWT token: {}", e)));
-                    },
-                }
-            }
+// Strong ETag for a version response, hashed from the body itself since an
+// auto-save version can still be rewritten while it is the latest one
+fn version_etag(response: &VersionResponse) -> EntityTag {
+    let body = serde_json::to_vec(response).unwrap_or_default();
+    EntityTag::new_strong(format!("{:x}", Sha256::digest(&body)))
+}
+
+// Whether the request's If-None-Match already names `etag`
+fn is_not_modified(http_req: &actix_web::HttpRequest, etag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(http_req) {
+        Ok(IfNoneMatch::Any) => true,
+        // If-None-Match always uses weak comparison
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        Err(_) => false,
+    }
+}
+
+// Helper to order batch version metadata to match the request
+// Returns the found versions in request order plus the requested numbers that don't exist
+fn order_version_meta(
+    requested: &[i32],
+    rows: Vec<crate::repository::VersionMetaRow>,
+) -> (Vec<VersionMetaResponse>, Vec<i32>) {
+    let mut by_number: std::collections::HashMap<i32, crate::repository::VersionMetaRow> =
+        rows.into_iter().map(|row| (row.version_number, row)).collect();
+
+    let mut versions = Vec::with_capacity(requested.len());
+    let mut missing = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+
+    for number in requested {
+        if !seen.insert(*number) {
+            continue;
+        }
+        match by_number.remove(number) {
+            Some(row) => versions.push(VersionMetaResponse {
+                version_number: row.version_number,
+                title: row.title,
+                created_by: row.created_by.to_string(),
+                created_at: row.created_at.and_utc().to_rfc3339(),
+                change_summary: row.change_summary,
+                pinned: row.is_pinned,
+            }),
+            None => missing.push(*number),
         }
     }
 
-    // Fall back to X-User-Id header for backward compatibility
-    req.headers()
-        .get("X-User-Id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string())
-        .ok_or_else(|| AppError::AuthenticationError("Missing or invalid authentication".to_string()))
+    (versions, missing)
+}
+
+pub use auth_service::identity::{
+    is_identity_mismatch, IdentityMismatchPolicy, IDENTITY_MISMATCH_CODE, IDENTITY_MISMATCH_MESSAGE,
+};
+
+/// 401 response for a failed `extract_user_id`
+pub fn unauthorized_response(err: &AppError) -> HttpResponse {
+    let code = if is_identity_mismatch(err) {
+        IDENTITY_MISMATCH_CODE
+    } else {
+        "UNAUTHORIZED"
+    };
+    HttpResponse::Unauthorized().json(ApiResponse::<()>::error(code, &err.to_string()))
+}
+
+/// User making the request, from API key middleware, a Bearer token or the
+/// `X-User-Id` header (see `auth_service::identity`)
+pub async fn extract_user_id(req: &actix_web::HttpRequest) -> Result<uuid::Uuid, AppError> {
+    extract_user_id_with_policy(req, IdentityMismatchPolicy::from_env()).await
+}
+
+async fn extract_user_id_with_policy(
+    req: &actix_web::HttpRequest,
+    policy: IdentityMismatchPolicy,
+) -> Result<uuid::Uuid, AppError> {
+    auth_service::identity::extract_request_user_id_with_policy(req, policy).await
 }
 
 // Create document
@@ -148,20 +300,17 @@ pub async fn create_document(
 
     // Validate request
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
     // Get user ID from header (in production, this comes from JWT)
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check space access
-    match check_space_access(&repo, &space_id, &user_id).await {
+    match check_space_access(&repo, &space_id, user_id).await {
         Ok(true) => {},
         Ok(false) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
@@ -177,9 +326,9 @@ pub async fn create_document(
         },
     }
 
-    // Check if user has permission to create documents (owner or editor)
-    match repo.get_user_space_role(&space_id, &user_id).await {
-        Ok(Some(role)) if role == "owner" || role == "editor" => {},
+    // Check if user has permission to create documents
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::CreateDocuments) => {},
         Ok(Some(_)) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
                 "PERMISSION_DENIED",
@@ -208,7 +357,7 @@ pub async fn create_document(
             &req.title,
             req.icon.as_deref(),
             req.content.clone(),
-            &user_id,
+            user_id,
         )
         .await
     {
@@ -232,25 +381,21 @@ pub async fn create_document(
 // Get document by ID
 pub async fn get_document(
     document_id: web::Path<String>,
+    query: web::Query<GetDocumentQuery>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -260,9 +405,35 @@ pub async fn get_document(
     }
 
     match repo.get_by_id(&document_id).await {
-        Ok(Some(document)) => HttpResponse::Ok().json(ApiResponse::<DocumentResponse>::success(
-            document_row_to_response(&document),
-        )),
+        Ok(Some(document)) => {
+            let mut response = document_row_to_response(&document);
+            if query.includes_favorite() {
+                if let Err(response) = with_favorite_flags(&repo, user_id, std::slice::from_mut(&mut response)).await {
+                    return response;
+                }
+            }
+
+            let etag = document_etag(&document, response.is_favorite);
+            let not_modified = is_not_modified(&http_req, &etag);
+
+            // Report whether it was unread before this view, then mark it read
+            match repo.record_view(&document_id, user_id).await {
+                Ok(previous) => {
+                    response.unread = document.last_edited_by != user_id
+                        && previous.map_or(true, |viewed_at| document.updated_at > viewed_at);
+                },
+                Err(e) => error!("Database error recording document view: {:?}", e),
+            }
+
+            // The client's copy is current; it still counts as a view
+            if not_modified {
+                return HttpResponse::NotModified().insert_header(ETag(etag)).finish();
+            }
+
+            HttpResponse::Ok()
+                .insert_header(ETag(etag))
+                .json(ApiResponse::<DocumentResponse>::success(response))
+        },
         Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("DOC_NOT_FOUND", "Document not found")),
         Err(e) => {
             error!("Database error getting document: {:?}", e);
@@ -284,27 +455,26 @@ pub async fn update_document(
     let document_id = document_id.into_inner();
 
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
-    // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
-        Ok(true) => {},
-        Ok(false) => {
+    // Check the caller may edit, not just view or comment on, the document
+    match repo.get_document_role(&document_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::EditDocuments) => {},
+        Ok(Some(_)) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
+                "PERMISSION_DENIED",
+                "You don't have permission to edit this document",
             ));
         },
-        Err(_) => {
+        Ok(None) => return document_access_denied(&http_req),
+        Err(e) => {
+            error!("Database error checking document role: {:?}", e);
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
                 "A database error occurred. Please try again later.",
@@ -312,24 +482,44 @@ pub async fn update_document(
         },
     }
 
-    match repo
-        .update(
-            &document_id,
-            req.title.as_deref(),
-            req.icon.as_deref(),
-            req.content.clone(),
-            &user_id,
-        )
-        .await
-    {
+    // Clients that send back the version they loaded get a 409 instead of
+    // overwriting someone else's change
+    let result = match req.version {
+        Some(expected_version) => repo
+            .update_at_version(
+                &document_id,
+                expected_version,
+                req.title.as_deref(),
+                req.icon.as_deref(),
+                req.content.clone(),
+                user_id,
+            )
+            .await
+            .map(Some),
+        None => repo
+            .update(
+                &document_id,
+                req.title.as_deref(),
+                req.icon.as_deref(),
+                req.content.clone(),
+                user_id,
+            )
+            .await
+            .map_err(UpdateDocumentError::from),
+    };
+
+    match result {
         Ok(Some(document)) => HttpResponse::Ok().json(ApiResponse::<DocumentResponse>::success(
             document_row_to_response(&document),
         )),
-        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+        Ok(None) | Err(UpdateDocumentError::NotFound) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
             "DOC_NOT_FOUND",
             "Document not found or archived",
         )),
-        Err(e) => {
+        Err(e @ UpdateDocumentError::VersionConflict { .. }) => {
+            HttpResponse::Conflict().json(ApiResponse::<()>::error("VERSION_CONFLICT", &e.to_string()))
+        },
+        Err(UpdateDocumentError::Database(e)) => {
             error!("Database error updating document: {:?}", e);
             HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -339,6 +529,65 @@ pub async fn update_document(
     }
 }
 
+// Apply a JSON Patch to document content
+pub async fn patch_document_content(
+    document_id: web::Path<String>,
+    req: web::Json<PatchDocumentContentRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.get_document_role(&document_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::EditDocuments) => {},
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "PERMISSION_DENIED",
+                "You don't have permission to edit this document",
+            ));
+        },
+        Ok(None) => return document_access_denied(&http_req),
+        Err(e) => {
+            error!("Database error checking document role: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    match repo.patch_content(&document_id, req.version, &req.patch, user_id).await {
+        Ok(document) => HttpResponse::Ok().json(ApiResponse::<DocumentResponse>::success(
+            document_row_to_response(&document),
+        )),
+        Err(PatchContentError::NotFound) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "DOC_NOT_FOUND",
+            "Document not found or archived",
+        )),
+        Err(e @ PatchContentError::VersionConflict { .. }) => {
+            HttpResponse::Conflict().json(ApiResponse::<()>::error("VERSION_CONFLICT", &e.to_string()))
+        },
+        Err(e @ PatchContentError::InvalidPatch(_)) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_PATCH", &e.to_string()))
+        },
+        Err(PatchContentError::InvalidContent(e)) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("VALIDATION_ERROR", &e.to_string()))
+        },
+        Err(PatchContentError::Database(e)) => {
+            error!("Database error patching document: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
 // Delete document (soft delete)
 pub async fn delete_document(
     document_id: web::Path<String>,
@@ -347,20 +596,15 @@ pub async fn delete_document(
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -385,6 +629,170 @@ pub async fn delete_document(
     }
 }
 
+// Bulk archive documents - POST /documents/bulk/archive
+//
+// Archiving needs edit rights, checked per document; denied or missing
+// documents are reported in `failed` with a reason code (`ACCESS_DENIED`,
+// `PERMISSION_DENIED`, `DOC_NOT_FOUND` or `DATABASE_ERROR`) without stopping
+// the rest of the batch.
+pub async fn bulk_archive_documents(
+    req: web::Json<BulkArchiveRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    if let Err(validation_errors) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    let mut allowed = Vec::new();
+    let mut failed = Vec::new();
+    for document_id in &req.document_ids {
+        let reason = match repo.get_document_role(&document_id.to_string(), user_id).await {
+            Ok(Some(role)) if has_permission(&role, Permission::EditDocuments) => {
+                allowed.push(*document_id);
+                continue;
+            },
+            Ok(Some(_)) => "PERMISSION_DENIED",
+            Ok(None) => "ACCESS_DENIED",
+            Err(e) => {
+                error!("Database error checking document role: {:?}", e);
+                "DATABASE_ERROR"
+            },
+        };
+        failed.push(FailedArchive {
+            document_id: *document_id,
+            reason: reason.to_string(),
+        });
+    }
+
+    let (archived, archive_failed) = repo.bulk_archive(&allowed).await;
+    failed.extend(archive_failed.into_iter().map(|(document_id, reason)| FailedArchive {
+        document_id,
+        reason: reason.to_string(),
+    }));
+
+    HttpResponse::Ok().json(ApiResponse::<BulkArchiveResponse>::success(BulkArchiveResponse {
+        archived,
+        failed,
+    }))
+}
+
+// Pinning reorders the space listing for everyone, so it needs edit rights
+// in the document's space rather than plain read access
+async fn check_can_pin(
+    repo: &DocumentRepository,
+    document_id: &str,
+    user_id: uuid::Uuid,
+    http_req: &actix_web::HttpRequest,
+) -> Result<(), HttpResponse> {
+    let space_id = match repo.get_by_id(document_id).await {
+        Ok(Some(document)) => document.space_id.to_string(),
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(ApiResponse::<()>::error(
+                "DOC_NOT_FOUND",
+                "Document not found or archived",
+            )));
+        },
+        Err(e) => {
+            error!("Database error loading document: {:?}", e);
+            return Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            )));
+        },
+    };
+
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::EditDocuments) => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "PERMISSION_DENIED",
+            "You don't have permission to pin documents in this space",
+        ))),
+        Ok(None) => Err(document_access_denied(http_req)),
+        Err(_) => Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+            "DATABASE_ERROR",
+            "A database error occurred. Please try again later.",
+        ))),
+    }
+}
+
+// Pin document to the top of its space listing
+pub async fn pin_document(
+    document_id: web::Path<String>,
+    req: Option<web::Json<PinDocumentRequest>>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+    let pin_order = req.and_then(|req| req.pin_order);
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if let Err(response) = check_can_pin(&repo, &document_id, user_id, &http_req).await {
+        return response;
+    }
+
+    match repo.pin(&document_id, pin_order).await {
+        Ok(Some(document)) => HttpResponse::Ok().json(ApiResponse::<DocumentResponse>::success(
+            document_row_to_response(&document),
+        )),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "DOC_NOT_FOUND",
+            "Document not found or archived",
+        )),
+        Err(e) => {
+            error!("Database error pinning document: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+// Unpin document, returning it to the normal listing order
+pub async fn unpin_document(
+    document_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if let Err(response) = check_can_pin(&repo, &document_id, user_id, &http_req).await {
+        return response;
+    }
+
+    match repo.unpin(&document_id).await {
+        Ok(Some(document)) => HttpResponse::Ok().json(ApiResponse::<DocumentResponse>::success(
+            document_row_to_response(&document),
+        )),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "DOC_NOT_FOUND",
+            "Document not found or archived",
+        )),
+        Err(e) => {
+            error!("Database error unpinning document: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
 // List documents in a space
 pub async fn list_documents(
     space_id: web::Path<String>,
@@ -394,13 +802,13 @@ pub async fn list_documents(
 ) -> impl Responder {
     let space_id = space_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check space access
-    match check_space_access(&repo, &space_id, &user_id).await {
+    match check_space_access(&repo, &space_id, user_id).await {
         Ok(true) => {},
         Ok(false) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
@@ -419,13 +827,36 @@ pub async fn list_documents(
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
     let offset = query.offset.unwrap_or(0);
 
-    match repo.list_in_space(&space_id, query.parent_id.as_deref(), limit, offset).await {
-        Ok((documents, total)) => {
+    let allow_estimate = query.estimate_total.unwrap_or(false);
+
+    match repo
+        .list_in_space(&space_id, query.parent_id.as_deref(), limit, offset, allow_estimate)
+        .await
+    {
+        Ok((rows, total)) => {
+            let mut documents = match with_unread_flags(&repo, user_id, &rows).await {
+                Ok(documents) => documents,
+                Err(response) => return response,
+            };
+            if query.includes_comments() {
+                if let Err(response) = with_comment_counts(&repo, &space_id, &rows, &mut documents).await {
+                    return response;
+                }
+            }
+            if query.includes_favorite() {
+                if let Err(response) = with_favorite_flags(&repo, user_id, &mut documents).await {
+                    return response;
+                }
+            }
+            let next_offset = next_offset(offset.into(), documents.len(), total.count);
             HttpResponse::Ok().json(ApiResponse::<DocumentListResponse>::success(DocumentListResponse {
-                documents: documents.iter().map(document_row_to_response).collect(),
-                total,
+                documents,
+                total: total.count,
+                total_is_estimate: total.is_estimate,
                 limit,
                 offset,
+                has_more: next_offset.is_some(),
+                next_offset,
             }))
         },
         Err(e) => {
@@ -446,20 +877,15 @@ pub async fn get_document_children(
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -469,10 +895,13 @@ pub async fn get_document_children(
     }
 
     match repo.get_children(&document_id).await {
-        Ok((children, total)) => HttpResponse::Ok().json(ApiResponse::<ChildrenResponse>::success(ChildrenResponse {
-            documents: children.iter().map(document_row_to_response).collect(),
-            total,
-        })),
+        Ok((children, total)) => match with_unread_flags(&repo, user_id, &children).await {
+            Ok(documents) => HttpResponse::Ok().json(ApiResponse::<ChildrenResponse>::success(ChildrenResponse {
+                documents,
+                total,
+            })),
+            Err(response) => response,
+        },
         Err(e) => {
             error!("Database error getting document children: {:?}", e);
             HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
@@ -491,20 +920,15 @@ pub async fn get_document_path(
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -538,32 +962,25 @@ pub async fn get_document_path(
 pub async fn create_version(
     document_id: web::Path<String>,
     req: web::Json<CreateVersionRequest>,
+    query: web::Query<CreateVersionQuery>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -572,21 +989,40 @@ pub async fn create_version(
         },
     }
 
-    match repo
-        .create_version(
+    // Auto-saves within the throttle window update the caller's latest
+    // auto-save version; explicit saves always create a new one
+    let result = if query.auto_save {
+        repo.create_auto_save_version(
             &document_id,
             req.content.clone(),
             &req.title,
-            &user_id,
+            user_id,
             req.change_summary.as_deref(),
         )
         .await
-    {
-        Ok(version) => {
-            HttpResponse::Created().json(ApiResponse::<CreateVersionResponse>::success(CreateVersionResponse {
+    } else {
+        repo.create_version(
+            &document_id,
+            req.content.clone(),
+            &req.title,
+            user_id,
+            req.change_summary.as_deref(),
+        )
+        .await
+        .map(|version| (version, false))
+    };
+
+    match result {
+        Ok((version, coalesced)) => {
+            let (mut response, message) = if coalesced {
+                (HttpResponse::Ok(), "Auto-save merged into latest version")
+            } else {
+                (HttpResponse::Created(), "Version created successfully")
+            };
+            response.json(ApiResponse::<CreateVersionResponse>::success(CreateVersionResponse {
                 id: version.id.to_string(),
                 version_number: version.version_number,
-                message: "Version created successfully".to_string(),
+                message: message.to_string(),
                 version: version_row_to_response(&version),
             }))
         },
@@ -609,20 +1045,15 @@ pub async fn list_versions(
 ) -> impl Responder {
     let document_id = document_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -636,11 +1067,14 @@ pub async fn list_versions(
 
     match repo.list_versions(&document_id, limit, offset).await {
         Ok((versions, total)) => {
+            let next_offset = next_offset(offset.into(), versions.len(), total);
             HttpResponse::Ok().json(ApiResponse::<VersionListResponse>::success(VersionListResponse {
                 versions: versions.iter().map(version_row_to_response).collect(),
                 total,
                 limit,
                 offset,
+                has_more: next_offset.is_some(),
+                next_offset,
             }))
         },
         Err(e) => {
@@ -653,6 +1087,53 @@ pub async fn list_versions(
     }
 }
 
+// Get metadata for several versions in one call
+pub async fn batch_version_meta(
+    document_id: web::Path<String>,
+    req: web::Json<BatchVersionMetaRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    if let Err(validation_errors) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    // Check document access
+    match check_document_access(&repo, &document_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => return document_access_denied(&http_req),
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    match repo.get_versions_meta(&document_id, &req.version_numbers).await {
+        Ok(rows) => {
+            let (versions, missing) = order_version_meta(&req.version_numbers, rows);
+            HttpResponse::Ok().json(ApiResponse::<BatchVersionMetaResponse>::success(
+                BatchVersionMetaResponse { versions, missing },
+            ))
+        },
+        Err(e) => {
+            error!("Database error fetching version metadata: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
 // Get specific version
 pub async fn get_version(
     path: actix_web::web::Path<(String, i32)>,
@@ -661,20 +1142,15 @@ pub async fn get_version(
 ) -> impl Responder {
     let (document_id, version_number) = path.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -684,9 +1160,16 @@ pub async fn get_version(
     }
 
     match repo.get_version(&document_id, version_number).await {
-        Ok(Some(version)) => HttpResponse::Ok().json(ApiResponse::<VersionResponse>::success(version_row_to_response(
-            &version,
-        ))),
+        Ok(Some(version)) => {
+            let response = version_row_to_response(&version);
+            let etag = version_etag(&response);
+            if is_not_modified(&http_req, &etag) {
+                return HttpResponse::NotModified().insert_header(ETag(etag)).finish();
+            }
+            HttpResponse::Ok()
+                .insert_header(ETag(etag))
+                .json(ApiResponse::<VersionResponse>::success(response))
+        },
         Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("VERSION_NOT_FOUND", "Version not found")),
         Err(_) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
             "DATABASE_ERROR",
@@ -703,20 +1186,15 @@ pub async fn restore_version(
 ) -> impl Responder {
     let (document_id, version_number) = path.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -725,7 +1203,7 @@ pub async fn restore_version(
         },
     }
 
-    match repo.restore_version(&document_id, version_number, &user_id).await {
+    match repo.restore_version(&document_id, version_number, user_id).await {
         Ok(Some(document)) => {
             HttpResponse::Ok().json(ApiResponse::<RestoreVersionResponse>::success(RestoreVersionResponse {
                 document: document_row_to_response(&document),
@@ -768,20 +1246,15 @@ pub async fn get_version_diff(
         (_, Err(msg)) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error("INVALID_PARAM", msg)),
     };
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -815,6 +1288,8 @@ pub async fn export_document(
     document_id: web::Path<String>,
     query: web::Query<ExportQuery>,
     repo: web::Data<DocumentRepository>,
+    pool: web::Data<sqlx::PgPool>,
+    storage: Option<web::Data<std::sync::Arc<file_service::storage::S3Storage>>>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let document_id = document_id.into_inner();
@@ -825,11 +1300,12 @@ pub async fn export_document(
         Some("html") | Some("htm") => ExportFormat::Html,
         Some("pdf") => ExportFormat::Pdf,
         Some("json") => ExportFormat::Json,
+        Some("docx") | Some("doc") => ExportFormat::Docx,
         Some(fmt) => {
             return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
                 "INVALID_FORMAT",
                 &format!(
-                    "Unknown export format: {}. Supported formats: markdown, html, pdf, json",
+                    "Unknown export format: {}. Supported formats: markdown, html, pdf, json, docx",
                     fmt
                 ),
             ));
@@ -837,20 +1313,15 @@ pub async fn export_document(
         None => ExportFormat::Markdown, // Default to markdown
     };
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check document access
-    match check_document_access(&repo, &document_id, &user_id).await {
+    match check_document_access(&repo, &document_id, user_id).await {
         Ok(true) => {},
-        Ok(false) => {
-            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
-                "ACCESS_DENIED",
-                "You don't have access to this document",
-            ));
-        },
+        Ok(false) => return document_access_denied(&http_req),
         Err(_) => {
             return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -864,7 +1335,15 @@ pub async fn export_document(
         Ok(Some(document)) => {
             // Create export service with temp directory
             let temp_dir = std::env::temp_dir().join("miniwiki_exports");
-            let export_service = ExportService::new(temp_dir);
+            let mut export_service = ExportService::new(temp_dir);
+            // Embed referenced images when file storage is configured
+            if let Some(storage) = storage {
+                export_service = export_service.with_image_store(std::sync::Arc::new(StorageImageStore::new(
+                    pool.get_ref().clone(),
+                    storage.get_ref().clone(),
+                    document.space_id,
+                )));
+            }
 
             // Create metadata
             let metadata = Some(crate::export::DocumentMetadata {
@@ -922,14 +1401,109 @@ pub async fn export_document(
     }
 }
 
+// Export a space as a zip - GET /spaces/{spaceId}/export
+// Documents are laid out in folders following the page tree. The archive is
+// built on disk and streamed back, so large spaces aren't held in memory.
+pub async fn export_space(
+    space_id: web::Path<String>,
+    query: web::Query<ExportQuery>,
+    repo: web::Data<DocumentRepository>,
+    pool: web::Data<sqlx::PgPool>,
+    storage: Option<web::Data<std::sync::Arc<file_service::storage::S3Storage>>>,
+    http_req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let space_id = space_id.into_inner();
+
+    let format = match query.format.as_deref() {
+        Some(fmt) => match ExportFormat::from_str(fmt) {
+            Some(format) => format,
+            None => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "INVALID_FORMAT",
+                    &format!(
+                        "Unknown export format: {}. Supported formats: markdown, html, pdf, json, docx",
+                        fmt
+                    ),
+                ));
+            },
+        },
+        None => ExportFormat::Markdown,
+    };
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match check_space_access(&repo, &space_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "You don't have access to this space",
+            ));
+        },
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    let temp_dir = std::env::temp_dir().join("miniwiki_exports");
+    let mut export_service = ExportService::new(temp_dir);
+    if let (Some(storage), Ok(space_uuid)) = (storage, uuid::Uuid::parse_str(&space_id)) {
+        export_service = export_service.with_image_store(std::sync::Arc::new(StorageImageStore::new(
+            pool.get_ref().clone(),
+            storage.get_ref().clone(),
+            space_uuid,
+        )));
+    }
+
+    let export_response = match export_service.export_space(&space_id, repo.get_ref(), format).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Space export error: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "EXPORT_ERROR",
+                &format!("Export failed: {}", e),
+            ));
+        },
+    };
+
+    let file_path = export_service.output_dir().join(&export_response.file_name);
+    let file = match actix_files::NamedFile::open_async(&file_path).await {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Error opening exported archive: {:?}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("EXPORT_ERROR", "Failed to read exported archive"));
+        },
+    };
+    // The open handle keeps the archive readable while it streams
+    let _ = std::fs::remove_file(&file_path);
+
+    let download_name = match repo.get_space(&space_id).await {
+        Ok(Some(space)) => format!("{}.zip", space.name.replace(|c: char| !c.is_alphanumeric() && c != '_', "_")),
+        _ => export_response.file_name.clone(),
+    };
+
+    file.set_content_disposition(actix_web::http::header::ContentDisposition {
+        disposition: actix_web::http::header::DispositionType::Attachment,
+        parameters: vec![actix_web::http::header::DispositionParam::Filename(download_name)],
+    })
+    .into_response(&http_req)
+}
+
 // Space handlers
 pub async fn list_spaces(repo: web::Data<DocumentRepository>, http_req: actix_web::HttpRequest) -> impl Responder {
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
-    match repo.list_spaces(&user_id).await {
+    match repo.list_spaces(user_id).await {
         Ok(spaces) => {
             let total = spaces.len() as i32;
             HttpResponse::Ok().json(ApiResponse::<SpaceListResponse>::success(SpaceListResponse {
@@ -953,20 +1527,17 @@ pub async fn create_space(
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     match repo
         .create_space(
-            &user_id,
+            user_id,
             &req.name,
             req.icon.as_deref(),
             req.description.as_deref(),
@@ -992,9 +1563,9 @@ pub async fn get_space(
 ) -> impl Responder {
     let space_id = space_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // First check if space exists
@@ -1014,7 +1585,7 @@ pub async fn get_space(
 
     // Then check access (skip for public spaces)
     if !space.is_public {
-        match check_space_access(&repo, &space_id, &user_id).await {
+        match check_space_access(&repo, &space_id, user_id).await {
             Ok(true) => {},
             Ok(false) => {
                 return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
@@ -1043,19 +1614,16 @@ pub async fn update_space(
     let space_id = space_id.into_inner();
 
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check if user is owner
-    match repo.is_space_owner(&space_id, &user_id).await {
+    match repo.is_space_owner(&space_id, user_id).await {
         Ok(true) => {},
         Ok(false) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
@@ -1084,36 +1652,191 @@ pub async fn update_space(
         Ok(Some(space)) => {
             HttpResponse::Ok().json(ApiResponse::<SpaceResponse>::success(space_row_to_response(&space)))
         },
-        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", "Space not found")),
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", "Space not found")),
+        Err(e) => {
+            error!("Database error updating space: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+pub async fn delete_space(
+    space_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    // Check if user is owner
+    match repo.is_space_owner(&space_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "Only space owner can delete space",
+            ));
+        },
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    match repo.delete_space(&space_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", "Space not found")),
+        Err(e) => {
+            error!("Database error deleting space: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+// Space membership handlers
+pub async fn list_space_members(
+    space_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    // Check access
+    match repo.check_space_access(&space_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "You don't have access to this space",
+            ));
+        },
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    match repo.list_space_members(&space_id).await {
+        Ok(members) => {
+            let total = members.len() as i32;
+            // Members aren't paged; every one is returned at once
+            HttpResponse::Ok().json(ApiResponse::<MemberListResponse>::success(MemberListResponse {
+                members: members.into_iter().map(|m| membership_row_to_response(&m)).collect(),
+                total,
+                has_more: false,
+                next_offset: None,
+            }))
+        },
+        Err(e) => {
+            error!("Database error listing members: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+// Members can only be given roles that exist in the space, built-in or custom
+pub(crate) async fn check_role_defined(
+    repo: &DocumentRepository,
+    space_id: &str,
+    role: &str,
+) -> Result<(), HttpResponse> {
+    match repo.find_space_role(space_id, role).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "INVALID_ROLE",
+            &format!("Role '{}' is not defined in this space", role),
+        ))),
+        Err(e) => {
+            error!("Database error looking up space role: {:?}", e);
+            Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            )))
+        },
+    }
+}
+
+/// Only the space owner may hand out the owner role; anyone else with
+/// `ManageMembers` granting it would be promoting themselves or others above
+/// their own role
+pub(crate) async fn check_can_grant_role(
+    repo: &DocumentRepository,
+    space_id: &str,
+    user_id: uuid::Uuid,
+    role: &str,
+) -> Result<(), HttpResponse> {
+    if role != OWNER_ROLE {
+        return Ok(());
+    }
+
+    match repo.is_space_owner(space_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "ACCESS_DENIED",
+            "Only the space owner can grant the owner role",
+        ))),
         Err(e) => {
-            error!("Database error updating space: {:?}", e);
-            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+            error!("Database error checking space owner: {:?}", e);
+            Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
                 "A database error occurred. Please try again later.",
-            ))
+            )))
         },
     }
 }
 
-pub async fn delete_space(
+pub async fn add_space_member(
     space_id: web::Path<String>,
+    req: web::Json<AddMemberRequest>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let space_id = space_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    if let Err(validation_errors) = (*req).validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
-    // Check if user is owner
-    match repo.is_space_owner(&space_id, &user_id).await {
-        Ok(true) => {},
-        Ok(false) => {
+    // Check if user can manage members
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::ManageMembers) => {},
+        Ok(Some(_)) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
                 "ACCESS_DENIED",
-                "Only space owner can delete space",
+                "Insufficient permissions to add members",
+            ));
+        },
+        Ok(None) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "You don't have access to this space",
             ));
         },
         Err(_) => {
@@ -1124,36 +1847,51 @@ pub async fn delete_space(
         },
     }
 
-    match repo.delete_space(&space_id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", "Space not found")),
-        Err(e) => {
-            error!("Database error deleting space: {:?}", e);
-            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
-                "DATABASE_ERROR",
-                "A database error occurred. Please try again later.",
-            ))
-        },
+    if let Err(response) = check_role_defined(&repo, &space_id, &req.role).await {
+        return response;
+    }
+    if let Err(response) = check_can_grant_role(&repo, &space_id, user_id, &req.role).await {
+        return response;
+    }
+
+    match repo.add_space_member(&space_id, &req.user_id, &req.role, user_id).await {
+        Ok(membership) => HttpResponse::Created().json(ApiResponse::<MemberResponse>::success(
+            membership_row_to_response(&membership),
+        )),
+        Err(e) => add_member_error_response(e),
     }
 }
 
-// Space membership handlers
-pub async fn list_space_members(
+/// Add several members to a space in one request
+///
+/// All members are added or none are: the import is rejected when the new
+/// members do not fit in the space's remaining member capacity.
+pub async fn import_space_members(
     space_id: web::Path<String>,
+    req: web::Json<ImportMembersRequest>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let space_id = space_id.into_inner();
 
-    let user_id = match extract_user_id(&http_req) {
+    if let Err(validation_errors) = (*req).validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
-    // Check access
-    match repo.check_space_access(&space_id, &user_id).await {
-        Ok(true) => {},
-        Ok(false) => {
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::ManageMembers) => {},
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "Insufficient permissions to add members",
+            ));
+        },
+        Ok(None) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
                 "ACCESS_DENIED",
                 "You don't have access to this space",
@@ -1167,47 +1905,61 @@ pub async fn list_space_members(
         },
     }
 
-    match repo.list_space_members(&space_id).await {
-        Ok(members) => {
-            let total = members.len() as i32;
-            HttpResponse::Ok().json(ApiResponse::<MemberListResponse>::success(MemberListResponse {
-                members: members.into_iter().map(|m| membership_row_to_response(&m)).collect(),
+    let roles: std::collections::BTreeSet<&str> = req.members.iter().map(|m| m.role.as_str()).collect();
+    for role in roles {
+        if let Err(response) = check_role_defined(&repo, &space_id, role).await {
+            return response;
+        }
+        if let Err(response) = check_can_grant_role(&repo, &space_id, user_id, role).await {
+            return response;
+        }
+    }
+
+    let members: Vec<(String, String)> = req
+        .members
+        .iter()
+        .map(|m| (m.user_id.clone(), m.role.clone()))
+        .collect();
+
+    match repo.add_space_members(&space_id, &members, user_id).await {
+        Ok(added) => {
+            let total = added.len() as i32;
+            HttpResponse::Created().json(ApiResponse::<MemberListResponse>::success(MemberListResponse {
+                members: added.iter().map(membership_row_to_response).collect(),
                 total,
+                has_more: false,
+                next_offset: None,
             }))
         },
-        Err(e) => {
-            error!("Database error listing members: {:?}", e);
-            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
-                "DATABASE_ERROR",
-                "A database error occurred. Please try again later.",
-            ))
-        },
+        Err(e) => add_member_error_response(e),
     }
 }
 
-pub async fn add_space_member(
+/// Add several members, reporting a result for each entry
+///
+/// Unlike the import, entries with an unknown user, an undefined role, a
+/// repeat of an earlier entry, the space owner or no free member slot fail on
+/// their own while the rest are added. Users who already have the requested
+/// role are left as they are. Only the owner may grant the owner role.
+pub async fn bulk_add_space_members(
     space_id: web::Path<String>,
-    req: web::Json<AddMemberRequest>,
+    req: web::Json<BulkAddMembersRequest>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let space_id = space_id.into_inner();
 
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
-    // Check if user is owner or editor
-    match repo.get_user_space_role(&space_id, &user_id).await {
-        Ok(Some(role)) if role == "owner" || role == "editor" => {},
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::ManageMembers) => {},
         Ok(Some(_)) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
                 "ACCESS_DENIED",
@@ -1228,11 +1980,72 @@ pub async fn add_space_member(
         },
     }
 
-    match repo.add_space_member(&space_id, &req.user_id, &req.role, &user_id).await {
-        Ok(membership) => HttpResponse::Created().json(ApiResponse::<MemberResponse>::success(
-            membership_row_to_response(&membership),
-        )),
-        Err(e) => {
+    if let Some(member) = req.members.iter().find(|m| m.role == OWNER_ROLE) {
+        if let Err(response) = check_can_grant_role(&repo, &space_id, user_id, &member.role).await {
+            return response;
+        }
+    }
+
+    let entries: Vec<(String, String)> = req
+        .members
+        .iter()
+        .map(|m| (m.user_id.clone(), m.role.clone()))
+        .collect();
+
+    let outcomes = match repo.add_members_bulk(&space_id, &entries, user_id).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => return add_member_error_response(e),
+    };
+
+    let mut response = BulkAddMembersResponse {
+        results: Vec::with_capacity(outcomes.len()),
+        added: 0,
+        role_changed: 0,
+        unchanged: 0,
+        failed: 0,
+    };
+    for ((entry_user_id, role), outcome) in entries.into_iter().zip(outcomes) {
+        let (status, error, member) = match outcome {
+            BulkMemberOutcome::Added(row) => {
+                response.added += 1;
+                ("added", None, Some(membership_row_to_response(&row)))
+            },
+            BulkMemberOutcome::RoleChanged(row) => {
+                response.role_changed += 1;
+                ("role_changed", None, Some(membership_row_to_response(&row)))
+            },
+            BulkMemberOutcome::Unchanged => {
+                response.unchanged += 1;
+                ("unchanged", None, None)
+            },
+            BulkMemberOutcome::Failed(failure) => {
+                response.failed += 1;
+                let error = ApiErrorResponse {
+                    error: failure.code().to_string(),
+                    message: failure.to_string(),
+                    details: None,
+                };
+                ("failed", Some(error), None)
+            },
+        };
+        response.results.push(BulkMemberResult {
+            user_id: entry_user_id,
+            role,
+            status: status.to_string(),
+            error,
+            member,
+        });
+    }
+
+    HttpResponse::Ok().json(ApiResponse::<BulkAddMembersResponse>::success(response))
+}
+
+pub(crate) fn add_member_error_response(e: AddMemberError) -> HttpResponse {
+    match e {
+        AddMemberError::LimitReached { .. } => {
+            HttpResponse::Conflict().json(ApiResponse::<()>::error("MEMBER_LIMIT_REACHED", &e.to_string()))
+        },
+        AddMemberError::Database(e) => {
             error!("Database error adding member: {:?}", e);
             HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
                 "DATABASE_ERROR",
@@ -1251,19 +2064,23 @@ pub async fn update_space_member(
     let (space_id, member_user_id) = path.into_inner();
 
     if let Err(validation_errors) = (*req).validate() {
-        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
-            "VALIDATION_ERROR",
-            &format!("Validation failed: {:?}", validation_errors),
-        ));
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let member_user_id = match uuid::Uuid::parse_str(&member_user_id) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error("VALIDATION_ERROR", "Invalid member id"));
+        },
+    };
+
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check if current user is owner
-    match repo.is_space_owner(&space_id, &user_id).await {
+    match repo.is_space_owner(&space_id, user_id).await {
         Ok(true) => {},
         Ok(false) => {
             return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
@@ -1281,7 +2098,7 @@ pub async fn update_space_member(
     }
 
     // Cannot change owner role
-    match repo.is_space_owner(&space_id, &member_user_id).await {
+    match repo.is_space_owner(&space_id, member_user_id).await {
         Ok(true) => {
             return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
                 "INVALID_OPERATION",
@@ -1298,7 +2115,11 @@ pub async fn update_space_member(
         },
     }
 
-    match repo.update_space_member(&space_id, &member_user_id, &req.role).await {
+    if let Err(response) = check_role_defined(&repo, &space_id, &req.role).await {
+        return response;
+    }
+
+    match repo.update_space_member(&space_id, member_user_id, &req.role, user_id).await {
         Ok(Some(membership)) => HttpResponse::Ok().json(ApiResponse::<MemberResponse>::success(
             membership_row_to_response(&membership),
         )),
@@ -1313,20 +2134,41 @@ pub async fn update_space_member(
     }
 }
 
+/// Remove a member from a space
+///
+/// `content_policy` decides what happens to the documents they created:
+/// `keep` (default), `reassign` to the space owner, or `archive`, which also
+/// needs `confirm=true`.
 pub async fn remove_space_member(
     path: actix_web::web::Path<(String, String)>,
+    query: web::Query<RemoveMemberQuery>,
     repo: web::Data<DocumentRepository>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let (space_id, member_user_id) = path.into_inner();
+    let policy = query.content_policy;
+
+    let member_user_id = match uuid::Uuid::parse_str(&member_user_id) {
+        Ok(id) => id,
+        Err(_) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error("VALIDATION_ERROR", "Invalid member id"));
+        },
+    };
 
-    let user_id = match extract_user_id(&http_req) {
+    if policy == MemberContentPolicy::Archive && !query.confirm {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "CONFIRMATION_REQUIRED",
+            "Archiving the member's documents requires confirm=true",
+        ));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
-        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+        Err(e) => return unauthorized_response(&e),
     };
 
     // Check permissions: owner can remove anyone, member can remove themselves
-    let is_owner = match repo.is_space_owner(&space_id, &user_id).await {
+    let is_owner = match repo.is_space_owner(&space_id, user_id).await {
         Ok(v) => v,
         Err(e) => {
             error!("Database error checking space owner: {:?}", e);
@@ -1347,7 +2189,7 @@ pub async fn remove_space_member(
 
     // Cannot remove owner
     if is_owner {
-        match repo.is_space_owner(&space_id, &member_user_id).await {
+        match repo.is_space_owner(&space_id, member_user_id).await {
             Ok(true) => {
                 return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
                     "INVALID_OPERATION",
@@ -1365,7 +2207,7 @@ pub async fn remove_space_member(
         }
     }
 
-    match repo.remove_space_member(&space_id, &member_user_id).await {
+    match repo.remove_space_member_with_policy(&space_id, member_user_id, user_id, policy).await {
         Ok(true) => HttpResponse::NoContent().finish(),
         Ok(false) => HttpResponse::NotFound().json(ApiResponse::<()>::error("MEMBER_NOT_FOUND", "Member not found")),
         Err(e) => {
@@ -1378,6 +2220,66 @@ pub async fn remove_space_member(
     }
 }
 
+/// Hand a space to another member (current owner only)
+///
+/// The new owner must already be a member; the previous owner stays on as
+/// an editor.
+pub async fn transfer_space_ownership(
+    space_id: web::Path<String>,
+    req: web::Json<TransferOwnershipRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if req.new_owner_id.parse::<uuid::Uuid>().ok() == Some(user_id) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+            "INVALID_OPERATION",
+            "You already own this space",
+        ));
+    }
+
+    if let Err(e) = repo.transfer_ownership(&space_id, user_id, &req.new_owner_id).await {
+        return match e {
+            TransferOwnershipError::SpaceNotFound => {
+                HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", &e.to_string()))
+            },
+            TransferOwnershipError::NotOwner => {
+                HttpResponse::Forbidden().json(ApiResponse::<()>::error("ACCESS_DENIED", &e.to_string()))
+            },
+            TransferOwnershipError::NotMember => {
+                HttpResponse::BadRequest().json(ApiResponse::<()>::error("NOT_A_MEMBER", &e.to_string()))
+            },
+            TransferOwnershipError::Database(e) => {
+                error!("Database error transferring space ownership: {:?}", e);
+                HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                    "DATABASE_ERROR",
+                    "A database error occurred. Please try again later.",
+                ))
+            },
+        };
+    }
+
+    match repo.get_space(&space_id).await {
+        Ok(Some(space)) => {
+            HttpResponse::Ok().json(ApiResponse::<SpaceResponse>::success(space_row_to_response(&space)))
+        },
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("SPACE_NOT_FOUND", "Space not found")),
+        Err(e) => {
+            error!("Database error loading space: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
 // Helper functions for space conversions
 fn space_row_to_response(row: &crate::repository::SpaceRow) -> SpaceResponse {
     SpaceResponse {
@@ -1393,7 +2295,7 @@ fn space_row_to_response(row: &crate::repository::SpaceRow) -> SpaceResponse {
     }
 }
 
-fn membership_row_to_response(row: &crate::repository::SpaceMembershipRow) -> MemberResponse {
+pub(crate) fn membership_row_to_response(row: &crate::repository::SpaceMembershipRow) -> MemberResponse {
     MemberResponse {
         id: row.id.to_string(),
         space_id: row.space_id.to_string(),
@@ -1407,8 +2309,9 @@ fn membership_row_to_response(row: &crate::repository::SpaceMembershipRow) -> Me
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::repository::{DocumentRow, DocumentVersionRow, SpaceMembershipRow, SpaceRow};
+    use crate::repository::{DocumentRow, DocumentVersionRow, SpaceMembershipRow, SpaceRow, VersionMetaRow};
     use actix_web::test::TestRequest;
+    use auth_service::rbac::roles::Role;
     use chrono::{Duration, Utc};
     use futures::executor::block_on;
     use serde_json::json;
@@ -1439,6 +2342,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         let response = document_row_to_response(&row);
@@ -1476,6 +2381,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         let response = document_row_to_response(&row);
@@ -1497,6 +2404,8 @@ mod tests {
             created_by: Uuid::new_v4(),
             created_at: now,
             change_summary: Some("Fixed typo".to_string()),
+            is_pinned: false,
+            is_auto_save: false,
         };
 
         let response = version_row_to_response(&row);
@@ -1508,6 +2417,122 @@ mod tests {
         assert_eq!(response.change_summary, Some("Fixed typo".to_string()));
     }
 
+    #[test]
+    fn test_version_etag_is_stable_and_tracks_content() {
+        let mut row = DocumentVersionRow {
+            id: Uuid::new_v4(),
+            document_id: Uuid::new_v4(),
+            version_number: 3,
+            title: "Version 3".to_string(),
+            content: json!({"ops": []}).into(),
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            change_summary: None,
+            is_pinned: false,
+            is_auto_save: true,
+        };
+
+        let etag = version_etag(&version_row_to_response(&row));
+        assert!(!etag.weak);
+        assert_eq!(etag, version_etag(&version_row_to_response(&row)));
+
+        // A coalesced auto-save rewrites the version in place
+        row.content = json!({"ops": ["insert"]}).into();
+        assert_ne!(etag, version_etag(&version_row_to_response(&row)));
+    }
+
+    #[test]
+    fn test_is_not_modified_matches_weakly() {
+        let etag = EntityTag::new_weak("4-1700000000".to_string());
+
+        let req = TestRequest::default()
+            .insert_header(("If-None-Match", r#""other", W/"4-1700000000""#))
+            .to_http_request();
+        assert!(is_not_modified(&req, &etag));
+
+        let req = TestRequest::default()
+            .insert_header(("If-None-Match", r#""4-1700000000""#))
+            .to_http_request();
+        assert!(is_not_modified(&req, &etag));
+
+        let req = TestRequest::default()
+            .insert_header(("If-None-Match", r#"W/"3-1600000000""#))
+            .to_http_request();
+        assert!(!is_not_modified(&req, &etag));
+
+        let req = TestRequest::default().to_http_request();
+        assert!(!is_not_modified(&req, &etag));
+    }
+
+    fn version_meta_row(version_number: i32, is_pinned: bool) -> VersionMetaRow {
+        VersionMetaRow {
+            version_number,
+            title: format!("Version {}", version_number),
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now().naive_utc(),
+            change_summary: Some(format!("Change {}", version_number)),
+            is_pinned,
+        }
+    }
+
+    #[test]
+    fn test_order_version_meta_follows_request_order() {
+        // Rows come back from the database in arbitrary order
+        let rows = vec![
+            version_meta_row(1, false),
+            version_meta_row(5, true),
+            version_meta_row(3, false),
+        ];
+
+        let (versions, missing) = order_version_meta(&[5, 1, 3], rows);
+
+        let numbers: Vec<i32> = versions.iter().map(|v| v.version_number).collect();
+        assert_eq!(numbers, vec![5, 1, 3]);
+        assert!(versions[0].pinned);
+        assert_eq!(versions[1].title, "Version 1");
+        assert_eq!(versions[2].change_summary, Some("Change 3".to_string()));
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_order_version_meta_omits_content() {
+        let (versions, _) = order_version_meta(&[2], vec![version_meta_row(2, false)]);
+
+        let serialized = serde_json::to_value(&versions[0]).unwrap();
+        assert!(serialized.get("content").is_none());
+        assert_eq!(serialized["version_number"], 2);
+        assert_eq!(serialized["pinned"], false);
+    }
+
+    #[test]
+    fn test_order_version_meta_reports_missing_versions() {
+        let rows = vec![version_meta_row(1, false), version_meta_row(2, false)];
+
+        let (versions, missing) = order_version_meta(&[1, 99, 2, 1], rows);
+
+        let numbers: Vec<i32> = versions.iter().map(|v| v.version_number).collect();
+        assert_eq!(numbers, vec![1, 2]);
+        assert_eq!(missing, vec![99]);
+    }
+
+    #[test]
+    fn test_batch_version_meta_request_validation() {
+        let empty = BatchVersionMetaRequest {
+            version_numbers: vec![],
+        };
+        assert!(empty.validate().is_err());
+
+        let too_many = BatchVersionMetaRequest {
+            version_numbers: (1..=101).collect(),
+        };
+        assert!(too_many.validate().is_err());
+
+        let valid = BatchVersionMetaRequest {
+            version_numbers: vec![1, 2, 3],
+        };
+        assert!(valid.validate().is_ok());
+    }
+
     #[test]
     fn test_space_row_to_response() {
         let now = Utc::now().naive_utc();
@@ -1554,8 +2579,8 @@ mod tests {
 
     // ===== Extract User ID Tests =====
 
-    #[test]
-    fn test_extract_user_id_from_jwt() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_from_jwt() {
         let secret = "test-secret-key-for-testing-only-do-not-use-in-production";
         let exp = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -1578,42 +2603,106 @@ mod tests {
             .insert_header(("Authorization", format!("Bearer {}", token)))
             .to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(result.unwrap().to_string(), "550e8400-e29b-41d4-a716-446655440000");
     }
 
-    #[test]
-    fn test_extract_user_id_from_x_user_id_header() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_from_x_user_id_header() {
         let req = TestRequest::get()
             .insert_header(("X-User-Id", "550e8400-e29b-41d4-a716-446655440000"))
             .to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(result.unwrap().to_string(), "550e8400-e29b-41d4-a716-446655440000");
     }
 
-    #[test]
-    fn test_extract_user_id_missing() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_missing() {
         let req = TestRequest::get().to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("Missing or invalid authentication"));
     }
 
-    #[test]
-    fn test_extract_user_id_invalid_jwt() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_invalid_jwt() {
         let req = TestRequest::get()
             .insert_header(("Authorization", "Bearer invalid.token.here"))
             .to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_err());
     }
 
+    fn bearer_token(user_id: &str) -> String {
+        let exp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &json!({ "sub": user_id, "exp": exp }),
+            &jsonwebtoken::EncodingKey::from_secret(
+                "test-secret-key-for-testing-only-do-not-use-in-production".as_bytes(),
+            ),
+        )
+        .unwrap();
+        format!("Bearer {}", token)
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_user_id_matching_identities_pass() {
+        let user_id = "550e8400-e29b-41d4-a716-446655440000";
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer_token(user_id)))
+            .insert_header(("X-User-Id", user_id))
+            .to_http_request();
+
+        let result = extract_user_id_with_policy(&req, IdentityMismatchPolicy::Strict).await;
+        assert_eq!(result.unwrap().to_string(), user_id);
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_user_id_mismatch_rejected_when_strict() {
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer_token("550e8400-e29b-41d4-a716-446655440000")))
+            .insert_header(("X-User-Id", "660e8400-e29b-41d4-a716-446655440000"))
+            .to_http_request();
+
+        let err = extract_user_id_with_policy(&req, IdentityMismatchPolicy::Strict).await.unwrap_err();
+        assert!(is_identity_mismatch(&err));
+
+        let response = unauthorized_response(&err);
+        assert_eq!(response.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_user_id_mismatch_prefers_jwt_when_lenient() {
+        let req = TestRequest::get()
+            .insert_header(("Authorization", bearer_token("550e8400-e29b-41d4-a716-446655440000")))
+            .insert_header(("X-User-Id", "660e8400-e29b-41d4-a716-446655440000"))
+            .to_http_request();
+
+        let result = extract_user_id_with_policy(&req, IdentityMismatchPolicy::Lenient).await;
+        assert_eq!(result.unwrap().to_string(), "550e8400-e29b-41d4-a716-446655440000");
+    }
+
+    #[test]
+    fn test_unauthorized_response_codes() {
+        let mismatch = AppError::AuthenticationError(IDENTITY_MISMATCH_MESSAGE.to_string());
+        let body = block_on(actix_web::body::to_bytes(unauthorized_response(&mismatch).into_body())).unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(IDENTITY_MISMATCH_CODE));
+
+        let missing = AppError::AuthenticationError("Missing or invalid authentication".to_string());
+        assert!(!is_identity_mismatch(&missing));
+    }
+
     // ===== Access Check Helper Tests =====
 
     #[test]
@@ -1681,26 +2770,26 @@ mod tests {
 
     #[test]
     fn test_space_role_owner_can_create() {
-        let role = "owner";
-        assert!(role == "owner" || role == "editor");
+        let role = Role::builtin("owner").unwrap();
+        assert!(has_permission(&role, Permission::CreateDocuments));
     }
 
     #[test]
     fn test_space_role_editor_can_create() {
-        let role = "editor";
-        assert!(role == "owner" || role == "editor");
+        let role = Role::builtin("editor").unwrap();
+        assert!(has_permission(&role, Permission::CreateDocuments));
     }
 
     #[test]
     fn test_space_role_viewer_cannot_create() {
-        let role = "viewer";
-        assert!(role != "owner" && role != "editor");
+        let role = Role::builtin("viewer").unwrap();
+        assert!(!has_permission(&role, Permission::CreateDocuments));
     }
 
     #[test]
     fn test_space_role_commenter_cannot_create() {
-        let role = "commenter";
-        assert!(role != "owner" && role != "editor");
+        let role = Role::builtin("commenter").unwrap();
+        assert!(!has_permission(&role, Permission::CreateDocuments));
     }
 
     // ===== Document Access Level Tests =====
@@ -1755,6 +2844,8 @@ mod tests {
             parent_id: None,
             limit: None,
             offset: None,
+            estimate_total: None,
+            include: None,
         };
         assert_eq!(query.parent_id, None);
         assert_eq!(query.limit, None);
@@ -1767,6 +2858,8 @@ mod tests {
             parent_id: Some("parent-uuid".to_string()),
             limit: Some(50),
             offset: Some(100),
+            estimate_total: Some(true),
+            include: None,
         };
         assert!(query.parent_id.is_some());
         assert_eq!(query.limit, Some(50));
@@ -1796,10 +2889,17 @@ mod tests {
             content: json!({"test": true}).into(),
             content_size: 100,
             is_archived: false,
+            is_pinned: false,
+            pin_order: None,
             created_by: "user-001".to_string(),
             last_edited_by: "user-002".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: "2024-01-02T00:00:00Z".to_string(),
+            version: 1,
+            unread: false,
+            comment_count: None,
+            unresolved_count: None,
+            is_favorite: None,
         };
 
         assert_eq!(response.id, "doc-001");
@@ -1813,8 +2913,11 @@ mod tests {
         let response = DocumentListResponse {
             documents: vec![],
             total: 10,
+            total_is_estimate: false,
             limit: 20,
             offset: 0,
+            has_more: false,
+            next_offset: None,
         };
 
         assert_eq!(response.total, 10);
@@ -1974,4 +3077,30 @@ mod tests {
 
         assert_eq!(token_data.claims.get("sub").unwrap(), "user-123");
     }
+
+    #[test]
+    fn test_verify_access_token_accepts_previous_key() {
+        let exp = (Utc::now() + Duration::hours(1)).timestamp();
+        let token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &json!({"sub": "user-123", "exp": exp}),
+            &jsonwebtoken::EncodingKey::from_secret(b"old-secret"),
+        )
+        .unwrap();
+
+        let config = |previous: &[&str]| auth_service::jwt::JwtConfig {
+            previous_secrets: previous
+                .iter()
+                .map(|s| auth_service::jwt::PreviousSecret::new(*s, Utc::now() + Duration::days(1)))
+                .collect(),
+            ..auth_service::jwt::JwtConfig::new("new-secret".to_string(), 3600, 86400)
+        };
+
+        // Signed with the retired key, which is still accepted
+        let claims = auth_service::jwt::verify_access_token(&token, &config(&["old-secret"])).unwrap();
+        assert_eq!(claims.sub, "user-123");
+
+        // Rejected once the old key is no longer accepted
+        assert!(auth_service::jwt::verify_access_token(&token, &config(&[])).is_err());
+    }
 }
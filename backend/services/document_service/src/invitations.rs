@@ -0,0 +1,142 @@
+//! Space Invitation Handlers
+//!
+//! Provides HTTP handlers for inviting people who may not have an account yet:
+//! - POST /spaces/{spaceId}/invitations - Invite an email address to a space
+//! - POST /invitations/{token}/accept - Join the space an invitation is for
+//!
+//! An invitation carries a random token that is sent to the invitee. Accepting
+//! it requires being signed in with the invited email address, and adds the
+//! membership under the usual member limit. As with adding members directly,
+//! only the space owner can invite someone as owner.
+use actix_web::{web, HttpResponse, Responder};
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::has_permission;
+use tracing::error;
+use validator::Validate;
+
+use crate::handlers::{
+    add_member_error_response, check_can_grant_role, check_role_defined, extract_user_id, membership_row_to_response,
+    unauthorized_response,
+};
+use crate::models::*;
+use crate::repository::{DocumentRepository, InvitationError, SpaceInvitationRow};
+
+fn invitation_row_to_response(row: &SpaceInvitationRow) -> InvitationResponse {
+    InvitationResponse {
+        id: row.id.to_string(),
+        space_id: row.space_id.to_string(),
+        email: row.email.clone(),
+        role: row.role.clone(),
+        token: row.token.clone(),
+        invited_by: row.invited_by.map(|id| id.to_string()),
+        created_at: row.created_at.and_utc().to_rfc3339(),
+        expires_at: row.expires_at.and_utc().to_rfc3339(),
+    }
+}
+
+fn invitation_error_response(e: InvitationError) -> HttpResponse {
+    match e {
+        InvitationError::InvalidEmail(_) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("VALIDATION_ERROR", &e.to_string()))
+        },
+        InvitationError::NotFound => {
+            HttpResponse::NotFound().json(ApiResponse::<()>::error("INVITATION_NOT_FOUND", &e.to_string()))
+        },
+        InvitationError::Expired => {
+            HttpResponse::Gone().json(ApiResponse::<()>::error("INVITATION_EXPIRED", &e.to_string()))
+        },
+        InvitationError::EmailMismatch => {
+            HttpResponse::Forbidden().json(ApiResponse::<()>::error("INVITATION_EMAIL_MISMATCH", &e.to_string()))
+        },
+        InvitationError::OwnerRoleNotAllowed => {
+            HttpResponse::Forbidden().json(ApiResponse::<()>::error("ACCESS_DENIED", &e.to_string()))
+        },
+        InvitationError::SpaceOwner => {
+            HttpResponse::Conflict().json(ApiResponse::<()>::error("OWNER_MEMBERSHIP", &e.to_string()))
+        },
+        InvitationError::AddMember(e) => add_member_error_response(e),
+        InvitationError::Database(e) => {
+            error!("Database error handling invitation: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+/// Invite an email address to a space with a role
+pub async fn create_invitation(
+    space_id: web::Path<String>,
+    req: web::Json<InviteMemberRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    if let Err(validation_errors) = (*req).validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.get_member_role(&space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::ManageMembers) => {},
+        Ok(Some(_)) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "Insufficient permissions to invite members",
+            ));
+        },
+        Ok(None) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "You don't have access to this space",
+            ));
+        },
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    if let Err(response) = check_role_defined(&repo, &space_id, &req.role).await {
+        return response;
+    }
+    if let Err(response) = check_can_grant_role(&repo, &space_id, user_id, &req.role).await {
+        return response;
+    }
+
+    match repo.invite_by_email(&space_id, &req.email, &req.role, user_id).await {
+        Ok(invitation) => HttpResponse::Created().json(ApiResponse::<InvitationResponse>::success(
+            invitation_row_to_response(&invitation),
+        )),
+        Err(e) => invitation_error_response(e),
+    }
+}
+
+/// Accept an invitation as the signed-in user, joining its space
+pub async fn accept_invitation(
+    token: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let token = token.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.accept_invitation(&token, user_id).await {
+        Ok(membership) => HttpResponse::Created().json(ApiResponse::<MemberResponse>::success(
+            membership_row_to_response(&membership),
+        )),
+        Err(e) => invitation_error_response(e),
+    }
+}
@@ -1,33 +1,65 @@
 pub mod export;
+pub mod audit;
 pub mod comments;
+pub mod count_cache;
+pub mod encryption;
+pub mod favorites;
 pub mod handlers;
+pub mod invitations;
+pub mod mentions;
 pub mod models;
+pub mod notifications;
 pub mod repository;
 pub mod validation;
 pub mod sharing;
+#[cfg(feature = "qr")]
+pub mod share_qr;
+pub mod tags;
+pub mod templates;
+pub mod text_metrics;
+pub mod webhooks;
 
 use actix_web::web;
 use crate::handlers::*;
+use crate::audit::list_space_audit;
 use crate::comments::*;
+use crate::favorites::*;
+use crate::invitations::{accept_invitation, create_invitation};
+use crate::notifications::*;
 use crate::sharing::*;
+use crate::tags::*;
+use crate::templates::*;
+use crate::text_metrics::get_document_metrics;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     // Document-scoped endpoints
     cfg.service(
         web::scope("/documents")
+            .route("/bulk/archive", web::post().to(bulk_archive_documents))
             .route("/{documentId}", web::get().to(get_document))
             .route("/{documentId}", web::patch().to(update_document))
             .route("/{documentId}", web::delete().to(delete_document))
+            .route("/{documentId}/content", web::patch().to(patch_document_content))
             .route("/{documentId}/children", web::get().to(get_document_children))
             .route("/{documentId}/path", web::get().to(get_document_path))
+            .route("/{documentId}/metrics", web::get().to(get_document_metrics))
+            .route("/{documentId}/pin", web::post().to(pin_document))
+            .route("/{documentId}/pin", web::delete().to(unpin_document))
+            .route("/{documentId}/favorite", web::put().to(add_favorite))
+            .route("/{documentId}/favorite", web::delete().to(remove_favorite))
             // Export endpoint
             .route("/{documentId}/export", web::get().to(export_document))
             // Version endpoints
             .route("/{documentId}/versions", web::post().to(create_version))
             .route("/{documentId}/versions", web::get().to(list_versions))
+            .route("/{documentId}/versions/batch-meta", web::post().to(batch_version_meta))
             .route("/{documentId}/versions/{versionNumber}", web::get().to(get_version))
             .route("/{documentId}/versions/{versionNumber}/restore", web::post().to(restore_version))
             .route("/{documentId}/versions/diff", web::get().to(get_version_diff))
+            // Tag endpoints
+            .route("/{documentId}/tags", web::get().to(list_tags))
+            .route("/{documentId}/tags", web::post().to(add_tags))
+            .route("/{documentId}/tags/{tag}", web::delete().to(remove_tag))
             // Comment endpoints
             .route("/{documentId}/comments", web::get().to(list_comments))
             .route("/{documentId}/comments", web::post().to(create_comment))
@@ -40,6 +72,8 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("/{commentId}/resolve", web::post().to(resolve_comment))
             .route("/{commentId}/unresolve", web::post().to(unresolve_comment))
             .route("/{commentId}", web::delete().to(delete_comment))
+            .route("/{commentId}/reactions", web::post().to(add_reaction))
+            .route("/{commentId}/reactions/{emoji}", web::delete().to(remove_reaction))
     );
 
     // Share link endpoints
@@ -49,4 +83,37 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("", web::get().to(get_document_share_links))
             .route("/{token}", web::delete().to(delete_share_link))
     );
+
+    // Template and space export endpoints. The space-scoped ones are plain
+    // resources rather than a `/spaces` scope so they don't shadow
+    // space_service's routes.
+    cfg.service(
+        web::resource("/spaces/{spaceId}/templates")
+            .route(web::post().to(create_template))
+            .route(web::get().to(list_templates))
+    );
+    cfg.service(web::resource("/spaces/{spaceId}/export").route(web::get().to(export_space)));
+    cfg.service(web::resource("/spaces/{spaceId}/members/bulk").route(web::post().to(bulk_add_space_members)));
+    cfg.service(web::resource("/spaces/{spaceId}/audit").route(web::get().to(list_space_audit)));
+    cfg.service(
+        web::resource("/spaces/{spaceId}/transfer-ownership").route(web::post().to(transfer_space_ownership))
+    );
+    cfg.service(web::resource("/spaces/{spaceId}/invitations").route(web::post().to(create_invitation)));
+    cfg.service(web::resource("/invitations/{token}/accept").route(web::post().to(accept_invitation)));
+    cfg.service(
+        web::scope("/templates")
+            .route("/{templateId}/instantiate", web::post().to(instantiate_template))
+    );
+
+    // Caller-scoped endpoints
+    cfg.service(
+        web::scope("/me")
+            .route("/favorites", web::get().to(list_favorites))
+    );
+
+    // Notification endpoints
+    cfg.service(
+        web::scope("/notifications")
+            .route("/read", web::post().to(mark_notifications_read))
+    );
 }
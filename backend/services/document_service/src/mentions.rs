@@ -0,0 +1,216 @@
+//! Comment @mentions
+//!
+//! A mention is `@` followed by a user id or a username, where the username
+//! is the local part of the user's email address. Mentions only count when
+//! they resolve to a member of the document's space; anything else stays
+//! literal text.
+
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::MentionResponse;
+use crate::repository::{DocumentRepository, MentionableUserRow};
+
+/// A mention token as written in comment content
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MentionToken {
+    Id(Uuid),
+    /// Lowercased username
+    Username(String),
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+')
+}
+
+/// Extract distinct mention tokens from comment content, in order of appearance
+///
+/// `@` only starts a mention at the beginning of the text or after a character
+/// that cannot be part of a token, so email addresses are not mentions.
+pub fn parse_mentions(content: &str) -> Vec<MentionToken> {
+    let mut tokens = Vec::new();
+    let mut seen = HashSet::new();
+    let mut prev: Option<char> = None;
+
+    for (i, c) in content.char_indices() {
+        let starts_mention = c == '@' && !prev.is_some_and(|p| is_token_char(p) || p == '@');
+        prev = Some(c);
+        if !starts_mention {
+            continue;
+        }
+
+        let rest = &content[i + 1..];
+        let end = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+        // Punctuation ending a sentence is not part of the name
+        let raw = rest[..end].trim_end_matches(['.', '-', '_', '+']);
+        if raw.is_empty() {
+            continue;
+        }
+
+        let token = match Uuid::parse_str(raw) {
+            Ok(id) => MentionToken::Id(id),
+            Err(_) => MentionToken::Username(raw.to_ascii_lowercase()),
+        };
+        if seen.insert(token.clone()) {
+            tokens.push(token);
+        }
+    }
+
+    tokens
+}
+
+/// Split tokens into the user ids and usernames to look up
+pub fn lookup_keys(tokens: &[MentionToken]) -> (Vec<Uuid>, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut usernames = Vec::new();
+    for token in tokens {
+        match token {
+            MentionToken::Id(id) => ids.push(*id),
+            MentionToken::Username(name) => usernames.push(name.clone()),
+        }
+    }
+    (ids, usernames)
+}
+
+/// Resolve tokens against the space members they could refer to
+///
+/// Tokens with no matching member are dropped, as are usernames shared by
+/// several members. Each user is returned once, in order of first mention.
+pub fn resolve_mentions(tokens: &[MentionToken], candidates: &[MentionableUserRow]) -> Vec<MentionableUserRow> {
+    let mut resolved: Vec<MentionableUserRow> = Vec::new();
+
+    for token in tokens {
+        let matches: Vec<&MentionableUserRow> = candidates
+            .iter()
+            .filter(|user| match token {
+                MentionToken::Id(id) => user.id == *id,
+                MentionToken::Username(name) => user.username.eq_ignore_ascii_case(name),
+            })
+            .collect();
+
+        if let [user] = matches.as_slice() {
+            if !resolved.iter().any(|r| r.id == user.id) {
+                resolved.push((*user).clone());
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Space members mentioned in `content` for a comment on `document_id`
+pub async fn resolve_content_mentions(
+    repo: &DocumentRepository,
+    document_id: &str,
+    content: &str,
+) -> Result<Vec<MentionableUserRow>, sqlx::Error> {
+    let tokens = parse_mentions(content);
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (ids, usernames) = lookup_keys(&tokens);
+    let candidates = repo.find_mentionable_users(document_id, &ids, &usernames).await?;
+    Ok(resolve_mentions(&tokens, &candidates))
+}
+
+pub fn mention_response(id: Uuid, display_name: &str, username: &str) -> MentionResponse {
+    MentionResponse {
+        user_id: id.to_string(),
+        display_name: display_name.to_string(),
+        username: username.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(username: &str) -> MentionableUserRow {
+        MentionableUserRow {
+            id: Uuid::new_v4(),
+            display_name: username.to_uppercase(),
+            username: username.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_usernames_and_ids() {
+        let id = Uuid::new_v4();
+        let content = format!("Thanks @Alice and @{}, see @bob.smith.", id);
+
+        assert_eq!(
+            parse_mentions(&content),
+            vec![
+                MentionToken::Username("alice".to_string()),
+                MentionToken::Id(id),
+                MentionToken::Username("bob.smith".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_emails_and_bare_at() {
+        assert!(parse_mentions("mail alice@example.com or write @ me").is_empty());
+        assert!(parse_mentions("@@alice").is_empty());
+        assert!(parse_mentions("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_deduplicates() {
+        assert_eq!(
+            parse_mentions("@carol (@CAROL) @carol!"),
+            vec![MentionToken::Username("carol".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_at_start_and_after_punctuation() {
+        assert_eq!(
+            parse_mentions("@dan:(@erin)"),
+            vec![
+                MentionToken::Username("dan".to_string()),
+                MentionToken::Username("erin".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_keys_split_ids_and_usernames() {
+        let id = Uuid::new_v4();
+        let tokens = vec![MentionToken::Username("alice".to_string()), MentionToken::Id(id)];
+
+        assert_eq!(lookup_keys(&tokens), (vec![id], vec!["alice".to_string()]));
+    }
+
+    #[test]
+    fn test_resolve_keeps_only_members() {
+        let alice = user("alice");
+        let bob = user("bob");
+        let tokens = vec![
+            MentionToken::Username("alice".to_string()),
+            MentionToken::Username("mallory".to_string()),
+            MentionToken::Id(Uuid::new_v4()),
+            MentionToken::Id(bob.id),
+        ];
+
+        let resolved = resolve_mentions(&tokens, &[alice.clone(), bob.clone()]);
+        let ids: Vec<Uuid> = resolved.iter().map(|u| u.id).collect();
+        assert_eq!(ids, vec![alice.id, bob.id]);
+    }
+
+    #[test]
+    fn test_resolve_drops_ambiguous_usernames_and_duplicates() {
+        let first = user("sam");
+        let second = user("sam");
+        let tokens = vec![MentionToken::Username("sam".to_string()), MentionToken::Id(first.id)];
+
+        // "@sam" matches two members, but the id mention is unambiguous
+        let resolved = resolve_mentions(&tokens, &[first.clone(), second]);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].id, first.id);
+
+        let tokens = vec![MentionToken::Id(first.id), MentionToken::Username("sam".to_string())];
+        assert_eq!(resolve_mentions(&tokens, &[first.clone()]).len(), 1);
+    }
+}
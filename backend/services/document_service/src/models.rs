@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 // ============================================
 // Request Types
@@ -27,6 +27,19 @@ pub struct UpdateDocumentRequest {
     pub icon: Option<String>,
 
     pub content: Option<serde_json::Value>,
+
+    /// Document version the caller last loaded; when given, the update is
+    /// rejected with 409 if the document has changed since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+}
+
+/// JSON Patch (RFC 6902) applied to the stored document content
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatchDocumentContentRequest {
+    /// Document version the patch was computed against
+    pub version: i64,
+    pub patch: Vec<json_patch::PatchOperation>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,6 +47,49 @@ pub struct ListDocumentsQuery {
     pub parent_id: Option<String>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Allow an estimated total for very large result sets
+    pub estimate_total: Option<bool>,
+    /// Comma-separated extras to include; `comments` adds comment counts,
+    /// `favorite` adds the caller's favorite flag
+    pub include: Option<String>,
+}
+
+fn includes(include: Option<&str>, extra: &str) -> bool {
+    include.is_some_and(|include| include.split(',').any(|part| part.trim() == extra))
+}
+
+impl ListDocumentsQuery {
+    pub fn includes_comments(&self) -> bool {
+        includes(self.include.as_deref(), "comments")
+    }
+
+    pub fn includes_favorite(&self) -> bool {
+        includes(self.include.as_deref(), "favorite")
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GetDocumentQuery {
+    /// Comma-separated extras to include; `favorite` adds the caller's favorite flag
+    pub include: Option<String>,
+}
+
+impl GetDocumentQuery {
+    pub fn includes_favorite(&self) -> bool {
+        includes(self.include.as_deref(), "favorite")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListFavoritesQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListSpaceAuditQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -47,17 +103,58 @@ pub struct CreateVersionRequest {
     pub change_summary: Option<String>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateVersionQuery {
+    /// Editor auto-save; coalesced with the caller's recent auto-save
+    /// version instead of always adding a new one
+    #[serde(default)]
+    pub auto_save: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListVersionsQuery {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BatchVersionMetaRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub version_numbers: Vec<i32>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RestoreVersionRequest {
     pub version_number: i32,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PinDocumentRequest {
+    /// Position among pinned documents; appended after existing pins when omitted
+    pub pin_order: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BulkArchiveRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub document_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateTemplateRequest {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+
+    #[serde(default)]
+    pub content: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    /// Parent for the new document; created at the space root when omitted
+    pub parent_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportQuery {
     pub format: Option<String>,
@@ -77,18 +174,57 @@ pub struct DocumentResponse {
     pub content: serde_json::Value,
     pub content_size: i32,
     pub is_archived: bool,
+    pub is_pinned: bool,
+    pub pin_order: Option<i32>,
     pub created_by: String,
     pub last_edited_by: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Content version, bumped on every content change
+    #[serde(default)]
+    pub version: i64,
+    /// Someone else changed the document since the caller last viewed it
+    #[serde(default)]
+    pub unread: bool,
+    /// Comment counts, only present when requested with `?include=comments`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_count: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unresolved_count: Option<i64>,
+    /// Whether the caller favorited the document, only present when requested
+    /// with `?include=favorite`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_favorite: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DocumentListResponse {
     pub documents: Vec<DocumentResponse>,
     pub total: i64,
+    /// True when `total` is a planner estimate rather than an exact count
+    #[serde(default)]
+    pub total_is_estimate: bool,
     pub limit: i32,
     pub offset: i32,
+    /// True when another page follows this one
+    pub has_more: bool,
+    /// Offset to request the next page with; null on the last page
+    pub next_offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateResponse {
+    pub id: String,
+    pub space_id: String,
+    pub title: String,
+    pub content: serde_json::Value,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateListResponse {
+    pub templates: Vec<TemplateResponse>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,6 +252,38 @@ pub struct VersionListResponse {
     pub total: i64,
     pub limit: i32,
     pub offset: i32,
+    /// True when another page follows this one
+    pub has_more: bool,
+    /// Offset to request the next page with; null on the last page
+    pub next_offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionMetaResponse {
+    pub version_number: i32,
+    pub title: String,
+    pub created_by: String,
+    pub created_at: String,
+    pub change_summary: Option<String>,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchVersionMetaResponse {
+    pub versions: Vec<VersionMetaResponse>,
+    pub missing: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkArchiveResponse {
+    pub archived: Vec<uuid::Uuid>,
+    pub failed: Vec<FailedArchive>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FailedArchive {
+    pub document_id: uuid::Uuid,
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -167,6 +335,9 @@ pub struct VersionDiffResponse {
 pub struct ApiErrorResponse {
     pub error: String,
     pub message: String,
+    /// Messages per invalid field, for `VALIDATION_ERROR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 // ============================================
@@ -196,6 +367,20 @@ impl<T> ApiResponse<T> {
             error: Some(ApiErrorResponse {
                 error: error_code.to_string(),
                 message: message.to_string(),
+                details: None,
+            }),
+        }
+    }
+
+    /// `VALIDATION_ERROR` listing the messages for each invalid field
+    pub fn validation_error(errors: &validator::ValidationErrors) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(ApiErrorResponse {
+                error: "VALIDATION_ERROR".to_string(),
+                message: "Validation failed".to_string(),
+                details: Some(shared_errors::validation_errors_to_json(errors)),
             }),
         }
     }
@@ -240,6 +425,50 @@ pub struct AddMemberRequest {
     pub role: String,
 }
 
+/// Request body for adding several members at once
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ImportMembersRequest {
+    #[validate(length(min = 1, max = 500), nested)]
+    pub members: Vec<AddMemberRequest>,
+}
+
+/// Request body for adding members with a result per entry
+///
+/// Entries aren't validated individually here: a bad entry is reported in
+/// its result rather than rejecting the request.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BulkAddMembersRequest {
+    #[validate(length(min = 1, max = 500))]
+    pub members: Vec<BulkMemberEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkMemberEntry {
+    pub user_id: String,
+    pub role: String,
+}
+
+/// Result of one bulk entry: `added`, `role_changed`, `unchanged` or `failed`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkMemberResult {
+    pub user_id: String,
+    pub role: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiErrorResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<MemberResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkAddMembersResponse {
+    pub results: Vec<BulkMemberResult>,
+    pub added: i32,
+    pub role_changed: i32,
+    pub unchanged: i32,
+    pub failed: i32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UpdateMemberRequest {
     #[validate(length(min = 1, max = 20))]
@@ -247,6 +476,33 @@ pub struct UpdateMemberRequest {
     pub role: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferOwnershipRequest {
+    /// Member who becomes the owner
+    pub new_owner_id: String,
+}
+
+/// What happens to a removed member's documents in the space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberContentPolicy {
+    /// Leave documents untouched
+    #[default]
+    Keep,
+    /// Transfer authorship to the space owner
+    Reassign,
+    /// Archive the documents; requires `confirm=true`
+    Archive,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RemoveMemberQuery {
+    #[serde(default)]
+    pub content_policy: MemberContentPolicy,
+    #[serde(default)]
+    pub confirm: bool,
+}
+
 // ============================================
 // Space Response Types
 // ============================================
@@ -284,6 +540,58 @@ pub struct MemberResponse {
 pub struct MemberListResponse {
     pub members: Vec<MemberResponse>,
     pub total: i32,
+    /// True when another page follows this one
+    pub has_more: bool,
+    /// Offset to request the next page with; null on the last page
+    pub next_offset: Option<i64>,
+}
+
+/// One membership change from a space's audit log
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceAuditEntryResponse {
+    pub id: String,
+    pub space_id: String,
+    /// Who made the change; null once that user is deleted
+    pub actor_id: Option<String>,
+    /// Whose membership changed; null once that user is deleted
+    pub target_user_id: Option<String>,
+    /// `member_added`, `member_removed` or `role_changed`
+    pub action: String,
+    pub old_role: Option<String>,
+    pub new_role: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceAuditListResponse {
+    pub entries: Vec<SpaceAuditEntryResponse>,
+    pub total: i64,
+    pub limit: i32,
+    pub offset: i32,
+    pub has_more: bool,
+    pub next_offset: Option<i64>,
+}
+
+/// Request body for inviting someone to a space by email
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct InviteMemberRequest {
+    #[validate(length(min = 3, max = 254))]
+    pub email: String,
+
+    #[validate(length(min = 1, max = 20))]
+    pub role: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvitationResponse {
+    pub id: String,
+    pub space_id: String,
+    pub email: String,
+    pub role: String,
+    pub token: String,
+    pub invited_by: Option<String>,
+    pub created_at: String,
+    pub expires_at: String,
 }
 
 // ============================================
@@ -304,6 +612,34 @@ pub struct UpdateCommentRequest {
     pub content: String,
 }
 
+/// Emoji that can be used as comment reactions
+pub const ALLOWED_REACTIONS: &[&str] = &["👍", "👎", "❤️", "🎉", "😄", "😕", "👀", "🚀"];
+
+fn validate_reaction_emoji(emoji: &str) -> Result<(), ValidationError> {
+    if ALLOWED_REACTIONS.contains(&emoji) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unsupported_reaction"))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReactionRequest {
+    #[validate(custom(function = "validate_reaction_emoji"))]
+    pub emoji: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddTagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentTagsResponse {
+    pub document_id: String,
+    pub tags: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListCommentsQuery {
     pub parent_id: Option<String>,
@@ -329,6 +665,32 @@ pub struct CommentResponse {
     pub resolved_at: Option<String>,
     pub created_at: String,
     pub updated_at: Option<String>,
+    /// Space members @mentioned in the content
+    #[serde(default)]
+    pub mentions: Vec<MentionResponse>,
+    #[serde(default)]
+    pub reactions: Vec<ReactionSummary>,
+}
+
+/// Reactions to a comment with one emoji
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionSummary {
+    pub emoji: String,
+    pub count: i64,
+    pub reacted_by_me: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentReactionsResponse {
+    pub comment_id: String,
+    pub reactions: Vec<ReactionSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MentionResponse {
+    pub user_id: String,
+    pub display_name: String,
+    pub username: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -346,6 +708,27 @@ pub struct CreateCommentResponse {
     pub comment: CommentResponse,
 }
 
+// ============================================
+// Notification Types
+// ============================================
+
+#[derive(Debug, Default, Serialize, Deserialize, Validate)]
+pub struct MarkNotificationsReadRequest {
+    /// Notifications to mark read; ignored when `all` is set
+    #[serde(default)]
+    #[validate(length(max = 500))]
+    pub ids: Vec<String>,
+
+    /// Mark every unread notification of the caller read
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarkNotificationsReadResponse {
+    pub updated: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,26 +766,72 @@ mod tests {
         assert!(request.validate().is_err());
     }
 
+    #[test]
+    fn test_title_too_long_reports_field_message() {
+        let request = CreateDocumentRequest {
+            title: "a".repeat(201),
+            icon: None,
+            parent_id: None,
+            content: None,
+        };
+        let errors = request.validate().unwrap_err();
+        assert_eq!(
+            shared_errors::validation_errors_to_json(&errors),
+            serde_json::json!({ "title": ["must have a length between 1 and 200"] })
+        );
+    }
+
     #[test]
     fn test_update_document_request_partial() {
         let request = UpdateDocumentRequest {
             title: Some("Updated Title".to_string()),
             icon: None,
             content: None,
+            version: None,
         };
         assert!(request.validate().is_ok());
     }
 
+    #[test]
+    fn test_update_document_request_version_is_optional() {
+        let request: UpdateDocumentRequest = serde_json::from_value(serde_json::json!({"title": "Renamed"})).unwrap();
+        assert_eq!(request.version, None);
+        assert!(serde_json::to_value(&request).unwrap().get("version").is_none());
+
+        let request: UpdateDocumentRequest =
+            serde_json::from_value(serde_json::json!({"title": "Renamed", "version": 4})).unwrap();
+        assert_eq!(request.version, Some(4));
+    }
+
     #[test]
     fn test_list_documents_query_defaults() {
         let query = ListDocumentsQuery {
             parent_id: None,
             limit: None,
             offset: None,
+            estimate_total: None,
+            include: None,
         };
         assert!(query.parent_id.is_none());
         assert!(query.limit.is_none());
         assert!(query.offset.is_none());
+        assert!(!query.includes_comments());
+    }
+
+    #[test]
+    fn test_list_documents_query_includes_comments() {
+        let query = |include: &str| ListDocumentsQuery {
+            parent_id: None,
+            limit: None,
+            offset: None,
+            estimate_total: None,
+            include: Some(include.to_string()),
+        };
+        assert!(query("comments").includes_comments());
+        assert!(query("authors, comments").includes_comments());
+        assert!(!query("comment").includes_comments());
+        assert!(query("comments,favorite").includes_favorite());
+        assert!(!query("comments").includes_favorite());
     }
 
     #[test]
@@ -436,10 +865,17 @@ mod tests {
             content: serde_json::json!({"text": "content"}),
             content_size: 100,
             is_archived: false,
+            is_pinned: false,
+            pin_order: None,
             created_by: "user-789".to_string(),
             last_edited_by: "user-789".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: "2024-01-01T00:00:00Z".to_string(),
+            version: 1,
+            unread: false,
+            comment_count: None,
+            unresolved_count: None,
+            is_favorite: None,
         };
         assert_eq!(response.id, "doc-123");
         assert!(response.icon.is_some());
@@ -451,8 +887,11 @@ mod tests {
         let response = DocumentListResponse {
             documents: vec![],
             total: 0,
+            total_is_estimate: false,
             limit: 50,
             offset: 0,
+            has_more: false,
+            next_offset: None,
         };
         assert!(response.documents.is_empty());
         assert_eq!(response.total, 0);
@@ -547,6 +986,23 @@ mod tests {
         assert!(request.validate().is_err());
     }
 
+    #[test]
+    fn test_reaction_request_allowlist() {
+        for emoji in ALLOWED_REACTIONS {
+            let request = ReactionRequest {
+                emoji: emoji.to_string(),
+            };
+            assert!(request.validate().is_ok(), "{} should be allowed", emoji);
+        }
+
+        for emoji in ["", "🍕", "+1", "👍👍"] {
+            let request = ReactionRequest {
+                emoji: emoji.to_string(),
+            };
+            assert!(request.validate().is_err(), "{:?} should be rejected", emoji);
+        }
+    }
+
     #[test]
     fn test_comment_response_creation() {
         let response = CommentResponse {
@@ -562,6 +1018,8 @@ mod tests {
             resolved_at: None,
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: Some("2024-01-01T00:00:00Z".to_string()),
+            mentions: Vec::new(),
+            reactions: Vec::new(),
         };
         assert_eq!(response.id, "comment-123");
         assert!(!response.is_resolved);
@@ -666,4 +1124,16 @@ mod tests {
         };
         assert_eq!(query.format, Some("markdown".to_string()));
     }
+
+    #[test]
+    fn test_remove_member_query_defaults_to_keep() {
+        let query: RemoveMemberQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.content_policy, MemberContentPolicy::Keep);
+        assert!(!query.confirm);
+
+        let query: RemoveMemberQuery =
+            serde_json::from_str(r#"{"content_policy": "archive", "confirm": true}"#).unwrap();
+        assert_eq!(query.content_policy, MemberContentPolicy::Archive);
+        assert!(query.confirm);
+    }
 }
@@ -0,0 +1,70 @@
+//! Notification Handlers
+//!
+//! Provides HTTP handlers for notification operations:
+//! - POST /notifications/read - Mark notifications read in bulk
+//!
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use shared_errors::ErrorCode;
+use tracing::error;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::handlers::extract_user_id;
+use crate::models::*;
+use crate::repository::DocumentRepository;
+
+/// POST /notifications/read
+///
+/// Marks the given notifications, or with `{ "all": true }` every unread
+/// notification, read for the caller in a single statement. IDs that belong
+/// to other users or are already read are skipped, so `updated` counts only
+/// the notifications that changed.
+pub async fn mark_notifications_read(
+    repo: web::Data<DocumentRepository>,
+    req: web::Json<MarkNotificationsReadRequest>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(ref e) => {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(
+                ErrorCode::from(e).to_string().as_str(),
+                e.to_string().as_str(),
+            ))
+        },
+    };
+
+    let req = req.into_inner();
+    if let Err(e) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&e));
+    }
+
+    let ids = if req.all {
+        None
+    } else {
+        if req.ids.is_empty() {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                "VALIDATION_ERROR",
+                "Provide notification ids or set all to true",
+            ));
+        }
+        match req.ids.iter().map(|id| Uuid::parse_str(id)).collect::<Result<Vec<_>, _>>() {
+            Ok(ids) => Some(ids),
+            Err(_) => {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error("VALIDATION_ERROR", "Invalid notification id"));
+            },
+        }
+    };
+
+    match repo.mark_notifications_read(user_id, ids.as_deref()).await {
+        Ok(updated) => HttpResponse::Ok().json(ApiResponse::success(MarkNotificationsReadResponse { updated })),
+        Err(e) => {
+            error!("Database error marking notifications read: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "Failed to mark notifications read",
+            ))
+        },
+    }
+}
@@ -1,5 +1,15 @@
+use crate::count_cache::{CountCache, CountKey, PageTotal, COUNT_ESTIMATE_THRESHOLD};
+use crate::encryption::{is_encrypted, ContentEncryption};
+use crate::models::MemberContentPolicy;
+use crate::tags::{normalize_tags, TagError, TagLimits};
+use crate::validation::{validate_patched_content, DocumentValidationError};
+use auth_service::rbac::roles::{PermissionSet, Role};
 use chrono::NaiveDateTime;
+use shared_database::soft_delete::{self, SoftDeletable};
+use shared_security::{generate_url_safe_token, validate_email};
 use sqlx::{FromRow, PgPool};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, FromRow)]
@@ -23,6 +33,26 @@ pub struct DocumentRow {
     pub vector_clock: Option<serde_json::Value>,
     pub client_id: Option<Uuid>,
     pub sync_state: Option<String>,
+    // Pinning within the space listing
+    pub is_pinned: bool,
+    pub pin_order: Option<i32>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct DocumentTemplateRow {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub title: String,
+    pub content: sqlx::types::Json<serde_json::Value>,
+    pub created_by: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+// Archiving is the documents' soft delete
+impl SoftDeletable for DocumentRow {
+    const TABLE: &'static str = "documents";
+    const DELETED_FLAG: &'static str = "is_archived";
+    const DELETED_AT: &'static str = "archived_at";
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -35,6 +65,19 @@ pub struct DocumentVersionRow {
     pub created_by: Uuid,
     pub created_at: NaiveDateTime,
     pub change_summary: Option<String>,
+    pub is_pinned: bool,
+    pub is_auto_save: bool,
+}
+
+/// Version metadata without the content body, used for timeline views
+#[derive(Debug, Clone, FromRow)]
+pub struct VersionMetaRow {
+    pub version_number: i32,
+    pub title: String,
+    pub created_by: Uuid,
+    pub created_at: NaiveDateTime,
+    pub change_summary: Option<String>,
+    pub is_pinned: bool,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -50,6 +93,12 @@ pub struct SpaceRow {
     pub user_role: Option<String>,
 }
 
+impl SoftDeletable for SpaceRow {
+    const TABLE: &'static str = "spaces";
+    const DELETED_FLAG: &'static str = "is_deleted";
+    const DELETED_AT: &'static str = "deleted_at";
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub struct SpaceMembershipRow {
     pub id: Uuid,
@@ -88,14 +137,445 @@ pub struct CommentRow {
     pub updated_at: NaiveDateTime,
 }
 
-#[derive(Debug, Clone)]
+/// A space member who can be @mentioned; `username` is their email's local part
+#[derive(Debug, Clone, FromRow)]
+pub struct MentionableUserRow {
+    pub id: Uuid,
+    pub display_name: String,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CommentMentionRow {
+    pub comment_id: Uuid,
+    pub user_id: Uuid,
+    pub display_name: String,
+    pub username: String,
+}
+
+/// Reactions to a comment with one emoji, from the point of view of one user
+#[derive(Debug, Clone, FromRow)]
+pub struct CommentReactionRow {
+    pub comment_id: Uuid,
+    pub emoji: String,
+    pub count: i64,
+    pub reacted_by_me: bool,
+}
+
+#[derive(Debug, Clone, FromRow, serde::Serialize)]
+pub struct NotificationRow {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+// The member cap is shared with space_service's add-member endpoint
+pub use space_service::repository::{max_space_members_from_env, DEFAULT_MAX_SPACE_MEMBERS};
+
+/// Default window in which a user's auto-save versions of a document coalesce
+pub const DEFAULT_VERSION_THROTTLE_SECS: u64 = 60;
+
+/// Read the auto-save coalescing window from `DOCUMENT_VERSION_THROTTLE_SECS`;
+/// 0 disables coalescing
+pub fn version_throttle_from_env() -> std::time::Duration {
+    let secs = std::env::var("DOCUMENT_VERSION_THROTTLE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_VERSION_THROTTLE_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Errors from applying a JSON Patch to document content
+#[derive(Debug, thiserror::Error)]
+pub enum PatchContentError {
+    #[error("Document not found or archived")]
+    NotFound,
+
+    #[error("Document is at version {current}, patch was made against {expected}")]
+    VersionConflict { expected: i64, current: i64 },
+
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(#[from] json_patch::PatchError),
+
+    #[error(transparent)]
+    InvalidContent(#[from] DocumentValidationError),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Errors from a version-checked document update
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateDocumentError {
+    #[error("Document not found or archived")]
+    NotFound,
+
+    #[error("Document is at version {current}, update was made against {expected}")]
+    VersionConflict { expected: i64, current: i64 },
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Apply a partial update to a non-archived document, bumping the version
+/// when the content changes
+async fn update_document_row<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    document_id: Uuid,
+    title: Option<&str>,
+    icon: Option<&str>,
+    content: Option<serde_json::Value>,
+    editor_uuid: Uuid,
+) -> Result<Option<DocumentRow>, sqlx::Error> {
+    sqlx::query_as!(
+        DocumentRow,
+        r#"
+        UPDATE documents
+        SET
+            title = COALESCE($2, title),
+            icon = COALESCE($3, icon),
+            content = COALESCE($4, content),
+            content_size = COALESCE(octet_length($4::text), content_size),
+            version = CASE WHEN $4::jsonb IS NULL THEN version ELSE version + 1 END,
+            last_edited_by = $5,
+            updated_at = NOW()
+        WHERE id = $1 AND is_archived = false
+        RETURNING *
+        "#,
+        document_id,
+        title,
+        icon,
+        content,
+        editor_uuid
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Errors from adding members to a space
+#[derive(Debug, thiserror::Error)]
+pub enum AddMemberError {
+    #[error("Space member limit of {limit} reached ({remaining} slots left)")]
+    LimitReached { limit: usize, remaining: usize },
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Why one entry of a bulk member add was skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum BulkMemberFailure {
+    #[error("User id is not a valid UUID")]
+    InvalidUserId,
+
+    #[error("User appears earlier in the batch")]
+    DuplicateEntry,
+
+    #[error("User not found")]
+    UserNotFound,
+
+    #[error("Role is not defined in this space")]
+    InvalidRole,
+
+    #[error("Space member limit reached")]
+    LimitReached,
+
+    #[error("The space owner's membership cannot be changed")]
+    SpaceOwner,
+}
+
+impl BulkMemberFailure {
+    /// Error code reported for the entry
+    pub fn code(&self) -> &'static str {
+        match self {
+            BulkMemberFailure::InvalidUserId => "INVALID_USER_ID",
+            BulkMemberFailure::DuplicateEntry => "DUPLICATE_ENTRY",
+            BulkMemberFailure::UserNotFound => "USER_NOT_FOUND",
+            BulkMemberFailure::InvalidRole => "INVALID_ROLE",
+            BulkMemberFailure::LimitReached => "MEMBER_LIMIT_REACHED",
+            BulkMemberFailure::SpaceOwner => "OWNER_MEMBERSHIP",
+        }
+    }
+}
+
+/// What happened to one entry of a bulk member add
+#[derive(Debug)]
+pub enum BulkMemberOutcome {
+    Added(SpaceMembershipRow),
+    RoleChanged(SpaceMembershipRow),
+    /// Already a member with this role
+    Unchanged,
+    Failed(BulkMemberFailure),
+}
+
+/// Errors from handing a space to a new owner
+#[derive(Debug, thiserror::Error)]
+pub enum TransferOwnershipError {
+    #[error("Space not found")]
+    SpaceNotFound,
+
+    #[error("Only the current owner can transfer the space")]
+    NotOwner,
+
+    #[error("The new owner must already be a member of the space")]
+    NotMember,
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Errors from inviting someone by email or redeeming an invitation
+#[derive(Debug, thiserror::Error)]
+pub enum InvitationError {
+    #[error("Invalid email address: {0}")]
+    InvalidEmail(String),
+
+    #[error("Invitation not found or already used")]
+    NotFound,
+
+    #[error("Invitation has expired")]
+    Expired,
+
+    #[error("Invitation was sent to a different email address")]
+    EmailMismatch,
+
+    #[error("Only the space owner can grant the owner role")]
+    OwnerRoleNotAllowed,
+
+    #[error("The space owner's membership cannot be changed")]
+    SpaceOwner,
+
+    #[error(transparent)]
+    AddMember(#[from] AddMemberError),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Length of the token that redeems an invitation
+pub const INVITATION_TOKEN_LENGTH: usize = 32;
+
+/// Days an invitation stays redeemable
+pub const INVITATION_TTL_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SpaceInvitationRow {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub token: String,
+    pub invited_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub accepted_by: Option<Uuid>,
+}
+
+/// Built-in role of the space owner. Only the owner may grant it.
+pub const OWNER_ROLE: &str = "owner";
+
+/// Role the previous owner keeps after transferring a space. There is no
+/// built-in admin role, so they become an editor, the highest one below owner.
+pub const PREVIOUS_OWNER_ROLE: &str = "editor";
+
+/// Membership change recorded in a space's audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceAuditAction {
+    MemberAdded,
+    MemberRemoved,
+    RoleChanged,
+}
+
+impl SpaceAuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MemberAdded => "member_added",
+            Self::MemberRemoved => "member_removed",
+            Self::RoleChanged => "role_changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SpaceAuditRow {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub target_user_id: Option<Uuid>,
+    pub action: String,
+    pub old_role: Option<String>,
+    pub new_role: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Record a membership change, on the executor (usually the transaction)
+/// that made it
+async fn record_space_audit<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    space_id: Uuid,
+    actor_id: Uuid,
+    target_user_id: Uuid,
+    action: SpaceAuditAction,
+    old_role: Option<&str>,
+    new_role: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO space_audit_log (space_id, actor_id, target_user_id, action, old_role, new_role)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(space_id)
+    .bind(actor_id)
+    .bind(target_user_id)
+    .bind(action.as_str())
+    .bind(old_role)
+    .bind(new_role)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Clone)]
 pub struct DocumentRepository {
     pool: PgPool,
+    count_cache: Arc<CountCache>,
+    encryption: Option<Arc<ContentEncryption>>,
+    max_space_members: usize,
+    version_throttle: std::time::Duration,
+    tag_limits: TagLimits,
+}
+
+impl std::fmt::Debug for DocumentRepository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentRepository")
+            .field("pool", &self.pool)
+            .field("count_cache", &self.count_cache)
+            .field("encryption", &self.encryption.is_some())
+            .field("max_space_members", &self.max_space_members)
+            .field("version_throttle", &self.version_throttle)
+            .field("tag_limits", &self.tag_limits)
+            .finish()
+    }
 }
 
 impl DocumentRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self::with_count_cache(pool, Arc::new(CountCache::from_env()))
+    }
+
+    pub fn with_count_cache(pool: PgPool, count_cache: Arc<CountCache>) -> Self {
+        let encryption = ContentEncryption::from_env().unwrap_or_else(|e| {
+            // Writes to encrypted spaces fail rather than fall back to plaintext
+            tracing::error!("Document content encryption disabled: {}", e);
+            None
+        });
+
+        Self {
+            pool,
+            count_cache,
+            encryption: encryption.map(Arc::new),
+            max_space_members: max_space_members_from_env(),
+            version_throttle: version_throttle_from_env(),
+            tag_limits: TagLimits::from_env(),
+        }
+    }
+
+    /// Replace the content encryption used for encrypted spaces
+    pub fn with_encryption(mut self, encryption: Option<Arc<ContentEncryption>>) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Replace the space member cap; 0 disables it
+    pub fn with_max_space_members(mut self, max_space_members: usize) -> Self {
+        self.max_space_members = max_space_members;
+        self
+    }
+
+    pub fn max_space_members(&self) -> usize {
+        self.max_space_members
+    }
+
+    /// Replace the auto-save coalescing window; zero disables it
+    pub fn with_version_throttle(mut self, version_throttle: std::time::Duration) -> Self {
+        self.version_throttle = version_throttle;
+        self
+    }
+
+    /// Replace the tag length and count caps
+    pub fn with_tag_limits(mut self, tag_limits: TagLimits) -> Self {
+        self.tag_limits = tag_limits;
+        self
+    }
+
+    pub fn tag_limits(&self) -> TagLimits {
+        self.tag_limits
+    }
+
+    pub fn count_cache(&self) -> &CountCache {
+        &self.count_cache
+    }
+
+    async fn is_space_encrypted(&self, space_uuid: &Uuid) -> Result<bool, sqlx::Error> {
+        let encrypted = sqlx::query_scalar!(r#"SELECT content_encrypted FROM spaces WHERE id = $1"#, space_uuid)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(encrypted.unwrap_or(false))
+    }
+
+    async fn document_space_id(&self, doc_uuid: &Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar!(r#"SELECT space_id FROM documents WHERE id = $1"#, doc_uuid)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    // Encrypt content before it is written when the space requires it
+    async fn seal_content(
+        &self,
+        space_uuid: &Uuid,
+        content: serde_json::Value,
+    ) -> Result<serde_json::Value, sqlx::Error> {
+        if !self.is_space_encrypted(space_uuid).await? {
+            return Ok(content);
+        }
+
+        let encryption = self.encryption.as_ref().ok_or_else(|| {
+            sqlx::Error::Configuration("space requires content encryption but no key is configured".into())
+        })?;
+        encryption
+            .encrypt(space_uuid, &content)
+            .map_err(|e| sqlx::Error::Protocol(e.to_string()))
+    }
+
+    /// Decrypt stored content; plaintext content is returned unchanged
+    pub fn decrypt_content(&self, content: serde_json::Value) -> Result<serde_json::Value, sqlx::Error> {
+        if !is_encrypted(&content) {
+            return Ok(content);
+        }
+
+        match &self.encryption {
+            Some(encryption) => encryption.decrypt(content).map_err(|e| sqlx::Error::Decode(Box::new(e))),
+            None => Err(sqlx::Error::Decode(
+                "content is encrypted but no key is configured".into(),
+            )),
+        }
+    }
+
+    fn open_document(&self, mut row: DocumentRow) -> Result<DocumentRow, sqlx::Error> {
+        row.content = sqlx::types::Json(self.decrypt_content(row.content.0)?);
+        Ok(row)
+    }
+
+    fn open_version(&self, mut row: DocumentVersionRow) -> Result<DocumentVersionRow, sqlx::Error> {
+        row.content = sqlx::types::Json(self.decrypt_content(row.content.0)?);
+        Ok(row)
     }
 
     pub async fn create(
@@ -105,16 +585,15 @@ impl DocumentRepository {
         title: &str,
         icon: Option<&str>,
         content: Option<serde_json::Value>,
-        created_by: &str,
+        created_by: Uuid,
     ) -> Result<DocumentRow, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
         let parent_uuid = match parent_id {
             Some(id) => Some(Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?),
             None => None,
         };
-        let created_by_uuid = Uuid::parse_str(created_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
         let content_value = content.unwrap_or_else(|| serde_json::json!({}));
-        let content_size = content_value.to_string().len() as i32;
+        let content_value = self.seal_content(&space_uuid, content_value).await?;
 
         let document = sqlx::query_as!(
             DocumentRow,
@@ -124,7 +603,7 @@ impl DocumentRepository {
                 content_size, is_archived, created_by, last_edited_by
             ) VALUES (
                 gen_random_uuid(), $1, $2, $3, $4, $5,
-                $6, false, $7, $7
+                octet_length($5::jsonb::text), false, $6, $6
             )
             RETURNING *
             "#,
@@ -133,13 +612,14 @@ impl DocumentRepository {
             title,
             icon,
             content_value,
-            content_size,
-            created_by_uuid
+            created_by
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(document)
+        self.count_cache.invalidate_space(&document.space_id);
+
+        self.open_document(document)
     }
 
     pub async fn get_by_id(&self, id: &str) -> Result<Option<DocumentRow>, sqlx::Error> {
@@ -149,7 +629,90 @@ impl DocumentRepository {
             .fetch_optional(&self.pool)
             .await?;
 
-        Ok(document)
+        document.map(|row| self.open_document(row)).transpose()
+    }
+
+    /// Record that the user viewed the document now, returning when they
+    /// previously viewed it
+    pub async fn record_view(&self, document_id: &str, user_id: Uuid) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+        let document_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let previous = sqlx::query_scalar!(
+            r#"
+            WITH previous AS (
+                SELECT last_viewed_at FROM document_views WHERE user_id = $1 AND document_id = $2
+            )
+            INSERT INTO document_views (user_id, document_id, last_viewed_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (user_id, document_id) DO UPDATE SET last_viewed_at = NOW()
+            RETURNING (SELECT last_viewed_at FROM previous) AS "previous?"
+            "#,
+            user_id,
+            document_uuid
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(previous)
+    }
+
+    /// Which of `document_ids` changed since the user last viewed them.
+    /// Never-viewed documents are unread; the user's own edits are not.
+    pub async fn unread_document_ids(
+        &self,
+        user_id: Uuid,
+        document_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, sqlx::Error> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT d.id
+            FROM documents d
+            LEFT JOIN document_views v ON v.document_id = d.id AND v.user_id = $1
+            WHERE d.id = ANY($2)
+              AND d.last_edited_by <> $1
+              AND (v.last_viewed_at IS NULL OR d.updated_at > v.last_viewed_at)
+            "#,
+            user_id,
+            document_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Total and unresolved comment counts for those of `document_ids` in the space.
+    /// Documents without comments are absent from the map.
+    pub async fn comment_counts(
+        &self,
+        space_id: &str,
+        document_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, (i64, i64)>, sqlx::Error> {
+        if document_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.document_id,
+                   COUNT(*) AS "total!",
+                   COUNT(*) FILTER (WHERE NOT c.is_resolved) AS "unresolved!"
+            FROM comments c
+            JOIN documents d ON d.id = c.document_id
+            WHERE d.space_id = $1 AND c.document_id = ANY($2)
+            GROUP BY c.document_id
+            "#,
+            space_uuid,
+            document_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.document_id, (row.total, row.unresolved)))
+            .collect())
     }
 
     pub async fn update(
@@ -158,52 +721,216 @@ impl DocumentRepository {
         title: Option<&str>,
         icon: Option<&str>,
         content: Option<serde_json::Value>,
-        last_edited_by: &str,
+        last_edited_by: Uuid,
     ) -> Result<Option<DocumentRow>, sqlx::Error> {
         let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let editor_uuid = Uuid::parse_str(last_edited_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let content = match (content, self.document_space_id(&document_id).await?) {
+            (Some(content), Some(space_uuid)) => Some(self.seal_content(&space_uuid, content).await?),
+            (content, _) => content,
+        };
+
+        let document = update_document_row(&self.pool, document_id, title, icon, content, last_edited_by).await?;
+
+        document.map(|row| self.open_document(row)).transpose()
+    }
+
+    /// Update a document only if it is still at `expected_version`
+    ///
+    /// The row is locked between the version check and the update, so two
+    /// clients editing from the same version can't both succeed.
+    pub async fn update_at_version(
+        &self,
+        id: &str,
+        expected_version: i64,
+        title: Option<&str>,
+        icon: Option<&str>,
+        content: Option<serde_json::Value>,
+        last_edited_by: Uuid,
+    ) -> Result<DocumentRow, UpdateDocumentError> {
+        let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let (space_id, current): (Uuid, i64) = sqlx::query_as(
+            "SELECT space_id, version::BIGINT FROM documents WHERE id = $1 AND is_archived = false FOR UPDATE",
+        )
+        .bind(document_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(UpdateDocumentError::NotFound)?;
+
+        if current != expected_version {
+            return Err(UpdateDocumentError::VersionConflict {
+                expected: expected_version,
+                current,
+            });
+        }
+
+        let content = match content {
+            Some(content) => Some(self.seal_content(&space_id, content).await?),
+            None => None,
+        };
+        let document = update_document_row(&mut *tx, document_id, title, icon, content, last_edited_by)
+            .await?
+            .ok_or(UpdateDocumentError::NotFound)?;
+
+        tx.commit().await?;
+
+        Ok(self.open_document(document)?)
+    }
+
+    /// Apply a JSON Patch to the stored content
+    ///
+    /// The patch must have been computed against `expected_version`; the row
+    /// is locked while patching so concurrent patches cannot both apply to
+    /// the same version. The patch applies atomically: a failing operation
+    /// leaves the content untouched.
+    pub async fn patch_content(
+        &self,
+        id: &str,
+        expected_version: i64,
+        patch: &[json_patch::PatchOperation],
+        last_edited_by: Uuid,
+    ) -> Result<DocumentRow, PatchContentError> {
+        let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let current = sqlx::query!(
+            r#"
+            SELECT space_id, content, version::BIGINT as "version!"
+            FROM documents
+            WHERE id = $1 AND is_archived = false
+            FOR UPDATE
+            "#,
+            document_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(PatchContentError::NotFound)?;
+
+        if current.version != expected_version {
+            return Err(PatchContentError::VersionConflict {
+                expected: expected_version,
+                current: current.version,
+            });
+        }
+
+        let mut content = self.decrypt_content(current.content)?;
+        json_patch::patch(&mut content, patch)?;
+        validate_patched_content(&content)?;
+        let content = self.seal_content(&current.space_id, content).await?;
 
         let document = sqlx::query_as!(
             DocumentRow,
             r#"
             UPDATE documents
             SET
-                title = COALESCE($2, title),
-                icon = COALESCE($3, icon),
-                content = COALESCE($4, content),
-                content_size = COALESCE(length($4::text), content_size),
-                last_edited_by = $5,
+                content = $2,
+                content_size = octet_length($2::text),
+                version = version + 1,
+                last_edited_by = $3,
                 updated_at = NOW()
-            WHERE id = $1 AND is_archived = false
+            WHERE id = $1
             RETURNING *
             "#,
             document_id,
-            title,
-            icon,
             content,
-            editor_uuid
+            last_edited_by
         )
-        .fetch_optional(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(document)
+        tx.commit().await?;
+
+        Ok(self.open_document(document)?)
     }
 
     pub async fn delete(&self, id: &str) -> Result<bool, sqlx::Error> {
         let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        self.archive(document_id).await
+    }
 
-        let result = sqlx::query!(
-            r#"
-            UPDATE documents
-            SET is_archived = true, archived_at = NOW()
-            WHERE id = $1 AND is_archived = false
-            "#,
-            document_id
-        )
-        .execute(&self.pool)
-        .await?;
-
-        Ok(result.rows_affected() > 0)
+    async fn archive(&self, document_id: Uuid) -> Result<bool, sqlx::Error> {
+        let Some(space_id) = self.document_space_id(&document_id).await? else {
+            return Ok(false);
+        };
+        let archived = soft_delete::soft_delete::<DocumentRow>(&self.pool, document_id).await?;
+        if archived {
+            self.count_cache.invalidate_space(&space_id);
+        }
+        Ok(archived)
+    }
+
+    /// Archive several documents, one at a time
+    ///
+    /// Returns the ids that were archived and, for the rest, a reason code:
+    /// `DOC_NOT_FOUND` or `DATABASE_ERROR`. A failure on one document does not
+    /// stop the batch.
+    pub async fn bulk_archive(&self, document_ids: &[Uuid]) -> (Vec<Uuid>, Vec<(Uuid, &'static str)>) {
+        let mut archived = Vec::new();
+        let mut failed = Vec::new();
+
+        for document_id in document_ids {
+            match self.archive(*document_id).await {
+                Ok(true) => archived.push(*document_id),
+                Ok(false) => failed.push((*document_id, "DOC_NOT_FOUND")),
+                Err(e) => {
+                    tracing::error!("Database error archiving document {}: {:?}", document_id, e);
+                    failed.push((*document_id, "DATABASE_ERROR"));
+                },
+            }
+        }
+
+        (archived, failed)
+    }
+
+    /// Pin a document to the top of its space listing
+    ///
+    /// Without an explicit `pin_order` the document goes after the space's
+    /// existing pins.
+    pub async fn pin(&self, id: &str, pin_order: Option<i32>) -> Result<Option<DocumentRow>, sqlx::Error> {
+        let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let document = sqlx::query_as!(
+            DocumentRow,
+            r#"
+            UPDATE documents d
+            SET is_pinned = true,
+                pin_order = COALESCE($2, (
+                    SELECT COALESCE(MAX(p.pin_order) + 1, 0) FROM documents p
+                    WHERE p.space_id = d.space_id AND p.is_pinned = true AND p.id <> d.id
+                ))
+            WHERE d.id = $1 AND d.is_archived = false
+            RETURNING d.*
+            "#,
+            document_id,
+            pin_order
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        document.map(|row| self.open_document(row)).transpose()
+    }
+
+    pub async fn unpin(&self, id: &str) -> Result<Option<DocumentRow>, sqlx::Error> {
+        let document_id = Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let document = sqlx::query_as!(
+            DocumentRow,
+            r#"
+            UPDATE documents
+            SET is_pinned = false, pin_order = NULL
+            WHERE id = $1 AND is_archived = false
+            RETURNING *
+            "#,
+            document_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        document.map(|row| self.open_document(row)).transpose()
     }
 
     pub async fn list_in_space(
@@ -212,7 +939,8 @@ impl DocumentRepository {
         parent_id: Option<&str>,
         limit: i32,
         offset: i32,
-    ) -> Result<(Vec<DocumentRow>, i64), sqlx::Error> {
+        allow_estimate: bool,
+    ) -> Result<(Vec<DocumentRow>, PageTotal), sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let documents = match parent_id {
@@ -225,7 +953,7 @@ impl DocumentRepository {
                     SELECT * FROM documents
                     WHERE space_id = $1 AND is_archived = false
                     AND parent_id = $2
-                    ORDER BY created_at DESC
+                    ORDER BY is_pinned DESC, pin_order ASC NULLS LAST, created_at DESC
                     LIMIT $3 OFFSET $4
                     "#,
                     space_uuid,
@@ -243,7 +971,7 @@ impl DocumentRepository {
                     SELECT * FROM documents
                     WHERE space_id = $1 AND is_archived = false
                     AND parent_id IS NULL
-                    ORDER BY created_at DESC
+                    ORDER BY is_pinned DESC, pin_order ASC NULLS LAST, created_at DESC
                     LIMIT $2 OFFSET $3
                     "#,
                     space_uuid,
@@ -255,10 +983,60 @@ impl DocumentRepository {
             },
         };
 
-        let total = match parent_id {
-            Some(parent_id_str) => {
-                let parent_uuid =
-                    Uuid::parse_str(parent_id_str).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let parent_uuid = match parent_id {
+            Some(id) => Some(Uuid::parse_str(id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?),
+            None => None,
+        };
+        let total = self.count_in_space(space_uuid, parent_uuid, allow_estimate).await?;
+        let documents = documents
+            .into_iter()
+            .map(|row| self.open_document(row))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((documents, total))
+    }
+
+    /// Total for a space listing, served from the count cache when possible
+    ///
+    /// With `allow_estimate`, a planner estimate is returned instead of an
+    /// exact count once it reaches [`COUNT_ESTIMATE_THRESHOLD`] rows.
+    async fn count_in_space(
+        &self,
+        space_uuid: Uuid,
+        parent_uuid: Option<Uuid>,
+        allow_estimate: bool,
+    ) -> Result<PageTotal, sqlx::Error> {
+        let key = CountKey::new(
+            space_uuid,
+            parent_uuid.map_or_else(|| "all".to_string(), |id| format!("parent:{}", id)),
+        );
+        if let Some(count) = self.count_cache.get(&key) {
+            return Ok(PageTotal::exact(count));
+        }
+
+        if allow_estimate {
+            // Uuids are formatted by us, so inlining them into the estimate query is safe
+            let query = match parent_uuid {
+                Some(parent_uuid) => format!(
+                    "SELECT 1 FROM documents WHERE space_id = '{}' AND is_archived = false AND parent_id = '{}'",
+                    space_uuid, parent_uuid
+                ),
+                None => format!(
+                    "SELECT 1 FROM documents WHERE space_id = '{}' AND is_archived = false",
+                    space_uuid
+                ),
+            };
+            let estimate = sqlx::query!(r#"SELECT count_estimate($1) as "estimate!""#, query)
+                .fetch_one(&self.pool)
+                .await?
+                .estimate;
+            if estimate >= COUNT_ESTIMATE_THRESHOLD {
+                return Ok(PageTotal::estimate(estimate));
+            }
+        }
+
+        let count = match parent_uuid {
+            Some(parent_uuid) => {
                 sqlx::query!(
                     r#"
                     SELECT COUNT(*) as "count!" FROM documents
@@ -286,7 +1064,26 @@ impl DocumentRepository {
             },
         };
 
-        Ok((documents, total as i64))
+        self.count_cache.insert(key, count);
+        Ok(PageTotal::exact(count))
+    }
+
+    /// Id, parent and title of every non-archived document in a space
+    ///
+    /// Oldest first, so parents normally come before their children.
+    pub async fn list_space_outline(&self, space_id: &str) -> Result<Vec<(Uuid, Option<Uuid>, String)>, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        sqlx::query_as::<_, (Uuid, Option<Uuid>, String)>(
+            r#"
+            SELECT id, parent_id, title FROM documents
+            WHERE space_id = $1 AND is_archived = false
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .bind(space_uuid)
+        .fetch_all(&self.pool)
+        .await
     }
 
     pub async fn get_children(&self, parent_id: &str) -> Result<(Vec<DocumentRow>, i64), sqlx::Error> {
@@ -305,6 +1102,10 @@ impl DocumentRepository {
         .await?;
 
         let total = documents.len() as i64;
+        let documents = documents
+            .into_iter()
+            .map(|row| self.open_document(row))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok((documents, total))
     }
@@ -336,22 +1137,26 @@ impl DocumentRepository {
         document_id: &str,
         content: serde_json::Value,
         title: &str,
-        created_by: &str,
+        created_by: Uuid,
         change_summary: Option<&str>,
     ) -> Result<DocumentVersionRow, sqlx::Error> {
         let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let creator_uuid = Uuid::parse_str(created_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         // Convert change_summary to String for sqlx compatibility
         let summary_str = change_summary.map(|s| s.to_string()).unwrap_or_default();
 
+        let content = match self.document_space_id(&doc_uuid).await? {
+            Some(space_uuid) => self.seal_content(&space_uuid, content).await?,
+            None => content,
+        };
+
         // Call the SQL function for version creation
         let version_id = sqlx::query_scalar!(
             r#"SELECT create_document_version($1, $2, $3, $4, $5) as id"#,
             doc_uuid,
             content,
             title,
-            creator_uuid,
+            created_by,
             summary_str
         )
         .fetch_one(&self.pool)
@@ -366,7 +1171,102 @@ impl DocumentRepository {
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(version)
+        self.open_version(version)
+    }
+
+    /// Create an auto-save version, coalescing rapid auto-saves
+    ///
+    /// When the document's latest version is an unpinned auto-save by the
+    /// same user created within the throttle window, it is overwritten with
+    /// the new content instead of adding another version. The window is
+    /// anchored at that version's creation, so continuous editing still
+    /// produces a version per window. The document row is locked for the
+    /// check and the write, so concurrent auto-saves can't both decide to add
+    /// a version or overwrite one that is no longer the latest. Returns the
+    /// version and whether it was coalesced.
+    pub async fn create_auto_save_version(
+        &self,
+        document_id: &str,
+        content: serde_json::Value,
+        title: &str,
+        created_by: Uuid,
+        change_summary: Option<&str>,
+    ) -> Result<(DocumentVersionRow, bool), sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let content = match self.document_space_id(&doc_uuid).await? {
+            Some(space_uuid) => self.seal_content(&space_uuid, content).await?,
+            None => content,
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(r#"SELECT id FROM documents WHERE id = $1 FOR UPDATE"#, doc_uuid)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if !self.version_throttle.is_zero() {
+            let window = chrono::Duration::from_std(self.version_throttle)
+                .map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+            let cutoff = chrono::Utc::now().naive_utc() - window;
+
+            // Only the latest version qualifies, so an explicit save in
+            // between always starts a new auto-save version
+            let coalesced = sqlx::query_as!(
+                DocumentVersionRow,
+                r#"
+                UPDATE document_versions
+                SET content = $3, title = $4, change_summary = $5
+                WHERE id = (
+                    SELECT id FROM document_versions
+                    WHERE document_id = $1
+                    ORDER BY version_number DESC
+                    LIMIT 1
+                )
+                AND created_by = $2
+                AND is_auto_save = true
+                AND is_pinned = false
+                AND created_at > $6
+                RETURNING *
+                "#,
+                doc_uuid,
+                created_by,
+                content,
+                title,
+                change_summary,
+                cutoff
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(version) = coalesced {
+                tx.commit().await?;
+                return Ok((self.open_version(version)?, true));
+            }
+        }
+
+        let version_id = sqlx::query_scalar!(
+            r#"SELECT create_document_version($1, $2, $3, $4, $5) as id"#,
+            doc_uuid,
+            content,
+            title,
+            created_by,
+            change_summary.unwrap_or_default()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let version = sqlx::query_as!(
+            DocumentVersionRow,
+            r#"UPDATE document_versions SET is_auto_save = true WHERE id = $1 RETURNING *"#,
+            version_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((self.open_version(version)?, false))
     }
 
     pub async fn list_versions(
@@ -399,6 +1299,10 @@ impl DocumentRepository {
         .fetch_one(&self.pool)
         .await?
         .count;
+        let versions = versions
+            .into_iter()
+            .map(|row| self.open_version(row))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok((versions, total as i64))
     }
@@ -422,17 +1326,39 @@ impl DocumentRepository {
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(version)
+        version.map(|row| self.open_version(row)).transpose()
+    }
+
+    pub async fn get_versions_meta(
+        &self,
+        document_id: &str,
+        version_numbers: &[i32],
+    ) -> Result<Vec<VersionMetaRow>, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let versions = sqlx::query_as!(
+            VersionMetaRow,
+            r#"
+            SELECT version_number, title, created_by, created_at, change_summary, is_pinned
+            FROM document_versions
+            WHERE document_id = $1 AND version_number = ANY($2)
+            "#,
+            doc_uuid,
+            version_numbers
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(versions)
     }
 
     pub async fn restore_version(
         &self,
         document_id: &str,
         version_number: i32,
-        restored_by: &str,
+        restored_by: Uuid,
     ) -> Result<Option<DocumentRow>, sqlx::Error> {
         let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let restorer_uuid = Uuid::parse_str(restored_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let version_exists: Option<DocumentVersionRow> = sqlx::query_as!(
             DocumentVersionRow,
@@ -451,7 +1377,7 @@ impl DocumentRepository {
             r#"SELECT restore_document_to_version($1, $2, $3) as result"#,
             doc_uuid,
             version_number,
-            restorer_uuid
+            restored_by
         )
         .fetch_one(&self.pool)
         .await?;
@@ -460,7 +1386,7 @@ impl DocumentRepository {
             .fetch_optional(&self.pool)
             .await?;
 
-        Ok(document)
+        document.map(|row| self.open_document(row)).transpose()
     }
 
     pub async fn get_version_diff(
@@ -490,42 +1416,43 @@ impl DocumentRepository {
         .await?;
 
         match (from_content_row, to_content_row) {
-            (Some(from), Some(to)) => Ok(Some((from.content, to.content))),
+            (Some(from), Some(to)) => Ok(Some((
+                self.decrypt_content(from.content)?,
+                self.decrypt_content(to.content)?,
+            ))),
             _ => Ok(None),
         }
     }
 
-    pub async fn check_space_access(&self, space_id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+    pub async fn check_space_access(&self, space_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let result = sqlx::query_as::<_, (i32,)>(
             r#"
-            SELECT 1 as found FROM spaces
-            WHERE id = $1 AND owner_id = $2
-            UNION
-            SELECT 1 as found FROM space_memberships
-            WHERE space_id = $1 AND user_id = $2
+            SELECT 1 as found FROM spaces s
+            WHERE s.id = $1 AND s.is_deleted = false
+              AND (s.owner_id = $2 OR EXISTS (
+                  SELECT 1 FROM space_memberships WHERE space_id = $1 AND user_id = $2
+              ))
             LIMIT 1
             "#,
         )
         .bind(space_uuid)
-        .bind(user_uuid)
+        .bind(user_id)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(result.is_some())
     }
 
-    pub async fn check_document_access(&self, document_id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+    pub async fn check_document_access(&self, document_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
         let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let result = sqlx::query_as::<_, (i32,)>(
             r#"
             SELECT 1 as found FROM documents d
             JOIN spaces s ON d.space_id = s.id
-            WHERE d.id = $1 AND (
+            WHERE d.id = $1 AND s.is_deleted = false AND (
                 s.owner_id = $2
                 OR s.id IN (SELECT space_id FROM space_memberships WHERE user_id = $2)
             )
@@ -533,18 +1460,56 @@ impl DocumentRepository {
             "#,
         )
         .bind(doc_uuid)
-        .bind(user_uuid)
+        .bind(user_id)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(result.is_some())
     }
 
-    // Space operations
+    /// Caller's role in the space containing a document
+    ///
+    /// The space owner always resolves to the built-in `owner` role; other
+    /// users need a membership. Returns `None` when the user has no access.
+    pub async fn get_document_role(&self, document_id: &str, user_id: Uuid) -> Result<Option<Role>, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-    pub async fn list_spaces(&self, user_id: &str) -> Result<Vec<SpaceRow>, sqlx::Error> {
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let row = sqlx::query!(
+            r#"
+            WITH access AS (
+                SELECT d.space_id,
+                       CASE WHEN s.owner_id = $2 THEN 'owner' ELSE sm.role END AS role
+                FROM documents d
+                JOIN spaces s ON d.space_id = s.id
+                LEFT JOIN space_memberships sm ON sm.space_id = s.id AND sm.user_id = $2
+                WHERE d.id = $1 AND s.is_deleted = false AND (s.owner_id = $2 OR sm.user_id IS NOT NULL)
+            )
+            SELECT a.role AS "role!", r.permissions AS "permissions?"
+            FROM access a
+            LEFT JOIN LATERAL (
+                SELECT permissions FROM space_roles
+                WHERE name = a.role AND (space_id = a.space_id OR space_id IS NULL)
+                ORDER BY space_id NULLS LAST
+                LIMIT 1
+            ) r ON true
+            "#,
+            doc_uuid,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| {
+            Role::new(
+                r.role,
+                r.permissions.map(PermissionSet::from_stored).unwrap_or_default(),
+            )
+        }))
+    }
 
+    // Space operations
+
+    pub async fn list_spaces(&self, user_id: Uuid) -> Result<Vec<SpaceRow>, sqlx::Error> {
         let spaces = sqlx::query_as!(
             SpaceRow,
             r#"
@@ -554,10 +1519,10 @@ impl DocumentRepository {
                 sm.role as user_role
             FROM spaces s
             LEFT JOIN space_memberships sm ON s.id = sm.space_id AND sm.user_id = $1
-            WHERE s.owner_id = $1 OR sm.user_id = $1 OR s.is_public = true
+            WHERE s.is_deleted = false AND (s.owner_id = $1 OR sm.user_id = $1 OR s.is_public = true)
             ORDER BY s.updated_at DESC
             "#,
-            user_uuid
+            user_id
         )
         .fetch_all(&self.pool)
         .await?;
@@ -567,14 +1532,12 @@ impl DocumentRepository {
 
     pub async fn create_space(
         &self,
-        owner_id: &str,
+        owner_id: Uuid,
         name: &str,
         icon: Option<&str>,
         description: Option<&str>,
         is_public: bool,
     ) -> Result<SpaceRow, sqlx::Error> {
-        let owner_uuid = Uuid::parse_str(owner_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-
         let space = sqlx::query_as!(
             SpaceRow,
             r#"
@@ -582,7 +1545,7 @@ impl DocumentRepository {
             VALUES (gen_random_uuid(), $1, $2, $3, $4, $5)
             RETURNING id, owner_id, name, icon, description, is_public, created_at, updated_at, NULL::text as user_role
             "#,
-            owner_uuid,
+            owner_id,
             name,
             icon,
             description,
@@ -598,7 +1561,7 @@ impl DocumentRepository {
             VALUES (gen_random_uuid(), $1, $2, 'owner', $2)
             "#,
             space.id,
-            owner_uuid
+            owner_id
         )
         .execute(&self.pool)
         .await?;
@@ -614,7 +1577,7 @@ impl DocumentRepository {
             r#"
             SELECT id, owner_id, name, icon, description, is_public, created_at, updated_at, NULL::text as user_role
             FROM spaces
-            WHERE id = $1
+            WHERE id = $1 AND is_deleted = false
             "#,
             space_uuid
         )
@@ -662,16 +1625,14 @@ impl DocumentRepository {
     pub async fn delete_space(&self, space_id: &str) -> Result<bool, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let result = sqlx::query!(r#"DELETE FROM spaces WHERE id = $1"#, space_uuid)
-            .execute(&self.pool)
-            .await?;
+        let deleted = soft_delete::soft_delete::<SpaceRow>(&self.pool, space_uuid).await?;
+        self.count_cache.invalidate_space(&space_uuid);
 
-        Ok(result.rows_affected() > 0)
+        Ok(deleted)
     }
 
-    pub async fn is_space_owner(&self, space_id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+    pub async fn is_space_owner(&self, space_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let space = sqlx::query_as!(
             SpaceRow,
@@ -681,7 +1642,7 @@ impl DocumentRepository {
             WHERE id = $1 AND owner_id = $2
             "#,
             space_uuid,
-            user_uuid
+            user_id
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -689,15 +1650,14 @@ impl DocumentRepository {
         Ok(space.is_some())
     }
 
-    pub async fn get_user_space_role(&self, space_id: &str, user_id: &str) -> Result<Option<String>, sqlx::Error> {
+    pub async fn get_user_space_role(&self, space_id: &str, user_id: Uuid) -> Result<Option<String>, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
         let result: Option<SpaceMembershipRow> = sqlx::query_as!(
             SpaceMembershipRow,
             r#"SELECT * FROM space_memberships WHERE space_id = $1 AND user_id = $2"#,
             space_uuid,
-            user_uuid
+            user_id
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -705,6 +1665,80 @@ impl DocumentRepository {
         Ok(result.map(|r| r.role))
     }
 
+    /// Role of a member in a space, resolved to its permission set
+    ///
+    /// A role name with no definition in `space_roles` grants nothing.
+    pub async fn get_member_role(&self, space_id: &str, user_id: Uuid) -> Result<Option<Role>, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT sm.role, r.permissions AS "permissions?"
+            FROM space_memberships sm
+            LEFT JOIN LATERAL (
+                SELECT permissions FROM space_roles
+                WHERE name = sm.role AND (space_id = sm.space_id OR space_id IS NULL)
+                ORDER BY space_id NULLS LAST
+                LIMIT 1
+            ) r ON true
+            WHERE sm.space_id = $1 AND sm.user_id = $2
+            "#,
+            space_uuid,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| {
+            Role::new(
+                r.role,
+                r.permissions.map(PermissionSet::from_stored).unwrap_or_default(),
+            )
+        }))
+    }
+
+    /// Look up a role by name as seen from a space
+    ///
+    /// Roles defined by the space take precedence over built-in roles of the
+    /// same name.
+    pub async fn find_space_role(&self, space_id: &str, name: &str) -> Result<Option<Role>, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT name, permissions FROM space_roles
+            WHERE name = $2 AND (space_id = $1 OR space_id IS NULL)
+            ORDER BY space_id NULLS LAST
+            LIMIT 1
+            "#,
+            space_uuid,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| Role::new(r.name, PermissionSet::from_stored(r.permissions))))
+    }
+
+    pub async fn create_space_role(&self, space_id: &str, role: &Role) -> Result<Role, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO space_roles (space_id, name, permissions)
+            VALUES ($1, $2, $3)
+            RETURNING name, permissions
+            "#,
+            space_uuid,
+            role.name,
+            role.permissions.to_stored()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Role::new(row.name, PermissionSet::from_stored(row.permissions)))
+    }
+
     pub async fn list_space_members(&self, space_id: &str) -> Result<Vec<SpaceMembershipRow>, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
@@ -724,39 +1758,271 @@ impl DocumentRepository {
         space_id: &str,
         user_id: &str,
         role: &str,
-        invited_by: &str,
-    ) -> Result<SpaceMembershipRow, sqlx::Error> {
+        invited_by: Uuid,
+    ) -> Result<SpaceMembershipRow, AddMemberError> {
+        let members = [(user_id.to_string(), role.to_string())];
+        let mut added = self.add_space_members(space_id, &members, invited_by).await?;
+
+        added.pop().ok_or_else(|| sqlx::Error::RowNotFound.into())
+    }
+
+    /// Add or update several members at once, as (user id, role) pairs
+    ///
+    /// Users who are already members only have their role changed. Nothing is
+    /// added unless every new member fits under the space's member cap, which
+    /// always counts the owner.
+    pub async fn add_space_members(
+        &self,
+        space_id: &str,
+        members: &[(String, String)],
+        invited_by: Uuid,
+    ) -> Result<Vec<SpaceMembershipRow>, AddMemberError> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let inviter_uuid = Uuid::parse_str(invited_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let members = members
+            .iter()
+            .map(|(user_id, role)| {
+                Uuid::parse_str(user_id)
+                    .map(|user_uuid| (user_uuid, role.as_str()))
+                    .map_err(|e| sqlx::Error::Decode(e.to_string().into()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let membership = sqlx::query_as!(
-            SpaceMembershipRow,
-            r#"
-            INSERT INTO space_memberships (id, space_id, user_id, role, invited_by)
-            VALUES (gen_random_uuid(), $1, $2, $3, $4)
-            ON CONFLICT (space_id, user_id) DO UPDATE SET role = EXCLUDED.role
-            RETURNING *
-            "#,
-            space_uuid,
-            user_uuid,
-            role,
-            inviter_uuid
+        let mut tx = self.pool.begin().await?;
+        let added = self.add_members_in_tx(&mut tx, space_uuid, invited_by, &members).await?;
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
+    // Body of `add_space_members`, on a caller's transaction so other writes
+    // (such as accepting an invitation) commit or fail with the membership
+    async fn add_members_in_tx(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        space_uuid: Uuid,
+        inviter_uuid: Uuid,
+        members: &[(Uuid, &str)],
+    ) -> Result<Vec<SpaceMembershipRow>, AddMemberError> {
+        // Lock the space so concurrent adds cannot both take the last slot
+        let owner_id = sqlx::query_scalar!(r#"SELECT owner_id FROM spaces WHERE id = $1 FOR UPDATE"#, space_uuid)
+            .fetch_one(&mut **tx)
+            .await?;
+
+        if self.max_space_members > 0 {
+            let mut current: HashSet<Uuid> = sqlx::query_scalar!(
+                r#"SELECT user_id FROM space_memberships WHERE space_id = $1"#,
+                space_uuid
+            )
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .collect();
+            current.insert(owner_id);
+
+            let new_members: HashSet<Uuid> = members
+                .iter()
+                .map(|(user_uuid, _)| *user_uuid)
+                .filter(|user_uuid| !current.contains(user_uuid))
+                .collect();
+            let remaining = self.max_space_members.saturating_sub(current.len());
+
+            if new_members.len() > remaining {
+                return Err(AddMemberError::LimitReached {
+                    limit: self.max_space_members,
+                    remaining,
+                });
+            }
+        }
+
+        // Current roles, so the audit log can tell additions from role changes
+        let user_uuids: Vec<Uuid> = members.iter().map(|(user_uuid, _)| *user_uuid).collect();
+        let mut previous: HashMap<Uuid, String> = sqlx::query_as::<_, (Uuid, String)>(
+            "SELECT user_id, role FROM space_memberships WHERE space_id = $1 AND user_id = ANY($2)",
         )
-        .fetch_one(&self.pool)
-        .await?;
+        .bind(space_uuid)
+        .bind(&user_uuids)
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .collect();
 
-        Ok(membership)
+        let mut added = Vec::with_capacity(members.len());
+        for &(user_uuid, role) in members {
+            let membership = sqlx::query_as!(
+                SpaceMembershipRow,
+                r#"
+                INSERT INTO space_memberships (id, space_id, user_id, role, invited_by)
+                VALUES (gen_random_uuid(), $1, $2, $3, $4)
+                ON CONFLICT (space_id, user_id) DO UPDATE SET role = EXCLUDED.role
+                RETURNING *
+                "#,
+                space_uuid,
+                user_uuid,
+                role,
+                inviter_uuid
+            )
+            .fetch_one(&mut **tx)
+            .await?;
+
+            match previous.insert(user_uuid, role.to_string()) {
+                None => {
+                    record_space_audit(
+                        &mut **tx,
+                        space_uuid,
+                        inviter_uuid,
+                        user_uuid,
+                        SpaceAuditAction::MemberAdded,
+                        None,
+                        Some(role),
+                    )
+                    .await?
+                },
+                Some(old_role) if old_role != role => {
+                    record_space_audit(
+                        &mut **tx,
+                        space_uuid,
+                        inviter_uuid,
+                        user_uuid,
+                        SpaceAuditAction::RoleChanged,
+                        Some(&old_role),
+                        Some(role),
+                    )
+                    .await?
+                },
+                Some(_) => {},
+            }
+            added.push(membership);
+        }
+
+        Ok(added)
+    }
+
+    /// Add members from (user id, role) pairs, reporting each entry's outcome
+    ///
+    /// Unlike `add_space_members`, a bad entry doesn't sink the batch: ids
+    /// that don't parse or name no user, roles the space doesn't define,
+    /// repeats of an earlier entry and new members past the member cap fail
+    /// on their own, and the rest are applied in one transaction. Existing
+    /// members only have their role changed, as with a single add. Outcomes
+    /// are in the order of `entries`.
+    pub async fn add_members_bulk(
+        &self,
+        space_id: &str,
+        entries: &[(String, String)],
+        inviter: Uuid,
+    ) -> Result<Vec<BulkMemberOutcome>, AddMemberError> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let parsed: Vec<Option<Uuid>> = entries.iter().map(|(user_id, _)| Uuid::parse_str(user_id).ok()).collect();
+        let user_uuids: Vec<Uuid> = parsed.iter().flatten().copied().collect();
+        let roles: Vec<String> = entries.iter().map(|(_, role)| role.clone()).collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the space first so the checks below still hold at commit
+        let owner_id: Uuid = sqlx::query_scalar("SELECT owner_id FROM spaces WHERE id = $1 FOR UPDATE")
+            .bind(space_uuid)
+            .fetch_one(&mut *tx)
+            .await?;
+        let known_users: HashSet<Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE id = ANY($1)")
+            .bind(&user_uuids)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .collect();
+        let defined_roles: HashSet<String> = sqlx::query_scalar(
+            "SELECT name FROM space_roles WHERE name = ANY($2) AND (space_id = $1 OR space_id IS NULL)",
+        )
+        .bind(space_uuid)
+        .bind(&roles)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+        let current: HashMap<Uuid, String> =
+            sqlx::query_as::<_, (Uuid, String)>("SELECT user_id, role FROM space_memberships WHERE space_id = $1")
+                .bind(space_uuid)
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .collect();
+        // The owner always takes a slot, member row or not
+        let mut member_count = current.len() + usize::from(!current.contains_key(&owner_id));
+
+        let mut seen = HashSet::new();
+        let mut outcomes = Vec::with_capacity(entries.len());
+        // Entries to write, with their index and whether they add a member
+        let mut pending: Vec<(usize, bool)> = Vec::new();
+        let mut to_write: Vec<(Uuid, &str)> = Vec::new();
+        for (index, ((_, role), user_uuid)) in entries.iter().zip(&parsed).enumerate() {
+            let outcome = match *user_uuid {
+                None => BulkMemberOutcome::Failed(BulkMemberFailure::InvalidUserId),
+                Some(user_uuid) if !seen.insert(user_uuid) => {
+                    BulkMemberOutcome::Failed(BulkMemberFailure::DuplicateEntry)
+                },
+                Some(user_uuid) if !known_users.contains(&user_uuid) => {
+                    BulkMemberOutcome::Failed(BulkMemberFailure::UserNotFound)
+                },
+                Some(user_uuid) if user_uuid == owner_id => BulkMemberOutcome::Failed(BulkMemberFailure::SpaceOwner),
+                Some(_) if !defined_roles.contains(role) => BulkMemberOutcome::Failed(BulkMemberFailure::InvalidRole),
+                Some(user_uuid) => match current.get(&user_uuid) {
+                    Some(current_role) if current_role == role => BulkMemberOutcome::Unchanged,
+                    None if self.max_space_members > 0 && member_count >= self.max_space_members => {
+                        BulkMemberOutcome::Failed(BulkMemberFailure::LimitReached)
+                    },
+                    current_role => {
+                        let is_new = current_role.is_none();
+                        if is_new {
+                            member_count += 1;
+                        }
+                        pending.push((index, is_new));
+                        to_write.push((user_uuid, role.as_str()));
+                        // Replaced by the written row below
+                        BulkMemberOutcome::Unchanged
+                    },
+                },
+            };
+            outcomes.push(outcome);
+        }
+
+        if !to_write.is_empty() {
+            let rows = self.add_members_in_tx(&mut tx, space_uuid, inviter, &to_write).await?;
+            for ((index, is_new), row) in pending.into_iter().zip(rows) {
+                outcomes[index] = if is_new {
+                    BulkMemberOutcome::Added(row)
+                } else {
+                    BulkMemberOutcome::RoleChanged(row)
+                };
+            }
+        }
+
+        tx.commit().await?;
+        Ok(outcomes)
     }
 
+    /// Change a member's role, recording the change as made by `actor_id`
     pub async fn update_space_member(
         &self,
         space_id: &str,
-        user_id: &str,
+        user_id: Uuid,
         role: &str,
+        actor_id: Uuid,
     ) -> Result<Option<SpaceMembershipRow>, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let old_role: Option<String> = sqlx::query_scalar(
+            "SELECT role FROM space_memberships WHERE space_id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(space_uuid)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(old_role) = old_role else {
+            tx.rollback().await?;
+            return Ok(None);
+        };
 
         let membership = sqlx::query_as!(
             SpaceMembershipRow,
@@ -767,42 +2033,388 @@ impl DocumentRepository {
             RETURNING *
             "#,
             space_uuid,
-            user_uuid,
+            user_id,
             role
         )
-        .fetch_optional(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(membership)
+        if old_role != role {
+            record_space_audit(
+                &mut *tx,
+                space_uuid,
+                actor_id,
+                user_id,
+                SpaceAuditAction::RoleChanged,
+                Some(&old_role),
+                Some(role),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(membership))
     }
 
-    pub async fn remove_space_member(&self, space_id: &str, user_id: &str) -> Result<bool, sqlx::Error> {
+    pub async fn remove_space_member(
+        &self,
+        space_id: &str,
+        user_id: Uuid,
+        actor_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        self.remove_space_member_with_policy(space_id, user_id, actor_id, MemberContentPolicy::Keep)
+            .await
+    }
+
+    /// Remove a member and apply `policy` to the documents they created in the
+    /// space, all in one transaction with the audit entry for `actor_id`
+    pub async fn remove_space_member_with_policy(
+        &self,
+        space_id: &str,
+        user_id: Uuid,
+        actor_id: Uuid,
+        policy: MemberContentPolicy,
+    ) -> Result<bool, sqlx::Error> {
         let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let _result = sqlx::query!(
-            r#"DELETE FROM space_memberships WHERE space_id = $1 AND user_id = $2"#,
+        let mut tx = self.pool.begin().await?;
+
+        let removed_role: Option<String> =
+            sqlx::query_scalar("DELETE FROM space_memberships WHERE space_id = $1 AND user_id = $2 RETURNING role")
+                .bind(space_uuid)
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        let Some(removed_role) = removed_role else {
+            tx.rollback().await?;
+            return Ok(false);
+        };
+
+        record_space_audit(
+            &mut *tx,
             space_uuid,
-            user_uuid
+            actor_id,
+            user_id,
+            SpaceAuditAction::MemberRemoved,
+            Some(&removed_role),
+            None,
         )
-        .execute(&self.pool)
         .await?;
 
-        Ok(_result.rows_affected() > 0)
+        match policy {
+            MemberContentPolicy::Keep => {},
+            MemberContentPolicy::Reassign => {
+                sqlx::query!(
+                    r#"
+                    UPDATE documents d
+                    SET created_by = s.owner_id, updated_at = NOW()
+                    FROM spaces s
+                    WHERE s.id = d.space_id AND d.space_id = $1 AND d.created_by = $2
+                    "#,
+                    space_uuid,
+                    user_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            },
+            MemberContentPolicy::Archive => {
+                sqlx::query!(
+                    r#"
+                    UPDATE documents
+                    SET is_archived = true, archived_at = NOW()
+                    WHERE space_id = $1 AND created_by = $2 AND is_archived = false
+                    "#,
+                    space_uuid,
+                    user_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            },
+        }
+
+        tx.commit().await?;
+
+        if policy == MemberContentPolicy::Archive {
+            self.count_cache.invalidate_space(&space_uuid);
+        }
+
+        Ok(true)
+    }
+
+    /// Make `new_owner` the owner of a space they are already a member of
+    ///
+    /// Updates `spaces.owner_id`, demotes `current_owner` to
+    /// [`PREVIOUS_OWNER_ROLE`] and promotes `new_owner` in one transaction,
+    /// auditing both role changes.
+    pub async fn transfer_ownership(
+        &self,
+        space_id: &str,
+        current_owner: Uuid,
+        new_owner: &str,
+    ) -> Result<(), TransferOwnershipError> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let new_uuid = Uuid::parse_str(new_owner).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        let owner_id: Option<Uuid> = sqlx::query_scalar("SELECT owner_id FROM spaces WHERE id = $1 FOR UPDATE")
+            .bind(space_uuid)
+            .fetch_optional(&mut *tx)
+            .await?;
+        match owner_id {
+            None => return Err(TransferOwnershipError::SpaceNotFound),
+            Some(owner_id) if owner_id != current_owner => return Err(TransferOwnershipError::NotOwner),
+            Some(_) => {},
+        }
+        if new_uuid == current_owner {
+            return Ok(());
+        }
+
+        let new_owner_role: Option<String> = sqlx::query_scalar(
+            "SELECT role FROM space_memberships WHERE space_id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(space_uuid)
+        .bind(new_uuid)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(new_owner_role) = new_owner_role else {
+            return Err(TransferOwnershipError::NotMember);
+        };
+
+        let current_owner_role: Option<String> = sqlx::query_scalar(
+            "SELECT role FROM space_memberships WHERE space_id = $1 AND user_id = $2 FOR UPDATE",
+        )
+        .bind(space_uuid)
+        .bind(current_owner)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE spaces SET owner_id = $2, updated_at = NOW() WHERE id = $1")
+            .bind(space_uuid)
+            .bind(new_uuid)
+            .execute(&mut *tx)
+            .await?;
+
+        // The old owner may predate owner memberships, so upsert their row
+        sqlx::query(
+            r#"
+            INSERT INTO space_memberships (id, space_id, user_id, role, invited_by)
+            VALUES (gen_random_uuid(), $1, $2, $3, $2)
+            ON CONFLICT (space_id, user_id) DO UPDATE SET role = EXCLUDED.role
+            "#,
+        )
+        .bind(space_uuid)
+        .bind(current_owner)
+        .bind(PREVIOUS_OWNER_ROLE)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE space_memberships SET role = 'owner' WHERE space_id = $1 AND user_id = $2")
+            .bind(space_uuid)
+            .bind(new_uuid)
+            .execute(&mut *tx)
+            .await?;
+
+        let demotion = match &current_owner_role {
+            Some(_) => SpaceAuditAction::RoleChanged,
+            None => SpaceAuditAction::MemberAdded,
+        };
+        record_space_audit(
+            &mut *tx,
+            space_uuid,
+            current_owner,
+            current_owner,
+            demotion,
+            current_owner_role.as_deref(),
+            Some(PREVIOUS_OWNER_ROLE),
+        )
+        .await?;
+        record_space_audit(
+            &mut *tx,
+            space_uuid,
+            current_owner,
+            new_uuid,
+            SpaceAuditAction::RoleChanged,
+            Some(&new_owner_role),
+            Some("owner"),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Invite `email` to a space with `role`, returning the pending invitation
+    ///
+    /// Inviting an address that already has an open invitation replaces its
+    /// role and token and restarts its expiry.
+    pub async fn invite_by_email(
+        &self,
+        space_id: &str,
+        email: &str,
+        role: &str,
+        inviter: Uuid,
+    ) -> Result<SpaceInvitationRow, InvitationError> {
+        validate_email(email).map_err(|e| InvitationError::InvalidEmail(e.to_string()))?;
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(INVITATION_TTL_DAYS);
+
+        let invitation = sqlx::query_as::<_, SpaceInvitationRow>(
+            r#"
+            INSERT INTO space_invitations (space_id, email, role, token, invited_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (space_id, email) WHERE accepted_at IS NULL DO UPDATE
+            SET role = EXCLUDED.role,
+                token = EXCLUDED.token,
+                invited_by = EXCLUDED.invited_by,
+                created_at = CURRENT_TIMESTAMP AT TIME ZONE 'UTC',
+                expires_at = EXCLUDED.expires_at
+            RETURNING *
+            "#,
+        )
+        .bind(space_uuid)
+        .bind(email.trim().to_lowercase())
+        .bind(role)
+        .bind(generate_url_safe_token(INVITATION_TOKEN_LENGTH))
+        .bind(inviter)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    /// Redeem an invitation for `user_id`, whose account email must match
+    ///
+    /// The membership is added (subject to the member cap) and the invitation
+    /// marked accepted in one transaction.
+    pub async fn accept_invitation(&self, token: &str, user_id: Uuid) -> Result<SpaceMembershipRow, InvitationError> {
+        let mut tx = self.pool.begin().await?;
+
+        let invitation = sqlx::query_as::<_, SpaceInvitationRow>(
+            "SELECT * FROM space_invitations WHERE token = $1 AND accepted_at IS NULL FOR UPDATE",
+        )
+        .bind(token)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(InvitationError::NotFound)?;
+
+        if invitation.expires_at <= chrono::Utc::now().naive_utc() {
+            return Err(InvitationError::Expired);
+        }
+
+        let email: Option<String> = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if !email.is_some_and(|email| email.trim().eq_ignore_ascii_case(&invitation.email)) {
+            return Err(InvitationError::EmailMismatch);
+        }
+
+        // Re-checked here as ownership may have moved since the invitation
+        // was sent
+        let owner_id: Uuid = sqlx::query_scalar("SELECT owner_id FROM spaces WHERE id = $1 FOR UPDATE")
+            .bind(invitation.space_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if user_id == owner_id {
+            return Err(InvitationError::SpaceOwner);
+        }
+        if invitation.role == OWNER_ROLE && invitation.invited_by != Some(owner_id) {
+            return Err(InvitationError::OwnerRoleNotAllowed);
+        }
+
+        // The inviter may have been deleted since; the invitee then counts as
+        // having added themselves
+        let inviter_uuid = invitation.invited_by.unwrap_or(user_id);
+        let membership = self
+            .add_members_in_tx(&mut tx, invitation.space_id, inviter_uuid, &[(user_id, &invitation.role)])
+            .await?
+            .pop()
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        sqlx::query(
+            r#"
+            UPDATE space_invitations
+            SET accepted_at = CURRENT_TIMESTAMP AT TIME ZONE 'UTC', accepted_by = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(invitation.id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(membership)
+    }
+
+    /// A space's audit log, newest first, with the total entry count
+    pub async fn list_space_audit(
+        &self,
+        space_id: &str,
+        limit: i32,
+        offset: i32,
+    ) -> Result<(Vec<SpaceAuditRow>, i64), sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM space_audit_log WHERE space_id = $1")
+            .bind(space_uuid)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let entries = sqlx::query_as::<_, SpaceAuditRow>(
+            r#"
+            SELECT id, space_id, actor_id, target_user_id, action, old_role, new_role, created_at
+            FROM space_audit_log
+            WHERE space_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(space_uuid)
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((entries, total))
     }
 
     // ==================== Comment Operations ====================
 
+    /// A comment with the author's current display name and avatar. The
+    /// stored author fields are only used when the user row is gone.
     pub async fn get_comment(&self, comment_id: &str) -> Result<Option<CommentRow>, sqlx::Error> {
         let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let comment = sqlx::query_as!(CommentRow, r#"SELECT * FROM comments WHERE id = $1"#, comment_uuid)
-            .fetch_optional(&self.pool)
-            .await?;
+        let comment = sqlx::query_as!(
+            CommentRow,
+            r#"
+            SELECT c.id, c.document_id, c.parent_id, c.author_id,
+                   COALESCE(u.display_name, c.author_name) AS author_name,
+                   COALESCE(u.avatar_url, c.author_avatar) AS author_avatar,
+                   c.content, c.is_resolved, c.resolved_by, c.resolved_at, c.created_at, c.updated_at
+            FROM comments c
+            LEFT JOIN users u ON u.id = c.author_id
+            WHERE c.id = $1
+            "#,
+            comment_uuid
+        )
+        .fetch_optional(&self.pool)
+        .await?;
 
         Ok(comment)
     }
 
+    /// Re-read a comment just written, with author display fields joined
+    async fn fetch_comment(&self, comment_id: Uuid) -> Result<CommentRow, sqlx::Error> {
+        self.get_comment(&comment_id.to_string())
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
     pub async fn list_comments(
         &self,
         document_id: &str,
@@ -821,7 +2433,17 @@ impl DocumentRepository {
         let comments = if let Some(parent) = parent_uuid {
             sqlx::query_as!(
                 CommentRow,
-                r#"SELECT * FROM comments WHERE document_id = $1 AND parent_id = $2 ORDER BY created_at LIMIT $3 OFFSET $4"#,
+                r#"
+                SELECT c.id, c.document_id, c.parent_id, c.author_id,
+                       COALESCE(u.display_name, c.author_name) AS author_name,
+                       COALESCE(u.avatar_url, c.author_avatar) AS author_avatar,
+                       c.content, c.is_resolved, c.resolved_by, c.resolved_at, c.created_at, c.updated_at
+                FROM comments c
+                LEFT JOIN users u ON u.id = c.author_id
+                WHERE c.document_id = $1 AND c.parent_id = $2
+                ORDER BY c.created_at
+                LIMIT $3 OFFSET $4
+                "#,
                 document_uuid,
                 parent,
                 limit_i64,
@@ -832,7 +2454,17 @@ impl DocumentRepository {
         } else {
             sqlx::query_as!(
                 CommentRow,
-                r#"SELECT * FROM comments WHERE document_id = $1 AND parent_id IS NULL ORDER BY created_at LIMIT $2 OFFSET $3"#,
+                r#"
+                SELECT c.id, c.document_id, c.parent_id, c.author_id,
+                       COALESCE(u.display_name, c.author_name) AS author_name,
+                       COALESCE(u.avatar_url, c.author_avatar) AS author_avatar,
+                       c.content, c.is_resolved, c.resolved_by, c.resolved_at, c.created_at, c.updated_at
+                FROM comments c
+                LEFT JOIN users u ON u.id = c.author_id
+                WHERE c.document_id = $1 AND c.parent_id IS NULL
+                ORDER BY c.created_at
+                LIMIT $2 OFFSET $3
+                "#,
                 document_uuid,
                 limit_i64,
                 offset_i64
@@ -868,17 +2500,19 @@ impl DocumentRepository {
     pub async fn create_comment(
         &self,
         document_id: &str,
-        author_id: &str,
+        author_id: Uuid,
         _author_name: &str,
         content: &str,
         parent_id: Option<&str>,
+        mentions: &[Uuid],
     ) -> Result<CommentRow, sqlx::Error> {
         let document_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let author_uuid = Uuid::parse_str(author_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
         let parent_uuid = parent_id
             .map(|s| Uuid::parse_str(s).map_err(|e| sqlx::Error::Decode(e.to_string().into())))
             .transpose()?;
 
+        let mut tx = self.pool.begin().await?;
+
         let comment = sqlx::query_as!(
             CommentRow,
             r#"
@@ -888,25 +2522,428 @@ impl DocumentRepository {
             "#,
             document_uuid,
             parent_uuid,
-            author_uuid,
+            author_id,
             content
         )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !mentions.is_empty() {
+            sqlx::query!(
+                r#"
+                INSERT INTO comment_mentions (comment_id, user_id)
+                SELECT $1, UNNEST($2::uuid[])
+                ON CONFLICT DO NOTHING
+                "#,
+                comment.id,
+                mentions
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        self.fetch_comment(comment.id).await
+    }
+
+    /// Members of a document's space matching any of the given ids or usernames
+    pub async fn find_mentionable_users(
+        &self,
+        document_id: &str,
+        ids: &[Uuid],
+        usernames: &[String],
+    ) -> Result<Vec<MentionableUserRow>, sqlx::Error> {
+        let document_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        sqlx::query_as!(
+            MentionableUserRow,
+            r#"
+            SELECT u.id, u.display_name, split_part(u.email, '@', 1) AS "username!"
+            FROM documents d
+            INNER JOIN space_memberships sm ON sm.space_id = d.space_id
+            INNER JOIN users u ON u.id = sm.user_id
+            WHERE d.id = $1
+              AND (u.id = ANY($2) OR LOWER(split_part(u.email, '@', 1)) = ANY($3))
+            "#,
+            document_uuid,
+            ids,
+            usernames
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Users mentioned in each of the given comments
+    pub async fn list_comment_mentions(&self, comment_ids: &[Uuid]) -> Result<Vec<CommentMentionRow>, sqlx::Error> {
+        if comment_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_as!(
+            CommentMentionRow,
+            r#"
+            SELECT cm.comment_id, cm.user_id, u.display_name, split_part(u.email, '@', 1) AS "username!"
+            FROM comment_mentions cm
+            INNER JOIN users u ON u.id = cm.user_id
+            WHERE cm.comment_id = ANY($1)
+            ORDER BY cm.created_at, u.display_name
+            "#,
+            comment_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// A document's tags in alphabetical order
+    pub async fn list_tags(&self, document_id: &str) -> Result<Vec<String>, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        sqlx::query_scalar!(
+            r#"SELECT tag FROM document_tags WHERE document_id = $1 ORDER BY tag"#,
+            doc_uuid
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Add normalized tags to a document, returning all of its tags
+    ///
+    /// Tags the document already has don't count against the cap. The
+    /// document row is locked so concurrent adds cannot overshoot it.
+    pub async fn add_tags(
+        &self,
+        document_id: &str,
+        tags: &[String],
+        created_by: Uuid,
+    ) -> Result<Vec<String>, TagError> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let tags = normalize_tags(tags, &self.tag_limits)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(r#"SELECT id FROM documents WHERE id = $1 FOR UPDATE"#, doc_uuid)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let existing = sqlx::query_scalar!(r#"SELECT tag FROM document_tags WHERE document_id = $1"#, doc_uuid)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let new_tags: Vec<String> = tags.into_iter().filter(|tag| !existing.contains(tag)).collect();
+        if existing.len() + new_tags.len() > self.tag_limits.max_count {
+            return Err(TagError::LimitReached {
+                limit: self.tag_limits.max_count,
+            });
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO document_tags (document_id, tag, created_by)
+            SELECT $1, tag, $3 FROM UNNEST($2::varchar[]) AS t(tag)
+            ON CONFLICT DO NOTHING
+            "#,
+            doc_uuid,
+            &new_tags,
+            created_by
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(self.list_tags(document_id).await?)
+    }
+
+    /// Remove an already normalized tag; returns false if the document didn't have it
+    pub async fn remove_tag(&self, document_id: &str, tag: &str) -> Result<bool, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let result = sqlx::query!(
+            r#"DELETE FROM document_tags WHERE document_id = $1 AND tag = $2"#,
+            doc_uuid,
+            tag
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark a document as one of the user's favorites; returns false if it already was
+    pub async fn add_favorite(&self, document_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO document_favorites (user_id, document_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            user_id,
+            doc_uuid
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a document from the user's favorites; returns false if it wasn't one
+    pub async fn remove_favorite(&self, document_id: &str, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let doc_uuid = Uuid::parse_str(document_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let result = sqlx::query!(
+            r#"DELETE FROM document_favorites WHERE user_id = $1 AND document_id = $2"#,
+            user_id,
+            doc_uuid
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Which of `document_ids` the user has favorited
+    pub async fn favorite_document_ids(
+        &self,
+        user_id: Uuid,
+        document_ids: &[Uuid],
+    ) -> Result<HashSet<Uuid>, sqlx::Error> {
+        let ids = sqlx::query_scalar!(
+            r#"SELECT document_id FROM document_favorites WHERE user_id = $1 AND document_id = ANY($2)"#,
+            user_id,
+            document_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    /// The user's favorite documents, most recently favorited first, with the total
+    ///
+    /// Archived documents and documents in spaces the user can no longer
+    /// access are left out.
+    pub async fn list_favorites(
+        &self,
+        user_id: Uuid,
+        limit: i32,
+        offset: i32,
+    ) -> Result<(Vec<DocumentRow>, i64), sqlx::Error> {
+        let documents = sqlx::query_as!(
+            DocumentRow,
+            r#"
+            SELECT d.* FROM document_favorites f
+            JOIN documents d ON d.id = f.document_id
+            JOIN spaces s ON s.id = d.space_id
+            WHERE f.user_id = $1 AND d.is_archived = false AND s.is_deleted = false AND (
+                s.owner_id = $1
+                OR s.id IN (SELECT space_id FROM space_memberships WHERE user_id = $1)
+            )
+            ORDER BY f.created_at DESC, d.id
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM document_favorites f
+            JOIN documents d ON d.id = f.document_id
+            JOIN spaces s ON s.id = d.space_id
+            WHERE f.user_id = $1 AND d.is_archived = false AND s.is_deleted = false AND (
+                s.owner_id = $1
+                OR s.id IN (SELECT space_id FROM space_memberships WHERE user_id = $1)
+            )
+            "#,
+            user_id
+        )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(comment)
+        let documents = documents
+            .into_iter()
+            .map(|row| self.open_document(row))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((documents, total))
+    }
+
+    /// Save a reusable template in a space
+    ///
+    /// Template content is encrypted like document content when the space
+    /// requires it.
+    pub async fn create_template(
+        &self,
+        space_id: &str,
+        title: &str,
+        content: serde_json::Value,
+        created_by: Uuid,
+    ) -> Result<DocumentTemplateRow, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+        let content = self.seal_content(&space_uuid, content).await?;
+
+        let template = sqlx::query_as!(
+            DocumentTemplateRow,
+            r#"
+            INSERT INTO document_templates (space_id, title, content, created_by)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            space_uuid,
+            title,
+            content,
+            created_by
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.open_template(template)
+    }
+
+    /// A space's templates in title order
+    pub async fn list_templates(&self, space_id: &str) -> Result<Vec<DocumentTemplateRow>, sqlx::Error> {
+        let space_uuid = Uuid::parse_str(space_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let templates = sqlx::query_as!(
+            DocumentTemplateRow,
+            r#"
+            SELECT * FROM document_templates
+            WHERE space_id = $1
+            ORDER BY title, created_at
+            "#,
+            space_uuid
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        templates.into_iter().map(|row| self.open_template(row)).collect()
+    }
+
+    pub async fn get_template(&self, template_id: &str) -> Result<Option<DocumentTemplateRow>, sqlx::Error> {
+        let template_uuid = Uuid::parse_str(template_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let template = sqlx::query_as!(
+            DocumentTemplateRow,
+            r#"
+            SELECT * FROM document_templates
+            WHERE id = $1
+            "#,
+            template_uuid
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        template.map(|row| self.open_template(row)).transpose()
+    }
+
+    /// Create a document in the template's space with a copy of its title
+    /// and content; returns `None` when the template doesn't exist
+    pub async fn instantiate_template(
+        &self,
+        template_id: &str,
+        parent_id: Option<&str>,
+        created_by: Uuid,
+    ) -> Result<Option<DocumentRow>, sqlx::Error> {
+        let Some(template) = self.get_template(template_id).await? else {
+            return Ok(None);
+        };
+
+        let document = self
+            .create(
+                &template.space_id.to_string(),
+                parent_id,
+                &template.title,
+                None,
+                Some(template.content.0),
+                created_by,
+            )
+            .await?;
+
+        Ok(Some(document))
+    }
+
+    fn open_template(&self, mut row: DocumentTemplateRow) -> Result<DocumentTemplateRow, sqlx::Error> {
+        row.content = sqlx::types::Json(self.decrypt_content(row.content.0)?);
+        Ok(row)
+    }
+
+    /// React to a comment; returns false if the user already reacted with this emoji
+    pub async fn add_reaction(&self, comment_id: &str, user_id: Uuid, emoji: &str) -> Result<bool, sqlx::Error> {
+        let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO comment_reactions (comment_id, user_id, emoji)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+            comment_uuid,
+            user_id,
+            emoji
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Withdraw a reaction; returns false if there was none
+    pub async fn remove_reaction(&self, comment_id: &str, user_id: Uuid, emoji: &str) -> Result<bool, sqlx::Error> {
+        let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        let result = sqlx::query!(
+            r#"DELETE FROM comment_reactions WHERE comment_id = $1 AND user_id = $2 AND emoji = $3"#,
+            comment_uuid,
+            user_id,
+            emoji
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Reaction counts per comment and emoji, in order of each emoji's first use
+    pub async fn list_comment_reactions(
+        &self,
+        comment_ids: &[Uuid],
+        user_id: Uuid,
+    ) -> Result<Vec<CommentReactionRow>, sqlx::Error> {
+        if comment_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        sqlx::query_as!(
+            CommentReactionRow,
+            r#"
+            SELECT comment_id, emoji,
+                   COUNT(*) AS "count!",
+                   BOOL_OR(user_id = $2) AS "reacted_by_me!"
+            FROM comment_reactions
+            WHERE comment_id = ANY($1)
+            GROUP BY comment_id, emoji
+            ORDER BY comment_id, MIN(created_at), emoji
+            "#,
+            comment_ids,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
     }
 
     pub async fn update_comment(&self, comment_id: &str, content: &str) -> Result<CommentRow, sqlx::Error> {
         let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let comment = sqlx::query_as!(
-            CommentRow,
+        let updated_id = sqlx::query_scalar!(
             r#"
             UPDATE comments
             SET content = $2, updated_at = NOW()
             WHERE id = $1
-            RETURNING *
+            RETURNING id
             "#,
             comment_uuid,
             content
@@ -914,47 +2951,44 @@ impl DocumentRepository {
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(comment)
+        self.fetch_comment(updated_id).await
     }
 
-    pub async fn resolve_comment(&self, comment_id: &str, resolved_by: &str) -> Result<CommentRow, sqlx::Error> {
+    pub async fn resolve_comment(&self, comment_id: &str, resolved_by: Uuid) -> Result<CommentRow, sqlx::Error> {
         let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
-        let resolver_uuid = Uuid::parse_str(resolved_by).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let comment = sqlx::query_as!(
-            CommentRow,
+        let updated_id = sqlx::query_scalar!(
             r#"
             UPDATE comments
             SET is_resolved = true, resolved_by = $2, resolved_at = NOW(), updated_at = NOW()
             WHERE id = $1
-            RETURNING *
+            RETURNING id
             "#,
             comment_uuid,
-            resolver_uuid
+            resolved_by
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(comment)
+        self.fetch_comment(updated_id).await
     }
 
     pub async fn unresolve_comment(&self, comment_id: &str) -> Result<CommentRow, sqlx::Error> {
         let comment_uuid = Uuid::parse_str(comment_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
 
-        let comment = sqlx::query_as!(
-            CommentRow,
+        let updated_id = sqlx::query_scalar!(
             r#"
             UPDATE comments
             SET is_resolved = false, resolved_by = NULL, resolved_at = NULL, updated_at = NOW()
             WHERE id = $1
-            RETURNING *
+            RETURNING id
             "#,
             comment_uuid
         )
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(comment)
+        self.fetch_comment(updated_id).await
     }
 
     pub async fn delete_comment(&self, comment_id: &str) -> Result<bool, sqlx::Error> {
@@ -988,6 +3022,50 @@ impl DocumentRepository {
 
         Ok(_result.rows_affected() > 0)
     }
+
+    pub async fn create_notification(
+        &self,
+        user_id: &str,
+        kind: &str,
+        payload: serde_json::Value,
+    ) -> Result<NotificationRow, sqlx::Error> {
+        let user_uuid = Uuid::parse_str(user_id).map_err(|e| sqlx::Error::Decode(e.to_string().into()))?;
+
+        sqlx::query_as!(
+            NotificationRow,
+            r#"
+            INSERT INTO notifications (user_id, kind, payload)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+            user_uuid,
+            kind,
+            payload
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Mark the user's unread notifications read, limited to `ids` when given.
+    /// Notifications belonging to other users are never touched. Returns the
+    /// number of notifications that changed.
+    pub async fn mark_notifications_read(&self, user_id: Uuid, ids: Option<&[Uuid]>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET read_at = NOW()
+            WHERE user_id = $1
+              AND read_at IS NULL
+              AND ($2::uuid[] IS NULL OR id = ANY($2))
+            "#,
+            user_id,
+            ids as Option<&[Uuid]>
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 #[cfg(test)]
@@ -1027,6 +3105,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         assert_eq!(row.id, id);
@@ -1066,6 +3146,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         assert!(row.parent_id.is_none());
@@ -1096,6 +3178,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: Some("synced".to_string()),
+            is_pinned: false,
+            pin_order: None,
         };
 
         assert!(row.is_archived);
@@ -1128,6 +3212,8 @@ mod tests {
             vector_clock: Some(vector_clock),
             client_id: Some(Uuid::new_v4()),
             sync_state: Some("pending".to_string()),
+            is_pinned: false,
+            pin_order: None,
         };
 
         assert!(row.last_synced_at.is_some());
@@ -1154,6 +3240,8 @@ mod tests {
             created_by,
             created_at: now,
             change_summary: Some("Fixed typos".to_string()),
+            is_pinned: false,
+            is_auto_save: false,
         };
 
         assert_eq!(version.id, id);
@@ -1176,6 +3264,8 @@ mod tests {
             created_by: Uuid::new_v4(),
             created_at: now,
             change_summary: None,
+            is_pinned: false,
+            is_auto_save: false,
         };
 
         assert!(version.change_summary.is_none());
@@ -1448,6 +3538,8 @@ mod tests {
                 vector_clock: None,
                 client_id: None,
                 sync_state: None,
+                is_pinned: false,
+                pin_order: None,
             };
             assert_eq!(row.content.0, content);
         }
@@ -1493,6 +3585,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         let cloned = original.clone();
@@ -1526,6 +3620,8 @@ mod tests {
             vector_clock: None,
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         let debug_str = format!("{:?}", row);
@@ -1562,6 +3658,8 @@ mod tests {
             vector_clock: Some(vector_clock.clone()),
             client_id: None,
             sync_state: None,
+            is_pinned: false,
+            pin_order: None,
         };
 
         let clock = row.vector_clock.unwrap();
@@ -0,0 +1,164 @@
+//! QR codes for share links (with the `qr` feature)
+//!
+//! `GET /share/{token}/qr` encodes the link's public URL (see
+//! `ShareUrlConfig`) as a PNG, or an SVG with `?format=svg`. Like the share
+//! link itself it needs no authentication: knowing the token is the
+//! capability. Links that can't be opened (unknown, deactivated, expired or
+//! out of accesses) get 404, and viewing the code does not count as a click.
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Deserialize;
+use shared_errors::AppError;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::sharing::{share_link_unusable_reason, ShareUrlConfig};
+
+/// Default width and height of the rendered code in pixels
+pub const DEFAULT_QR_SIZE: u32 = 256;
+
+/// Smallest size a caller can ask for
+pub const MIN_QR_SIZE: u32 = 64;
+
+/// Largest size a caller can ask for
+pub const MAX_QR_SIZE: u32 = 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct ShareQrQuery {
+    /// Requested width and height, clamped to `MIN_QR_SIZE..=MAX_QR_SIZE`
+    pub size: Option<u32>,
+    /// `png` (default) or `svg`
+    pub format: Option<String>,
+}
+
+/// Image format of a rendered code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn parse(value: Option<&str>) -> Option<Self> {
+        match value.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("png") => Some(QrFormat::Png),
+            Some("svg") => Some(QrFormat::Svg),
+            Some(_) => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Requested size within the allowed bounds, or the default
+pub fn clamp_qr_size(size: Option<u32>) -> u32 {
+    size.unwrap_or(DEFAULT_QR_SIZE).clamp(MIN_QR_SIZE, MAX_QR_SIZE)
+}
+
+/// Render `data` as a QR code no larger than `size` pixels square
+pub fn render_qr(data: &str, size: u32, format: QrFormat) -> Result<Vec<u8>, AppError> {
+    let code = QrCode::new(data.as_bytes()).map_err(|e| AppError::InternalError(format!("QR encoding failed: {}", e)))?;
+
+    match format {
+        QrFormat::Png => {
+            let image = code.render::<image::Luma<u8>>().max_dimensions(size, size).build();
+            let mut png = Vec::new();
+            image::DynamicImage::ImageLuma8(image)
+                .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+                .map_err(|e| AppError::InternalError(format!("PNG encoding failed: {}", e)))?;
+            Ok(png)
+        },
+        QrFormat::Svg => Ok(code
+            .render::<svg::Color>()
+            .max_dimensions(size, size)
+            .build()
+            .into_bytes()),
+    }
+}
+
+/// QR code for a share link's public URL (public endpoint)
+pub async fn share_link_qr(
+    pool: web::Data<PgPool>,
+    path: web::Path<(String,)>,
+    query: web::Query<ShareQrQuery>,
+    req: HttpRequest,
+    url_config: Option<web::Data<ShareUrlConfig>>,
+) -> Result<HttpResponse, AppError> {
+    let token = path.into_inner().0;
+    let format = QrFormat::parse(query.format.as_deref())
+        .ok_or_else(|| AppError::ValidationError("format must be 'png' or 'svg'".to_string()))?;
+    let size = clamp_qr_size(query.size);
+
+    let link: Option<(bool, Option<DateTime<Utc>>, i32, Option<i32>)> = sqlx::query_as(
+        "SELECT is_active, expires_at, click_count, max_access_count FROM share_links WHERE token = $1",
+    )
+    .bind(&token)
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| {
+        error!("Failed to look up share link for QR code: {:?}", e);
+        AppError::DatabaseError(e)
+    })?;
+
+    let Some((is_active, expires_at, click_count, max_access_count)) = link else {
+        return Err(AppError::NotFoundError("Share link not found".to_string()));
+    };
+    if let Some(reason) = share_link_unusable_reason(is_active, expires_at, click_count, max_access_count) {
+        return Err(AppError::NotFoundError(reason.to_string()));
+    }
+
+    let url = ShareUrlConfig::resolve_share_url(url_config.as_ref(), &req, &token);
+    let body = render_qr(&url, size, format)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((header::CACHE_CONTROL, "private, max-age=300"))
+        .body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://wiki.example.com/share/abcdefghijklmnopqrstuvwxyz012345";
+
+    #[test]
+    fn test_png_is_produced_for_token() {
+        let png = render_qr(URL, DEFAULT_QR_SIZE, QrFormat::Png).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let image = image::load_from_memory(&png).unwrap();
+        assert!(image.width() > 0 && image.width() <= DEFAULT_QR_SIZE);
+        assert_eq!(image.width(), image.height());
+    }
+
+    #[test]
+    fn test_oversize_request_is_clamped() {
+        let size = clamp_qr_size(Some(100_000));
+        assert_eq!(size, MAX_QR_SIZE);
+        assert_eq!(clamp_qr_size(Some(1)), MIN_QR_SIZE);
+        assert_eq!(clamp_qr_size(None), DEFAULT_QR_SIZE);
+
+        let png = render_qr(URL, size, QrFormat::Png).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert!(image.width() <= MAX_QR_SIZE);
+    }
+
+    #[test]
+    fn test_svg_format() {
+        assert_eq!(QrFormat::parse(Some("SVG")), Some(QrFormat::Svg));
+        assert_eq!(QrFormat::parse(None), Some(QrFormat::Png));
+        assert_eq!(QrFormat::parse(Some("gif")), None);
+
+        let svg = String::from_utf8(render_qr(URL, 128, QrFormat::Svg).unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+}
@@ -1,20 +1,37 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::{DateTime, Duration, Utc};
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use shared_errors::AppError;
 use sqlx::PgPool;
-use std::sync::Arc;
 use tracing::{error, info};
 use uuid::Uuid;
 use validator::Validate;
 
-const SHARE_TOKEN_LENGTH: usize = 32;
+use crate::handlers::extract_user_id;
+use crate::repository::DocumentRepository;
+
 const DEFAULT_EXPIRY_DAYS: i64 = 30;
-// const MAX_ACCESS_CODE_LENGTH: usize = 10;
-// const MIN_ACCESS_CODE_LENGTH: usize = 4;
+
+/// Default length of share tokens
+pub const DEFAULT_SHARE_TOKEN_LENGTH: usize = 32;
+/// Shortest configurable share token; shorter ones become guessable
+pub const MIN_SHARE_TOKEN_LENGTH: usize = 16;
+/// Longest configurable share token, the width of `share_links.token`
+pub const MAX_SHARE_TOKEN_LENGTH: usize = 64;
+
+/// Default length of generated access codes
+pub const DEFAULT_ACCESS_CODE_LENGTH: usize = 6;
+/// Access code length bounds, matching `VerifyAccessCodeRequest`'s validation
+pub const MIN_ACCESS_CODE_LENGTH: usize = 4;
+pub const MAX_ACCESS_CODE_LENGTH: usize = 10;
+/// Default access code alphabet, without the look-alikes 0/O and 1/I
+pub const DEFAULT_ACCESS_CODE_CHARSET: &str = "ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// Fewest distinct characters a configured access code alphabet may have
+pub const MIN_ACCESS_CODE_CHARSET_SIZE: usize = 10;
+
+const SHARE_TOKEN_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
 
 /// Request to create a new share link
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
@@ -35,6 +52,179 @@ pub struct CreateShareLinkRequest {
 
     #[serde(rename = "maxAccessCount")]
     pub max_access_count: Option<i32>,
+
+    /// Protect the link with a generated access code when `accessCode` is
+    /// not supplied; the code is returned once, in the creation response
+    #[serde(rename = "requireAccessCode", default)]
+    pub require_access_code: bool,
+}
+
+/// How share tokens and generated access codes are made
+///
+/// Built with `ShareLinkConfig::new`, which keeps the token within the
+/// `share_links.token` column and generated access codes within the bounds
+/// `VerifyAccessCodeRequest` accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareLinkConfig {
+    token_length: usize,
+    access_code_length: usize,
+    access_code_charset: Vec<u8>,
+}
+
+impl Default for ShareLinkConfig {
+    fn default() -> Self {
+        Self {
+            token_length: DEFAULT_SHARE_TOKEN_LENGTH,
+            access_code_length: DEFAULT_ACCESS_CODE_LENGTH,
+            access_code_charset: DEFAULT_ACCESS_CODE_CHARSET.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl ShareLinkConfig {
+    /// Check the settings, dropping repeated characters from the charset
+    ///
+    /// The charset must be printable ASCII without spaces, since access codes
+    /// are trimmed and their length is checked in characters.
+    pub fn new(token_length: usize, access_code_length: usize, access_code_charset: &str) -> Result<Self, String> {
+        if !(MIN_SHARE_TOKEN_LENGTH..=MAX_SHARE_TOKEN_LENGTH).contains(&token_length) {
+            return Err(format!(
+                "share token length must be between {} and {}, got {}",
+                MIN_SHARE_TOKEN_LENGTH, MAX_SHARE_TOKEN_LENGTH, token_length
+            ));
+        }
+        if !(MIN_ACCESS_CODE_LENGTH..=MAX_ACCESS_CODE_LENGTH).contains(&access_code_length) {
+            return Err(format!(
+                "access code length must be between {} and {}, got {}",
+                MIN_ACCESS_CODE_LENGTH, MAX_ACCESS_CODE_LENGTH, access_code_length
+            ));
+        }
+        if !access_code_charset.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err("access code charset must be printable ASCII without spaces".to_string());
+        }
+
+        let mut charset = Vec::new();
+        for b in access_code_charset.bytes() {
+            if !charset.contains(&b) {
+                charset.push(b);
+            }
+        }
+        if charset.len() < MIN_ACCESS_CODE_CHARSET_SIZE {
+            return Err(format!(
+                "access code charset must have at least {} distinct characters, got {}",
+                MIN_ACCESS_CODE_CHARSET_SIZE,
+                charset.len()
+            ));
+        }
+
+        Ok(Self {
+            token_length,
+            access_code_length,
+            access_code_charset: charset,
+        })
+    }
+
+    pub fn token_length(&self) -> usize {
+        self.token_length
+    }
+
+    pub fn access_code_length(&self) -> usize {
+        self.access_code_length
+    }
+
+    /// Random token for a new share link
+    pub fn generate_token(&self) -> String {
+        generate_share_token(self.token_length)
+    }
+
+    /// Random access code for a protected link created without one
+    pub fn generate_access_code(&self) -> String {
+        random_string(&self.access_code_charset, self.access_code_length)
+    }
+}
+
+/// Where share link URLs point
+///
+/// With `public_base_url` set every URL is `{public_base_url}/share/{token}`,
+/// whatever host the request came in on. Otherwise the request's own host and
+/// scheme are used, taken from `X-Forwarded-Host`/`X-Forwarded-Proto` only
+/// when `trust_proxy` says a proxy in front of us sets them.
+#[derive(Debug, Clone, Default)]
+pub struct ShareUrlConfig {
+    /// Validated origin without a trailing slash, e.g. `https://wiki.example.com`
+    pub public_base_url: Option<String>,
+    pub trust_proxy: bool,
+}
+
+impl ShareUrlConfig {
+    /// Public URL of the share link with `token`
+    pub fn share_url(&self, req: &HttpRequest, token: &str) -> String {
+        match &self.public_base_url {
+            Some(base) => build_share_url(base, token),
+            None => build_share_url(&request_base_url(req, self.trust_proxy), token),
+        }
+    }
+
+    /// Public URL of the share link with `token` under the registered
+    /// config, or the default when none is registered
+    pub fn resolve_share_url(config: Option<&web::Data<ShareUrlConfig>>, req: &HttpRequest, token: &str) -> String {
+        match config {
+            Some(config) => config.share_url(req, token),
+            None => ShareUrlConfig::default().share_url(req, token),
+        }
+    }
+}
+
+/// Why a share link can no longer be opened, or `None` while it can
+///
+/// A link is usable while it is active, not past `expires_at` and, with a
+/// `max_access_count`, opened fewer times than that.
+pub fn share_link_unusable_reason(
+    is_active: bool,
+    expires_at: Option<DateTime<Utc>>,
+    click_count: i32,
+    max_access_count: Option<i32>,
+) -> Option<&'static str> {
+    if !is_active {
+        Some("Share link has been deactivated")
+    } else if expires_at.is_some_and(|expires| expires < Utc::now()) {
+        Some("Share link has expired")
+    } else if max_access_count.is_some_and(|max| click_count >= max) {
+        Some("Share link has reached maximum access count")
+    } else {
+        None
+    }
+}
+
+fn build_share_url(base: &str, token: &str) -> String {
+    format!("{}/share/{}", base.trim_end_matches('/'), token)
+}
+
+// First value of a possibly comma-separated forwarding header
+fn forwarded_header<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+// Scheme and host the client used, ignoring forwarding headers unless trusted
+fn request_base_url(req: &HttpRequest, trust_proxy: bool) -> String {
+    let forwarded = |name| if trust_proxy { forwarded_header(req, name) } else { None };
+
+    let scheme = match forwarded("x-forwarded-proto") {
+        Some("https") => "https",
+        Some(_) => "http",
+        None if req.app_config().secure() => "https",
+        None => "http",
+    };
+    let host = forwarded("x-forwarded-host")
+        .or_else(|| req.headers().get("host").and_then(|h| h.to_str().ok()))
+        .unwrap_or("localhost:8080");
+
+    format!("{}://{}", scheme, host)
 }
 
 /// Request to verify access code for protected share link
@@ -57,6 +247,9 @@ pub struct ShareLinkResponse {
     pub permission: String,
     pub created_at: String,
     pub max_access_count: Option<i32>,
+    /// Generated access code, only present in the response that created it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_code: Option<String>,
 }
 
 /// Response for share link retrieval
@@ -77,45 +270,35 @@ pub struct ShareLinkDetailResponse {
 }
 
 /// Generate a secure random share token
-fn generate_share_token() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-    let mut rng = rand::thread_rng();
-    let token: String = (0..SHARE_TOKEN_LENGTH)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect();
-    token
+fn generate_share_token(length: usize) -> String {
+    random_string(SHARE_TOKEN_CHARSET, length)
 }
 
-/*
-/// Generate access code if not provided
-fn generate_access_code() -> String {
-    const CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+// `length` characters drawn uniformly from a non-empty ASCII `charset`
+fn random_string(charset: &[u8], length: usize) -> String {
     let mut rng = rand::thread_rng();
-    let code: String = (0..6)
+    (0..length)
         .map(|_| {
-            let idx = rng.gen_range(0..CODE_CHARSET.len());
-            CODE_CHARSET[idx] as char
+            let idx = rng.gen_range(0..charset.len());
+            charset[idx] as char
         })
-        .collect();
-    code
+        .collect()
 }
-*/
 
 /// Create a new share link for a document
 pub async fn create_share_link(
     pool: web::Data<PgPool>,
     req: HttpRequest,
     create_req: web::Json<CreateShareLinkRequest>,
+    url_config: Option<web::Data<ShareUrlConfig>>,
+    link_config: Option<web::Data<ShareLinkConfig>>,
 ) -> Result<impl Responder, AppError> {
     let create_req = create_req.into_inner();
 
     // Validate request
     create_req.validate().map_err(|e| {
         error!("Share link validation error: {:?}", e);
-        AppError::ValidationError(e.to_string())
+        AppError::from(e)
     })?;
 
     // Validate permission
@@ -131,7 +314,7 @@ pub async fn create_share_link(
         .map_err(|_| AppError::ValidationError("Invalid document ID format".to_string()))?;
 
     // Extract user_id from JWT token
-    let user_id = extract_user_id_from_request(&req).await?;
+    let user_id = extract_user_id(&req).await?;
 
     // Verify document exists and user has permission to share it
     let owner_check = sqlx::query_as::<_, (Uuid,)>("SELECT owner_id FROM documents WHERE id = $1")
@@ -151,26 +334,33 @@ pub async fn create_share_link(
         None => return Err(AppError::NotFoundError("Document not found".to_string())),
     }
 
+    let link_config = match &link_config {
+        Some(link_config) => link_config.get_ref().clone(),
+        None => ShareLinkConfig::default(),
+    };
+
     // Generate share token
-    let token = generate_share_token();
-
-    // Hash access code if provided, otherwise leave as None
-    let access_code_hash = if let Some(code) = &create_req.access_code {
-        let code = code.trim();
-        if !code.is_empty() {
-            // User provided a non-empty code, hash it
-            let hashed = hash(code, DEFAULT_COST).map_err(|e| {
-                error!("Failed to hash access code: {:?}", e);
-                AppError::InternalError("Failed to hash access code".to_string())
-            })?;
-            Some(hashed)
-        } else {
-            // User provided empty string, treat as no code
-            None
-        }
-    } else {
-        // No access code provided
-        None
+    let token = link_config.generate_token();
+
+    // An empty access code counts as none; a protected link without one
+    // gets a generated code
+    let supplied_code = create_req
+        .access_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|code| !code.is_empty());
+    let generated_code = match supplied_code {
+        None if create_req.require_access_code => Some(link_config.generate_access_code()),
+        _ => None,
+    };
+
+    // Hash the access code if there is one, otherwise leave as None
+    let access_code_hash = match supplied_code.or(generated_code.as_deref()) {
+        Some(code) => Some(hash(code, DEFAULT_COST).map_err(|e| {
+            error!("Failed to hash access code: {:?}", e);
+            AppError::InternalError("Failed to hash access code".to_string())
+        })?),
+        None => None,
     };
 
     // Parse expiry date
@@ -213,20 +403,7 @@ pub async fn create_share_link(
 
     info!("Created share link {} for document {}", share_id, document_id);
 
-    // Generate share URL
-    let host = req
-        .headers()
-        .get("host")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("localhost:8080");
-
-    let scheme = if req.connection_info().scheme() == "https" {
-        "https"
-    } else {
-        "http"
-    };
-
-    let share_url = format!("{}://{}/share/{}", scheme, host, token);
+    let share_url = ShareUrlConfig::resolve_share_url(url_config.as_ref(), &req, &token);
 
     let response = ShareLinkResponse {
         id: share_id.to_string(),
@@ -238,6 +415,7 @@ pub async fn create_share_link(
         permission,
         created_at: result.3.to_rfc3339(),
         max_access_count: create_req.max_access_count,
+        access_code: generated_code,
     };
 
     Ok(HttpResponse::Created().json(response))
@@ -254,7 +432,7 @@ pub async fn get_document_share_links(
         .map_err(|_| AppError::ValidationError("Invalid document ID format".to_string()))?;
 
     // Authorization: verify user owns the document
-    let user_id = extract_user_id_from_request(&req).await?;
+    let user_id = extract_user_id(&req).await?;
     let owner_check = sqlx::query_as::<_, (Uuid,)>("SELECT owner_id FROM documents WHERE id = $1")
         .bind(document_id)
         .fetch_optional(pool.get_ref())
@@ -352,6 +530,7 @@ pub async fn get_document_share_links(
 /// Get share link by token (public endpoint)
 pub async fn get_share_link_by_token(
     pool: web::Data<PgPool>,
+    repo: web::Data<DocumentRepository>,
     path: web::Path<(String,)>,
 ) -> Result<impl Responder, AppError> {
     let token = path.into_inner().0;
@@ -408,25 +587,8 @@ pub async fn get_share_link_by_token(
             title,
             content,
         )) => {
-            // Check if link is active
-            if !is_active {
-                return Err(AppError::NotFoundError("Share link has been deactivated".to_string()));
-            }
-
-            // Check if link has expired
-            if let Some(expires) = expires_at {
-                if expires < Utc::now() {
-                    return Err(AppError::NotFoundError("Share link has expired".to_string()));
-                }
-            }
-
-            // Check max access count
-            if let Some(max) = max_access_count {
-                if click_count >= max {
-                    return Err(AppError::NotFoundError(
-                        "Share link has reached maximum access count".to_string(),
-                    ));
-                }
+            if let Some(reason) = share_link_unusable_reason(is_active, expires_at, click_count, max_access_count) {
+                return Err(AppError::NotFoundError(reason.to_string()));
             }
 
             // Check if access code is required - if so, don't return content
@@ -450,6 +612,8 @@ pub async fn get_share_link_by_token(
                 tracing::error!("Failed to increment click_count for share link id {}: {}", id, e);
             }
 
+            let content = repo.decrypt_content(content).map_err(AppError::DatabaseError)?;
+
             Ok(HttpResponse::Ok().json(serde_json::json!({
                 "id": id.to_string(),
                 "document_id": document_id.to_string(),
@@ -467,6 +631,7 @@ pub async fn get_share_link_by_token(
 /// Verify access code for a share link
 pub async fn verify_share_link_access_code(
     pool: web::Data<PgPool>,
+    repo: web::Data<DocumentRepository>,
     path: web::Path<(String,)>,
     verify_req: web::Json<VerifyAccessCodeRequest>,
 ) -> Result<impl Responder, AppError> {
@@ -525,27 +690,8 @@ pub async fn verify_share_link_access_code(
             title,
             content,
         )) => {
-            // Check if link is active
-            if !is_active {
-                return Err(AppError::AuthenticationError(
-                    "Share link has been deactivated".to_string(),
-                ));
-            }
-
-            // Check if link has expired
-            if let Some(expires) = expires_at {
-                if expires < Utc::now() {
-                    return Err(AppError::AuthenticationError("Share link has expired".to_string()));
-                }
-            }
-
-            // Check max access count
-            if let Some(max) = max_access_count {
-                if click_count >= max {
-                    return Err(AppError::AuthenticationError(
-                        "Share link has reached maximum access count".to_string(),
-                    ));
-                }
+            if let Some(reason) = share_link_unusable_reason(is_active, expires_at, click_count, max_access_count) {
+                return Err(AppError::AuthenticationError(reason.to_string()));
             }
 
             // Verify access code using bcrypt (constant-time comparison built-in)
@@ -564,6 +710,8 @@ pub async fn verify_share_link_access_code(
                 tracing::error!("Failed to increment click_count for share link id {}: {}", id, e);
             }
 
+            let content = repo.decrypt_content(content).map_err(AppError::DatabaseError)?;
+
             Ok(HttpResponse::Ok().json(serde_json::json!({
                 "id": id.to_string(),
                 "document_id": document_id.to_string(),
@@ -591,7 +739,7 @@ pub async fn delete_share_link(
         .map_err(|_| AppError::ValidationError("Invalid document ID format".to_string()))?;
 
     // Extract user_id from request (JWT token)
-    let user_id = extract_user_id_from_request(&req).await?;
+    let user_id = extract_user_id(&req).await?;
 
     // Authorization check: verify the user owns the document or created the share link
     let auth_query = r#"
@@ -646,39 +794,6 @@ pub async fn delete_share_link(
     Ok(HttpResponse::NoContent().finish())
 }
 
-/// Helper function to extract user ID from request
-async fn extract_user_id_from_request(req: &HttpRequest) -> Result<Uuid, AppError> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| AppError::AuthenticationError("Missing authorization header".to_string()))?;
-
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or_else(|| AppError::AuthenticationError("Invalid authorization format".to_string()))?;
-
-    let secret = req
-        .app_data::<web::Data<Arc<String>>>()
-        .map(|d| d.get_ref().clone())
-        .ok_or_else(|| AppError::InternalError("JWT secret not configured".to_string()))?;
-
-    #[derive(Debug, Deserialize)]
-    struct Claims {
-        sub: String,
-    }
-
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::new(Algorithm::HS256),
-    )
-    .map_err(|_| AppError::AuthenticationError("Invalid token".to_string()))?;
-
-    let user_id_str = &token_data.claims.sub;
-    Uuid::parse_str(user_id_str).map_err(|_| AppError::AuthenticationError("Invalid user ID format".to_string()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -720,6 +835,7 @@ mod tests {
             expires_at: Some("2026-01-22T10:00:00Z".to_string()),
             permission: Some("view".to_string()),
             max_access_count: Some(10),
+            require_access_code: false,
         };
 
         assert!(req.validate().is_ok());
@@ -735,6 +851,7 @@ mod tests {
             expires_at: None,
             permission: None,
             max_access_count: None,
+            require_access_code: false,
         };
 
         // Permission is marked with #[validate(required)] so None should fail validation
@@ -754,6 +871,7 @@ mod tests {
             expires_at: None,
             permission: Some("view".to_string()),
             max_access_count: None,
+            require_access_code: false,
         };
 
         // Validate that the struct itself passes validation
@@ -770,6 +888,7 @@ mod tests {
             expires_at: None,
             permission: Some("view".to_string()),
             max_access_count: None,
+            require_access_code: false,
         };
 
         let result = req.validate();
@@ -785,6 +904,7 @@ mod tests {
             expires_at: None,
             permission: Some("view".to_string()),
             max_access_count: None,
+            require_access_code: false,
         };
 
         let result = req.validate();
@@ -837,6 +957,7 @@ mod tests {
             permission: "view".to_string(),
             created_at: "2026-01-22T08:00:00Z".to_string(),
             max_access_count: Some(10),
+            access_code: None,
         };
 
         let json = serde_json::to_string(&resp).unwrap();
@@ -878,13 +999,13 @@ mod tests {
     // generate_share_token Tests
     #[test]
     fn test_generate_share_token_length() {
-        let token = generate_share_token();
-        assert_eq!(token.len(), SHARE_TOKEN_LENGTH);
+        let token = generate_share_token(DEFAULT_SHARE_TOKEN_LENGTH);
+        assert_eq!(token.len(), DEFAULT_SHARE_TOKEN_LENGTH);
     }
 
     #[test]
     fn test_generate_share_token_uniqueness() {
-        let tokens = std::iter::repeat_with(generate_share_token)
+        let tokens = std::iter::repeat_with(|| generate_share_token(DEFAULT_SHARE_TOKEN_LENGTH))
             .take(100)
             .collect::<std::collections::HashSet<_>>();
         // Generate 100 tokens and check uniqueness (very unlikely to have collisions)
@@ -893,7 +1014,7 @@ mod tests {
 
     #[test]
     fn test_generate_share_token_charset() {
-        let token = generate_share_token();
+        let token = generate_share_token(DEFAULT_SHARE_TOKEN_LENGTH);
         let charset: std::collections::HashSet<char> =
             b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
                 .iter()
@@ -908,12 +1029,79 @@ mod tests {
 
     #[test]
     fn test_generate_share_token_randomness() {
-        let token1 = generate_share_token();
-        let token2 = generate_share_token();
+        let token1 = generate_share_token(DEFAULT_SHARE_TOKEN_LENGTH);
+        let token2 = generate_share_token(DEFAULT_SHARE_TOKEN_LENGTH);
         // Very unlikely to be the same
         assert_ne!(token1, token2);
     }
 
+    // ShareLinkConfig Tests
+    #[test]
+    fn test_configured_token_length() {
+        let config = ShareLinkConfig::new(48, DEFAULT_ACCESS_CODE_LENGTH, DEFAULT_ACCESS_CODE_CHARSET).unwrap();
+        assert_eq!(config.generate_token().len(), 48);
+        assert_eq!(ShareLinkConfig::default().generate_token().len(), DEFAULT_SHARE_TOKEN_LENGTH);
+    }
+
+    #[test]
+    fn test_generated_access_code_length_and_charset() {
+        for length in MIN_ACCESS_CODE_LENGTH..=MAX_ACCESS_CODE_LENGTH {
+            let config = ShareLinkConfig::new(DEFAULT_SHARE_TOKEN_LENGTH, length, "0123456789").unwrap();
+            let code = config.generate_access_code();
+
+            assert_eq!(code.len(), length);
+            assert!(code.chars().all(|c| c.is_ascii_digit()), "unexpected character in {}", code);
+        }
+
+        let code = ShareLinkConfig::default().generate_access_code();
+        assert_eq!(code.len(), DEFAULT_ACCESS_CODE_LENGTH);
+        assert!(code.chars().all(|c| DEFAULT_ACCESS_CODE_CHARSET.contains(c)));
+    }
+
+    #[test]
+    fn test_generated_access_code_passes_validation() {
+        for length in [MIN_ACCESS_CODE_LENGTH, DEFAULT_ACCESS_CODE_LENGTH, MAX_ACCESS_CODE_LENGTH] {
+            let config = ShareLinkConfig::new(DEFAULT_SHARE_TOKEN_LENGTH, length, DEFAULT_ACCESS_CODE_CHARSET).unwrap();
+            let req = VerifyAccessCodeRequest {
+                access_code: config.generate_access_code(),
+            };
+
+            assert!(req.validate().is_ok(), "{} should validate", req.access_code);
+        }
+    }
+
+    #[test]
+    fn test_share_link_config_rejects_out_of_bounds_settings() {
+        let charset = DEFAULT_ACCESS_CODE_CHARSET;
+        assert!(ShareLinkConfig::new(MIN_SHARE_TOKEN_LENGTH - 1, 6, charset).is_err());
+        assert!(ShareLinkConfig::new(MAX_SHARE_TOKEN_LENGTH + 1, 6, charset).is_err());
+        assert!(ShareLinkConfig::new(32, MIN_ACCESS_CODE_LENGTH - 1, charset).is_err());
+        assert!(ShareLinkConfig::new(32, MAX_ACCESS_CODE_LENGTH + 1, charset).is_err());
+        assert!(ShareLinkConfig::new(32, 6, "ABC DEF GHIJ").is_err());
+        assert!(ShareLinkConfig::new(32, 6, "AABBCCDDEE").is_err());
+        assert!(ShareLinkConfig::new(32, 6, "").is_err());
+    }
+
+    #[test]
+    fn test_generated_access_code_only_serialized_when_present() {
+        let mut resp = ShareLinkResponse {
+            id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
+            document_id: "550e8400-e29b-41d4-a716-446655440001".to_string(),
+            token: "test-token".to_string(),
+            url: "http://localhost:8080/share/test-token".to_string(),
+            access_code_required: true,
+            expires_at: None,
+            permission: "view".to_string(),
+            created_at: "2026-01-22T08:00:00Z".to_string(),
+            max_access_count: None,
+            access_code: None,
+        };
+        assert!(serde_json::to_value(&resp).unwrap().get("access_code").is_none());
+
+        resp.access_code = Some("K7PX2M".to_string());
+        assert_eq!(serde_json::to_value(&resp).unwrap()["access_code"], "K7PX2M");
+    }
+
     // Test: Permission Validation Logic
     #[test]
     fn test_permission_validation_logic() {
@@ -1013,6 +1201,47 @@ mod tests {
         assert_eq!(url, "http://localhost:8080/share/test-token");
     }
 
+    #[test]
+    fn test_share_url_uses_configured_base() {
+        use actix_web::test::TestRequest;
+        let config = ShareUrlConfig {
+            public_base_url: Some("https://wiki.example.com".to_string()),
+            trust_proxy: false,
+        };
+        let req = TestRequest::get()
+            .insert_header(("Host", "internal:8080"))
+            .insert_header(("X-Forwarded-Host", "evil.example"))
+            .to_http_request();
+
+        assert_eq!(
+            config.share_url(&req, "abc123"),
+            "https://wiki.example.com/share/abc123"
+        );
+        assert_eq!(
+            build_share_url("https://wiki.example.com/base/", "abc123"),
+            "https://wiki.example.com/base/share/abc123"
+        );
+    }
+
+    #[test]
+    fn test_share_url_forwarded_headers_need_trust_proxy() {
+        use actix_web::test::TestRequest;
+        let req = TestRequest::get()
+            .insert_header(("Host", "internal:8080"))
+            .insert_header(("X-Forwarded-Host", "wiki.example.com, proxy.local"))
+            .insert_header(("X-Forwarded-Proto", "https"))
+            .to_http_request();
+
+        let untrusted = ShareUrlConfig::default();
+        assert_eq!(untrusted.share_url(&req, "abc123"), "http://internal:8080/share/abc123");
+
+        let trusted = ShareUrlConfig {
+            public_base_url: None,
+            trust_proxy: true,
+        };
+        assert_eq!(trusted.share_url(&req, "abc123"), "https://wiki.example.com/share/abc123");
+    }
+
     // Test: Click Count and Max Access Count Logic
     #[test]
     fn test_max_access_count_check() {
@@ -1243,7 +1472,23 @@ mod tests {
     // Test: Constants
     #[test]
     fn test_constants() {
-        assert_eq!(SHARE_TOKEN_LENGTH, 32);
+        assert_eq!(DEFAULT_SHARE_TOKEN_LENGTH, 32);
         assert_eq!(DEFAULT_EXPIRY_DAYS, 30);
     }
+
+    // Test: Usable link check
+    #[test]
+    fn test_share_link_unusable_reason() {
+        let tomorrow = Some(Utc::now() + Duration::days(1));
+        let yesterday = Some(Utc::now() - Duration::days(1));
+
+        assert_eq!(share_link_unusable_reason(true, None, 0, None), None);
+        assert_eq!(share_link_unusable_reason(true, tomorrow, 2, Some(3)), None);
+        assert_eq!(share_link_unusable_reason(false, tomorrow, 0, None), Some("Share link has been deactivated"));
+        assert_eq!(share_link_unusable_reason(true, yesterday, 0, None), Some("Share link has expired"));
+        assert_eq!(
+            share_link_unusable_reason(true, tomorrow, 3, Some(3)),
+            Some("Share link has reached maximum access count")
+        );
+    }
 }
@@ -0,0 +1,262 @@
+//! Document Tag Handlers
+//!
+//! Provides HTTP handlers for document tags:
+//! - GET /documents/{documentId}/tags - List tags
+//! - POST /documents/{documentId}/tags - Add tags
+//! - DELETE /documents/{documentId}/tags/{tag} - Remove a tag
+//!
+//! Tags are trimmed and lowercased before storage so `Draft` and `draft`
+//! are the same tag. Tag length and the number of tags per document are
+//! capped (`DOCUMENT_TAG_MAX_LENGTH`, `DOCUMENT_MAX_TAGS`).
+use actix_web::{web, HttpResponse, Responder};
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::has_permission;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::handlers::{document_access_denied, extract_user_id, unauthorized_response};
+use crate::models::*;
+use crate::repository::DocumentRepository;
+
+/// Default maximum tag length in characters, after normalization
+pub const DEFAULT_TAG_MAX_LENGTH: usize = 50;
+
+/// Default maximum number of tags on one document
+pub const DEFAULT_MAX_TAGS_PER_DOCUMENT: usize = 20;
+
+/// Caps applied when tags are added
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagLimits {
+    pub max_length: usize,
+    pub max_count: usize,
+}
+
+impl Default for TagLimits {
+    fn default() -> Self {
+        Self {
+            max_length: DEFAULT_TAG_MAX_LENGTH,
+            max_count: DEFAULT_MAX_TAGS_PER_DOCUMENT,
+        }
+    }
+}
+
+impl TagLimits {
+    /// Read `DOCUMENT_TAG_MAX_LENGTH` and `DOCUMENT_MAX_TAGS`, falling back to
+    /// the defaults for missing, invalid or zero values
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_length: read("DOCUMENT_TAG_MAX_LENGTH", DEFAULT_TAG_MAX_LENGTH),
+            max_count: read("DOCUMENT_MAX_TAGS", DEFAULT_MAX_TAGS_PER_DOCUMENT),
+        }
+    }
+}
+
+/// Errors from adding tags to a document
+#[derive(Debug, thiserror::Error)]
+pub enum TagError {
+    #[error("Tags must not be empty")]
+    Empty,
+
+    #[error("Tag \"{tag}\" is longer than {max_length} characters")]
+    TooLong { tag: String, max_length: usize },
+
+    #[error("Documents can have at most {limit} tags")]
+    LimitReached { limit: usize },
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Trim and lowercase a tag so near-duplicates collapse
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Normalize and check tags against the length cap, dropping duplicates
+pub fn normalize_tags(tags: &[String], limits: &TagLimits) -> Result<Vec<String>, TagError> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_tag(tag);
+        if tag.is_empty() {
+            return Err(TagError::Empty);
+        }
+        if tag.chars().count() > limits.max_length {
+            return Err(TagError::TooLong {
+                tag,
+                max_length: limits.max_length,
+            });
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+// Tags are part of the document, so changing them needs edit rights
+async fn check_can_edit(
+    repo: &DocumentRepository,
+    document_id: &str,
+    user_id: Uuid,
+    http_req: &actix_web::HttpRequest,
+) -> Result<(), HttpResponse> {
+    match repo.get_document_role(document_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::EditDocuments) => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "PERMISSION_DENIED",
+            "You don't have permission to edit this document",
+        ))),
+        Ok(None) => Err(document_access_denied(http_req)),
+        Err(e) => {
+            error!("Database error checking document role: {:?}", e);
+            Err(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            )))
+        },
+    }
+}
+
+fn tags_response(document_id: String, result: Result<Vec<String>, sqlx::Error>) -> HttpResponse {
+    match result {
+        Ok(tags) => HttpResponse::Ok().json(ApiResponse::success(DocumentTagsResponse { document_id, tags })),
+        Err(e) => {
+            error!("Database error listing document tags: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+/// List a document's tags in alphabetical order
+pub async fn list_tags(
+    document_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.check_document_access(&document_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => return document_access_denied(&http_req),
+        Err(e) => {
+            error!("Database error checking document access: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    let result = repo.list_tags(&document_id).await;
+    tags_response(document_id, result)
+}
+
+/// Add tags to a document; tags it already has are ignored
+pub async fn add_tags(
+    document_id: web::Path<String>,
+    req: web::Json<AddTagsRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if let Err(response) = check_can_edit(&repo, &document_id, user_id, &http_req).await {
+        return response;
+    }
+
+    match repo.add_tags(&document_id, &req.tags, user_id).await {
+        Ok(tags) => tags_response(document_id, Ok(tags)),
+        Err(e @ TagError::Empty) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("VALIDATION_ERROR", &e.to_string()))
+        },
+        Err(e @ TagError::TooLong { .. }) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("TAG_TOO_LONG", &e.to_string()))
+        },
+        Err(e @ TagError::LimitReached { .. }) => {
+            HttpResponse::BadRequest().json(ApiResponse::<()>::error("TAG_LIMIT", &e.to_string()))
+        },
+        Err(TagError::Database(e)) => tags_response(document_id, Err(e)),
+    }
+}
+
+/// Remove a tag from a document; removing a missing tag is a no-op
+pub async fn remove_tag(
+    path: web::Path<(String, String)>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let (document_id, tag) = path.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if let Err(response) = check_can_edit(&repo, &document_id, user_id, &http_req).await {
+        return response;
+    }
+
+    if let Err(e) = repo.remove_tag(&document_id, &normalize_tag(&tag)).await {
+        return tags_response(document_id, Err(e));
+    }
+
+    let result = repo.list_tags(&document_id).await;
+    tags_response(document_id, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_tag_trims_and_lowercases() {
+        assert_eq!(normalize_tag("  Draft "), "draft");
+        assert_eq!(normalize_tag("draft"), normalize_tag("DRAFT"));
+    }
+
+    #[test]
+    fn test_normalize_tags_drops_near_duplicates() {
+        let normalized = normalize_tags(&tags(&["Draft", "draft ", "Review"]), &TagLimits::default()).unwrap();
+        assert_eq!(normalized, tags(&["draft", "review"]));
+    }
+
+    #[test]
+    fn test_normalize_tags_rejects_empty_and_long_tags() {
+        let limits = TagLimits {
+            max_length: 5,
+            max_count: 10,
+        };
+        assert!(matches!(normalize_tags(&tags(&["  "]), &limits), Err(TagError::Empty)));
+        assert!(matches!(
+            normalize_tags(&tags(&["abcdef"]), &limits),
+            Err(TagError::TooLong { max_length: 5, .. })
+        ));
+        // Length is counted in characters, not bytes
+        assert!(normalize_tags(&tags(&["ärger"]), &limits).is_ok());
+    }
+}
@@ -0,0 +1,201 @@
+//! Document Template Handlers
+//!
+//! Provides HTTP handlers for space-scoped page templates:
+//! - POST /spaces/{spaceId}/templates - Save a template
+//! - GET /spaces/{spaceId}/templates - List a space's templates
+//! - POST /templates/{templateId}/instantiate - Create a document from a template
+//!
+//! Instantiating copies the template's title and content into a new document
+//! in the template's space; later edits to the template don't affect
+//! documents already created from it.
+use actix_web::{web, HttpResponse, Responder};
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::has_permission;
+use tracing::error;
+use validator::Validate;
+use uuid::Uuid;
+
+use crate::handlers::{document_row_to_response, extract_user_id, unauthorized_response};
+use crate::models::*;
+use crate::repository::{DocumentRepository, DocumentTemplateRow};
+
+fn template_row_to_response(row: &DocumentTemplateRow) -> TemplateResponse {
+    TemplateResponse {
+        id: row.id.to_string(),
+        space_id: row.space_id.to_string(),
+        title: row.title.clone(),
+        content: row.content.0.clone(),
+        created_by: row.created_by.to_string(),
+        created_at: row.created_at.and_utc().to_rfc3339(),
+    }
+}
+
+fn database_error() -> HttpResponse {
+    HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+        "DATABASE_ERROR",
+        "A database error occurred. Please try again later.",
+    ))
+}
+
+// Saving and using templates both create content in the space
+async fn check_can_create(repo: &DocumentRepository, space_id: &str, user_id: Uuid) -> Result<(), HttpResponse> {
+    match repo.get_member_role(space_id, user_id).await {
+        Ok(Some(role)) if has_permission(&role, Permission::CreateDocuments) => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "PERMISSION_DENIED",
+            "You don't have permission to create documents in this space",
+        ))),
+        Ok(None) => Err(HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "ACCESS_DENIED",
+            "You are not a member of this space",
+        ))),
+        Err(e) => {
+            error!("Database error checking space role: {:?}", e);
+            Err(database_error())
+        },
+    }
+}
+
+/// Save a template in a space
+pub async fn create_template(
+    space_id: web::Path<String>,
+    req: web::Json<CreateTemplateRequest>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    if let Err(validation_errors) = req.validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if let Err(response) = check_can_create(&repo, &space_id, user_id).await {
+        return response;
+    }
+
+    let content = req.content.clone().unwrap_or_else(|| serde_json::json!({}));
+    match repo.create_template(&space_id, &req.title, content, user_id).await {
+        Ok(template) => {
+            HttpResponse::Created().json(ApiResponse::<TemplateResponse>::success(template_row_to_response(&template)))
+        },
+        Err(e) => {
+            error!("Database error creating template: {:?}", e);
+            database_error()
+        },
+    }
+}
+
+/// List a space's templates
+pub async fn list_templates(
+    space_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let space_id = space_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.check_space_access(&space_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+                "ACCESS_DENIED",
+                "You don't have access to this space",
+            ));
+        },
+        Err(e) => {
+            error!("Database error checking space access: {:?}", e);
+            return database_error();
+        },
+    }
+
+    match repo.list_templates(&space_id).await {
+        Ok(templates) => HttpResponse::Ok().json(ApiResponse::<TemplateListResponse>::success(TemplateListResponse {
+            templates: templates.iter().map(template_row_to_response).collect(),
+        })),
+        Err(e) => {
+            error!("Database error listing templates: {:?}", e);
+            database_error()
+        },
+    }
+}
+
+/// Create a document from a template
+pub async fn instantiate_template(
+    template_id: web::Path<String>,
+    req: Option<web::Json<InstantiateTemplateRequest>>,
+    repo: web::Data<DocumentRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let template_id = template_id.into_inner();
+    let req = req.map(|r| r.into_inner()).unwrap_or_default();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    let space_id = match repo.get_template(&template_id).await {
+        Ok(Some(template)) => template.space_id.to_string(),
+        Ok(None) => {
+            return HttpResponse::NotFound().json(ApiResponse::<()>::error(
+                "TEMPLATE_NOT_FOUND",
+                "Template not found",
+            ));
+        },
+        Err(e) => {
+            error!("Database error loading template: {:?}", e);
+            return database_error();
+        },
+    };
+
+    if let Err(response) = check_can_create(&repo, &space_id, user_id).await {
+        return response;
+    }
+
+    // The parent must be a live document in the template's space
+    if let Some(parent_id) = req.parent_id.as_deref() {
+        match repo.get_by_id(parent_id).await {
+            Ok(Some(parent)) if parent.space_id.to_string() == space_id && !parent.is_archived => {},
+            Ok(_) => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "INVALID_PARENT",
+                    "Parent document not found in the template's space",
+                ));
+            },
+            Err(e) => {
+                error!("Database error loading parent document: {:?}", e);
+                return database_error();
+            },
+        }
+    }
+
+    match repo
+        .instantiate_template(&template_id, req.parent_id.as_deref(), user_id)
+        .await
+    {
+        Ok(Some(document)) => {
+            HttpResponse::Created().json(ApiResponse::<CreateDocumentResponse>::success(CreateDocumentResponse {
+                id: document.id.to_string(),
+                message: "Document created from template".to_string(),
+                document: document_row_to_response(&document),
+            }))
+        },
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error(
+            "TEMPLATE_NOT_FOUND",
+            "Template not found",
+        )),
+        Err(e) => {
+            error!("Database error instantiating template: {:?}", e);
+            database_error()
+        },
+    }
+}
@@ -0,0 +1,160 @@
+//! Document Text Metrics
+//!
+//! Provides `GET /documents/{documentId}/metrics`, reporting word count,
+//! character count and estimated reading time for a document. Counts are
+//! taken from the same plain text the exporter extracts; reading time
+//! divides the word count by a reading speed (`DOCUMENT_READING_WPM`).
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::export::extract_plain_text;
+use crate::handlers::{document_access_denied, extract_user_id, unauthorized_response};
+use crate::models::ApiResponse;
+use crate::repository::DocumentRepository;
+
+/// Default reading speed in words per minute
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 200;
+
+/// Reading speed used for reading-time estimates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingSpeed {
+    pub words_per_minute: u32,
+}
+
+impl Default for ReadingSpeed {
+    fn default() -> Self {
+        Self {
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+        }
+    }
+}
+
+impl ReadingSpeed {
+    /// Read `DOCUMENT_READING_WPM`, falling back to the default for missing
+    /// or invalid values
+    pub fn from_env() -> Self {
+        let words_per_minute = std::env::var("DOCUMENT_READING_WPM")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_WORDS_PER_MINUTE);
+
+        Self { words_per_minute }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentTextMetrics {
+    pub word_count: usize,
+    /// Characters excluding whitespace
+    pub character_count: usize,
+    /// Estimated reading time, rounded to the nearest second
+    pub reading_time_seconds: u64,
+    /// Estimated reading time, rounded up to whole minutes
+    pub reading_time_minutes: u64,
+    pub words_per_minute: u32,
+}
+
+impl DocumentTextMetrics {
+    /// Compute metrics for extracted plain text
+    pub fn from_text(text: &str, speed: ReadingSpeed) -> Self {
+        let word_count = text.split_whitespace().count();
+        let character_count = text.chars().filter(|c| !c.is_whitespace()).count();
+        let wpm = u64::from(speed.words_per_minute.max(1));
+        let words = word_count as u64;
+
+        Self {
+            word_count,
+            character_count,
+            reading_time_seconds: (words * 60 + wpm / 2) / wpm,
+            reading_time_minutes: words.div_ceil(wpm),
+            words_per_minute: speed.words_per_minute,
+        }
+    }
+}
+
+/// Word count, character count and reading time for a document
+pub async fn get_document_metrics(
+    document_id: web::Path<String>,
+    repo: web::Data<DocumentRepository>,
+    speed: Option<web::Data<ReadingSpeed>>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let document_id = document_id.into_inner();
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    match repo.check_document_access(&document_id, user_id).await {
+        Ok(true) => {},
+        Ok(false) => return document_access_denied(&http_req),
+        Err(e) => {
+            error!("Database error checking document access: {:?}", e);
+            return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ));
+        },
+    }
+
+    let speed = speed.map(|s| **s.as_ref()).unwrap_or_default();
+
+    match repo.get_by_id(&document_id).await {
+        Ok(Some(document)) => {
+            let text = extract_plain_text(&document.content.0);
+            HttpResponse::Ok().json(ApiResponse::<DocumentTextMetrics>::success(DocumentTextMetrics::from_text(
+                &text, speed,
+            )))
+        },
+        Ok(None) => HttpResponse::NotFound().json(ApiResponse::<()>::error("DOC_NOT_FOUND", "Document not found")),
+        Err(e) => {
+            error!("Database error getting document: {:?}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "DATABASE_ERROR",
+                "A database error occurred. Please try again later.",
+            ))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_for_known_text() {
+        let content = serde_json::json!({"text": "The quick brown fox jumps over the lazy dog"});
+        let text = extract_plain_text(&content);
+        let metrics = DocumentTextMetrics::from_text(&text, ReadingSpeed { words_per_minute: 3 });
+
+        assert_eq!(metrics.word_count, 9);
+        assert_eq!(metrics.character_count, 35);
+        assert_eq!(metrics.reading_time_seconds, 180);
+        assert_eq!(metrics.reading_time_minutes, 3);
+        assert_eq!(metrics.words_per_minute, 3);
+    }
+
+    #[test]
+    fn test_reading_time_rounds_partial_minutes_up() {
+        let text = vec!["word"; 250].join(" ");
+        let metrics = DocumentTextMetrics::from_text(&text, ReadingSpeed::default());
+
+        assert_eq!(metrics.word_count, 250);
+        assert_eq!(metrics.reading_time_seconds, 75);
+        assert_eq!(metrics.reading_time_minutes, 2);
+    }
+
+    #[test]
+    fn test_empty_document_reports_zeros() {
+        for content in [serde_json::json!({}), serde_json::json!({"text": ""}), serde_json::json!({"text": "  \n "})] {
+            let metrics = DocumentTextMetrics::from_text(&extract_plain_text(&content), ReadingSpeed::default());
+            assert_eq!(metrics.word_count, 0);
+            assert_eq!(metrics.character_count, 0);
+            assert_eq!(metrics.reading_time_seconds, 0);
+            assert_eq!(metrics.reading_time_minutes, 0);
+        }
+    }
+}
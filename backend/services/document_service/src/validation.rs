@@ -17,6 +17,8 @@ pub enum DocumentValidationError {
     InvalidVersionNumber,
     #[error("Change summary must be at most 500 characters")]
     InvalidChangeSummary,
+    #[error("Content must be a JSON object")]
+    InvalidContent,
 }
 
 pub fn validate_create_document(req: &CreateDocumentRequest) -> Result<(), DocumentValidationError> {
@@ -82,6 +84,19 @@ pub fn validate_create_version(req: &CreateVersionRequest) -> Result<(), Documen
     Ok(())
 }
 
+/// Validate content produced by applying a JSON Patch
+pub fn validate_patched_content(content: &serde_json::Value) -> Result<(), DocumentValidationError> {
+    if !content.is_object() {
+        return Err(DocumentValidationError::InvalidContent);
+    }
+
+    if content.to_string().len() > 10_485_760 {
+        return Err(DocumentValidationError::ContentTooLarge);
+    }
+
+    Ok(())
+}
+
 pub fn validate_uuid(uuid: &str) -> Result<(), DocumentValidationError> {
     Uuid::parse_str(uuid)
         .map(|_| ())
@@ -139,6 +154,7 @@ mod tests {
             title: Some("Updated Title".to_string()),
             icon: None,
             content: None,
+            version: None,
         };
         assert!(validate_update_document(&req).is_ok());
     }
@@ -149,6 +165,7 @@ mod tests {
             title: None,
             icon: Some("📄".to_string()),
             content: None,
+            version: None,
         };
         assert!(validate_update_document(&req).is_ok());
     }
@@ -185,4 +202,18 @@ mod tests {
         assert!(validate_version_number(0).is_err());
         assert!(validate_version_number(-1).is_err());
     }
+
+    #[test]
+    fn test_validate_patched_content() {
+        assert!(validate_patched_content(&serde_json::json!({"text": "ok"})).is_ok());
+        assert!(matches!(
+            validate_patched_content(&serde_json::json!(["not", "an", "object"])),
+            Err(DocumentValidationError::InvalidContent)
+        ));
+        let huge = serde_json::json!({"text": "x".repeat(10_485_761)});
+        assert!(matches!(
+            validate_patched_content(&huge),
+            Err(DocumentValidationError::ContentTooLarge)
+        ));
+    }
 }
@@ -0,0 +1,423 @@
+//! Outgoing webhook delivery
+//!
+//! Every delivery is bounded by a timeout, configurable per webhook, so a
+//! slow endpoint cannot hold a worker indefinitely. Each webhook also has a
+//! circuit breaker: after `failure_threshold` consecutive failures (errors,
+//! non-2xx responses or timeouts) it opens and deliveries to that endpoint are
+//! skipped until `cooldown` has passed. The next delivery is then sent as a
+//! single probe; success closes the breaker, failure opens it for another
+//! cooldown. [`WebhookDispatcher::health`] reports the breaker state so
+//! unhealthy endpoints can be surfaced to their owners.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Default time allowed for a single delivery in milliseconds
+pub const DEFAULT_DELIVERY_TIMEOUT_MS: u64 = 5_000;
+
+/// Default consecutive failures that open a webhook's breaker
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default seconds an open breaker waits before allowing a probe
+pub const DEFAULT_BREAKER_COOLDOWN_SECS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub delivery_timeout: Duration,
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            delivery_timeout: Duration::from_millis(DEFAULT_DELIVERY_TIMEOUT_MS),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            cooldown: Duration::from_secs(DEFAULT_BREAKER_COOLDOWN_SECS),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Read `WEBHOOK_DELIVERY_TIMEOUT_MS`, `WEBHOOK_FAILURE_THRESHOLD` and
+    /// `WEBHOOK_BREAKER_COOLDOWN_SECS`, falling back to the defaults for
+    /// missing or invalid values
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let delivery_timeout = std::env::var("WEBHOOK_DELIVERY_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.delivery_timeout);
+        let failure_threshold = std::env::var("WEBHOOK_FAILURE_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(defaults.failure_threshold);
+        let cooldown = std::env::var("WEBHOOK_BREAKER_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.cooldown);
+
+        Self {
+            delivery_timeout,
+            failure_threshold,
+            cooldown,
+        }
+    }
+}
+
+/// Destination of a webhook
+#[derive(Debug, Clone)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub url: String,
+    /// Overrides [`WebhookConfig::delivery_timeout`] for this webhook
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Error)]
+pub enum DeliveryError {
+    #[error("Request failed: {0}")]
+    Request(String),
+    #[error("Endpoint responded with status {0}")]
+    Status(u16),
+    #[error("Delivery timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Circuit open, next attempt in {0:?}")]
+    CircuitOpen(Duration),
+}
+
+/// Sends a payload to an endpoint
+#[async_trait]
+pub trait WebhookTransport {
+    async fn send(&self, url: &str, payload: &serde_json::Value) -> Result<(), DeliveryError>;
+}
+
+/// JSON POST over HTTP; any 2xx response counts as delivered
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+impl HttpTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+#[async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn send(&self, url: &str, payload: &serde_json::Value) -> Result<(), DeliveryError> {
+        let response = self
+            .client
+            .post(url)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| DeliveryError::Request(e.to_string()))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(DeliveryError::Status(response.status().as_u16()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    /// Cooldown has passed and a probe delivery is in flight
+    HalfOpen,
+}
+
+/// Delivery health of one webhook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointHealth {
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    /// Seconds until a probe is allowed while the breaker is open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl EndpointHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.state == BreakerState::Closed
+    }
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+impl Breaker {
+    fn state(&self, now: Instant) -> BreakerState {
+        match self.open_until {
+            _ if self.probing => BreakerState::HalfOpen,
+            Some(until) if now < until => BreakerState::Open,
+            // Cooldown over; the next delivery becomes the probe
+            Some(_) => BreakerState::HalfOpen,
+            None => BreakerState::Closed,
+        }
+    }
+}
+
+/// Delivers webhooks with per-endpoint timeouts and circuit breakers
+///
+/// Breaker state lives in memory, so share one dispatcher between workers.
+pub struct WebhookDispatcher<T> {
+    transport: T,
+    config: WebhookConfig,
+    breakers: Mutex<HashMap<Uuid, Breaker>>,
+}
+
+impl<T: WebhookTransport> WebhookDispatcher<T> {
+    pub fn new(transport: T, config: WebhookConfig) -> Self {
+        Self {
+            transport,
+            config,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Deliver `payload` unless the endpoint's breaker is open
+    pub async fn deliver(&self, endpoint: &WebhookEndpoint, payload: &serde_json::Value) -> Result<(), DeliveryError> {
+        self.acquire(endpoint.id)?;
+
+        let timeout = endpoint.timeout.unwrap_or(self.config.delivery_timeout);
+        let result = match tokio::time::timeout(timeout, self.transport.send(&endpoint.url, payload)).await {
+            Ok(result) => result,
+            Err(_) => Err(DeliveryError::Timeout(timeout)),
+        };
+
+        match &result {
+            Ok(()) => self.record_success(endpoint.id),
+            Err(e) => {
+                tracing::warn!("Webhook {} delivery to {} failed: {}", endpoint.id, endpoint.url, e);
+                self.record_failure(endpoint.id);
+            },
+        }
+        result
+    }
+
+    pub fn health(&self, webhook_id: Uuid) -> EndpointHealth {
+        let now = Instant::now();
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        match breakers.get(&webhook_id) {
+            Some(breaker) => EndpointHealth {
+                state: breaker.state(now),
+                consecutive_failures: breaker.consecutive_failures,
+                retry_after_secs: breaker
+                    .open_until
+                    .filter(|until| *until > now)
+                    .map(|until| until.duration_since(now).as_secs().max(1)),
+            },
+            None => EndpointHealth {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                retry_after_secs: None,
+            },
+        }
+    }
+
+    // Decide whether a delivery may go out, claiming the probe slot when
+    // the cooldown has passed
+    fn acquire(&self, webhook_id: Uuid) -> Result<(), DeliveryError> {
+        let now = Instant::now();
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(webhook_id).or_default();
+
+        match breaker.state(now) {
+            BreakerState::Closed => Ok(()),
+            BreakerState::Open => Err(DeliveryError::CircuitOpen(
+                breaker.open_until.map(|until| until - now).unwrap_or_default(),
+            )),
+            BreakerState::HalfOpen if breaker.probing => Err(DeliveryError::CircuitOpen(Duration::ZERO)),
+            BreakerState::HalfOpen => {
+                breaker.probing = true;
+                Ok(())
+            },
+        }
+    }
+
+    fn record_success(&self, webhook_id: Uuid) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(breaker) = breakers.get_mut(&webhook_id) {
+            if breaker.open_until.is_some() {
+                tracing::info!("Webhook {} recovered, closing circuit", webhook_id);
+            }
+            *breaker = Breaker::default();
+        }
+    }
+
+    fn record_failure(&self, webhook_id: Uuid) {
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers.entry(webhook_id).or_default();
+        breaker.consecutive_failures += 1;
+
+        // A failed probe reopens immediately
+        if breaker.probing || breaker.consecutive_failures >= self.config.failure_threshold {
+            tracing::warn!(
+                "Webhook {} circuit opened after {} consecutive failures",
+                webhook_id,
+                breaker.consecutive_failures
+            );
+            breaker.open_until = Some(Instant::now() + self.config.cooldown);
+            breaker.probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    // Transport whose outcome can be switched, counting calls that reach it
+    #[derive(Default)]
+    struct ScriptedTransport {
+        calls: AtomicUsize,
+        healthy: AtomicBool,
+        delay: Option<Duration>,
+    }
+
+    #[async_trait]
+    impl WebhookTransport for ScriptedTransport {
+        async fn send(&self, _url: &str, _payload: &serde_json::Value) -> Result<(), DeliveryError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+            if self.healthy.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err(DeliveryError::Status(500))
+            }
+        }
+    }
+
+    fn endpoint() -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: Uuid::new_v4(),
+            url: "https://hooks.example.com/miniwiki".to_string(),
+            timeout: None,
+        }
+    }
+
+    fn config(cooldown: Duration) -> WebhookConfig {
+        WebhookConfig {
+            delivery_timeout: Duration::from_secs(1),
+            failure_threshold: 3,
+            cooldown,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_endpoint_trips_breaker() {
+        let dispatcher = WebhookDispatcher::new(ScriptedTransport::default(), config(Duration::from_secs(60)));
+        let endpoint = endpoint();
+        let payload = serde_json::json!({"event": "document.updated"});
+
+        for _ in 0..3 {
+            assert!(matches!(
+                dispatcher.deliver(&endpoint, &payload).await,
+                Err(DeliveryError::Status(500))
+            ));
+        }
+
+        let health = dispatcher.health(endpoint.id);
+        assert_eq!(health.state, BreakerState::Open);
+        assert!(!health.is_healthy());
+        assert_eq!(health.consecutive_failures, 3);
+        assert!(health.retry_after_secs.is_some());
+
+        // Deliveries are paused without reaching the endpoint
+        assert!(matches!(
+            dispatcher.deliver(&endpoint, &payload).await,
+            Err(DeliveryError::CircuitOpen(_))
+        ));
+        assert_eq!(dispatcher.transport.calls.load(Ordering::SeqCst), 3);
+
+        // Other webhooks are unaffected
+        assert!(dispatcher.health(Uuid::new_v4()).is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_probe_after_cooldown_closes_breaker() {
+        let dispatcher = WebhookDispatcher::new(ScriptedTransport::default(), config(Duration::from_millis(50)));
+        let endpoint = endpoint();
+        let payload = serde_json::json!({"event": "document.updated"});
+
+        for _ in 0..3 {
+            let _ = dispatcher.deliver(&endpoint, &payload).await;
+        }
+        assert_eq!(dispatcher.health(endpoint.id).state, BreakerState::Open);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(dispatcher.health(endpoint.id).state, BreakerState::HalfOpen);
+
+        dispatcher.transport.healthy.store(true, Ordering::SeqCst);
+        assert!(dispatcher.deliver(&endpoint, &payload).await.is_ok());
+        assert_eq!(dispatcher.transport.calls.load(Ordering::SeqCst), 4);
+
+        let health = dispatcher.health(endpoint.id);
+        assert_eq!(health.state, BreakerState::Closed);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_reopens_breaker() {
+        let dispatcher = WebhookDispatcher::new(ScriptedTransport::default(), config(Duration::from_millis(50)));
+        let endpoint = endpoint();
+        let payload = serde_json::json!({"event": "document.updated"});
+
+        for _ in 0..3 {
+            let _ = dispatcher.deliver(&endpoint, &payload).await;
+        }
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        assert!(dispatcher.deliver(&endpoint, &payload).await.is_err());
+        assert_eq!(dispatcher.health(endpoint.id).state, BreakerState::Open);
+        assert!(matches!(
+            dispatcher.deliver(&endpoint, &payload).await,
+            Err(DeliveryError::CircuitOpen(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_endpoint_times_out() {
+        let transport = ScriptedTransport {
+            delay: Some(Duration::from_secs(5)),
+            healthy: AtomicBool::new(true),
+            ..Default::default()
+        };
+        let dispatcher = WebhookDispatcher::new(transport, config(Duration::from_secs(60)));
+        let endpoint = WebhookEndpoint {
+            timeout: Some(Duration::from_millis(20)),
+            ..endpoint()
+        };
+
+        let result = dispatcher.deliver(&endpoint, &serde_json::json!({})).await;
+        assert!(matches!(result, Err(DeliveryError::Timeout(t)) if t == Duration::from_millis(20)));
+        assert_eq!(dispatcher.health(endpoint.id).consecutive_failures, 1);
+    }
+}
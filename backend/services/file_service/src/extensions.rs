@@ -0,0 +1,109 @@
+//! Per-space file extension allowlist
+//!
+//! On top of the global MIME type checks, a space may restrict uploads to a
+//! list of file extensions (`spaces.allowed_extensions`). A NULL list keeps
+//! the global behaviour. Matching is case-insensitive; when a list is set,
+//! files without an extension are rejected.
+
+use crate::models::ErrorResponse;
+use actix_web::HttpResponse;
+use shared_models::extensions::normalize_extension;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Lowercase extension of a file name, without the dot
+///
+/// Names without a dot, ending in a dot, or made of a single leading dot
+/// (such as `.env`) have no extension.
+pub fn file_extension(file_name: &str) -> Option<String> {
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    if stem.is_empty() || extension.is_empty() {
+        return None;
+    }
+    Some(extension.to_lowercase())
+}
+
+/// Whether `file_name` may be uploaded given a space's allowlist
+pub fn is_extension_allowed(file_name: &str, allowed: Option<&[String]>) -> bool {
+    let Some(allowed) = allowed else {
+        return true;
+    };
+    match file_extension(file_name) {
+        Some(extension) => allowed.iter().any(|a| normalize_extension(a).as_ref() == Some(&extension)),
+        None => false,
+    }
+}
+
+/// The space's extension allowlist; `None` when unrestricted or the space doesn't exist
+pub async fn space_allowed_extensions(pool: &PgPool, space_id: Uuid) -> Result<Option<Vec<String>>, sqlx::Error> {
+    let allowed = sqlx::query_scalar!(r#"SELECT allowed_extensions FROM spaces WHERE id = $1"#, space_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(allowed.flatten())
+}
+
+/// Check `file_name` against the space's allowlist, producing the error response on rejection
+pub async fn check_extension(pool: &PgPool, space_id: Uuid, file_name: &str) -> Result<(), HttpResponse> {
+    let allowed = space_allowed_extensions(pool, space_id).await.map_err(|e| {
+        HttpResponse::InternalServerError().json(ErrorResponse {
+            code: "DATABASE_ERROR".to_string(),
+            message: format!("Failed to load space upload settings: {}", e),
+            details: None,
+        })
+    })?;
+
+    if is_extension_allowed(file_name, allowed.as_deref()) {
+        return Ok(());
+    }
+
+    Err(HttpResponse::UnsupportedMediaType().json(ErrorResponse {
+        code: "EXTENSION_NOT_ALLOWED".to_string(),
+        message: match file_extension(file_name) {
+            Some(extension) => format!("Files with extension '.{}' are not allowed in this space", extension),
+            None => "Files without an extension are not allowed in this space".to_string(),
+        },
+        details: allowed.map(|allowed| serde_json::json!({ "allowed_extensions": allowed })),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(extensions: &[&str]) -> Vec<String> {
+        extensions.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allowed_extension_case_insensitive() {
+        let allowed = list(&["pdf", "PNG", ".jpg"]);
+        assert!(is_extension_allowed("report.pdf", Some(&allowed)));
+        assert!(is_extension_allowed("Photo.JPG", Some(&allowed)));
+        assert!(is_extension_allowed("diagram.png", Some(&allowed)));
+    }
+
+    #[test]
+    fn test_disallowed_extension() {
+        let allowed = list(&["pdf", "png"]);
+        assert!(!is_extension_allowed("setup.exe", Some(&allowed)));
+        assert!(!is_extension_allowed("backup.tar.gz", Some(&allowed)));
+        // Only the last extension counts
+        assert!(!is_extension_allowed("invoice.pdf.exe", Some(&allowed)));
+    }
+
+    #[test]
+    fn test_no_extension() {
+        let allowed = list(&["pdf"]);
+        assert_eq!(file_extension("README"), None);
+        assert_eq!(file_extension(".env"), None);
+        assert_eq!(file_extension("trailing."), None);
+        assert!(!is_extension_allowed("README", Some(&allowed)));
+        assert!(!is_extension_allowed(".pdf", Some(&allowed)));
+    }
+
+    #[test]
+    fn test_unrestricted_space_allows_everything() {
+        assert!(is_extension_allowed("setup.exe", None));
+        assert!(is_extension_allowed("README", None));
+    }
+}
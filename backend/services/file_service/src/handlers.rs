@@ -1,10 +1,16 @@
+use crate::extensions::check_extension;
+use crate::image_metadata::{strip_upload, ImageMetadataPolicy};
 use crate::models::*;
+use crate::repository::{find_version, find_with_uploader, insert_file, list_versions, InsertFileError, NewFile};
+use crate::scanner::{scan_upload, FileScanner};
 use crate::storage::S3Storage;
 use actix_web::http::header::HeaderMap;
-use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use chrono::Utc;
 use futures_util::stream::StreamExt;
+use shared_database::soft_delete;
 use shared_errors::AppError;
+use shared_models::pagination::next_offset;
 use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -16,6 +22,28 @@ pub const FIELD_SPACE_ID: &str = "space_id";
 pub const FIELD_DOCUMENT_ID: &str = "document_id";
 pub const FIELD_FILE_NAME: &str = "file_name";
 
+/// Upload size limit used when no `UploadLimits` is registered (50MB)
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Upload limits shared with handlers through app_data
+#[derive(Debug, Clone, Copy)]
+pub struct UploadLimits {
+    pub max_file_size: u64,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self { max_file_size: DEFAULT_MAX_FILE_SIZE }
+    }
+}
+
+impl UploadLimits {
+    /// Resolve limits from optional app data, falling back to the defaults
+    pub fn resolve(limits: Option<&web::Data<UploadLimits>>) -> Self {
+        limits.map(|l| **l.as_ref()).unwrap_or_default()
+    }
+}
+
 /// Extract boundary from content-type header
 pub fn extract_boundary(headers: &HeaderMap) -> Option<String> {
     let content_type = headers.get("content-type")?.to_str().ok()?;
@@ -25,23 +53,25 @@ pub fn extract_boundary(headers: &HeaderMap) -> Option<String> {
 }
 
 /// Extract user ID from request for authentication context
-/// First tries to get from request extensions (set by JWT middleware), falls back to X-User-Id header
-pub fn extract_user_id(req: &HttpRequest) -> Result<Uuid, AppError> {
-    // Try to get from request extensions first (set by JWT middleware)
-    if let Some(user_uuid) = req.extensions().get::<Uuid>() {
-        return Ok(*user_uuid);
-    }
-
-    // Fallback to X-User-Id header
-    req.headers()
-        .get("X-User-Id")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| Uuid::parse_str(s).ok())
-        .ok_or_else(|| {
-            AppError::AuthenticationError(
-                "User ID not found in request. Provide valid JWT token or X-User-Id header.".to_string(),
-            )
-        })
+/// Uses the ID set by authentication middleware, a Bearer token or the X-User-Id header, in that order
+pub async fn extract_user_id(req: &HttpRequest) -> Result<Uuid, AppError> {
+    auth_service::identity::extract_request_user_id(req).await
+}
+
+/// Response for a file record that could not be saved
+fn insert_file_error_response(e: InsertFileError) -> HttpResponse {
+    match e {
+        InsertFileError::ConcurrentVersion => HttpResponse::Conflict().json(ErrorResponse {
+            code: "FILE_VERSION_CONFLICT".to_string(),
+            message: e.to_string(),
+            details: None,
+        }),
+        InsertFileError::Database(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            code: "DATABASE_ERROR".to_string(),
+            message: format!("Failed to save file record: {}", e),
+            details: None,
+        }),
+    }
 }
 
 /// Upload file handler - POST /api/v1/files/upload
@@ -49,8 +79,13 @@ pub async fn upload_file(
     payload: web::Payload,
     pool: web::Data<PgPool>,
     storage: web::Data<Arc<S3Storage>>,
+    limits: Option<web::Data<UploadLimits>>,
+    metadata_policy: Option<web::Data<ImageMetadataPolicy>>,
+    scanner: Option<web::Data<Arc<dyn FileScanner>>>,
     req: actix_web::HttpRequest,
 ) -> impl Responder {
+    let max_file_size = UploadLimits::resolve(limits.as_ref()).max_file_size;
+    let metadata_policy = ImageMetadataPolicy::resolve(metadata_policy.as_ref());
     let _boundary = match extract_boundary(req.headers()) {
         Some(b) => b,
         None => {
@@ -115,14 +150,13 @@ pub async fn upload_file(
                 let ct: Option<String> = field.content_type().map(|ct: &mime::Mime| ct.to_string());
                 content_type = ct;
 
-                const MAX_FILE_SIZE: usize = 50 * 1024 * 1024;
                 let mut bytes = Vec::new();
                 while let Some(chunk_result) = field.next().await {
                     if let Ok(data) = chunk_result {
-                        if bytes.len() + data.len() > MAX_FILE_SIZE {
+                        if (bytes.len() + data.len()) as u64 > max_file_size {
                             return HttpResponse::PayloadTooLarge().json(ErrorResponse {
                                 code: "FILE_TOO_LARGE".to_string(),
-                                message: format!("File exceeds maximum size of {} bytes", MAX_FILE_SIZE),
+                                message: format!("File exceeds maximum size of {} bytes", max_file_size),
                                 details: None,
                             });
                         }
@@ -173,9 +207,13 @@ pub async fn upload_file(
         });
     }
 
-    // Validate file size (50MB limit)
-    const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
-    if let Err(e) = S3Storage::validate_file_size(file_size as u64, MAX_FILE_SIZE) {
+    // The space may restrict which extensions it accepts
+    if let Err(response) = check_extension(pool.as_ref(), space_id, &file_name).await {
+        return response;
+    }
+
+    // Validate file size against the configured limit
+    if let Err(e) = S3Storage::validate_file_size(file_size as u64, max_file_size) {
         return HttpResponse::PayloadTooLarge().json(ErrorResponse {
             code: "FILE_TOO_LARGE".to_string(),
             message: e.to_string(),
@@ -183,6 +221,14 @@ pub async fn upload_file(
         });
     }
 
+    // Strip EXIF and other metadata from photos; size and checksum below
+    // are taken from the bytes actually stored
+    let file_content = match strip_upload(&metadata_policy, &space_id, file_content, &content_type).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+    let file_size = file_content.len() as i64;
+
     // Generate storage path and upload to S3
     let file_id = Uuid::new_v4();
     let storage_path = format!("{}/{}/{}", space_id, file_id, file_name);
@@ -195,6 +241,11 @@ pub async fn upload_file(
         });
     }
 
+    // Nothing is recorded, and so nothing is downloadable, until the scan passes
+    if let Err(response) = scan_upload(scanner.as_ref(), &storage, &storage_path, &file_content).await {
+        return response;
+    }
+
     // Generate download URL
     let download_url = match storage.presigned_download_url(&storage_path, 900).await {
         Ok(url) => url,
@@ -203,49 +254,38 @@ pub async fn upload_file(
 
     let bucket = storage.bucket().to_string();
 
-    // Insert file record into database
-    let file_record = match sqlx::query_as!(
-        File,
-        r#"
-        INSERT INTO files (
-            id, space_id, document_id, uploaded_by, file_name,
-            file_type, file_size, storage_path, storage_bucket,
-            checksum, is_deleted, deleted_at, created_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, false, NULL, NOW())
-        RETURNING *
-        "#,
-        file_id,
+    let uploaded_by = match extract_user_id(&req).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            let _ = storage.delete_file(&storage_path).await;
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                code: "AUTHENTICATION_ERROR".to_string(),
+                message: e.to_string(),
+                details: None,
+            });
+        },
+    };
+
+    // Insert file record into database; a name already present in the
+    // space/document becomes its next version
+    let new_file = NewFile {
+        id: file_id,
         space_id,
         document_id,
-        // Extract user_id from authentication context
-        match extract_user_id(&req) {
-            Ok(user_id) => user_id,
-            Err(e) => {
-                return HttpResponse::Unauthorized().json(ErrorResponse {
-                    code: "AUTHENTICATION_ERROR".to_string(),
-                    message: e.to_string(),
-                    details: None,
-                });
-            },
-        },
+        uploaded_by,
         file_name,
-        content_type,
+        file_type: content_type,
         file_size,
-        storage_path,
-        bucket,
-        format!("{:x}", md5::compute(&file_content))
-    )
-    .fetch_one(pool.as_ref())
-    .await
-    {
+        storage_path: storage_path.clone(),
+        storage_bucket: bucket,
+        checksum: format!("{:x}", md5::compute(&file_content)),
+    };
+
+    let file_record = match insert_file(pool.as_ref(), &new_file).await {
         Ok(record) => record,
         Err(e) => {
             let _ = storage.delete_file(&storage_path).await;
-            return HttpResponse::InternalServerError().json(ErrorResponse {
-                code: "DATABASE_ERROR".to_string(),
-                message: format!("Failed to save file record: {}", e),
-                details: None,
-            });
+            return insert_file_error_response(e);
         },
     };
 
@@ -262,7 +302,20 @@ pub async fn upload_file(
 }
 
 /// Initialize chunked upload - POST /api/v1/files/upload/chunked/init
-pub async fn init_chunked_upload(req: web::Json<InitChunkedUploadRequest>, pool: web::Data<PgPool>) -> impl Responder {
+pub async fn init_chunked_upload(
+    req: web::Json<InitChunkedUploadRequest>,
+    pool: web::Data<PgPool>,
+    limits: Option<web::Data<UploadLimits>>,
+) -> impl Responder {
+    let max_file_size = UploadLimits::resolve(limits.as_ref()).max_file_size;
+    if let Err(e) = S3Storage::validate_file_size(req.total_size, max_file_size) {
+        return HttpResponse::PayloadTooLarge().json(ErrorResponse {
+            code: "FILE_TOO_LARGE".to_string(),
+            message: e.to_string(),
+            details: None,
+        });
+    }
+
     let upload_id = Uuid::new_v4();
     let now = Utc::now();
     let expires_at = now.naive_utc() + chrono::Duration::hours(24);
@@ -432,9 +485,12 @@ pub async fn complete_chunked_upload(
     req: web::Json<CompleteChunkedUploadRequest>,
     pool: web::Data<PgPool>,
     storage: web::Data<Arc<S3Storage>>,
+    metadata_policy: Option<web::Data<ImageMetadataPolicy>>,
+    scanner: Option<web::Data<Arc<dyn FileScanner>>>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let upload_id = upload_id.into_inner();
+    let metadata_policy = ImageMetadataPolicy::resolve(metadata_policy.as_ref());
 
     let session_result = sqlx::query!(
         r#"
@@ -466,6 +522,10 @@ pub async fn complete_chunked_upload(
         },
     };
 
+    if let Err(response) = check_extension(pool.as_ref(), session.space_id, &session.file_name).await {
+        return response;
+    }
+
     let uploaded_chunks: Vec<i32> = session.uploaded_chunks.unwrap_or_default();
     let total_chunks = session.total_chunks as usize;
 
@@ -502,6 +562,29 @@ pub async fn complete_chunked_upload(
         }
     }
 
+    // The client's checksum covers the bytes it sent, so check it before stripping
+    let client_checksum = format!("{:x}", md5::compute(&assembled_content));
+    if req.checksum != client_checksum {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            code: "CHECKSUM_MISMATCH".to_string(),
+            message: "Client-provided checksum does not match computed checksum".to_string(),
+            details: Some(serde_json::json!({
+                "client_provided": req.checksum,
+                "computed": client_checksum
+            })),
+        });
+    }
+
+    // Assembled uploads get the same metadata stripping as single uploads;
+    // size and checksum below are taken from the bytes actually stored
+    let assembled_content =
+        match strip_upload(&metadata_policy, &session.space_id, assembled_content, &session.content_type).await {
+            Ok(content) => content,
+            Err(response) => return response,
+        };
+    let file_size = assembled_content.len() as i64;
+    let computed_checksum = format!("{:x}", md5::compute(&assembled_content));
+
     let file_id = Uuid::new_v4();
     let storage_path = format!("{}/{}/{}", session.space_id, file_id, session.file_name);
 
@@ -531,57 +614,36 @@ pub async fn complete_chunked_upload(
 
     let bucket = storage.bucket().to_string();
 
-    let computed_checksum = format!("{:x}", md5::compute(&assembled_content));
-
-    // Validate client-provided checksum matches computed checksum
-    if req.checksum != computed_checksum {
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            code: "CHECKSUM_MISMATCH".to_string(),
-            message: "Client-provided checksum does not match computed checksum".to_string(),
-            details: Some(serde_json::json!({
-                "client_provided": req.checksum,
-                "computed": computed_checksum
-            })),
-        });
+    if let Err(response) = scan_upload(scanner.as_ref(), &storage, &storage_path, &assembled_content).await {
+        return response;
     }
 
-    if let Err(e) = sqlx::query!(
-        r#"
-        INSERT INTO files (
-            id, space_id, document_id, uploaded_by, file_name,
-            file_type, file_size, storage_path, storage_bucket,
-            checksum, is_deleted, deleted_at, created_at
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, false, NULL, NOW())
-        "#,
-        file_id,
-        session.space_id,
-        session.document_id,
-        // Extract user_id from authentication context
-        match extract_user_id(&http_req) {
-            Ok(user_id) => user_id,
-            Err(e) => {
-                return HttpResponse::Unauthorized().json(ErrorResponse {
-                    code: "AUTHENTICATION_ERROR".to_string(),
-                    message: e.to_string(),
-                    details: None,
-                });
-            },
+    let uploaded_by = match extract_user_id(&http_req).await {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                code: "AUTHENTICATION_ERROR".to_string(),
+                message: e.to_string(),
+                details: None,
+            });
         },
-        session.file_name,
-        session.content_type,
-        session.total_size,
-        storage_path,
-        bucket,
-        computed_checksum
-    )
-    .execute(pool.as_ref())
-    .await
-    {
-        return HttpResponse::InternalServerError().json(ErrorResponse {
-            code: "DATABASE_ERROR".to_string(),
-            message: format!("Failed to save file record: {}", e),
-            details: None,
-        });
+    };
+
+    let new_file = NewFile {
+        id: file_id,
+        space_id: session.space_id,
+        document_id: session.document_id,
+        uploaded_by,
+        file_name: session.file_name.clone(),
+        file_type: session.content_type.clone(),
+        file_size,
+        storage_path: storage_path.clone(),
+        storage_bucket: bucket,
+        checksum: computed_checksum,
+    };
+
+    if let Err(e) = insert_file(pool.as_ref(), &new_file).await {
+        return insert_file_error_response(e);
     }
 
     let _ = sqlx::query!("DELETE FROM chunked_uploads WHERE upload_id = $1", upload_id)
@@ -594,7 +656,7 @@ pub async fn complete_chunked_upload(
         document_id: session.document_id,
         file_name: session.file_name,
         file_type: session.content_type,
-        file_size: session.total_size,
+        file_size,
         download_url,
         created_at: Utc::now().naive_utc(),
     })
@@ -708,25 +770,17 @@ pub async fn get_presigned_upload_url(
 }
 
 /// Download file - GET /api/v1/files/{fileId}/download
+///
+/// Serves the latest version unless `?version=` names an earlier one
 pub async fn download_file(
     file_id: web::Path<Uuid>,
+    query: web::Query<DownloadQuery>,
     pool: web::Data<PgPool>,
     storage: web::Data<Arc<S3Storage>>,
 ) -> impl Responder {
     let file_id = file_id.into_inner();
 
-    let file_result = sqlx::query_as!(
-        File,
-        r#"
-        SELECT id, space_id, document_id, uploaded_by, file_name,
-               file_type, file_size, storage_path, storage_bucket,
-               checksum, is_deleted, deleted_at, created_at
-        FROM files WHERE id = $1 AND is_deleted = false
-        "#,
-        file_id
-    )
-    .fetch_optional(pool.as_ref())
-    .await;
+    let file_result = find_version(pool.as_ref(), file_id, query.version).await;
 
     let file = match file_result {
         Ok(Some(f)) => f,
@@ -757,25 +811,17 @@ pub async fn download_file(
 }
 
 /// Get presigned download URL - GET /api/v1/files/{fileId}/download/presigned-url
+///
+/// Like `download_file`, links the latest version unless `?version=` is given
 pub async fn get_presigned_download_url(
     file_id: web::Path<Uuid>,
+    query: web::Query<DownloadQuery>,
     pool: web::Data<PgPool>,
     storage: web::Data<Arc<S3Storage>>,
 ) -> impl Responder {
     let file_id = file_id.into_inner();
 
-    let file_result = sqlx::query_as!(
-        File,
-        r#"
-        SELECT id, space_id, document_id, uploaded_by, file_name,
-               file_type, file_size, storage_path, storage_bucket,
-               checksum, is_deleted, deleted_at, created_at
-        FROM files WHERE id = $1 AND is_deleted = false
-        "#,
-        file_id
-    )
-    .fetch_optional(pool.as_ref())
-    .await;
+    let file_result = find_version(pool.as_ref(), file_id, query.version).await;
 
     let file = match file_result {
         Ok(Some(f)) => f,
@@ -816,24 +862,56 @@ pub async fn get_presigned_download_url(
     }
 }
 
+/// List file versions - GET /api/v1/files/{fileId}/versions
+///
+/// `fileId` may be any version; the whole history is returned, newest first
+pub async fn list_file_versions(file_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> impl Responder {
+    let file_id = file_id.into_inner();
+
+    let versions = match list_versions(pool.as_ref(), file_id).await {
+        Ok(versions) => versions,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                code: "DATABASE_ERROR".to_string(),
+                message: format!("Failed to get file versions: {}", e),
+                details: None,
+            });
+        },
+    };
+
+    let Some(latest) = versions.first() else {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            code: "FILE_NOT_FOUND".to_string(),
+            message: "File not found".to_string(),
+            details: None,
+        });
+    };
+
+    HttpResponse::Ok().json(FileVersionListResponse {
+        file_name: latest.file_name.clone(),
+        latest_version: latest.version,
+        versions: versions
+            .into_iter()
+            .map(|f| FileVersionResponse {
+                id: f.id,
+                version: f.version,
+                previous_version_id: f.previous_version_id,
+                file_size: f.file_size,
+                checksum: f.checksum,
+                uploaded_by: f.uploaded_by,
+                created_at: f.created_at,
+            })
+            .collect(),
+    })
+}
+
 /// Get file metadata - GET /api/v1/files/{fileId}
 pub async fn get_file_metadata(file_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> impl Responder {
     let file_id = file_id.into_inner();
 
-    let file_result = sqlx::query_as!(
-        File,
-        r#"
-        SELECT id, space_id, document_id, uploaded_by, file_name,
-               file_type, file_size, storage_path, storage_bucket,
-               checksum, is_deleted, deleted_at, created_at
-        FROM files WHERE id = $1
-        "#,
-        file_id
-    )
-    .fetch_optional(pool.as_ref())
-    .await;
+    let file_result = find_with_uploader(pool.as_ref(), file_id).await;
 
-    let file = match file_result {
+    let row = match file_result {
         Ok(Some(f)) => f,
         Ok(None) => {
             return HttpResponse::NotFound().json(ErrorResponse {
@@ -851,6 +929,9 @@ pub async fn get_file_metadata(file_id: web::Path<Uuid>, pool: web::Data<PgPool>
         },
     };
 
+    let uploader = row.uploader();
+    let file = row.file;
+
     let download_url = format!("/api/v1/files/{}/download", file.id);
 
     HttpResponse::Ok().json(FileDetailResponse {
@@ -864,11 +945,7 @@ pub async fn get_file_metadata(file_id: web::Path<Uuid>, pool: web::Data<PgPool>
             download_url,
             created_at: file.created_at,
         },
-        uploaded_by: UploaderInfo {
-            id: file.uploaded_by,
-            display_name: "User".to_string(),
-            avatar_url: None,
-        },
+        uploaded_by: uploader,
         checksum: file.checksum,
         storage_path: file.storage_path,
         deleted_at: file.deleted_at,
@@ -879,17 +956,9 @@ pub async fn get_file_metadata(file_id: web::Path<Uuid>, pool: web::Data<PgPool>
 pub async fn delete_file(file_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> impl Responder {
     let file_id = file_id.into_inner();
 
-    match sqlx::query!(
-        r#"
-        UPDATE files SET is_deleted = true, deleted_at = NOW() WHERE id = $1 AND is_deleted = false
-        "#,
-        file_id
-    )
-    .execute(pool.as_ref())
-    .await
-    {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
+    match soft_delete::soft_delete::<File>(pool.as_ref(), file_id).await {
+        Ok(deleted) => {
+            if !deleted {
                 return HttpResponse::NotFound().json(ErrorResponse {
                     code: "FILE_NOT_FOUND".to_string(),
                     message: "File not found or already deleted".to_string(),
@@ -915,17 +984,9 @@ pub async fn delete_file(file_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> i
 pub async fn restore_file(file_id: web::Path<Uuid>, pool: web::Data<PgPool>) -> impl Responder {
     let file_id = file_id.into_inner();
 
-    match sqlx::query!(
-        r#"
-        UPDATE files SET is_deleted = false, deleted_at = NULL WHERE id = $1 AND is_deleted = true
-        "#,
-        file_id
-    )
-    .execute(pool.as_ref())
-    .await
-    {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
+    match soft_delete::restore::<File>(pool.as_ref(), file_id).await {
+        Ok(restored) => {
+            if !restored {
                 return HttpResponse::NotFound().json(ErrorResponse {
                     code: "FILE_NOT_FOUND".to_string(),
                     message: "File not found or not deleted".to_string(),
@@ -960,7 +1021,8 @@ pub async fn permanent_delete_file(
         r#"
         SELECT id, space_id, document_id, uploaded_by, file_name,
                file_type, file_size, storage_path, storage_bucket,
-               checksum, is_deleted, deleted_at, created_at
+               checksum, is_deleted, deleted_at, created_at,
+               version, previous_version_id
         FROM files WHERE id = $1
         "#,
         file_id
@@ -986,10 +1048,12 @@ pub async fn permanent_delete_file(
         },
     };
 
-    match sqlx::query!("DELETE FROM files WHERE id = $1", file_id)
-        .execute(pool.as_ref())
-        .await
-    {
+    // Live files go through the soft delete first so purging is the same for every entity
+    let purged = match soft_delete::soft_delete::<File>(pool.as_ref(), file_id).await {
+        Ok(_) => soft_delete::purge::<File>(pool.as_ref(), file_id).await,
+        Err(e) => Err(e),
+    };
+    match purged {
         Ok(_) => {},
         Err(e) => {
             return HttpResponse::InternalServerError().json(ErrorResponse {
@@ -1025,7 +1089,8 @@ pub async fn list_space_files(
                 r#"
                 SELECT id, space_id, document_id, uploaded_by, file_name,
                        file_type, file_size, storage_path, storage_bucket,
-                       checksum, is_deleted, deleted_at, created_at
+                       checksum, is_deleted, deleted_at, created_at,
+                       version, previous_version_id
                 FROM files
                 WHERE space_id = $1 AND document_id = $2 AND is_deleted = false
                 ORDER BY created_at DESC
@@ -1044,7 +1109,8 @@ pub async fn list_space_files(
                 r#"
                 SELECT id, space_id, document_id, uploaded_by, file_name,
                        file_type, file_size, storage_path, storage_bucket,
-                       checksum, is_deleted, deleted_at, created_at
+                       checksum, is_deleted, deleted_at, created_at,
+                       version, previous_version_id
                 FROM files
                 WHERE space_id = $1 AND is_deleted = false
                 ORDER BY created_at DESC
@@ -1100,7 +1166,7 @@ pub async fn list_space_files(
         },
     };
 
-    let file_responses = files
+    let file_responses: Vec<FileResponse> = files
         .into_iter()
         .map(|f| FileResponse {
             id: f.id,
@@ -1114,11 +1180,14 @@ pub async fn list_space_files(
         })
         .collect();
 
+    let next_offset = next_offset(offset as i64, file_responses.len(), total);
     HttpResponse::Ok().json(FileListResponse {
         files: file_responses,
         total,
         limit: limit as i64,
         offset: offset as i64,
+        has_more: next_offset.is_some(),
+        next_offset,
     })
 }
 
@@ -1128,17 +1197,9 @@ pub async fn bulk_delete_files(req: web::Json<BulkDeleteRequest>, pool: web::Dat
     let mut failed = Vec::new();
 
     for file_id in &req.file_ids {
-        match sqlx::query!(
-            r#"
-            UPDATE files SET is_deleted = true, deleted_at = NOW() WHERE id = $1 AND is_deleted = false
-            "#,
-            file_id
-        )
-        .execute(pool.as_ref())
-        .await
-        {
-            Ok(result) => {
-                if result.rows_affected() > 0 {
+        match soft_delete::soft_delete::<File>(pool.as_ref(), *file_id).await {
+            Ok(was_live) => {
+                if was_live {
                     deleted.push(*file_id);
                 } else {
                     failed.push(FailedDelete {
@@ -1163,6 +1224,7 @@ pub async fn bulk_delete_files(req: web::Json<BulkDeleteRequest>, pool: web::Dat
 mod tests {
     use super::*;
     use actix_web::test::TestRequest;
+    use actix_web::HttpMessage;
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -1209,33 +1271,33 @@ mod tests {
     }
 
     // extract_user_id Tests
-    #[test]
-    fn test_extract_user_id_from_extensions() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_from_extensions() {
         let user_uuid = Uuid::new_v4();
         let mut req = TestRequest::get().to_http_request();
 
         // Simulate what middleware does - set user_id in extensions
         req.extensions_mut().insert(user_uuid);
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), user_uuid);
     }
 
-    #[test]
-    fn test_extract_user_id_from_header() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_from_header() {
         let user_uuid = Uuid::new_v4();
         let mut req = TestRequest::get()
             .insert_header(("X-User-Id", user_uuid.to_string()))
             .to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), user_uuid);
     }
 
-    #[test]
-    fn test_extract_user_id_fallback_order() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_fallback_order() {
         let header_uuid = Uuid::new_v4();
         let ext_uuid = Uuid::new_v4();
         let mut req = TestRequest::get()
@@ -1244,15 +1306,15 @@ mod tests {
             .to_http_request();
         req.extensions_mut().insert(ext_uuid);
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert_eq!(result.unwrap(), ext_uuid); // extensions should take precedence over header
     }
 
-    #[test]
-    fn test_extract_user_id_missing() {
+    #[actix_rt::test]
+    async fn test_extract_user_id_missing() {
         let req = TestRequest::get().to_http_request();
 
-        let result = extract_user_id(&req);
+        let result = extract_user_id(&req).await;
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), AppError::AuthenticationError(_)));
     }
@@ -1266,6 +1328,15 @@ mod tests {
         assert_eq!(FIELD_FILE_NAME, "file_name");
     }
 
+    // Upload Limits Tests
+    #[test]
+    fn test_upload_limits_resolve() {
+        assert_eq!(UploadLimits::resolve(None).max_file_size, DEFAULT_MAX_FILE_SIZE);
+
+        let limits = web::Data::new(UploadLimits { max_file_size: 1024 });
+        assert_eq!(UploadLimits::resolve(Some(&limits)).max_file_size, 1024);
+    }
+
     // File Size Validation Tests
     #[test]
     fn test_file_size_valid() {
@@ -0,0 +1,298 @@
+//! Image metadata stripping
+//!
+//! Photos straight from a phone or camera carry EXIF metadata, including
+//! GPS coordinates and device details. Uploaded JPEG and PNG images are
+//! decoded and re-encoded in the same format, which writes pixels only.
+//! The EXIF orientation is applied to the pixels first so stripped photos
+//! still display the right way up.
+//!
+//! Stripping is on by default; the policy comes from the app config
+//! (`UPLOAD_STRIP_IMAGE_METADATA`, `UPLOAD_PRESERVE_METADATA_SPACES`). Images
+//! are only decoded within fixed dimension, pixel and allocation limits, so a
+//! small file can't expand into gigabytes of pixels. When an image cannot be
+//! processed the original bytes are stored unchanged.
+
+use crate::models::ErrorResponse;
+use actix_web::{web, HttpResponse};
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use std::collections::HashSet;
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// JPEG quality used when re-encoding stripped photos
+const JPEG_QUALITY: u8 = 90;
+
+/// Widest or tallest image that is decoded for stripping
+const MAX_DIMENSION: u32 = 16_384;
+
+/// Most pixels an image may have to be decoded, about 50 megapixels
+const MAX_PIXELS: u64 = 50_000_000;
+
+/// Most memory the decoder may allocate
+const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// Whether uploaded images have their metadata removed
+#[derive(Debug, Clone)]
+pub struct ImageMetadataPolicy {
+    pub strip: bool,
+    pub preserve_spaces: HashSet<Uuid>,
+}
+
+impl Default for ImageMetadataPolicy {
+    fn default() -> Self {
+        Self {
+            strip: true,
+            preserve_spaces: HashSet::new(),
+        }
+    }
+}
+
+impl ImageMetadataPolicy {
+    /// Resolve the policy from optional app data, falling back to the default
+    pub fn resolve(policy: Option<&web::Data<ImageMetadataPolicy>>) -> Self {
+        policy.map(|p| p.as_ref().clone()).unwrap_or_default()
+    }
+
+    pub fn strips_for(&self, space_id: &Uuid) -> bool {
+        self.strip && !self.preserve_spaces.contains(space_id)
+    }
+}
+
+fn supported_format(content_type: &str) -> Option<ImageFormat> {
+    match content_type.split(';').next().unwrap_or_default().trim() {
+        "image/jpeg" | "image/jpg" => Some(ImageFormat::Jpeg),
+        "image/png" => Some(ImageFormat::Png),
+        _ => None,
+    }
+}
+
+/// EXIF orientation tag value (1-8), if present
+fn exif_orientation(bytes: &[u8]) -> Option<u32> {
+    let exif = exif::Reader::new().read_from_container(&mut Cursor::new(bytes)).ok()?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)
+}
+
+fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+fn decode_limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    limits
+}
+
+/// Decode `bytes`, refusing images over the size limits before any pixels
+/// are allocated
+fn decode(bytes: &[u8], format: ImageFormat) -> Result<DynamicImage, image::ImageError> {
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(decode_limits());
+    let (width, height) = reader.into_dimensions()?;
+    if u64::from(width) * u64::from(height) > MAX_PIXELS {
+        return Err(image::ImageError::Limits(image::error::LimitError::from_kind(
+            image::error::LimitErrorKind::DimensionError,
+        )));
+    }
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(decode_limits());
+    reader.decode()
+}
+
+fn reencode(bytes: &[u8], format: ImageFormat) -> Result<Vec<u8>, image::ImageError> {
+    let mut image = decode(bytes, format)?;
+    if let Some(orientation) = exif_orientation(bytes) {
+        image = apply_orientation(image, orientation);
+    }
+
+    let mut output = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, JPEG_QUALITY);
+            image.write_with_encoder(encoder)?;
+        },
+        _ => image.write_to(&mut Cursor::new(&mut output), format)?,
+    }
+    Ok(output)
+}
+
+/// Remove metadata from an uploaded image
+///
+/// Returns the bytes to store: re-encoded without metadata for supported
+/// images, or the original bytes for other content and on any failure.
+pub fn strip_metadata(bytes: Vec<u8>, content_type: &str) -> Vec<u8> {
+    let Some(format) = supported_format(content_type) else {
+        return bytes;
+    };
+
+    match reencode(&bytes, format) {
+        Ok(stripped) => stripped,
+        Err(e) => {
+            tracing::warn!("Failed to strip image metadata, storing original ({}): {}", content_type, e);
+            bytes
+        },
+    }
+}
+
+/// Apply the space's metadata policy to an upload, on the blocking pool
+///
+/// Returns the bytes to store, or the error response to send.
+pub async fn strip_upload(
+    policy: &ImageMetadataPolicy,
+    space_id: &Uuid,
+    bytes: Vec<u8>,
+    content_type: &str,
+) -> Result<Vec<u8>, HttpResponse> {
+    if !policy.strips_for(space_id) {
+        return Ok(bytes);
+    }
+
+    let content_type = content_type.to_string();
+    web::block(move || strip_metadata(bytes, &content_type)).await.map_err(|e| {
+        HttpResponse::InternalServerError().json(ErrorResponse {
+            code: "UPLOAD_FAILED".to_string(),
+            message: format!("Failed to process image: {}", e),
+            details: None,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// APP1 segment with an IFD0 holding `Make = "Canon"` and `Orientation = 6`
+    fn exif_segment() -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II*\0");
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0 with two entries
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        // Make (0x010F), ASCII, 6 bytes at offset 38
+        tiff.extend_from_slice(&0x010Fu16.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&6u32.to_le_bytes());
+        tiff.extend_from_slice(&38u32.to_le_bytes());
+        // Orientation (0x0112), SHORT, value 6 stored inline
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&6u16.to_le_bytes());
+        tiff.extend_from_slice(&0u16.to_le_bytes());
+        // No next IFD
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(b"Canon\0");
+
+        let mut payload = b"Exif\0\0".to_vec();
+        payload.extend_from_slice(&tiff);
+
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(&payload);
+        segment
+    }
+
+    /// A 4x2 JPEG with the EXIF segment inserted after SOI
+    fn jpeg_with_exif() -> Vec<u8> {
+        let image = DynamicImage::new_rgb8(4, 2);
+        let mut plain = Vec::new();
+        image.write_to(&mut Cursor::new(&mut plain), ImageFormat::Jpeg).unwrap();
+
+        let mut jpeg = plain[..2].to_vec();
+        jpeg.extend_from_slice(&exif_segment());
+        jpeg.extend_from_slice(&plain[2..]);
+        jpeg
+    }
+
+    fn make_tag(bytes: &[u8]) -> Option<String> {
+        let exif = exif::Reader::new().read_from_container(&mut Cursor::new(bytes)).ok()?;
+        let field = exif.get_field(exif::Tag::Make, exif::In::PRIMARY)?;
+        Some(field.display_value().to_string())
+    }
+
+    #[test]
+    fn test_exif_tag_absent_after_stripping() {
+        let original = jpeg_with_exif();
+        assert_eq!(make_tag(&original).as_deref(), Some("\"Canon\""));
+
+        let stripped = strip_metadata(original, "image/jpeg");
+
+        assert!(make_tag(&stripped).is_none());
+        // Orientation 6 was applied to the pixels
+        let image = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((image.width(), image.height()), (2, 4));
+    }
+
+    #[test]
+    fn test_invalid_image_keeps_original_bytes() {
+        let bytes = b"\xFF\xD8not really a jpeg".to_vec();
+        assert_eq!(strip_metadata(bytes.clone(), "image/jpeg"), bytes);
+    }
+
+    #[test]
+    fn test_non_image_content_is_untouched() {
+        let bytes = b"%PDF-1.7".to_vec();
+        assert_eq!(strip_metadata(bytes.clone(), "application/pdf"), bytes);
+    }
+
+    #[test]
+    fn test_oversized_image_is_not_decoded() {
+        // A JPEG whose frame header claims 12000x12000 pixels, within the
+        // dimension limit but far over the pixel limit
+        let mut jpeg = Vec::new();
+        DynamicImage::new_rgb8(4, 2)
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let sof = jpeg.windows(2).position(|marker| marker == [0xFF, 0xC0]).unwrap();
+        jpeg[sof + 5..sof + 7].copy_from_slice(&12_000u16.to_be_bytes());
+        jpeg[sof + 7..sof + 9].copy_from_slice(&12_000u16.to_be_bytes());
+
+        assert!(matches!(decode(&jpeg, ImageFormat::Jpeg), Err(image::ImageError::Limits(_))));
+        assert_eq!(strip_metadata(jpeg.clone(), "image/jpeg"), jpeg);
+    }
+
+    #[actix_rt::test]
+    async fn test_strip_upload_follows_the_space_policy() {
+        let preserved = Uuid::new_v4();
+        let policy = ImageMetadataPolicy {
+            strip: true,
+            preserve_spaces: HashSet::from([preserved]),
+        };
+
+        let kept = strip_upload(&policy, &preserved, jpeg_with_exif(), "image/jpeg").await.unwrap();
+        assert_eq!(kept, jpeg_with_exif());
+
+        let stripped = strip_upload(&policy, &Uuid::new_v4(), jpeg_with_exif(), "image/jpeg").await.unwrap();
+        assert!(make_tag(&stripped).is_none());
+    }
+
+    #[test]
+    fn test_preserved_spaces_are_not_stripped() {
+        let space_id = Uuid::new_v4();
+        let policy = ImageMetadataPolicy {
+            strip: true,
+            preserve_spaces: HashSet::from([space_id]),
+        };
+
+        assert!(!policy.strips_for(&space_id));
+        assert!(policy.strips_for(&Uuid::new_v4()));
+        assert!(!ImageMetadataPolicy {
+            strip: false,
+            ..Default::default()
+        }
+        .strips_for(&Uuid::new_v4()));
+    }
+}
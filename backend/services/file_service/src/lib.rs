@@ -1,7 +1,17 @@
+pub mod extensions;
 pub mod handlers;
+pub mod image_metadata;
 pub mod models;
+pub mod repository;
+pub mod retention;
+pub mod scanner;
 pub mod storage;
 
+pub use handlers::{UploadLimits, DEFAULT_MAX_FILE_SIZE};
+pub use image_metadata::ImageMetadataPolicy;
+pub use retention::FileRetention;
+pub use scanner::{FileScanner, ScanResult};
+
 /// Configure file service routes
 /// Pool and storage will be extracted by handlers from app_data
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
@@ -20,6 +30,7 @@ pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
             // Download endpoints
             .route("/{file_id}/download", actix_web::web::get().to(download_file))
             .route("/{file_id}/download/presigned-url", actix_web::web::get().to(get_presigned_download_url))
+            .route("/{file_id}/versions", actix_web::web::get().to(list_file_versions))
 
             // Management endpoints
             .route("/{file_id}", actix_web::web::get().to(get_file_metadata))
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{NaiveDateTime, Utc, DateTime};
+use shared_database::SoftDeletable;
 use sqlx::FromRow;
 
 /// File entity from database
@@ -19,6 +20,14 @@ pub struct File {
     pub is_deleted: bool,
     pub deleted_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
+    pub version: i32,
+    pub previous_version_id: Option<Uuid>,
+}
+
+impl SoftDeletable for File {
+    const TABLE: &'static str = "files";
+    const DELETED_FLAG: &'static str = "is_deleted";
+    const DELETED_AT: &'static str = "deleted_at";
 }
 
 /// File with uploader info (for detail responses)
@@ -28,6 +37,30 @@ pub struct FileDetail {
     pub uploaded_by: UploaderInfo,
 }
 
+/// File row joined with its uploader's display fields
+///
+/// The uploader fields are `None` when the user record no longer exists.
+#[derive(Debug, Clone, FromRow)]
+pub struct FileWithUploader {
+    #[sqlx(flatten)]
+    pub file: File,
+    pub uploader_name: Option<String>,
+    pub uploader_avatar: Option<String>,
+}
+
+/// Shown for uploaders whose user record no longer exists
+pub const UNKNOWN_UPLOADER: &str = "Unknown User";
+
+impl FileWithUploader {
+    pub fn uploader(&self) -> UploaderInfo {
+        UploaderInfo {
+            id: self.file.uploaded_by,
+            display_name: self.uploader_name.clone().unwrap_or_else(|| UNKNOWN_UPLOADER.to_string()),
+            avatar_url: self.uploader_avatar.clone(),
+        }
+    }
+}
+
 /// Uploader user info
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploaderInfo {
@@ -51,6 +84,10 @@ pub struct FileListResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    /// True when another page follows this one
+    pub has_more: bool,
+    /// Offset to request the next page with; null on the last page
+    pub next_offset: Option<i64>,
 }
 
 /// Upload response
@@ -66,6 +103,33 @@ pub struct FileResponse {
     pub created_at: NaiveDateTime,
 }
 
+/// Download query parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct DownloadQuery {
+    /// Version number to fetch; the latest version when omitted
+    pub version: Option<i32>,
+}
+
+/// One entry in a file's version history
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileVersionResponse {
+    pub id: Uuid,
+    pub version: i32,
+    pub previous_version_id: Option<Uuid>,
+    pub file_size: i64,
+    pub checksum: String,
+    pub uploaded_by: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+/// File version history, newest first
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileVersionListResponse {
+    pub file_name: String,
+    pub latest_version: i32,
+    pub versions: Vec<FileVersionResponse>,
+}
+
 /// Detailed file response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileDetailResponse {
@@ -0,0 +1,189 @@
+//! File record queries
+//!
+//! Uploading a name that already exists in the same space and document does
+//! not replace the earlier file: the new row gets the next version number and
+//! `previous_version_id` pointing at the version it replaces. Every version
+//! keeps its own storage object, so older versions stay downloadable until
+//! they are deleted.
+
+use crate::models::{File, FileWithUploader};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Columns selected into `File`
+const FILE_COLUMNS: &str = "id, space_id, document_id, uploaded_by, file_name, \
+     file_type, file_size, storage_path, storage_bucket, \
+     checksum, is_deleted, deleted_at, created_at, version, previous_version_id";
+
+/// A file record about to be inserted
+#[derive(Debug, Clone)]
+pub struct NewFile {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub document_id: Option<Uuid>,
+    pub uploaded_by: Uuid,
+    pub file_name: String,
+    pub file_type: String,
+    pub file_size: i64,
+    pub storage_path: String,
+    pub storage_bucket: String,
+    pub checksum: String,
+}
+
+/// Unique index that keeps a file's history from forking
+const PREVIOUS_VERSION_INDEX: &str = "idx_files_previous_version";
+
+/// Errors from inserting a file record
+#[derive(Debug, thiserror::Error)]
+pub enum InsertFileError {
+    #[error("Another version of this file was uploaded at the same time")]
+    ConcurrentVersion,
+
+    #[error(transparent)]
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for InsertFileError {
+    fn from(e: sqlx::Error) -> Self {
+        let forked = e
+            .as_database_error()
+            .and_then(|db| db.constraint())
+            .is_some_and(|constraint| constraint == PREVIOUS_VERSION_INDEX);
+        if forked {
+            Self::ConcurrentVersion
+        } else {
+            Self::Database(e)
+        }
+    }
+}
+
+/// Insert a file record, continuing the history of a file with the same name
+///
+/// Deleted versions still count, so numbering never goes backwards. The
+/// current latest version is locked until the insert commits; a concurrent
+/// upload of the same name fails with `ConcurrentVersion` on the unique
+/// `previous_version_id` index rather than forking the history.
+pub async fn insert_file(pool: &PgPool, new_file: &NewFile) -> Result<File, InsertFileError> {
+    let mut tx = pool.begin().await?;
+
+    let previous: Option<(Uuid, i32)> = sqlx::query_as(
+        r#"
+        SELECT id, version
+        FROM files
+        WHERE space_id = $1
+          AND document_id IS NOT DISTINCT FROM $2
+          AND file_name = $3
+        ORDER BY version DESC, created_at DESC
+        LIMIT 1
+        FOR UPDATE
+        "#,
+    )
+    .bind(new_file.space_id)
+    .bind(new_file.document_id)
+    .bind(&new_file.file_name)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let (version, previous_version_id) = match previous {
+        Some((id, version)) => (version + 1, Some(id)),
+        None => (1, None),
+    };
+
+    let file = sqlx::query_as::<_, File>(&format!(
+        r#"
+        INSERT INTO files (
+            id, space_id, document_id, uploaded_by, file_name,
+            file_type, file_size, storage_path, storage_bucket,
+            checksum, is_deleted, deleted_at, created_at,
+            version, previous_version_id
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, false, NULL, NOW(), $11, $12)
+        RETURNING {}
+        "#,
+        FILE_COLUMNS
+    ))
+    .bind(new_file.id)
+    .bind(new_file.space_id)
+    .bind(new_file.document_id)
+    .bind(new_file.uploaded_by)
+    .bind(&new_file.file_name)
+    .bind(&new_file.file_type)
+    .bind(new_file.file_size)
+    .bind(&new_file.storage_path)
+    .bind(&new_file.storage_bucket)
+    .bind(&new_file.checksum)
+    .bind(version)
+    .bind(previous_version_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(file)
+}
+
+/// All live versions in the history of `file_id`, newest first
+///
+/// `file_id` may be any live version in the history. Walks back through
+/// `previous_version_id` to the first version, then forward to the latest.
+/// Returns an empty list when the file does not exist or was deleted.
+pub async fn list_versions(pool: &PgPool, file_id: Uuid) -> Result<Vec<File>, sqlx::Error> {
+    sqlx::query_as::<_, File>(&format!(
+        r#"
+        WITH RECURSIVE earlier AS (
+            SELECT id, previous_version_id FROM files WHERE id = $1 AND is_deleted = false
+            UNION ALL
+            SELECT f.id, f.previous_version_id
+            FROM files f
+            JOIN earlier e ON f.id = e.previous_version_id
+        ),
+        history AS (
+            SELECT f.id FROM files f
+            WHERE f.id IN (SELECT id FROM earlier WHERE previous_version_id IS NULL)
+            UNION ALL
+            SELECT f.id
+            FROM files f
+            JOIN history h ON f.previous_version_id = h.id
+        )
+        SELECT {}
+        FROM files
+        WHERE id IN (SELECT id FROM history) AND is_deleted = false
+        ORDER BY version DESC
+        "#,
+        FILE_COLUMNS
+    ))
+    .bind(file_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Resolve a download to a live version in the history of `file_id`
+///
+/// Without `version` this is the latest live version; otherwise the version
+/// with that number, if it hasn't been deleted.
+pub async fn find_version(pool: &PgPool, file_id: Uuid, version: Option<i32>) -> Result<Option<File>, sqlx::Error> {
+    let versions = list_versions(pool, file_id).await?;
+
+    Ok(match version {
+        Some(number) => versions.into_iter().find(|f| f.version == number),
+        None => versions.into_iter().next(),
+    })
+}
+
+/// A file, soft-deleted or not, with its uploader's display name and avatar
+pub async fn find_with_uploader(pool: &PgPool, file_id: Uuid) -> Result<Option<FileWithUploader>, sqlx::Error> {
+    sqlx::query_as::<_, FileWithUploader>(
+        r#"
+        SELECT f.id, f.space_id, f.document_id, f.uploaded_by, f.file_name,
+               f.file_type, f.file_size, f.storage_path, f.storage_bucket,
+               f.checksum, f.is_deleted, f.deleted_at, f.created_at,
+               f.version, f.previous_version_id,
+               u.display_name AS uploader_name,
+               u.avatar_url AS uploader_avatar
+        FROM files f
+        LEFT JOIN users u ON u.id = f.uploaded_by
+        WHERE f.id = $1
+        "#,
+    )
+    .bind(file_id)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,156 @@
+//! Retention of soft-deleted files
+//!
+//! A deleted file can be restored until its retention window runs out; after
+//! that a background task removes it for good, deleting the row and then the
+//! stored object, the same way `permanent_delete_file` does for one file.
+//! Rows are purged in batches so a large backlog doesn't hold one long
+//! transaction, and an object is only removed once no remaining row points
+//! at it.
+
+use crate::storage::S3Storage;
+use chrono::{NaiveDateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default days a soft-deleted file is kept before it is purged
+pub const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Default seconds between purge runs
+pub const DEFAULT_PURGE_INTERVAL_SECS: u64 = 3600;
+
+/// Rows removed per purge statement
+const PURGE_BATCH_SIZE: i64 = 100;
+
+/// How long deleted files are kept and how often they are purged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileRetention {
+    pub retention: chrono::Duration,
+    pub interval: Duration,
+}
+
+impl Default for FileRetention {
+    fn default() -> Self {
+        Self {
+            retention: chrono::Duration::days(DEFAULT_RETENTION_DAYS),
+            interval: Duration::from_secs(DEFAULT_PURGE_INTERVAL_SECS),
+        }
+    }
+}
+
+impl FileRetention {
+    /// Read `FILE_RETENTION_DAYS` and `FILE_PURGE_INTERVAL_SECS`, falling
+    /// back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let days = std::env::var("FILE_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        let interval_secs = std::env::var("FILE_PURGE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_PURGE_INTERVAL_SECS);
+
+        Self {
+            retention: chrono::Duration::days(days),
+            interval: Duration::from_secs(interval_secs),
+        }
+    }
+
+    /// Files deleted before this time are due for purging
+    pub fn cutoff(&self) -> NaiveDateTime {
+        Utc::now().naive_utc() - self.retention
+    }
+
+    /// Purge expired files every `interval` until the process exits
+    pub fn spawn_purge_task(self, pool: PgPool, storage: Arc<S3Storage>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                interval.tick().await;
+                match purge_deleted_files_older_than(&pool, &storage, self.cutoff()).await {
+                    Ok(0) => {},
+                    Ok(purged) => tracing::info!("Purged {} files past their retention window", purged),
+                    Err(e) => tracing::error!("Failed to purge deleted files: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Permanently delete files soft deleted before `cutoff`, returning how many
+///
+/// Rows go first; a stored object that fails to delete is logged and left
+/// behind, as with a single permanent delete.
+pub async fn purge_deleted_files_older_than(
+    pool: &PgPool,
+    storage: &S3Storage,
+    cutoff: NaiveDateTime,
+) -> Result<usize, sqlx::Error> {
+    let mut purged = 0;
+
+    loop {
+        let storage_paths: Vec<String> = sqlx::query_scalar(
+            r#"
+            DELETE FROM files
+            WHERE id IN (
+                SELECT id FROM files
+                WHERE is_deleted = true AND deleted_at < $1
+                ORDER BY deleted_at
+                LIMIT $2
+            )
+            RETURNING storage_path
+            "#,
+        )
+        .bind(cutoff)
+        .bind(PURGE_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        purged += storage_paths.len();
+
+        for path in &storage_paths {
+            let still_referenced: bool =
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM files WHERE storage_path = $1)")
+                    .bind(path)
+                    .fetch_one(pool)
+                    .await?;
+            if still_referenced {
+                continue;
+            }
+            if let Err(e) = storage.delete_file(path).await {
+                tracing::error!("Failed to delete purged file {} from storage: {}", path, e);
+            }
+        }
+
+        if (storage_paths.len() as i64) < PURGE_BATCH_SIZE {
+            return Ok(purged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_retention() {
+        let retention = FileRetention::default();
+
+        assert_eq!(retention.retention, chrono::Duration::days(30));
+        assert_eq!(retention.interval, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_cutoff_is_retention_before_now() {
+        let retention = FileRetention {
+            retention: chrono::Duration::days(7),
+            interval: Duration::from_secs(60),
+        };
+
+        let expected = Utc::now().naive_utc() - chrono::Duration::days(7);
+        let drift = (retention.cutoff() - expected).num_seconds().abs();
+        assert!(drift <= 1);
+    }
+}
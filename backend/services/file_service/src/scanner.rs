@@ -0,0 +1,195 @@
+//! Malware scanning for uploads
+//!
+//! Uploaded bytes are passed to a `FileScanner` after they are stored but
+//! before the file record is inserted, so nothing is downloadable until it
+//! has been scanned. Infected uploads are rejected with `422
+//! MALWARE_DETECTED` and their storage object is deleted. A scanner that
+//! cannot give an answer also rejects the upload.
+//!
+//! Scanning is off unless a scanner is registered as app data. With the
+//! `clamav` feature, `CLAMAV_ADDRESS` (e.g. `localhost:3310`) enables
+//! `ClamAvScanner`, which talks to clamd's `INSTREAM` command over TCP.
+
+use crate::models::ErrorResponse;
+use crate::storage::S3Storage;
+use actix_web::{web, HttpResponse};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Outcome of scanning an upload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    Clean,
+    /// Malware was found; holds the scanner's signature name
+    Infected(String),
+    /// The scanner could not check the file
+    Error(String),
+}
+
+/// Pluggable malware scanner
+#[async_trait]
+pub trait FileScanner: Send + Sync {
+    async fn scan(&self, bytes: &[u8]) -> ScanResult;
+}
+
+/// The configured scanner, if any
+///
+/// Returns `ClamAvScanner` when built with the `clamav` feature and
+/// `CLAMAV_ADDRESS` is set; otherwise uploads are not scanned.
+pub fn scanner_from_env() -> Option<Arc<dyn FileScanner>> {
+    #[cfg(feature = "clamav")]
+    {
+        if let Some(scanner) = clamav::ClamAvScanner::from_env() {
+            return Some(Arc::new(scanner));
+        }
+    }
+    None
+}
+
+/// Scan stored upload bytes, deleting the storage object when they're rejected
+///
+/// Returns the response to send when the upload must not be kept.
+pub async fn scan_upload(
+    scanner: Option<&web::Data<Arc<dyn FileScanner>>>,
+    storage: &S3Storage,
+    storage_path: &str,
+    bytes: &[u8],
+) -> Result<(), HttpResponse> {
+    let Some(scanner) = scanner else {
+        return Ok(());
+    };
+
+    let rejection = match scanner.scan(bytes).await {
+        ScanResult::Clean => return Ok(()),
+        ScanResult::Infected(signature) => {
+            tracing::warn!("Rejected infected upload {} ({})", storage_path, signature);
+            HttpResponse::UnprocessableEntity().json(ErrorResponse {
+                code: "MALWARE_DETECTED".to_string(),
+                message: "The file was rejected by the malware scanner".to_string(),
+                details: Some(serde_json::json!({ "signature": signature })),
+            })
+        },
+        ScanResult::Error(e) => {
+            tracing::error!("Malware scan failed for {}: {}", storage_path, e);
+            HttpResponse::ServiceUnavailable().json(ErrorResponse {
+                code: "SCAN_FAILED".to_string(),
+                message: "The file could not be scanned. Please try again later.".to_string(),
+                details: None,
+            })
+        },
+    };
+
+    if let Err(e) = storage.delete_file(storage_path).await {
+        tracing::error!("Failed to delete rejected upload {}: {}", storage_path, e);
+    }
+    Err(rejection)
+}
+
+#[cfg(feature = "clamav")]
+pub use clamav::ClamAvScanner;
+
+#[cfg(feature = "clamav")]
+mod clamav {
+    use super::{FileScanner, ScanResult};
+    use async_trait::async_trait;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Default time allowed for a whole scan
+    const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+    /// Bytes sent per INSTREAM chunk; well below clamd's StreamMaxLength
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Scanner backed by a clamd daemon reachable over TCP
+    #[derive(Debug, Clone)]
+    pub struct ClamAvScanner {
+        address: String,
+        timeout: Duration,
+    }
+
+    impl ClamAvScanner {
+        pub fn new(address: impl Into<String>) -> Self {
+            Self {
+                address: address.into(),
+                timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            }
+        }
+
+        pub fn with_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+
+        /// Read `CLAMAV_ADDRESS` and `CLAMAV_TIMEOUT_SECS`; `None` when no
+        /// address is set
+        pub fn from_env() -> Option<Self> {
+            let address = std::env::var("CLAMAV_ADDRESS").ok().filter(|v| !v.trim().is_empty())?;
+            let timeout = std::env::var("CLAMAV_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(DEFAULT_TIMEOUT_SECS);
+
+            Some(Self::new(address.trim()).with_timeout(Duration::from_secs(timeout)))
+        }
+
+        async fn instream(&self, bytes: &[u8]) -> std::io::Result<String> {
+            let mut stream = TcpStream::connect(&self.address).await?;
+            stream.write_all(b"zINSTREAM\0").await?;
+            for chunk in bytes.chunks(CHUNK_SIZE) {
+                stream.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+                stream.write_all(chunk).await?;
+            }
+            stream.write_all(&0u32.to_be_bytes()).await?;
+
+            let mut reply = Vec::new();
+            stream.read_to_end(&mut reply).await?;
+            Ok(String::from_utf8_lossy(&reply).trim_end_matches('\0').trim().to_string())
+        }
+    }
+
+    /// Interpret a clamd reply such as `stream: OK` or
+    /// `stream: Eicar-Signature FOUND`
+    pub(super) fn parse_reply(reply: &str) -> ScanResult {
+        let status = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+        if status == "OK" {
+            ScanResult::Clean
+        } else if let Some(signature) = status.strip_suffix("FOUND") {
+            ScanResult::Infected(signature.trim().to_string())
+        } else {
+            ScanResult::Error(reply.to_string())
+        }
+    }
+
+    #[async_trait]
+    impl FileScanner for ClamAvScanner {
+        async fn scan(&self, bytes: &[u8]) -> ScanResult {
+            match tokio::time::timeout(self.timeout, self.instream(bytes)).await {
+                Ok(Ok(reply)) => parse_reply(&reply),
+                Ok(Err(e)) => ScanResult::Error(format!("clamd at {}: {}", self.address, e)),
+                Err(_) => ScanResult::Error(format!("clamd at {} timed out", self.address)),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "clamav"))]
+mod tests {
+    use super::clamav::parse_reply;
+    use super::ScanResult;
+
+    #[test]
+    fn test_parse_clamd_replies() {
+        assert_eq!(parse_reply("stream: OK"), ScanResult::Clean);
+        assert_eq!(
+            parse_reply("stream: Eicar-Test-Signature FOUND"),
+            ScanResult::Infected("Eicar-Test-Signature".to_string())
+        );
+        assert!(matches!(
+            parse_reply("INSTREAM size limit exceeded. ERROR"),
+            ScanResult::Error(_)
+        ));
+    }
+}
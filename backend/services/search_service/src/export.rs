@@ -0,0 +1,107 @@
+use crate::repository::SearchResultRow;
+
+/// Upper bound on the number of rows written to a single export
+pub const EXPORT_MAX_ROWS: i32 = 1000;
+
+/// Column header written as the first CSV line
+pub const CSV_HEADER: &str = "document_id,title,space_id,space_name,score,link";
+
+/// Escape a single CSV field
+///
+/// Values starting with `=`, `+`, `-` or `@` are prefixed with `'` so
+/// spreadsheet applications do not evaluate them as formulas. Fields
+/// containing commas, quotes or line breaks are quoted, with embedded quotes
+/// doubled.
+pub fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Relative link to a document in the web app
+pub fn document_link(row: &SearchResultRow) -> String {
+    format!("/spaces/{}/documents/{}", row.space_id, row.document_id)
+}
+
+/// Render one search result as a CSV line, including the trailing newline
+pub fn csv_row(row: &SearchResultRow) -> String {
+    let fields = [
+        csv_field(&row.document_id.to_string()),
+        csv_field(&row.title),
+        csv_field(&row.space_id.to_string()),
+        csv_field(&row.space_name),
+        // Scores are numbers we produce, so they are written as-is
+        format!("{:.4}", row.score),
+        csv_field(&document_link(row)),
+    ];
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn row(title: &str, space_name: &str) -> SearchResultRow {
+        SearchResultRow {
+            document_id: Uuid::new_v4(),
+            space_id: Uuid::new_v4(),
+            space_name: space_name.to_string(),
+            title: title.to_string(),
+            content: serde_json::Value::Null,
+            score: 2.5,
+            is_archived: false,
+        }
+    }
+
+    #[test]
+    fn test_header_and_one_line_per_result() {
+        let rows = vec![row("First", "Docs"), row("Second", "Docs")];
+        let csv: String = std::iter::once(format!("{}\n", CSV_HEADER))
+            .chain(rows.iter().map(csv_row))
+            .collect();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            format!(
+                "{},First,{},Docs,2.5000,/spaces/{}/documents/{}",
+                rows[0].document_id, rows[0].space_id, rows[0].space_id, rows[0].document_id
+            )
+        );
+    }
+
+    #[test]
+    fn test_commas_and_quotes_are_escaped() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("Rust, async"), "\"Rust, async\"");
+        assert_eq!(csv_field("The \"best\" guide"), "\"The \"\"best\"\" guide\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+
+        let line = csv_row(&row("Hello, \"world\"", "Team, A"));
+        assert!(line.contains(",\"Hello, \"\"world\"\"\","));
+        assert!(line.contains(",\"Team, A\","));
+    }
+
+    #[test]
+    fn test_formula_injection_is_neutralized() {
+        assert_eq!(csv_field(r#"=HYPERLINK("http://evil")"#), r#""'=HYPERLINK(""http://evil"")""#);
+        assert_eq!(csv_field("+1+1"), "'+1+1");
+        assert_eq!(csv_field("-2"), "'-2");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("a=b"), "a=b");
+
+        let line = csv_row(&row("=cmd|' /C calc'!A0", "Docs"));
+        assert!(line.contains(",'=cmd|' /C calc'!A0,"));
+    }
+}
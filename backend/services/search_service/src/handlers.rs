@@ -1,46 +1,117 @@
 use actix_web::{web, Responder, HttpResponse};
+use futures_util::StreamExt;
+use sqlx::PgPool;
+use std::sync::Arc;
 use tracing::{info, error};
+use uuid::Uuid;
+use crate::export::{csv_row, CSV_HEADER, EXPORT_MAX_ROWS};
+use crate::indexer::SearchIndexManager;
 use crate::models::*;
+use crate::rate_limit::SearchRateLimit;
 use crate::repository::{SearchRepository, SearchRepositoryTrait};
 use shared_errors::AppError;
 use validator::Validate;
 
-// Helper for user extraction - in real implementation, this would come from JWT
-fn extract_user_id(req: &actix_web::HttpRequest) -> Result<String, AppError> {
-    req.headers()
-        .get("X-User-Id")
-        .and_then(|h| h.to_str().ok())
-        .map(|s| s.to_string())
-        .ok_or_else(|| AppError::AuthenticationError("Missing X-User-Id header".to_string()))
+// User making the request: a verified Bearer token, or the X-User-Id header
+async fn extract_user_id(req: &actix_web::HttpRequest) -> Result<Uuid, AppError> {
+    auth_service::identity::extract_request_user_id(req).await
+}
+
+// Range checks from `SearchQuery` for requests that skip the `q` validation
+fn validate_paging(limit: Option<i32>, offset: Option<i32>) -> Result<(), String> {
+    if limit.is_some_and(|l| !(1..=100).contains(&l)) {
+        return Err("limit must be between 1 and 100".to_string());
+    }
+    if offset.is_some_and(|o| o < 0) {
+        return Err("offset must not be negative".to_string());
+    }
+    Ok(())
 }
 
 // Search documents endpoint
 pub async fn search_documents(
     query: web::Query<SearchQuery>,
     repo: web::Data<SearchRepository>,
+    empty_query: Option<web::Data<EmptyQueryBehavior>>,
+    rate_limit: Option<web::Data<SearchRateLimit>>,
     http_req: actix_web::HttpRequest,
 ) -> impl Responder {
     let start_time = std::time::Instant::now();
 
+    let empty_query = empty_query.map(|b| **b.as_ref()).unwrap_or_default();
+    let list_recent = query.q.trim().is_empty() && empty_query == EmptyQueryBehavior::Recent;
+
     // Validate request
-    if let Err(validation_errors) = (*query).validate() {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("VALIDATION_ERROR", &format!("Validation failed: {:?}", validation_errors)));
+    if list_recent {
+        if let Err(validation_errors) = validate_paging(query.limit, query.offset) {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error("VALIDATION_ERROR", &validation_errors));
+        }
+    } else if let Err(validation_errors) = (*query).validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
     }
 
-    let user_id = match extract_user_id(&http_req) {
+    let facets = match query.facets.as_deref().map(Facet::parse_list).transpose() {
+        Ok(facets) => facets.unwrap_or_default(),
+        Err(message) => return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("VALIDATION_ERROR", &message)),
+    };
+
+    let user_id = match extract_user_id(&http_req).await {
         Ok(id) => id,
         Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
     };
 
+    if let Some(rate_limit) = &rate_limit {
+        if let Err(response) = rate_limit.check(&user_id.to_string()).await {
+            return response;
+        }
+    }
+
     let limit = query.limit.unwrap_or(20).clamp(1, 100);
     let offset = query.offset.unwrap_or(0);
 
     let query_length = query.q.len();
     info!("Search initiated (query_length={}, limit={}, offset={})", query_length, limit, offset);
 
-    match repo.search(&user_id, &query.q, query.space_id.as_deref(), limit, offset).await {
-        Ok((results, total)) => {
+    if query.include_archived {
+        match repo.can_include_archived(user_id, query.space_id.as_deref()).await {
+            Ok(true) => {}
+            Ok(false) => return HttpResponse::Forbidden()
+                .json(ApiResponse::<()>::error("FORBIDDEN", "Only space owners can include archived documents")),
+            Err(e) => {
+                error!("Failed to check archived search permission: {:?}", e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<()>::error("SEARCH_ERROR", "Search failed. Please try again later."));
+            }
+        }
+    }
+
+    let outcome = if list_recent {
+        repo.recent(user_id, query.space_id.as_deref(), limit, offset).await
+    } else {
+        repo.search(user_id, &query.q, query.space_id.as_deref(), limit, offset, query.include_archived).await
+    };
+
+    // Facets count every match, so they need their own aggregate queries
+    let outcome = match outcome {
+        Ok(page) if !facets.is_empty() => {
+            // The recent listing ignores include_archived, so its facets do too
+            let (facet_query, include_archived) = if list_recent {
+                (None, false)
+            } else {
+                (Some(query.q.as_str()), query.include_archived)
+            };
+            repo.facets(user_id, facet_query, query.space_id.as_deref(), include_archived, &facets)
+                .await
+                .map(|counts| (page, Some(counts)))
+        }
+        Ok(page) => Ok((page, None)),
+        Err(e) => Err(e),
+    };
+
+    match outcome {
+        Ok(((results, total), facet_counts)) => {
             let elapsed_ms = start_time.elapsed().as_millis() as i64;
             info!("Search completed in {}ms, found {} results", elapsed_ms, total);
 
@@ -53,9 +124,21 @@ pub async fn search_documents(
                         title: r.title,
                         snippet: r.content.as_str().unwrap_or("").to_string(),
                         score: r.score,
+                        is_archived: r.is_archived,
                     }).collect(),
                     total,
                     took: elapsed_ms,
+                    facets: facet_counts.map(|counts| SearchFacets {
+                        space: counts.space.map(|rows| rows.into_iter().map(|r| SpaceFacet {
+                            id: r.space_id.to_string(),
+                            name: r.space_name,
+                            count: r.count,
+                        }).collect()),
+                        tag: counts.tag.map(|rows| rows.into_iter().map(|r| TagFacet {
+                            name: r.tag,
+                            count: r.count,
+                        }).collect()),
+                    }),
                 }))
         }
         Err(e) => {
@@ -65,3 +148,172 @@ pub async fn search_documents(
         }
     }
 }
+
+// Title suggestions endpoint for search-as-you-type
+pub async fn suggest_documents(
+    query: web::Query<SuggestQuery>,
+    repo: web::Data<SearchRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+    };
+
+    match repo.suggest(user_id, &query.q, query.limit).await {
+        Ok(rows) => HttpResponse::Ok().json(ApiResponse::<Vec<SuggestItem>>::success(
+            rows.into_iter().map(|r| SuggestItem {
+                document_id: r.document_id.to_string(),
+                title: r.title,
+                space_name: r.space_name,
+            }).collect(),
+        )),
+        Err(e) => {
+            error!("Suggest error: {:?}", e);
+            HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("SEARCH_ERROR", "Suggestions failed. Please try again later."))
+        }
+    }
+}
+
+// Export search results endpoint; runs the same permission-filtered search
+// and streams the rows as CSV as they are read from the database
+pub async fn export_search_results(
+    query: web::Query<ExportQuery>,
+    repo: web::Data<SearchRepository>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    if let Err(validation_errors) = (*query).validate() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::validation_error(&validation_errors));
+    }
+
+    if !query.format.eq_ignore_ascii_case("csv") {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("UNSUPPORTED_FORMAT", "Only format=csv is supported"));
+    }
+
+    let user_id = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+    };
+
+    let limit = query.limit.unwrap_or(EXPORT_MAX_ROWS).clamp(1, EXPORT_MAX_ROWS);
+
+    let mut rows = Box::pin(repo.export(user_id, &query.q, query.space_id.as_deref(), limit));
+
+    // A query that fails before the first row still gets a 500 rather than
+    // an empty 200; later failures can only cut the body short
+    let first = match rows.next().await {
+        Some(Err(e)) => {
+            error!("Search export error: {:?}", e);
+            return HttpResponse::InternalServerError()
+                .json(ApiResponse::<()>::error("SEARCH_ERROR", "Export failed. Please try again later."));
+        }
+        first => first,
+    };
+
+    let header = web::Bytes::from(format!("{}\n", CSV_HEADER));
+    let lines = futures_util::stream::iter(first).chain(rows).map(|row| match row {
+        Ok(row) => Ok(web::Bytes::from(csv_row(&row))),
+        Err(e) => {
+            error!("Search export failed mid-stream: {:?}", e);
+            Err(e)
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"search-results.csv\""))
+        .streaming(futures_util::stream::once(async move { Ok(header) }).chain(lines))
+}
+
+// Re-index documents endpoint (space owners only)
+pub async fn reindex_documents(
+    body: Option<web::Json<ReindexRequest>>,
+    pool: web::Data<PgPool>,
+    http_req: actix_web::HttpRequest,
+) -> impl Responder {
+    let user_uuid = match extract_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => return HttpResponse::Unauthorized().json(ApiResponse::<()>::error("UNAUTHORIZED", &e.to_string())),
+    };
+
+    let requested_space = body.and_then(|b| b.into_inner().space_id);
+
+    let space_ids: Vec<Uuid> = match requested_space {
+        Some(space_id) => {
+            let space_uuid = match Uuid::parse_str(&space_id) {
+                Ok(id) => id,
+                Err(_) => return HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error("VALIDATION_ERROR", "Invalid space ID format")),
+            };
+
+            let owner: Result<Option<(Uuid,)>, sqlx::Error> = sqlx::query_as(
+                "SELECT owner_id FROM spaces WHERE id = $1"
+            )
+            .bind(space_uuid)
+            .fetch_optional(pool.get_ref())
+            .await;
+
+            match owner {
+                Ok(Some((owner_id,))) if owner_id == user_uuid => vec![space_uuid],
+                Ok(Some(_)) => return HttpResponse::Forbidden()
+                    .json(ApiResponse::<()>::error("FORBIDDEN", "Only the space owner can re-index a space")),
+                Ok(None) => return HttpResponse::NotFound()
+                    .json(ApiResponse::<()>::error("NOT_FOUND", "Space not found")),
+                Err(e) => {
+                    error!("Failed to look up space owner: {:?}", e);
+                    return HttpResponse::InternalServerError()
+                        .json(ApiResponse::<()>::error("REINDEX_ERROR", "Re-index failed. Please try again later."));
+                }
+            }
+        }
+        None => {
+            let owned: Result<Vec<(Uuid,)>, sqlx::Error> = sqlx::query_as(
+                "SELECT id FROM spaces WHERE owner_id = $1"
+            )
+            .bind(user_uuid)
+            .fetch_all(pool.get_ref())
+            .await;
+
+            match owned {
+                Ok(rows) => rows.into_iter().map(|(id,)| id).collect(),
+                Err(e) => {
+                    error!("Failed to list owned spaces: {:?}", e);
+                    return HttpResponse::InternalServerError()
+                        .json(ApiResponse::<()>::error("REINDEX_ERROR", "Re-index failed. Please try again later."));
+                }
+            }
+        }
+    };
+
+    let manager = SearchIndexManager::new(Arc::new(pool.get_ref().clone()));
+    let mut response = ReindexResponse {
+        space_ids: Vec::with_capacity(space_ids.len()),
+        total: 0,
+        indexed: 0,
+        failed: 0,
+    };
+
+    for space_id in space_ids {
+        match manager.reindex_space_with_stats(&space_id).await {
+            Ok(stats) => {
+                info!(
+                    "Re-indexed space {}: {} of {} documents ({} failed)",
+                    space_id, stats.indexed, stats.total, stats.failed
+                );
+                response.space_ids.push(space_id.to_string());
+                response.total += stats.total;
+                response.indexed += stats.indexed;
+                response.failed += stats.failed;
+            }
+            Err(e) => {
+                error!("Re-index error for space {}: {:?}", space_id, e);
+                return HttpResponse::InternalServerError()
+                    .json(ApiResponse::<()>::error("REINDEX_ERROR", "Re-index failed. Please try again later."));
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(ApiResponse::<ReindexResponse>::success(response))
+}
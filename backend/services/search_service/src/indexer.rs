@@ -6,9 +6,13 @@
 use async_trait::async_trait;
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing;
 use uuid::Uuid;
 
+/// Default number of documents re-indexed per batch
+pub const DEFAULT_REINDEX_BATCH_SIZE: usize = 500;
+
 /// Represents the content extracted from a document for indexing
 ///
 /// This struct contains the essential information needed to index a document
@@ -25,6 +29,218 @@ pub struct DocumentContent {
     pub space_id: Uuid,
 }
 
+/// Row shape used when re-reading documents for a re-index pass
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReindexRow {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub title: String,
+    pub content: serde_json::Value,
+}
+
+impl From<ReindexRow> for DocumentContent {
+    fn from(row: ReindexRow) -> Self {
+        Self {
+            document_id: row.id,
+            title: row.title,
+            content: row.content,
+            space_id: row.space_id,
+        }
+    }
+}
+
+/// Outcome of a re-index pass
+///
+/// `failed` counts documents that were skipped because indexing them
+/// returned an error; the pass itself still completes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReindexStats {
+    /// Number of documents selected for re-indexing
+    pub total: usize,
+    /// Number of documents successfully indexed
+    pub indexed: usize,
+    /// Number of documents that failed and were skipped
+    pub failed: usize,
+}
+
+/// Pacing of a re-index pass
+///
+/// Documents are read and indexed `batch_size` at a time; `batch_delay`
+/// optionally sleeps between batches to keep load on PostgreSQL down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReindexConfig {
+    pub batch_size: usize,
+    pub batch_delay: Option<Duration>,
+}
+
+impl Default for ReindexConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_REINDEX_BATCH_SIZE,
+            batch_delay: None,
+        }
+    }
+}
+
+impl ReindexConfig {
+    /// Read `SEARCH_REINDEX_BATCH_SIZE` and `SEARCH_REINDEX_BATCH_DELAY_MS`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let batch_size = std::env::var("SEARCH_REINDEX_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_REINDEX_BATCH_SIZE);
+        let batch_delay = std::env::var("SEARCH_REINDEX_BATCH_DELAY_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .map(Duration::from_millis);
+
+        Self {
+            batch_size,
+            batch_delay,
+        }
+    }
+}
+
+/// Progress of a running re-index pass, reported after each batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReindexProgress {
+    /// Documents handled so far, indexed or failed
+    pub processed: usize,
+    /// Documents expected in this pass
+    pub total: usize,
+}
+
+/// Where a re-index pass reads documents from
+///
+/// Batches are keyed by document id so each call resumes after the last
+/// row of the previous batch.
+#[async_trait]
+pub trait ReindexSource {
+    /// Number of documents the pass will cover
+    async fn count(&self) -> Result<usize, sqlx::Error>;
+
+    /// Up to `limit` documents with ids greater than `after`, ordered by id
+    async fn fetch_batch(&self, after: Option<Uuid>, limit: usize) -> Result<Vec<ReindexRow>, sqlx::Error>;
+}
+
+/// Reads non-archived documents, optionally limited to one space
+pub struct PgReindexSource {
+    pool: Arc<PgPool>,
+    space_id: Option<Uuid>,
+}
+
+impl PgReindexSource {
+    pub fn new(pool: Arc<PgPool>, space_id: Option<Uuid>) -> Self {
+        Self { pool, space_id }
+    }
+}
+
+#[async_trait]
+impl ReindexSource for PgReindexSource {
+    async fn count(&self) -> Result<usize, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM documents
+            WHERE is_archived = false AND ($1::uuid IS NULL OR space_id = $1)
+            "#,
+        )
+        .bind(self.space_id)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    async fn fetch_batch(&self, after: Option<Uuid>, limit: usize) -> Result<Vec<ReindexRow>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, space_id, title, content
+            FROM documents
+            WHERE is_archived = false
+              AND ($1::uuid IS NULL OR space_id = $1)
+              AND ($2::uuid IS NULL OR id > $2)
+            ORDER BY id
+            LIMIT $3
+            "#,
+        )
+        .bind(self.space_id)
+        .bind(after)
+        .bind(limit as i64)
+        .fetch_all(&*self.pool)
+        .await
+    }
+}
+
+/// Re-index every document from `source` in batches
+///
+/// A document that fails to index is logged and skipped so that one bad
+/// document does not abort the whole pass. `on_progress` is called after
+/// each batch.
+pub async fn run_reindex<S, I, F>(
+    source: &S,
+    indexer: &I,
+    config: &ReindexConfig,
+    mut on_progress: F,
+) -> Result<ReindexStats, sqlx::Error>
+where
+    S: ReindexSource + Sync,
+    I: SearchIndexer + Sync,
+    F: FnMut(ReindexProgress) + Send,
+{
+    let batch_size = config.batch_size.max(1);
+    let expected = source.count().await?;
+    let mut stats = ReindexStats::default();
+    let mut after = None;
+
+    loop {
+        let rows = source.fetch_batch(after, batch_size).await?;
+        let last_batch = rows.len() < batch_size;
+        after = rows.last().map(|row| row.id);
+
+        for row in rows {
+            let doc = DocumentContent::from(row);
+            stats.total += 1;
+            match indexer.index_document(&doc).await {
+                Ok(()) => stats.indexed += 1,
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to re-index document: id={}, error={}",
+                        doc.document_id,
+                        e
+                    );
+                    stats.failed += 1;
+                },
+            }
+        }
+
+        on_progress(ReindexProgress {
+            processed: stats.total,
+            // Documents created mid-pass can push the count past the estimate
+            total: expected.max(stats.total),
+        });
+
+        if last_batch {
+            break;
+        }
+        if let Some(delay) = config.batch_delay {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    if stats.failed > 0 {
+        tracing::warn!(
+            "reindex: {} of {} documents failed to index",
+            stats.failed,
+            stats.total
+        );
+    }
+
+    Ok(stats)
+}
+
 /// Indexer trait for document search indexing
 ///
 /// This trait defines the interface for search indexers, allowing for
@@ -287,6 +503,7 @@ impl SearchIndexer for PostgresSearchIndexer {
 /// individual document operations.
 pub struct SearchIndexManager {
     indexer: PostgresSearchIndexer,
+    reindex_config: ReindexConfig,
 }
 
 impl SearchIndexManager {
@@ -298,9 +515,16 @@ impl SearchIndexManager {
     pub fn new(pool: Arc<PgPool>) -> Self {
         Self {
             indexer: PostgresSearchIndexer::new(pool),
+            reindex_config: ReindexConfig::from_env(),
         }
     }
 
+    /// Override the batch size and pacing used by re-index passes
+    pub fn with_reindex_config(mut self, config: ReindexConfig) -> Self {
+        self.reindex_config = config;
+        self
+    }
+
     /// Initialize search indexes in the database
     ///
     /// This method creates necessary extensions and indexes for
@@ -326,6 +550,55 @@ impl SearchIndexManager {
         self.indexer.rebuild_index().await
     }
 
+    /// Re-index every non-archived document in a single space
+    ///
+    /// # Arguments
+    ///
+    /// * `space_id` - ID of the space to re-index
+    ///
+    /// # Returns
+    ///
+    /// Number of successfully indexed documents
+    pub async fn reindex_space(&self, space_id: &Uuid) -> Result<usize, sqlx::Error> {
+        Ok(self.reindex_space_with_stats(space_id).await?.indexed)
+    }
+
+    /// Re-index every non-archived document across all spaces
+    ///
+    /// # Returns
+    ///
+    /// Number of successfully indexed documents
+    pub async fn reindex_all(&self) -> Result<usize, sqlx::Error> {
+        Ok(self.reindex_all_with_stats().await?.indexed)
+    }
+
+    /// Re-index a single space, reporting indexed and failed counts
+    ///
+    /// A document that fails to index is logged and skipped so that one bad
+    /// document does not abort the whole pass.
+    pub async fn reindex_space_with_stats(&self, space_id: &Uuid) -> Result<ReindexStats, sqlx::Error> {
+        self.reindex_with_progress(Some(*space_id), log_progress).await
+    }
+
+    /// Re-index all spaces, reporting indexed and failed counts
+    pub async fn reindex_all_with_stats(&self) -> Result<ReindexStats, sqlx::Error> {
+        self.reindex_with_progress(None, log_progress).await
+    }
+
+    /// Re-index one space, or every space when `space_id` is `None`, calling
+    /// `on_progress` after each batch
+    pub async fn reindex_with_progress<F>(
+        &self,
+        space_id: Option<Uuid>,
+        on_progress: F,
+    ) -> Result<ReindexStats, sqlx::Error>
+    where
+        F: FnMut(ReindexProgress) + Send,
+    {
+        let source = PgReindexSource::new(self.indexer.pool.clone(), space_id);
+        run_reindex(&source, &self.indexer, &self.reindex_config, on_progress).await
+    }
+
     /// Index a single document
     ///
     /// # Arguments
@@ -353,6 +626,10 @@ impl SearchIndexManager {
     }
 }
 
+fn log_progress(progress: ReindexProgress) {
+    tracing::info!("reindex progress: {}/{}", progress.processed, progress.total);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -633,6 +910,140 @@ mod tests {
         assert_eq!(result.unwrap(), 100, "should report 100 indexed documents");
     }
 
+    // ========================================
+    // Batched Re-index Tests
+    // ========================================
+
+    /// In-memory source that records the limit of every batch it serves
+    struct InstrumentedSource {
+        rows: Vec<ReindexRow>,
+        requested_limits: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl InstrumentedSource {
+        fn with_documents(count: usize) -> Self {
+            let mut rows: Vec<ReindexRow> = (0..count)
+                .map(|i| ReindexRow {
+                    id: Uuid::new_v4(),
+                    space_id: Uuid::nil(),
+                    title: format!("Doc {}", i),
+                    content: serde_json::json!({"text": "body"}),
+                })
+                .collect();
+            rows.sort_by_key(|row| row.id);
+            Self {
+                rows,
+                requested_limits: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ReindexSource for InstrumentedSource {
+        async fn count(&self) -> Result<usize, sqlx::Error> {
+            Ok(self.rows.len())
+        }
+
+        async fn fetch_batch(&self, after: Option<Uuid>, limit: usize) -> Result<Vec<ReindexRow>, sqlx::Error> {
+            self.requested_limits.lock().unwrap().push(limit);
+            Ok(self
+                .rows
+                .iter()
+                .filter(|row| after.is_none_or(|after| row.id > after))
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_honors_batch_size() {
+        let source = InstrumentedSource::with_documents(7);
+        let mut indexer = MockIndexer::new();
+        indexer.expect_index_document().returning(|_| Ok(())).times(7);
+        let config = ReindexConfig {
+            batch_size: 3,
+            batch_delay: None,
+        };
+
+        let mut batch_sizes = Vec::new();
+        let mut last_processed = 0;
+        let stats = run_reindex(&source, &indexer, &config, |progress| {
+            batch_sizes.push(progress.processed - last_processed);
+            last_processed = progress.processed;
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(*source.requested_limits.lock().unwrap(), vec![3, 3, 3]);
+        assert_eq!(batch_sizes, vec![3, 3, 1]);
+        assert_eq!(
+            stats,
+            ReindexStats {
+                total: 7,
+                indexed: 7,
+                failed: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reindex_progress_is_monotonic_to_completion() {
+        let source = InstrumentedSource::with_documents(10);
+        let mut indexer = MockIndexer::new();
+        // One failure still counts as processed
+        let mut calls = 0;
+        indexer.expect_index_document().returning(move |_| {
+            calls += 1;
+            if calls == 4 {
+                Err(sqlx::Error::RowNotFound)
+            } else {
+                Ok(())
+            }
+        });
+        let config = ReindexConfig {
+            batch_size: 4,
+            batch_delay: Some(Duration::from_millis(1)),
+        };
+
+        let mut progress = Vec::new();
+        let stats = run_reindex(&source, &indexer, &config, |p| progress.push(p))
+            .await
+            .unwrap();
+
+        assert!(progress.windows(2).all(|w| w[0].processed < w[1].processed));
+        assert!(progress.iter().all(|p| p.total == 10));
+        assert_eq!(
+            progress.last(),
+            Some(&ReindexProgress {
+                processed: 10,
+                total: 10
+            })
+        );
+        assert_eq!(stats.indexed, 9);
+        assert_eq!(stats.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reindex_empty_source_reports_completion() {
+        let source = InstrumentedSource::with_documents(0);
+        let indexer = MockIndexer::new();
+
+        let mut progress = Vec::new();
+        let stats = run_reindex(&source, &indexer, &ReindexConfig::default(), |p| progress.push(p))
+            .await
+            .unwrap();
+
+        assert_eq!(stats, ReindexStats::default());
+        assert_eq!(
+            progress,
+            vec![ReindexProgress {
+                processed: 0,
+                total: 0
+            }]
+        );
+    }
+
     // ========================================
     // Edge Case Tests
     // ========================================
@@ -652,6 +1063,24 @@ mod tests {
         assert_eq!(space_id, content.space_id);
     }
 
+    #[test]
+    fn test_document_content_from_reindex_row() {
+        let doc_id = Uuid::new_v4();
+        let space_id = Uuid::new_v4();
+        let row = ReindexRow {
+            id: doc_id,
+            space_id,
+            title: "Reindexed".to_string(),
+            content: serde_json::json!({"ops": [{"insert": "Body"}]}),
+        };
+
+        let content = DocumentContent::from(row);
+        assert_eq!(content.document_id, doc_id);
+        assert_eq!(content.space_id, space_id);
+        assert_eq!(content.title, "Reindexed");
+        assert_eq!(PostgresSearchIndexer::extract_text_content(&content.content), "Body");
+    }
+
     #[test]
     fn test_document_content_with_empty_title() {
         let doc_id = Uuid::new_v4();
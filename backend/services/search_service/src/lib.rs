@@ -1,7 +1,10 @@
+pub mod export;
 pub mod handlers;
 pub mod models;
 pub mod repository;
 pub mod indexer;
+pub mod query_parser;
+pub mod rate_limit;
 
 use actix_web::web;
 use crate::handlers::*;
@@ -10,5 +13,8 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/search")
             .route("", web::get().to(search_documents))
+            .route("/suggest", web::get().to(suggest_documents))
+            .route("/export", web::get().to(export_search_results))
+            .route("/reindex", web::post().to(reindex_documents))
     );
 }
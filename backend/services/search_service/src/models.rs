@@ -7,6 +7,7 @@ use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct SearchQuery {
+    #[serde(default)]
     #[validate(length(min = 1, max = 500))]
     pub q: String,
 
@@ -17,6 +18,77 @@ pub struct SearchQuery {
 
     #[validate(range(min = 0))]
     pub offset: Option<i32>,
+
+    /// Include archived documents; only space owners may set this
+    #[serde(default)]
+    pub include_archived: bool,
+
+    /// Comma-separated facets to count over all matches, e.g. `space,tag`
+    pub facets: Option<String>,
+}
+
+/// Aggregation that `GET /search?facets=` can return alongside the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Space,
+    Tag,
+}
+
+impl Facet {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "space" => Some(Self::Space),
+            "tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+
+    /// Parse a comma-separated list, ignoring empty entries and duplicates
+    pub fn parse_list(value: &str) -> Result<Vec<Self>, String> {
+        let mut facets = Vec::new();
+        for name in value.split(',').filter(|n| !n.trim().is_empty()) {
+            let facet = Self::parse(name).ok_or_else(|| format!("Unknown facet '{}'", name.trim()))?;
+            if !facets.contains(&facet) {
+                facets.push(facet);
+            }
+        }
+        Ok(facets)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestQuery {
+    /// Title prefix typed so far; fewer than 2 characters yields no suggestions
+    #[serde(default)]
+    pub q: String,
+
+    /// Maximum suggestions to return, capped at 10
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ExportQuery {
+    #[validate(length(min = 1, max = 500))]
+    pub q: String,
+
+    pub space_id: Option<String>,
+
+    /// Export format; only `csv` is supported
+    #[serde(default = "default_export_format")]
+    pub format: String,
+
+    /// Maximum rows to export, capped at `EXPORT_MAX_ROWS`
+    pub limit: Option<i32>,
+}
+
+fn default_export_format() -> String {
+    "csv".to_string()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReindexRequest {
+    /// Space to re-index; when omitted, every space owned by the caller is re-indexed
+    pub space_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +111,8 @@ pub struct SearchResult {
     pub title: String,
     pub snippet: String,
     pub score: f64,
+    #[serde(default)]
+    pub is_archived: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,6 +120,31 @@ pub struct SearchResponse {
     pub results: Vec<SearchResult>,
     pub total: i64,
     pub took: i64,
+    /// Only present when facets were requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facets: Option<SearchFacets>,
+}
+
+/// Match counts over the whole result set, not just the returned page
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchFacets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space: Option<Vec<SpaceFacet>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Vec<TagFacet>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceFacet {
+    pub id: String,
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagFacet {
+    pub name: String,
+    pub count: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,6 +154,53 @@ pub struct SearchIndexResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SuggestItem {
+    pub document_id: String,
+    pub title: String,
+    pub space_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReindexResponse {
+    pub space_ids: Vec<String>,
+    pub total: usize,
+    pub indexed: usize,
+    pub failed: usize,
+}
+
+// ============================================
+// Configuration
+// ============================================
+
+/// How `GET /search` treats an empty (or whitespace-only) query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyQueryBehavior {
+    /// Reject with a 400 validation error
+    #[default]
+    Reject,
+    /// List the most recently updated documents the user can access
+    Recent,
+}
+
+impl EmptyQueryBehavior {
+    /// Read from `SEARCH_EMPTY_QUERY` (`reject` or `recent`), defaulting to `reject`
+    pub fn from_env() -> Self {
+        std::env::var("SEARCH_EMPTY_QUERY")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "reject" => Some(Self::Reject),
+            "recent" => Some(Self::Recent),
+            _ => None,
+        }
+    }
+}
+
 // ============================================
 // API Response Wrapper
 // ============================================
@@ -63,6 +209,9 @@ pub struct SearchIndexResponse {
 pub struct ApiErrorResponse {
     pub error: String,
     pub message: String,
+    /// Messages per invalid field, for `VALIDATION_ERROR`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,7 +237,57 @@ impl<T> ApiResponse<T> {
             error: Some(ApiErrorResponse {
                 error: error_code.to_string(),
                 message: message.to_string(),
+                details: None,
             }),
         }
     }
+
+    /// `VALIDATION_ERROR` listing the messages for each invalid field
+    pub fn validation_error(errors: &validator::ValidationErrors) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(ApiErrorResponse {
+                error: "VALIDATION_ERROR".to_string(),
+                message: "Validation failed".to_string(),
+                details: Some(shared_errors::validation_errors_to_json(errors)),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_facet_list_parsing() {
+        assert_eq!(Facet::parse_list("space,tag"), Ok(vec![Facet::Space, Facet::Tag]));
+        assert_eq!(Facet::parse_list(" Tag , space,tag,"), Ok(vec![Facet::Tag, Facet::Space]));
+        assert_eq!(Facet::parse_list(""), Ok(vec![]));
+        assert_eq!(Facet::parse_list("space,author"), Err("Unknown facet 'author'".to_string()));
+    }
+
+    #[test]
+    fn test_search_response_omits_facets_unless_requested() {
+        let response = SearchResponse {
+            results: vec![],
+            total: 0,
+            took: 1,
+            facets: None,
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("facets").is_none());
+
+        let response = SearchResponse {
+            facets: Some(SearchFacets {
+                space: None,
+                tag: Some(vec![TagFacet { name: "rust".to_string(), count: 2 }]),
+            }),
+            ..response
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json["facets"].get("space").is_none());
+        assert_eq!(json["facets"]["tag"][0]["count"], 2);
+    }
 }
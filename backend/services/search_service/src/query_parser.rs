@@ -0,0 +1,217 @@
+//! Search query parsing for boolean and phrase operators
+//!
+//! Supports a small query language on top of the plain `q` string:
+//!
+//! - `"exact phrase"` matches the words in order
+//! - `-term` excludes documents containing `term`
+//! - `AND` between terms is accepted and implied by default
+//!
+//! A parsed query is translated into a PostgreSQL `tsquery` string. Input that
+//! cannot be parsed (for example an unbalanced quote) yields `None` so callers
+//! can fall back to plain-text search instead of returning an error.
+
+/// Structured representation of a search query
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    /// Terms that must all appear
+    pub must: Vec<String>,
+    /// Terms that must not appear
+    pub must_not: Vec<String>,
+    /// Phrases whose words must appear adjacent and in order
+    pub phrases: Vec<String>,
+    /// Whether the input used any operator syntax (quotes, `-`, `AND`)
+    pub has_operators: bool,
+}
+
+impl ParsedQuery {
+    /// Translate the query into a PostgreSQL `tsquery` expression
+    ///
+    /// Every lexeme is single-quoted with embedded quotes and backslashes
+    /// escaped, so user input cannot inject tsquery operators.
+    pub fn to_tsquery(&self) -> String {
+        let mut clauses: Vec<String> = Vec::new();
+
+        for phrase in &self.phrases {
+            let words: Vec<String> = phrase.split_whitespace().map(quote_lexeme).collect();
+            match words.len() {
+                0 => {},
+                1 => clauses.push(words.into_iter().next().unwrap_or_default()),
+                _ => clauses.push(format!("({})", words.join(" <-> "))),
+            }
+        }
+
+        clauses.extend(self.must.iter().map(|term| quote_lexeme(term)));
+        clauses.extend(self.must_not.iter().map(|term| format!("!{}", quote_lexeme(term))));
+
+        clauses.join(" & ")
+    }
+
+    /// Term used to highlight matches in result snippets
+    pub fn highlight_term(&self) -> Option<&str> {
+        self.phrases.first().or_else(|| self.must.first()).map(String::as_str)
+    }
+}
+
+/// Parse a raw search string into a [`ParsedQuery`]
+///
+/// Returns `None` for malformed input: unbalanced quotes, empty phrases,
+/// dangling `-` or `AND`, or a query with no positive terms.
+pub fn parse_query(input: &str) -> Option<ParsedQuery> {
+    let mut parsed = ParsedQuery::default();
+    let mut chars = input.chars().peekable();
+    // Tracks whether the previous token was a bare AND waiting for an operand
+    let mut pending_and = false;
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let negated = c == '-';
+        if negated {
+            chars.next();
+            parsed.has_operators = true;
+            match chars.peek() {
+                Some(next) if !next.is_whitespace() && *next != '"' && *next != '-' => {},
+                _ => return None,
+            }
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            parsed.has_operators = true;
+            let mut phrase = String::new();
+            let mut closed = false;
+            for ch in chars.by_ref() {
+                if ch == '"' {
+                    closed = true;
+                    break;
+                }
+                phrase.push(ch);
+            }
+            let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !closed || phrase.is_empty() {
+                return None;
+            }
+            parsed.phrases.push(phrase);
+            pending_and = false;
+            continue;
+        }
+
+        let mut term = String::new();
+        while let Some(&ch) = chars.peek() {
+            if ch.is_whitespace() {
+                break;
+            }
+            if ch == '"' {
+                // Quote glued to a term, e.g. foo"bar
+                return None;
+            }
+            term.push(ch);
+            chars.next();
+        }
+
+        if !negated && term == "AND" {
+            if pending_and || (parsed.must.is_empty() && parsed.phrases.is_empty() && parsed.must_not.is_empty()) {
+                return None;
+            }
+            parsed.has_operators = true;
+            pending_and = true;
+            continue;
+        }
+
+        if negated {
+            parsed.must_not.push(term);
+        } else {
+            parsed.must.push(term);
+        }
+        pending_and = false;
+    }
+
+    if pending_and || (parsed.must.is_empty() && parsed.phrases.is_empty()) {
+        return None;
+    }
+
+    Some(parsed)
+}
+
+// Quote a lexeme for safe inclusion in a tsquery expression
+fn quote_lexeme(term: &str) -> String {
+    format!("'{}'", term.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_terms() {
+        let parsed = parse_query("rust  async").unwrap();
+        assert_eq!(parsed.must, vec!["rust", "async"]);
+        assert!(parsed.must_not.is_empty());
+        assert!(parsed.phrases.is_empty());
+        assert!(!parsed.has_operators);
+        assert_eq!(parsed.to_tsquery(), "'rust' & 'async'");
+    }
+
+    #[test]
+    fn test_parse_quoted_phrase() {
+        let parsed = parse_query(r#""exact  phrase" other"#).unwrap();
+        assert_eq!(parsed.phrases, vec!["exact phrase"]);
+        assert_eq!(parsed.must, vec!["other"]);
+        assert!(parsed.has_operators);
+        assert_eq!(parsed.to_tsquery(), "('exact' <-> 'phrase') & 'other'");
+        assert_eq!(parsed.highlight_term(), Some("exact phrase"));
+    }
+
+    #[test]
+    fn test_parse_exclusion_and_explicit_and() {
+        let parsed = parse_query("term AND other -excluded").unwrap();
+        assert_eq!(parsed.must, vec!["term", "other"]);
+        assert_eq!(parsed.must_not, vec!["excluded"]);
+        assert!(parsed.has_operators);
+        assert_eq!(parsed.to_tsquery(), "'term' & 'other' & !'excluded'");
+    }
+
+    #[test]
+    fn test_hyphenated_word_is_not_exclusion() {
+        let parsed = parse_query("e-mail").unwrap();
+        assert_eq!(parsed.must, vec!["e-mail"]);
+        assert!(parsed.must_not.is_empty());
+    }
+
+    #[test]
+    fn test_tsquery_escapes_special_characters() {
+        let parsed = parse_query(r"it's a\b x&y|z:*").unwrap();
+        assert_eq!(parsed.to_tsquery(), r"'it''s' & 'a\\b' & 'x&y|z:*'");
+    }
+
+    #[test]
+    fn test_malformed_unbalanced_quote_falls_back() {
+        assert!(parse_query(r#""unterminated phrase"#).is_none());
+        assert!(parse_query(r#"foo"bar"#).is_none());
+    }
+
+    #[test]
+    fn test_malformed_operators_fall_back() {
+        assert!(parse_query("-").is_none());
+        assert!(parse_query("foo -").is_none());
+        assert!(parse_query("AND foo").is_none());
+        assert!(parse_query("foo AND").is_none());
+        assert!(parse_query("foo AND AND bar").is_none());
+        assert!(parse_query(r#""""#).is_none());
+        assert!(parse_query(r#"-"negated phrase""#).is_none());
+    }
+
+    #[test]
+    fn test_only_exclusions_falls_back() {
+        assert!(parse_query("-spam -eggs").is_none());
+    }
+
+    #[test]
+    fn test_empty_query_falls_back() {
+        assert!(parse_query("").is_none());
+        assert!(parse_query("   ").is_none());
+    }
+}
@@ -0,0 +1,120 @@
+//! Per-user rate limit for `GET /search`
+//!
+//! Full-text queries are the most expensive reads the service serves, so each
+//! user gets a token bucket (see `shared_cache::rate_limit`). Requests over
+//! the limit get `429 RATE_LIMITED` with a `Retry-After` header.
+
+use actix_web::http::header;
+use actix_web::HttpResponse;
+use shared_cache::rate_limit::{retry_after_secs, RateLimitConfig, TokenBucketLimiter};
+use std::time::Duration;
+
+use crate::models::ApiResponse;
+
+/// Default sustained searches per second for one user
+pub const DEFAULT_SEARCH_RATE_PER_SEC: f64 = 2.0;
+
+/// Default searches one user can make back to back
+pub const DEFAULT_SEARCH_BURST: f64 = 10.0;
+
+/// Error code for searches refused by the limiter
+pub const RATE_LIMITED_CODE: &str = "RATE_LIMITED";
+
+const KEY_PREFIX: &str = "search:ratelimit:";
+
+/// Token-bucket limiter for search requests, keyed by user id
+pub struct SearchRateLimit(TokenBucketLimiter);
+
+impl SearchRateLimit {
+    /// In-memory limiter, for a single instance or tests
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self(TokenBucketLimiter::new(KEY_PREFIX, config))
+    }
+
+    /// Read `SEARCH_RATE_PER_SEC` and `SEARCH_RATE_BURST`, keeping buckets in
+    /// Redis when `redis_url` is reachable
+    pub async fn connect_from_env(redis_url: Option<&str>) -> Self {
+        let config = RateLimitConfig::from_env(
+            "SEARCH_RATE_PER_SEC",
+            "SEARCH_RATE_BURST",
+            RateLimitConfig {
+                per_sec: DEFAULT_SEARCH_RATE_PER_SEC,
+                burst: DEFAULT_SEARCH_BURST,
+            },
+        );
+        Self(TokenBucketLimiter::connect(KEY_PREFIX, config, redis_url).await)
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        self.0.config()
+    }
+
+    /// Spend one of `user_id`'s searches, or get the 429 to send back
+    pub async fn check(&self, user_id: &str) -> Result<(), HttpResponse> {
+        self.0.check(user_id).await.map_err(rate_limited_response)
+    }
+}
+
+/// 429 telling the client when to retry
+pub fn rate_limited_response(wait: Duration) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header((header::RETRY_AFTER, retry_after_secs(wait).to_string()))
+        .json(ApiResponse::<()>::error(
+            RATE_LIMITED_CODE,
+            "Too many searches. Please try again later.",
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::search_documents;
+    use crate::repository::SearchRepository;
+    use actix_web::{test, web, App};
+    use std::sync::Arc;
+
+    const USER_ID: &str = "550e8400-e29b-41d4-a716-446655440000";
+
+    #[actix_rt::test]
+    async fn test_search_over_limit_gets_429_with_retry_after() {
+        // Lazy pool: refused requests never reach the database
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let limiter = SearchRateLimit::new(RateLimitConfig {
+            per_sec: 0.5,
+            burst: 1.0,
+        });
+        // Spend the only token up front
+        assert!(limiter.check(USER_ID).await.is_ok());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(SearchRepository::new(Arc::new(pool))))
+                .app_data(web::Data::new(limiter))
+                .route("/search", web::get().to(search_documents)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/search?q=roadmap")
+            .insert_header(("X-User-Id", USER_ID))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "2");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["error"], RATE_LIMITED_CODE);
+    }
+
+    #[actix_rt::test]
+    async fn test_limit_is_per_user() {
+        let limiter = SearchRateLimit::new(RateLimitConfig {
+            per_sec: 1.0,
+            burst: 1.0,
+        });
+
+        assert!(limiter.check("user-1").await.is_ok());
+        assert!(limiter.check("user-1").await.is_err());
+        assert!(limiter.check("user-2").await.is_ok());
+    }
+}
@@ -2,7 +2,10 @@ use sqlx::PgPool;
 use uuid::Uuid;
 use std::sync::Arc;
 use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
 use regex::{Regex, Captures};
+use crate::models::Facet;
+use crate::query_parser::{parse_query, ParsedQuery};
 
 // Row types for search results
 #[derive(sqlx::FromRow)]
@@ -13,17 +16,146 @@ pub struct SearchResultRow {
     pub title: String,
     pub content: serde_json::Value,
     pub score: f64,
+    pub is_archived: bool,
+}
+
+/// Row type for title suggestions
+#[derive(Debug, sqlx::FromRow)]
+pub struct SuggestionRow {
+    pub document_id: Uuid,
+    pub title: String,
+    pub space_name: String,
+}
+
+/// Documents matched in one space, for the `space` facet
+#[derive(Debug, sqlx::FromRow)]
+pub struct SpaceFacetRow {
+    pub space_id: Uuid,
+    pub space_name: String,
+    pub count: i64,
+}
+
+/// Documents matched carrying one tag, for the `tag` facet
+#[derive(Debug, sqlx::FromRow)]
+pub struct TagFacetRow {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// Facet counts; each is `None` unless that facet was requested
+#[derive(Debug, Default)]
+pub struct FacetCounts {
+    pub space: Option<Vec<SpaceFacetRow>>,
+    pub tag: Option<Vec<TagFacetRow>>,
+}
+
+/// Most buckets returned for one facet
+pub const FACET_MAX_BUCKETS: i64 = 50;
+
+/// Export rows read ahead of the response body
+pub const EXPORT_BUFFERED_ROWS: usize = 64;
+
+/// Minimum number of characters before suggestions are returned
+pub const SUGGEST_MIN_PREFIX_LEN: usize = 2;
+
+/// Upper bound on the number of suggestions returned per request
+pub const SUGGEST_MAX_LIMIT: i32 = 10;
+
+/// Normalize suggest parameters
+///
+/// Returns `None` when the trimmed prefix is shorter than
+/// [`SUGGEST_MIN_PREFIX_LEN`] characters, otherwise the trimmed prefix and a
+/// limit clamped to `1..=SUGGEST_MAX_LIMIT`.
+pub fn normalize_suggest_params(prefix: &str, limit: Option<i32>) -> Option<(&str, i32)> {
+    let prefix = prefix.trim();
+    if prefix.chars().count() < SUGGEST_MIN_PREFIX_LEN {
+        return None;
+    }
+    Some((prefix, limit.unwrap_or(SUGGEST_MAX_LIMIT).clamp(1, SUGGEST_MAX_LIMIT)))
+}
+
+// Escape LIKE wildcards so user input is matched literally
+fn escape_like(input: &str) -> String {
+    input.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// SQL fragments that decide how documents are matched and ranked for a query
+struct QueryMatcher {
+    condition: &'static str,
+    score: &'static str,
+    order: &'static str,
+    bind_value: String,
+}
+
+impl QueryMatcher {
+    // Case-insensitive substring match on title and content
+    fn plain(query: &str) -> Self {
+        Self {
+            condition: "(d.title ILIKE $1 OR d.content_text ILIKE $1)",
+            score: r#"(
+                        CASE
+                            WHEN d.title ILIKE $1 THEN 2.0
+                            ELSE 1.0
+                        END +
+                        CASE
+                            WHEN d.title ILIKE $1 || ' %' THEN 0.5
+                            ELSE 0.0
+                        END
+                    )"#,
+            order: "CASE WHEN d.title ILIKE $1 THEN 0 ELSE 1 END, d.updated_at DESC",
+            bind_value: format!("%{}%", query),
+        }
+    }
+
+    // Full-text match against a tsquery built from the parsed query
+    fn full_text(parsed: &ParsedQuery) -> Self {
+        Self {
+            condition: "to_tsvector('english', d.title || ' ' || COALESCE(d.content_text, '')) \
+                        @@ to_tsquery('english', $1)",
+            score: "ts_rank(to_tsvector('english', d.title || ' ' || COALESCE(d.content_text, '')), \
+                    to_tsquery('english', $1))::float8",
+            order: "score DESC, d.updated_at DESC",
+            bind_value: parsed.to_tsquery(),
+        }
+    }
+}
+
+// Archived documents are hidden unless the flag bound at `$param` is set,
+// and even then only in spaces the user ($2) owns
+fn archived_condition(param: usize) -> String {
+    format!(
+        "(d.is_archived = false OR (${param}::boolean AND EXISTS (\
+            SELECT 1 FROM spaces os WHERE os.id = d.space_id AND os.owner_id = $2)))",
+        param = param
+    )
 }
 
 #[async_trait]
 pub trait SearchRepositoryTrait {
     async fn search(
         &self,
-        user_id: &str,
+        user_id: Uuid,
         query: &str,
         space_id: Option<&str>,
         limit: i32,
         offset: i32,
+        include_archived: bool,
+    ) -> Result<(Vec<SearchResultRow>, i64), sqlx::Error>;
+
+    async fn suggest(
+        &self,
+        user_id: Uuid,
+        prefix: &str,
+        limit: Option<i32>,
+    ) -> Result<Vec<SuggestionRow>, sqlx::Error>;
+
+    /// Most recently updated documents the user can access, used for empty queries
+    async fn recent(
+        &self,
+        user_id: Uuid,
+        space_id: Option<&str>,
+        limit: i32,
+        offset: i32,
     ) -> Result<(Vec<SearchResultRow>, i64), sqlx::Error>;
 }
 
@@ -35,63 +167,266 @@ impl SearchRepository {
     pub fn new(pool: Arc<PgPool>) -> Self {
         Self { pool }
     }
+
+    /// Whether the user may search archived documents: they must own the
+    /// given space, or own at least one space when no space is given
+    pub async fn can_include_archived(&self, user_id: Uuid, space_id: Option<&str>) -> Result<bool, sqlx::Error> {
+        let space_uuid: Option<Uuid> = match space_id {
+            Some(sid) => Some(sid.parse().map_err(|_| sqlx::Error::Decode("Invalid space ID format".into()))?),
+            None => None,
+        };
+
+        let (owns,): (bool,) = sqlx::query_as(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM spaces
+                WHERE owner_id = $1 AND is_deleted = false AND ($2::uuid IS NULL OR id = $2)
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(space_uuid)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(owns)
+    }
+
+    /// Count matching documents by space and/or tag
+    ///
+    /// Uses the same match, archived and membership filters as `search`, so
+    /// the counts cover exactly the documents a search could return. With no
+    /// query every accessible document counts, as for the recent listing.
+    pub async fn facets(
+        &self,
+        user_id: Uuid,
+        query: Option<&str>,
+        space_id: Option<&str>,
+        include_archived: bool,
+        facets: &[Facet],
+    ) -> Result<FacetCounts, sqlx::Error> {
+        let mut counts = FacetCounts::default();
+        if facets.is_empty() {
+            return Ok(counts);
+        }
+
+        let space_uuid: Option<Uuid> = match space_id {
+            Some(sid) => Some(sid.parse().map_err(|_| sqlx::Error::Decode("Invalid space ID format".into()))?),
+            None => None,
+        };
+
+        // Without a query $1 is bound as NULL and every document matches
+        let matcher = query.map(|query| match parse_query(query).filter(|p| p.has_operators) {
+            Some(parsed) => QueryMatcher::full_text(&parsed),
+            None => QueryMatcher::plain(query),
+        });
+        let condition = matcher.as_ref().map_or("$1::text IS NULL", |m| m.condition);
+        let pattern = matcher.map(|m| m.bind_value);
+
+        let filter = format!(r#"
+            {archived}
+            AND {condition}
+            AND ($3::uuid IS NULL OR d.space_id = $3)
+            AND EXISTS (
+                SELECT 1 FROM space_memberships sm
+                JOIN spaces s ON sm.space_id = s.id
+                WHERE sm.space_id = d.space_id
+                AND sm.user_id = $2
+                AND s.is_deleted = false
+            )
+            "#, archived = archived_condition(4), condition = condition);
+
+        if facets.contains(&Facet::Space) {
+            let space_sql = format!(r#"
+            SELECT d.space_id, s.name as space_name, COUNT(*) as count
+            FROM documents d
+            JOIN spaces s ON d.space_id = s.id
+            WHERE {filter}
+            GROUP BY d.space_id, s.name
+            ORDER BY count DESC, s.name
+            LIMIT $5
+            "#, filter = filter);
+            counts.space = Some(
+                sqlx::query_as(&space_sql)
+                    .bind(&pattern)
+                    .bind(user_id)
+                    .bind(space_uuid)
+                    .bind(include_archived)
+                    .bind(FACET_MAX_BUCKETS)
+                    .fetch_all(&*self.pool)
+                    .await?,
+            );
+        }
+
+        if facets.contains(&Facet::Tag) {
+            let tag_sql = format!(r#"
+            SELECT dt.tag, COUNT(*) as count
+            FROM documents d
+            JOIN document_tags dt ON dt.document_id = d.id
+            WHERE {filter}
+            GROUP BY dt.tag
+            ORDER BY count DESC, dt.tag
+            LIMIT $5
+            "#, filter = filter);
+            counts.tag = Some(
+                sqlx::query_as(&tag_sql)
+                    .bind(&pattern)
+                    .bind(user_id)
+                    .bind(space_uuid)
+                    .bind(include_archived)
+                    .bind(FACET_MAX_BUCKETS)
+                    .fetch_all(&*self.pool)
+                    .await?,
+            );
+        }
+
+        Ok(counts)
+    }
+
+    /// Stream the documents `search` would return for `query`, best first
+    ///
+    /// Rows are fetched as the stream is polled, with at most
+    /// `EXPORT_BUFFERED_ROWS` read ahead, so memory stays flat however many
+    /// documents match. Archived documents are left out, and `content` is
+    /// `null` since exports carry no snippets.
+    pub fn export(
+        &self,
+        user_id: Uuid,
+        query: &str,
+        space_id: Option<&str>,
+        limit: i32,
+    ) -> impl Stream<Item = Result<SearchResultRow, sqlx::Error>> + 'static {
+        let parsed = parse_query(query).filter(|p| p.has_operators);
+        let matcher = match &parsed {
+            Some(parsed) => QueryMatcher::full_text(parsed),
+            None => QueryMatcher::plain(query),
+        };
+        let space_id = space_id.map(str::to_string);
+        let pool = Arc::clone(&self.pool);
+
+        // The query runs in its own task so the stream owns everything it
+        // borrows; the bounded channel holds it back while the client reads
+        let (tx, rx) = tokio::sync::mpsc::channel(EXPORT_BUFFERED_ROWS);
+        tokio::spawn(async move {
+            let space_uuid: Option<Uuid> = match space_id.as_deref().map(str::parse).transpose() {
+                Ok(space_uuid) => space_uuid,
+                Err(_) => {
+                    let _ = tx.send(Err(sqlx::Error::Decode("Invalid space ID format".into()))).await;
+                    return;
+                }
+            };
+
+            let export_sql = format!(r#"
+            SELECT
+                d.id as document_id,
+                d.space_id,
+                s.name as space_name,
+                d.title,
+                'null'::jsonb as content,
+                {score} as score,
+                d.is_archived
+            FROM documents d
+            JOIN spaces s ON d.space_id = s.id
+            WHERE d.is_archived = false
+            AND {condition}
+            AND ($3::uuid IS NULL OR d.space_id = $3)
+            AND EXISTS (
+                SELECT 1 FROM space_memberships sm
+                JOIN spaces s ON sm.space_id = s.id
+                WHERE sm.space_id = d.space_id
+                AND sm.user_id = $2
+                AND s.is_deleted = false
+            )
+            ORDER BY {order}
+            LIMIT $4
+            "#, condition = matcher.condition, score = matcher.score, order = matcher.order);
+
+            let mut rows = sqlx::query_as::<_, SearchResultRow>(&export_sql)
+                .bind(&matcher.bind_value)
+                .bind(user_id)
+                .bind(space_uuid)
+                .bind(limit)
+                .fetch(&*pool);
+            while let Some(row) = rows.next().await {
+                let failed = row.is_err();
+                // A closed channel means the client went away
+                if tx.send(row).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|row| (row, rx)) })
+    }
 }
 
 #[async_trait]
 impl SearchRepositoryTrait for SearchRepository {
     async fn search(
         &self,
-        user_id: &str,
+        user_id: Uuid,
         query: &str,
         space_id: Option<&str>,
         limit: i32,
         offset: i32,
+        include_archived: bool,
     ) -> Result<(Vec<SearchResultRow>, i64), sqlx::Error> {
-        let user_uuid: Uuid = user_id.parse()
-            .map_err(|_| sqlx::Error::Decode("Invalid user ID format".into()))?;
+        // Route the query through the parser; operator syntax uses full-text
+        // search, while plain or malformed input keeps the substring match
+        let parsed = parse_query(query).filter(|p| p.has_operators);
+        let matcher = match &parsed {
+            Some(parsed) => QueryMatcher::full_text(parsed),
+            None => QueryMatcher::plain(query),
+        };
+        let highlight = parsed.as_ref().and_then(|p| p.highlight_term()).unwrap_or(query);
 
         // Count total results
-        let query_pattern = format!("%{}%", query);
+        let query_pattern = matcher.bind_value.clone();
 
         let total: i64 = match space_id {
             Some(sid) => {
-                let count_sql = r#"
+                let count_sql = format!(r#"
                 SELECT COUNT(*) as total
                 FROM documents d
-                WHERE d.is_archived = false
-                AND (d.title ILIKE $1 OR d.content_text ILIKE $1)
+                WHERE {archived}
+                AND {condition}
                 AND d.space_id = $3
                 AND EXISTS (
                     SELECT 1 FROM space_memberships sm
+                    JOIN spaces s ON sm.space_id = s.id
                     WHERE sm.space_id = d.space_id
                     AND sm.user_id = $2
+                    AND s.is_deleted = false
                 )
-                "#.to_string();
+                "#, archived = archived_condition(4), condition = matcher.condition);
                 sqlx::query_as::<_, (i64,)>(&count_sql)
                     .bind(&query_pattern)
-                    .bind(user_uuid)
+                    .bind(user_id)
                     .bind(sid)
+                    .bind(include_archived)
                     .fetch_one(&*self.pool)
                     .await?
                     .0
             }
             None => {
-                let count_sql = r#"
+                let count_sql = format!(r#"
                 SELECT COUNT(*) as total
                 FROM documents d
-                WHERE d.is_archived = false
-                AND (d.title ILIKE $1 OR d.content_text ILIKE $1)
+                WHERE {archived}
+                AND {condition}
                 AND EXISTS (
                     SELECT 1 FROM space_memberships sm
                     JOIN spaces s ON sm.space_id = s.id
                     WHERE sm.space_id = d.space_id
                     AND sm.user_id = $2
                     AND (s.is_public OR sm.user_id = $2)
+                    AND s.is_deleted = false
                 )
-                "#.to_string();
+                "#, archived = archived_condition(3), condition = matcher.condition);
                 sqlx::query_as::<_, (i64,)>(&count_sql)
                     .bind(&query_pattern)
-                    .bind(user_uuid)
+                    .bind(user_id)
+                    .bind(include_archived)
                     .fetch_one(&*self.pool)
                     .await?
                     .0
@@ -99,89 +434,74 @@ impl SearchRepositoryTrait for SearchRepository {
         };
 
         // Search with ranking
-        // Using ILIKE for simple pattern matching (PostgreSQL full-text search with tsvector can be added later)
+        // Plain queries use ILIKE pattern matching; operator queries rank with ts_rank
         let results: Vec<SearchResultRow> = match space_id {
             Some(sid) => {
-                let search_sql = r#"
+                let search_sql = format!(r#"
                 SELECT
                     d.id as document_id,
                     d.space_id,
                     s.name as space_name,
                     d.title,
                     d.content as content,
-                    (
-                        CASE
-                            WHEN d.title ILIKE $1 THEN 2.0
-                            ELSE 1.0
-                        END +
-                        CASE
-                            WHEN d.title ILIKE $1 || ' %' THEN 0.5
-                            ELSE 0.0
-                        END
-                    ) as score
+                    {score} as score,
+                    d.is_archived
                 FROM documents d
                 JOIN spaces s ON d.space_id = s.id
-                WHERE d.is_archived = false
-                AND (d.title ILIKE $1 OR d.content_text ILIKE $1)
-                AND d.space_id = $4
+                WHERE {archived}
+                AND {condition}
+                AND d.space_id = $5
                 AND EXISTS (
                     SELECT 1 FROM space_memberships sm
+                    JOIN spaces s ON sm.space_id = s.id
                     WHERE sm.space_id = d.space_id
                     AND sm.user_id = $2
+                    AND s.is_deleted = false
                 )
-                ORDER BY
-                    CASE WHEN d.title ILIKE $1 THEN 0 ELSE 1 END,
-                    d.updated_at DESC
+                ORDER BY {order}
                 LIMIT $3 OFFSET $4
-                "#.to_string();
+                "#, archived = archived_condition(6), condition = matcher.condition, score = matcher.score, order = matcher.order);
                 sqlx::query_as(&search_sql)
                     .bind(&query_pattern)
-                    .bind(user_uuid)
+                    .bind(user_id)
                     .bind(limit)
                     .bind(offset)
                     .bind(sid)
+                    .bind(include_archived)
                     .fetch_all(&*self.pool)
                     .await?
             }
             None => {
-                let search_sql = r#"
+                let search_sql = format!(r#"
                 SELECT
                     d.id as document_id,
                     d.space_id,
                     s.name as space_name,
                     d.title,
                     d.content as content,
-                    (
-                        CASE
-                            WHEN d.title ILIKE $1 THEN 2.0
-                            ELSE 1.0
-                        END +
-                        CASE
-                            WHEN d.title ILIKE $1 || ' %' THEN 0.5
-                            ELSE 0.0
-                        END
-                    ) as score
+                    {score} as score,
+                    d.is_archived
                 FROM documents d
                 JOIN spaces s ON d.space_id = s.id
-                WHERE d.is_archived = false
-                AND (d.title ILIKE $1 OR d.content_text ILIKE $1)
+                WHERE {archived}
+                AND {condition}
                 AND EXISTS (
                     SELECT 1 FROM space_memberships sm
                     JOIN spaces s ON sm.space_id = s.id
                     WHERE sm.space_id = d.space_id
                     AND sm.user_id = $2
                     AND (s.is_public OR sm.user_id = $2)
+                    AND s.is_deleted = false
                 )
-                ORDER BY
-                    CASE WHEN d.title ILIKE $1 THEN 0 ELSE 1 END,
-                    d.updated_at DESC
+                ORDER BY {order}
                 LIMIT $3 OFFSET $4
-                "#.to_string();
+                "#, archived = archived_condition(5), condition = matcher.condition, score = matcher.score, order = matcher.order);
                 sqlx::query_as(&search_sql)
                     .bind(&query_pattern)
-                    .bind(user_uuid)
+                    .bind(user_id)
                     .bind(limit)
                     .bind(offset)
+                    .bind(include_archived)
                     .fetch_all(&*self.pool)
                     .await?
             }
@@ -191,7 +511,7 @@ impl SearchRepositoryTrait for SearchRepository {
         let results_with_snippets: Vec<SearchResultRow> = results.into_iter()
             .map(|mut row| {
                 // Extract a snippet around the match
-                let snippet = generate_snippet(&row.content, query);
+                let snippet = generate_snippet(&row.content, highlight);
                 row.content = serde_json::Value::String(snippet.clone());
                 row
             })
@@ -199,9 +519,180 @@ impl SearchRepositoryTrait for SearchRepository {
 
         Ok((results_with_snippets, total))
     }
+
+    async fn recent(
+        &self,
+        user_id: Uuid,
+        space_id: Option<&str>,
+        limit: i32,
+        offset: i32,
+    ) -> Result<(Vec<SearchResultRow>, i64), sqlx::Error> {
+        let space_uuid: Option<Uuid> = match space_id {
+            Some(sid) => Some(sid.parse().map_err(|_| sqlx::Error::Decode("Invalid space ID format".into()))?),
+            None => None,
+        };
+
+        let total: (i64,) = sqlx::query_as(r#"
+            SELECT COUNT(*) as total
+            FROM documents d
+            WHERE d.is_archived = false
+            AND ($2::uuid IS NULL OR d.space_id = $2)
+            AND EXISTS (
+                SELECT 1 FROM space_memberships sm
+                JOIN spaces s ON sm.space_id = s.id
+                WHERE sm.space_id = d.space_id
+                AND sm.user_id = $1
+                AND s.is_deleted = false
+            )
+            "#)
+            .bind(user_id)
+            .bind(space_uuid)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        // No query to rank by, so every row scores 0 and the snippet is the
+        // start of the document text
+        let results: Vec<SearchResultRow> = sqlx::query_as(r#"
+            SELECT
+                d.id as document_id,
+                d.space_id,
+                s.name as space_name,
+                d.title,
+                to_jsonb(LEFT(COALESCE(d.content_text, ''), 150)) as content,
+                0.0::float8 as score,
+                d.is_archived
+            FROM documents d
+            JOIN spaces s ON d.space_id = s.id
+            WHERE d.is_archived = false
+            AND ($2::uuid IS NULL OR d.space_id = $2)
+            AND EXISTS (
+                SELECT 1 FROM space_memberships sm
+                JOIN spaces s ON sm.space_id = s.id
+                WHERE sm.space_id = d.space_id
+                AND sm.user_id = $1
+                AND s.is_deleted = false
+            )
+            ORDER BY d.updated_at DESC
+            LIMIT $3 OFFSET $4
+            "#)
+            .bind(user_id)
+            .bind(space_uuid)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&*self.pool)
+            .await?;
+
+        Ok((results, total.0))
+    }
+
+    async fn suggest(
+        &self,
+        user_id: Uuid,
+        prefix: &str,
+        limit: Option<i32>,
+    ) -> Result<Vec<SuggestionRow>, sqlx::Error> {
+        let Some((prefix, limit)) = normalize_suggest_params(prefix, limit) else {
+            return Ok(Vec::new());
+        };
+
+        // Prefix matches rank first; trigram similarity catches near-misses
+        let suggest_sql = r#"
+        SELECT
+            d.id as document_id,
+            d.title,
+            s.name as space_name
+        FROM documents d
+        JOIN spaces s ON d.space_id = s.id
+        WHERE d.is_archived = false
+        AND (d.title ILIKE $1 ESCAPE '\' OR word_similarity($2, d.title) > 0.3)
+        AND EXISTS (
+            SELECT 1 FROM space_memberships sm
+            JOIN spaces s ON sm.space_id = s.id
+            WHERE sm.space_id = d.space_id
+            AND sm.user_id = $3
+            AND s.is_deleted = false
+        )
+        ORDER BY
+            CASE WHEN d.title ILIKE $1 ESCAPE '\' THEN 0 ELSE 1 END,
+            word_similarity($2, d.title) DESC,
+            d.updated_at DESC
+        LIMIT $4
+        "#;
+
+        sqlx::query_as(suggest_sql)
+            .bind(format!("{}%", escape_like(prefix)))
+            .bind(prefix)
+            .bind(user_id)
+            .bind(limit)
+            .fetch_all(&*self.pool)
+            .await
+    }
+}
+
+/// Most match windows joined into one snippet
+const SNIPPET_MAX_WINDOWS: usize = 3;
+
+/// Bytes of context kept on each side of a match
+const SNIPPET_CONTEXT: usize = 50;
+
+/// Cap on the snippet text taken from the document, before ellipses and
+/// highlight markers are added
+const SNIPPET_MAX_LEN: usize = 300;
+
+// Largest char boundary at or before `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+// Smallest char boundary at or after `index`
+fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+// Byte ranges of context around distinct matches, merged where they overlap
+// or touch and limited to `SNIPPET_MAX_WINDOWS` and `SNIPPET_MAX_LEN`
+fn snippet_windows(text: &str, matcher: &Regex) -> Vec<(usize, usize)> {
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    let mut length = 0;
+
+    for m in matcher.find_iter(text).filter(|m| !m.as_str().is_empty()) {
+        let start = floor_char_boundary(text, m.start().saturating_sub(SNIPPET_CONTEXT));
+        let end = ceil_char_boundary(text, m.end() + SNIPPET_CONTEXT);
+
+        if let Some(last) = windows.last_mut() {
+            if start <= last.1 {
+                let added = end.saturating_sub(last.1);
+                if length + added > SNIPPET_MAX_LEN {
+                    break;
+                }
+                length += added;
+                last.1 = last.1.max(end);
+                continue;
+            }
+            if windows.len() == SNIPPET_MAX_WINDOWS || length + (end - start) > SNIPPET_MAX_LEN {
+                break;
+            }
+        }
+
+        length += end - start;
+        windows.push((start, end));
+    }
+
+    windows
 }
 
 // Helper function to generate a search result snippet
+//
+// Shows up to `SNIPPET_MAX_WINDOWS` windows around separate matches, joined
+// by an ellipsis, with each match highlighted in bold
 fn generate_snippet(content: &serde_json::Value, query: &str) -> String {
     // Extract text content from JSONB
     let text = content.as_str()
@@ -213,40 +704,25 @@ fn generate_snippet(content: &serde_json::Value, query: &str) -> String {
         return String::new();
     }
 
-    // Simple case-insensitive find
-    let lower_text = text.to_lowercase();
-    let query_lower = query.to_lowercase();
-
-    if let Some(pos) = lower_text.find(&query_lower) {
-        let start = pos.saturating_sub(50);
-        let end = (pos + query.len() + 100).min(text.len());
-
-        // Find safe UTF-8 boundaries
-        let safe_start = text[..start]
-            .char_indices().next_back()
-            .map(|(i, c)| i + c.len_utf8())
-            .unwrap_or(0);
-
-        let safe_end = text[end..]
-            .char_indices()
-            .next()
-            .map(|(i, _)| end + i)
-            .unwrap_or_else(|| text.len());
-
-        let mut snippet = if safe_start > 0 { "...".to_string() } else { String::new() };
-        snippet.push_str(&text[safe_start..safe_end]);
-        if safe_end < text.len() { snippet.push_str("..."); }
-
-        // Highlight the match using case-insensitive regex
-        let escaped_query = regex::escape(query);
-        if let Ok(regex) = Regex::new(&format!("(?i){}", escaped_query)) {
-            let highlighted = regex.replace_all(&snippet, |caps: &Captures| {
+    // Case-insensitive regex on the original text, so match offsets stay
+    // valid even where lowercasing would change byte lengths
+    let matcher = Regex::new(&format!("(?i){}", regex::escape(query))).ok();
+    let windows = matcher.as_ref().map(|m| snippet_windows(&text, m)).unwrap_or_default();
+
+    if let Some(matcher) = matcher.as_ref().filter(|_| !windows.is_empty()) {
+        let mut snippet = if windows[0].0 > 0 { "...".to_string() } else { String::new() };
+        for (i, &(start, end)) in windows.iter().enumerate() {
+            if i > 0 {
+                snippet.push_str(" ... ");
+            }
+            snippet.push_str(&matcher.replace_all(&text[start..end], |caps: &Captures| {
                 format!("**{}**", &caps[0])
-            });
-            highlighted.into_owned()
-        } else {
-            snippet
+            }));
         }
+        if windows.last().is_some_and(|&(_, end)| end < text.len()) {
+            snippet.push_str("...");
+        }
+        snippet
     } else {
         // Return first 150 chars if no match found
         let truncated = if text.len() > 150 {
@@ -263,3 +739,62 @@ fn generate_snippet(content: &serde_json::Value, query: &str) -> String {
         truncated
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_far_apart_matches_produce_two_windows() {
+        let filler = "lorem ipsum ".repeat(20);
+        let text = format!("Rust starts here. {}And Rust ends here.", filler);
+
+        let snippet = generate_snippet(&json!(text), "rust");
+
+        assert_eq!(snippet.matches("**Rust**").count(), 2);
+        assert_eq!(snippet.matches(" ... ").count(), 1);
+        assert!(snippet.starts_with("**Rust** starts"));
+        assert!(snippet.ends_with("ends here."));
+        assert!(snippet.len() < text.len());
+    }
+
+    #[test]
+    fn test_overlapping_matches_merge_into_one_window() {
+        let text = format!("{}rust and more rust{}", "a".repeat(100), "b".repeat(100));
+
+        let snippet = generate_snippet(&json!(text), "rust");
+
+        assert_eq!(snippet.matches("**rust**").count(), 2);
+        assert!(!snippet.contains(" ... "));
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+    }
+
+    #[test]
+    fn test_windows_capped_in_count_and_length() {
+        let text = format!("needle {}", "x".repeat(120) + " needle ").repeat(10);
+
+        let windows = snippet_windows(&text, &Regex::new("(?i)needle").unwrap());
+
+        assert_eq!(windows.len(), SNIPPET_MAX_WINDOWS);
+        assert!(windows.iter().map(|(start, end)| end - start).sum::<usize>() <= SNIPPET_MAX_LEN);
+        assert!(windows.windows(2).all(|pair| pair[0].1 < pair[1].0));
+    }
+
+    #[test]
+    fn test_windows_respect_char_boundaries() {
+        let text = format!("{}match{}", "€".repeat(40), "€".repeat(40));
+
+        let snippet = generate_snippet(&json!(text), "MATCH");
+
+        assert!(snippet.contains("**match**"));
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+    }
+
+    #[test]
+    fn test_no_match_falls_back_to_leading_text() {
+        let snippet = generate_snippet(&json!("nothing relevant"), "rust");
+
+        assert_eq!(snippet, "nothing relevant...");
+    }
+}
@@ -8,7 +8,9 @@ use uuid::Uuid;
 
 use search_service::indexer::DocumentContent;
 use search_service::models::*;
-use search_service::repository::SearchResultRow;
+use search_service::repository::{
+    normalize_suggest_params, SearchResultRow, SUGGEST_MAX_LIMIT, SUGGEST_MIN_PREFIX_LEN,
+};
 
 // ========================================
 // Model Tests
@@ -207,6 +209,27 @@ fn test_search_repository_new_exists() {
     let _ = verify_constructor_exists;
 }
 
+#[test]
+fn test_suggest_params_require_min_prefix_length() {
+    assert_eq!(SUGGEST_MIN_PREFIX_LEN, 2);
+    assert!(normalize_suggest_params("", None).is_none());
+    assert!(normalize_suggest_params("a", None).is_none());
+    // Surrounding whitespace does not count towards the minimum
+    assert!(normalize_suggest_params("  a  ", None).is_none());
+    // Length is measured in characters, not bytes
+    assert!(normalize_suggest_params("é", None).is_none());
+    assert_eq!(normalize_suggest_params(" ab ", None), Some(("ab", SUGGEST_MAX_LIMIT)));
+}
+
+#[test]
+fn test_suggest_params_clamp_limit() {
+    assert_eq!(normalize_suggest_params("doc", Some(5)), Some(("doc", 5)));
+    assert_eq!(normalize_suggest_params("doc", Some(50)), Some(("doc", SUGGEST_MAX_LIMIT)));
+    assert_eq!(normalize_suggest_params("doc", Some(0)), Some(("doc", 1)));
+    assert_eq!(normalize_suggest_params("doc", Some(-3)), Some(("doc", 1)));
+    assert_eq!(normalize_suggest_params("doc", None), Some(("doc", 10)));
+}
+
 // ========================================
 // Indexer Tests (unit tests that don't require DB)
 // ========================================
@@ -318,6 +341,7 @@ fn test_api_error_response_structure() {
     let error_response = ApiErrorResponse {
         error: "ERR_CODE".to_string(),
         message: "Error message here".to_string(),
+        details: None,
     };
     assert_eq!(error_response.error, "ERR_CODE");
     assert_eq!(error_response.message, "Error message here");
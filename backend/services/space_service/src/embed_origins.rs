@@ -0,0 +1,279 @@
+//! Per-space CORS origins for embedded widgets
+//!
+//! Public spaces can be embedded on third-party sites. Each space lists the
+//! origins allowed to call its public, read-only endpoints; those origins are
+//! not added to the global allowlist, so they cannot reach any other route.
+//!
+//! CORS origin checks run synchronously, so the origins are held in memory
+//! and refreshed from the database periodically and whenever a space owner
+//! changes them.
+
+use actix_web::dev::RequestHead;
+use actix_web::http::{header, Method};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+use crate::repository::SpaceRepository;
+
+/// Default seconds between reloads of the embed origins from the database
+pub const DEFAULT_REFRESH_SECS: u64 = 60;
+
+/// Embed origins of public spaces, shared between workers
+#[derive(Clone, Default)]
+pub struct SpaceEmbedOrigins {
+    origins: Arc<RwLock<HashMap<Uuid, HashSet<String>>>>,
+}
+
+impl SpaceEmbedOrigins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the origins of every space with those of public spaces in the database
+    pub async fn reload(&self, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut origins: HashMap<Uuid, HashSet<String>> = HashMap::new();
+        for (space_id, origin) in SpaceRepository::list_public_embed_origins(pool).await? {
+            origins.entry(space_id).or_default().insert(origin);
+        }
+
+        *self.origins.write().unwrap_or_else(|e| e.into_inner()) = origins;
+        Ok(())
+    }
+
+    /// Set the origins of one space; an empty list removes the space
+    pub fn set(&self, space_id: Uuid, origins: &[String]) {
+        let mut map = self.origins.write().unwrap_or_else(|e| e.into_inner());
+        if origins.is_empty() {
+            map.remove(&space_id);
+        } else {
+            map.insert(space_id, origins.iter().cloned().collect());
+        }
+    }
+
+    pub fn is_allowed(&self, space_id: Uuid, origin: &str) -> bool {
+        let Some(origin) = normalize_origin(origin) else {
+            return false;
+        };
+
+        self.origins
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&space_id)
+            .is_some_and(|origins| origins.contains(&origin))
+    }
+
+    /// Whether `origin` may call the request's endpoint because it is a public
+    /// endpoint of a space that lists the origin. Intended for
+    /// `Cors::allowed_origin_fn`, after the global allowlist check.
+    pub fn allows(&self, origin: &str, req: &RequestHead) -> bool {
+        if !is_read_only(req) {
+            return false;
+        }
+
+        match public_space_id(req.uri.path()) {
+            Some(space_id) => self.is_allowed(space_id, origin),
+            None => false,
+        }
+    }
+}
+
+/// Normalize an origin to lowercase `scheme://host[:port]`
+///
+/// Returns `None` for anything that is not a plain http(s) origin, including
+/// paths, wildcards and credentials.
+pub fn normalize_origin(origin: &str) -> Option<String> {
+    let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+    let host = origin.strip_prefix("https://").or_else(|| origin.strip_prefix("http://"))?;
+
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+
+    valid.then_some(origin)
+}
+
+// Only reads are exposed to embeds; preflights count when they ask for one
+fn is_read_only(req: &RequestHead) -> bool {
+    let method = if req.method == Method::OPTIONS {
+        match req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|m| m.to_str().ok())
+            .and_then(|m| Method::from_bytes(m.as_bytes()).ok())
+        {
+            Some(method) => method,
+            None => return false,
+        }
+    } else {
+        req.method.clone()
+    };
+
+    method == Method::GET || method == Method::HEAD
+}
+
+/// Space id of a public space endpoint, whatever prefix the API is mounted under
+///
+/// Public endpoints are `/spaces/{id}` and `/space-docs/{id}/documents`.
+fn public_space_id(path: &str) -> Option<Uuid> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    match segments.as_slice() {
+        [.., "spaces", id] => Uuid::parse_str(id).ok(),
+        [.., "space-docs", id, "documents"] => Uuid::parse_str(id).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_cors::Cors;
+    use actix_web::{test, web, App, HttpResponse};
+
+    const GLOBAL_ORIGIN: &str = "https://app.miniwiki.example";
+    const EMBED_ORIGIN: &str = "https://blog.example.com";
+
+    async fn ok() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    // Mirrors the CORS setup in main.rs: global allowlist first, then embeds
+    fn cors(embed_origins: SpaceEmbedOrigins) -> Cors {
+        Cors::default()
+            .allowed_origin_fn(move |origin, req_head| {
+                let origin = origin.to_str().unwrap_or("");
+                origin == GLOBAL_ORIGIN || embed_origins.allows(origin, req_head)
+            })
+            .allowed_methods(vec!["GET", "POST", "PATCH"])
+    }
+
+    fn allowed_origin<B>(resp: &actix_web::dev::ServiceResponse<B>) -> Option<String> {
+        resp.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[actix_web::test]
+    async fn test_embed_origin_allowed_for_its_public_space() {
+        let embedded_space = Uuid::new_v4();
+        let other_space = Uuid::new_v4();
+        let embed_origins = SpaceEmbedOrigins::new();
+        embed_origins.set(embedded_space, &[EMBED_ORIGIN.to_string()]);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cors(embed_origins))
+                .route("/api/v1/spaces/{id}", web::get().to(ok))
+                .route("/api/v1/space-docs/{id}/documents", web::get().to(ok)),
+        )
+        .await;
+
+        for uri in [
+            format!("/api/v1/spaces/{}", embedded_space),
+            format!("/api/v1/space-docs/{}/documents", embedded_space),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&uri)
+                .insert_header((header::ORIGIN, EMBED_ORIGIN))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 200);
+            assert_eq!(allowed_origin(&resp).as_deref(), Some(EMBED_ORIGIN));
+        }
+
+        // Same origin, different space
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/spaces/{}", other_space))
+            .insert_header((header::ORIGIN, EMBED_ORIGIN))
+            .to_request();
+        let resp = test::try_call_service(&app, req).await;
+        assert!(resp.map_or(true, |resp| allowed_origin(&resp).is_none()));
+    }
+
+    #[actix_web::test]
+    async fn test_global_allowlist_governs_authenticated_routes() {
+        let space_id = Uuid::new_v4();
+        let embed_origins = SpaceEmbedOrigins::new();
+        embed_origins.set(space_id, &[EMBED_ORIGIN.to_string()]);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cors(embed_origins))
+                .route("/api/v1/spaces/{id}", web::patch().to(ok))
+                .route("/api/v1/spaces/{id}/members", web::get().to(ok)),
+        )
+        .await;
+
+        // The embed origin cannot write to its space or reach other routes
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/v1/spaces/{}", space_id))
+            .insert_header((header::ORIGIN, EMBED_ORIGIN))
+            .to_request();
+        let resp = test::try_call_service(&app, req).await;
+        assert!(resp.map_or(true, |resp| allowed_origin(&resp).is_none()));
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/spaces/{}/members", space_id))
+            .insert_header((header::ORIGIN, EMBED_ORIGIN))
+            .to_request();
+        let resp = test::try_call_service(&app, req).await;
+        assert!(resp.map_or(true, |resp| allowed_origin(&resp).is_none()));
+
+        // The global allowlist is unaffected
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/spaces/{}/members", space_id))
+            .insert_header((header::ORIGIN, GLOBAL_ORIGIN))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(allowed_origin(&resp).as_deref(), Some(GLOBAL_ORIGIN));
+    }
+
+    #[actix_web::test]
+    async fn test_preflight_for_read_is_allowed() {
+        let space_id = Uuid::new_v4();
+        let embed_origins = SpaceEmbedOrigins::new();
+        embed_origins.set(space_id, &[EMBED_ORIGIN.to_string()]);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(cors(embed_origins))
+                .route("/api/v1/spaces/{id}", web::get().to(ok)),
+        )
+        .await;
+
+        let preflight = |method: &str| {
+            test::TestRequest::default()
+                .method(Method::OPTIONS)
+                .uri(&format!("/api/v1/spaces/{}", space_id))
+                .insert_header((header::ORIGIN, EMBED_ORIGIN))
+                .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, preflight("GET")).await;
+        assert_eq!(allowed_origin(&resp).as_deref(), Some(EMBED_ORIGIN));
+
+        let resp = test::try_call_service(&app, preflight("PATCH")).await;
+        assert!(resp.map_or(true, |resp| allowed_origin(&resp).is_none()));
+    }
+
+    #[actix_web::test]
+    async fn test_normalize_origin() {
+        assert_eq!(
+            normalize_origin("HTTPS://Blog.Example.com/").as_deref(),
+            Some("https://blog.example.com")
+        );
+        assert_eq!(
+            normalize_origin("http://localhost:8080").as_deref(),
+            Some("http://localhost:8080")
+        );
+        assert!(normalize_origin("https://blog.example.com/widget").is_none());
+        assert!(normalize_origin("https://*.example.com").is_none());
+        assert!(normalize_origin("https://user@example.com").is_none());
+        assert!(normalize_origin("ftp://example.com").is_none());
+        assert!(normalize_origin("https://").is_none());
+    }
+}
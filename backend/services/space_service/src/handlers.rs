@@ -1,38 +1,23 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use uuid::Uuid;
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use auth_service::identity::extract_token_user_id;
+use validator::Validate;
+use crate::embed_origins::{normalize_origin, SpaceEmbedOrigins};
 use crate::models::*;
-use crate::repository::SpaceRepository;
+use crate::repository::{AddMemberError, SpaceMemberLimit, SpaceRepository};
+use shared_errors::AppError;
+use shared_models::extensions::normalize_extension;
 
-const TEST_JWT_SECRET: &str = "test-secret-key-for-testing-only-do-not-use-in-production";
-
-fn extract_user_id_from_request(req: &HttpRequest) -> Option<Uuid> {
-    let auth_header = req.headers().get("authorization")?;
-    let token_str = auth_header.to_str().ok()?;
-    
-    if !token_str.starts_with("Bearer ") {
-        return None;
-    }
-    
-    let token = &token_str[7..];
-    let decoding_key = DecodingKey::from_secret(TEST_JWT_SECRET.as_bytes());
-    let validation = Validation::default();
-    
-    match decode::<serde_json::Value>(token, &decoding_key, &validation) {
-        Ok(token_data) => {
-            token_data.claims.get("sub")
-                .and_then(|v| v.as_str())
-                .and_then(|s| Uuid::parse_str(s).ok())
-        }
-        Err(_) => None,
-    }
+async fn extract_user_id_from_request(req: &HttpRequest) -> Option<Uuid> {
+    // Bearer token (or authentication middleware) only; X-User-Id is not trusted here
+    extract_token_user_id(req).await.ok()
 }
 
 pub async fn list_spaces(
     pool: web::Data<sqlx::PgPool>,
     req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -51,7 +36,7 @@ pub async fn create_space(
     req: HttpRequest,
     request: web::Json<CreateSpaceRequest>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -86,7 +71,7 @@ pub async fn get_space(
     space_id: web::Path<Uuid>,
 ) -> Result<HttpResponse> {
     eprintln!("DEBUG get_space: handler called");
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => {
             eprintln!("DEBUG get_space: user_id extracted = {}", id);
             id
@@ -134,11 +119,12 @@ pub async fn get_space(
 
 pub async fn update_space(
     pool: web::Data<sqlx::PgPool>,
+    embed_origins: Option<web::Data<SpaceEmbedOrigins>>,
     req: HttpRequest,
     space_id: web::Path<Uuid>,
     request: web::Json<UpdateSpaceRequest>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -168,16 +154,214 @@ pub async fn update_space(
             eprintln!("Repository update error: {:?}", e);
             actix_web::error::ErrorInternalServerError(e)
         })?;
+
+    // Embed origins only apply while the space is public
+    if let (Some(embed_origins), Some(_)) = (embed_origins, request.is_public) {
+        let origins = if space.is_public {
+            SpaceRepository::list_embed_origins(&pool, space_id)
+                .await
+                .map_err(|e| {
+                    eprintln!("list_embed_origins error: {:?}", e);
+                    actix_web::error::ErrorInternalServerError(e)
+                })?
+        } else {
+            Vec::new()
+        };
+        embed_origins.set(space_id, &origins);
+    }
     
     Ok(HttpResponse::Ok().json(space))
 }
 
+pub async fn get_embed_origins(
+    pool: web::Data<sqlx::PgPool>,
+    req: HttpRequest,
+    space_id: web::Path<Uuid>,
+) -> Result<HttpResponse> {
+    let user_id = match extract_user_id_from_request(&req).await {
+        Some(id) => id,
+        None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
+    };
+
+    let space_id = *space_id;
+    let space = SpaceRepository::find_by_id(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("find_by_id error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Space not found"))?;
+
+    if space.owner_id != user_id {
+        return Err(actix_web::error::ErrorForbidden("Only owner can view embed origins"));
+    }
+
+    let origins = SpaceRepository::list_embed_origins(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("list_embed_origins error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    Ok(HttpResponse::Ok().json(EmbedOriginsResponse { space_id, origins }))
+}
+
+/// Replace the origins allowed to embed the space's public endpoints.
+/// They take effect only while the space is public.
+pub async fn update_embed_origins(
+    pool: web::Data<sqlx::PgPool>,
+    embed_origins: web::Data<SpaceEmbedOrigins>,
+    req: HttpRequest,
+    space_id: web::Path<Uuid>,
+    request: web::Json<UpdateEmbedOriginsRequest>,
+) -> Result<HttpResponse> {
+    let user_id = match extract_user_id_from_request(&req).await {
+        Some(id) => id,
+        None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
+    };
+
+    request.validate().map_err(AppError::from)?;
+
+    let mut origins = Vec::with_capacity(request.origins.len());
+    for origin in &request.origins {
+        match normalize_origin(origin) {
+            Some(origin) if !origins.contains(&origin) => origins.push(origin),
+            Some(_) => {}
+            None => {
+                return Err(actix_web::error::ErrorBadRequest(format!(
+                    "Invalid origin '{}', expected scheme://host[:port]",
+                    origin
+                )))
+            }
+        }
+    }
+    origins.sort();
+
+    let space_id = *space_id;
+    let space = SpaceRepository::find_by_id(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("find_by_id error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Space not found"))?;
+
+    if space.owner_id != user_id {
+        return Err(actix_web::error::ErrorForbidden("Only owner can update embed origins"));
+    }
+
+    SpaceRepository::set_embed_origins(&pool, space_id, &origins)
+        .await
+        .map_err(|e| {
+            eprintln!("set_embed_origins error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    if space.is_public {
+        embed_origins.set(space_id, &origins);
+    }
+
+    Ok(HttpResponse::Ok().json(EmbedOriginsResponse { space_id, origins }))
+}
+
+pub async fn get_allowed_extensions(
+    pool: web::Data<sqlx::PgPool>,
+    req: HttpRequest,
+    space_id: web::Path<Uuid>,
+) -> Result<HttpResponse> {
+    let user_id = match extract_user_id_from_request(&req).await {
+        Some(id) => id,
+        None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
+    };
+
+    let space_id = *space_id;
+    let space = SpaceRepository::find_by_id(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("find_by_id error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Space not found"))?;
+
+    if space.owner_id != user_id {
+        return Err(actix_web::error::ErrorForbidden("Only owner can view allowed extensions"));
+    }
+
+    let extensions = SpaceRepository::get_allowed_extensions(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("get_allowed_extensions error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    Ok(HttpResponse::Ok().json(AllowedExtensionsResponse { space_id, extensions }))
+}
+
+/// Restrict uploads in the space to a list of file extensions, or lift the
+/// restriction with `null`
+pub async fn update_allowed_extensions(
+    pool: web::Data<sqlx::PgPool>,
+    req: HttpRequest,
+    space_id: web::Path<Uuid>,
+    request: web::Json<UpdateAllowedExtensionsRequest>,
+) -> Result<HttpResponse> {
+    let user_id = match extract_user_id_from_request(&req).await {
+        Some(id) => id,
+        None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
+    };
+
+    request.validate().map_err(AppError::from)?;
+
+    let extensions = match &request.extensions {
+        Some(requested) => {
+            let mut extensions = Vec::with_capacity(requested.len());
+            for extension in requested {
+                match normalize_extension(extension) {
+                    Some(extension) if !extensions.contains(&extension) => extensions.push(extension),
+                    Some(_) => {}
+                    None => {
+                        return Err(actix_web::error::ErrorBadRequest(format!(
+                            "Invalid extension '{}', expected letters and digits only",
+                            extension
+                        )))
+                    }
+                }
+            }
+            extensions.sort();
+            Some(extensions)
+        }
+        None => None,
+    };
+
+    let space_id = *space_id;
+    let space = SpaceRepository::find_by_id(&pool, space_id)
+        .await
+        .map_err(|e| {
+            eprintln!("find_by_id error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Space not found"))?;
+
+    if space.owner_id != user_id {
+        return Err(actix_web::error::ErrorForbidden("Only owner can update allowed extensions"));
+    }
+
+    SpaceRepository::set_allowed_extensions(&pool, space_id, extensions.as_deref())
+        .await
+        .map_err(|e| {
+            eprintln!("set_allowed_extensions error: {:?}", e);
+            actix_web::error::ErrorInternalServerError(e)
+        })?;
+
+    Ok(HttpResponse::Ok().json(AllowedExtensionsResponse { space_id, extensions }))
+}
+
 pub async fn delete_space(
     pool: web::Data<sqlx::PgPool>,
     req: HttpRequest,
     space_id: web::Path<Uuid>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -209,8 +393,9 @@ pub async fn list_space_members(
     pool: web::Data<sqlx::PgPool>,
     req: HttpRequest,
     space_id: web::Path<Uuid>,
+    query: web::Query<ListMembersQuery>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -234,22 +419,35 @@ pub async fn list_space_members(
         return Err(actix_web::error::ErrorForbidden("Access denied"));
     }
     
-    let members = SpaceRepository::list_members(&pool, space_id).await
+    let limit = query.limit.unwrap_or(50).clamp(1, 100);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    // One row past the page tells whether another page follows
+    let mut members = SpaceRepository::list_members(&pool, space_id, limit + 1, offset).await
         .map_err(|e| {
             eprintln!("list_members error: {:?}", e);
             actix_web::error::ErrorInternalServerError(e)
         })?;
-    
-    Ok(HttpResponse::Ok().json(members))
+    let has_more = members.len() as i64 > limit;
+    members.truncate(limit as usize);
+
+    Ok(HttpResponse::Ok().json(SpaceMemberListResponse {
+        members,
+        limit,
+        offset,
+        has_more,
+        next_offset: has_more.then_some(offset + limit),
+    }))
 }
 
 pub async fn add_space_member(
     pool: web::Data<sqlx::PgPool>,
+    member_limit: Option<web::Data<SpaceMemberLimit>>,
     req: HttpRequest,
     space_id: web::Path<Uuid>,
     request: web::Json<AddMemberRequest>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -285,10 +483,14 @@ pub async fn add_space_member(
         &request.user_id,
         &request.role,
         &user_id.to_string(),
+        member_limit.map_or_else(SpaceMemberLimit::default, |limit| **limit).0,
     ).await
-        .map_err(|e| {
-            eprintln!("add_member error: {:?}", e);
-            actix_web::error::ErrorInternalServerError(e)
+        .map_err(|e| match e {
+            AddMemberError::LimitReached(_) => actix_web::error::ErrorConflict(e.to_string()),
+            AddMemberError::Database(e) => {
+                eprintln!("add_member error: {:?}", e);
+                actix_web::error::ErrorInternalServerError(e)
+            },
         })?;
     
     Ok(HttpResponse::Created().json(membership))
@@ -302,7 +504,7 @@ pub async fn update_member_role(
 ) -> Result<HttpResponse> {
     let (space_id, member_id) = path.into_inner();
     
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -323,6 +525,7 @@ pub async fn update_member_role(
         &pool,
         member_id,
         &request.role,
+        user_id,
     ).await
         .map_err(|e| {
             eprintln!("update_member_role error: {:?}", e);
@@ -338,7 +541,7 @@ pub async fn remove_member(
     space_id: web::Path<Uuid>,
     member_id: web::Path<Uuid>,
 ) -> Result<HttpResponse> {
-    let user_id = match extract_user_id_from_request(&req) {
+    let user_id = match extract_user_id_from_request(&req).await {
         Some(id) => id,
         None => return Err(actix_web::error::ErrorUnauthorized("Missing or invalid token")),
     };
@@ -371,7 +574,7 @@ pub async fn remove_member(
         return Err(actix_web::error::ErrorBadRequest("Cannot remove owner"));
     }
     
-    SpaceRepository::remove_member(&pool, member_id)
+    SpaceRepository::remove_member(&pool, member_id, user_id)
         .await
         .map_err(|e| {
             eprintln!("remove_member error: {:?}", e);
@@ -1,4 +1,5 @@
 pub mod models;
+pub mod embed_origins;
 pub mod handlers;
 pub mod repository;
 
@@ -12,6 +13,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/{id}", web::get().to(handlers::get_space))
             .route("/{id}", web::patch().to(handlers::update_space))
             .route("/{id}", web::delete().to(handlers::delete_space))
+            .route("/{id}/embed-origins", web::get().to(handlers::get_embed_origins))
+            .route("/{id}/embed-origins", web::put().to(handlers::update_embed_origins))
+            .route("/{id}/allowed-extensions", web::get().to(handlers::get_allowed_extensions))
+            .route("/{id}/allowed-extensions", web::put().to(handlers::update_allowed_extensions))
             .route("/{id}/members", web::get().to(handlers::list_space_members))
             .route("/{id}/members", web::post().to(handlers::add_space_member))
             .route("/{id}/members/{member_id}", web::patch().to(handlers::update_member_role))
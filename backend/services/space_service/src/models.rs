@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use shared_database::SoftDeletable;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -14,6 +15,12 @@ pub struct Space {
     pub updated_at: chrono::NaiveDateTime,
 }
 
+impl SoftDeletable for Space {
+    const TABLE: &'static str = "spaces";
+    const DELETED_FLAG: &'static str = "is_deleted";
+    const DELETED_AT: &'static str = "deleted_at";
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateSpaceRequest {
     #[validate(length(min = 1, max = 200))]
@@ -46,6 +53,23 @@ pub struct SpaceMembership {
     pub invited_by: Uuid,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListMembersQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceMemberListResponse {
+    pub members: Vec<SpaceMembership>,
+    pub limit: i64,
+    pub offset: i64,
+    /// True when another page follows this one
+    pub has_more: bool,
+    /// Offset to request the next page with; null on the last page
+    pub next_offset: Option<i64>,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct AddMemberRequest {
     pub user_id: String,
@@ -59,6 +83,31 @@ pub struct UpdateMemberRequest {
     pub role: String,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateEmbedOriginsRequest {
+    #[validate(length(max = 20))]
+    pub origins: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedOriginsResponse {
+    pub space_id: Uuid,
+    pub origins: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAllowedExtensionsRequest {
+    /// Extensions accepted for uploads, e.g. `["pdf", "png"]`; `null` allows any
+    #[validate(length(max = 50))]
+    pub extensions: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AllowedExtensionsResponse {
+    pub space_id: Uuid,
+    pub extensions: Option<Vec<String>>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SpaceError {
     #[error("Space not found")]
@@ -1,7 +1,73 @@
+use shared_database::soft_delete;
 use sqlx::PgPool;
 use uuid::Uuid;
 use crate::models::{Space, SpaceMembership};
 
+/// Default maximum number of members in a space, owner included
+pub const DEFAULT_MAX_SPACE_MEMBERS: usize = 1000;
+
+/// Read the space member cap from `SPACE_MAX_MEMBERS`; 0 disables the cap
+pub fn max_space_members_from_env() -> usize {
+    std::env::var("SPACE_MAX_MEMBERS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_SPACE_MEMBERS)
+}
+
+/// Space member cap, registered once as app data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpaceMemberLimit(pub usize);
+
+impl SpaceMemberLimit {
+    pub fn from_env() -> Self {
+        Self(max_space_members_from_env())
+    }
+}
+
+impl Default for SpaceMemberLimit {
+    fn default() -> Self {
+        Self(DEFAULT_MAX_SPACE_MEMBERS)
+    }
+}
+
+/// Errors from adding a member to a space
+#[derive(Debug, thiserror::Error)]
+pub enum AddMemberError {
+    #[error("Space member limit of {0} reached")]
+    LimitReached(usize),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+// Record a membership change in `space_audit_log`, on the transaction that made it
+async fn record_audit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    space_id: Uuid,
+    actor_id: Uuid,
+    target_user_id: Uuid,
+    action: &str,
+    old_role: Option<&str>,
+    new_role: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO space_audit_log (space_id, actor_id, target_user_id, action, old_role, new_role)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        space_id,
+        actor_id,
+        target_user_id,
+        action,
+        old_role,
+        new_role
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
 pub struct SpaceRepository;
 
 impl SpaceRepository {
@@ -11,7 +77,7 @@ impl SpaceRepository {
             r#"
             SELECT id, owner_id, name, icon, description, is_public, created_at, updated_at
             FROM spaces
-            WHERE id = $1
+            WHERE id = $1 AND is_deleted = false
             "#,
             id
         )
@@ -26,7 +92,7 @@ impl SpaceRepository {
             SELECT s.id, s.owner_id, s.name, s.icon, s.description, s.is_public, s.created_at, s.updated_at
             FROM spaces s
             LEFT JOIN space_memberships sm ON s.id = sm.space_id
-            WHERE s.owner_id = $1 OR sm.user_id = $1
+            WHERE s.is_deleted = false AND (s.owner_id = $1 OR sm.user_id = $1)
             GROUP BY s.id
             ORDER BY s.updated_at DESC
             "#,
@@ -102,7 +168,7 @@ impl SpaceRepository {
                 description = COALESCE($4, description),
                 is_public = COALESCE($5, is_public),
                 updated_at = $6
-            WHERE id = $1
+            WHERE id = $1 AND is_deleted = false
             RETURNING id, owner_id, name, icon, description, is_public, created_at, updated_at
             "#,
             id,
@@ -118,16 +184,9 @@ impl SpaceRepository {
         Ok(space)
     }
 
-    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!("DELETE FROM space_memberships WHERE space_id = $1", id)
-            .execute(pool)
-            .await?;
-
-        sqlx::query!("DELETE FROM spaces WHERE id = $1", id)
-            .execute(pool)
-            .await?;
-
-        Ok(())
+    /// Soft delete the space; memberships are kept so it can be restored
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        soft_delete::soft_delete::<Space>(pool, id).await
     }
 
     pub async fn check_membership(pool: &PgPool, space_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
@@ -145,33 +204,70 @@ impl SpaceRepository {
         Ok(count > 0)
     }
 
-    pub async fn list_members(pool: &PgPool, space_id: Uuid) -> Result<Vec<SpaceMembership>, sqlx::Error> {
+    pub async fn list_members(
+        pool: &PgPool,
+        space_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SpaceMembership>, sqlx::Error> {
         sqlx::query_as!(
             SpaceMembership,
             r#"
             SELECT id, space_id, user_id, role, joined_at, invited_by
             FROM space_memberships
             WHERE space_id = $1
-            ORDER BY joined_at ASC
+            ORDER BY joined_at ASC, id ASC
+            LIMIT $2 OFFSET $3
             "#,
-            space_id
+            space_id,
+            limit,
+            offset
         )
         .fetch_all(pool)
         .await
     }
 
+    /// Add a member, keeping the space within `max_members` (0 for no cap)
+    /// and recording the addition in the audit log in the same transaction
     pub async fn add_member(
         pool: &PgPool,
         space_id: Uuid,
         user_id: &str,
         role: &str,
         invited_by: &str,
-    ) -> Result<SpaceMembership, sqlx::Error> {
+        max_members: usize,
+    ) -> Result<SpaceMembership, AddMemberError> {
         let id = Uuid::new_v4();
         let user_uuid = Uuid::parse_str(user_id).map_err(|_| sqlx::Error::Decode("Invalid user_id UUID".into()))?;
         let invited_by_uuid = Uuid::parse_str(invited_by).map_err(|_| sqlx::Error::Decode("Invalid invited_by UUID".into()))?;
         let now = chrono::Utc::now().naive_utc();
 
+        let mut tx = pool.begin().await?;
+
+        // Lock the space so concurrent adds cannot both take the last slot
+        let owner_id = sqlx::query_scalar!("SELECT owner_id FROM spaces WHERE id = $1 FOR UPDATE", space_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if max_members > 0 {
+            let others = sqlx::query_scalar!(
+                r#"
+                SELECT COUNT(*) AS "count!" FROM space_memberships
+                WHERE space_id = $1 AND user_id <> $2 AND user_id <> $3
+                "#,
+                space_id,
+                owner_id,
+                user_uuid
+            )
+            .fetch_one(&mut *tx)
+            .await? as usize;
+
+            // The owner, everyone else and the new member
+            if others + 2 > max_members {
+                return Err(AddMemberError::LimitReached(max_members));
+            }
+        }
+
         let membership = sqlx::query_as!(
             SpaceMembership,
             r#"
@@ -186,9 +282,12 @@ impl SpaceRepository {
             now,
             invited_by_uuid
         )
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        record_audit(&mut tx, space_id, invited_by_uuid, user_uuid, "member_added", None, Some(role)).await?;
+        tx.commit().await?;
+
         Ok(membership)
     }
 
@@ -206,43 +305,161 @@ impl SpaceRepository {
         .await
     }
 
+    /// Change a member's role, recording it in the audit log when the role
+    /// actually changes
     pub async fn update_member_role(
         pool: &PgPool,
         id: Uuid,
         role: &str,
+        actor_id: Uuid,
     ) -> Result<SpaceMembership, sqlx::Error> {
-        let _membership = sqlx::query_as!(
+        let mut tx = pool.begin().await?;
+
+        let current = sqlx::query_as!(
             SpaceMembership,
-            r#"SELECT id, space_id, user_id, role, joined_at, invited_by FROM space_memberships WHERE id = $1"#,
+            r#"
+            SELECT id, space_id, user_id, role, joined_at, invited_by
+            FROM space_memberships
+            WHERE id = $1
+            FOR UPDATE
+            "#,
             id
         )
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        sqlx::query!(
-            "UPDATE space_memberships SET role = $1 WHERE id = $2",
+        let updated = sqlx::query_as!(
+            SpaceMembership,
+            r#"
+            UPDATE space_memberships SET role = $1 WHERE id = $2
+            RETURNING id, space_id, user_id, role, joined_at, invited_by
+            "#,
             role,
             id
         )
-        .execute(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        let updated = sqlx::query_as!(
-            SpaceMembership,
-            r#"SELECT id, space_id, user_id, role, joined_at, invited_by FROM space_memberships WHERE id = $1"#,
+        if current.role != updated.role {
+            record_audit(
+                &mut tx,
+                updated.space_id,
+                actor_id,
+                updated.user_id,
+                "role_changed",
+                Some(&current.role),
+                Some(&updated.role),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    /// Remove a membership, recording the removal in the audit log
+    pub async fn remove_member(pool: &PgPool, id: Uuid, actor_id: Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let removed = sqlx::query!(
+            "DELETE FROM space_memberships WHERE id = $1 RETURNING space_id, user_id, role",
             id
         )
-        .fetch_one(pool)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        Ok(updated)
+        if let Some(removed) = removed {
+            record_audit(
+                &mut tx,
+                removed.space_id,
+                actor_id,
+                removed.user_id,
+                "member_removed",
+                Some(&removed.role),
+                None,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn list_embed_origins(pool: &PgPool, space_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT origin FROM space_embed_origins
+            WHERE space_id = $1
+            ORDER BY origin ASC
+            "#,
+            space_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Replace a space's embed origins with `origins`
+    pub async fn set_embed_origins(pool: &PgPool, space_id: Uuid, origins: &[String]) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!("DELETE FROM space_embed_origins WHERE space_id = $1", space_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO space_embed_origins (space_id, origin)
+            SELECT $1, UNNEST($2::varchar[])
+            ON CONFLICT DO NOTHING
+            "#,
+            space_id,
+            origins
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
     }
 
-    pub async fn remove_member(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
-        sqlx::query!("DELETE FROM space_memberships WHERE id = $1", id)
-            .execute(pool)
+    /// Upload extension allowlist; `None` when the space accepts any extension
+    pub async fn get_allowed_extensions(pool: &PgPool, space_id: Uuid) -> Result<Option<Vec<String>>, sqlx::Error> {
+        let allowed = sqlx::query_scalar!("SELECT allowed_extensions FROM spaces WHERE id = $1", space_id)
+            .fetch_one(pool)
             .await?;
 
+        Ok(allowed)
+    }
+
+    /// Replace the upload extension allowlist; `None` lifts the restriction
+    pub async fn set_allowed_extensions(
+        pool: &PgPool,
+        space_id: Uuid,
+        extensions: Option<&[String]>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE spaces SET allowed_extensions = $2 WHERE id = $1",
+            space_id,
+            extensions
+        )
+        .execute(pool)
+        .await?;
+
         Ok(())
     }
+
+    /// Embed origins of every public space, as (space id, origin) pairs
+    pub async fn list_public_embed_origins(pool: &PgPool) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT o.space_id, o.origin
+            FROM space_embed_origins o
+            INNER JOIN spaces s ON s.id = o.space_id
+            WHERE s.is_public = true
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.space_id, row.origin)).collect())
+    }
 }
@@ -3,6 +3,7 @@
 
 use crate::state_vector::{StateVector, ClientId, Clock};
 use std::cmp::Ordering;
+use thiserror::Error;
 
 /// Conflict resolution strategy
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -17,6 +18,40 @@ pub enum ConflictResolutionStrategy {
     Custom,
 }
 
+impl ConflictResolutionStrategy {
+    /// Strategy used when a sync request does not pick one
+    pub const DEFAULT: Self = Self::Merge;
+
+    /// Parse a strategy name sent by a client
+    ///
+    /// `last_write_wins` is accepted as an alias for `timestamp`. `custom`
+    /// has no resolver behind it, so clients cannot select it.
+    pub fn parse(value: &str) -> Result<Self, SyncError> {
+        match value.to_lowercase().as_str() {
+            "merge" => Ok(Self::Merge),
+            "timestamp" | "last_write_wins" => Ok(Self::Timestamp),
+            "client_id" => Ok(Self::ClientId),
+            _ => Err(SyncError::UnknownStrategy(value.to_string())),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Timestamp => "timestamp",
+            Self::ClientId => "client_id",
+            Self::Custom => "custom",
+        }
+    }
+}
+
+/// Errors from sync request handling
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SyncError {
+    #[error("Unknown conflict resolution strategy '{0}', expected one of: merge, timestamp, client_id")]
+    UnknownStrategy(String),
+}
+
 /// Result of conflict resolution
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConflictResolution {
@@ -52,6 +87,10 @@ impl ConflictResolver {
         Self { strategy }
     }
 
+    pub fn strategy(&self) -> ConflictResolutionStrategy {
+        self.strategy
+    }
+
     /// Resolve a conflict between two state vectors
     pub fn resolve_state_vector(
         &self,
@@ -113,6 +152,25 @@ impl ConflictResolver {
         }
     }
 
+    /// Decide whether an incoming update lands on top of the server state
+    ///
+    /// Only a client that had not seen everything the server has is in
+    /// conflict. `Merge` then keeps both sides through the CRDT merge; the
+    /// other strategies pick a winner, where `KeepFirst` means the server state
+    /// wins and the update is rejected.
+    pub fn resolve_update(&self, server_sv: &StateVector, client_sv: &StateVector) -> ConflictResolution {
+        if server_sv.is_ancestor_of(client_sv) {
+            return ConflictResolution::NoConflict;
+        }
+
+        match self.strategy {
+            ConflictResolutionStrategy::Merge => ConflictResolution::Merged,
+            ConflictResolutionStrategy::Timestamp => self.resolve_by_timestamp(&(), &(), server_sv, client_sv).1,
+            ConflictResolutionStrategy::ClientId => self.resolve_by_client_id(&(), &(), server_sv, client_sv).1,
+            ConflictResolutionStrategy::Custom => ConflictResolution::Unresolved,
+        }
+    }
+
     /// Resolve conflict by comparing state vector timestamps
     fn resolve_by_timestamp<T: Clone + PartialEq>(
         &self,
@@ -188,7 +246,7 @@ impl ConflictResolver {
 /// Default conflict resolver using merge strategy
 impl Default for ConflictResolver {
     fn default() -> Self {
-        Self::new(ConflictResolutionStrategy::Merge)
+        Self::new(ConflictResolutionStrategy::DEFAULT)
     }
 }
 
@@ -268,6 +326,85 @@ mod tests {
         assert_eq!(resolution, ConflictResolution::KeepSecond);
     }
 
+    // Local edit has the higher clock, remote comes from the higher client id
+    fn crafted_updates() -> (StateVector, StateVector) {
+        let mut local_sv = StateVector::new();
+        local_sv.set(5, 30);
+
+        let mut remote_sv = StateVector::new();
+        remote_sv.set(9, 20);
+
+        (local_sv, remote_sv)
+    }
+
+    #[test]
+    fn test_each_strategy_on_crafted_updates() {
+        let (local_sv, remote_sv) = crafted_updates();
+        let cases = [
+            (ConflictResolutionStrategy::Merge, "local", ConflictResolution::KeepFirst),
+            (ConflictResolutionStrategy::Timestamp, "local", ConflictResolution::KeepFirst),
+            (ConflictResolutionStrategy::ClientId, "remote", ConflictResolution::KeepSecond),
+            (ConflictResolutionStrategy::Custom, "local", ConflictResolution::Unresolved),
+        ];
+
+        for (strategy, expected_value, expected_resolution) in cases {
+            let resolver = ConflictResolver::new(strategy);
+            let (result, resolution) = resolver.resolve_document_conflict(
+                &"local",
+                &"remote",
+                &local_sv,
+                &remote_sv,
+            );
+            assert_eq!(result, expected_value, "{:?}", strategy);
+            assert_eq!(resolution, expected_resolution, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_resolve_update_per_strategy() {
+        let (server_sv, client_sv) = crafted_updates();
+        let cases = [
+            (ConflictResolutionStrategy::Merge, ConflictResolution::Merged),
+            (ConflictResolutionStrategy::Timestamp, ConflictResolution::KeepFirst),
+            (ConflictResolutionStrategy::ClientId, ConflictResolution::KeepSecond),
+        ];
+
+        for (strategy, expected) in cases {
+            let resolver = ConflictResolver::new(strategy);
+            assert_eq!(resolver.resolve_update(&server_sv, &client_sv), expected, "{:?}", strategy);
+        }
+
+        // A client that has seen the server state is never in conflict
+        let mut caught_up = server_sv.clone();
+        caught_up.set(9, 20);
+        for strategy in [ConflictResolutionStrategy::Timestamp, ConflictResolutionStrategy::ClientId] {
+            let resolution = ConflictResolver::new(strategy).resolve_update(&server_sv, &caught_up);
+            assert_eq!(resolution, ConflictResolution::NoConflict);
+        }
+    }
+
+    #[test]
+    fn test_parse_strategy() {
+        assert_eq!(ConflictResolutionStrategy::parse("merge"), Ok(ConflictResolutionStrategy::Merge));
+        assert_eq!(ConflictResolutionStrategy::parse("Timestamp"), Ok(ConflictResolutionStrategy::Timestamp));
+        assert_eq!(
+            ConflictResolutionStrategy::parse("last_write_wins"),
+            Ok(ConflictResolutionStrategy::Timestamp)
+        );
+        assert_eq!(ConflictResolutionStrategy::parse("client_id"), Ok(ConflictResolutionStrategy::ClientId));
+
+        for value in ["custom", "newest", ""] {
+            let err = ConflictResolutionStrategy::parse(value).unwrap_err();
+            assert_eq!(err, SyncError::UnknownStrategy(value.to_string()));
+            assert!(err.to_string().contains("expected one of"));
+        }
+    }
+
+    #[test]
+    fn test_default_strategy_matches_default_resolver() {
+        assert_eq!(ConflictResolver::default().strategy(), ConflictResolutionStrategy::DEFAULT);
+    }
+
     #[test]
     fn test_calculate_missing_updates() {
         let resolver = ConflictResolver::new(ConflictResolutionStrategy::Merge);
@@ -2,6 +2,7 @@ pub mod yjs_handler;
 pub mod state_vector;
 pub mod sync_handler;
 pub mod conflict_resolver;
+pub mod persistence;
 
 pub fn config(cfg: &mut actix_web::web::ServiceConfig) {
     yjs_handler::config(cfg);
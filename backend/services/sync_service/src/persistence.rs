@@ -0,0 +1,600 @@
+// Durable storage for Yjs document updates
+// Updates are appended to `document_updates` and replayed in order to rebuild
+// a document when the first client joins after a restart. Once enough
+// updates pile up they are merged into a single Yjs update, stored as a
+// snapshot row.
+
+use crate::state_vector::StateVector;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, Transact, Update};
+
+/// Default number of buffered updates that triggers a flush
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Default interval between background flushes in milliseconds
+pub const DEFAULT_FLUSH_INTERVAL_MS: u64 = 500;
+
+/// Default number of updates kept in memory while writes keep failing
+pub const DEFAULT_MAX_PENDING_UPDATES: usize = 10_000;
+
+/// Default number of updates since the last snapshot that triggers compaction
+pub const DEFAULT_COMPACT_MAX_UPDATES: i64 = 500;
+
+/// Default size in bytes of updates since the last snapshot that triggers compaction
+pub const DEFAULT_COMPACT_MAX_BYTES: i64 = 1024 * 1024;
+
+/// Persisted state of a document
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentState {
+    pub document_id: Uuid,
+    /// Updates in the order they were received (a snapshot counts as one);
+    /// apply all of them to rebuild
+    pub updates: Vec<Vec<u8>>,
+    /// State vector stored with the most recent update, empty if none.
+    /// After compaction this is the merged state vector of every update.
+    pub state_vector: Vec<u8>,
+    /// Sequence number of the most recent update, 0 if none
+    pub last_seq: i64,
+}
+
+/// An update waiting to be written
+#[derive(Debug, Clone)]
+pub struct PendingUpdate {
+    pub document_id: Uuid,
+    pub update: Vec<u8>,
+    pub state_vector: Vec<u8>,
+}
+
+/// Append a single update for a document
+pub async fn persist_update(
+    pool: &PgPool,
+    document_id: Uuid,
+    update: &[u8],
+    state_vector: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO document_updates (document_id, update_data, state_vector)
+        VALUES ($1, $2, $3)
+        "#,
+        document_id,
+        update,
+        state_vector
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Append several updates in one statement, preserving their order
+pub async fn persist_updates(pool: &PgPool, updates: &[PendingUpdate]) -> Result<u64, sqlx::Error> {
+    if updates.is_empty() {
+        return Ok(0);
+    }
+
+    let document_ids: Vec<Uuid> = updates.iter().map(|u| u.document_id).collect();
+    let update_data: Vec<Vec<u8>> = updates.iter().map(|u| u.update.clone()).collect();
+    let state_vectors: Vec<Vec<u8>> = updates.iter().map(|u| u.state_vector.clone()).collect();
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO document_updates (document_id, update_data, state_vector)
+        SELECT * FROM UNNEST($1::uuid[], $2::bytea[], $3::bytea[])
+        "#,
+        &document_ids,
+        &update_data,
+        &state_vectors
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Load every persisted update for a document
+pub async fn load_state(pool: &PgPool, document_id: Uuid) -> Result<DocumentState, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, update_data, state_vector
+        FROM document_updates
+        WHERE document_id = $1
+        ORDER BY id ASC
+        "#,
+        document_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut state = DocumentState {
+        document_id,
+        ..Default::default()
+    };
+    for row in rows {
+        state.updates.push(row.update_data);
+        state.state_vector = row.state_vector;
+        state.last_seq = row.id;
+    }
+
+    Ok(state)
+}
+
+/// Updates a client with the given state vector has not seen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentDiff {
+    /// Missing updates in the order they were received
+    pub updates: Vec<Vec<u8>>,
+    /// Merged state vector of every stored update
+    pub state_vector: StateVector,
+}
+
+/// Collect the stored updates not covered by `client_state`
+///
+/// An update is missing when its stored state vector has a clock the client
+/// has not reached. Updates stored without a decodable state vector are
+/// always sent, since applying an update twice is harmless.
+pub async fn diff_since(
+    pool: &PgPool,
+    document_id: Uuid,
+    client_state: &StateVector,
+) -> Result<DocumentDiff, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT update_data, state_vector
+        FROM document_updates
+        WHERE document_id = $1
+        ORDER BY id ASC
+        "#,
+        document_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut diff = DocumentDiff::default();
+    for row in rows {
+        let covered = match StateVector::decode(&row.state_vector) {
+            Ok(sv) if !sv.is_empty() => {
+                diff.state_vector.merge(&sv);
+                sv.is_ancestor_of(client_state)
+            }
+            _ => false,
+        };
+        if !covered {
+            diff.updates.push(row.update_data);
+        }
+    }
+
+    Ok(diff)
+}
+
+/// When to compact a document's update log
+///
+/// Only updates appended since the last snapshot count towards the limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionThreshold {
+    pub max_updates: i64,
+    pub max_bytes: i64,
+}
+
+impl Default for CompactionThreshold {
+    fn default() -> Self {
+        Self {
+            max_updates: DEFAULT_COMPACT_MAX_UPDATES,
+            max_bytes: DEFAULT_COMPACT_MAX_BYTES,
+        }
+    }
+}
+
+impl CompactionThreshold {
+    /// Read `SYNC_COMPACT_MAX_UPDATES` and `SYNC_COMPACT_MAX_BYTES`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let max_updates = std::env::var("SYNC_COMPACT_MAX_UPDATES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 1)
+            .unwrap_or(DEFAULT_COMPACT_MAX_UPDATES);
+        let max_bytes = std::env::var("SYNC_COMPACT_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_COMPACT_MAX_BYTES);
+
+        Self { max_updates, max_bytes }
+    }
+
+    pub fn is_exceeded(&self, updates: i64, bytes: i64) -> bool {
+        // A lone update has nothing to be merged with
+        updates > 1 && (updates >= self.max_updates || bytes >= self.max_bytes)
+    }
+}
+
+/// Merge all stored updates of a document into a single snapshot row
+///
+/// The snapshot is one Yjs update (`yrs::merge_updates_v1`) equivalent to
+/// applying every stored update in order, and takes the sequence number of the
+/// newest update it replaces. Its state vector is the merge of the stored
+/// ones, or the newest one if any cannot be decoded. Returns `None` when there
+/// was nothing to compact, or when the stored updates are not valid Yjs
+/// updates and the log is left as it is.
+pub async fn compact_document(pool: &PgPool, document_id: Uuid) -> Result<Option<DocumentState>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, update_data, state_vector
+        FROM document_updates
+        WHERE document_id = $1
+        ORDER BY id ASC
+        FOR UPDATE
+        "#,
+        document_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if rows.len() < 2 {
+        tx.commit().await?;
+        return Ok(None);
+    }
+
+    let mut ids = Vec::with_capacity(rows.len());
+    let mut updates = Vec::with_capacity(rows.len());
+    let mut merged = Some(StateVector::new());
+    let mut latest_state_vector = Vec::new();
+    for row in rows {
+        ids.push(row.id);
+        updates.push(row.update_data);
+        merged = match (merged, StateVector::decode(&row.state_vector)) {
+            (Some(mut merged), Ok(sv)) => {
+                merged.merge(&sv);
+                Some(merged)
+            }
+            _ => None,
+        };
+        latest_state_vector = row.state_vector;
+    }
+
+    let snapshot = match yrs::merge_updates_v1(&updates.iter().map(Vec::as_slice).collect::<Vec<_>>()) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            tracing::warn!("Not compacting document {}: updates could not be merged: {}", document_id, e);
+            tx.rollback().await?;
+            return Ok(None);
+        }
+    };
+    let last_seq = *ids.last().unwrap_or(&0);
+    let state_vector = merged.map(|sv| sv.encode()).unwrap_or(latest_state_vector);
+
+    sqlx::query!(
+        "DELETE FROM document_updates WHERE id = ANY($1)",
+        &ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO document_updates (id, document_id, update_data, state_vector, is_snapshot)
+        VALUES ($1, $2, $3, $4, true)
+        "#,
+        last_seq,
+        document_id,
+        &snapshot,
+        &state_vector
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Some(DocumentState {
+        document_id,
+        updates: vec![snapshot],
+        state_vector,
+        last_seq,
+    }))
+}
+
+/// Compact a document once the updates since its last snapshot exceed `threshold`
+pub async fn compact_if_needed(
+    pool: &PgPool,
+    document_id: Uuid,
+    threshold: &CompactionThreshold,
+) -> Result<bool, sqlx::Error> {
+    let pending = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "updates!", COALESCE(SUM(octet_length(update_data)), 0)::BIGINT AS "bytes!"
+        FROM document_updates
+        WHERE document_id = $1 AND is_snapshot = false
+        "#,
+        document_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !threshold.is_exceeded(pending.updates, pending.bytes) {
+        return Ok(false);
+    }
+
+    Ok(compact_document(pool, document_id).await?.is_some())
+}
+
+/// What a client is missing, as Yjs encodings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct YjsDiff {
+    /// One Yjs update holding everything the client has not seen
+    pub update: Vec<u8>,
+    /// Yjs state vector of the stored document
+    pub state_vector: Vec<u8>,
+}
+
+/// Rebuild a document from its stored updates and encode what a client with
+/// `client_state` is missing
+///
+/// Stored updates that are not valid Yjs updates are skipped with a warning.
+/// A client that is up to date gets an update that changes nothing.
+pub fn encode_diff(state: &DocumentState, client_state: &yrs::StateVector) -> YjsDiff {
+    let doc = Doc::new();
+    {
+        let mut txn = doc.transact_mut();
+        for update in &state.updates {
+            let applied = Update::decode_v1(update)
+                .map_err(|e| e.to_string())
+                .and_then(|update| txn.apply_update(update).map_err(|e| e.to_string()));
+            if let Err(e) = applied {
+                tracing::warn!("Skipping invalid update for document {}: {}", state.document_id, e);
+            }
+        }
+    }
+
+    let txn = doc.transact();
+    YjsDiff {
+        update: txn.encode_state_as_update_v1(client_state),
+        state_vector: txn.state_vector().encode_v1(),
+    }
+}
+
+/// Buffers updates and writes them in batches
+///
+/// Editors send an update per keystroke, so writing each one directly would
+/// cost a round-trip per key. Updates are held in memory until `batch_size`
+/// are queued or the background task started with `spawn_flush_task` runs.
+/// `load_state` flushes first so readers never miss buffered updates.
+/// After each write, documents whose log passed the compaction threshold
+/// are compacted. Failed writes keep their updates for the next attempt, up
+/// to `max_pending`; past that the oldest updates are dropped.
+pub struct UpdateBatcher {
+    pool: PgPool,
+    pending: Mutex<Vec<PendingUpdate>>,
+    batch_size: usize,
+    max_pending: usize,
+    flush_interval: Duration,
+    compaction: CompactionThreshold,
+}
+
+impl UpdateBatcher {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            pending: Mutex::new(Vec::new()),
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_pending: DEFAULT_MAX_PENDING_UPDATES,
+            flush_interval: Duration::from_millis(DEFAULT_FLUSH_INTERVAL_MS),
+            compaction: CompactionThreshold::default(),
+        }
+    }
+
+    /// Read `SYNC_UPDATE_BATCH_SIZE`, `SYNC_UPDATE_FLUSH_INTERVAL_MS` and
+    /// `SYNC_UPDATE_MAX_PENDING`, falling back to the defaults for missing or
+    /// invalid values
+    pub fn from_env(pool: PgPool) -> Self {
+        let batch_size = std::env::var("SYNC_UPDATE_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_BATCH_SIZE);
+        let flush_interval_ms = std::env::var("SYNC_UPDATE_FLUSH_INTERVAL_MS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_FLUSH_INTERVAL_MS);
+        let max_pending = std::env::var("SYNC_UPDATE_MAX_PENDING")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_PENDING_UPDATES);
+
+        Self::new(pool)
+            .with_batch_size(batch_size)
+            .with_max_pending(max_pending)
+            .with_flush_interval(Duration::from_millis(flush_interval_ms))
+            .with_compaction(CompactionThreshold::from_env())
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Most updates held in memory; never less than one batch
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn with_compaction(mut self, compaction: CompactionThreshold) -> Self {
+        self.compaction = compaction;
+        self
+    }
+
+    /// Queue an update, writing the batch once it is full
+    pub async fn push(&self, document_id: Uuid, update: &[u8], state_vector: &[u8]) -> Result<(), sqlx::Error> {
+        let documents = {
+            let mut pending = self.pending.lock().await;
+            pending.push(PendingUpdate {
+                document_id,
+                update: update.to_vec(),
+                state_vector: state_vector.to_vec(),
+            });
+            self.drop_oldest(&mut pending);
+
+            if pending.len() < self.batch_size {
+                return Ok(());
+            }
+            Self::write(&self.pool, &mut pending).await?
+        };
+
+        self.compact(documents).await;
+        Ok(())
+    }
+
+    /// Write all buffered updates, returning how many were written
+    pub async fn flush(&self) -> Result<u64, sqlx::Error> {
+        let (written, documents) = {
+            let mut pending = self.pending.lock().await;
+            let written = pending.len() as u64;
+            (written, Self::write(&self.pool, &mut pending).await?)
+        };
+
+        self.compact(documents).await;
+        Ok(written)
+    }
+
+    /// Flush buffered updates, then load the document's state
+    pub async fn load_state(&self, document_id: Uuid) -> Result<DocumentState, sqlx::Error> {
+        self.flush().await?;
+        load_state(&self.pool, document_id).await
+    }
+
+    /// Number of updates waiting to be written
+    pub async fn pending(&self) -> usize {
+        self.pending.lock().await.len()
+    }
+
+    /// Flush on a fixed interval so quiet documents are not left in memory
+    pub fn spawn_flush_task(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.flush_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.flush().await {
+                    tracing::error!("Failed to flush document updates: {}", e);
+                }
+            }
+        })
+    }
+
+    // The buffer is only cleared once the write succeeds, so a failed flush
+    // is retried with the same updates on the next attempt. Returns the
+    // documents that received updates.
+    async fn write(pool: &PgPool, pending: &mut Vec<PendingUpdate>) -> Result<HashSet<Uuid>, sqlx::Error> {
+        persist_updates(pool, pending).await?;
+        Ok(pending.drain(..).map(|u| u.document_id).collect())
+    }
+
+    // Keep the buffer within `max_pending` (and at least one batch) when
+    // writes keep failing, dropping the oldest updates first
+    fn drop_oldest(&self, pending: &mut Vec<PendingUpdate>) {
+        let limit = self.max_pending.max(self.batch_size);
+        if pending.len() > limit {
+            let excess = pending.len() - limit;
+            pending.drain(0..excess);
+            tracing::warn!("Update buffer is full ({} updates), dropped {} oldest", limit, excess);
+        }
+    }
+
+    // Runs outside the buffer lock; a failed compaction leaves the log intact
+    // and is retried after the next write
+    async fn compact(&self, documents: HashSet<Uuid>) {
+        for document_id in documents {
+            if let Err(e) = compact_if_needed(&self.pool, document_id, &self.compaction).await {
+                tracing::warn!("Failed to compact updates for document {}: {}", document_id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yrs::{GetString, Text};
+
+    fn text_of(update: &[u8]) -> String {
+        let doc = Doc::new();
+        let text = doc.get_or_insert_text("content");
+        doc.transact_mut().apply_update(Update::decode_v1(update).unwrap()).unwrap();
+        let txn = doc.transact();
+        text.get_string(&txn)
+    }
+
+    #[test]
+    fn test_encode_diff_sends_only_what_the_client_lacks() {
+        let doc = Doc::with_client_id(1);
+        let text = doc.get_or_insert_text("content");
+        let mut updates = Vec::new();
+        for chunk in ["Hello", ", world"] {
+            let mut txn = doc.transact_mut();
+            let len = text.len(&txn);
+            text.insert(&mut txn, len, chunk);
+            updates.push(txn.encode_update_v1());
+        }
+        let state = DocumentState {
+            updates: updates.clone(),
+            ..Default::default()
+        };
+
+        // A fresh client gets the whole document
+        let diff = encode_diff(&state, &yrs::StateVector::default());
+        assert_eq!(text_of(&diff.update), "Hello, world");
+        assert_eq!(diff.state_vector, doc.transact().state_vector().encode_v1());
+
+        // A client that has the first edit only needs the second
+        let client = Doc::with_client_id(2);
+        let client_text = client.get_or_insert_text("content");
+        client.transact_mut().apply_update(Update::decode_v1(&updates[0]).unwrap()).unwrap();
+        let diff = encode_diff(&state, &client.transact().state_vector());
+        client.transact_mut().apply_update(Update::decode_v1(&diff.update).unwrap()).unwrap();
+        assert_eq!(client_text.get_string(&client.transact()), "Hello, world");
+        assert!(diff.update.len() < updates.iter().map(Vec::len).sum::<usize>());
+    }
+
+    #[test]
+    fn test_encode_diff_skips_invalid_updates() {
+        let doc = Doc::with_client_id(1);
+        let text = doc.get_or_insert_text("content");
+        let mut txn = doc.transact_mut();
+        text.insert(&mut txn, 0, "kept");
+        let state = DocumentState {
+            updates: vec![vec![0xff, 0x00], txn.encode_update_v1()],
+            ..Default::default()
+        };
+        drop(txn);
+
+        assert_eq!(text_of(&encode_diff(&state, &yrs::StateVector::default()).update), "kept");
+    }
+
+    #[test]
+    fn test_compaction_threshold() {
+        let threshold = CompactionThreshold {
+            max_updates: 10,
+            max_bytes: 100,
+        };
+        assert!(!threshold.is_exceeded(9, 99));
+        assert!(threshold.is_exceeded(10, 0));
+        assert!(threshold.is_exceeded(2, 100));
+        assert!(!threshold.is_exceeded(1, 1_000), "A single update is never compacted");
+    }
+}
@@ -49,6 +49,16 @@ impl StateVector {
         &mut self.0
     }
 
+    /// Raise each clock to the highest seen in either vector
+    pub fn merge(&mut self, other: &StateVector) {
+        for (&client_id, &clock) in &other.0 {
+            let entry = self.0.entry(client_id).or_insert(clock);
+            if *entry < clock {
+                *entry = clock;
+            }
+        }
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
         let mut entries: Vec<_> = self.0.iter().collect();
@@ -232,6 +242,23 @@ mod tests {
         assert!(!unrelated.is_ancestor_of(&ancestor));
     }
 
+    #[test]
+    fn test_state_vector_merge() {
+        let mut sv = StateVector::new();
+        sv.set(1, 10);
+        sv.set(2, 5);
+
+        let mut other = StateVector::new();
+        other.set(1, 7);
+        other.set(2, 9);
+        other.set(3, 1);
+
+        sv.merge(&other);
+        assert_eq!(sv.get(1), Some(&10));
+        assert_eq!(sv.get(2), Some(&9));
+        assert_eq!(sv.get(3), Some(&1));
+    }
+
     #[test]
     fn test_state_vector_get_missing() {
         let mut base = StateVector::new();
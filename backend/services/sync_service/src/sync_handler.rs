@@ -1,14 +1,18 @@
 // Sync handler implementation for offline-first sync endpoints
 // Handles document sync state retrieval, update submission, and sync status
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, FromRow};
 use uuid::Uuid;
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use crate::conflict_resolver::{ConflictResolution, ConflictResolutionStrategy, ConflictResolver};
+use crate::persistence::{diff_since, encode_diff, UpdateBatcher};
 use crate::state_vector::StateVector;
+use auth_service::identity::extract_request_user_id;
 use chrono::NaiveDateTime;
+use yrs::updates::decoder::Decode;
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct SyncDocument {
@@ -27,6 +31,10 @@ pub struct SyncUpdateRequest {
     /// Client's state vector
     #[serde(default)]
     pub state_vector: Option<StateVectorDto>,
+    /// Conflict resolution strategy for this document: `merge` (default),
+    /// `timestamp` (alias `last_write_wins`) or `client_id`
+    #[serde(default)]
+    pub strategy: Option<String>,
 }
 
 /// State vector data transfer object
@@ -43,6 +51,9 @@ pub struct SyncUpdateResponse {
     pub merged: bool,
     pub server_clock: u64,
     pub missing_updates: Option<Vec<MissingUpdate>>,
+    /// Strategy applied to the update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
     pub error: Option<String>,
 }
 
@@ -65,6 +76,37 @@ pub struct SyncStateResponse {
     pub error: Option<String>,
 }
 
+/// Request body for a state vector diff
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncDiffRequest {
+    /// Base64 encoded Yjs state vector of the client; empty when the client
+    /// has nothing yet
+    #[serde(default)]
+    pub state_vector: String,
+}
+
+/// Response for a state vector diff
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncDiffResponse {
+    pub document_id: String,
+    /// Base64 encoded Yjs update with everything the client is missing
+    pub update: String,
+    /// Base64 encoded Yjs state vector of the server's document
+    pub state_vector: String,
+    pub error: Option<String>,
+}
+
+impl SyncDiffResponse {
+    fn error(document_id: Uuid, message: impl Into<String>) -> Self {
+        SyncDiffResponse {
+            document_id: document_id.to_string(),
+            update: String::new(),
+            state_vector: String::new(),
+            error: Some(message.into()),
+        }
+    }
+}
+
 /// Response for sync status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SyncStatusResponse {
@@ -159,6 +201,21 @@ pub async fn post_sync_update(
 ) -> impl Responder {
     let document_id = path.into_inner();
 
+    let strategy = match body.strategy.as_deref().map(ConflictResolutionStrategy::parse) {
+        None => ConflictResolutionStrategy::DEFAULT,
+        Some(Ok(strategy)) => strategy,
+        Some(Err(e)) => {
+            return HttpResponse::BadRequest().json(SyncUpdateResponse {
+                success: false,
+                merged: false,
+                server_clock: 0,
+                missing_updates: None,
+                strategy: None,
+                error: Some(e.to_string()),
+            });
+        }
+    };
+
     // Decode base64 update
     use base64::Engine;
     let update_data = match base64::engine::general_purpose::STANDARD.decode(&body.update) {
@@ -169,6 +226,7 @@ pub async fn post_sync_update(
                 merged: false,
                 server_clock: 0,
                 missing_updates: None,
+                strategy: None,
                 error: Some(format!("Invalid base64 encoding: {}", e)),
             });
         }
@@ -189,6 +247,49 @@ pub async fn post_sync_update(
 
     match doc_check {
         Ok(Some(_doc)) => {
+            // Extract client's state vector
+            let client_sv = body.state_vector.as_ref().map(|sv| {
+                let mut sv_obj = StateVector::new();
+                if let Ok(client_id) = sv.client_id.parse::<u64>() {
+                    sv_obj.set(client_id, sv.clock);
+                }
+                sv_obj
+            });
+
+            // A client that missed server changes is in conflict; the chosen
+            // strategy decides whether its update still lands
+            if let Some(client_sv) = &client_sv {
+                let server_sv = match diff_since(&state.pool, document_id, client_sv).await {
+                    Ok(diff) => diff.state_vector,
+                    Err(e) => {
+                        tracing::error!("Failed to load sync state for document {}: {}", document_id, e);
+                        return HttpResponse::InternalServerError().json(SyncUpdateResponse {
+                            success: false,
+                            merged: false,
+                            server_clock: 0,
+                            missing_updates: None,
+                            strategy: None,
+                            error: Some("Failed to load sync state".to_string()),
+                        });
+                    }
+                };
+
+                let resolution = ConflictResolver::new(strategy).resolve_update(&server_sv, client_sv);
+                if matches!(resolution, ConflictResolution::KeepFirst | ConflictResolution::Unresolved) {
+                    return HttpResponse::Conflict().json(SyncUpdateResponse {
+                        success: false,
+                        merged: false,
+                        server_clock: 0,
+                        missing_updates: None,
+                        strategy: Some(strategy.as_str().to_string()),
+                        error: Some(format!(
+                            "Update rejected by the {} strategy: the server has newer changes",
+                            strategy.as_str()
+                        )),
+                    });
+                }
+            }
+
             // Atomically increment server_clock and return the new value
             let result = sqlx::query!(
                 r#"
@@ -213,6 +314,7 @@ pub async fn post_sync_update(
                         merged: false,
                         server_clock: 0,
                         missing_updates: None,
+                        strategy: None,
                         error: Some("Failed to update sync state".to_string()),
                     });
                 }
@@ -225,15 +327,6 @@ pub async fn post_sync_update(
             }
             drop(clock);
 
-            // Extract client's state vector
-            let client_sv = body.state_vector.as_ref().map(|sv| {
-                let mut sv_obj = StateVector::new();
-                if let Ok(client_id) = sv.client_id.parse::<u64>() {
-                    sv_obj.set(client_id, sv.clock);
-                }
-                sv_obj
-            });
-
             // In a real implementation, we would:
             // 1. Decode the Yjs update
             // 2. Merge with existing document state using CRDT
@@ -269,8 +362,9 @@ pub async fn post_sync_update(
 
             // Log that we are acknowledging the update but not yet persisting
             tracing::info!(
-                "Acknowledged CRDT update for document {} (merged=true [simulated], persistence deferred)",
-                document_id
+                "Acknowledged CRDT update for document {} (strategy={}, merged=true [simulated], persistence deferred)",
+                document_id,
+                strategy.as_str()
             );
 
             // Return success response assuming "in-memory" or "client-side" handling for now
@@ -285,6 +379,7 @@ pub async fn post_sync_update(
                 merged: false,
                 server_clock: new_clock,
                 missing_updates,
+                strategy: Some(strategy.as_str().to_string()),
                 error: None,
             })
         }
@@ -294,6 +389,7 @@ pub async fn post_sync_update(
                 merged: false,
                 server_clock: 0,
                 missing_updates: None,
+                strategy: None,
                 error: Some("Document not found".to_string()),
             })
         }
@@ -303,12 +399,100 @@ pub async fn post_sync_update(
                 merged: false,
                 server_clock: 0,
                 missing_updates: None,
+                strategy: None,
                 error: Some(format!("Database error: {}", e)),
             })
         }
     }
 }
 
+/// Whether a user owns or is a member of the space holding a document
+async fn has_document_access(pool: &PgPool, document_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let found = sqlx::query!(
+        r#"
+        SELECT 1 as found FROM documents d
+        JOIN spaces s ON d.space_id = s.id
+        WHERE d.id = $1 AND s.is_deleted = false AND (
+            s.owner_id = $2
+            OR s.id IN (SELECT space_id FROM space_memberships WHERE user_id = $2)
+        )
+        LIMIT 1
+        "#,
+        document_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(found.is_some())
+}
+
+/// Compute the Yjs update a rejoining client is missing from its state vector
+pub async fn post_sync_diff(
+    path: web::Path<Uuid>,
+    body: web::Json<SyncDiffRequest>,
+    batcher: web::Data<UpdateBatcher>,
+    state: web::Data<SyncAppState>,
+    http_req: HttpRequest,
+) -> impl Responder {
+    let document_id = path.into_inner();
+
+    let user_id = match extract_request_user_id(&http_req).await {
+        Ok(id) => id,
+        Err(e) => {
+            return HttpResponse::Unauthorized().json(SyncDiffResponse::error(document_id, e.to_string()));
+        }
+    };
+
+    match has_document_access(&state.pool, document_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::Forbidden().json(SyncDiffResponse::error(
+                document_id,
+                "You don't have access to this document",
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error checking document access: {}", e);
+            return HttpResponse::InternalServerError()
+                .json(SyncDiffResponse::error(document_id, "Failed to verify access"));
+        }
+    }
+
+    use base64::Engine;
+    let engine = base64::engine::general_purpose::STANDARD;
+    let client_state = match engine.decode(&body.state_vector) {
+        Ok(bytes) if bytes.is_empty() => Some(yrs::StateVector::default()),
+        Ok(bytes) => yrs::StateVector::decode_v1(&bytes).ok(),
+        Err(_) => None,
+    };
+    let client_state = match client_state {
+        Some(sv) => sv,
+        None => {
+            return HttpResponse::BadRequest()
+                .json(SyncDiffResponse::error(document_id, "Invalid state vector encoding"));
+        }
+    };
+
+    // Loading through the batcher flushes buffered updates first
+    match batcher.load_state(document_id).await {
+        Ok(stored) => {
+            let diff = encode_diff(&stored, &client_state);
+            HttpResponse::Ok().json(SyncDiffResponse {
+                document_id: document_id.to_string(),
+                update: engine.encode(diff.update),
+                state_vector: engine.encode(diff.state_vector),
+                error: None,
+            })
+        }
+        Err(e) => {
+            tracing::error!("Failed to compute diff for document {}: {}", document_id, e);
+            HttpResponse::InternalServerError()
+                .json(SyncDiffResponse::error(document_id, "Failed to compute diff"))
+        }
+    }
+}
+
 /// Get sync status for offline-first
 pub async fn get_sync_status(
     state: web::Data<SyncAppState>,
@@ -477,6 +661,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 "/documents/{document_id}",
                 web::post().to(post_sync_update),
             )
+            .route(
+                "/{document_id}/diff",
+                web::post().to(post_sync_diff),
+            )
             .route(
                 "/offline/status",
                 web::get().to(get_sync_status),
@@ -1,5 +1,7 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::persistence::UpdateBatcher;
 // use std::sync::Arc;
 // use shared_models::entities::Document;
 
@@ -9,6 +11,9 @@ use serde::{Deserialize, Serialize};
 pub struct SyncUpdateRequest {
     pub document_id: String,
     pub update: Vec<u8>,
+    /// Sender's state vector after applying `update`
+    #[serde(default)]
+    pub state_vector: Vec<u8>,
     pub client_id: Option<String>,
     pub clock: Option<u64>,
 }
@@ -44,12 +49,36 @@ pub struct SyncInitRequest {
 pub struct SyncInitResponse {
     pub document_id: String,
     pub state: Vec<u8>,
+    /// Persisted updates to apply, oldest first
+    #[serde(default)]
+    pub updates: Vec<Vec<u8>>,
     pub server_clock: u64,
 }
 
+fn invalid_document_id() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "INVALID_DOCUMENT_ID",
+        "message": "document_id must be a UUID",
+    }))
+}
+
 pub async fn handle_sync_update(
     data: web::Json<SyncUpdateRequest>,
+    batcher: web::Data<UpdateBatcher>,
 ) -> impl Responder {
+    let document_id = match Uuid::parse_str(&data.document_id) {
+        Ok(id) => id,
+        Err(_) => return invalid_document_id(),
+    };
+
+    if let Err(e) = batcher.push(document_id, &data.update, &data.state_vector).await {
+        tracing::error!("Failed to persist update for document {}: {}", document_id, e);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "DATABASE_ERROR",
+            "message": "Failed to persist update",
+        }));
+    }
+
     HttpResponse::Ok().json(SyncUpdateResponse {
         success: true,
         document_id: data.document_id.clone(),
@@ -68,14 +97,32 @@ pub async fn handle_sync_state(
     })
 }
 
+/// Called when the first client joins a document; returns the persisted
+/// updates so it can rebuild state lost from memory
 pub async fn handle_sync_init(
     data: web::Json<SyncInitRequest>,
+    batcher: web::Data<UpdateBatcher>,
 ) -> impl Responder {
-    HttpResponse::Ok().json(SyncInitResponse {
-        document_id: data.document_id.clone(),
-        state: Vec::new(),
-        server_clock: 0,
-    })
+    let document_id = match Uuid::parse_str(&data.document_id) {
+        Ok(id) => id,
+        Err(_) => return invalid_document_id(),
+    };
+
+    match batcher.load_state(document_id).await {
+        Ok(state) => HttpResponse::Ok().json(SyncInitResponse {
+            document_id: data.document_id.clone(),
+            state: state.state_vector,
+            updates: state.updates,
+            server_clock: state.last_seq as u64,
+        }),
+        Err(e) => {
+            tracing::error!("Failed to load state for document {}: {}", document_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "DATABASE_ERROR",
+                "message": "Failed to load document state",
+            }))
+        }
+    }
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -1,17 +1,60 @@
 use crate::{
     handlers::handle_message,
-    models::ClientMessage,
-    presence::{PresenceEntry, PresenceStore, PRESENCE_STORE},
-    WebSocketSession, SESSION_STORE,
+    keepalive::{KeepaliveConfig, HEARTBEAT_TIMEOUT_CODE, KEEPALIVE},
+    message_size::{message_too_large, MessageSizeLimit, MESSAGE_SIZE_LIMIT, MESSAGE_TOO_LARGE_CODE},
+    models::{ClientMessage, ConnectionInfo, MessageType, ServerMessage, UserState},
+    presence::{PresenceEntry, PresenceStore, AWARENESS_STORE, PRESENCE_STORE},
+    shutdown::{shutdown_notice, SERVER_SHUTDOWN_CODE, WS_SHUTDOWN},
+    SessionLimitExceeded, WebSocketSession, SESSION_LIMIT_CODE, SESSION_STORE,
 };
-use actix::{ActorContext, ActorFutureExt, AsyncContext, WrapFuture};
-use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix::{ActorContext, ActorFutureExt, AsyncContext, Recipient, WrapFuture};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
-use std::time::{Duration, Instant};
+use shared_models::request_id::RequestId;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::Instrument;
 use uuid::Uuid;
 
-const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
-const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+/// A serialized message for a session actor to send to its client
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct SessionText(pub String);
+
+/// Mailboxes of the session actors running on this instance, by session id
+///
+/// Broadcasts look sessions up in [`SESSION_STORE`] and deliver through
+/// here, since only the actor can write to its socket.
+#[derive(Default)]
+pub struct SessionMailboxes {
+    mailboxes: RwLock<HashMap<Uuid, Recipient<SessionText>>>,
+}
+
+impl SessionMailboxes {
+    pub fn register(&self, session_id: Uuid, recipient: Recipient<SessionText>) {
+        self.mailboxes.write().unwrap_or_else(|e| e.into_inner()).insert(session_id, recipient);
+    }
+
+    pub fn unregister(&self, session_id: Uuid) {
+        self.mailboxes.write().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    }
+
+    /// Queue `text` for the session's client; false if the session is not
+    /// running on this instance
+    pub fn send(&self, session_id: Uuid, text: String) -> bool {
+        let mailboxes = self.mailboxes.read().unwrap_or_else(|e| e.into_inner());
+        match mailboxes.get(&session_id) {
+            Some(recipient) => {
+                recipient.do_send(SessionText(text));
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+pub static SESSION_MAILBOXES: once_cell::sync::Lazy<SessionMailboxes> =
+    once_cell::sync::Lazy::new(SessionMailboxes::default);
 
 pub struct DocumentWsHandler {
     session_id: Uuid,
@@ -19,9 +62,13 @@ pub struct DocumentWsHandler {
     user_id: Uuid,
     display_name: String,
     color: String,
-    last_heartbeat: Instant,
+    connection: ConnectionInfo,
+    keepalive: KeepaliveConfig,
     presence_store: &'static PresenceStore,
+    message_size_limit: MessageSizeLimit,
     session_cleaned_up: bool, // Guard against double cleanup
+    // Carries the upgrade request's id into logs for the session's lifetime
+    span: tracing::Span,
 }
 
 impl DocumentWsHandler {
@@ -34,20 +81,35 @@ impl DocumentWsHandler {
             user_id,
             display_name,
             color,
-            last_heartbeat: Instant::now(),
+            connection: ConnectionInfo::new(session_id, document_id, user_id),
+            keepalive: *KEEPALIVE,
             presence_store: &PRESENCE_STORE,
+            message_size_limit: *MESSAGE_SIZE_LIMIT,
             session_cleaned_up: false,
+            span: tracing::Span::none(),
         }
     }
 
-    fn start_session(&self) {
-        let session = WebSocketSession::new(
+    /// Tag the session's log lines with the id of the request that opened it
+    pub fn with_request_id(mut self, request_id: &RequestId) -> Self {
+        self.span = tracing::info_span!(
+            "ws_session",
+            request_id = %request_id,
+            session_id = %self.session_id,
+        );
+        self
+    }
+
+    fn start_session(&self) -> Result<(), SessionLimitExceeded> {
+        let mut session = WebSocketSession::new(
             self.document_id,
             self.user_id,
             self.display_name.clone(),
             self.color.clone(),
         );
-        SESSION_STORE.add_session(session);
+        // Register under the actor's id so end_session removes this exact entry
+        session.id = self.session_id;
+        SESSION_STORE.try_add_session(session)?;
 
         let entry = PresenceEntry::new(
             self.user_id,
@@ -56,6 +118,18 @@ impl DocumentWsHandler {
             self.document_id,
         );
         self.presence_store.set_presence(entry);
+
+        AWARENESS_STORE.join(
+            self.document_id,
+            UserState {
+                user_id: self.user_id,
+                display_name: self.display_name.clone(),
+                color: self.color.clone(),
+                cursor: None,
+                last_active: chrono::Utc::now(),
+            },
+        );
+        Ok(())
     }
 
     fn end_session(&mut self) {
@@ -66,7 +140,69 @@ impl DocumentWsHandler {
         self.session_cleaned_up = true;
 
         SESSION_STORE.remove_session(self.session_id);
+        SESSION_MAILBOXES.unregister(self.session_id);
         self.presence_store.remove_presence(self.user_id);
+        // The user is still in the document while another of their tabs is open
+        if !SESSION_STORE.has_user_session_in_document(self.document_id, self.user_id) {
+            AWARENESS_STORE.leave(self.document_id, self.user_id);
+            crate::handlers::broadcast_user_leave(self.document_id, self.user_id);
+        }
+    }
+
+    // Close a connection the client stopped answering, usually half-open
+    fn close_for_timeout(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        tracing::warn!(
+            "WebSocket client timeout for session {} (user {}), last heard from at {}",
+            self.session_id,
+            self.user_id,
+            self.connection.last_ping
+        );
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Away,
+            description: Some(HEARTBEAT_TIMEOUT_CODE.to_string()),
+        }));
+        self.end_session();
+        ctx.stop();
+    }
+
+    // Refuse a message over the size limit without deserializing it
+    fn reject_oversized(&mut self, ctx: &mut ws::WebsocketContext<Self>, len: Option<usize>) {
+        tracing::warn!(
+            "Closing WebSocket session {} (user {}): message over {} bytes",
+            self.session_id,
+            self.user_id,
+            self.message_size_limit.max_bytes()
+        );
+        if let Ok(json) = serde_json::to_string(&message_too_large(self.message_size_limit, len)) {
+            ctx.text(json);
+        }
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Size,
+            description: Some(MESSAGE_TOO_LARGE_CODE.to_string()),
+        }));
+        self.end_session();
+        ctx.stop();
+    }
+
+    // Tell the client the server is going away so it reconnects elsewhere
+    fn close_for_shutdown(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let session = WebSocketSession {
+            id: self.session_id,
+            document_id: self.document_id,
+            user_id: self.user_id,
+            display_name: self.display_name.clone(),
+            color: self.color.clone(),
+            last_activity: chrono::Utc::now(),
+        };
+        if let Ok(json) = serde_json::to_string(&shutdown_notice(&session)) {
+            ctx.text(json);
+        }
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Restart,
+            description: Some(SERVER_SHUTDOWN_CODE.to_string()),
+        }));
+        self.end_session();
+        ctx.stop();
     }
 }
 
@@ -74,43 +210,85 @@ impl actix::Actor for DocumentWsHandler {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.start_session();
+        let _span = self.span.clone().entered();
+        if let Err(e) = self.start_session() {
+            tracing::warn!("Rejecting WebSocket session {}: {}", self.session_id, e);
+            // Nothing was registered, so there is nothing to clean up on stop
+            self.session_cleaned_up = true;
+            let message = session_limit_message(self.document_id, &e);
+            if let Ok(json) = serde_json::to_string(&message) {
+                ctx.text(json);
+            }
+            ctx.close(Some(ws::CloseReason {
+                code: ws::CloseCode::Policy,
+                description: Some(SESSION_LIMIT_CODE.to_string()),
+            }));
+            ctx.stop();
+            return;
+        }
+        SESSION_MAILBOXES.register(self.session_id, ctx.address().recipient());
 
-        // Run heartbeat: send ping to client and check for timeout
-        ctx.run_interval(HEARTBEAT_INTERVAL, |actor, ctx| {
-            // Send ping to client to probe connection
+        // Close the session when the server starts shutting down
+        let mut shutdown = WS_SHUTDOWN.subscribe();
+        ctx.spawn(
+            async move {
+                let _ = shutdown.wait_for(|closing| *closing).await;
+            }
+            .into_actor(self)
+            .map(|_, actor, ctx| actor.close_for_shutdown(ctx)),
+        );
+
+        // Run heartbeat: close the session if the client went silent,
+        // otherwise ping it so a live client answers before the next check
+        ctx.run_interval(self.keepalive.interval, |actor, ctx| {
+            let _span = actor.span.clone().entered();
+            if actor.connection.is_timed_out(chrono::Utc::now(), actor.keepalive.timeout) {
+                actor.close_for_timeout(ctx);
+                return;
+            }
             ctx.ping(&[0u8]);
 
-            // Check if client has responded within timeout window
-            if Instant::now().duration_since(actor.last_heartbeat) > CLIENT_TIMEOUT {
-                tracing::warn!(
-                    "WebSocket client timeout for session {} (user {})",
-                    actor.session_id,
-                    actor.user_id
-                );
-                actor.end_session();
-                ctx.stop();
+            // Drop participants whose connections went away without a clean close
+            for user_id in AWARENESS_STORE.prune_inactive(actor.document_id) {
+                crate::handlers::broadcast_user_leave(actor.document_id, user_id);
             }
         });
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
+        let _span = self.span.clone().entered();
         self.end_session();
     }
 }
 
+impl actix::Handler<SessionText> for DocumentWsHandler {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionText, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
 impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWsHandler {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        let _span = self.span.clone().entered();
+        // Any frame from the client shows the connection is alive
+        if msg.is_ok() {
+            self.connection.touch();
+        }
         match msg {
             Ok(ws::Message::Ping(msg)) => {
-                self.last_heartbeat = Instant::now();
+                AWARENESS_STORE.touch(self.document_id, self.user_id);
                 ctx.pong(&msg);
             },
             Ok(ws::Message::Pong(_)) => {
-                self.last_heartbeat = Instant::now();
+                AWARENESS_STORE.touch(self.document_id, self.user_id);
             },
             Ok(ws::Message::Text(text)) => {
-                self.last_heartbeat = Instant::now();
+                if !self.message_size_limit.allows(text.len()) {
+                    self.reject_oversized(ctx, Some(text.len()));
+                    return;
+                }
                 if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
                     let session = WebSocketSession {
                         id: self.session_id,
@@ -121,8 +299,8 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
                         last_activity: chrono::Utc::now(),
                     };
 
-                    let fut = async move { handle_message(&session, client_msg).await };
-                    ctx.spawn(fut.into_actor(self).map(|result, _actor, ctx| match result {
+                    let fut = async move { handle_message(&session, client_msg).await }.instrument(self.span.clone());
+                    ctx.spawn(fut.into_actor(self).map(|result, actor, ctx| match result {
                         Ok(messages_to_send) => {
                             for msg in messages_to_send {
                                 if let Ok(json) = serde_json::to_string(&msg) {
@@ -131,13 +309,17 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
                             }
                         },
                         Err(e) => {
+                            let _span = actor.span.enter();
                             tracing::error!("Error handling WebSocket message: {}", e);
                         },
                     }));
                 }
             },
             Ok(ws::Message::Binary(bin)) => {
-                self.last_heartbeat = Instant::now();
+                if !self.message_size_limit.allows(bin.len()) {
+                    self.reject_oversized(ctx, Some(bin.len()));
+                    return;
+                }
                 if let Ok(client_msg) = serde_json::from_slice::<ClientMessage>(&bin) {
                     let session = WebSocketSession {
                         id: self.session_id,
@@ -148,8 +330,8 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
                         last_activity: chrono::Utc::now(),
                     };
 
-                    let fut = async move { handle_message(&session, client_msg).await };
-                    ctx.spawn(fut.into_actor(self).map(|result, _actor, ctx| match result {
+                    let fut = async move { handle_message(&session, client_msg).await }.instrument(self.span.clone());
+                    ctx.spawn(fut.into_actor(self).map(|result, actor, ctx| match result {
                         Ok(messages_to_send) => {
                             for msg in messages_to_send {
                                 if let Ok(json) = serde_json::to_string(&msg) {
@@ -158,6 +340,7 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
                             }
                         },
                         Err(e) => {
+                            let _span = actor.span.enter();
                             tracing::error!("Error handling WebSocket message: {}", e);
                         },
                     }));
@@ -168,6 +351,10 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
             },
             Ok(ws::Message::Nop) => {},
             Ok(ws::Message::Continuation(_)) => {},
+            // The codec refuses frames over the limit before reading them
+            Err(ws::ProtocolError::Overflow) => {
+                self.reject_oversized(ctx, None);
+            },
             Err(e) => {
                 tracing::error!("WebSocket error: {:?}", e);
             },
@@ -175,6 +362,19 @@ impl actix::StreamHandler<Result<ws::Message, ws::ProtocolError>> for DocumentWs
     }
 }
 
+fn session_limit_message(document_id: Uuid, error: &SessionLimitExceeded) -> ServerMessage {
+    ServerMessage {
+        type_: MessageType::Error,
+        document_id,
+        payload: serde_json::json!({
+            "code": SESSION_LIMIT_CODE,
+            "message": error.to_string(),
+            "limit": error.limit,
+        }),
+        timestamp: chrono::Utc::now(),
+    }
+}
+
 pub async fn ws_document_handler(
     req: HttpRequest,
     stream: web::Payload,
@@ -189,9 +389,22 @@ pub async fn ws_document_handler(
     let color = color.into_inner();
     let color = if color.is_empty() { "#3B82F6".to_string() } else { color };
 
-    let handler = DocumentWsHandler::new(document_id, user_id, display_name, color);
+    if !SESSION_STORE.has_capacity_for(user_id) {
+        return Ok(HttpResponse::TooManyRequests().json(serde_json::json!({
+            "error": SESSION_LIMIT_CODE,
+            "message": "Too many concurrent sessions for this user",
+            "limit": SESSION_STORE.max_sessions_per_user(),
+        })));
+    }
+
+    let mut handler = DocumentWsHandler::new(document_id, user_id, display_name, color);
+    if let Some(request_id) = req.extensions().get::<RequestId>() {
+        handler = handler.with_request_id(request_id);
+    }
 
-    let response = ws::start(handler, &req, stream)?;
+    let response = ws::WsResponseBuilder::new(handler, &req, stream)
+        .frame_size(MESSAGE_SIZE_LIMIT.max_bytes())
+        .start()?;
     Ok(response)
 }
 
@@ -202,7 +415,7 @@ pub async fn ws_info_handler(document_id: web::Path<Uuid>) -> actix_web::Result<
     let active_users: Vec<_> = sessions
         .iter()
         .filter_map(|session_arc| {
-            let session = session_arc.lock().ok()?;
+            let session = session_arc.lock().unwrap_or_else(|e| e.into_inner());
             Some(serde_json::json!({
                 "session_id": session.id,
                 "user_id": session.user_id,
@@ -212,11 +425,13 @@ pub async fn ws_info_handler(document_id: web::Path<Uuid>) -> actix_web::Result<
             }))
         })
         .collect();
+    let participants = AWARENESS_STORE.participants(document_id);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "document_id": document_id,
         "active_users": active_users,
         "user_count": active_users.len(),
+        "participants": participants,
     })))
 }
 
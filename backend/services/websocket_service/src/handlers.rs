@@ -1,6 +1,8 @@
 use crate::{
+    actor::SESSION_MAILBOXES,
     models::{AwarenessMessage, ClientMessage, MessageType, ServerMessage, SyncMessage},
-    CursorPosition, UserPresence, WebSocketMessage, WebSocketSession, PRESENCE_STORE, SESSION_STORE,
+    rate_limit::{rate_limited_message, UpdateRateLimiter, UPDATE_RATE_LIMITER},
+    CursorPosition, UserPresence, WebSocketMessage, WebSocketSession, AWARENESS_STORE, PRESENCE_STORE, SESSION_STORE,
 };
 use chrono::Utc;
 use serde_json::json;
@@ -114,6 +116,27 @@ pub static SYNC_MANAGER: once_cell::sync::Lazy<DocumentSyncManager> =
     once_cell::sync::Lazy::new(DocumentSyncManager::new);
 
 pub async fn handle_message(session: &WebSocketSession, msg: ClientMessage) -> Result<Vec<ServerMessage>, String> {
+    handle_message_with_limiter(session, msg, &UPDATE_RATE_LIMITER).await
+}
+
+/// Handle a client message, dropping document updates that exceed the
+/// sender's per-document rate
+pub async fn handle_message_with_limiter(
+    session: &WebSocketSession,
+    msg: ClientMessage,
+    limiter: &UpdateRateLimiter,
+) -> Result<Vec<ServerMessage>, String> {
+    if matches!(msg.type_, MessageType::Sync | MessageType::DocumentUpdate) {
+        if let Err(retry_after) = limiter.check(session.document_id, session.user_id) {
+            tracing::debug!(
+                "Throttling updates from user {} on document {}",
+                session.user_id,
+                session.document_id
+            );
+            return Ok(vec![rate_limited_message(session.document_id, retry_after)]);
+        }
+    }
+
     match msg.type_ {
         MessageType::Sync => handle_sync(session, msg.payload).await,
         MessageType::Awareness => handle_awareness(session, msg.payload).await,
@@ -264,6 +287,9 @@ async fn handle_awareness(
 
     if let Some(ref cursor) = cursor {
         PRESENCE_STORE.update_cursor(user_id, cursor.clone());
+        AWARENESS_STORE.update_cursor(document_id, user_id, cursor.clone());
+    } else {
+        AWARENESS_STORE.touch(document_id, user_id);
     }
 
     // Broadcast awareness update to all clients in the document
@@ -302,6 +328,7 @@ async fn handle_cursor(session: &WebSocketSession, payload: serde_json::Value) -
 
     // Update cursor in presence store
     PRESENCE_STORE.update_cursor(user_id, cursor.clone());
+    AWARENESS_STORE.update_cursor(document_id, user_id, cursor.clone());
 
     Ok(vec![])
 }
@@ -316,13 +343,10 @@ async fn handle_ping(_session: &WebSocketSession) -> Result<Vec<ServerMessage>,
 /// The send_fn callback should take (session_id, message) and send the message
 /// to the WebSocket connection for that session.
 ///
-/// TODO: The actual WebSocket send operation requires the actor context.
-/// When calling this from within a DocumentWsHandler actor, pass a closure like:
+/// To reach the sessions on this instance, pass a closure like:
 ///     |session_id, msg| {
-///         // Look up the connection by session_id and send
-///         // This requires access to the actor's Addr or context
+///         SESSION_MAILBOXES.send(session_id, msg);
 ///     }
-/// For now, this logs the send attempt.
 pub fn broadcast_to_document(
     document_id: Uuid,
     message: ServerMessage,
@@ -332,7 +356,7 @@ pub fn broadcast_to_document(
     let sessions = SESSION_STORE.get_document_sessions(document_id);
 
     for session_arc in sessions {
-        let session = session_arc.lock().unwrap();
+        let session = session_arc.lock().unwrap_or_else(|e| e.into_inner());
         if let Some(exclude) = exclude_user_id {
             if session.user_id == exclude {
                 continue;
@@ -353,13 +377,8 @@ pub fn broadcast_user_leave(document_id: Uuid, user_id: Uuid) {
         timestamp: Utc::now(),
     };
 
-    // Use placeholder delivery callback
-    // TODO: Wire this to the actual WebSocket send mechanism when actor context is available
-    broadcast_to_document(document_id, message, None, |_session_id, _msg| {
-        tracing::warn!(
-            "broadcast_user_leave: WebSocket delivery not implemented - message would be sent to session {}",
-            _session_id
-        );
+    broadcast_to_document(document_id, message, None, |session_id, msg| {
+        SESSION_MAILBOXES.send(session_id, msg);
     });
 }
 
@@ -548,6 +567,108 @@ mod tests {
         assert_eq!(cursor.selection_end, Some(75));
     }
 
+    fn update_message(session: &WebSocketSession, update: Vec<u8>) -> ClientMessage {
+        ClientMessage {
+            type_: MessageType::Sync,
+            document_id: session.document_id,
+            user_id: session.user_id,
+            payload: serde_json::to_value(SyncMessage {
+                state_vector: None,
+                update: Some(update),
+            })
+            .unwrap(),
+        }
+    }
+
+    fn is_rate_limited(messages: &[ServerMessage]) -> bool {
+        messages
+            .iter()
+            .any(|m| m.type_ == MessageType::Error && m.payload["code"] == crate::rate_limit::RATE_LIMITED_CODE)
+    }
+
+    #[tokio::test]
+    async fn test_flooding_client_is_throttled_while_co_editor_continues() {
+        let limiter = UpdateRateLimiter::new(0.001, 2.0);
+        let document_id = Uuid::new_v4();
+        let flooder = WebSocketSession::new(document_id, Uuid::new_v4(), "A".to_string(), "#FFF".to_string());
+        let co_editor = WebSocketSession::new(document_id, Uuid::new_v4(), "B".to_string(), "#000".to_string());
+
+        for i in 0..2u8 {
+            let replies = handle_message_with_limiter(&flooder, update_message(&flooder, vec![i]), &limiter)
+                .await
+                .unwrap();
+            assert!(!is_rate_limited(&replies));
+        }
+
+        // The third update within the burst window is dropped with a backpressure signal
+        let replies = handle_message_with_limiter(&flooder, update_message(&flooder, vec![9]), &limiter)
+            .await
+            .unwrap();
+        assert!(is_rate_limited(&replies));
+        assert!(replies[0].payload["retry_after_ms"].as_u64().unwrap() > 0);
+        let state = SYNC_MANAGER.get_or_create_sync_state(document_id).await;
+        assert_eq!(state.lock().await.last_update, vec![1]);
+
+        // The co-editor's update on the same document is still applied
+        let replies = handle_message_with_limiter(&co_editor, update_message(&co_editor, vec![42]), &limiter)
+            .await
+            .unwrap();
+        assert!(!is_rate_limited(&replies));
+        assert_eq!(state.lock().await.last_update, vec![42]);
+
+        // Non-update messages from the throttled client are not limited
+        let ping = ClientMessage {
+            type_: MessageType::Ping,
+            document_id,
+            user_id: flooder.user_id,
+            payload: serde_json::Value::Null,
+        };
+        let replies = handle_message_with_limiter(&flooder, ping, &limiter).await.unwrap();
+        assert!(!is_rate_limited(&replies));
+    }
+
+    struct Collector(Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl actix::Actor for Collector {
+        type Context = actix::Context<Self>;
+    }
+
+    impl actix::Handler<crate::actor::SessionText> for Collector {
+        type Result = ();
+
+        fn handle(&mut self, msg: crate::actor::SessionText, _ctx: &mut Self::Context) {
+            self.0.lock().unwrap().push(msg.0);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_user_leave_is_delivered_to_sessions_on_the_document() {
+        use actix::Actor;
+
+        let document_id = Uuid::new_v4();
+        let watcher = WebSocketSession::new(document_id, Uuid::new_v4(), "A".to_string(), "#FFF".to_string());
+        let watcher_id = watcher.id;
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collector = Collector(Arc::clone(&received)).start();
+        SESSION_STORE.add_session(watcher);
+        SESSION_MAILBOXES.register(watcher_id, collector.clone().recipient());
+
+        let leaving_user = Uuid::new_v4();
+        broadcast_user_leave(document_id, leaving_user);
+
+        // Messages are handled in order, so once this one is handled the broadcast was too
+        collector.send(crate::actor::SessionText(String::new())).await.unwrap();
+        let mut messages = received.lock().unwrap().clone();
+        messages.pop();
+        assert_eq!(messages.len(), 1);
+        let message: ServerMessage = serde_json::from_str(&messages[0]).unwrap();
+        assert_eq!(message.type_, MessageType::UserLeave);
+        assert_eq!(message.payload["user_id"], leaving_user.to_string());
+
+        SESSION_MAILBOXES.unregister(watcher_id);
+        SESSION_STORE.remove_session(watcher_id);
+    }
+
     #[test]
     fn test_cursor_position_no_selection() {
         let cursor = CursorPosition {
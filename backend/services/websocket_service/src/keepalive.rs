@@ -0,0 +1,114 @@
+//! WebSocket keepalive
+//!
+//! Each session pings its client every `interval`. Any frame from the client
+//! (a pong, a ping or a message) counts as a sign of life and updates the
+//! connection's `last_ping`. A connection that stays silent for longer than
+//! `timeout` is treated as dead, typically a half-open TCP connection, and
+//! the session is closed and cleaned up.
+
+use std::time::Duration;
+
+/// Default seconds between pings
+pub const DEFAULT_PING_INTERVAL_SECS: u64 = 30;
+
+/// Default seconds of silence before a connection is closed
+pub const DEFAULT_CLIENT_TIMEOUT_SECS: u64 = 60;
+
+/// Close reason sent to a client that stopped answering pings
+pub const HEARTBEAT_TIMEOUT_CODE: &str = "HEARTBEAT_TIMEOUT";
+
+/// Ping interval and liveness timeout for WebSocket sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(DEFAULT_PING_INTERVAL_SECS),
+            timeout: Duration::from_secs(DEFAULT_CLIENT_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl KeepaliveConfig {
+    /// Read `WS_PING_INTERVAL_SECS` and `WS_CLIENT_TIMEOUT_SECS`, falling
+    /// back to the defaults for missing or invalid values
+    ///
+    /// Liveness is checked when a ping is sent, so a timeout shorter than the
+    /// interval behaves like one equal to it.
+    pub fn from_env() -> Self {
+        let interval = std::env::var("WS_PING_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_PING_INTERVAL_SECS);
+        let timeout = std::env::var("WS_CLIENT_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_CLIENT_TIMEOUT_SECS);
+
+        Self {
+            interval: Duration::from_secs(interval),
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+}
+
+/// Keepalive settings applied to every WebSocket session on this instance
+pub static KEEPALIVE: once_cell::sync::Lazy<KeepaliveConfig> = once_cell::sync::Lazy::new(KeepaliveConfig::from_env);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionInfo;
+    use chrono::{DateTime, Utc};
+    use uuid::Uuid;
+
+    fn connection(last_ping: DateTime<Utc>) -> ConnectionInfo {
+        ConnectionInfo {
+            last_ping,
+            ..ConnectionInfo::new(Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4())
+        }
+    }
+
+    #[test]
+    fn test_stale_last_ping_times_out() {
+        let now = Utc::now();
+        let timeout = Duration::from_secs(60);
+
+        assert!(connection(now - chrono::Duration::seconds(61)).is_timed_out(now, timeout));
+        assert!(connection(now - chrono::Duration::hours(1)).is_timed_out(now, timeout));
+    }
+
+    #[test]
+    fn test_recent_last_ping_is_alive() {
+        let now = Utc::now();
+        let timeout = Duration::from_secs(60);
+
+        assert!(!connection(now).is_timed_out(now, timeout));
+        assert!(!connection(now - chrono::Duration::seconds(30)).is_timed_out(now, timeout));
+        // Exactly at the timeout still counts as alive
+        assert!(!connection(now - chrono::Duration::seconds(60)).is_timed_out(now, timeout));
+    }
+
+    #[test]
+    fn test_touch_revives_connection() {
+        let now = Utc::now();
+        let mut info = connection(now - chrono::Duration::minutes(5));
+        assert!(info.is_timed_out(Utc::now(), Duration::from_secs(60)));
+
+        info.touch();
+        assert!(!info.is_timed_out(Utc::now(), Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = KeepaliveConfig::default();
+        assert_eq!(config.interval, Duration::from_secs(DEFAULT_PING_INTERVAL_SECS));
+        assert_eq!(config.timeout, Duration::from_secs(DEFAULT_CLIENT_TIMEOUT_SECS));
+    }
+}
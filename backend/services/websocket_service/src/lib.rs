@@ -1,21 +1,29 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use uuid::Uuid;
 
 pub mod actor;
 pub mod connection_manager;
 pub mod handlers;
+pub mod keepalive;
+pub mod message_size;
 pub mod models;
 pub mod presence;
+pub mod rate_limit;
 pub mod redis_pubsub;
+pub mod shutdown;
 
 pub use actor::*;
 pub use handlers::*;
+pub use keepalive::*;
+pub use message_size::*;
 pub use models::*;
 pub use presence::*;
+pub use rate_limit::*;
 pub use redis_pubsub::*;
+pub use shutdown::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CursorPosition {
@@ -134,27 +142,124 @@ impl WebSocketSession {
     }
 }
 
+/// Default maximum number of concurrent sessions a single user may hold
+pub const DEFAULT_MAX_SESSIONS_PER_USER: usize = 20;
+
+/// Error code sent to clients whose connection is rejected by the per-user cap
+pub const SESSION_LIMIT_CODE: &str = "SESSION_LIMIT";
+
+/// Returned when a user already holds the maximum number of concurrent sessions
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("user {user_id} already has the maximum of {limit} concurrent sessions")]
+pub struct SessionLimitExceeded {
+    pub user_id: Uuid,
+    pub limit: usize,
+}
+
+/// In-memory index of active sessions by id, document and user
+///
+/// The maps sit behind `RwLock`s so broadcast fan-out, which only reads,
+/// does not serialize on a single lock. When more than one map is locked,
+/// they are always acquired in the order `sessions`, `document_sessions`,
+/// `user_sessions` to avoid lock-ordering deadlocks.
+///
+/// A panic while a lock is held poisons it; every lock here recovers the
+/// guard instead of failing, so one panicking connection doesn't take down
+/// all later session operations. Each update leaves the maps consistent
+/// before it can panic, so the recovered data is safe to use.
 #[derive(Default)]
 pub struct SessionStore {
-    sessions: Arc<Mutex<HashMap<Uuid, Arc<Mutex<WebSocketSession>>>>>,
-    document_sessions: Arc<Mutex<HashMap<Uuid, Vec<Uuid>>>>,
-    user_sessions: Arc<Mutex<HashMap<Uuid, Vec<Uuid>>>>,
+    sessions: Arc<RwLock<HashMap<Uuid, Arc<Mutex<WebSocketSession>>>>>,
+    document_sessions: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>,
+    user_sessions: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>,
+    /// Maximum concurrent sessions per user; `None` means unlimited
+    max_sessions_per_user: Option<usize>,
 }
 
 impl SessionStore {
     pub fn new() -> Self {
         Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
-            document_sessions: Arc::new(Mutex::new(HashMap::new())),
-            user_sessions: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            document_sessions: Arc::new(RwLock::new(HashMap::new())),
+            user_sessions: Arc::new(RwLock::new(HashMap::new())),
+            max_sessions_per_user: None,
+        }
+    }
+
+    /// Create a store that allows at most `limit` concurrent sessions per user
+    pub fn with_max_sessions_per_user(limit: usize) -> Self {
+        Self {
+            max_sessions_per_user: Some(limit),
+            ..Self::new()
+        }
+    }
+
+    /// Create a store whose per-user cap is read from `WS_MAX_SESSIONS_PER_USER`
+    ///
+    /// Falls back to [`DEFAULT_MAX_SESSIONS_PER_USER`] when unset or invalid.
+    /// A value of `0` disables the cap.
+    pub fn from_env() -> Self {
+        let limit = std::env::var("WS_MAX_SESSIONS_PER_USER")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_SESSIONS_PER_USER);
+
+        if limit == 0 {
+            Self::new()
+        } else {
+            Self::with_max_sessions_per_user(limit)
+        }
+    }
+
+    pub fn max_sessions_per_user(&self) -> Option<usize> {
+        self.max_sessions_per_user
+    }
+
+    /// Whether the user can open another session without exceeding the cap
+    pub fn has_capacity_for(&self, user_id: Uuid) -> bool {
+        match self.max_sessions_per_user {
+            Some(limit) => self.get_user_sessions(user_id).len() < limit,
+            None => true,
+        }
+    }
+
+    /// Add a session, rejecting it if the user is already at the per-user cap
+    ///
+    /// The check and insert happen under the same locks, so concurrent
+    /// connections from one user cannot race past the limit.
+    pub fn try_add_session(&self, session: WebSocketSession) -> Result<(), SessionLimitExceeded> {
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut document_sessions = self.document_sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut user_sessions = self.user_sessions.write().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(limit) = self.max_sessions_per_user {
+            let active = user_sessions.get(&session.user_id).map_or(0, Vec::len);
+            if active >= limit {
+                return Err(SessionLimitExceeded {
+                    user_id: session.user_id,
+                    limit,
+                });
+            }
         }
+
+        Self::insert_session(&mut sessions, &mut document_sessions, &mut user_sessions, session);
+        Ok(())
     }
 
     pub fn add_session(&self, session: WebSocketSession) {
-        let mut sessions = self.sessions.lock().unwrap();
-        let mut document_sessions = self.document_sessions.lock().unwrap();
-        let mut user_sessions = self.user_sessions.lock().unwrap();
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut document_sessions = self.document_sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut user_sessions = self.user_sessions.write().unwrap_or_else(|e| e.into_inner());
+
+        Self::insert_session(&mut sessions, &mut document_sessions, &mut user_sessions, session);
+    }
 
+    fn insert_session(
+        sessions: &mut HashMap<Uuid, Arc<Mutex<WebSocketSession>>>,
+        document_sessions: &mut HashMap<Uuid, Vec<Uuid>>,
+        user_sessions: &mut HashMap<Uuid, Vec<Uuid>>,
+        session: WebSocketSession,
+    ) {
         let session_id = session.id;
         let document_id = session.document_id;
         let user_id = session.user_id;
@@ -167,12 +272,12 @@ impl SessionStore {
     }
 
     pub fn remove_session(&self, session_id: Uuid) {
-        let mut sessions = self.sessions.lock().unwrap();
-        let mut document_sessions = self.document_sessions.lock().unwrap();
-        let mut user_sessions = self.user_sessions.lock().unwrap();
+        let mut sessions = self.sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut document_sessions = self.document_sessions.write().unwrap_or_else(|e| e.into_inner());
+        let mut user_sessions = self.user_sessions.write().unwrap_or_else(|e| e.into_inner());
 
         if let Some(session_arc) = sessions.remove(&session_id) {
-            let session = session_arc.lock().unwrap();
+            let session = session_arc.lock().unwrap_or_else(|e| e.into_inner());
             let document_id = session.document_id;
             let user_id = session.user_id;
 
@@ -186,14 +291,58 @@ impl SessionStore {
         }
     }
 
+    /// Number of active sessions across all documents
+    pub fn session_count(&self) -> usize {
+        self.sessions.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Number of live sessions, for the collaboration load gauges
+    pub fn total_session_count(&self) -> usize {
+        self.session_count()
+    }
+
+    /// Number of documents with at least one live session
+    pub fn active_document_count(&self) -> usize {
+        let document_sessions = self.document_sessions.read().unwrap_or_else(|e| e.into_inner());
+        document_sessions.values().filter(|ids| !ids.is_empty()).count()
+    }
+
+    /// The `n` documents with the most live sessions, busiest first
+    ///
+    /// Ties are ordered by document id so the result is stable. The counts
+    /// are copied out under the read lock; selecting and sorting happen
+    /// after it is released.
+    pub fn top_documents_by_sessions(&self, n: usize) -> Vec<(Uuid, usize)> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut counts: Vec<(Uuid, usize)> = {
+            let document_sessions = self.document_sessions.read().unwrap_or_else(|e| e.into_inner());
+            document_sessions
+                .iter()
+                .filter(|(_, ids)| !ids.is_empty())
+                .map(|(document_id, ids)| (*document_id, ids.len()))
+                .collect()
+        };
+
+        let busiest_first = |a: &(Uuid, usize), b: &(Uuid, usize)| b.1.cmp(&a.1).then(a.0.cmp(&b.0));
+        if counts.len() > n {
+            counts.select_nth_unstable_by(n - 1, busiest_first);
+            counts.truncate(n);
+        }
+        counts.sort_unstable_by(busiest_first);
+        counts
+    }
+
     pub fn get_session(&self, session_id: Uuid) -> Option<Arc<Mutex<WebSocketSession>>> {
-        let sessions = self.sessions.lock().unwrap();
+        let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
         sessions.get(&session_id).cloned()
     }
 
     pub fn get_document_sessions(&self, document_id: Uuid) -> Vec<Arc<Mutex<WebSocketSession>>> {
-        let sessions = self.sessions.lock().unwrap();
-        let document_sessions = self.document_sessions.lock().unwrap();
+        let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
+        let document_sessions = self.document_sessions.read().unwrap_or_else(|e| e.into_inner());
 
         if let Some(session_ids) = document_sessions.get(&document_id) {
             session_ids.iter().filter_map(|id| sessions.get(id).cloned()).collect()
@@ -202,9 +351,17 @@ impl SessionStore {
         }
     }
 
+    /// Whether the user still has a session open on the document
+    pub fn has_user_session_in_document(&self, document_id: Uuid, user_id: Uuid) -> bool {
+        self.get_user_sessions(user_id).iter().any(|session_arc| {
+            let session = session_arc.lock().unwrap_or_else(|e| e.into_inner());
+            session.document_id == document_id
+        })
+    }
+
     pub fn get_user_sessions(&self, user_id: Uuid) -> Vec<Arc<Mutex<WebSocketSession>>> {
-        let sessions = self.sessions.lock().unwrap();
-        let user_sessions = self.user_sessions.lock().unwrap();
+        let sessions = self.sessions.read().unwrap_or_else(|e| e.into_inner());
+        let user_sessions = self.user_sessions.read().unwrap_or_else(|e| e.into_inner());
 
         if let Some(session_ids) = user_sessions.get(&user_id) {
             session_ids.iter().filter_map(|id| sessions.get(id).cloned()).collect()
@@ -214,4 +371,153 @@ impl SessionStore {
     }
 }
 
-pub static SESSION_STORE: once_cell::sync::Lazy<SessionStore> = once_cell::sync::Lazy::new(SessionStore::new);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_document_lookup_proceeds_while_another_reader_holds_the_maps() {
+        let store = Arc::new(SessionStore::new());
+        let document_id = Uuid::new_v4();
+        store.add_session(WebSocketSession::new(document_id, Uuid::new_v4(), "A".to_string(), "#fff".to_string()));
+
+        // Hold read guards on both maps, as an in-flight broadcast would
+        let _sessions = store.sessions.read().unwrap();
+        let _document_sessions = store.document_sessions.read().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let reader = Arc::clone(&store);
+        std::thread::spawn(move || {
+            tx.send(reader.get_document_sessions(document_id).len()).unwrap();
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(1), "concurrent reader was blocked");
+    }
+
+    #[test]
+    fn test_store_keeps_working_after_a_panic_poisons_its_locks() {
+        let store = Arc::new(SessionStore::new());
+        let document_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let first = WebSocketSession::new(document_id, user_id, "A".to_string(), "#fff".to_string());
+        let first_id = first.id;
+        store.add_session(first);
+
+        // Panic while holding every map lock and the session's own mutex
+        let poisoner = Arc::clone(&store);
+        let result = std::thread::spawn(move || {
+            let session = poisoner.get_session(first_id).unwrap();
+            let _sessions = poisoner.sessions.write().unwrap();
+            let _document_sessions = poisoner.document_sessions.write().unwrap();
+            let _user_sessions = poisoner.user_sessions.write().unwrap();
+            let _session = session.lock().unwrap();
+            panic!("connection handler panicked");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(store.sessions.is_poisoned());
+        assert!(store.get_session(first_id).unwrap().is_poisoned());
+
+        let second = WebSocketSession::new(document_id, user_id, "B".to_string(), "#000".to_string());
+        let second_id = second.id;
+        store.add_session(second);
+        assert_eq!(store.session_count(), 2);
+        assert_eq!(store.get_document_sessions(document_id).len(), 2);
+        assert_eq!(store.get_user_sessions(user_id).len(), 2);
+
+        store.remove_session(first_id);
+        assert!(store.get_session(first_id).is_none());
+        assert!(store.get_session(second_id).is_some());
+        assert_eq!(store.get_document_sessions(document_id).len(), 1);
+        assert!(store.has_capacity_for(user_id));
+    }
+
+    #[test]
+    fn test_user_session_in_document_tracks_the_last_tab() {
+        let store = SessionStore::new();
+        let document_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let first = WebSocketSession::new(document_id, user_id, "A".to_string(), "#fff".to_string());
+        let second = WebSocketSession::new(document_id, user_id, "A".to_string(), "#fff".to_string());
+        let elsewhere = WebSocketSession::new(Uuid::new_v4(), user_id, "A".to_string(), "#fff".to_string());
+        let (first_id, second_id) = (first.id, second.id);
+        store.add_session(first);
+        store.add_session(second);
+        store.add_session(elsewhere);
+
+        store.remove_session(first_id);
+        assert!(store.has_user_session_in_document(document_id, user_id));
+
+        // A session on another document does not keep the user in this one
+        store.remove_session(second_id);
+        assert!(!store.has_user_session_in_document(document_id, user_id));
+    }
+
+    fn add_sessions(store: &SessionStore, document_id: Uuid, users: &[Uuid]) -> Vec<Uuid> {
+        users
+            .iter()
+            .map(|user_id| {
+                let session = WebSocketSession::new(document_id, *user_id, "User".to_string(), "#fff".to_string());
+                let session_id = session.id;
+                store.add_session(session);
+                session_id
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_aggregate_counts_across_documents_and_users() {
+        let store = SessionStore::new();
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (busy, quiet, idle) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        add_sessions(&store, busy, &[alice, bob, carol, alice]);
+        add_sessions(&store, quiet, &[bob]);
+        let idle_sessions = add_sessions(&store, idle, &[carol, alice]);
+
+        assert_eq!(store.total_session_count(), 7);
+        assert_eq!(store.active_document_count(), 3);
+
+        // A document whose last session ends no longer counts as active
+        for session_id in idle_sessions {
+            store.remove_session(session_id);
+        }
+        assert_eq!(store.total_session_count(), 5);
+        assert_eq!(store.active_document_count(), 2);
+        assert_eq!(store.top_documents_by_sessions(10), vec![(busy, 4), (quiet, 1)]);
+    }
+
+    #[test]
+    fn test_top_documents_are_busiest_first_and_limited_to_n() {
+        let store = SessionStore::new();
+        let documents: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        for (i, document_id) in documents.iter().enumerate() {
+            let users: Vec<Uuid> = (0..=i).map(|_| Uuid::new_v4()).collect();
+            add_sessions(&store, *document_id, &users);
+        }
+
+        let top = store.top_documents_by_sessions(3);
+
+        assert_eq!(top, vec![(documents[4], 5), (documents[3], 4), (documents[2], 3)]);
+        assert!(store.top_documents_by_sessions(0).is_empty());
+        assert_eq!(store.top_documents_by_sessions(100).len(), 5);
+    }
+
+    #[test]
+    fn test_top_documents_break_ties_by_document_id() {
+        let store = SessionStore::new();
+        let mut tied = vec![Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        for document_id in &tied {
+            add_sessions(&store, *document_id, &[Uuid::new_v4(), Uuid::new_v4()]);
+        }
+        tied.sort();
+
+        let top = store.top_documents_by_sessions(2);
+
+        assert_eq!(top, vec![(tied[0], 2), (tied[1], 2)]);
+    }
+}
+
+pub static SESSION_STORE: once_cell::sync::Lazy<SessionStore> = once_cell::sync::Lazy::new(SessionStore::from_env);
@@ -0,0 +1,105 @@
+//! Inbound message size limit
+//!
+//! Text and binary frames larger than the limit are rejected before they are
+//! deserialized: the client gets a `MESSAGE_TOO_LARGE` error and the
+//! connection is closed. The same limit is the codec's maximum frame size, so
+//! an oversized frame is refused while it is being read rather than buffered
+//! in full.
+
+use crate::models::ErrorResponse;
+
+/// Default largest inbound message, in bytes (1MB)
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+/// Error code sent to clients whose message exceeds the limit
+pub const MESSAGE_TOO_LARGE_CODE: &str = "MESSAGE_TOO_LARGE";
+
+/// Largest message a client may send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageSizeLimit {
+    max_bytes: usize,
+}
+
+impl Default for MessageSizeLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_MESSAGE_BYTES)
+    }
+}
+
+impl MessageSizeLimit {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes: max_bytes.max(1),
+        }
+    }
+
+    /// Read `WS_MAX_MESSAGE_BYTES`, falling back to the default for missing
+    /// or invalid values
+    pub fn from_env() -> Self {
+        let max_bytes = std::env::var("WS_MAX_MESSAGE_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_MESSAGE_BYTES);
+
+        Self::new(max_bytes)
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Whether a message of `len` bytes may be processed
+    pub fn allows(&self, len: usize) -> bool {
+        len <= self.max_bytes
+    }
+}
+
+/// Error sent before closing a connection that sent an oversized message
+///
+/// `len` is `None` when the codec refused the frame before it was read.
+pub fn message_too_large(limit: MessageSizeLimit, len: Option<usize>) -> ErrorResponse {
+    let message = match len {
+        Some(len) => format!("Message of {} bytes exceeds the limit of {} bytes", len, limit.max_bytes()),
+        None => format!("Message exceeds the limit of {} bytes", limit.max_bytes()),
+    };
+    ErrorResponse::new(MESSAGE_TOO_LARGE_CODE, &message)
+}
+
+/// Limit applied to every WebSocket session on this instance
+pub static MESSAGE_SIZE_LIMIT: once_cell::sync::Lazy<MessageSizeLimit> =
+    once_cell::sync::Lazy::new(MessageSizeLimit::from_env);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lengths_up_to_the_limit_are_allowed() {
+        let limit = MessageSizeLimit::new(1024);
+
+        assert!(limit.allows(0));
+        assert!(limit.allows(1023));
+        assert!(limit.allows(1024));
+        assert!(!limit.allows(1025));
+        assert!(!limit.allows(10 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_default_limit_is_one_megabyte() {
+        let limit = MessageSizeLimit::default();
+
+        assert_eq!(limit.max_bytes(), 1024 * 1024);
+        assert!(limit.allows(1024 * 1024));
+        assert!(!limit.allows(1024 * 1024 + 1));
+    }
+
+    #[test]
+    fn test_error_reports_code_and_sizes() {
+        let error = message_too_large(MessageSizeLimit::new(10), Some(11));
+
+        assert_eq!(error.code, MESSAGE_TOO_LARGE_CODE);
+        assert!(error.message.contains("11 bytes"));
+        assert!(error.message.contains("10 bytes"));
+    }
+}
@@ -81,7 +81,9 @@ impl DocumentAwareness {
         }
     }
 
-    pub fn add_user(&mut self, user: UserState) {
+    /// Add or replace a user, marking them active now
+    pub fn add_user(&mut self, mut user: UserState) {
+        user.last_active = Utc::now();
         self.users.insert(user.user_id, user);
     }
 
@@ -96,6 +98,33 @@ impl DocumentAwareness {
         }
     }
 
+    /// Mark a user active without changing their state
+    pub fn touch(&mut self, user_id: Uuid) {
+        if let Some(user) = self.users.get_mut(&user_id) {
+            user.last_active = Utc::now();
+        }
+    }
+
+    /// Remove users not active within `max_age`, returning their ids so
+    /// their departure can be broadcast
+    pub fn prune_inactive(&mut self, max_age: std::time::Duration) -> Vec<Uuid> {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        let Some(cutoff) = Utc::now().checked_sub_signed(max_age) else {
+            return Vec::new();
+        };
+
+        let stale: Vec<Uuid> = self
+            .users
+            .values()
+            .filter(|user| user.last_active < cutoff)
+            .map(|user| user.user_id)
+            .collect();
+        for user_id in &stale {
+            self.users.remove(user_id);
+        }
+        stale
+    }
+
     pub fn get_users(&self) -> Vec<&UserState> {
         self.users.values().collect()
     }
@@ -129,6 +158,32 @@ pub struct ConnectionInfo {
     pub last_ping: DateTime<Utc>,
 }
 
+impl ConnectionInfo {
+    pub fn new(session_id: Uuid, document_id: Uuid, user_id: Uuid) -> Self {
+        let now = Utc::now();
+        Self {
+            session_id,
+            document_id,
+            user_id,
+            connected_at: now,
+            last_ping: now,
+        }
+    }
+
+    /// Record that the client was heard from
+    pub fn touch(&mut self) {
+        self.last_ping = Utc::now();
+    }
+
+    /// Whether the client has been silent for longer than `timeout` at `now`
+    pub fn is_timed_out(&self, now: DateTime<Utc>, timeout: std::time::Duration) -> bool {
+        match chrono::Duration::from_std(timeout) {
+            Ok(timeout) => now.signed_duration_since(self.last_ping) > timeout,
+            Err(_) => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub code: String,
@@ -1,10 +1,15 @@
+use crate::models::{DocumentAwareness, UserState};
 use crate::CursorPosition;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// How long a participant stays in a document's awareness without activity
+pub const AWARENESS_MAX_AGE: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PresenceEntry {
     pub user_id: Uuid,
@@ -89,6 +94,97 @@ impl PresenceStore {
 
 pub static PRESENCE_STORE: once_cell::sync::Lazy<PresenceStore> = once_cell::sync::Lazy::new(PresenceStore::new);
 
+/// Per-document awareness of who is participating
+///
+/// Participants that stop sending anything, including heartbeats, are
+/// pruned once they have been inactive for longer than `max_age`, so a
+/// dropped client doesn't linger in the participant list.
+pub struct AwarenessStore {
+    documents: Mutex<HashMap<Uuid, DocumentAwareness>>,
+    max_age: Duration,
+}
+
+impl Default for AwarenessStore {
+    fn default() -> Self {
+        Self::new(AWARENESS_MAX_AGE)
+    }
+}
+
+impl AwarenessStore {
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            documents: Mutex::new(HashMap::new()),
+            max_age,
+        }
+    }
+
+    pub fn join(&self, document_id: Uuid, user: UserState) {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        documents
+            .entry(document_id)
+            .or_insert_with(|| DocumentAwareness::new(document_id))
+            .add_user(user);
+    }
+
+    pub fn leave(&self, document_id: Uuid, user_id: Uuid) {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(awareness) = documents.get_mut(&document_id) {
+            awareness.remove_user(user_id);
+            if awareness.users.is_empty() {
+                documents.remove(&document_id);
+            }
+        }
+    }
+
+    /// Record activity from a participant
+    pub fn touch(&self, document_id: Uuid, user_id: Uuid) {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(awareness) = documents.get_mut(&document_id) {
+            awareness.touch(user_id);
+        }
+    }
+
+    pub fn update_cursor(&self, document_id: Uuid, user_id: Uuid, cursor: CursorPosition) {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(awareness) = documents.get_mut(&document_id) {
+            awareness.update_cursor(user_id, cursor);
+        }
+    }
+
+    /// Remove the document's inactive participants, returning their ids
+    pub fn prune_inactive(&self, document_id: Uuid) -> Vec<Uuid> {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        Self::prune_document(&mut documents, document_id, self.max_age)
+    }
+
+    /// The document's active participants; inactive ones are pruned first
+    pub fn participants(&self, document_id: Uuid) -> Vec<UserState> {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        Self::prune_document(&mut documents, document_id, self.max_age);
+        documents
+            .get(&document_id)
+            .map(|awareness| awareness.get_users().into_iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn prune_document(
+        documents: &mut HashMap<Uuid, DocumentAwareness>,
+        document_id: Uuid,
+        max_age: Duration,
+    ) -> Vec<Uuid> {
+        let Some(awareness) = documents.get_mut(&document_id) else {
+            return Vec::new();
+        };
+        let pruned = awareness.prune_inactive(max_age);
+        if awareness.users.is_empty() {
+            documents.remove(&document_id);
+        }
+        pruned
+    }
+}
+
+pub static AWARENESS_STORE: once_cell::sync::Lazy<AwarenessStore> = once_cell::sync::Lazy::new(AwarenessStore::default);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +246,85 @@ mod tests {
         let doc_presence = store.get_document_presence(document_id);
         assert_eq!(doc_presence.len(), 3);
     }
+
+    fn backdated_user(seconds_ago: i64) -> UserState {
+        UserState {
+            user_id: Uuid::new_v4(),
+            display_name: "Participant".to_string(),
+            color: "#00FF00".to_string(),
+            cursor: None,
+            last_active: Utc::now() - chrono::Duration::seconds(seconds_ago),
+        }
+    }
+
+    #[test]
+    fn test_prune_inactive_removes_only_stale_users() {
+        let mut awareness = DocumentAwareness::new(Uuid::new_v4());
+        let fresh = backdated_user(5);
+        let stale = backdated_user(120);
+        let older = backdated_user(3600);
+        // Insert directly so the backdated timestamps are kept
+        for user in [&fresh, &stale, &older] {
+            awareness.users.insert(user.user_id, user.clone());
+        }
+
+        let mut pruned = awareness.prune_inactive(Duration::from_secs(60));
+        pruned.sort();
+        let mut expected = vec![stale.user_id, older.user_id];
+        expected.sort();
+
+        assert_eq!(pruned, expected);
+        assert_eq!(awareness.users.len(), 1);
+        assert!(awareness.users.contains_key(&fresh.user_id));
+        assert!(awareness.prune_inactive(Duration::from_secs(60)).is_empty());
+    }
+
+    #[test]
+    fn test_add_user_and_cursor_updates_refresh_activity() {
+        let mut awareness = DocumentAwareness::new(Uuid::new_v4());
+        let joined = backdated_user(600);
+        let moved = backdated_user(5);
+        awareness.add_user(joined.clone());
+        awareness.users.insert(moved.user_id, UserState {
+            last_active: Utc::now() - chrono::Duration::seconds(600),
+            ..moved.clone()
+        });
+        awareness.update_cursor(
+            moved.user_id,
+            CursorPosition {
+                x: 1.0,
+                y: 2.0,
+                selection_start: None,
+                selection_end: None,
+            },
+        );
+
+        assert!(awareness.prune_inactive(Duration::from_secs(60)).is_empty());
+        assert_eq!(awareness.users.len(), 2);
+    }
+
+    #[test]
+    fn test_participants_query_prunes_stale_users() {
+        let store = AwarenessStore::new(Duration::from_secs(60));
+        let document_id = Uuid::new_v4();
+        let active = backdated_user(0);
+        store.join(document_id, active.clone());
+        store.join(document_id, backdated_user(0));
+
+        // Backdate the second participant as if their client dropped
+        {
+            let mut documents = store.documents.lock().unwrap();
+            let awareness = documents.get_mut(&document_id).unwrap();
+            for user in awareness.users.values_mut() {
+                if user.user_id != active.user_id {
+                    user.last_active = Utc::now() - chrono::Duration::seconds(300);
+                }
+            }
+        }
+
+        let participants = store.participants(document_id);
+        assert_eq!(participants.len(), 1);
+        assert_eq!(participants[0].user_id, active.user_id);
+        assert!(store.prune_inactive(document_id).is_empty());
+    }
 }
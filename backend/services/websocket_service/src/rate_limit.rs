@@ -0,0 +1,151 @@
+//! Per-document, per-user rate limiting for inbound updates
+//!
+//! Each (document, user) pair gets a token bucket. `Sync` and
+//! `DocumentUpdate` messages spend a token; when the bucket is empty the
+//! message is dropped and the sender gets a `RATE_LIMITED` error telling it
+//! how long to back off. Buckets are independent, so one runaway client does
+//! not slow down co-editors on the same document.
+
+use crate::models::{MessageType, ServerMessage};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Default sustained updates per second for one user on one document
+pub const DEFAULT_UPDATES_PER_SEC: f64 = 50.0;
+
+/// Default burst size for one user on one document
+pub const DEFAULT_UPDATE_BURST: f64 = 100.0;
+
+/// Error code sent to clients whose updates are being dropped
+pub const RATE_LIMITED_CODE: &str = "RATE_LIMITED";
+
+/// Buckets tracked before idle, fully refilled ones are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token-bucket limiter keyed by (document, user)
+pub struct UpdateRateLimiter {
+    buckets: Mutex<HashMap<(Uuid, Uuid), Bucket>>,
+    per_sec: f64,
+    burst: f64,
+}
+
+impl UpdateRateLimiter {
+    pub fn new(per_sec: f64, burst: f64) -> Self {
+        Self {
+            buckets: Mutex::new(HashMap::new()),
+            per_sec,
+            burst: burst.max(1.0),
+        }
+    }
+
+    /// Read `WS_UPDATE_RATE_PER_SEC` and `WS_UPDATE_BURST`, falling back to
+    /// the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let per_sec = std::env::var("WS_UPDATE_RATE_PER_SEC")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(DEFAULT_UPDATES_PER_SEC);
+        let burst = std::env::var("WS_UPDATE_BURST")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v >= 1.0)
+            .unwrap_or(DEFAULT_UPDATE_BURST);
+
+        Self::new(per_sec, burst)
+    }
+
+    /// Spend one token for `user_id` on `document_id`
+    ///
+    /// Returns how long the client should wait when the bucket is empty.
+    pub fn check(&self, document_id: Uuid, user_id: Uuid) -> Result<(), Duration> {
+        self.check_at(document_id, user_id, Instant::now())
+    }
+
+    fn check_at(&self, document_id: Uuid, user_id: Uuid, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            let (per_sec, burst) = (self.per_sec, self.burst);
+            buckets.retain(|_, b| b.tokens + now.duration_since(b.updated_at).as_secs_f64() * per_sec < burst);
+        }
+
+        let bucket = buckets.entry((document_id, user_id)).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_sec).min(self.burst);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if self.per_sec > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_sec))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+}
+
+/// Backpressure signal for a client whose update was dropped
+pub fn rate_limited_message(document_id: Uuid, retry_after: Duration) -> ServerMessage {
+    ServerMessage {
+        type_: MessageType::Error,
+        document_id,
+        payload: serde_json::json!({
+            "code": RATE_LIMITED_CODE,
+            "message": "Too many updates for this document; slow down",
+            "retry_after_ms": u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX),
+        }),
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+/// Global limiter shared by every WebSocket session on this instance
+pub static UPDATE_RATE_LIMITER: once_cell::sync::Lazy<UpdateRateLimiter> =
+    once_cell::sync::Lazy::new(UpdateRateLimiter::from_env);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_empties_after_burst_and_refills() {
+        let limiter = UpdateRateLimiter::new(10.0, 3.0);
+        let (document_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(document_id, user_id, start).is_ok());
+        }
+        let retry_after = limiter.check_at(document_id, user_id, start).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(100));
+
+        // One token back after 100ms at 10/s
+        assert!(limiter.check_at(document_id, user_id, start + Duration::from_millis(100)).is_ok());
+        assert!(limiter.check_at(document_id, user_id, start + Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_buckets_are_independent_per_user_and_document() {
+        let limiter = UpdateRateLimiter::new(1.0, 1.0);
+        let (document_id, user_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let now = Instant::now();
+
+        assert!(limiter.check_at(document_id, user_id, now).is_ok());
+        assert!(limiter.check_at(document_id, user_id, now).is_err());
+
+        assert!(limiter.check_at(document_id, Uuid::new_v4(), now).is_ok());
+        assert!(limiter.check_at(Uuid::new_v4(), user_id, now).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+//! Graceful shutdown of WebSocket sessions
+//!
+//! On shutdown the server triggers [`WS_SHUTDOWN`]. Every
+//! `DocumentWsHandler` subscribes when it starts, and on the signal sends
+//! its client a `UserLeave` notice, closes the socket with
+//! `1012 Service Restart` and removes itself from [`SESSION_STORE`], so
+//! clients reconnect to another instance instead of seeing the connection
+//! drop mid-edit.
+//!
+//! [`SESSION_STORE`]: crate::SESSION_STORE
+
+use crate::models::{MessageType, ServerMessage};
+use crate::{SessionStore, WebSocketSession};
+use chrono::Utc;
+use serde_json::json;
+use tokio::sync::watch;
+
+/// Close reason sent to clients when the server shuts down
+pub const SERVER_SHUTDOWN_CODE: &str = "SERVER_SHUTDOWN";
+
+/// One-shot signal telling WebSocket actors to close their sessions
+pub struct ShutdownSignal {
+    tx: watch::Sender<bool>,
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx }
+    }
+
+    /// Receiver that observes the signal; already-triggered signals are
+    /// seen immediately
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.tx.subscribe()
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Tell every subscribed session in `store` to close
+    ///
+    /// Returns how many sessions were active when the signal fired.
+    pub fn begin_shutdown(&self, store: &SessionStore) -> usize {
+        let active = store.session_count();
+        self.tx.send_replace(true);
+        tracing::info!("Closing {} WebSocket sessions for shutdown", active);
+        active
+    }
+}
+
+/// Notice sent to a session's client before its socket is closed
+pub fn shutdown_notice(session: &WebSocketSession) -> ServerMessage {
+    ServerMessage {
+        type_: MessageType::UserLeave,
+        document_id: session.document_id,
+        payload: json!({
+            "user_id": session.user_id.to_string(),
+            "reason": SERVER_SHUTDOWN_CODE,
+        }),
+        timestamp: Utc::now(),
+    }
+}
+
+pub static WS_SHUTDOWN: once_cell::sync::Lazy<ShutdownSignal> = once_cell::sync::Lazy::new(ShutdownSignal::new);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_begin_shutdown_signals_every_stored_session() {
+        let store = SessionStore::new();
+        let document_id = Uuid::new_v4();
+        let first = WebSocketSession::new(document_id, Uuid::new_v4(), "Ada".into(), "#fff".into());
+        let second = WebSocketSession::new(Uuid::new_v4(), Uuid::new_v4(), "Bob".into(), "#000".into());
+        store.add_session(first.clone());
+        store.add_session(second);
+
+        let signal = ShutdownSignal::new();
+        let mut before = signal.subscribe();
+        assert!(!signal.is_triggered());
+
+        assert_eq!(signal.begin_shutdown(&store), 2);
+        assert!(signal.is_triggered());
+        before.wait_for(|closing| *closing).await.expect("Signal dropped");
+
+        // Sessions that subscribe late still see the signal
+        let mut late = signal.subscribe();
+        late.wait_for(|closing| *closing).await.expect("Signal dropped");
+
+        let notice = shutdown_notice(&first);
+        assert_eq!(notice.type_, MessageType::UserLeave);
+        assert_eq!(notice.document_id, document_id);
+        assert_eq!(notice.payload["user_id"], first.user_id.to_string());
+        assert_eq!(notice.payload["reason"], SERVER_SHUTDOWN_CODE);
+    }
+}
@@ -5,8 +5,8 @@ use uuid::Uuid;
 
 use websocket_service::{
     AwarenessMessage, ClientMessage, ConnectionInfo, CursorPosition, DocumentAwareness, DocumentState, ErrorResponse,
-    MessageType, ServerMessage, SessionStore, SyncMessage, UserPresence, UserState, WebSocketMessage,
-    WebSocketMessageType, WebSocketSession,
+    MessageType, ServerMessage, SessionLimitExceeded, SessionStore, SyncMessage, UserPresence, UserState,
+    WebSocketMessage, WebSocketMessageType, WebSocketSession,
 };
 
 // ========================================
@@ -211,6 +211,144 @@ fn test_session_store_get_document_sessions() {
     assert_eq!(sessions.len(), 3);
 }
 
+#[test]
+fn test_session_store_concurrent_document_lookups() {
+    let store = std::sync::Arc::new(SessionStore::new());
+    let document_id = Uuid::new_v4();
+    for i in 0..3 {
+        store.add_session(WebSocketSession::new(
+            document_id,
+            Uuid::new_v4(),
+            format!("U{}", i),
+            "#FFF".to_string(),
+        ));
+    }
+
+    // Many readers plus a writer churning sessions on another document
+    let readers: Vec<_> = (0..16)
+        .map(|_| {
+            let store = store.clone();
+            std::thread::spawn(move || {
+                for _ in 0..1_000 {
+                    assert_eq!(store.get_document_sessions(document_id).len(), 3);
+                }
+            })
+        })
+        .collect();
+    let writer = {
+        let store = store.clone();
+        std::thread::spawn(move || {
+            for _ in 0..200 {
+                let session = WebSocketSession::new(Uuid::new_v4(), Uuid::new_v4(), "W".to_string(), "#FFF".to_string());
+                let session_id = session.id;
+                store.add_session(session);
+                store.remove_session(session_id);
+            }
+        })
+    };
+
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    writer.join().unwrap();
+    assert_eq!(store.session_count(), 3);
+}
+
+#[test]
+fn test_session_store_allows_sessions_up_to_limit() {
+    let store = SessionStore::with_max_sessions_per_user(2);
+    let user_id = Uuid::new_v4();
+    for _ in 0..2 {
+        let session = WebSocketSession::new(Uuid::new_v4(), user_id, "Test".to_string(), "#FFF".to_string());
+        assert!(store.try_add_session(session).is_ok());
+    }
+    assert_eq!(store.get_user_sessions(user_id).len(), 2);
+    assert!(!store.has_capacity_for(user_id));
+}
+
+#[test]
+fn test_session_store_rejects_session_beyond_limit() {
+    let store = SessionStore::with_max_sessions_per_user(2);
+    let user_id = Uuid::new_v4();
+    for _ in 0..2 {
+        store
+            .try_add_session(WebSocketSession::new(
+                Uuid::new_v4(),
+                user_id,
+                "Test".to_string(),
+                "#FFF".to_string(),
+            ))
+            .unwrap();
+    }
+
+    let extra = WebSocketSession::new(Uuid::new_v4(), user_id, "Test".to_string(), "#FFF".to_string());
+    let extra_id = extra.id;
+    let err = store.try_add_session(extra).unwrap_err();
+    assert_eq!(err, SessionLimitExceeded { user_id, limit: 2 });
+    assert!(store.get_session(extra_id).is_none());
+    assert_eq!(store.get_user_sessions(user_id).len(), 2);
+}
+
+#[test]
+fn test_session_store_closing_session_frees_slot() {
+    let store = SessionStore::with_max_sessions_per_user(1);
+    let user_id = Uuid::new_v4();
+    let first = WebSocketSession::new(Uuid::new_v4(), user_id, "Test".to_string(), "#FFF".to_string());
+    let first_id = first.id;
+    store.try_add_session(first).unwrap();
+
+    let second = WebSocketSession::new(Uuid::new_v4(), user_id, "Test".to_string(), "#FFF".to_string());
+    assert!(store.try_add_session(second.clone()).is_err());
+
+    store.remove_session(first_id);
+    assert!(store.has_capacity_for(user_id));
+    assert!(store.try_add_session(second).is_ok());
+}
+
+#[test]
+fn test_session_store_limit_is_per_user() {
+    let store = SessionStore::with_max_sessions_per_user(1);
+    let user_a = Uuid::new_v4();
+    let user_b = Uuid::new_v4();
+    store
+        .try_add_session(WebSocketSession::new(
+            Uuid::new_v4(),
+            user_a,
+            "A".to_string(),
+            "#FFF".to_string(),
+        ))
+        .unwrap();
+
+    assert!(!store.has_capacity_for(user_a));
+    assert!(store.has_capacity_for(user_b));
+    assert!(store
+        .try_add_session(WebSocketSession::new(
+            Uuid::new_v4(),
+            user_b,
+            "B".to_string(),
+            "#FFF".to_string()
+        ))
+        .is_ok());
+}
+
+#[test]
+fn test_session_store_without_limit_is_unbounded() {
+    let store = SessionStore::new();
+    let user_id = Uuid::new_v4();
+    for _ in 0..50 {
+        store
+            .try_add_session(WebSocketSession::new(
+                Uuid::new_v4(),
+                user_id,
+                "Test".to_string(),
+                "#FFF".to_string(),
+            ))
+            .unwrap();
+    }
+    assert!(store.max_sessions_per_user().is_none());
+    assert_eq!(store.get_user_sessions(user_id).len(), 50);
+}
+
 // ========================================
 // Document Awareness Tests
 // ========================================
@@ -1,2 +1,3 @@
 pub mod service;
 pub mod error;
+pub mod rate_limit;
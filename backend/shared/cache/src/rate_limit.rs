@@ -0,0 +1,363 @@
+//! Token-bucket rate limiting
+//!
+//! Every key (usually a user id) gets a bucket holding up to `burst` tokens
+//! that refills at `per_sec` tokens a second. A request spends one token;
+//! when the bucket is empty it is refused with the time until the next token
+//! arrives. Buckets live in Redis when a connection is available, updated by
+//! one script so concurrent requests on any instance can't overspend; if
+//! Redis is not configured or a command fails, an in-memory map is used.
+//!
+//! Each limiter has its own key prefix, so endpoints with different limits
+//! can share one Redis without their buckets mixing.
+//!
+//! `take` also reports what is left in the bucket, which
+//! `rate_limit_headers` turns into `X-RateLimit-*` headers so clients can
+//! see their remaining budget.
+
+use redis::aio::MultiplexedConnection;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets kept in memory before idle, fully refilled ones are pruned
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Refill and spend one token in a single step, using the Redis clock so
+/// every instance agrees on elapsed time. Returns
+/// `{allowed, wait_ms, tokens_milli}`; Redis truncates numbers to integers,
+/// so the tokens left are scaled by a thousand.
+const TAKE_SCRIPT: &str = r#"
+local rate = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or burst
+local ts = tonumber(state[2]) or now
+tokens = math.min(burst, tokens + math.max(0, now - ts) * rate)
+
+local allowed = 0
+local wait_ms = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+else
+    wait_ms = math.ceil((1 - tokens) / rate * 1000)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / rate * 1000) + 1000)
+return {allowed, wait_ms, math.floor(tokens * 1000)}
+"#;
+
+/// Sustained rate and burst size of a limiter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    /// Tokens added per second
+    pub per_sec: f64,
+    /// Most tokens a bucket holds, i.e. requests allowed back to back
+    pub burst: f64,
+}
+
+impl RateLimitConfig {
+    /// Read the rate and burst from the given variables, keeping `defaults`
+    /// for missing or invalid values
+    pub fn from_env(rate_var: &str, burst_var: &str, defaults: Self) -> Self {
+        let per_sec = std::env::var(rate_var)
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(defaults.per_sec);
+        let burst = std::env::var(burst_var)
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v >= 1.0)
+            .unwrap_or(defaults.burst);
+
+        Self { per_sec, burst }
+    }
+}
+
+/// Result of spending a token, with the state of the bucket afterwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Requests the bucket allows back to back
+    pub limit: u64,
+    /// Whole tokens left after this request
+    pub remaining: u64,
+    /// Time until the bucket is full again
+    pub reset: Duration,
+    /// Time until the next token when the request was refused
+    pub retry_after: Duration,
+}
+
+impl RateLimitDecision {
+    fn new(config: &RateLimitConfig, tokens: f64, refused_for: Option<Duration>) -> Self {
+        let tokens = tokens.clamp(0.0, config.burst);
+        Self {
+            allowed: refused_for.is_none(),
+            limit: config.burst.floor() as u64,
+            remaining: tokens.floor() as u64,
+            reset: Duration::from_secs_f64((config.burst - tokens) / config.per_sec),
+            retry_after: refused_for.unwrap_or(Duration::ZERO),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    /// Tokens after refilling for the time since the last update
+    fn refilled(&self, now: Instant, config: &RateLimitConfig) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        (self.tokens + elapsed * config.per_sec).min(config.burst)
+    }
+
+    /// Refill, then spend a token or report how long until one is available
+    fn take(&mut self, now: Instant, config: &RateLimitConfig) -> Result<(), Duration> {
+        self.tokens = self.refilled(now, config);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / config.per_sec))
+        }
+    }
+}
+
+/// Token-bucket limiter with a Redis store and in-memory fallback
+pub struct TokenBucketLimiter {
+    prefix: String,
+    config: RateLimitConfig,
+    redis: Option<MultiplexedConnection>,
+    fallback: Mutex<HashMap<String, Bucket>>,
+}
+
+impl TokenBucketLimiter {
+    /// Create an in-memory limiter whose keys start with `prefix`
+    pub fn new(prefix: &str, config: RateLimitConfig) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            config,
+            redis: None,
+            fallback: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a limiter backed by Redis, using in-memory buckets if the
+    /// connection cannot be established
+    pub async fn connect(prefix: &str, config: RateLimitConfig, redis_url: Option<&str>) -> Self {
+        let redis = match redis_url.filter(|url| !url.is_empty()) {
+            Some(url) => match redis::Client::open(url) {
+                Ok(client) => match client.get_multiplexed_async_connection().await {
+                    Ok(conn) => Some(conn),
+                    Err(e) => {
+                        tracing::warn!("Failed to connect to Redis for rate limiting, using in-memory: {}", e);
+                        None
+                    },
+                },
+                Err(e) => {
+                    tracing::warn!("Invalid Redis URL for rate limiting, using in-memory: {}", e);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        Self {
+            redis,
+            ..Self::new(prefix, config)
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Spend one token from `key`'s bucket
+    ///
+    /// Returns how long the caller should wait when the bucket is empty.
+    pub async fn check(&self, key: &str) -> Result<(), Duration> {
+        let decision = self.take(key).await;
+        if decision.allowed {
+            Ok(())
+        } else {
+            Err(decision.retry_after)
+        }
+    }
+
+    /// Spend one token from `key`'s bucket and report what is left
+    pub async fn take(&self, key: &str) -> RateLimitDecision {
+        let key = format!("{}{}", self.prefix, key);
+
+        if let Some(conn) = &self.redis {
+            let mut conn = conn.clone();
+            let result: redis::RedisResult<(i64, i64, i64)> = redis::Script::new(TAKE_SCRIPT)
+                .key(&key)
+                .arg(self.config.per_sec)
+                .arg(self.config.burst)
+                .invoke_async(&mut conn)
+                .await;
+            match result {
+                Ok((allowed, wait_ms, tokens_milli)) => {
+                    let refused_for = (allowed != 1).then(|| Duration::from_millis(wait_ms.max(1) as u64));
+                    return RateLimitDecision::new(&self.config, tokens_milli as f64 / 1000.0, refused_for);
+                },
+                Err(e) => tracing::warn!("Redis error checking rate limit: {}", e),
+            }
+        }
+
+        self.take_in_memory(&key, Instant::now())
+    }
+
+    fn take_in_memory(&self, key: &str, now: Instant) -> RateLimitDecision {
+        let mut buckets = self.fallback.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            let config = self.config;
+            buckets.retain(|_, bucket| bucket.refilled(now, &config) < config.burst);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.config.burst,
+            updated_at: now,
+        });
+        let refused_for = bucket.take(now, &self.config).err();
+        RateLimitDecision::new(&self.config, bucket.tokens, refused_for)
+    }
+}
+
+/// Whole seconds for a `Retry-After` header, never less than one
+pub fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0 || wait.is_zero())
+}
+
+/// `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset`
+/// (whole seconds until the bucket is full again) for a decision
+pub fn rate_limit_headers(decision: &RateLimitDecision) -> [(&'static str, String); 3] {
+    let reset_secs = decision.reset.as_secs() + u64::from(decision.reset.subsec_nanos() > 0);
+    [
+        ("x-ratelimit-limit", decision.limit.to_string()),
+        ("x-ratelimit-remaining", decision.remaining.to_string()),
+        ("x-ratelimit-reset", reset_secs.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(per_sec: f64, burst: f64) -> RateLimitConfig {
+        RateLimitConfig { per_sec, burst }
+    }
+
+    #[test]
+    fn test_bucket_refills_at_rate_up_to_burst() {
+        let config = config(2.0, 5.0);
+        let start = Instant::now();
+        let bucket = Bucket {
+            tokens: 1.0,
+            updated_at: start,
+        };
+
+        assert_eq!(bucket.refilled(start, &config), 1.0);
+        assert_eq!(bucket.refilled(start + Duration::from_millis(500), &config), 2.0);
+        assert_eq!(bucket.refilled(start + Duration::from_secs(1), &config), 3.0);
+        // Capped at the burst size however long it sat idle
+        assert_eq!(bucket.refilled(start + Duration::from_secs(60), &config), 5.0);
+    }
+
+    #[test]
+    fn test_take_reports_wait_until_next_token() {
+        let config = config(4.0, 2.0);
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 2.0,
+            updated_at: start,
+        };
+
+        assert!(bucket.take(start, &config).is_ok());
+        assert!(bucket.take(start, &config).is_ok());
+        assert_eq!(bucket.take(start, &config), Err(Duration::from_millis(250)));
+
+        // Half a token after 125ms, so half the wait is left
+        let later = start + Duration::from_millis(125);
+        assert_eq!(bucket.take(later, &config), Err(Duration::from_millis(125)));
+        assert!(bucket.take(start + Duration::from_millis(250), &config).is_ok());
+    }
+
+    #[test]
+    fn test_in_memory_buckets_are_per_key() {
+        let limiter = TokenBucketLimiter::new("test:", config(1.0, 1.0));
+        let now = Instant::now();
+
+        assert!(limiter.take_in_memory("alice", now).allowed);
+        assert!(!limiter.take_in_memory("alice", now).allowed);
+        assert!(limiter.take_in_memory("bob", now).allowed);
+    }
+
+    #[test]
+    fn test_decision_reports_remaining_and_reset() {
+        let limiter = TokenBucketLimiter::new("test:", config(0.5, 3.0));
+        let now = Instant::now();
+
+        let first = limiter.take_in_memory("alice", now);
+        assert_eq!((first.limit, first.remaining), (3, 2));
+        assert_eq!(first.reset, Duration::from_secs(2));
+        assert_eq!(limiter.take_in_memory("alice", now).remaining, 1);
+        assert_eq!(limiter.take_in_memory("alice", now).remaining, 0);
+
+        let refused = limiter.take_in_memory("alice", now);
+        assert!(!refused.allowed);
+        assert_eq!(refused.remaining, 0);
+        assert_eq!(refused.reset, Duration::from_secs(6));
+        assert_eq!(refused.retry_after, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let decision = RateLimitDecision {
+            allowed: true,
+            limit: 10,
+            remaining: 7,
+            reset: Duration::from_millis(1500),
+            retry_after: Duration::ZERO,
+        };
+
+        assert_eq!(
+            rate_limit_headers(&decision),
+            [
+                ("x-ratelimit-limit", "10".to_string()),
+                ("x-ratelimit-remaining", "7".to_string()),
+                ("x-ratelimit-reset", "2".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_without_redis_uses_memory() {
+        let limiter = TokenBucketLimiter::connect("test:", config(1.0, 2.0), None).await;
+
+        assert!(limiter.check("user").await.is_ok());
+        assert!(limiter.check("user").await.is_ok());
+        let wait = limiter.check("user").await.unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+        assert_eq!(retry_after_secs(wait), 1);
+    }
+
+    #[test]
+    fn test_retry_after_rounds_up() {
+        assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after_secs(Duration::from_secs(2)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(2100)), 3);
+        assert_eq!(retry_after_secs(Duration::ZERO), 1);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod connection;
+pub mod soft_delete;
 pub use connection::DatabaseConnection;
+pub use soft_delete::SoftDeletable;
@@ -0,0 +1,132 @@
+//! Shared soft-delete lifecycle
+//!
+//! Files, documents and spaces are soft deleted by setting a flag and a
+//! timestamp column, under different names per table. Implementing
+//! [`SoftDeletable`] for an entity describes those columns once, and the
+//! helpers here give every entity the same lifecycle:
+//!
+//! - [`soft_delete`] hides a live row and records when it was deleted
+//! - [`restore`] brings a deleted row back and clears the timestamp
+//! - [`purge`] removes a row for good, but only once it is soft deleted
+
+use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+
+/// An entity stored in a table with soft-delete columns
+///
+/// The names are interpolated into SQL, so they must be static identifiers.
+pub trait SoftDeletable {
+    /// Table holding the entity, keyed by a UUID `id` column
+    const TABLE: &'static str;
+    /// Boolean column set while the row is deleted
+    const DELETED_FLAG: &'static str;
+    /// Timestamp column recording when the row was deleted
+    const DELETED_AT: &'static str;
+}
+
+fn soft_delete_sql<T: SoftDeletable>() -> String {
+    format!(
+        "UPDATE {table} SET {flag} = true, {at} = NOW() WHERE id = $1 AND {flag} = false",
+        table = T::TABLE,
+        flag = T::DELETED_FLAG,
+        at = T::DELETED_AT
+    )
+}
+
+fn restore_sql<T: SoftDeletable>() -> String {
+    format!(
+        "UPDATE {table} SET {flag} = false, {at} = NULL WHERE id = $1 AND {flag} = true",
+        table = T::TABLE,
+        flag = T::DELETED_FLAG,
+        at = T::DELETED_AT
+    )
+}
+
+fn purge_sql<T: SoftDeletable>() -> String {
+    format!(
+        "DELETE FROM {table} WHERE id = $1 AND {flag} = true",
+        table = T::TABLE,
+        flag = T::DELETED_FLAG
+    )
+}
+
+fn purge_before_sql<T: SoftDeletable>() -> String {
+    format!(
+        "DELETE FROM {table} WHERE {flag} = true AND {at} < $1",
+        table = T::TABLE,
+        flag = T::DELETED_FLAG,
+        at = T::DELETED_AT
+    )
+}
+
+fn is_deleted_sql<T: SoftDeletable>() -> String {
+    format!(
+        "SELECT {flag} FROM {table} WHERE id = $1",
+        table = T::TABLE,
+        flag = T::DELETED_FLAG
+    )
+}
+
+/// Soft delete a live row; false if it is missing or already deleted
+pub async fn soft_delete<T: SoftDeletable>(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&soft_delete_sql::<T>()).bind(id).execute(pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Restore a soft-deleted row; false if it is missing or not deleted
+pub async fn restore<T: SoftDeletable>(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&restore_sql::<T>()).bind(id).execute(pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Permanently remove a soft-deleted row; false if it is missing or still live
+pub async fn purge<T: SoftDeletable>(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(&purge_sql::<T>()).bind(id).execute(pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Permanently remove rows soft deleted before `cutoff`, returning how many
+pub async fn purge_deleted_before<T: SoftDeletable>(pool: &PgPool, cutoff: NaiveDateTime) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&purge_before_sql::<T>()).bind(cutoff).execute(pool).await?;
+    Ok(result.rows_affected())
+}
+
+/// Whether the row is soft deleted, or `None` if it does not exist
+pub async fn is_deleted<T: SoftDeletable>(pool: &PgPool, id: Uuid) -> Result<Option<bool>, sqlx::Error> {
+    sqlx::query_scalar(&is_deleted_sql::<T>()).bind(id).fetch_optional(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Widget;
+
+    impl SoftDeletable for Widget {
+        const TABLE: &'static str = "widgets";
+        const DELETED_FLAG: &'static str = "is_removed";
+        const DELETED_AT: &'static str = "removed_at";
+    }
+
+    #[test]
+    fn test_lifecycle_sql_uses_entity_columns() {
+        assert_eq!(
+            soft_delete_sql::<Widget>(),
+            "UPDATE widgets SET is_removed = true, removed_at = NOW() WHERE id = $1 AND is_removed = false"
+        );
+        assert_eq!(
+            restore_sql::<Widget>(),
+            "UPDATE widgets SET is_removed = false, removed_at = NULL WHERE id = $1 AND is_removed = true"
+        );
+    }
+
+    #[test]
+    fn test_purge_sql_only_targets_deleted_rows() {
+        assert_eq!(purge_sql::<Widget>(), "DELETE FROM widgets WHERE id = $1 AND is_removed = true");
+        assert_eq!(
+            purge_before_sql::<Widget>(),
+            "DELETE FROM widgets WHERE is_removed = true AND removed_at < $1"
+        );
+    }
+}
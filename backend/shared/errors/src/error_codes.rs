@@ -35,7 +35,7 @@ impl From<&AppError> for ErrorCode {
     fn from(err: &AppError) -> Self {
         match err {
             AppError::DatabaseError(_) => ErrorCode::DatabaseError,
-            AppError::ValidationError(_) => ErrorCode::ValidationError,
+            AppError::ValidationError(_) | AppError::InvalidFields(_) => ErrorCode::ValidationError,
             AppError::AuthenticationError(_) => ErrorCode::AuthenticationError,
             AppError::AuthorizationError(_) => ErrorCode::AuthorizationError,
             AppError::NotFoundError(_) => ErrorCode::NotFoundError,
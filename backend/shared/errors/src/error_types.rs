@@ -8,6 +8,10 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     ValidationError(String),
 
+    /// Failed `validator` rules, reported field by field
+    #[error("Validation error: {0}")]
+    InvalidFields(#[from] validator::ValidationErrors),
+
     #[error("Authentication failed: {0}")]
     AuthenticationError(String),
 
@@ -51,7 +55,7 @@ impl actix_web::ResponseError for AppError {
     fn status_code(&self) -> actix_web::http::StatusCode {
         match self {
             AppError::DatabaseError(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
-            AppError::ValidationError(_) => actix_web::http::StatusCode::BAD_REQUEST,
+            AppError::ValidationError(_) | AppError::InvalidFields(_) => actix_web::http::StatusCode::BAD_REQUEST,
             AppError::AuthenticationError(_) => actix_web::http::StatusCode::UNAUTHORIZED,
             AppError::AuthorizationError(_) => actix_web::http::StatusCode::FORBIDDEN,
             AppError::NotFoundError(_) => actix_web::http::StatusCode::NOT_FOUND,
@@ -64,12 +68,16 @@ impl actix_web::ResponseError for AppError {
     }
 
     fn error_response(&self) -> actix_web::HttpResponse {
-        actix_web::HttpResponse::build(self.status_code())
-            .json(serde_json::json!({
-                "error": ErrorCode::from(self).to_string(),
-                "message": self.to_string()
-            }))
+        let mut body = serde_json::json!({
+            "error": ErrorCode::from(self).to_string(),
+            "message": self.to_string()
+        });
+        if let AppError::InvalidFields(errors) = self {
+            body["details"] = validation_errors_to_json(errors);
+        }
+        actix_web::HttpResponse::build(self.status_code()).json(body)
     }
 }
 
 use super::error_codes::ErrorCode;
+use super::validation::validation_errors_to_json;
@@ -3,3 +3,6 @@ pub use error_types::AppError;
 
 pub mod error_codes;
 pub use error_codes::ErrorCode;
+
+pub mod validation;
+pub use validation::validation_errors_to_json;
@@ -0,0 +1,136 @@
+//! Field-keyed validation error output
+//!
+//! `validator` errors are turned into `{ "field": ["message", ...] }` so
+//! clients can show each message next to its input. Nested structs and
+//! lists use dotted and indexed paths (`members[2].role`), and fields are
+//! sorted, so the same input always gives the same output.
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// Messages for every invalid field, keyed by the field's path
+pub fn validation_errors_to_json(errors: &ValidationErrors) -> Value {
+    let mut fields = BTreeMap::new();
+    collect(errors, "", &mut fields);
+
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(field, messages)| (field, Value::from(messages)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn collect(errors: &ValidationErrors, prefix: &str, fields: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields
+                    .entry(path)
+                    .or_default()
+                    .extend(errors.iter().map(error_message));
+            },
+            ValidationErrorsKind::Struct(errors) => collect(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect(errors, &format!("{}[{}]", path, index), fields);
+                }
+            },
+        }
+    }
+}
+
+// The validator's own message when the field declares one, otherwise one
+// built from the rule and its limits
+fn error_message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+    match error.code.as_ref() {
+        "length" => match (param("equal"), param("min"), param("max")) {
+            (Some(equal), _, _) => format!("must have a length of exactly {}", equal),
+            (None, Some(min), Some(max)) => format!("must have a length between {} and {}", min, max),
+            (None, Some(min), None) => format!("must have a length of at least {}", min),
+            (None, None, Some(max)) => format!("must have a length of at most {}", max),
+            (None, None, None) => "has an invalid length".to_string(),
+        },
+        "range" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("must be between {} and {}", min, max),
+            (Some(min), None) => format!("must be at least {}", min),
+            (None, Some(max)) => format!("must be at most {}", max),
+            (None, None) => "is out of range".to_string(),
+        },
+        "email" => "must be a valid email address".to_string(),
+        "url" => "must be a valid URL".to_string(),
+        "required" => "is required".to_string(),
+        "regex" => "has an invalid format".to_string(),
+        code => format!("is invalid ({})", code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Member {
+        #[validate(length(min = 1))]
+        role: String,
+    }
+
+    #[derive(Validate)]
+    struct Invite {
+        #[validate(email, length(max = 10, message = "is too long"))]
+        email: String,
+        #[validate(nested)]
+        members: Vec<Member>,
+    }
+
+    #[test]
+    fn test_fields_and_nested_paths() {
+        let invite = Invite {
+            email: "not-an-email".to_string(),
+            members: vec![
+                Member {
+                    role: "viewer".to_string(),
+                },
+                Member { role: String::new() },
+            ],
+        };
+        let errors = invite.validate().unwrap_err();
+
+        // Messages keep the order the rules are declared in
+        assert_eq!(
+            validation_errors_to_json(&errors),
+            json!({
+                "email": ["must be a valid email address", "is too long"],
+                "members[1].role": ["must have a length of at least 1"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_length_message_uses_limits() {
+        let mut error = ValidationError::new("length");
+        error.add_param("min".into(), &1);
+        error.add_param("max".into(), &200);
+        let mut errors = ValidationErrors::new();
+        errors.add("title", error);
+
+        assert_eq!(
+            validation_errors_to_json(&errors),
+            json!({ "title": ["must have a length between 1 and 200"] })
+        );
+    }
+}
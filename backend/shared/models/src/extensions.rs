@@ -0,0 +1,29 @@
+//! Upload file extensions as configured on a space's allowlist
+
+/// Normalize an upload extension: trimmed, lowercase, without the leading dot
+///
+/// Returns `None` for anything that isn't 1-16 ASCII letters or digits.
+pub fn normalize_extension(extension: &str) -> Option<String> {
+    let extension = extension.trim().trim_start_matches('.').to_ascii_lowercase();
+    let valid = (1..=16).contains(&extension.len()) && extension.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_case_dot_and_whitespace() {
+        assert_eq!(normalize_extension("pdf"), Some("pdf".to_string()));
+        assert_eq!(normalize_extension(" .PNG "), Some("png".to_string()));
+    }
+
+    #[test]
+    fn test_rejects_invalid_extensions() {
+        assert_eq!(normalize_extension(""), None);
+        assert_eq!(normalize_extension("."), None);
+        assert_eq!(normalize_extension("tar.gz"), None);
+        assert_eq!(normalize_extension("a".repeat(17).as_str()), None);
+    }
+}
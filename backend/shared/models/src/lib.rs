@@ -1 +1,4 @@
 pub mod entities;
+pub mod extensions;
+pub mod pagination;
+pub mod request_id;
@@ -0,0 +1,34 @@
+//! Offset pagination metadata shared by list responses
+
+/// Offset of the page after one that started at `offset` and returned
+/// `returned` of `total` rows, or `None` on the last page
+///
+/// An empty page is always treated as the last, so a stale or estimated
+/// `total` can't send clients round the same offset forever.
+pub fn next_offset(offset: i64, returned: usize, total: i64) -> Option<i64> {
+    let next = offset + returned as i64;
+    (returned > 0 && next < total).then_some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_more_pages_until_total_reached() {
+        assert_eq!(next_offset(0, 20, 45), Some(20));
+        assert_eq!(next_offset(20, 20, 45), Some(40));
+        assert_eq!(next_offset(40, 5, 45), None);
+    }
+
+    #[test]
+    fn test_exact_final_page_has_no_more() {
+        assert_eq!(next_offset(0, 20, 20), None);
+        assert_eq!(next_offset(0, 0, 0), None);
+    }
+
+    #[test]
+    fn test_empty_page_ends_pagination() {
+        assert_eq!(next_offset(100, 0, 500), None);
+    }
+}
@@ -0,0 +1,71 @@
+//! Per-request correlation id
+//!
+//! The HTTP middleware stores a `RequestId` in each request's extensions, so
+//! services that need it outside the request's tracing span (a WebSocket
+//! session, say) can read it without depending on the server crate.
+
+use std::fmt;
+use uuid::Uuid;
+
+/// Header a client may send the id in, and that responses echo it in
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied id kept; longer ones are replaced
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation id of one request
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// A fresh random id
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Keep the client's id when it is usable, otherwise generate one
+    ///
+    /// Only printable ASCII without spaces is kept, so a supplied id can't
+    /// break log lines or response headers.
+    pub fn from_header(value: Option<&str>) -> Self {
+        let usable = |id: &&str| {
+            !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+        };
+        match value.map(str::trim).filter(usable) {
+            Some(id) => Self(id.to_string()),
+            None => Self::generate(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supplied_id_is_kept() {
+        assert_eq!(RequestId::from_header(Some("abc-123")).as_str(), "abc-123");
+        assert_eq!(RequestId::from_header(Some("  trace.42  ")).as_str(), "trace.42");
+    }
+
+    #[test]
+    fn test_missing_or_unusable_id_is_replaced() {
+        for value in [None, Some(""), Some("has space"), Some("line\nbreak"), Some("é")] {
+            let id = RequestId::from_header(value);
+            assert!(Uuid::parse_str(id.as_str()).is_ok(), "{:?}", value);
+        }
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+        assert_ne!(RequestId::from_header(Some(&too_long)).as_str(), too_long);
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! This crate provides common security-related functionality including:
 //! - Password hashing and verification
-//! - Password strength validation
+//! - Password strength validation, with an optional common password check
+//! - Password strength scoring (with the `zxcvbn` feature)
 //! - Secure token generation
 //! - Email validation (ReDoS-safe)
 
 pub mod password;
 pub mod email;
+#[cfg(feature = "zxcvbn")]
+pub mod strength;
 
 pub use password::{
     hash_password,
@@ -17,10 +20,13 @@ pub use password::{
     validate_password_strength_with_requirements,
     generate_reset_token,
     generate_url_safe_token,
+    is_common_password,
+    PasswordConfigError,
     PasswordError,
     PasswordRequirements,
     PasswordValidationError,
     DEFAULT_BCRYPT_COST,
+    MIN_PASSWORD_LENGTH_FLOOR,
 };
 
 pub use email::{
@@ -28,3 +34,6 @@ pub use email::{
     EmailValidationError,
     is_valid_email,
 };
+
+#[cfg(feature = "zxcvbn")]
+pub use strength::{password_strength_score, PasswordStrength};
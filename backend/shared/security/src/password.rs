@@ -6,10 +6,37 @@
 use bcrypt::{hash, verify, DEFAULT_COST};
 use rand::Rng;
 use std::fmt;
+use std::sync::OnceLock;
 
 /// Default cost factor for bcrypt hashing
 pub const DEFAULT_BCRYPT_COST: u32 = DEFAULT_COST;
 
+/// Lowest minimum length [`PasswordRequirements::with_min_length`] accepts
+pub const MIN_PASSWORD_LENGTH_FLOOR: usize = 8;
+
+/// Frequently used passwords, one per line, lowercase
+const COMMON_PASSWORDS_LIST: &str = include_str!("common_passwords.txt");
+
+/// The common password list as a sorted slice, built on first use
+fn common_passwords() -> &'static [&'static str] {
+    static SORTED: OnceLock<Vec<&'static str>> = OnceLock::new();
+    SORTED.get_or_init(|| {
+        let mut passwords: Vec<&str> = COMMON_PASSWORDS_LIST
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        passwords.sort_unstable();
+        passwords.dedup();
+        passwords
+    })
+}
+
+/// Whether `password` is on the common password list, ignoring case
+pub fn is_common_password(password: &str) -> bool {
+    common_passwords().binary_search(&password.to_lowercase().as_str()).is_ok()
+}
+
 /// Configuration for password requirements
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PasswordRequirements {
@@ -25,6 +52,8 @@ pub struct PasswordRequirements {
     pub require_digit: bool,
     /// Require at least one special character
     pub require_special_char: bool,
+    /// Reject passwords on the embedded common password list
+    pub reject_common_passwords: bool,
 }
 
 impl Default for PasswordRequirements {
@@ -36,7 +65,119 @@ impl Default for PasswordRequirements {
             require_lowercase: true,
             require_digit: true,
             require_special_char: false,
+            reject_common_passwords: false,
+        }
+    }
+}
+
+impl PasswordRequirements {
+    /// Same rules with a different minimum length
+    ///
+    /// Values below [`MIN_PASSWORD_LENGTH_FLOOR`] are raised to the floor, so
+    /// configuration can tighten the minimum but never weaken it.
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length.max(MIN_PASSWORD_LENGTH_FLOOR);
+        self
+    }
+
+    /// Same rules, also rejecting passwords on the common password list
+    pub fn with_common_password_check(mut self, enabled: bool) -> Self {
+        self.reject_common_passwords = enabled;
+        self
+    }
+
+    /// Read the requirements from the environment
+    ///
+    /// | Variable | Field |
+    /// |---|---|
+    /// | `PASSWORD_MIN_LENGTH` | `min_length`, at least [`MIN_PASSWORD_LENGTH_FLOOR`] |
+    /// | `PASSWORD_MAX_LENGTH` | `max_length`, at least the minimum |
+    /// | `PASSWORD_REQUIRE_UPPERCASE` | `require_uppercase` |
+    /// | `PASSWORD_REQUIRE_LOWERCASE` | `require_lowercase` |
+    /// | `PASSWORD_REQUIRE_NUMBER` | `require_digit` |
+    /// | `PASSWORD_REQUIRE_SPECIAL` | `require_special_char` |
+    /// | `PASSWORD_REJECT_COMMON` | `reject_common_passwords` |
+    ///
+    /// Unset variables keep the defaults. A set but invalid value is an
+    /// error rather than a silent fallback, so a mistyped policy fails at
+    /// startup instead of running weaker than intended.
+    pub fn from_env() -> Result<Self, PasswordConfigError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, PasswordConfigError> {
+        let defaults = Self::default();
+        let min_length = parse_var(&lookup, "PASSWORD_MIN_LENGTH", parse_length)?.unwrap_or(defaults.min_length);
+        if min_length < MIN_PASSWORD_LENGTH_FLOOR {
+            return Err(PasswordConfigError::new(
+                "PASSWORD_MIN_LENGTH",
+                min_length.to_string(),
+                format!("must be at least {}", MIN_PASSWORD_LENGTH_FLOOR),
+            ));
         }
+        let max_length = parse_var(&lookup, "PASSWORD_MAX_LENGTH", parse_length)?.unwrap_or(defaults.max_length);
+        if max_length < min_length {
+            return Err(PasswordConfigError::new(
+                "PASSWORD_MAX_LENGTH",
+                max_length.to_string(),
+                format!("must not be less than the minimum length of {}", min_length),
+            ));
+        }
+
+        Ok(Self {
+            min_length,
+            max_length,
+            require_uppercase: parse_var(&lookup, "PASSWORD_REQUIRE_UPPERCASE", parse_flag)?
+                .unwrap_or(defaults.require_uppercase),
+            require_lowercase: parse_var(&lookup, "PASSWORD_REQUIRE_LOWERCASE", parse_flag)?
+                .unwrap_or(defaults.require_lowercase),
+            require_digit: parse_var(&lookup, "PASSWORD_REQUIRE_NUMBER", parse_flag)?.unwrap_or(defaults.require_digit),
+            require_special_char: parse_var(&lookup, "PASSWORD_REQUIRE_SPECIAL", parse_flag)?
+                .unwrap_or(defaults.require_special_char),
+            reject_common_passwords: parse_var(&lookup, "PASSWORD_REJECT_COMMON", parse_flag)?
+                .unwrap_or(defaults.reject_common_passwords),
+        })
+    }
+}
+
+/// A password requirement set in the environment to an unusable value
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid {var}={value:?}: {reason}")]
+pub struct PasswordConfigError {
+    pub var: &'static str,
+    pub value: String,
+    pub reason: String,
+}
+
+impl PasswordConfigError {
+    fn new(var: &'static str, value: String, reason: String) -> Self {
+        Self { var, value, reason }
+    }
+}
+
+/// Parse `name` if it is set and not blank
+fn parse_var<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &'static str,
+    parse: fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, PasswordConfigError> {
+    match lookup(name) {
+        Some(value) if !value.trim().is_empty() => parse(value.trim())
+            .map(Some)
+            .map_err(|reason| PasswordConfigError::new(name, value, reason)),
+        _ => Ok(None),
+    }
+}
+
+fn parse_length(value: &str) -> Result<usize, String> {
+    value.parse::<usize>().map_err(|_| "expected a whole number".to_string())
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err("expected true or false".to_string()),
     }
 }
 
@@ -69,6 +210,7 @@ pub enum PasswordValidationError {
     MissingLowercase,
     MissingDigit,
     MissingSpecialChar,
+    TooCommon,
 }
 
 impl fmt::Display for PasswordValidationError {
@@ -80,6 +222,7 @@ impl fmt::Display for PasswordValidationError {
             PasswordValidationError::MissingLowercase => write!(f, "Password must contain at least one lowercase letter"),
             PasswordValidationError::MissingDigit => write!(f, "Password must contain at least one number"),
             PasswordValidationError::MissingSpecialChar => write!(f, "Password must contain at least one special character"),
+            PasswordValidationError::TooCommon => write!(f, "Password is too common"),
         }
     }
 }
@@ -206,6 +349,11 @@ pub fn validate_password_strength_with_requirements(
         errors.push(PasswordValidationError::MissingSpecialChar.to_string());
     }
 
+    // Check against the common password list
+    if requirements.reject_common_passwords && is_common_password(password) {
+        errors.push(PasswordValidationError::TooCommon.to_string());
+    }
+
     if errors.is_empty() {
         Ok(())
     } else {
@@ -461,6 +609,7 @@ mod tests {
             require_lowercase: true,
             require_digit: true,
             require_special_char: true,
+            reject_common_passwords: false,
         };
 
         let password = "Short1!"; // Too short
@@ -483,6 +632,7 @@ mod tests {
             require_lowercase: true,
             require_digit: true,
             require_special_char: false,
+            reject_common_passwords: false,
         };
 
         let password = "TestPass123";
@@ -500,6 +650,7 @@ mod tests {
             require_lowercase: false,
             require_digit: false,
             require_special_char: false,
+            reject_common_passwords: false,
         };
 
         let password = "a";
@@ -508,6 +659,113 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_custom_min_length_rejects_shorter_password() {
+        let password = "Abcdefgh123"; // 11 characters
+        assert!(validate_password_strength(password).is_ok());
+
+        let requirements = PasswordRequirements::default().with_min_length(12);
+        let result = validate_password_strength_with_requirements(password, &requirements);
+        assert!(matches!(result, Err(PasswordError::WeakPassword(msg)) if msg.contains("at least 12")));
+
+        assert!(validate_password_strength_with_requirements("Abcdefgh1234", &requirements).is_ok());
+    }
+
+    #[test]
+    fn test_common_password_is_rejected_when_enabled() {
+        let requirements = PasswordRequirements::default().with_common_password_check(true);
+
+        for password in ["Password123!", "PASSWORD123", "Welcome1", "Qwerty123"] {
+            let result = validate_password_strength_with_requirements(password, &requirements);
+            assert!(
+                matches!(&result, Err(PasswordError::WeakPassword(msg)) if msg.contains("too common")),
+                "{} was accepted",
+                password
+            );
+        }
+
+        // The check is opt-in
+        assert!(validate_password_strength("Password123!").is_ok());
+    }
+
+    #[test]
+    fn test_strong_password_passes_common_check() {
+        let requirements = PasswordRequirements::default().with_common_password_check(true);
+
+        assert!(!is_common_password("Tangerine-Orbit-42"));
+        assert!(validate_password_strength_with_requirements("Tangerine-Orbit-42", &requirements).is_ok());
+    }
+
+    #[test]
+    fn test_common_password_list_is_lowercase() {
+        assert!(common_passwords().len() > 100);
+        assert!(common_passwords().iter().all(|p| *p == p.to_lowercase()));
+    }
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_requirements_from_env_default_when_unset() {
+        assert_eq!(PasswordRequirements::from_lookup(lookup(&[])), Ok(PasswordRequirements::default()));
+        assert_eq!(
+            PasswordRequirements::from_lookup(lookup(&[("PASSWORD_MIN_LENGTH", "  ")])),
+            Ok(PasswordRequirements::default())
+        );
+    }
+
+    #[test]
+    fn test_requirements_from_env_are_parsed() {
+        let requirements = PasswordRequirements::from_lookup(lookup(&[
+            ("PASSWORD_MIN_LENGTH", "14"),
+            ("PASSWORD_MAX_LENGTH", "64"),
+            ("PASSWORD_REQUIRE_UPPERCASE", "false"),
+            ("PASSWORD_REQUIRE_NUMBER", "0"),
+            ("PASSWORD_REQUIRE_SPECIAL", "TRUE"),
+            ("PASSWORD_REJECT_COMMON", "yes"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            requirements,
+            PasswordRequirements {
+                min_length: 14,
+                max_length: 64,
+                require_uppercase: false,
+                require_lowercase: true,
+                require_digit: false,
+                require_special_char: true,
+                reject_common_passwords: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_requirements_from_env_are_errors() {
+        let error = PasswordRequirements::from_lookup(lookup(&[("PASSWORD_MIN_LENGTH", "0")])).unwrap_err();
+        assert_eq!(error.var, "PASSWORD_MIN_LENGTH");
+        assert_eq!(error.to_string(), "Invalid PASSWORD_MIN_LENGTH=\"0\": must be at least 8");
+
+        for vars in [
+            [("PASSWORD_MIN_LENGTH", "eight")],
+            [("PASSWORD_MAX_LENGTH", "4")],
+            [("PASSWORD_REQUIRE_SPECIAL", "sometimes")],
+        ] {
+            assert!(PasswordRequirements::from_lookup(lookup(&vars)).is_err(), "{:?} was accepted", vars);
+        }
+    }
+
+    #[test]
+    fn test_min_length_below_floor_is_clamped() {
+        let requirements = PasswordRequirements::default().with_min_length(4);
+        assert_eq!(requirements.min_length, MIN_PASSWORD_LENGTH_FLOOR);
+
+        let result = validate_password_strength_with_requirements("Abc123", &requirements);
+        assert!(matches!(result, Err(PasswordError::WeakPassword(_))));
+    }
+
     // ========================================
     // Token Generation Tests
     // ========================================
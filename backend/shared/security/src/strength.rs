@@ -0,0 +1,76 @@
+//! Password strength scoring
+//!
+//! Scores passwords from 0 (trivially guessable) to 4 (very strong) with
+//! zxcvbn, which estimates how many guesses an attacker needs by looking for
+//! dictionary words, keyboard patterns, dates and repeats instead of only
+//! counting character classes. Words about the user, such as their email and
+//! name, are added to its dictionaries so a password built from them scores
+//! low.
+//!
+//! Only built with the `zxcvbn` feature.
+
+use serde::Serialize;
+
+/// Highest strength score
+pub const MAX_STRENGTH_SCORE: u8 = 4;
+
+/// Estimated strength of a password
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PasswordStrength {
+    /// 0 (too guessable) to 4 (very unguessable)
+    pub score: u8,
+    /// Warning and suggestions to show the user, most important first
+    pub feedback: Vec<String>,
+}
+
+/// Score `password`, treating `user_inputs` as words an attacker would try
+pub fn password_strength_score(password: &str, user_inputs: &[&str]) -> PasswordStrength {
+    let entropy = zxcvbn::zxcvbn(password, user_inputs);
+
+    let feedback = entropy
+        .feedback()
+        .map(|feedback| {
+            feedback
+                .warning()
+                .map(|warning| warning.to_string())
+                .into_iter()
+                .chain(feedback.suggestions().iter().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    PasswordStrength {
+        score: u8::from(entropy.score()),
+        feedback,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_passwords_score_low() {
+        assert_eq!(password_strength_score("password", &[]).score, 0);
+        assert!(password_strength_score("Password123!", &[]).score <= 1);
+        assert!(password_strength_score("qwerty123", &[]).score <= 1);
+    }
+
+    #[test]
+    fn test_random_password_scores_high() {
+        let strength = password_strength_score("vN7#qLz!2wR9@xTp", &[]);
+
+        assert_eq!(strength.score, MAX_STRENGTH_SCORE);
+        assert!(strength.feedback.is_empty());
+    }
+
+    #[test]
+    fn test_user_inputs_lower_the_score() {
+        let without = password_strength_score("Zorblax2024!", &[]);
+        let with = password_strength_score("Zorblax2024!", &["zorblax@example.com", "zorblax"]);
+
+        assert!(with.score < without.score);
+        assert!(with.score <= 2);
+        assert!(!with.feedback.is_empty());
+    }
+}
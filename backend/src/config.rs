@@ -256,6 +256,46 @@ pub struct Config {
     pub api_cors_origins: Vec<String>,
     #[serde(default)]
     pub csrf_strict_redis: bool,
+    /// Largest file accepted by uploads, from `MAX_FILE_SIZE_BYTES`
+    #[serde(default = "default_max_file_size_bytes")]
+    pub max_file_size_bytes: u64,
+    /// bcrypt cost for new password hashes, from `BCRYPT_COST` (4-31)
+    #[serde(default = "default_bcrypt_cost")]
+    pub bcrypt_cost: u32,
+    /// Origin that public links such as share URLs are built on, from
+    /// `PUBLIC_BASE_URL`; normalized without a trailing slash
+    #[serde(default)]
+    pub public_base_url: Option<String>,
+    /// Honor `X-Forwarded-Host`/`X-Forwarded-Proto` and key auth rate limits
+    /// on `X-Forwarded-For`, from `TRUST_PROXY`; only enable behind a proxy
+    /// that sets them
+    #[serde(default)]
+    pub trust_proxy: bool,
+    /// Length of new share tokens, from `SHARE_TOKEN_LENGTH` (16-64)
+    #[serde(default = "default_share_token_length")]
+    pub share_token_length: usize,
+    /// Length of generated share access codes, from
+    /// `SHARE_ACCESS_CODE_LENGTH` (4-10)
+    #[serde(default = "default_share_access_code_length")]
+    pub share_access_code_length: usize,
+    /// Characters generated share access codes are drawn from, from
+    /// `SHARE_ACCESS_CODE_CHARSET`
+    #[serde(default = "default_share_access_code_charset")]
+    pub share_access_code_charset: String,
+    /// Validated share link settings built from the fields above
+    #[serde(skip)]
+    share_link: document_service::sharing::ShareLinkConfig,
+    /// Remove EXIF and other metadata from uploaded JPEG and PNG images,
+    /// from `UPLOAD_STRIP_IMAGE_METADATA`
+    #[serde(default = "default_upload_strip_image_metadata")]
+    pub upload_strip_image_metadata: bool,
+    /// Spaces whose uploads keep their image metadata, from
+    /// `UPLOAD_PRESERVE_METADATA_SPACES` (comma-separated space ids)
+    #[serde(deserialize_with = "deserialize_comma_separated", default)]
+    pub upload_preserve_metadata_spaces: Vec<String>,
+    /// Image metadata policy built from the two fields above
+    #[serde(skip)]
+    image_metadata: file_service::ImageMetadataPolicy,
     pub security_headers: SecurityHeadersConfig,
     /// Security headers configuration (raw, will be parsed)
     #[serde(default)]
@@ -272,19 +312,91 @@ fn default_app_env() -> String {
     "development".to_string()
 }
 
+fn default_max_file_size_bytes() -> u64 {
+    file_service::DEFAULT_MAX_FILE_SIZE
+}
+
+fn default_bcrypt_cost() -> u32 {
+    shared_security::DEFAULT_BCRYPT_COST
+}
+
+fn default_share_token_length() -> usize {
+    document_service::sharing::DEFAULT_SHARE_TOKEN_LENGTH
+}
+
+fn default_share_access_code_length() -> usize {
+    document_service::sharing::DEFAULT_ACCESS_CODE_LENGTH
+}
+
+fn default_share_access_code_charset() -> String {
+    document_service::sharing::DEFAULT_ACCESS_CODE_CHARSET.to_string()
+}
+
+fn default_upload_strip_image_metadata() -> bool {
+    true
+}
+
 impl Config {
     pub fn from_env() -> Result<Self, config::ConfigError> {
+        Self::from_source(config::Environment::default().separator("__"))
+    }
+
+    fn from_source(source: config::Environment) -> Result<Self, config::ConfigError> {
         let config: Self = config::Config::builder()
-            .add_source(config::Environment::default().separator("__"))
+            .add_source(source)
             .build()?
             .try_deserialize()?;
 
+        if !(bcrypt::MIN_COST..=bcrypt::MAX_COST).contains(&config.bcrypt_cost) {
+            return Err(config::ConfigError::Message(format!(
+                "BCRYPT_COST must be between {} and {}, got {}",
+                bcrypt::MIN_COST,
+                bcrypt::MAX_COST,
+                config.bcrypt_cost
+            )));
+        }
+        if config.max_file_size_bytes == 0 {
+            return Err(config::ConfigError::Message(
+                "MAX_FILE_SIZE_BYTES must be greater than 0".to_string(),
+            ));
+        }
+
+        let public_base_url = match config.public_base_url.as_deref().map(str::trim) {
+            Some("") | None => None,
+            Some(url) => Some(normalize_public_base_url(url).map_err(config::ConfigError::Message)?),
+        };
+        let share_link = document_service::sharing::ShareLinkConfig::new(
+            config.share_token_length,
+            config.share_access_code_length,
+            &config.share_access_code_charset,
+        )
+        .map_err(|e| config::ConfigError::Message(format!("Invalid share link settings: {}", e)))?;
+        let preserve_spaces = config
+            .upload_preserve_metadata_spaces
+            .iter()
+            .map(|id| {
+                uuid::Uuid::parse_str(id).map_err(|_| {
+                    config::ConfigError::Message(format!(
+                        "UPLOAD_PRESERVE_METADATA_SPACES must list space ids, got {:?}",
+                        id
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        let image_metadata = file_service::ImageMetadataPolicy {
+            strip: config.upload_strip_image_metadata,
+            preserve_spaces,
+        };
+
         let mut security_headers = SecurityHeadersConfig::from_raw(
             config.security_headers_raw.clone()
         );
         security_headers.update_csp();
 
         Ok(Config {
+            public_base_url,
+            share_link,
+            image_metadata,
             database_url: config.database_url.clone(),
             redis_cache_ttl_default: Some(config.redis_cache_ttl_default.unwrap_or(3600)),
             redis_cache_ttl_short: Some(config.redis_cache_ttl_short.unwrap_or(300)),
@@ -294,6 +406,24 @@ impl Config {
         })
     }
 
+    /// Share link URL settings for the document service
+    pub fn share_url_config(&self) -> document_service::sharing::ShareUrlConfig {
+        document_service::sharing::ShareUrlConfig {
+            public_base_url: self.public_base_url.clone(),
+            trust_proxy: self.trust_proxy,
+        }
+    }
+
+    /// Share token and access code settings for the document service
+    pub fn share_link_config(&self) -> document_service::sharing::ShareLinkConfig {
+        self.share_link.clone()
+    }
+
+    /// Image metadata stripping settings for the file service
+    pub fn image_metadata_policy(&self) -> file_service::ImageMetadataPolicy {
+        self.image_metadata.clone()
+    }
+
     pub async fn create_pool(&self) -> Result<sqlx::PgPool, sqlx::Error> {
         // Read connection count configurations with defaults
         let min_connections = self.db_min_connections.unwrap_or(5);
@@ -321,6 +451,24 @@ impl Config {
             .await
     }
 }
+/// Check that `PUBLIC_BASE_URL` is an absolute http(s) URL with a host and
+/// no query or fragment, and drop any trailing slash
+fn normalize_public_base_url(value: &str) -> Result<String, String> {
+    let url = url::Url::parse(value).map_err(|e| format!("PUBLIC_BASE_URL is not a valid URL: {}", e))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("PUBLIC_BASE_URL must use http or https, got {}", url.scheme()));
+    }
+    if url.host_str().is_none() {
+        return Err("PUBLIC_BASE_URL must include a host".to_string());
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err("PUBLIC_BASE_URL must not have a query or fragment".to_string());
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
 use serde::Deserializer;
 
 pub fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
@@ -430,4 +578,122 @@ mod tests {
         assert!(!csp.contains("connect-src 'self' https://"));
     }
 
+    fn required_env() -> std::collections::HashMap<String, String> {
+        [
+            ("host", "0.0.0.0"),
+            ("port", "8080"),
+            ("database_url", "postgres://localhost/miniwiki"),
+            ("jwt_secret", "secret"),
+            ("jwt_access_expiry", "3600"),
+            ("jwt_refresh_expiry", "86400"),
+            ("redis_url", ""),
+            ("minio_endpoint", "http://localhost:9000"),
+            ("minio_access_key", "minio"),
+            ("minio_secret_key", "minio123"),
+            ("minio_bucket", "miniwiki"),
+            ("minio_region", "us-east-1"),
+            ("minio_use_ssl", "false"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn config_from(vars: std::collections::HashMap<String, String>) -> Result<Config, config::ConfigError> {
+        Config::from_source(config::Environment::default().separator("__").source(Some(vars)))
+    }
+
+    #[test]
+    fn test_file_size_and_bcrypt_cost_defaults() {
+        let config = config_from(required_env()).unwrap();
+
+        assert_eq!(config.max_file_size_bytes, 50 * 1024 * 1024);
+        assert_eq!(config.bcrypt_cost, shared_security::DEFAULT_BCRYPT_COST);
+    }
+
+    #[test]
+    fn test_file_size_and_bcrypt_cost_from_env() {
+        let mut vars = required_env();
+        vars.insert("max_file_size_bytes".to_string(), "1048576".to_string());
+        vars.insert("bcrypt_cost".to_string(), "10".to_string());
+        let config = config_from(vars).unwrap();
+
+        assert_eq!(config.max_file_size_bytes, 1_048_576);
+        assert_eq!(config.bcrypt_cost, 10);
+    }
+
+    #[test]
+    fn test_bcrypt_cost_outside_range_is_rejected() {
+        for cost in ["3", "32"] {
+            let mut vars = required_env();
+            vars.insert("bcrypt_cost".to_string(), cost.to_string());
+            assert!(config_from(vars).is_err(), "cost {} should be rejected", cost);
+        }
+
+        let mut vars = required_env();
+        vars.insert("max_file_size_bytes".to_string(), "0".to_string());
+        assert!(config_from(vars).is_err());
+    }
+
+    #[test]
+    fn test_public_base_url_is_normalized() {
+        let config = config_from(required_env()).unwrap();
+        assert_eq!(config.public_base_url, None);
+        assert!(!config.trust_proxy);
+
+        let mut vars = required_env();
+        vars.insert("public_base_url".to_string(), "https://wiki.example.com/".to_string());
+        vars.insert("trust_proxy".to_string(), "true".to_string());
+        let config = config_from(vars).unwrap();
+        assert_eq!(config.public_base_url.as_deref(), Some("https://wiki.example.com"));
+        assert!(config.share_url_config().trust_proxy);
+    }
+
+    #[test]
+    fn test_invalid_public_base_url_is_rejected() {
+        for url in ["wiki.example.com", "ftp://wiki.example.com", "https://wiki.example.com/?a=1"] {
+            let mut vars = required_env();
+            vars.insert("public_base_url".to_string(), url.to_string());
+            assert!(config_from(vars).is_err(), "{} should be rejected", url);
+        }
+    }
+
+    #[test]
+    fn test_share_link_settings() {
+        let config = config_from(required_env()).unwrap();
+        assert_eq!(config.share_link_config(), document_service::sharing::ShareLinkConfig::default());
+
+        let mut vars = required_env();
+        vars.insert("share_token_length".to_string(), "48".to_string());
+        vars.insert("share_access_code_length".to_string(), "8".to_string());
+        vars.insert("share_access_code_charset".to_string(), "0123456789".to_string());
+        let share_link = config_from(vars).unwrap().share_link_config();
+        assert_eq!(share_link.token_length(), 48);
+        assert_eq!(share_link.generate_access_code().len(), 8);
+
+        for (var, value) in [("share_token_length", "8"), ("share_access_code_length", "12")] {
+            let mut vars = required_env();
+            vars.insert(var.to_string(), value.to_string());
+            assert!(config_from(vars).is_err(), "{}={} should be rejected", var, value);
+        }
+    }
+
+    #[test]
+    fn test_image_metadata_settings() {
+        let policy = config_from(required_env()).unwrap().image_metadata_policy();
+        assert!(policy.strip);
+        assert!(policy.preserve_spaces.is_empty());
+
+        let space_id = uuid::Uuid::new_v4();
+        let mut vars = required_env();
+        vars.insert("upload_strip_image_metadata".to_string(), "true".to_string());
+        vars.insert("upload_preserve_metadata_spaces".to_string(), format!(" {} ", space_id));
+        let policy = config_from(vars).unwrap().image_metadata_policy();
+        assert!(!policy.strips_for(&space_id));
+        assert!(policy.strips_for(&uuid::Uuid::new_v4()));
+
+        let mut vars = required_env();
+        vars.insert("upload_preserve_metadata_spaces".to_string(), "not-a-space".to_string());
+        assert!(config_from(vars).is_err());
+    }
 }
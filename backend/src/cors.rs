@@ -0,0 +1,200 @@
+//! Reloadable CORS allowlist
+//!
+//! The origins from `API_CORS_ORIGINS` are held behind an `ArcSwap`, so the
+//! CORS check reads the current list without locking and
+//! `POST /api/v1/admin/cors/reload` can replace it without a restart. A
+//! reload re-reads `API_CORS_ORIGINS` from the `.env` file, falling back to
+//! the process environment. If any entry is not a valid origin the whole
+//! reload is rejected and the current list stays in place.
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use arc_swap::ArcSwap;
+use auth_service::jwt::JwtService;
+use auth_service::permissions::Role;
+use auth_service::rbac::RbacMiddleware;
+use serde_json::json;
+use space_service::embed_origins::normalize_origin;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Environment variable holding the comma separated allowlist
+pub const CORS_ORIGINS_VAR: &str = "API_CORS_ORIGINS";
+
+/// Origins allowed by the global CORS check, shared between workers
+#[derive(Clone, Default)]
+pub struct CorsAllowlist {
+    origins: Arc<ArcSwap<BTreeSet<String>>>,
+}
+
+/// Entries of a new allowlist that are not valid origins
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid CORS origins: {}", .0.join(", "))]
+pub struct InvalidOrigins(pub Vec<String>);
+
+/// Normalize every entry, or report all the ones that aren't origins
+fn parse_origins(origins: &[String]) -> Result<BTreeSet<String>, InvalidOrigins> {
+    let mut parsed = BTreeSet::new();
+    let mut invalid = Vec::new();
+    for origin in origins {
+        match normalize_origin(origin) {
+            Some(origin) => {
+                parsed.insert(origin);
+            },
+            None => invalid.push(origin.clone()),
+        }
+    }
+
+    if invalid.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(InvalidOrigins(invalid))
+    }
+}
+
+/// Split a comma separated list the way the config loader does
+fn split_origins(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+/// The current `API_CORS_ORIGINS`, preferring the `.env` file
+///
+/// The process environment cannot change after startup, so edits to `.env`
+/// are what a reload picks up.
+fn configured_origins() -> Vec<String> {
+    if let Ok(items) = dotenv::dotenv_iter() {
+        if let Some((_, value)) = items.flatten().find(|(key, _)| key == CORS_ORIGINS_VAR) {
+            return split_origins(&value);
+        }
+    }
+    std::env::var(CORS_ORIGINS_VAR).map(|v| split_origins(&v)).unwrap_or_default()
+}
+
+impl CorsAllowlist {
+    /// Build the startup allowlist; invalid entries are logged and skipped
+    pub fn new(origins: &[String]) -> Self {
+        let parsed = parse_origins(origins).unwrap_or_else(|InvalidOrigins(invalid)| {
+            tracing::warn!("Ignoring invalid CORS origins: {}", invalid.join(", "));
+            origins.iter().filter_map(|o| normalize_origin(o)).collect()
+        });
+
+        Self {
+            origins: Arc::new(ArcSwap::from_pointee(parsed)),
+        }
+    }
+
+    /// Swap in a new list, returning how many origins it holds
+    ///
+    /// Nothing changes when any entry is invalid.
+    pub fn replace(&self, origins: &[String]) -> Result<usize, InvalidOrigins> {
+        let parsed = parse_origins(origins)?;
+        let count = parsed.len();
+        self.origins.store(Arc::new(parsed));
+        Ok(count)
+    }
+
+    /// Re-read the allowlist from configuration
+    pub fn reload(&self) -> Result<usize, InvalidOrigins> {
+        self.replace(&configured_origins())
+    }
+
+    /// Whether `origin` is on the current list
+    pub fn allows(&self, origin: &str) -> bool {
+        match normalize_origin(origin) {
+            Some(origin) => self.origins.load().contains(&origin),
+            None => false,
+        }
+    }
+
+    pub fn origins(&self) -> Vec<String> {
+        self.origins.load().iter().cloned().collect()
+    }
+}
+
+/// `POST /api/v1/admin/cors/reload` - re-read the CORS allowlist (owner role only)
+pub async fn reload_cors(
+    req: HttpRequest,
+    allowlist: web::Data<CorsAllowlist>,
+    jwt_service: web::Data<JwtService>,
+) -> HttpResponse {
+    let claims = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(JwtService::extract_token_from_header)
+        .and_then(|token| jwt_service.validate_token(token).ok());
+    let Some(claims) = claims else {
+        return HttpResponse::Unauthorized()
+            .json(json!({ "error": "AUTHENTICATION_ERROR", "message": "Missing or invalid authorization header" }));
+    };
+    if !RbacMiddleware::has_role(&claims.role, Role::Owner) {
+        return HttpResponse::Forbidden().json(json!({ "error": "FORBIDDEN", "message": "Admin permission required" }));
+    }
+
+    match allowlist.reload() {
+        Ok(count) => {
+            tracing::info!("CORS allowlist reloaded by {}: {} origins", claims.user_id, count);
+            HttpResponse::Ok().json(json!({ "origins": allowlist.origins(), "count": count }))
+        },
+        Err(e) => {
+            tracing::warn!("Rejected CORS allowlist reload, keeping the current list: {}", e);
+            HttpResponse::BadRequest().json(json!({
+                "error": "INVALID_CORS_ORIGINS",
+                "message": "The configured allowlist has invalid origins; the current list was kept",
+                "invalid": e.0,
+            }))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins(list: &[&str]) -> Vec<String> {
+        list.iter().map(|o| o.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allows_reads_the_swapped_list() {
+        let allowlist = CorsAllowlist::new(&origins(&["http://localhost:3000"]));
+        let shared = allowlist.clone();
+        assert!(shared.allows("http://localhost:3000"));
+        assert!(!shared.allows("https://wiki.example.com"));
+
+        assert_eq!(allowlist.replace(&origins(&["https://Wiki.Example.com/"])), Ok(1));
+
+        // Clones handed to other workers see the new list
+        assert!(shared.allows("https://wiki.example.com"));
+        assert!(!shared.allows("http://localhost:3000"));
+        assert!(!shared.allows("not an origin"));
+    }
+
+    #[test]
+    fn test_invalid_entries_keep_the_current_list() {
+        let allowlist = CorsAllowlist::new(&origins(&["http://localhost:3000"]));
+
+        let result = allowlist.replace(&origins(&["https://ok.example.com", "ftp://files.example.com", "*"]));
+
+        assert_eq!(
+            result,
+            Err(InvalidOrigins(origins(&["ftp://files.example.com", "*"])))
+        );
+        assert!(allowlist.allows("http://localhost:3000"));
+        assert!(!allowlist.allows("https://ok.example.com"));
+    }
+
+    #[test]
+    fn test_startup_list_skips_invalid_entries() {
+        let allowlist = CorsAllowlist::new(&origins(&["http://localhost:3000", "localhost:8080"]));
+
+        assert_eq!(allowlist.origins(), origins(&["http://localhost:3000"]));
+    }
+
+    #[test]
+    fn test_split_origins_trims_and_drops_empty_entries() {
+        assert_eq!(
+            split_origins(" http://a.example.com ,, https://b.example.com,"),
+            origins(&["http://a.example.com", "https://b.example.com"])
+        );
+    }
+}
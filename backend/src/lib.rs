@@ -1,4 +1,5 @@
 pub mod config;
+pub mod cors;
 pub mod observability;
 pub mod routes;
 pub mod middleware;
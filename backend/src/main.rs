@@ -7,17 +7,56 @@ use std::sync::Arc;
 // Use symbols from the library crate
 use miniwiki_backend::{
     config::Config,
+    cors::CorsAllowlist,
     middleware::{
+        compression::CompressionPolicy,
+        concurrency::ConcurrencyLimit,
         error_handler::ErrorHandler,
         security_headers::SecurityHeaders,
+        validation::RequestSizeLimit,
         csrf::{CsrfMiddleware, CsrfConfig, CsrfStore, InMemoryCsrfStore, RedisCsrfStore},
+        idempotency::{Idempotency, IdempotencyStore, InMemoryIdempotencyStore, RedisIdempotencyStore},
+        request_id::PropagateRequestId,
     },
-    routes,
-    observability::RequestMetrics,
+    routes::{self, health::ReadinessProbe},
+    observability::{MetricsMiddleware, RequestMetrics},
 };
+use auth_service::lockout::{LockoutConfig, LoginLockout};
 use auth_service::repository::AuthRepository;
+use space_service::embed_origins::{SpaceEmbedOrigins, DEFAULT_REFRESH_SECS};
 use tokio::sync::Mutex;
+use sync_service::persistence::UpdateBatcher;
 use sync_service::sync_handler::SyncAppState;
+use websocket_service::{SESSION_STORE, WS_SHUTDOWN};
+
+/// Default seconds to let in-flight requests finish after a shutdown signal
+const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// Allowance for multipart boundaries and form fields on top of the file size limit
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// Resolve on SIGINT, or SIGTERM on Unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+                return;
+            },
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen for shutdown signal: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -107,14 +146,156 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    // Failed-login lockout shares Redis with the CSRF store when available
+    let login_lockout = web::Data::new(
+        LoginLockout::connect(LockoutConfig::from_env(), Some(config.redis_url.as_str())).await,
+    );
+    // Refresh tokens revoked at logout stay denied on every instance
+    let token_denylist = web::Data::new(
+        auth_service::token_denylist::TokenDenylist::connect(Some(config.redis_url.as_str())).await,
+    );
+
+    // Shared across workers so count-cache invalidation is seen by every worker
+    // One member cap for both the space and the document service endpoints
+    let space_member_limit = web::Data::new(space_service::repository::SpaceMemberLimit::from_env());
+    let document_repo = web::Data::new(
+        document_service::repository::DocumentRepository::new(pool.clone())
+            .with_max_space_members(space_member_limit.get_ref().0),
+    );
+
+    // Yjs updates are buffered and written in batches; shared so every worker
+    // flushes into the same buffer
+    let update_batcher = web::Data::new(UpdateBatcher::from_env(pool.clone()));
+    update_batcher.clone().into_inner().spawn_flush_task();
+    let batcher_for_shutdown = update_batcher.clone();
+
+    // Origins allowed to embed public spaces, consulted by the CORS check below
+    let embed_origins = SpaceEmbedOrigins::new();
+    if let Err(e) = embed_origins.reload(&pool).await {
+        warn!("Failed to load space embed origins: {}", e);
+    }
+    let embed_refresh_secs = std::env::var("SPACE_EMBED_ORIGINS_REFRESH_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_REFRESH_SECS);
+    let embed_origins_for_refresh = embed_origins.clone();
+    let pool_for_refresh = pool.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(embed_refresh_secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = embed_origins_for_refresh.reload(&pool_for_refresh).await {
+                warn!("Failed to refresh space embed origins: {}", e);
+            }
+        }
+    });
+
+    // Idempotency keys live in Redis so retries hitting another instance replay too
+    let idempotency_store: Arc<dyn IdempotencyStore> = if config.redis_url.is_empty() {
+        Arc::new(InMemoryIdempotencyStore::new())
+    } else {
+        match redis::Client::open(config.redis_url.as_str()) {
+            Ok(client) => match client.get_multiplexed_async_connection().await {
+                Ok(conn) => Arc::new(RedisIdempotencyStore::new(conn)),
+                Err(e) => {
+                    warn!("Failed to connect to Redis for idempotency keys: {}. Using in-memory store.", e);
+                    Arc::new(InMemoryIdempotencyStore::new())
+                }
+            },
+            Err(e) => {
+                warn!("Failed to open Redis client for idempotency keys: {}. Using in-memory store.", e);
+                Arc::new(InMemoryIdempotencyStore::new())
+            }
+        }
+    };
+    let idempotency = Idempotency::from_env(idempotency_store);
+
+    // Uploads get the file size limit plus room for multipart framing
+    let request_size_limit = RequestSizeLimit::for_api(
+        (config.max_file_size_bytes as usize).saturating_add(MULTIPART_OVERHEAD_BYTES),
+    );
+
+    let compression_policy = CompressionPolicy::from_env();
+    info!(
+        "Response compression: {} (min {} bytes)",
+        if compression_policy.enabled() { "enabled" } else { "disabled" },
+        compression_policy.min_size()
+    );
+
+    // One set of permits for all workers so the ceiling is global
+    let concurrency_limit = ConcurrencyLimit::from_env();
+    info!("Max concurrent requests: {}", concurrency_limit.max_concurrent());
+
+    let readiness_probe = web::Data::new(ReadinessProbe::from_env(&config.redis_url));
+    let upload_limits = web::Data::new(file_service::UploadLimits { max_file_size: config.max_file_size_bytes });
+    let image_metadata_policy = web::Data::new(config.image_metadata_policy());
+    let file_scanner = file_service::scanner::scanner_from_env().map(web::Data::new);
+    info!("Upload malware scanning: {}", if file_scanner.is_some() { "enabled" } else { "disabled" });
+
+    // Soft-deleted files are purged, with their stored objects, once past retention
+    let file_retention = file_service::FileRetention::from_env();
+    let storage_config = file_service::storage::S3StorageConfig {
+        endpoint: config.minio_endpoint.clone(),
+        access_key: config.minio_access_key.clone(),
+        secret_key: config.minio_secret_key.clone(),
+        bucket: config.minio_bucket.clone(),
+        region: config.minio_region.clone(),
+        use_ssl: config.minio_use_ssl,
+    };
+    // Shared by the purge task and every worker's file and export handlers
+    let storage = match file_service::storage::S3Storage::new(storage_config).await {
+        Ok(storage) => {
+            let storage = Arc::new(storage);
+            info!("Purging deleted files after {} days", file_retention.retention.num_days());
+            file_retention.spawn_purge_task(pool.clone(), storage.clone());
+            Some(storage)
+        },
+        Err(e) => {
+            warn!("File storage unavailable, file endpoints and export images are disabled: {}", e);
+            None
+        },
+    };
+    let bcrypt_cost = web::Data::new(auth_service::password::BcryptCost(config.bcrypt_cost));
+    let password_requirements = web::Data::new(
+        auth_service::password::PasswordRequirements::from_env().unwrap_or_else(|e| {
+            error!("Invalid password requirements: {}", e);
+            std::process::exit(1);
+        }),
+    );
+    let reading_speed = web::Data::new(document_service::text_metrics::ReadingSpeed::from_env());
+    let access_denial_policy = web::Data::new(document_service::handlers::AccessDenialPolicy::from_env());
+    let share_url_config = web::Data::new(config.share_url_config());
+    let share_link_config = web::Data::new(config.share_link_config());
+    let search_repo = web::Data::new(search_service::repository::SearchRepository::new(Arc::new(pool.clone())));
+    let empty_search_query = web::Data::new(search_service::models::EmptyQueryBehavior::from_env());
+    let search_rate_limit = web::Data::new(
+        search_service::rate_limit::SearchRateLimit::connect_from_env(Some(config.redis_url.as_str())).await,
+    );
+    let auth_rate_limit = web::Data::new(
+        auth_service::rate_limit::AuthRateLimit::connect_from_env(Some(config.redis_url.as_str()))
+            .await
+            .with_trust_proxy(config.trust_proxy),
+    );
+
     let port = config.port;
 
     let allow_all_origins = std::env::var("ALLOW_ALL_ORIGINS").unwrap_or_default() == "true";
 
+    // Shared by every worker so a reload through the admin endpoint applies everywhere
+    let cors_allowlist = CorsAllowlist::new(&config.api_cors_origins);
+
+    let shutdown_grace_secs = std::env::var("SHUTDOWN_GRACE_PERIOD_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_GRACE_SECS);
+
     let server = HttpServer::new(move || {
         let cors_config = config.clone();
+        let cors_allowlist_for_check = cors_allowlist.clone();
+        let cors_embed_origins = embed_origins.clone();
         let cors = Cors::default()
-            .allowed_origin_fn(move |origin, _req_head| {
+            .allowed_origin_fn(move |origin, req_head| {
                 let origin_str = origin.to_str().unwrap_or("");
 
                 // Allow all ONLY in development AND if explicitly allowed via env var
@@ -123,7 +304,12 @@ async fn main() -> std::io::Result<()> {
                 }
 
                 // Check against configured allowlist
-                cors_config.api_cors_origins.iter().any(|o| o == origin_str)
+                if cors_allowlist_for_check.allows(origin_str) {
+                    return true;
+                }
+
+                // Embed origins only reach their own public space's read endpoints
+                cors_embed_origins.allows(origin_str, req_head)
             })
             .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
             .allowed_headers(vec![
@@ -131,6 +317,15 @@ async fn main() -> std::io::Result<()> {
                 actix_web::http::header::ACCEPT,
                 actix_web::http::header::CONTENT_TYPE,
                 actix_web::http::header::HeaderName::from_static("x-csrf-token"),
+                actix_web::http::header::HeaderName::from_static("idempotency-key"),
+                actix_web::http::header::HeaderName::from_static("x-request-id"),
+            ])
+            .expose_headers(vec![
+                actix_web::http::header::HeaderName::from_static("x-request-id"),
+                actix_web::http::header::HeaderName::from_static("x-ratelimit-limit"),
+                actix_web::http::header::HeaderName::from_static("x-ratelimit-remaining"),
+                actix_web::http::header::HeaderName::from_static("x-ratelimit-reset"),
+                actix_web::http::header::RETRY_AFTER,
             ])
             .supports_credentials()
             .max_age(3600);
@@ -138,25 +333,84 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(AuthRepository::new(pool.clone())))
-            .app_data(web::Data::new(document_service::repository::DocumentRepository::new(pool.clone())))
+            .app_data(login_lockout.clone())
+            .app_data(token_denylist.clone())
+            .app_data(auth_rate_limit.clone())
+            .app_data(document_repo.clone())
+            .app_data(space_member_limit.clone())
             .app_data(web::Data::new(SyncAppState {
                 pool: pool.clone(),
                 server_clock: Arc::new(Mutex::new(0)),
             }))
-            .app_data(web::Data::new(metrics.clone()))
+            .app_data(update_batcher.clone())
+            .app_data(web::Data::new(embed_origins.clone()))
+            .app_data(web::Data::new(cors_allowlist.clone()))
+            .app_data(readiness_probe.clone())
+            .app_data(upload_limits.clone())
+            .app_data(image_metadata_policy.clone())
+            // Uploads are only scanned when a scanner is configured
+            .configure(|cfg| {
+                if let Some(scanner) = &file_scanner {
+                    cfg.app_data(scanner.clone());
+                }
+            })
+            .configure(routes::storage_config(storage.clone()))
+            .app_data(bcrypt_cost.clone())
+            .app_data(password_requirements.clone())
+            .app_data(search_repo.clone())
+            .app_data(empty_search_query.clone())
+            .app_data(search_rate_limit.clone())
+            .app_data(reading_speed.clone())
+            .app_data(access_denial_policy.clone())
+            .app_data(share_url_config.clone())
+            .app_data(share_link_config.clone())
+            .app_data(web::Data::from(metrics.clone()))
             .app_data(web::Data::new(csrf_config.clone()))
             .app_data(web::Data::new(csrf_store.clone()))
             .wrap(actix_middleware::Logger::default())
             .wrap(ErrorHandler)
             .wrap(SecurityHeaders::new())
+            .wrap(request_size_limit.clone())
             .wrap(CsrfMiddleware::new(csrf_config.clone(), csrf_store.clone()))
+            // Outside CSRF so a replayed retry doesn't need a fresh token, and
+            // inside Compress so it caches the unencoded body and a replay is
+            // encoded for the retrying client's Accept-Encoding
+            .wrap(idempotency.clone())
+            // Policy sits inside Compress so it can opt small and binary responses out
+            .wrap(compression_policy.clone())
+            .wrap(actix_middleware::Condition::new(compression_policy.enabled(), actix_middleware::Compress::default()))
+            // Inside CORS so shed responses still carry CORS headers
+            .wrap(concurrency_limit.clone())
             .wrap(cors)
+            // Outermost so shed and rejected requests are counted too
+            .wrap(MetricsMiddleware::new(metrics.clone()))
+            // Outside everything so every log line and response carries the id
+            .wrap(PropagateRequestId)
             .configure(routes::config)
     })
     .bind(("0.0.0.0", port))?
+    // Signals are handled below so WebSocket sessions close before workers stop
+    .disable_signals()
+    .shutdown_timeout(shutdown_grace_secs)
     .run();
 
     info!("Server listening on http://0.0.0.0:{}", port);
 
-    server.await
+    let server_handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutdown requested, draining for up to {}s", shutdown_grace_secs);
+        WS_SHUTDOWN.begin_shutdown(&SESSION_STORE);
+        // Stops accepting connections, then waits for in-flight requests
+        server_handle.stop(true).await;
+    });
+
+    let result = server.await;
+
+    // Don't lose updates still buffered at shutdown
+    if let Err(e) = batcher_for_shutdown.flush().await {
+        error!("Failed to flush document updates on shutdown: {}", e);
+    }
+
+    result
 }
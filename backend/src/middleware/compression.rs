@@ -0,0 +1,212 @@
+//! Response compression policy
+//!
+//! actix's `Compress` middleware encodes every response the client accepts.
+//! This wrapper sits just inside it and opts responses out by marking them
+//! `Content-Encoding: identity`, which `Compress` leaves untouched:
+//!
+//! - bodies smaller than the configured threshold, where the encoding
+//!   overhead outweighs the savings
+//! - content that is not text-like (file downloads, images, archives), which
+//!   is usually compressed already
+//!
+//! Streaming bodies of unknown size are left to `Compress` when text-like.
+
+use actix_web::{
+    body::{BodySize, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{self, HeaderValue},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::Ready;
+
+/// Default smallest body, in bytes, that is compressed
+pub const DEFAULT_COMPRESSION_MIN_BYTES: u64 = 1024;
+
+/// Which responses `Compress` may encode
+///
+/// # Example
+///
+/// ```ignore
+/// let policy = CompressionPolicy::from_env();
+/// App::new()
+///     .wrap(policy.clone())
+///     .wrap(Condition::new(policy.enabled(), Compress::default()))
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompressionPolicy {
+    enabled: bool,
+    min_size: u64,
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size: DEFAULT_COMPRESSION_MIN_BYTES,
+        }
+    }
+}
+
+impl CompressionPolicy {
+    pub fn new(min_size: u64) -> Self {
+        Self { enabled: true, min_size }
+    }
+
+    /// Read `RESPONSE_COMPRESSION_ENABLED` and `RESPONSE_COMPRESSION_MIN_BYTES`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let enabled = std::env::var("RESPONSE_COMPRESSION_ENABLED")
+            .map(|v| v.to_lowercase() != "false")
+            .unwrap_or(true);
+        let min_size = std::env::var("RESPONSE_COMPRESSION_MIN_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_COMPRESSION_MIN_BYTES);
+
+        Self { enabled, min_size }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn min_size(&self) -> u64 {
+        self.min_size
+    }
+}
+
+/// Whether a content type benefits from compression
+fn is_compressible(content_type: Option<&HeaderValue>) -> bool {
+    let Some(content_type) = content_type.and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let content_type = content_type.to_ascii_lowercase();
+    content_type.starts_with("text/")
+        || content_type.contains("json")
+        || content_type.contains("xml")
+        || content_type.contains("javascript")
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CompressionPolicy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CompressionPolicyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(CompressionPolicyMiddleware {
+            service,
+            min_size: self.min_size,
+        }))
+    }
+}
+
+pub struct CompressionPolicyMiddleware<S> {
+    service: S,
+    min_size: u64,
+}
+
+impl<S, B> Service<ServiceRequest> for CompressionPolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let fut = self.service.call(req);
+        let min_size = self.min_size;
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            if res.headers().contains_key(header::CONTENT_ENCODING) {
+                return Ok(res);
+            }
+
+            let too_small = match res.response().body().size() {
+                BodySize::Sized(len) => len < min_size,
+                BodySize::None => true,
+                BodySize::Stream => false,
+            };
+
+            if too_small || !is_compressible(res.headers().get(header::CONTENT_TYPE)) {
+                res.headers_mut()
+                    .insert(header::CONTENT_ENCODING, HeaderValue::from_static("identity"));
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::Compress, test, web, App, HttpResponse};
+
+    async fn large_json() -> HttpResponse {
+        let items: Vec<String> = (0..500).map(|i| format!("paragraph {}", i)).collect();
+        HttpResponse::Ok().json(serde_json::json!({ "content": items }))
+    }
+
+    async fn small_json() -> HttpResponse {
+        HttpResponse::Ok().json(serde_json::json!({ "ok": true }))
+    }
+
+    async fn large_binary() -> HttpResponse {
+        HttpResponse::Ok().content_type("image/png").body(vec![7u8; 8 * 1024])
+    }
+
+    fn get(uri: &str) -> test::TestRequest {
+        test::TestRequest::get().uri(uri).insert_header((header::ACCEPT_ENCODING, "gzip"))
+    }
+
+    #[actix_web::test]
+    async fn test_large_json_is_compressed_and_small_is_not() {
+        let app = test::init_service(
+            App::new()
+                .wrap(CompressionPolicy::new(1024))
+                .wrap(Compress::default())
+                .route("/large", web::get().to(large_json))
+                .route("/small", web::get().to(small_json))
+                .route("/file", web::get().to(large_binary)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, get("/large").to_request()).await;
+        assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+
+        let resp = test::call_service(&app, get("/small").to_request()).await;
+        assert_ne!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(test::read_body(resp).await, r#"{"ok":true}"#);
+
+        // Binary downloads are passed through as-is
+        let resp = test::call_service(&app, get("/file").to_request()).await;
+        assert_ne!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(test::read_body(resp).await.len(), 8 * 1024);
+    }
+
+    #[test]
+    fn test_compressible_content_types() {
+        assert!(is_compressible(Some(&HeaderValue::from_static("application/json"))));
+        assert!(is_compressible(Some(&HeaderValue::from_static("text/csv; charset=utf-8"))));
+        assert!(!is_compressible(Some(&HeaderValue::from_static("application/octet-stream"))));
+        assert!(!is_compressible(Some(&HeaderValue::from_static("image/png"))));
+        assert!(!is_compressible(None));
+    }
+}
@@ -0,0 +1,257 @@
+//! Global request concurrency limit
+//!
+//! Caps how many requests are processed at once across all workers so a
+//! traffic spike queues at the edge instead of exhausting the database pool.
+//! When every permit is taken the request is shed immediately with
+//! `503 Service Unavailable` and a `Retry-After` header. Health checks are
+//! exempt so load balancers keep seeing the instance as alive while it is
+//! busy.
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    Error, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::Ready;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Default number of requests processed concurrently
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 256;
+
+/// Default `Retry-After` value in seconds for shed requests
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
+
+/// Paths that bypass the limit
+const EXEMPT_PATHS: &[&str] = &["/health", "/health/ready"];
+
+/// Concurrency limit middleware
+///
+/// Clones share the same permits, so create it once outside the
+/// `HttpServer` factory and clone it into each worker.
+///
+/// # Example
+///
+/// ```ignore
+/// let limit = ConcurrencyLimit::from_env();
+/// HttpServer::new(move || App::new().wrap(limit.clone()))
+/// ```
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: usize,
+    retry_after_secs: u64,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            retry_after_secs: DEFAULT_RETRY_AFTER_SECS,
+        }
+    }
+
+    /// Read `MAX_CONCURRENT_REQUESTS` and `CONCURRENCY_RETRY_AFTER_SECS`,
+    /// falling back to the defaults for missing or invalid values
+    pub fn from_env() -> Self {
+        let max_concurrent = std::env::var("MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS);
+        let retry_after_secs = std::env::var("CONCURRENCY_RETRY_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+
+        Self::new(max_concurrent).with_retry_after(retry_after_secs)
+    }
+
+    pub fn with_retry_after(mut self, retry_after_secs: u64) -> Self {
+        self.retry_after_secs = retry_after_secs;
+        self
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Permits not currently held by in-flight requests
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConcurrencyLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ConcurrencyLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(ConcurrencyLimitMiddleware {
+            service,
+            semaphore: self.semaphore.clone(),
+            retry_after_secs: self.retry_after_secs,
+        }))
+    }
+}
+
+pub struct ConcurrencyLimitMiddleware<S> {
+    service: S,
+    semaphore: Arc<Semaphore>,
+    retry_after_secs: u64,
+}
+
+impl<S, B> Service<ServiceRequest> for ConcurrencyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if EXEMPT_PATHS.contains(&req.path()) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        }
+
+        let permit = match self.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                tracing::warn!("Concurrency limit reached, shedding {} {}", req.method(), req.path());
+                let response = HttpResponse::ServiceUnavailable()
+                    .insert_header((header::RETRY_AFTER, self.retry_after_secs.to_string()))
+                    .json(serde_json::json!({
+                        "error": "SERVICE_BUSY",
+                        "message": "Server is busy, please retry later",
+                        "retry_after": self.retry_after_secs,
+                    }));
+                return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+            },
+        };
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+            // Held until the handler completes so the slot frees exactly once
+            drop(permit);
+            res.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+    use tokio::sync::Notify;
+
+    // Handler that blocks until the test releases it, so requests stay in flight
+    async fn held(release: web::Data<Notify>) -> HttpResponse {
+        release.notified().await;
+        HttpResponse::Ok().finish()
+    }
+
+    async fn health() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_requests_beyond_ceiling_are_shed() {
+        let limit = ConcurrencyLimit::new(2).with_retry_after(3);
+        let release = web::Data::new(Notify::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(release.clone())
+                .wrap(limit.clone())
+                .route("/slow", web::get().to(held))
+                .route("/health", web::get().to(health)),
+        )
+        .await;
+
+        // Two requests occupy both permits
+        let first = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request());
+        let second = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request());
+        let mut in_flight = Box::pin(futures_util::future::join(first, second));
+        assert!(futures_util::poll!(in_flight.as_mut()).is_pending());
+        assert_eq!(limit.available(), 0);
+
+        // The next one is shed
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert_eq!(resp.status(), 503);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "3");
+
+        // Health checks are exempt while saturated
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/health").to_request()).await;
+        assert_eq!(resp.status(), 200);
+
+        release.notify_waiters();
+        let (first, second) = in_flight.await;
+        assert_eq!(first.status(), 200);
+        assert_eq!(second.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_capacity_recovers_after_requests_complete() {
+        let limit = ConcurrencyLimit::new(1);
+        let release = web::Data::new(Notify::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(release.clone())
+                .wrap(limit.clone())
+                .route("/slow", web::get().to(held)),
+        )
+        .await;
+
+        let mut in_flight = Box::pin(test::call_service(
+            &app,
+            test::TestRequest::get().uri("/slow").to_request(),
+        ));
+        assert!(futures_util::poll!(in_flight.as_mut()).is_pending());
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/slow").to_request()).await;
+        assert_eq!(resp.status(), 503);
+
+        release.notify_waiters();
+        assert_eq!(in_flight.await.status(), 200);
+        assert_eq!(limit.available(), 1);
+
+        // The freed permit admits the next request
+        let mut next = Box::pin(test::call_service(
+            &app,
+            test::TestRequest::get().uri("/slow").to_request(),
+        ));
+        assert!(futures_util::poll!(next.as_mut()).is_pending());
+        release.notify_waiters();
+        assert_eq!(next.await.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn test_permits_are_shared_between_clones() {
+        let limit = ConcurrencyLimit::new(4);
+        let worker_copy = limit.clone();
+
+        let _permit = limit.semaphore.clone().try_acquire_owned().unwrap();
+        assert_eq!(worker_copy.available(), 3);
+        assert_eq!(worker_copy.max_concurrent(), 4);
+    }
+}
@@ -5,8 +5,10 @@ use actix_web::{
     HttpRequest,
 };
 use async_trait::async_trait;
+use auth_service::handlers::PrivilegeChange;
 use chrono::Utc;
 use redis::AsyncCommands;
+use ring::{constant_time, digest};
 use serde::{Deserialize, Serialize};
 use std::future::{ready, Ready};
 use std::pin::Pin;
@@ -74,9 +76,23 @@ impl Default for CsrfConfig {
 pub trait CsrfStore: Send + Sync {
     async fn generate(&self, session_id: &str, ttl: i64) -> Result<String, actix_web::Error>;
     async fn validate_and_consume(&self, session_id: &str, token: &str) -> bool;
+    /// Invalidate every token outstanding for the session and issue a fresh
+    /// one in a single step. Used when the session's privileges change (login,
+    /// logout), so tokens captured before the change stop working after it.
+    async fn rotate(&self, session_id: &str, ttl: i64) -> Result<String, actix_web::Error>;
     async fn cleanup_expired(&self);
 }
 
+/// Compare a stored token with a presented one in constant time
+///
+/// Both are hashed first so the comparison doesn't leak the token's length
+/// or how much of a guess matched.
+fn token_matches(stored: &str, presented: &str) -> bool {
+    let stored = digest::digest(&digest::SHA256, stored.as_bytes());
+    let presented = digest::digest(&digest::SHA256, presented.as_bytes());
+    constant_time::verify_slices_are_equal(stored.as_ref(), presented.as_ref()).is_ok()
+}
+
 pub struct InMemoryCsrfStore {
     // Changed from HashMap<String, CsrfToken> to HashMap<String, Vec<CsrfToken>>
     // to support multiple concurrent tokens per session (multi-tab scenarios)
@@ -142,7 +158,7 @@ impl CsrfStore for InMemoryCsrfStore {
 
         if let Some(session_tokens) = tokens_map.get_mut(session_id) {
             // Find the matching, non-expired token
-            if let Some(pos) = session_tokens.iter().position(|t| t.token == token && !t.is_expired()) {
+            if let Some(pos) = session_tokens.iter().position(|t| !t.is_expired() && token_matches(&t.token, token)) {
                 // Remove only the matched token (consume it)
                 session_tokens.remove(pos);
 
@@ -158,6 +174,19 @@ impl CsrfStore for InMemoryCsrfStore {
         false
     }
 
+    async fn rotate(&self, session_id: &str, ttl: i64) -> Result<String, actix_web::Error> {
+        let token = CsrfToken::new(ttl);
+        let token_str = token.token.clone();
+
+        // Replace under one write lock so no request can use the old token in between
+        let mut tokens_map = self.tokens.write().await;
+        if let Some(previous) = tokens_map.insert(session_id.to_string(), vec![token]) {
+            tracing::debug!("Rotated CSRF tokens for session {}, invalidated {}", session_id, previous.len());
+        }
+
+        Ok(token_str)
+    }
+
     async fn cleanup_expired(&self) {
         let mut tokens_map = self.tokens.write().await;
 
@@ -179,6 +208,8 @@ impl CsrfStore for InMemoryCsrfStore {
 pub trait RedisConnection: Send + Sync {
     async fn add_token(&self, key: String, token: String, ttl: u64) -> Result<(), redis::RedisError>;
     async fn remove_token(&self, key: String, token: String) -> Result<bool, redis::RedisError>;
+    /// Atomically replace every token under `key` with `token`
+    async fn replace_tokens(&self, key: String, token: String, ttl: u64) -> Result<(), redis::RedisError>;
 }
 
 #[async_trait]
@@ -205,6 +236,24 @@ impl RedisConnection for redis::aio::MultiplexedConnection {
         let removed: bool = conn.srem(key, token).await?;
         Ok(removed)
     }
+
+    async fn replace_tokens(&self, key: String, token: String, ttl: u64) -> Result<(), redis::RedisError> {
+        let mut conn = self.clone();
+        let ttl_secs = ttl.try_into().unwrap_or(3600);
+
+        let _: () = redis::pipe()
+            .atomic()
+            .del(&key)
+            .ignore()
+            .sadd(&key, &token)
+            .ignore()
+            .expire(&key, ttl_secs)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(())
+    }
 }
 
 pub struct RedisCsrfStore {
@@ -223,6 +272,15 @@ impl RedisCsrfStore {
     fn key(&self, session_id: &str) -> String {
         format!("{}{}", self.prefix, session_id)
     }
+
+    fn ttl(ttl: i64) -> u64 {
+        if ttl > 0 {
+            ttl.try_into().unwrap_or(3600)
+        } else {
+            log::warn!("Non-positive TTL provided: {}, using default", ttl);
+            3600
+        }
+    }
 }
 
 #[async_trait]
@@ -231,12 +289,7 @@ impl CsrfStore for RedisCsrfStore {
         let token = Uuid::new_v4().to_string();
 
         let key = self.key(session_id);
-        let u_ttl: u64 = if ttl > 0 {
-            ttl.try_into().unwrap_or(3600)
-        } else {
-            log::warn!("Non-positive TTL provided: {}, using default", ttl);
-            3600
-        };
+        let u_ttl = Self::ttl(ttl);
 
         self.redis.add_token(key, token.clone(), u_ttl).await.map_err(|e| {
             log::error!("Failed to store CSRF token in Redis: {}", e);
@@ -257,6 +310,21 @@ impl CsrfStore for RedisCsrfStore {
         }
     }
 
+    async fn rotate(&self, session_id: &str, ttl: i64) -> Result<String, actix_web::Error> {
+        let token = Uuid::new_v4().to_string();
+
+        // Replacing the whole set drops the old token with the rest
+        self.redis
+            .replace_tokens(self.key(session_id), token.clone(), Self::ttl(ttl))
+            .await
+            .map_err(|e| {
+                log::error!("Failed to rotate CSRF token in Redis: {}", e);
+                actix_web::error::ErrorInternalServerError("Failed to rotate CSRF token")
+            })?;
+
+        Ok(token)
+    }
+
     async fn cleanup_expired(&self) {
         // Redis handles TTL automatically
     }
@@ -347,7 +415,15 @@ where
                 if let Ok(ref mut response) = res {
                     if response.status().is_success() {
                         let ttl_i64 = i64::try_from(config.cookie_max_age).unwrap_or(i64::MAX);
-                        if let Ok(token) = store.generate(session_id.as_ref().unwrap(), ttl_i64).await {
+                        let sid = session_id.as_ref().unwrap();
+                        // Login and logout mark their responses so earlier tokens stop working
+                        let privilege_changed = response.response().extensions().contains::<PrivilegeChange>();
+                        let issued = if privilege_changed {
+                            store.rotate(sid, ttl_i64).await
+                        } else {
+                            store.generate(sid, ttl_i64).await
+                        };
+                        if let Ok(token) = issued {
                             let display_ttl = config.cookie_max_age.min(i64::MAX as u64);
                             let mut cookie = format!(
                                 "{}={}; SameSite=Strict; Path=/; Max-Age={}",
@@ -410,6 +486,9 @@ mod tests {
         async fn validate_and_consume(&self, _sid: &str, _token: &str) -> bool {
             true
         }
+        async fn rotate(&self, _sid: &str, _ttl: i64) -> Result<String, actix_web::Error> {
+            Ok("rotated-token".to_string())
+        }
         async fn cleanup_expired(&self) {}
     }
 
@@ -514,6 +593,11 @@ mod tests {
                 Ok(false)
             }
         }
+        async fn replace_tokens(&self, key: String, token: String, ttl: u64) -> Result<(), redis::RedisError> {
+            let mut data = self.data.write().await;
+            data.insert(key, (std::collections::HashSet::from([token]), ttl));
+            Ok(())
+        }
     }
 
     #[tokio::test]
@@ -690,4 +774,87 @@ mod tests {
         assert!(tokens.contains_key("session2"), "Valid token should still exist");
         assert!(!tokens.contains_key("session1"), "Expired token should be removed");
     }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("a1b2-c3d4", "a1b2-c3d4"));
+        assert!(!token_matches("a1b2-c3d4", "a1b2-c3d5"));
+        assert!(!token_matches("a1b2-c3d4", "a1b2"));
+        assert!(!token_matches("a1b2-c3d4", ""));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_rotate_invalidates_old_token() {
+        let store = InMemoryCsrfStore::new();
+        let session_id = "rotate-session";
+
+        let old_token = store.generate(session_id, 3600).await.unwrap();
+        let other_tab = store.generate(session_id, 3600).await.unwrap();
+        let new_token = store.rotate(session_id, 3600).await.unwrap();
+
+        assert_ne!(old_token, new_token);
+        assert!(!store.validate_and_consume(session_id, &old_token).await);
+        assert!(!store.validate_and_consume(session_id, &other_tab).await);
+        assert!(store.validate_and_consume(session_id, &new_token).await);
+    }
+
+    #[tokio::test]
+    async fn test_redis_rotate_invalidates_old_token() {
+        let mock_redis = Arc::new(MockRedis {
+            data: tokio::sync::RwLock::new(std::collections::HashMap::new()),
+        });
+        let store = RedisCsrfStore::new(mock_redis.clone());
+        let session_id = "rotate-session";
+
+        let old_token = store.generate(session_id, 300).await.unwrap();
+        let other_tab = store.generate(session_id, 300).await.unwrap();
+        let new_token = store.rotate(session_id, 300).await.unwrap();
+
+        assert_ne!(old_token, new_token);
+        assert_eq!(mock_redis.data.read().await.get("csrf:rotate-session").unwrap().1, 300);
+        assert!(!store.validate_and_consume(session_id, &old_token).await);
+        assert!(!store.validate_and_consume(session_id, &other_tab).await);
+        assert!(store.validate_and_consume(session_id, &new_token).await);
+    }
+
+    #[actix_web::test]
+    async fn test_privilege_change_rotates_session_tokens() {
+        let config = CsrfConfig {
+            cookie_name: "csrf".to_string(),
+            cookie_max_age: 3600,
+            header_name: "X-CSRF".to_string(),
+            secure_cookie: false,
+        };
+        let store = Arc::new(InMemoryCsrfStore::new());
+        let pre_auth = store.generate("123", 3600).await.unwrap();
+        let presented = store.generate("123", 3600).await.unwrap();
+
+        let srv = test::init_service(
+            App::new()
+                .wrap(CsrfMiddleware::new(config, store.clone()))
+                .route(
+                    "/login",
+                    web::post().to(|| async {
+                        let mut response = HttpResponse::Ok().finish();
+                        response.extensions_mut().insert(PrivilegeChange);
+                        response
+                    }),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .insert_header(("Cookie", "session_id=123"))
+            .insert_header(("X-CSRF", presented.as_str()))
+            .to_request();
+        let resp = test::call_service(&srv, req).await;
+        assert!(resp.status().is_success());
+
+        let cookie = resp.headers().get(header::SET_COOKIE).unwrap().to_str().unwrap();
+        let new_token = cookie.trim_start_matches("csrf=").split(';').next().unwrap();
+
+        assert!(!store.validate_and_consume("123", &pre_auth).await);
+        assert!(store.validate_and_consume("123", new_token).await);
+    }
 }
@@ -77,7 +77,7 @@ impl AppErrorResponse for AppError {
         let status_code = self.status_code().as_u16();
         let error_code = match self {
             AppError::DatabaseError(_) => "DATABASE_ERROR",
-            AppError::ValidationError(_) => "VALIDATION_ERROR",
+            AppError::ValidationError(_) | AppError::InvalidFields(_) => "VALIDATION_ERROR",
             AppError::AuthenticationError(_) => "AUTHENTICATION_ERROR",
             AppError::AuthorizationError(_) => "AUTHORIZATION_ERROR",
             AppError::NotFoundError(_) => "NOT_FOUND",
@@ -0,0 +1,596 @@
+//! Idempotency keys for POST requests
+//!
+//! Clients that retry a POST after a timeout send the same `Idempotency-Key`
+//! header on every attempt. The first attempt runs the handler and its
+//! response (status, headers, body) is cached for a TTL, keyed by caller,
+//! path and key. Retries replay the cached response instead of running the
+//! handler again. A retry that arrives while the first attempt is still
+//! running gets `409 Conflict` with `REQUEST_IN_PROGRESS`.
+//!
+//! The caller is the verified token subject; requests without a valid token
+//! are passed through uncached. Only `2xx` responses are cached, so a retry
+//! after an error runs the handler again.
+
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method, StatusCode,
+    },
+    Error, HttpResponse,
+};
+use async_trait::async_trait;
+use auth_service::identity::extract_token_user_id;
+use futures_util::future::LocalBoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Ready;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Request header carrying the client-chosen key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Response header set on replayed responses
+pub const IDEMPOTENCY_REPLAYED_HEADER: &str = "Idempotency-Replayed";
+
+/// Default lifetime of a cached response in seconds
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Longest accepted `Idempotency-Key` value
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Response captured from the first attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    fn to_http_response(&self) -> HttpResponse {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+        let mut builder = HttpResponse::build(status);
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::from_str(value)) {
+                builder.append_header((name, value));
+            }
+        }
+        builder.insert_header((IDEMPOTENCY_REPLAYED_HEADER, "true"));
+        builder.body(self.body.clone())
+    }
+}
+
+/// Outcome of claiming a key
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdempotencyClaim {
+    /// First use of the key; the caller should run the handler
+    Started,
+    /// Another request with this key has not finished yet
+    InProgress,
+    /// A response was already recorded for this key
+    Completed(CachedResponse),
+}
+
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Atomically claim `key`, or report what is already recorded for it
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<IdempotencyClaim, actix_web::Error>;
+    /// Record the response for a claimed key
+    async fn complete(&self, key: &str, response: &CachedResponse, ttl: Duration) -> Result<(), actix_web::Error>;
+    /// Release a claimed key without recording a response, so a retry runs again
+    async fn release(&self, key: &str);
+}
+
+enum InMemoryEntry {
+    InProgress,
+    Completed(CachedResponse),
+}
+
+/// Process-local store, used when Redis is not configured
+#[derive(Default)]
+pub struct InMemoryIdempotencyStore {
+    entries: tokio::sync::Mutex<HashMap<String, (InMemoryEntry, Instant)>>,
+}
+
+impl InMemoryIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<IdempotencyClaim, actix_web::Error> {
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+
+        match entries.get(key) {
+            Some((InMemoryEntry::InProgress, _)) => Ok(IdempotencyClaim::InProgress),
+            Some((InMemoryEntry::Completed(response), _)) => Ok(IdempotencyClaim::Completed(response.clone())),
+            None => {
+                entries.insert(key.to_string(), (InMemoryEntry::InProgress, now + ttl));
+                Ok(IdempotencyClaim::Started)
+            },
+        }
+    }
+
+    async fn complete(&self, key: &str, response: &CachedResponse, ttl: Duration) -> Result<(), actix_web::Error> {
+        let mut entries = self.entries.lock().await;
+        entries.insert(
+            key.to_string(),
+            (InMemoryEntry::Completed(response.clone()), Instant::now() + ttl),
+        );
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) {
+        self.entries.lock().await.remove(key);
+    }
+}
+
+/// Marker stored while the first attempt is running
+const IN_PROGRESS_MARKER: &str = "in_progress";
+
+/// Redis-backed store shared by every instance
+pub struct RedisIdempotencyStore {
+    redis: redis::aio::MultiplexedConnection,
+    prefix: String,
+}
+
+impl RedisIdempotencyStore {
+    pub fn new(redis: redis::aio::MultiplexedConnection) -> Self {
+        Self {
+            redis,
+            prefix: "idempotency:".to_string(),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+fn store_error(e: impl std::fmt::Display) -> actix_web::Error {
+    log::error!("Idempotency store error: {}", e);
+    actix_web::error::ErrorInternalServerError("Idempotency store unavailable")
+}
+
+#[async_trait]
+impl IdempotencyStore for RedisIdempotencyStore {
+    async fn claim(&self, key: &str, ttl: Duration) -> Result<IdempotencyClaim, actix_web::Error> {
+        let mut conn = self.redis.clone();
+        let key = self.key(key);
+
+        // SET NX claims the key atomically across instances
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(IN_PROGRESS_MARKER)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async(&mut conn)
+            .await
+            .map_err(store_error)?;
+        if claimed.is_some() {
+            return Ok(IdempotencyClaim::Started);
+        }
+
+        let existing: Option<String> = redis::cmd("GET").arg(&key).query_async(&mut conn).await.map_err(store_error)?;
+        match existing.as_deref() {
+            // Expired between SET and GET; treat as in progress and let the client retry
+            None | Some(IN_PROGRESS_MARKER) => Ok(IdempotencyClaim::InProgress),
+            Some(value) => serde_json::from_str(value)
+                .map(IdempotencyClaim::Completed)
+                .map_err(store_error),
+        }
+    }
+
+    async fn complete(&self, key: &str, response: &CachedResponse, ttl: Duration) -> Result<(), actix_web::Error> {
+        let mut conn = self.redis.clone();
+        let value = serde_json::to_string(response).map_err(store_error)?;
+        redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(value)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async::<()>(&mut conn)
+            .await
+            .map_err(store_error)
+    }
+
+    async fn release(&self, key: &str) {
+        let mut conn = self.redis.clone();
+        if let Err(e) = redis::cmd("DEL").arg(self.key(key)).query_async::<()>(&mut conn).await {
+            log::warn!("Failed to release idempotency key: {}", e);
+        }
+    }
+}
+
+/// Idempotency middleware
+///
+/// Only POST requests with an `Idempotency-Key` header are affected; all
+/// other requests pass straight through. Wrap it inside `Compress`: the cache
+/// key ignores `Accept-Encoding`, so it must store unencoded bodies.
+///
+/// # Example
+///
+/// ```ignore
+/// let idempotency = Idempotency::new(Arc::new(InMemoryIdempotencyStore::new()));
+/// HttpServer::new(move || App::new().wrap(idempotency.clone()))
+/// ```
+#[derive(Clone)]
+pub struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    pub fn new(store: Arc<dyn IdempotencyStore>) -> Self {
+        Self {
+            store,
+            ttl: Duration::from_secs(DEFAULT_IDEMPOTENCY_TTL_SECS),
+        }
+    }
+
+    /// Read `IDEMPOTENCY_TTL_SECS`, falling back to the default for missing
+    /// or invalid values
+    pub fn from_env(store: Arc<dyn IdempotencyStore>) -> Self {
+        let ttl_secs = std::env::var("IDEMPOTENCY_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS);
+
+        Self::new(store).with_ttl(Duration::from_secs(ttl_secs))
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(IdempotencyMiddleware {
+            service: Arc::new(service),
+            store: self.store.clone(),
+            ttl: self.ttl,
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Arc<S>,
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let idempotency_key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+            Some(value) if req.method() == Method::POST => value.to_str().ok().map(str::to_string),
+            _ => {
+                let fut = self.service.call(req);
+                return Box::pin(async move { fut.await.map(ServiceResponse::map_into_boxed_body) });
+            },
+        };
+
+        let idempotency_key = match idempotency_key {
+            Some(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key,
+            _ => {
+                let response = HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "INVALID_IDEMPOTENCY_KEY",
+                    "message": format!(
+                        "{} must be 1-{} visible ASCII characters",
+                        IDEMPOTENCY_KEY_HEADER, MAX_IDEMPOTENCY_KEY_LEN
+                    ),
+                }));
+                return Box::pin(async move { Ok(req.into_response(response)) });
+            },
+        };
+
+        let service = self.service.clone();
+        let store = self.store.clone();
+        let ttl = self.ttl;
+
+        Box::pin(async move {
+            // Keys belong to the verified caller so users never see each other's responses
+            let caller: Uuid = match extract_token_user_id(req.request()).await {
+                Ok(user_id) => user_id,
+                Err(_) => return service.call(req).await.map(ServiceResponse::map_into_boxed_body),
+            };
+            let store_key = format!("{}:{}:{}", caller, req.path(), idempotency_key);
+
+            match store.claim(&store_key, ttl).await? {
+                IdempotencyClaim::Completed(cached) => {
+                    tracing::debug!("Replaying cached response for {} {}", req.method(), req.path());
+                    return Ok(req.into_response(cached.to_http_response()));
+                },
+                IdempotencyClaim::InProgress => {
+                    let response = HttpResponse::Conflict().json(serde_json::json!({
+                        "error": "REQUEST_IN_PROGRESS",
+                        "message": "A request with this idempotency key is still being processed",
+                    }));
+                    return Ok(req.into_response(response));
+                },
+                IdempotencyClaim::Started => {},
+            }
+
+            let res = match service.call(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    store.release(&store_key).await;
+                    return Err(e);
+                },
+            };
+
+            if !res.status().is_success() {
+                store.release(&store_key).await;
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let (http_req, http_res) = res.into_parts();
+            let (head, body) = http_res.into_parts();
+            let bytes = match body::to_bytes(body).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    store.release(&store_key).await;
+                    let e: Box<dyn std::error::Error> = e.into();
+                    return Err(actix_web::error::ErrorInternalServerError(e.to_string()));
+                },
+            };
+
+            let cached = CachedResponse {
+                status: head.status().as_u16(),
+                headers: head
+                    .headers()
+                    .iter()
+                    .filter(|(name, _)| *name != header::SET_COOKIE)
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                    .collect(),
+                body: bytes.to_vec(),
+            };
+            if let Err(e) = store.complete(&store_key, &cached, ttl).await {
+                log::warn!("Failed to cache idempotent response: {}", e);
+                store.release(&store_key).await;
+            }
+
+            Ok(ServiceResponse::new(http_req, head.set_body(BoxBody::new(bytes))))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+    use auth_service::jwt::{JwtConfig, JwtService};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Notify;
+
+    const USER_1: &str = "550e8400-e29b-41d4-a716-446655440000";
+    const USER_2: &str = "660e8400-e29b-41d4-a716-446655440000";
+
+    fn jwt_config() -> JwtConfig {
+        JwtConfig::new("idempotency-test-secret".to_string(), 3600, 86400)
+    }
+
+    fn bearer(user_id: &str) -> String {
+        let token = JwtService::new(jwt_config()).generate_access_token(user_id, "a@b.c", "user").unwrap();
+        format!("Bearer {}", token)
+    }
+
+    async fn create(calls: web::Data<AtomicUsize>) -> HttpResponse {
+        let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        HttpResponse::Created().json(serde_json::json!({ "id": n }))
+    }
+
+    // Handler that blocks until the test releases it, so the request stays in flight
+    async fn held(release: web::Data<Notify>) -> HttpResponse {
+        release.notified().await;
+        HttpResponse::Created().finish()
+    }
+
+    fn post(uri: &str, key: &str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, bearer(USER_1)))
+            .insert_header((IDEMPOTENCY_KEY_HEADER, key))
+    }
+
+    async fn fail(calls: web::Data<AtomicUsize>) -> HttpResponse {
+        calls.fetch_add(1, Ordering::SeqCst);
+        HttpResponse::UnprocessableEntity().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_retry_replays_first_response() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(calls.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .route("/documents", web::post().to(create)),
+        )
+        .await;
+
+        let first = test::call_service(&app, post("/documents", "abc").to_request()).await;
+        assert_eq!(first.status(), 201);
+        assert!(first.headers().get(IDEMPOTENCY_REPLAYED_HEADER).is_none());
+        let first_body = test::read_body(first).await;
+
+        let retry = test::call_service(&app, post("/documents", "abc").to_request()).await;
+        assert_eq!(retry.status(), 201);
+        assert_eq!(retry.headers().get(IDEMPOTENCY_REPLAYED_HEADER).unwrap(), "true");
+        assert_eq!(retry.headers().get(header::CONTENT_TYPE).unwrap(), "application/json");
+        assert_eq!(test::read_body(retry).await, first_body);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A different key runs the handler again
+        let other = test::call_service(&app, post("/documents", "def").to_request()).await;
+        assert_eq!(other.status(), 201);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Requests without a key are never cached
+        let req = test::TestRequest::post().uri("/documents").to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_keys_are_scoped_per_user() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(calls.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .route("/documents", web::post().to(create)),
+        )
+        .await;
+
+        test::call_service(&app, post("/documents", "abc").to_request()).await;
+        let req = post("/documents", "abc").insert_header((header::AUTHORIZATION, bearer(USER_2))).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.headers().get(IDEMPOTENCY_REPLAYED_HEADER).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // A spoofed X-User-Id header does not move the key to another caller
+        let req = post("/documents", "abc").insert_header(("X-User-Id", USER_2)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(IDEMPOTENCY_REPLAYED_HEADER).unwrap(), "true");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_replay_is_encoded_for_the_retrying_client() {
+        // Wrapped as in main.rs: Compress outside, so the cached body is unencoded
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(calls.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .wrap(actix_web::middleware::Compress::default())
+                .route("/documents", web::post().to(create)),
+        )
+        .await;
+
+        let req = post("/documents", "abc").insert_header((header::ACCEPT_ENCODING, "gzip")).to_request();
+        let first = test::call_service(&app, req).await;
+        assert_eq!(first.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+
+        // A retry that accepts no encoding gets a body it can read
+        let req = post("/documents", "abc").insert_header((header::ACCEPT_ENCODING, "identity")).to_request();
+        let retry = test::call_service(&app, req).await;
+        assert_eq!(retry.headers().get(IDEMPOTENCY_REPLAYED_HEADER).unwrap(), "true");
+        assert!(retry.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(test::read_body(retry).await, r#"{"id":1}"#);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_requests_without_a_verified_caller_are_not_cached() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(calls.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .route("/documents", web::post().to(create)),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = test::TestRequest::post()
+                .uri("/documents")
+                .insert_header(("X-User-Id", USER_1))
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "abc"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.headers().get(IDEMPOTENCY_REPLAYED_HEADER).is_none());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_error_responses_are_not_cached() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(calls.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .route("/documents", web::post().to(fail)),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let resp = test::call_service(&app, post("/documents", "abc").to_request()).await;
+            assert_eq!(resp.status(), 422);
+            assert!(resp.headers().get(IDEMPOTENCY_REPLAYED_HEADER).is_none());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_concurrent_duplicate_gets_conflict() {
+        let release = web::Data::new(Notify::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(release.clone())
+                .app_data(web::Data::new(jwt_config()))
+                .wrap(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new())))
+                .route("/spaces", web::post().to(held)),
+        )
+        .await;
+
+        let mut in_flight = Box::pin(test::call_service(&app, post("/spaces", "abc").to_request()));
+        assert!(futures_util::poll!(in_flight.as_mut()).is_pending());
+
+        let duplicate = test::call_service(&app, post("/spaces", "abc").to_request()).await;
+        assert_eq!(duplicate.status(), 409);
+        let body: serde_json::Value = test::read_body_json(duplicate).await;
+        assert_eq!(body["error"], "REQUEST_IN_PROGRESS");
+
+        release.notify_waiters();
+        assert_eq!(in_flight.await.status(), 201);
+
+        // Once finished, the same key replays instead of conflicting
+        let retry = test::call_service(&app, post("/spaces", "abc").to_request()).await;
+        assert_eq!(retry.status(), 201);
+        assert_eq!(retry.headers().get(IDEMPOTENCY_REPLAYED_HEADER).unwrap(), "true");
+    }
+}
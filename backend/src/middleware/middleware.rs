@@ -4,7 +4,7 @@ use actix_web::{
     Error, HttpMessage,
 };
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use auth_service::jwt::{verify_access_token, JwtConfig};
 use std::future::{ready, Ready};
 use std::task::{Context, Poll};
 use std::sync::Arc;
@@ -19,7 +19,7 @@ pub struct AuthUser {
 
 pub struct JwtMiddleware<S> {
     service: S,
-    jwt_secret: Arc<String>,
+    jwt_config: Arc<JwtConfig>,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtMiddleware<S>
@@ -37,32 +37,17 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let jwt_secret = self.jwt_secret.clone();
+        let jwt_config = self.jwt_config.clone();
         let auth_header = req.headers().get("authorization").cloned();
         let fut = self.service.call(req);
 
         let auth_user = auth_header.and_then(|header_value| {
             header_value.to_str().ok().and_then(|token_str| {
                 if let Some(token) = token_str.strip_prefix("Bearer ") {
-                    let decoding_key = DecodingKey::from_secret(jwt_secret.as_bytes());
-                    let validation = Validation::default();
-                    
-                    decode::<serde_json::Value>(token, &decoding_key, &validation).ok().map(|token_data| {
-                        let claims = token_data.claims;
-                        let user_id = claims.get("sub")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or_default()
-                            .to_string();
-                        let email = claims.get("email")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or_default()
-                            .to_string();
-                        let role = claims.get("role")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("user")
-                            .to_string();
-                        
-                        AuthUser { user_id, email, role }
+                    verify_access_token(token, &jwt_config).ok().map(|claims| {
+                        let role = if claims.role.is_empty() { "user".to_string() } else { claims.role };
+
+                        AuthUser { user_id: claims.sub, email: claims.email, role }
                     })
                 } else {
                     None
@@ -86,7 +71,7 @@ where
 }
 
 pub struct JwtAuth {
-    jwt_secret: Arc<String>,
+    jwt_config: Arc<JwtConfig>,
 }
 
 impl<S, B> Transform<S, ServiceRequest> for JwtAuth
@@ -104,14 +89,14 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(JwtMiddleware {
             service,
-            jwt_secret: self.jwt_secret.clone(),
+            jwt_config: self.jwt_config.clone(),
         }))
     }
 }
 
 pub fn require_auth(jwt_secret: &str) -> JwtAuth {
     JwtAuth {
-        jwt_secret: Arc::new(jwt_secret.to_string()),
+        jwt_config: Arc::new(JwtConfig::new(jwt_secret.to_string(), 0, 0)),
     }
 }
 
@@ -3,11 +3,19 @@ pub mod error_handler;
 pub mod security_headers;
 pub mod validation;
 pub mod csrf;
+pub mod concurrency;
+pub mod compression;
+pub mod idempotency;
+pub mod request_id;
 
 pub use error_handler::{ErrorHandler, ErrorResponse, ErrorHandlerMiddleware};
 pub use security_headers::{SecurityHeaders, SecurityHeadersMiddleware};
 pub use validation::{
     validate_request_size, validate_content_type, validate_request_size_fn,
-    validate_content_type_fn, ValidationError, ValidationResult,
+    validate_content_type_fn, RequestSizeLimit, ValidationError, ValidationResult,
 };
 pub use csrf::{CsrfMiddleware, CsrfConfig, CsrfStore, InMemoryCsrfStore, RedisCsrfStore};
+pub use concurrency::ConcurrencyLimit;
+pub use idempotency::{Idempotency, IdempotencyStore, InMemoryIdempotencyStore, RedisIdempotencyStore};
+pub use compression::CompressionPolicy;
+pub use request_id::PropagateRequestId;
@@ -0,0 +1,195 @@
+//! Request correlation ids
+//!
+//! Every request gets an id: the client's `X-Request-Id` when it sent a
+//! usable one, otherwise a fresh UUID. The id is stored in the request
+//! extensions as a `RequestId`, written back to the request header so
+//! downstream code sees the same value, echoed in the response, and recorded
+//! on a tracing span wrapping the request so every log line emitted while
+//! handling it carries the id. Errors from inner middleware are turned into
+//! their responses here so they carry the id as well.
+
+use actix_web::{
+    body::EitherBody,
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use shared_models::request_id::{RequestId, REQUEST_ID_HEADER};
+use std::future::Ready;
+use tracing::Instrument;
+
+/// Request id middleware
+///
+/// Wrap it outside the other middleware so shed and rejected requests are
+/// tagged too.
+#[derive(Clone, Default)]
+pub struct PropagateRequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for PropagateRequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = PropagateRequestIdMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(PropagateRequestIdMiddleware { service }))
+    }
+}
+
+pub struct PropagateRequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for PropagateRequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let request_id = RequestId::from_header(
+            req.headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|value| value.to_str().ok()),
+        );
+        // Only printable ASCII survives `from_header`, so this can't fail
+        let header_value = HeaderValue::from_str(request_id.as_str()).ok();
+        if let Some(value) = &header_value {
+            req.headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), value.clone());
+        }
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.path(),
+        );
+        req.extensions_mut().insert(request_id);
+        let http_req = req.request().clone();
+
+        // Inner middleware may log before its future is first polled
+        let fut = span.in_scope(|| self.service.call(req));
+
+        Box::pin(
+            async move {
+                let mut res = match fut.await {
+                    Ok(res) => res.map_into_left_body(),
+                    Err(e) => ServiceResponse::from_err(e, http_req).map_into_right_body(),
+                };
+                if let Some(value) = header_value {
+                    res.headers_mut()
+                        .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                }
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpRequest, HttpResponse};
+
+    // Echo the id the handler sees in the extensions
+    async fn echo(req: HttpRequest) -> HttpResponse {
+        let id = req.extensions().get::<RequestId>().map(|id| id.to_string());
+        HttpResponse::Ok().body(id.unwrap_or_default())
+    }
+
+    #[actix_web::test]
+    async fn test_response_carries_generated_request_id() {
+        let app = test::init_service(
+            App::new()
+                .wrap(PropagateRequestId)
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/echo").to_request()).await;
+
+        assert_eq!(resp.status(), 200);
+        let header = resp.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+        // The handler saw the same id
+        assert_eq!(test::read_body(resp).await, header.as_bytes());
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_carries_request_id() {
+        let app = test::init_service(
+            App::new()
+                // Fails like the CSRF check does, before any handler runs
+                .wrap_fn(|_req, _srv| {
+                    std::future::ready(Err::<ServiceResponse, _>(actix_web::error::ErrorForbidden("denied")))
+                })
+                .wrap(PropagateRequestId)
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header(("X-Request-Id", "client-trace-42"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), 403);
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "client-trace-42");
+    }
+
+    #[actix_web::test]
+    async fn test_supplied_request_id_is_preserved() {
+        let app = test::init_service(
+            App::new()
+                .wrap(PropagateRequestId)
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header(("X-Request-Id", "client-trace-42"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "client-trace-42");
+        assert_eq!(test::read_body(resp).await, "client-trace-42".as_bytes());
+    }
+
+    #[actix_web::test]
+    async fn test_unusable_request_id_is_replaced() {
+        let app = test::init_service(
+            App::new()
+                .wrap(PropagateRequestId)
+                .route("/echo", web::get().to(echo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header(("X-Request-Id", "a".repeat(500)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        let header = resp.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(header).is_ok());
+    }
+}
@@ -3,6 +3,9 @@
 //! Provides request validation utilities for security.
 //! Validates incoming request size and content-type before reaching handlers.
 //!
+//! `RequestSizeLimit` applies per-path-prefix body limits as middleware, so
+//! uploads can be large while JSON endpoints stay small.
+//!
 //! Usage: Add validation logic in your handlers using the helper functions.
 //! Example:
 //! ```ignore
@@ -14,10 +17,16 @@
 //! ```
 
 use actix_web::{
-    dev::ServiceRequest,
-    http::StatusCode,
-    HttpResponse,
+    body::EitherBody,
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::{header, StatusCode},
+    Error, HttpResponse, ResponseError,
 };
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use std::future::Ready;
+use std::rc::Rc;
 use thiserror::Error;
 
 /// Validation errors
@@ -44,9 +53,13 @@ impl actix_web::ResponseError for ValidationError {
 
     fn error_response(&self) -> actix_web::HttpResponse {
         let message = self.to_string();
+        let code = match self {
+            ValidationError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            _ => "VALIDATION_ERROR",
+        };
         HttpResponse::build(self.status_code())
             .json(serde_json::json!({
-                "error": "VALIDATION_ERROR",
+                "error": code,
                 "message": message,
             }))
     }
@@ -119,6 +132,153 @@ pub fn validate_content_type_fn() -> impl Fn(&ServiceRequest) -> ValidationResul
     |req: &ServiceRequest| validate_content_type(req)
 }
 
+/// Default body limit for routes without a more specific prefix (256KB)
+pub const DEFAULT_REQUEST_BODY_LIMIT: usize = 256 * 1024;
+
+/// Body limit for document content and sync updates, matching the largest
+/// document the server accepts (10MB)
+pub const DOCUMENT_BODY_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Prefixes that carry document content or sync updates
+pub const DOCUMENT_BODY_PREFIXES: [&str; 3] = ["/api/v1/documents", "/api/v1/space-docs", "/api/v1/sync"];
+
+/// Per-path-prefix request body limits
+///
+/// The longest matching prefix wins; paths matching no prefix get the
+/// default. Requests whose `Content-Length` exceeds the limit are rejected
+/// with `413 PAYLOAD_TOO_LARGE` before the body is read. Bodies without a
+/// `Content-Length` are cut off with a payload overflow error as soon as they
+/// cross the limit.
+///
+/// # Example
+///
+/// ```ignore
+/// let limits = RequestSizeLimit::new(256 * 1024).with_prefix("/api/v1/files/upload", 50 * 1024 * 1024);
+/// App::new().wrap(limits.clone())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestSizeLimit {
+    default_limit: usize,
+    /// Sorted longest prefix first
+    prefixes: Vec<(String, usize)>,
+}
+
+impl RequestSizeLimit {
+    pub fn new(default_limit: usize) -> Self {
+        Self {
+            default_limit,
+            prefixes: Vec::new(),
+        }
+    }
+
+    /// Read the default limit from `REQUEST_BODY_LIMIT_BYTES`, falling back to
+    /// [`DEFAULT_REQUEST_BODY_LIMIT`] for missing or invalid values
+    pub fn from_env() -> Self {
+        let default_limit = std::env::var("REQUEST_BODY_LIMIT_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_REQUEST_BODY_LIMIT);
+
+        Self::new(default_limit)
+    }
+
+    /// Limits the server runs with: the `from_env` default, document and sync
+    /// bodies up to [`DOCUMENT_BODY_LIMIT`], and uploads up to `max_upload_bytes`
+    pub fn for_api(max_upload_bytes: usize) -> Self {
+        DOCUMENT_BODY_PREFIXES
+            .iter()
+            .fold(Self::from_env(), |limits, prefix| limits.with_prefix(*prefix, DOCUMENT_BODY_LIMIT))
+            .with_prefix("/api/v1/files/upload", max_upload_bytes)
+    }
+
+    /// Apply `max_bytes` to every path starting with `prefix`
+    pub fn with_prefix(mut self, prefix: impl Into<String>, max_bytes: usize) -> Self {
+        let prefix = prefix.into();
+        self.prefixes.retain(|(p, _)| *p != prefix);
+        self.prefixes.push((prefix, max_bytes));
+        self.prefixes.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        self
+    }
+
+    /// Body limit that applies to `path`
+    pub fn limit_for(&self, path: &str) -> usize {
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map_or(self.default_limit, |(_, max)| *max)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequestSizeLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestSizeLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(RequestSizeLimitMiddleware {
+            service,
+            limits: Rc::new(self.clone()),
+        }))
+    }
+}
+
+pub struct RequestSizeLimitMiddleware<S> {
+    service: S,
+    limits: Rc<RequestSizeLimit>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestSizeLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let max_bytes = self.limits.limit_for(req.path());
+
+        if req.headers().contains_key(header::CONTENT_LENGTH) {
+            if let Err(e) = validate_request_size(&req, max_bytes) {
+                let response = e.error_response();
+                return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+            }
+        } else {
+            // No declared length: count bytes as they arrive instead
+            let mut seen = 0usize;
+            let limited = req.take_payload().map(move |chunk| {
+                let chunk = chunk?;
+                seen += chunk.len();
+                if seen > max_bytes {
+                    Err(PayloadError::Overflow)
+                } else {
+                    Ok(chunk)
+                }
+            });
+            req.set_payload(Payload::from(
+                Box::pin(limited) as actix_web::dev::BoxedPayloadStream
+            ));
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,4 +366,80 @@ mod tests {
         let result = validate_content_type(&req);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_request_size_limit_matches_longest_prefix() {
+        let limits = RequestSizeLimit::new(1024)
+            .with_prefix("/api/v1", 2048)
+            .with_prefix("/api/v1/files/upload", 50 * 1024 * 1024);
+
+        assert_eq!(limits.limit_for("/api/v1/documents"), 2048);
+        assert_eq!(limits.limit_for("/api/v1/files/upload"), 50 * 1024 * 1024);
+        assert_eq!(limits.limit_for("/api/v1/files/upload/chunked/abc"), 50 * 1024 * 1024);
+        assert_eq!(limits.limit_for("/api/v1/files/123"), 2048);
+        assert_eq!(limits.limit_for("/health"), 1024);
+    }
+
+    async fn echo(body: actix_web::web::Bytes) -> HttpResponse {
+        HttpResponse::Ok().body(body)
+    }
+
+    #[actix_web::test]
+    async fn test_request_size_limit_rejects_oversized_bodies() {
+        use actix_web::{test, web, App};
+
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestSizeLimit::new(16).with_prefix("/upload", 1024))
+                .route("/documents", web::post().to(echo))
+                .route("/upload", web::post().to(echo)),
+        )
+        .await;
+
+        // Over the JSON limit
+        let req = test::TestRequest::post().uri("/documents").set_payload(vec![b'a'; 64]).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "PAYLOAD_TOO_LARGE");
+
+        // The same body is fine under the upload prefix
+        let req = test::TestRequest::post().uri("/upload").set_payload(vec![b'a'; 64]).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        // Within the default limit
+        let req = test::TestRequest::post().uri("/documents").set_payload(vec![b'a'; 8]).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_api_limits_accept_large_document_and_sync_bodies() {
+        use actix_web::{test, web, App};
+
+        let app = test::init_service(
+            App::new()
+                .wrap(RequestSizeLimit::for_api(50 * 1024 * 1024))
+                .route("/api/v1/documents/{id}/content", web::patch().to(echo))
+                .route("/api/v1/sync/documents/{id}", web::post().to(echo))
+                .route("/api/v1/auth/login", web::post().to(echo)),
+        )
+        .await;
+        let body = vec![b'a'; DEFAULT_REQUEST_BODY_LIMIT + 1];
+
+        let req = test::TestRequest::patch()
+            .uri("/api/v1/documents/abc/content")
+            .set_payload(body.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/sync/documents/abc")
+            .set_payload(body.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        // Everything else keeps the default limit
+        let req = test::TestRequest::post().uri("/api/v1/auth/login").set_payload(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
 }
@@ -2,16 +2,43 @@
 //!
 //! This module provides:
 //! - Structured logging with JSON support
-//! - Request latency metrics
+//! - Request latency metrics, exposed in Prometheus format at `/metrics`
 //! - Error rate tracking
 //! - Distributed tracing for sync operations
 
 pub mod security_audit;
 
-use actix_web::web;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header,
+    web, Error, HttpRequest, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Ready;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tracing::{Level, span};
+use websocket_service::{SessionStore, SESSION_STORE};
+
+/// Upper bounds of the request latency histogram buckets, in milliseconds
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Endpoint label for requests that matched no route, so probing random
+/// paths cannot grow the label set without bound
+const UNMATCHED_ENDPOINT: &str = "unmatched";
+
+/// Per-endpoint counters, keyed by method and route pattern
+#[derive(Debug, Default, Clone)]
+struct EndpointStats {
+    statuses: BTreeMap<u16, u64>,
+    /// Non-cumulative counts per bucket; the last slot holds `+Inf`
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    count: u64,
+    sum_ms: u64,
+}
 
 /// Request metrics aggregated across all endpoints
 #[derive(Debug, Default)]
@@ -20,17 +47,13 @@ pub struct RequestMetrics {
     pub successful_requests: AtomicU64,
     pub failed_requests: AtomicU64,
     pub total_latency_ms: AtomicU64,
+    endpoints: Mutex<BTreeMap<(String, String), EndpointStats>>,
 }
 
 impl RequestMetrics {
     /// Create a new metrics collector
     pub fn new() -> Self {
-        Self {
-            total_requests: AtomicU64::new(0),
-            successful_requests: AtomicU64::new(0),
-            failed_requests: AtomicU64::new(0),
-            total_latency_ms: AtomicU64::new(0),
-        }
+        Self::default()
     }
 
     /// Record a completed request with latency
@@ -45,6 +68,28 @@ impl RequestMetrics {
         }
     }
 
+    /// Record a completed request against its endpoint
+    ///
+    /// `endpoint` should be the route pattern (`/api/v1/documents/{id}`),
+    /// not the concrete path, to keep label cardinality bounded.
+    pub fn record_endpoint(&self, method: &str, endpoint: &str, status: u16, latency_ms: u64) {
+        self.record_request(latency_ms, status < 400);
+
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = endpoints
+            .entry((method.to_string(), endpoint.to_string()))
+            .or_default();
+        *stats.statuses.entry(status).or_insert(0) += 1;
+        stats.buckets[bucket] += 1;
+        stats.count += 1;
+        stats.sum_ms += latency_ms;
+    }
+
     /// Get current metrics snapshot
     pub fn snapshot(&self) -> MetricsSnapshot {
         MetricsSnapshot {
@@ -54,6 +99,117 @@ impl RequestMetrics {
             total_latency_ms: self.total_latency_ms.load(Ordering::Relaxed),
         }
     }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let endpoints = self
+            .endpoints
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let mut out = String::new();
+
+        out.push_str("# HELP miniwiki_requests_total Total HTTP requests handled.\n");
+        out.push_str("# TYPE miniwiki_requests_total counter\n");
+        let _ = writeln!(out, "miniwiki_requests_total {}", snapshot.total_requests);
+        out.push_str("# HELP miniwiki_requests_failed_total HTTP requests that ended with a 4xx or 5xx status.\n");
+        out.push_str("# TYPE miniwiki_requests_failed_total counter\n");
+        let _ = writeln!(out, "miniwiki_requests_failed_total {}", snapshot.failed_requests);
+
+        out.push_str("# HELP miniwiki_http_requests_total HTTP requests by endpoint and status code.\n");
+        out.push_str("# TYPE miniwiki_http_requests_total counter\n");
+        for ((method, endpoint), stats) in &endpoints {
+            for (status, count) in &stats.statuses {
+                let _ = writeln!(
+                    out,
+                    "miniwiki_http_requests_total{{method=\"{}\",endpoint=\"{}\",status=\"{}\"}} {}",
+                    escape_label(method),
+                    escape_label(endpoint),
+                    status,
+                    count
+                );
+            }
+        }
+
+        out.push_str("# HELP miniwiki_http_request_duration_seconds HTTP request latency by endpoint.\n");
+        out.push_str("# TYPE miniwiki_http_request_duration_seconds histogram\n");
+        for ((method, endpoint), stats) in &endpoints {
+            let labels = format!(
+                "method=\"{}\",endpoint=\"{}\"",
+                escape_label(method),
+                escape_label(endpoint)
+            );
+            let mut cumulative = 0u64;
+            for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(stats.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "miniwiki_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels,
+                    *bound as f64 / 1000.0,
+                    cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "miniwiki_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, stats.count
+            );
+            let _ = writeln!(
+                out,
+                "miniwiki_http_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                stats.sum_ms as f64 / 1000.0
+            );
+            let _ = writeln!(
+                out,
+                "miniwiki_http_request_duration_seconds_count{{{}}} {}",
+                labels, stats.count
+            );
+        }
+
+        out
+    }
+}
+
+/// Number of documents listed individually in the per-document session gauge
+pub const TOP_DOCUMENTS_REPORTED: usize = 10;
+
+/// Render live collaboration load gauges in the Prometheus text format
+///
+/// Only the busiest `TOP_DOCUMENTS_REPORTED` documents get a per-document
+/// series, which keeps the label set bounded.
+pub fn render_session_gauges(store: &SessionStore) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP miniwiki_ws_sessions Live WebSocket collaboration sessions.\n");
+    out.push_str("# TYPE miniwiki_ws_sessions gauge\n");
+    let _ = writeln!(out, "miniwiki_ws_sessions {}", store.total_session_count());
+
+    out.push_str("# HELP miniwiki_ws_active_documents Documents with at least one live session.\n");
+    out.push_str("# TYPE miniwiki_ws_active_documents gauge\n");
+    let _ = writeln!(out, "miniwiki_ws_active_documents {}", store.active_document_count());
+
+    out.push_str("# HELP miniwiki_ws_document_sessions Live sessions for the busiest documents.\n");
+    out.push_str("# TYPE miniwiki_ws_document_sessions gauge\n");
+    for (document_id, count) in store.top_documents_by_sessions(TOP_DOCUMENTS_REPORTED) {
+        let _ = writeln!(
+            out,
+            "miniwiki_ws_document_sessions{{document_id=\"{}\"}} {}",
+            document_id, count
+        );
+    }
+
+    out
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
 }
 
 /// Snapshot of current metrics state
@@ -85,9 +241,10 @@ impl MetricsSnapshot {
     }
 }
 
-/// Latency histogram buckets for request timing (in milliseconds)
-
 /// Request timing middleware for metrics collection
+///
+/// Records every response against its matched route pattern. Clones share
+/// the same collector, so create it once outside the `HttpServer` factory.
 #[derive(Clone)]
 pub struct MetricsMiddleware {
     metrics: Arc<RequestMetrics>,
@@ -105,6 +262,119 @@ impl MetricsMiddleware {
     }
 }
 
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(MetricsMiddlewareService {
+            service,
+            metrics: self.metrics.clone(),
+        }))
+    }
+}
+
+pub struct MetricsMiddlewareService<S> {
+    service: S,
+    metrics: Arc<RequestMetrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let endpoint = req
+            .match_pattern()
+            .unwrap_or_else(|| UNMATCHED_ENDPOINT.to_string());
+        let metrics = self.metrics.clone();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+            let status = match &res {
+                Ok(res) => res.status().as_u16(),
+                Err(e) => e.as_response_error().status_code().as_u16(),
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+            metrics.record_endpoint(&method, &endpoint, status, latency_ms);
+            res
+        })
+    }
+}
+
+/// Bearer token guarding `/metrics`, read from `METRICS_BEARER_TOKEN`
+///
+/// When unset the endpoint is open, which is only appropriate when the
+/// port is not reachable from outside the cluster.
+#[derive(Clone, Default)]
+pub struct MetricsToken(Option<String>);
+
+impl MetricsToken {
+    pub fn new(token: Option<String>) -> Self {
+        Self(token.filter(|t| !t.is_empty()))
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("METRICS_BEARER_TOKEN").ok())
+    }
+
+    /// Whether the request's `Authorization` header satisfies the token
+    fn allows(&self, req: &HttpRequest) -> bool {
+        let Some(expected) = &self.0 else {
+            return true;
+        };
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|provided| {
+                ring::constant_time::verify_slices_are_equal(provided.trim().as_bytes(), expected.as_bytes()).is_ok()
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// `GET /metrics` - Prometheus scrape endpoint
+pub async fn metrics_endpoint(
+    req: HttpRequest,
+    metrics: web::Data<RequestMetrics>,
+    token: web::Data<MetricsToken>,
+) -> HttpResponse {
+    if !token.allows(&req) {
+        return HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+            .json(serde_json::json!({
+                "error": "UNAUTHORIZED",
+                "message": "A valid metrics token is required",
+            }));
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(metrics.render_prometheus() + &render_session_gauges(&SESSION_STORE))
+}
+
 /// Helper function to create a tracing span for sync operations
 pub fn create_sync_span(document_id: &str, operation: &str) -> tracing::Span {
     span!(
@@ -171,3 +441,117 @@ impl RequestMetricsExt for web::Data<RequestMetrics> {
         Some(self.get_ref().snapshot())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_render_prometheus_includes_endpoint_status_and_buckets() {
+        let metrics = RequestMetrics::new();
+        metrics.record_endpoint("GET", "/api/v1/documents/{id}", 200, 7);
+        metrics.record_endpoint("GET", "/api/v1/documents/{id}", 404, 120);
+
+        let rendered = metrics.render_prometheus();
+
+        assert!(rendered.contains("miniwiki_requests_total 2\n"));
+        assert!(rendered.contains("miniwiki_requests_failed_total 1\n"));
+        assert!(rendered.contains(
+            "miniwiki_http_requests_total{method=\"GET\",endpoint=\"/api/v1/documents/{id}\",status=\"200\"} 1\n"
+        ));
+        assert!(rendered.contains(
+            "miniwiki_http_requests_total{method=\"GET\",endpoint=\"/api/v1/documents/{id}\",status=\"404\"} 1\n"
+        ));
+        let labels = "method=\"GET\",endpoint=\"/api/v1/documents/{id}\"";
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_bucket{{{},le=\"0.005\"}} 0\n", labels)));
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_bucket{{{},le=\"0.01\"}} 1\n", labels)));
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_bucket{{{},le=\"0.25\"}} 2\n", labels)));
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2\n", labels)));
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_sum{{{}}} 0.127\n", labels)));
+        assert!(rendered.contains(&format!("miniwiki_http_request_duration_seconds_count{{{}}} 2\n", labels)));
+    }
+
+    #[test]
+    fn test_render_session_gauges() {
+        let store = SessionStore::new();
+        let (busy, quiet) = (Uuid::new_v4(), Uuid::new_v4());
+        for document_id in [busy, busy, busy, quiet] {
+            store.add_session(websocket_service::WebSocketSession::new(
+                document_id,
+                Uuid::new_v4(),
+                "User".to_string(),
+                "#fff".to_string(),
+            ));
+        }
+
+        let rendered = render_session_gauges(&store);
+
+        assert!(rendered.contains("miniwiki_ws_sessions 4\n"));
+        assert!(rendered.contains("miniwiki_ws_active_documents 2\n"));
+        let busy_line = format!("miniwiki_ws_document_sessions{{document_id=\"{}\"}} 3\n", busy);
+        let quiet_line = format!("miniwiki_ws_document_sessions{{document_id=\"{}\"}} 1\n", quiet);
+        assert!(rendered.find(&busy_line).unwrap() < rendered.find(&quiet_line).unwrap());
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_records_route_pattern() {
+        let metrics = Arc::new(RequestMetrics::new());
+        let app = test::init_service(
+            App::new()
+                .wrap(MetricsMiddleware::new(metrics.clone()))
+                .route("/items/{id}", web::get().to(|| async { HttpResponse::Ok().finish() })),
+        )
+        .await;
+
+        test::call_service(&app, test::TestRequest::get().uri("/items/1").to_request()).await;
+        test::call_service(&app, test::TestRequest::get().uri("/items/2").to_request()).await;
+        test::call_service(&app, test::TestRequest::get().uri("/nope").to_request()).await;
+
+        let rendered = metrics.render_prometheus();
+        assert!(rendered.contains(
+            "miniwiki_http_requests_total{method=\"GET\",endpoint=\"/items/{id}\",status=\"200\"} 2\n"
+        ));
+        assert!(rendered.contains(
+            "miniwiki_http_requests_total{method=\"GET\",endpoint=\"unmatched\",status=\"404\"} 1\n"
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_metrics_endpoint_requires_configured_token() {
+        let metrics = web::Data::new(RequestMetrics::new());
+        let app = test::init_service(
+            App::new()
+                .app_data(metrics.clone())
+                .app_data(web::Data::new(MetricsToken::new(Some("scrape-secret".to_string()))))
+                .route("/metrics", web::get().to(metrics_endpoint)),
+        )
+        .await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/metrics")
+                .insert_header((header::AUTHORIZATION, "Bearer wrong"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri("/metrics")
+                .insert_header((header::AUTHORIZATION, "Bearer scrape-secret"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        let body = test::read_body(resp).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains("miniwiki_requests_total 0"));
+    }
+}
@@ -1,6 +1,11 @@
+pub mod health;
+
 use actix_web::web;
 use document_service::sharing::{get_share_link_by_token, verify_share_link_access_code};
 use auth_service::jwt::JwtService;
+use file_service::storage::S3Storage;
+use std::sync::Arc;
+use crate::observability::{metrics_endpoint, MetricsToken};
 
 const DEFAULT_JWT_SECRET: &str = "test-secret-key-for-testing-only-do-not-use-in-production";
 
@@ -25,33 +30,51 @@ fn get_jwt_secret() -> String {
     }
 }
 
+/// Register object storage in the form the file and export handlers extract
+/// (`web::Data<Arc<S3Storage>>`); nothing is registered without storage
+pub fn storage_config(storage: Option<Arc<S3Storage>>) -> impl Fn(&mut web::ServiceConfig) {
+    move |cfg| {
+        if let Some(storage) = &storage {
+            cfg.app_data(web::Data::new(storage.clone()));
+        }
+    }
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.route("/health", web::get().to(|| async {
-        actix_web::web::Json(serde_json::json!({
-            "status": "healthy",
-            "service": "miniwiki-api",
-            "version": "0.1.0"
-        }))
-    }));
+    // Liveness stays cheap; readiness checks the database and Redis
+    cfg.route("/health", web::get().to(health::liveness));
+    cfg.route("/health/ready", web::get().to(health::readiness));
+
+    // Prometheus scrape endpoint, guarded by METRICS_BEARER_TOKEN when set
+    cfg.app_data(web::Data::new(MetricsToken::from_env()));
+    cfg.route("/metrics", web::get().to(metrics_endpoint));
 
     // Public share link endpoints (no auth required)
-    cfg.service(
-        web::scope("/share")
-            .route("/{token}", web::get().to(get_share_link_by_token))
-            .route("/{token}/verify", web::post().to(verify_share_link_access_code))
-    );
+    let share = web::scope("/share")
+        .route("/{token}", web::get().to(get_share_link_by_token))
+        .route("/{token}/verify", web::post().to(verify_share_link_access_code));
+    #[cfg(feature = "qr")]
+    let share = share.route("/{token}/qr", web::get().to(document_service::share_qr::share_link_qr));
+    cfg.service(share);
 
     // Configure auth service with required data
     // The pool is already registered in main.rs, but we need to create JwtService and register it
-    cfg.app_data(web::Data::new(JwtService::new(auth_service::jwt::JwtConfig {
-        secret: get_jwt_secret(),
-        access_expiry: 3600,
-        refresh_expiry: 86400,
-    })));
+    // JWT_ALGORITHM=RS256 switches signing to the key pair from JWT_PRIVATE_KEY_PATH/JWT_PUBLIC_KEY_PATH
+    let jwt_config = auth_service::jwt::JwtConfig::new(get_jwt_secret(), 3600, 86400)
+        .with_env_keys()
+        .unwrap_or_else(|e| panic!("Invalid JWT configuration: {}", e));
+    cfg.app_data(web::Data::new(JwtService::new(jwt_config)));
+
+    // Verification settings read once here, for every handler that identifies the caller
+    let verification_config = auth_service::identity::jwt_config_from_env()
+        .unwrap_or_else(|e| panic!("Invalid JWT verification configuration: {}", e));
+    cfg.app_data(web::Data::new(verification_config));
 
     // Register auth service routes (under /api/v1/auth)
     cfg.service(
         web::scope("/api/v1")
+            // `Authorization: ApiKey <key>` resolves the key's owner; read-only keys are limited to GET
+            .wrap(auth_service::api_keys::ApiKeyAuth::new())
             // Auth endpoints first to ensure they're available
             .configure(auth_service::config)
             // Document endpoints
@@ -67,6 +90,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             )
             .configure(file_service::config)
             .configure(sync_service::config)
+            .configure(search_service::config)
+            // Re-read API_CORS_ORIGINS without a restart (admin only)
+            .route("/admin/cors/reload", web::post().to(crate::cors::reload_cors))
     );
 
     cfg.configure(websocket_service::config);
@@ -0,0 +1,138 @@
+//! API key authentication tests
+//!
+//! Covers looking keys up by hash through `AuthRepository` and the scope
+//! check applied by the `ApiKeyAuth` middleware.
+//!
+//! Run with: cargo test --test lib auth::api_keys_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{http::header, test, web, App, HttpMessage, HttpRequest, HttpResponse};
+use auth_service::api_keys::{generate_api_key, hash_api_key, ApiKeyAuth, ApiKeyScope};
+use auth_service::repository::AuthRepository;
+use uuid::Uuid;
+
+/// Create a key for the user with the given scopes, returning (key id, key)
+async fn create_key(repo: &AuthRepository, user_id: Uuid, scopes: &[&str]) -> (Uuid, String) {
+    let generated = generate_api_key();
+    let scopes: Vec<String> = scopes.iter().map(|s| s.to_string()).collect();
+    let row = repo
+        .create_api_key(
+            &user_id,
+            "CI integration",
+            &generated.key_prefix,
+            &generated.key_hash,
+            &scopes,
+            None,
+        )
+        .await
+        .expect("Failed to create API key");
+
+    (row.id, generated.key)
+}
+
+// Echo the user id the middleware stored in the request extensions
+async fn whoami(req: HttpRequest) -> HttpResponse {
+    match req.extensions().get::<Uuid>() {
+        Some(user_id) => HttpResponse::Ok().body(user_id.to_string()),
+        None => HttpResponse::Unauthorized().finish(),
+    }
+}
+
+#[tokio::test]
+async fn test_api_key_lookup_by_hash() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    let (key_id, key) = create_key(&repo, user.id, &["read"]).await;
+
+    // Only the hash is stored
+    let stored: (String,) = sqlx::query_as("SELECT key_hash FROM api_keys WHERE id = $1")
+        .bind(key_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.0, hash_api_key(&key));
+    assert_ne!(stored.0, key);
+
+    let row = repo
+        .authenticate_api_key(&hash_api_key(&key))
+        .await
+        .unwrap()
+        .expect("Key should authenticate");
+    assert_eq!(row.id, key_id);
+    assert_eq!(row.user_id, user.id);
+    assert!(row.last_used_at.is_some());
+
+    assert!(repo
+        .authenticate_api_key(&hash_api_key("mwk_not-a-real-key"))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_revoked_api_key_is_rejected() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let other_user = app.create_test_user().await;
+
+    let (key_id, key) = create_key(&repo, user.id, &["read"]).await;
+
+    assert!(!repo.revoke_api_key(&other_user.id, &key_id).await.unwrap());
+    assert!(repo.revoke_api_key(&user.id, &key_id).await.unwrap());
+
+    assert!(repo.authenticate_api_key(&hash_api_key(&key)).await.unwrap().is_none());
+    assert!(repo.list_api_keys(&user.id).await.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn test_middleware_enforces_scopes() {
+    let test_app = TestApp::create().await;
+    let repo = AuthRepository::new(test_app.pool.clone());
+    let user = test_app.create_test_user().await;
+
+    let (_, read_key) = create_key(&repo, user.id, &["read"]).await;
+    let (_, write_key) = create_key(&repo, user.id, &["read", "write"]).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .wrap(ApiKeyAuth::new())
+            .route("/whoami", web::get().to(whoami))
+            .route("/whoami", web::post().to(whoami))
+            .service(
+                web::resource("/admin")
+                    .wrap(ApiKeyAuth::require(ApiKeyScope::Write))
+                    .route(web::get().to(whoami)),
+            ),
+    )
+    .await;
+
+    let call = |method: test::TestRequest, key: &str| {
+        method
+            .insert_header((header::AUTHORIZATION, format!("ApiKey {}", key)))
+            .to_request()
+    };
+
+    // Read key can read and the owner is injected into extensions
+    let resp = test::call_service(&app, call(test::TestRequest::get().uri("/whoami"), &read_key)).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(test::read_body(resp).await, user.id.to_string());
+
+    // Read key cannot write
+    let resp = test::call_service(&app, call(test::TestRequest::post().uri("/whoami"), &read_key)).await;
+    assert_eq!(resp.status(), 403);
+
+    // Route-level scope applies regardless of method
+    let resp = test::call_service(&app, call(test::TestRequest::get().uri("/admin"), &read_key)).await;
+    assert_eq!(resp.status(), 403);
+
+    let resp = test::call_service(&app, call(test::TestRequest::post().uri("/whoami"), &write_key)).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = test::call_service(&app, call(test::TestRequest::get().uri("/whoami"), "mwk_unknown")).await;
+    assert_eq!(resp.status(), 401);
+}
@@ -0,0 +1,170 @@
+//! Email verification resend tests
+//!
+//! Covers `AuthRepository::reissue_email_verification`: a reissued token
+//! replaces the pending one, and requests inside the cooldown are refused.
+//! Also covers confirming a token, which only ever stores and compares its
+//! SHA-256 hash.
+//!
+//! Run with: cargo test --test lib auth::email_verification_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use auth_service::email_verification::{hash_verification_token, verify_email_token, VerificationTokenError};
+use auth_service::password_reset::ResetTokenConfig;
+use auth_service::repository::{AuthRepository, VerificationReissue};
+use uuid::Uuid;
+
+fn expires_at() -> chrono::NaiveDateTime {
+    (chrono::Utc::now() + chrono::Duration::days(1)).naive_utc()
+}
+
+async fn pending_token_hashes(app: &TestApp, user_id: &Uuid) -> Vec<String> {
+    sqlx::query_scalar("SELECT token_hash FROM email_verifications WHERE user_id = $1 AND verified_at IS NULL")
+        .bind(user_id)
+        .fetch_all(&app.pool)
+        .await
+        .expect("Failed to read verification tokens")
+}
+
+#[tokio::test]
+async fn test_reissue_invalidates_previous_token() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    repo.create_email_verification(&user.id, "first-token-hash", expires_at())
+        .await
+        .expect("Failed to store first token");
+
+    let outcome = repo
+        .reissue_email_verification(&user.id, "second-token-hash", expires_at(), chrono::Duration::zero())
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, VerificationReissue::Issued);
+    assert_eq!(pending_token_hashes(&app, &user.id).await, vec!["second-token-hash"]);
+}
+
+#[tokio::test]
+async fn test_reissue_refused_during_cooldown() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let cooldown = chrono::Duration::minutes(5);
+
+    // No previous token, so the first request goes through
+    let outcome = repo
+        .reissue_email_verification(&user.id, "first-token-hash", expires_at(), cooldown)
+        .await
+        .unwrap();
+    assert_eq!(outcome, VerificationReissue::Issued);
+
+    let outcome = repo
+        .reissue_email_verification(&user.id, "second-token-hash", expires_at(), cooldown)
+        .await
+        .unwrap();
+    match outcome {
+        VerificationReissue::Cooldown { retry_after_secs } => {
+            assert!(retry_after_secs > 0 && retry_after_secs <= 300);
+        },
+        other => panic!("Expected cooldown, got {:?}", other),
+    }
+
+    // The refused request leaves the earlier token in place
+    assert_eq!(pending_token_hashes(&app, &user.id).await, vec!["first-token-hash"]);
+}
+
+/// A fresh plaintext token in the configured format
+fn new_token() -> String {
+    ResetTokenConfig::default().issue(chrono::Utc::now().naive_utc()).token
+}
+
+async fn is_email_verified(app: &TestApp, user_id: &Uuid) -> bool {
+    sqlx::query_scalar("SELECT is_email_verified FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Failed to read user")
+}
+
+#[tokio::test]
+async fn test_raw_verification_token_is_not_persisted() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let token = new_token();
+
+    repo.reissue_email_verification(&user.id, &hash_verification_token(&token), expires_at(), chrono::Duration::zero())
+        .await
+        .unwrap();
+
+    let stored = pending_token_hashes(&app, &user.id).await;
+    assert_eq!(stored, vec![hash_verification_token(&token)]);
+    assert!(!stored.contains(&token));
+}
+
+#[tokio::test]
+async fn test_stored_hash_verifies_original_token() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let token = new_token();
+
+    repo.create_email_verification(&user.id, &hash_verification_token(&token), expires_at())
+        .await
+        .unwrap();
+
+    let verified = verify_email_token(&repo, &token).await.unwrap();
+
+    assert_eq!(verified, Ok(user.id));
+    assert!(is_email_verified(&app, &user.id).await);
+    // A token can only be redeemed once
+    assert_eq!(
+        verify_email_token(&repo, &token).await.unwrap(),
+        Err(VerificationTokenError::AlreadyVerified)
+    );
+}
+
+#[tokio::test]
+async fn test_wrong_or_hashed_token_fails_verification() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let token = new_token();
+    let token_hash = hash_verification_token(&token);
+
+    repo.create_email_verification(&user.id, &token_hash, expires_at())
+        .await
+        .unwrap();
+
+    let wrong = new_token();
+    assert_eq!(
+        verify_email_token(&repo, &wrong).await.unwrap(),
+        Err(VerificationTokenError::NotFound)
+    );
+    // Someone who read the stored hash cannot submit it as the token
+    assert_eq!(
+        verify_email_token(&repo, &token_hash).await.unwrap(),
+        Err(VerificationTokenError::NotFound)
+    );
+    assert!(!is_email_verified(&app, &user.id).await);
+}
+
+#[tokio::test]
+async fn test_expired_token_fails_verification() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let token = new_token();
+    let expired = (chrono::Utc::now() - chrono::Duration::minutes(1)).naive_utc();
+
+    repo.create_email_verification(&user.id, &hash_verification_token(&token), expired)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        verify_email_token(&repo, &token).await.unwrap(),
+        Err(VerificationTokenError::Expired)
+    );
+    assert!(!is_email_verified(&app, &user.id).await);
+}
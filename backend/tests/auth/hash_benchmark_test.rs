@@ -0,0 +1,78 @@
+//! `POST /admin/security/hash-benchmark` tests
+//!
+//! Checks that the benchmark needs the owner role and that the reported durations
+//! grow with the bcrypt cost.
+//!
+//! Run with: cargo test --test lib auth::hash_benchmark_test
+
+use crate::helpers::test_jwt_service;
+use actix_web::{http::header, test, web, App};
+use auth_service::hash_benchmark::hash_benchmark;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Run the benchmark with an optional token for `role`, returning the status and body
+async fn benchmark(role: Option<&str>, body: Value) -> (u16, Value) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(test_jwt_service()))
+            .route("/admin/security/hash-benchmark", web::post().to(hash_benchmark)),
+    )
+    .await;
+
+    let mut req = test::TestRequest::post()
+        .uri("/admin/security/hash-benchmark")
+        .set_json(body);
+    if let Some(role) = role {
+        let token = test_jwt_service()
+            .generate_access_token(&Uuid::new_v4().to_string(), "ops@example.com", role)
+            .expect("Failed to generate access token");
+        req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", token)));
+    }
+
+    let resp = test::call_service(&app, req.to_request()).await;
+    let status = resp.status().as_u16();
+    // Extractor errors have a plain text body
+    let body = test::read_body(resp).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[actix_rt::test]
+async fn test_durations_increase_with_cost() {
+    let (status, body) = benchmark(Some("owner"), json!({ "costs": [10, 4, 7] })).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["algorithm"], "bcrypt");
+
+    let results = body["results"].as_array().unwrap();
+    let costs: Vec<u64> = results.iter().map(|r| r["cost"].as_u64().unwrap()).collect();
+    assert_eq!(costs, vec![4, 7, 10]);
+
+    let durations: Vec<f64> = results.iter().map(|r| r["duration_ms"].as_f64().unwrap()).collect();
+    assert!(
+        durations.windows(2).all(|pair| pair[0] < pair[1]),
+        "Durations should grow with cost: {:?}",
+        durations
+    );
+}
+
+#[actix_rt::test]
+async fn test_requires_admin() {
+    for role in ["user", "editor"] {
+        let (status, body) = benchmark(Some(role), json!({ "costs": [4] })).await;
+        assert_eq!(status, 403);
+        assert_eq!(body["error"], "FORBIDDEN");
+    }
+
+    let (status, _) = benchmark(None, json!({ "costs": [4] })).await;
+    assert_eq!(status, 401);
+}
+
+#[actix_rt::test]
+async fn test_rejects_password_and_invalid_costs() {
+    let (status, _) = benchmark(Some("owner"), json!({ "costs": [4], "password": "hunter2" })).await;
+    assert_eq!(status, 400);
+
+    let (status, body) = benchmark(Some("owner"), json!({ "costs": [31] })).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "VALIDATION_ERROR");
+}
@@ -18,11 +18,7 @@ mod auth_integration_test {
             .await
             .expect("Failed to connect to test database");
 
-        let jwt_service = web::Data::new(JwtService::new(JwtConfig {
-            secret: "test_secret".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        }));
+        let jwt_service = web::Data::new(JwtService::new(JwtConfig::new("test_secret".to_string(), 3600, 86400)));
 
         let repo = web::Data::new(AuthRepository::new(pool.clone()));
 
@@ -62,11 +58,7 @@ mod auth_integration_test {
 
     #[actix_web::test]
     async fn test_register_endpoint_returns_400() {
-        let jwt_service = web::Data::new(JwtService::new(JwtConfig {
-            secret: "test_secret".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        }));
+        let jwt_service = web::Data::new(JwtService::new(JwtConfig::new("test_secret".to_string(), 3600, 86400)));
 
         let database_url = std::env::var("TEST_DATABASE_URL")
             .or_else(|_| std::env::var("DATABASE_URL"))
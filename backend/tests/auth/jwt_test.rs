@@ -4,11 +4,7 @@ mod jwt_test {
 
     #[test]
     fn test_jwt_service_generate_access_token() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -26,11 +22,7 @@ mod jwt_test {
 
     #[test]
     fn test_jwt_service_generate_refresh_token() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -44,11 +36,7 @@ mod jwt_test {
 
     #[test]
     fn test_jwt_service_validate_token_valid() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -68,11 +56,7 @@ mod jwt_test {
 
     #[test]
     fn test_jwt_service_validate_token_invalid() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -83,11 +67,7 @@ mod jwt_test {
 
     #[test]
     fn test_jwt_service_validate_token_wrong_secret() {
-        let config = JwtConfig {
-            secret: "correct_secret".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("correct_secret".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -95,11 +75,7 @@ mod jwt_test {
             .generate_access_token("user_id_123", "test@example.com", "user")
             .unwrap();
 
-        let wrong_config = JwtConfig {
-            secret: "wrong_secret".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let wrong_config = JwtConfig::new("wrong_secret".to_string(), 3600, 86400);
 
         let wrong_jwt_service = JwtService::new(wrong_config);
 
@@ -0,0 +1,145 @@
+//! Logout refresh token denylist tests
+//!
+//! Checks that logging out records the refresh token's `jti` in the
+//! `TokenDenylist` and that `/auth/refresh` refuses denylisted tokens and
+//! anything that is not a refresh token.
+//!
+//! Run with: cargo test --test lib auth::logout_denylist_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{test_jwt_service, TestApp};
+use actix_web::{test, web, App};
+use auth_service::repository::AuthRepository;
+use auth_service::token_denylist::{TokenDenylist, TOKEN_REVOKED_CODE};
+use serde_json::Value;
+use shared_models::entities::RefreshToken;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Issue and store a refresh token for the user, returning (token, jti)
+async fn store_refresh_token(test_app: &TestApp, user_id: Uuid) -> (String, String) {
+    let token = test_jwt_service()
+        .generate_refresh_token(&user_id.to_string())
+        .expect("Failed to generate refresh token");
+    let now = chrono::Utc::now();
+    AuthRepository::new(test_app.pool.clone())
+        .create_refresh_token(&RefreshToken {
+            id: Uuid::new_v4(),
+            user_id,
+            token: token.clone(),
+            expires_at: (now + chrono::Duration::days(1)).naive_utc(),
+            ip_address: None,
+            user_agent: None,
+            is_revoked: false,
+            revoked_at: None,
+            created_at: now.naive_utc(),
+        })
+        .await
+        .expect("Failed to store refresh token");
+
+    let jti = test_jwt_service()
+        .validate_token(&token)
+        .expect("Failed to decode refresh token")
+        .jti
+        .expect("Refresh tokens carry a jti");
+    (token, jti)
+}
+
+fn post(uri: &str, token: &str) -> test::TestRequest {
+    test::TestRequest::post()
+        .uri(uri)
+        .set_json(serde_json::json!({ "refresh_token": token }))
+}
+
+#[actix_rt::test]
+async fn test_logout_denylists_refresh_token() {
+    let test_app = TestApp::create().await;
+    let user = test_app.create_test_user().await;
+    let (token, jti) = store_refresh_token(&test_app, user.id).await;
+    let denylist = web::Data::new(TokenDenylist::new());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .app_data(denylist.clone())
+            .configure(auth_service::config),
+    )
+    .await;
+
+    let resp = test::call_service(&app, post("/auth/logout", &token).to_request()).await;
+    assert_eq!(resp.status(), 200);
+    assert!(denylist.is_revoked(&jti).await);
+
+    let resp = test::call_service(&app, post("/auth/refresh", &token).to_request()).await;
+    assert_eq!(resp.status(), 401);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"], TOKEN_REVOKED_CODE);
+}
+
+#[actix_rt::test]
+async fn test_refresh_checks_denylist_before_database() {
+    let test_app = TestApp::create().await;
+    let user = test_app.create_test_user().await;
+    let (token, jti) = store_refresh_token(&test_app, user.id).await;
+    let (other_token, _) = store_refresh_token(&test_app, user.id).await;
+    let denylist = web::Data::new(TokenDenylist::new());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .app_data(denylist.clone())
+            .configure(auth_service::config),
+    )
+    .await;
+
+    // Still valid in the database, e.g. logged out through another instance
+    denylist.revoke(&jti, Duration::from_secs(60)).await;
+
+    let resp = test::call_service(&app, post("/auth/refresh", &token).to_request()).await;
+    assert_eq!(resp.status(), 401);
+
+    let resp = test::call_service(&app, post("/auth/refresh", &other_token).to_request()).await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[actix_rt::test]
+async fn test_refresh_and_logout_reject_access_tokens() {
+    let test_app = TestApp::create().await;
+    let user = test_app.create_test_user().await;
+    let access_token = test_jwt_service()
+        .generate_access_token(&user.id.to_string(), &user.email, "user")
+        .expect("Failed to generate access token");
+
+    // Even stored as if it were a refresh token, it must not refresh
+    let now = chrono::Utc::now();
+    AuthRepository::new(test_app.pool.clone())
+        .create_refresh_token(&RefreshToken {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            token: access_token.clone(),
+            expires_at: (now + chrono::Duration::days(1)).naive_utc(),
+            ip_address: None,
+            user_agent: None,
+            is_revoked: false,
+            revoked_at: None,
+            created_at: now.naive_utc(),
+        })
+        .await
+        .expect("Failed to store token");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .configure(auth_service::config),
+    )
+    .await;
+
+    let resp = test::call_service(&app, post("/auth/refresh", &access_token).to_request()).await;
+    assert_eq!(resp.status(), 401);
+
+    let resp = test::call_service(&app, post("/auth/logout", &access_token).to_request()).await;
+    assert_eq!(resp.status(), 401);
+}
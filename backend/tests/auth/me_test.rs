@@ -0,0 +1,129 @@
+//! `/auth/me` effective permissions tests
+//!
+//! Checks that the response reports the caller's global role and, when a
+//! `space_id` is given, their role and permissions in that space. With
+//! `include=spaces` it also lists the caller's own space memberships.
+//!
+//! Run with: cargo test --test lib auth::me_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{test_jwt_service, TestApp};
+use actix_web::{http::header, test, web, App};
+use auth_service::handlers::me;
+use auth_service::repository::AuthRepository;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Call `/auth/me` as the given user, optionally scoped to a space
+async fn get_me(test_app: &TestApp, user_id: Uuid, email: &str, space_id: Option<Uuid>) -> Value {
+    let uri = match space_id {
+        Some(space_id) => format!("/auth/me?space_id={}", space_id),
+        None => "/auth/me".to_string(),
+    };
+    get_me_uri(test_app, user_id, email, &uri).await
+}
+
+async fn get_me_uri(test_app: &TestApp, user_id: Uuid, email: &str, uri: &str) -> Value {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .route("/auth/me", web::get().to(me)),
+    )
+    .await;
+
+    let token = test_jwt_service()
+        .generate_access_token(&user_id.to_string(), email, "user")
+        .expect("Failed to generate access token");
+    let req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    test::read_body_json(resp).await
+}
+
+#[actix_rt::test]
+async fn test_me_includes_roles() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+
+    let body = get_me(&app, user.id, &user.email, None).await;
+
+    assert_eq!(body["id"], user.id.to_string());
+    assert_eq!(body["role"], "user");
+    assert_eq!(body["roles"], serde_json::json!(["user"]));
+    assert!(body["permissions"].is_array());
+    assert!(body.get("space").is_none());
+}
+
+#[actix_rt::test]
+async fn test_me_with_space_includes_space_role() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+
+    app.add_space_member(&space.id, &member.id, "commenter").await;
+
+    let body = get_me(&app, member.id, &member.email, Some(space.id)).await;
+    assert_eq!(body["space"]["space_id"], space.id.to_string());
+    assert_eq!(body["space"]["role"], "commenter");
+    assert_eq!(
+        body["space"]["permissions"],
+        serde_json::json!(["view_documents", "comment"])
+    );
+
+    let body = get_me(&app, owner.id, &owner.email, Some(space.id)).await;
+    assert_eq!(body["space"]["role"], "owner");
+}
+
+#[actix_rt::test]
+async fn test_me_include_spaces_lists_own_memberships() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let other = app.create_test_user().await;
+    let first = app.create_test_space_for_user(&owner.id).await;
+    let second = app.create_test_space_for_user(&owner.id).await;
+    let unrelated = app.create_test_space_for_user(&owner.id).await;
+    app.add_space_member(&first.id, &member.id, "editor").await;
+    app.add_space_member(&second.id, &member.id, "viewer").await;
+    app.add_space_member(&unrelated.id, &other.id, "commenter").await;
+
+    let body = get_me_uri(&app, member.id, &member.email, "/auth/me?include=spaces").await;
+
+    let mut spaces: Vec<(String, String)> = body["spaces"]
+        .as_array()
+        .expect("spaces should be listed")
+        .iter()
+        .map(|s| (s["space_id"].as_str().unwrap().to_string(), s["role"].as_str().unwrap().to_string()))
+        .collect();
+    spaces.sort();
+    let mut expected = vec![
+        (first.id.to_string(), "editor".to_string()),
+        (second.id.to_string(), "viewer".to_string()),
+    ];
+    expected.sort();
+    assert_eq!(spaces, expected);
+
+    // The default response stays lean
+    let body = get_me(&app, member.id, &member.email, None).await;
+    assert!(body.get("spaces").is_none());
+}
+
+#[actix_rt::test]
+async fn test_me_without_space_role_reports_none() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+
+    let body = get_me(&app, outsider.id, &outsider.email, Some(space.id)).await;
+
+    assert_eq!(body["space"]["space_id"], space.id.to_string());
+    assert!(body["space"]["role"].is_null());
+    assert_eq!(body["space"]["permissions"], serde_json::json!([]));
+}
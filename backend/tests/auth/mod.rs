@@ -1,4 +1,12 @@
+pub mod api_keys_test;
 pub mod e2e_flow_test;
+pub mod email_verification_test;
+pub mod hash_benchmark_test;
 pub mod integration_test;
 pub mod jwt_test;
+pub mod logout_denylist_test;
+pub mod me_test;
+pub mod password_reset_test;
 pub mod refresh_token_test;
+pub mod sessions_test;
+pub mod users_resolve_test;
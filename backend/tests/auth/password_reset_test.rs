@@ -0,0 +1,103 @@
+//! Password reset redemption tests
+//!
+//! Covers `redeem_reset_token`: a valid token sets the new password and is
+//! marked used in the same step, while expired and reused tokens are
+//! rejected with their own error codes.
+//!
+//! Run with: cargo test --test lib auth::password_reset_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::web;
+use auth_service::password_reset::{
+    hash_reset_token, redeem_reset_token, ResetTokenConfig, ResetTokenError, RESET_EXPIRED_CODE, RESET_USED_CODE,
+};
+use auth_service::repository::AuthRepository;
+use uuid::Uuid;
+
+const NEW_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$bmV3c2FsdA$bmV3aGFzaA";
+
+/// Store a reset token for the user issued at `issued_at`, returning the plaintext token
+async fn issue_token(repo: &AuthRepository, user_id: Uuid, issued_at: chrono::NaiveDateTime) -> String {
+    let issued = ResetTokenConfig::default().issue(issued_at);
+    repo.create_password_reset(&user_id, &issued.token_hash, issued.expires_at)
+        .await
+        .expect("Failed to store reset token");
+    issued.token
+}
+
+async fn password_hash(app: &TestApp, user_id: Uuid) -> String {
+    sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Failed to read password hash")
+}
+
+#[tokio::test]
+async fn test_valid_token_resets_password_and_is_marked_used() {
+    let app = TestApp::create().await;
+    let repo = web::Data::new(AuthRepository::new(app.pool.clone()));
+    let user = app.create_test_user().await;
+    let token = issue_token(&repo, user.id, chrono::Utc::now().naive_utc()).await;
+
+    let redeemed = redeem_reset_token(&token, NEW_PASSWORD_HASH, repo.clone())
+        .await
+        .expect("Database error");
+
+    assert_eq!(redeemed, Ok(user.id));
+    assert_eq!(password_hash(&app, user.id).await, NEW_PASSWORD_HASH);
+    let record = repo
+        .find_password_reset(&hash_reset_token(&token))
+        .await
+        .unwrap()
+        .expect("Token record should exist");
+    assert!(record.used_at.is_some());
+}
+
+#[tokio::test]
+async fn test_used_token_is_rejected() {
+    let app = TestApp::create().await;
+    let repo = web::Data::new(AuthRepository::new(app.pool.clone()));
+    let user = app.create_test_user().await;
+    let token = issue_token(&repo, user.id, chrono::Utc::now().naive_utc()).await;
+
+    redeem_reset_token(&token, NEW_PASSWORD_HASH, repo.clone())
+        .await
+        .expect("Database error")
+        .expect("First redemption should succeed");
+    let before = password_hash(&app, user.id).await;
+
+    let second = redeem_reset_token(&token, "$argon2id$other", repo.clone())
+        .await
+        .expect("Database error");
+
+    assert_eq!(second, Err(ResetTokenError::Used));
+    assert_eq!(ResetTokenError::Used.code(), RESET_USED_CODE);
+    assert_eq!(password_hash(&app, user.id).await, before);
+}
+
+#[tokio::test]
+async fn test_expired_token_is_rejected() {
+    let app = TestApp::create().await;
+    let repo = web::Data::new(AuthRepository::new(app.pool.clone()));
+    let user = app.create_test_user().await;
+    let before = password_hash(&app, user.id).await;
+
+    // Issued two TTLs ago, so it has already expired
+    let issued_at = chrono::Utc::now().naive_utc() - ResetTokenConfig::default().ttl * 2;
+    let token = issue_token(&repo, user.id, issued_at).await;
+
+    let redeemed = redeem_reset_token(&token, NEW_PASSWORD_HASH, repo.clone())
+        .await
+        .expect("Database error");
+
+    assert_eq!(redeemed, Err(ResetTokenError::Expired));
+    assert_eq!(ResetTokenError::Expired.code(), RESET_EXPIRED_CODE);
+    assert_eq!(password_hash(&app, user.id).await, before);
+
+    let unknown = redeem_reset_token("not-a-real-token", NEW_PASSWORD_HASH, repo)
+        .await
+        .expect("Database error");
+    assert_eq!(unknown, Err(ResetTokenError::NotFound));
+}
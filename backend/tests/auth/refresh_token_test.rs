@@ -4,11 +4,7 @@ mod refresh_token_test {
 
     #[test]
     fn test_refresh_token_generation() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -21,11 +17,7 @@ mod refresh_token_test {
 
     #[test]
     fn test_refresh_token_rotation() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -48,11 +40,7 @@ mod refresh_token_test {
 
     #[test]
     fn test_refresh_token_expiration() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -73,11 +61,7 @@ mod refresh_token_test {
 
     #[test]
     fn test_refresh_token_different_from_access_token() {
-        let config = JwtConfig {
-            secret: "test_secret_key".to_string(),
-            access_expiry: 3600,
-            refresh_expiry: 86400,
-        };
+        let config = JwtConfig::new("test_secret_key".to_string(), 3600, 86400);
 
         let jwt_service = JwtService::new(config);
 
@@ -0,0 +1,235 @@
+//! Session registry tests
+//!
+//! Covers inserting, listing and revoking sessions through `AuthRepository`,
+//! and checks that a revoked session's refresh token no longer refreshes and
+//! that the session endpoints only accept live access tokens.
+//!
+//! Run with: cargo test --test lib auth::sessions_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{test_jwt_service, TestApp};
+use actix_web::{test, web, App};
+use auth_service::repository::AuthRepository;
+use auth_service::token_denylist::TokenDenylist;
+use shared_models::entities::RefreshToken;
+use uuid::Uuid;
+
+/// Store a refresh token and register a session for it, returning (session id, token)
+async fn create_session(repo: &AuthRepository, user_id: Uuid, user_agent: &str) -> (Uuid, String) {
+    let token = test_jwt_service()
+        .generate_refresh_token(&user_id.to_string())
+        .expect("Failed to generate refresh token");
+    let now = chrono::Utc::now();
+    let record = RefreshToken {
+        id: Uuid::new_v4(),
+        user_id,
+        token: token.clone(),
+        expires_at: (now + chrono::Duration::days(1)).naive_utc(),
+        ip_address: Some("127.0.0.1".to_string()),
+        user_agent: Some(user_agent.to_string()),
+        is_revoked: false,
+        revoked_at: None,
+        created_at: now.naive_utc(),
+    };
+    repo.create_refresh_token(&record)
+        .await
+        .expect("Failed to store refresh token");
+
+    let session_id = Uuid::new_v4();
+    repo.create_session(&session_id, &user_id, &record.id, Some(user_agent), Some("127.0.0.1"))
+        .await
+        .expect("Failed to create session");
+
+    (session_id, token)
+}
+
+#[tokio::test]
+async fn test_session_insert_and_list() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    let (session_id, _) = create_session(&repo, user.id, "Firefox").await;
+
+    let sessions = repo.list_sessions(&user.id).await.expect("Failed to list sessions");
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, session_id);
+    assert_eq!(sessions[0].user_id, user.id);
+    assert_eq!(sessions[0].user_agent.as_deref(), Some("Firefox"));
+}
+
+#[tokio::test]
+async fn test_touch_session_updates_last_used() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    let (session_id, token) = create_session(&repo, user.id, "Firefox").await;
+    let before = repo.list_sessions(&user.id).await.unwrap()[0].last_used;
+
+    let touched = repo.touch_session(&token).await.expect("Failed to touch session");
+    assert_eq!(touched, Some(session_id));
+
+    let after = repo.list_sessions(&user.id).await.unwrap()[0].last_used;
+    assert!(after >= before);
+}
+
+#[tokio::test]
+async fn test_revoke_session() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let other_user = app.create_test_user().await;
+
+    let (session_id, token) = create_session(&repo, user.id, "Firefox").await;
+
+    // Another user cannot revoke it
+    assert!(!repo.revoke_session(&other_user.id, &session_id).await.unwrap());
+
+    assert!(repo.revoke_session(&user.id, &session_id).await.unwrap());
+    assert!(repo.list_sessions(&user.id).await.unwrap().is_empty());
+    assert!(repo.find_refresh_token(&token).await.unwrap().is_none());
+
+    // Revoking twice reports nothing to revoke
+    assert!(!repo.revoke_session(&user.id, &session_id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_revoke_other_sessions_keeps_current() {
+    let app = TestApp::create().await;
+    let repo = AuthRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    let (current, current_token) = create_session(&repo, user.id, "Firefox").await;
+    let (laptop, laptop_token) = create_session(&repo, user.id, "Safari").await;
+    let (phone, phone_token) = create_session(&repo, user.id, "Mobile").await;
+
+    let mut revoked = repo.revoke_other_sessions(&user.id, &current).await.unwrap();
+    revoked.sort();
+    let mut expected = vec![laptop, phone];
+    expected.sort();
+    assert_eq!(revoked, expected);
+
+    let sessions = repo.list_sessions(&user.id).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, current);
+    assert!(repo.find_refresh_token(&current_token).await.unwrap().is_some());
+    assert!(repo.find_refresh_token(&laptop_token).await.unwrap().is_none());
+    assert!(repo.find_refresh_token(&phone_token).await.unwrap().is_none());
+}
+
+#[actix_rt::test]
+async fn test_revoked_session_refresh_token_is_rejected() {
+    let test_app = TestApp::create().await;
+    let repo = AuthRepository::new(test_app.pool.clone());
+    let user = test_app.create_test_user().await;
+
+    let (session_id, token) = create_session(&repo, user.id, "Firefox").await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .configure(auth_service::config),
+    )
+    .await;
+
+    let refresh = |token: String| {
+        test::TestRequest::post()
+            .uri("/auth/refresh")
+            .set_json(serde_json::json!({ "refresh_token": token }))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, refresh(token.clone())).await;
+    assert_eq!(resp.status(), 200);
+
+    repo.revoke_session(&user.id, &session_id).await.unwrap();
+
+    let resp = test::call_service(&app, refresh(token)).await;
+    assert_eq!(resp.status(), 401);
+}
+
+#[actix_rt::test]
+async fn test_session_endpoints_reject_refresh_and_revoked_tokens() {
+    let test_app = TestApp::create().await;
+    let repo = AuthRepository::new(test_app.pool.clone());
+    let user = test_app.create_test_user().await;
+    let denylist = web::Data::new(TokenDenylist::new());
+
+    let (session_id, refresh_token) = create_session(&repo, user.id, "Firefox").await;
+    let access_token = test_jwt_service()
+        .generate_session_access_token(&user.id.to_string(), &user.email, "user", &session_id.to_string())
+        .expect("Failed to generate access token");
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .app_data(denylist.clone())
+            .configure(auth_service::config),
+    )
+    .await;
+
+    let list = |token: &str| {
+        test::TestRequest::get()
+            .uri("/auth/sessions")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, list(&access_token)).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = test::call_service(&app, list(&refresh_token)).await;
+    assert_eq!(resp.status(), 401, "Refresh tokens are not access tokens");
+
+    denylist
+        .revoke_session(&session_id.to_string(), std::time::Duration::from_secs(60))
+        .await;
+    let resp = test::call_service(&app, list(&access_token)).await;
+    assert_eq!(resp.status(), 401, "Tokens of a revoked session stop working");
+}
+
+#[actix_rt::test]
+async fn test_revoke_other_sessions_rejects_their_access_tokens() {
+    let test_app = TestApp::create().await;
+    let repo = AuthRepository::new(test_app.pool.clone());
+    let user = test_app.create_test_user().await;
+
+    let (current, _) = create_session(&repo, user.id, "Firefox").await;
+    let (laptop, _) = create_session(&repo, user.id, "Safari").await;
+    let access_token = |session_id: Uuid| {
+        test_jwt_service()
+            .generate_session_access_token(&user.id.to_string(), &user.email, "user", &session_id.to_string())
+            .expect("Failed to generate access token")
+    };
+    let current_token = access_token(current);
+    let laptop_token = access_token(laptop);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .app_data(web::Data::new(TokenDenylist::new()))
+            .configure(auth_service::config),
+    )
+    .await;
+
+    let request = |method: test::TestRequest, token: &str| {
+        method
+            .uri("/auth/sessions")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, request(test::TestRequest::delete(), &current_token)).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["revoked"], 1);
+
+    let resp = test::call_service(&app, request(test::TestRequest::get(), &laptop_token)).await;
+    assert_eq!(resp.status(), 401, "The other device's access token is revoked");
+    let resp = test::call_service(&app, request(test::TestRequest::get(), &current_token)).await;
+    assert_eq!(resp.status(), 200);
+}
@@ -0,0 +1,97 @@
+//! `POST /users/resolve` tests
+//!
+//! Checks that user ids resolve to public profiles, that unknown ids are left
+//! out, and that the per-request cap is enforced.
+//!
+//! Run with: cargo test --test lib auth::users_resolve_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{test_jwt_service, TestApp, TestUser};
+use actix_web::{http::header, test, web, App};
+use auth_service::models::MAX_RESOLVE_USER_IDS;
+use auth_service::repository::AuthRepository;
+use auth_service::users::resolve_users;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Resolve `user_ids` as `caller`, returning the status and body
+async fn resolve(test_app: &TestApp, caller: &TestUser, user_ids: Vec<String>) -> (u16, Value) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AuthRepository::new(test_app.pool.clone())))
+            .app_data(web::Data::new(test_jwt_service()))
+            .route("/users/resolve", web::post().to(resolve_users)),
+    )
+    .await;
+
+    let token = test_jwt_service()
+        .generate_access_token(&caller.id.to_string(), &caller.email, "user")
+        .expect("Failed to generate access token");
+    let req = test::TestRequest::post()
+        .uri("/users/resolve")
+        .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+        .set_json(json!({ "user_ids": user_ids }))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    let status = resp.status().as_u16();
+    (status, test::read_body_json(resp).await)
+}
+
+#[actix_rt::test]
+async fn test_resolve_known_ids_returns_profiles() {
+    let app = TestApp::create().await;
+    let caller = app.create_test_user().await;
+    let alice = app.create_test_user().await;
+    let bob = app.create_test_user().await;
+
+    let (status, body) = resolve(&app, &caller, vec![alice.id.to_string(), bob.id.to_string()]).await;
+    assert_eq!(status, 200);
+
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    let alice_profile = users.iter().find(|u| u["id"] == alice.id.to_string()).unwrap();
+    assert_eq!(alice_profile["display_name"], alice.display_name.as_str());
+    assert!(alice_profile["avatar_url"].is_null());
+    assert!(alice_profile.get("email").is_none(), "Only the public profile is exposed");
+}
+
+#[actix_rt::test]
+async fn test_resolve_omits_unknown_ids() {
+    let app = TestApp::create().await;
+    let caller = app.create_test_user().await;
+    let known = app.create_test_user().await;
+
+    let (status, body) = resolve(
+        &app,
+        &caller,
+        vec![
+            Uuid::new_v4().to_string(),
+            known.id.to_string(),
+            "not-a-uuid".to_string(),
+            known.id.to_string(),
+        ],
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let users = body["users"].as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["id"], known.id.to_string());
+}
+
+#[actix_rt::test]
+async fn test_resolve_enforces_cap() {
+    let app = TestApp::create().await;
+    let caller = app.create_test_user().await;
+
+    let at_cap: Vec<String> = (0..MAX_RESOLVE_USER_IDS).map(|_| Uuid::new_v4().to_string()).collect();
+    let (status, _) = resolve(&app, &caller, at_cap.clone()).await;
+    assert_eq!(status, 200);
+
+    let mut over_cap = at_cap;
+    over_cap.push(caller.id.to_string());
+    let (status, body) = resolve(&app, &caller, over_cap).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "VALIDATION_ERROR");
+}
@@ -0,0 +1,162 @@
+//! Document access masking tests
+//!
+//! Checks that with `hide_forbidden_as_not_found` a caller outside the space
+//! gets the same `404 DOC_NOT_FOUND` for an existing document as for a
+//! missing one (for reads and for writes such as commenting, tagging and
+//! pinning), that a viewer editing a document still gets `403`, and that
+//! without the flag the outsider still gets `403 ACCESS_DENIED`.
+//!
+//! Run with: cargo test --test lib documents::access_masking_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::comments::{create_comment, delete_comment, list_comments, resolve_comment, unresolve_comment};
+use document_service::handlers::{get_document, pin_document, update_document, AccessDenialPolicy};
+use document_service::repository::DocumentRepository;
+use document_service::tags::add_tags;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Send `req` to the document routes, registering `policy` when given
+async fn call(app: &TestApp, policy: Option<AccessDenialPolicy>, req: test::TestRequest) -> (u16, Value) {
+    let mut service_app = App::new().app_data(web::Data::new(DocumentRepository::new(app.pool.clone())));
+    if let Some(policy) = policy {
+        service_app = service_app.app_data(web::Data::new(policy));
+    }
+    let service = test::init_service(
+        service_app
+            .route("/documents/{documentId}", web::get().to(get_document))
+            .route("/documents/{documentId}", web::patch().to(update_document))
+            .route("/documents/{documentId}/comments", web::get().to(list_comments))
+            .route("/documents/{documentId}/comments", web::post().to(create_comment))
+            .route("/documents/{documentId}/tags", web::post().to(add_tags))
+            .route("/documents/{documentId}/pin", web::post().to(pin_document))
+            .route("/comments/{commentId}/resolve", web::post().to(resolve_comment))
+            .route("/comments/{commentId}/unresolve", web::post().to(unresolve_comment))
+            .route("/comments/{commentId}", web::delete().to(delete_comment)),
+    )
+    .await;
+
+    let resp = test::call_service(&service, req.to_request()).await;
+    let status = resp.status().as_u16();
+    let body: Value = test::read_body_json(resp).await;
+    (status, body)
+}
+
+const HIDE: Option<AccessDenialPolicy> = Some(AccessDenialPolicy {
+    hide_forbidden_as_not_found: true,
+});
+
+#[actix_rt::test]
+async fn test_inaccessible_document_looks_missing_under_flag() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+
+    for uri in [
+        format!("/documents/{}", document.id),
+        format!("/documents/{}/comments", document.id),
+    ] {
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("X-User-Id", outsider.id.to_string()));
+        let (status, body) = call(&app, HIDE, req).await;
+
+        assert_eq!(status, 404, "{}", uri);
+        assert_eq!(body["error"]["error"], "DOC_NOT_FOUND");
+    }
+
+    // Same answer as for an id that doesn't exist at all
+    let req = test::TestRequest::get()
+        .uri(&format!("/documents/{}", Uuid::new_v4()))
+        .insert_header(("X-User-Id", outsider.id.to_string()));
+    let (status, body) = call(&app, HIDE, req).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"]["error"], "DOC_NOT_FOUND");
+
+    let req = test::TestRequest::patch()
+        .uri(&format!("/documents/{}", document.id))
+        .insert_header(("X-User-Id", outsider.id.to_string()))
+        .set_json(serde_json::json!({ "title": "Renamed" }));
+    let (status, body) = call(&app, HIDE, req).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"]["error"], "DOC_NOT_FOUND");
+}
+
+#[actix_rt::test]
+async fn test_writes_to_inaccessible_document_look_missing_under_flag() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+    let comment = repo
+        .create_comment(&document.id.to_string(), owner.id, "Owner", "Looks good", None, &[])
+        .await
+        .expect("Failed to create comment");
+
+    let requests = [
+        test::TestRequest::post()
+            .uri(&format!("/documents/{}/comments", document.id))
+            .set_json(serde_json::json!({ "content": "Hi" })),
+        test::TestRequest::post()
+            .uri(&format!("/documents/{}/tags", document.id))
+            .set_json(serde_json::json!({ "tags": ["draft"] })),
+        test::TestRequest::post().uri(&format!("/documents/{}/pin", document.id)),
+        test::TestRequest::post().uri(&format!("/comments/{}/resolve", comment.id)),
+        test::TestRequest::post().uri(&format!("/comments/{}/unresolve", comment.id)),
+        test::TestRequest::delete().uri(&format!("/comments/{}", comment.id)),
+    ];
+    for req in requests {
+        let req = req.insert_header(("X-User-Id", outsider.id.to_string()));
+        let (status, body) = call(&app, HIDE, req).await;
+
+        assert_eq!(status, 404, "{}", body);
+        assert_eq!(body["error"]["error"], "DOC_NOT_FOUND");
+    }
+}
+
+#[actix_rt::test]
+async fn test_viewer_editing_still_gets_forbidden_under_flag() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let viewer = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+    repo.add_space_member(&space.id.to_string(), &viewer.id.to_string(), "viewer", owner.id)
+        .await
+        .expect("Failed to add viewer");
+
+    let req = test::TestRequest::patch()
+        .uri(&format!("/documents/{}", document.id))
+        .insert_header(("X-User-Id", viewer.id.to_string()))
+        .set_json(serde_json::json!({ "title": "Renamed" }));
+    let (status, body) = call(&app, HIDE, req).await;
+
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["error"], "PERMISSION_DENIED");
+}
+
+#[actix_rt::test]
+async fn test_inaccessible_document_is_forbidden_without_flag() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+
+    for policy in [None, Some(AccessDenialPolicy::default())] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/documents/{}", document.id))
+            .insert_header(("X-User-Id", outsider.id.to_string()));
+        let (status, body) = call(&app, policy, req).await;
+
+        assert_eq!(status, 403);
+        assert_eq!(body["error"]["error"], "ACCESS_DENIED");
+    }
+}
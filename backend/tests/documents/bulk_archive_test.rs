@@ -0,0 +1,148 @@
+//! Bulk document archive tests
+//!
+//! Checks that `DocumentRepository::bulk_archive` archives every live
+//! document in a batch and reports missing or already archived ones, and
+//! that `POST /documents/bulk/archive` reports documents the caller cannot
+//! edit as failures with a reason code without aborting the batch.
+//!
+//! Run with: cargo test --test lib documents::bulk_archive_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{create_document, TestApp};
+use actix_web::{test, web, App};
+use document_service::handlers::bulk_archive_documents;
+use document_service::repository::DocumentRepository;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_bulk_archive_mixed_batch() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let first = create_document(&repo, &space.id, &user.id, "First").await;
+    let second = create_document(&repo, &space.id, &user.id, "Second").await;
+    let already_archived = create_document(&repo, &space.id, &user.id, "Old").await;
+    assert!(repo.delete(&already_archived.to_string()).await.unwrap());
+    let missing = Uuid::new_v4();
+
+    let (archived, failed) = repo
+        .bulk_archive(&[first, missing, second, already_archived])
+        .await;
+
+    assert_eq!(archived, vec![first, second]);
+    let failed_ids: Vec<Uuid> = failed.iter().map(|(id, _)| *id).collect();
+    assert_eq!(failed_ids, vec![missing, already_archived]);
+    assert!(failed.iter().all(|(_, reason)| *reason == "DOC_NOT_FOUND"));
+
+    for id in [first, second] {
+        let document = repo.get_by_id(&id.to_string()).await.unwrap().unwrap();
+        assert!(document.is_archived);
+    }
+}
+
+#[tokio::test]
+async fn test_bulk_archive_empty_batch() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+
+    let (archived, failed) = repo.bulk_archive(&[]).await;
+
+    assert!(archived.is_empty());
+    assert!(failed.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_bulk_archive_endpoint_reports_access_denied_items() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let other = app.create_test_user().await;
+    let own_space = app.create_test_space_for_user(&user.id).await;
+    let other_space = app.create_test_space_for_user(&other.id).await;
+
+    let own = create_document(&repo, &own_space.id, &user.id, "Mine").await;
+    let foreign = create_document(&repo, &other_space.id, &other.id, "Theirs").await;
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/documents/bulk/archive", web::post().to(bulk_archive_documents)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/documents/bulk/archive")
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .set_json(serde_json::json!({ "document_ids": [foreign, own] }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["archived"], serde_json::json!([own]));
+    assert_eq!(body["data"]["failed"][0]["document_id"], foreign.to_string());
+    assert_eq!(body["data"]["failed"][0]["reason"], "ACCESS_DENIED");
+
+    // The document the caller could not access is untouched
+    let document = repo.get_by_id(&foreign.to_string()).await.unwrap().unwrap();
+    assert!(!document.is_archived);
+}
+
+#[actix_rt::test]
+async fn test_bulk_archive_endpoint_requires_edit_permission() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let viewer = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    app.add_space_member(&space.id, &viewer.id, "viewer").await;
+
+    let document = create_document(&repo, &space.id, &owner.id, "Read only").await;
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/documents/bulk/archive", web::post().to(bulk_archive_documents)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/documents/bulk/archive")
+        .insert_header(("X-User-Id", viewer.id.to_string()))
+        .set_json(serde_json::json!({ "document_ids": [document] }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["archived"], serde_json::json!([]));
+    assert_eq!(body["data"]["failed"][0]["document_id"], document.to_string());
+    assert_eq!(body["data"]["failed"][0]["reason"], "PERMISSION_DENIED");
+
+    let document = repo.get_by_id(&document.to_string()).await.unwrap().unwrap();
+    assert!(!document.is_archived);
+}
+
+#[actix_rt::test]
+async fn test_bulk_archive_endpoint_rejects_empty_request() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/documents/bulk/archive", web::post().to(bulk_archive_documents)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/documents/bulk/archive")
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .set_json(serde_json::json!({ "document_ids": [] }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 400);
+}
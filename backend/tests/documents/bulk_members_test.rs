@@ -0,0 +1,110 @@
+//! Bulk member add tests
+//!
+//! Checks that `DocumentRepository::add_members_bulk` reports an outcome per
+//! entry: valid entries are added or have their role changed, while repeats,
+//! undefined roles, unknown users, the space owner and entries past the
+//! member cap fail on their own without blocking the rest of the batch.
+//!
+//! Run with: cargo test --test lib documents::bulk_members_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{role_of, TestApp};
+use document_service::repository::{BulkMemberFailure, BulkMemberOutcome, DocumentRepository};
+use uuid::Uuid;
+
+fn entry(user_id: impl ToString, role: &str) -> (String, String) {
+    (user_id.to_string(), role.to_string())
+}
+
+#[tokio::test]
+async fn test_mixed_batch_reports_each_entry() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+    let existing = app.create_test_user().await;
+    let unchanged = app.create_test_user().await;
+    let fresh = app.create_test_user().await;
+    let other = app.create_test_user().await;
+    for (user, role) in [(&existing, "viewer"), (&unchanged, "commenter")] {
+        repo.add_space_member(&space_id, &user.id.to_string(), role, owner.id)
+            .await
+            .expect("Failed to add member");
+    }
+
+    let entries = vec![
+        entry(fresh.id, "editor"),
+        entry(existing.id, "editor"),
+        entry(unchanged.id, "commenter"),
+        entry(fresh.id, "viewer"),
+        entry(other.id, "superuser"),
+        entry(Uuid::new_v4(), "viewer"),
+        entry("not-a-uuid", "viewer"),
+    ];
+    let outcomes = repo
+        .add_members_bulk(&space_id, &entries, owner.id)
+        .await
+        .expect("Bulk add should succeed");
+
+    assert_eq!(outcomes.len(), entries.len());
+    assert!(matches!(&outcomes[0], BulkMemberOutcome::Added(row) if row.user_id == fresh.id));
+    assert!(matches!(&outcomes[1], BulkMemberOutcome::RoleChanged(row) if row.role == "editor"));
+    assert!(matches!(outcomes[2], BulkMemberOutcome::Unchanged));
+    assert!(matches!(outcomes[3], BulkMemberOutcome::Failed(BulkMemberFailure::DuplicateEntry)));
+    assert!(matches!(outcomes[4], BulkMemberOutcome::Failed(BulkMemberFailure::InvalidRole)));
+    assert!(matches!(outcomes[5], BulkMemberOutcome::Failed(BulkMemberFailure::UserNotFound)));
+    assert!(matches!(outcomes[6], BulkMemberOutcome::Failed(BulkMemberFailure::InvalidUserId)));
+
+    // The first entry for a repeated user wins
+    assert_eq!(role_of(&repo, &space_id, &fresh.id).await.as_deref(), Some("editor"));
+    assert_eq!(role_of(&repo, &space_id, &existing.id).await.as_deref(), Some("editor"));
+    assert_eq!(role_of(&repo, &space_id, &other.id).await, None);
+}
+
+#[tokio::test]
+async fn test_entries_past_cap_fail_individually() {
+    let app = TestApp::create().await;
+    // Owner plus one member
+    let repo = DocumentRepository::new(app.pool.clone()).with_max_space_members(2);
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let first = app.create_test_user().await;
+    let second = app.create_test_user().await;
+
+    let outcomes = repo
+        .add_members_bulk(
+            &space.id.to_string(),
+            &[entry(first.id, "viewer"), entry(second.id, "viewer")],
+            owner.id,
+        )
+        .await
+        .expect("Bulk add should succeed");
+
+    assert!(matches!(outcomes[0], BulkMemberOutcome::Added(_)));
+    assert!(matches!(outcomes[1], BulkMemberOutcome::Failed(BulkMemberFailure::LimitReached)));
+}
+
+#[tokio::test]
+async fn test_owner_entry_fails_and_keeps_owner_role() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+    let member = app.create_test_user().await;
+
+    let outcomes = repo
+        .add_members_bulk(
+            &space_id,
+            &[entry(owner.id, "viewer"), entry(member.id, "viewer")],
+            owner.id,
+        )
+        .await
+        .expect("Bulk add should succeed");
+
+    assert!(matches!(outcomes[0], BulkMemberOutcome::Failed(BulkMemberFailure::SpaceOwner)));
+    assert!(matches!(outcomes[1], BulkMemberOutcome::Added(_)));
+    assert!(repo.is_space_owner(&space_id, owner.id).await.unwrap());
+    assert_ne!(role_of(&repo, &space_id, &owner.id).await.as_deref(), Some("viewer"));
+}
@@ -0,0 +1,63 @@
+//! Comment author display tests
+//!
+//! Checks that comment queries join the author's current display name and
+//! avatar from `users`, rather than returning the unused stored fields.
+//!
+//! Run with: cargo test --test lib documents::comment_authors_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::DocumentRepository;
+
+#[tokio::test]
+async fn test_comment_queries_join_author_display_fields() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let owner_id = owner.id;
+
+    sqlx::query("UPDATE users SET avatar_url = $2 WHERE id = $1")
+        .bind(owner.id)
+        .bind("https://example.com/owner.png")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let document = repo
+        .create(&space.id.to_string(), None, "Discussed", None, None, owner_id)
+        .await
+        .expect("Failed to create document");
+
+    // The header-supplied name is ignored in favour of the user record
+    let created = repo
+        .create_comment(&document.id.to_string(), owner_id, "Header Name", "First", None, &[])
+        .await
+        .expect("Failed to create comment");
+    assert_eq!(created.author_name.as_deref(), Some(owner.display_name.as_str()));
+    assert_eq!(created.author_avatar.as_deref(), Some("https://example.com/owner.png"));
+
+    let (listed, total) = repo
+        .list_comments(&document.id.to_string(), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(listed[0].author_name.as_deref(), Some(owner.display_name.as_str()));
+
+    // Renames show up on existing comments
+    sqlx::query("UPDATE users SET display_name = 'Renamed Owner' WHERE id = $1")
+        .bind(owner.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let resolved = repo
+        .resolve_comment(&created.id.to_string(), owner_id)
+        .await
+        .unwrap();
+    assert_eq!(resolved.author_name.as_deref(), Some("Renamed Owner"));
+
+    let fetched = repo.get_comment(&created.id.to_string()).await.unwrap().unwrap();
+    assert_eq!(fetched.author_name.as_deref(), Some("Renamed Owner"));
+    assert_eq!(fetched.author_avatar.as_deref(), Some("https://example.com/owner.png"));
+}
@@ -0,0 +1,55 @@
+//! Per-document comment count tests
+//!
+//! Checks that `comment_counts` groups total and unresolved comments per
+//! document and ignores documents outside the requested space.
+//!
+//! Run with: cargo test --test lib documents::comment_counts_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::DocumentRepository;
+
+#[tokio::test]
+async fn test_comment_counts_group_total_and_unresolved() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let other_space = app.create_test_space_for_user(&owner.id).await;
+    let owner_id = owner.id;
+
+    let mut documents = Vec::new();
+    for (space_id, title) in [(space.id, "Busy"), (space.id, "Quiet"), (other_space.id, "Elsewhere")] {
+        let document = repo
+            .create(&space_id.to_string(), None, title, None, None, owner_id)
+            .await
+            .expect("Failed to create document");
+        documents.push(document.id);
+    }
+    let (busy, quiet, elsewhere) = (documents[0], documents[1], documents[2]);
+
+    let mut busy_comments = Vec::new();
+    for content in ["One", "Two", "Three"] {
+        let comment = repo
+            .create_comment(&busy.to_string(), owner_id, "Owner", content, None, &[])
+            .await
+            .expect("Failed to create comment");
+        busy_comments.push(comment);
+    }
+    repo.resolve_comment(&busy_comments[0].id.to_string(), owner_id)
+        .await
+        .expect("Failed to resolve comment");
+    repo.create_comment(&elsewhere.to_string(), owner_id, "Owner", "Hidden", None, &[])
+        .await
+        .expect("Failed to create comment");
+
+    let counts = repo
+        .comment_counts(&space.id.to_string(), &[busy, quiet, elsewhere])
+        .await
+        .expect("Failed to count comments");
+
+    assert_eq!(counts.get(&busy), Some(&(3, 2)));
+    assert!(!counts.contains_key(&quiet), "Documents without comments are absent");
+    assert!(!counts.contains_key(&elsewhere), "Other spaces are not counted");
+    assert!(repo.comment_counts(&space.id.to_string(), &[]).await.unwrap().is_empty());
+}
@@ -0,0 +1,93 @@
+//! Comment permission tests
+//!
+//! Checks that members with the `commenter` role can comment on and edit
+//! their own comments but not edit documents, while `viewer` members cannot
+//! comment at all.
+//!
+//! Run with: cargo test --test lib documents::comment_permissions_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::has_permission;
+use document_service::comments::{create_comment, update_comment};
+use document_service::repository::DocumentRepository;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Create a document in a new space and add a member with `role`,
+/// returning (document id, member id)
+async fn document_with_member(app: &TestApp, repo: &DocumentRepository, role: &str) -> (Uuid, Uuid) {
+    let owned = app.create_owned_document(repo, "Design review", None).await;
+    let member = app.create_space_member(&owned.space.id, role).await;
+    (owned.document.id, member.id)
+}
+
+/// Post a comment as `user_id`, returning the status and body
+async fn post_comment(app: &TestApp, document_id: Uuid, user_id: Uuid) -> (u16, Value) {
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/documents/{documentId}/comments", web::post().to(create_comment)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/documents/{}/comments", document_id))
+        .insert_header(("X-User-Id", user_id.to_string()))
+        .set_json(serde_json::json!({"content": "Looks good to me"}))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    let status = resp.status().as_u16();
+    (status, test::read_body_json(resp).await)
+}
+
+#[actix_rt::test]
+async fn test_commenter_can_comment() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (document_id, commenter_id) = document_with_member(&app, &repo, "commenter").await;
+
+    let (status, body) = post_comment(&app, document_id, commenter_id).await;
+    assert_eq!(status, 201);
+    let comment_id = body["data"]["id"].as_str().expect("Comment id").to_string();
+
+    // Commenters can edit their own comment
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/comments/{commentId}", web::patch().to(update_comment)),
+    )
+    .await;
+    let req = test::TestRequest::patch()
+        .uri(&format!("/comments/{}", comment_id))
+        .insert_header(("X-User-Id", commenter_id.to_string()))
+        .set_json(serde_json::json!({"content": "Looks good, one nit"}))
+        .to_request();
+    assert_eq!(test::call_service(&service, req).await.status(), 200);
+
+    // ...but the role grants no edit rights on the document itself
+    let role = repo
+        .get_document_role(&document_id.to_string(), commenter_id)
+        .await
+        .unwrap()
+        .expect("Commenter should have a role");
+    assert_eq!(role.name, "commenter");
+    assert!(!has_permission(&role, Permission::EditDocuments));
+}
+
+#[actix_rt::test]
+async fn test_viewer_cannot_comment() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (document_id, viewer_id) = document_with_member(&app, &repo, "viewer").await;
+
+    let (status, body) = post_comment(&app, document_id, viewer_id).await;
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["error"], "PERMISSION_DENIED");
+
+    // Non-members are denied as well
+    let (status, _) = post_comment(&app, document_id, Uuid::new_v4()).await;
+    assert_eq!(status, 403);
+}
@@ -0,0 +1,66 @@
+//! Document content size tests
+//!
+//! Checks that `content_size` is the byte length of the stored JSON text on
+//! both the create and update paths, so multibyte content is not undercounted
+//! as it was when characters were counted.
+//!
+//! Run with: cargo test --test lib documents::content_size_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::DocumentRepository;
+use serde_json::json;
+
+// Postgres prints jsonb with a space after each colon
+fn stored_text_bytes(text: &str) -> i32 {
+    format!(r#"{{"text": "{}"}}"#, text).len() as i32
+}
+
+#[tokio::test]
+async fn test_multibyte_content_size_is_byte_length() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let created_text = "héllo wörld 日本語";
+    let document = repo
+        .create(
+            &space.id.to_string(),
+            None,
+            "Multibyte",
+            None,
+            Some(json!({ "text": created_text })),
+            user.id,
+        )
+        .await
+        .expect("Failed to create document");
+
+    assert_eq!(document.content_size, stored_text_bytes(created_text));
+    // More bytes than characters, so a character count would be too small
+    assert!(document.content_size as usize > r#"{"text": "héllo wörld 日本語"}"#.chars().count());
+
+    let updated_text = "emoji 🎉🎉 and ümlauts";
+    let document = repo
+        .update(
+            &document.id.to_string(),
+            None,
+            None,
+            Some(json!({ "text": updated_text })),
+            user.id,
+        )
+        .await
+        .expect("Failed to update document")
+        .expect("Document should exist");
+
+    assert_eq!(document.content_size, stored_text_bytes(updated_text));
+
+    // A title-only update keeps the size
+    let document = repo
+        .update(&document.id.to_string(), Some("Renamed"), None, None, user.id)
+        .await
+        .expect("Failed to update document")
+        .expect("Document should exist");
+
+    assert_eq!(document.content_size, stored_text_bytes(updated_text));
+}
@@ -148,6 +148,7 @@ async fn test_update_document_title() {
         title: Some("Updated Title".to_string()),
         icon: None,
         content: None,
+        version: None,
     };
 
     let response = app
@@ -185,6 +186,7 @@ async fn test_update_document_content() {
         title: None,
         icon: None,
         content: Some(new_content),
+        version: None,
     };
 
     let response = app
@@ -308,3 +310,56 @@ async fn test_document_hierarchy_nested() {
     assert_eq!(result["success"], true);
     assert!(result["error"].is_null());
 }
+
+#[tokio::test]
+async fn test_update_document_with_version_round_trip() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+
+    let response = app
+        .auth_get(&format!("/api/v1/documents/{}", document.id), Some(user.id), None)
+        .await
+        .send()
+        .await
+        .expect("Get document request failed");
+    let result: serde_json::Value = response.json().await.expect("Parse response failed");
+    let version = result["data"]["version"].as_i64().expect("Document should carry its version");
+
+    // Sending back the loaded version succeeds and bumps it
+    let request = UpdateDocumentRequest {
+        title: None,
+        icon: None,
+        content: Some(serde_json::json!({"type": "Y.Doc", "text": "First edit"})),
+        version: Some(version),
+    };
+    let response = app
+        .auth_patch(&format!("/api/v1/documents/{}", document.id), Some(user.id), None)
+        .await
+        .json(&request)
+        .send()
+        .await
+        .expect("Update document request failed");
+    assert!(response.status().is_success());
+    let result: serde_json::Value = response.json().await.expect("Parse response failed");
+    assert_eq!(result["data"]["version"], version + 1);
+
+    // The same, now stale, version is rejected
+    let request = UpdateDocumentRequest {
+        title: None,
+        icon: None,
+        content: Some(serde_json::json!({"type": "Y.Doc", "text": "Second edit"})),
+        version: Some(version),
+    };
+    let response = app
+        .auth_patch(&format!("/api/v1/documents/{}", document.id), Some(user.id), None)
+        .await
+        .json(&request)
+        .send()
+        .await
+        .expect("Update document request failed");
+    assert_eq!(response.status(), 409);
+    let result: serde_json::Value = response.json().await.expect("Parse response failed");
+    assert_eq!(result["error"]["error"], "VERSION_CONFLICT");
+}
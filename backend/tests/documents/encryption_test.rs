@@ -0,0 +1,131 @@
+//! Document content encryption at rest tests
+//!
+//! Checks that content in spaces flagged `content_encrypted` is stored as a
+//! ciphertext envelope while `DocumentRepository` reads return plaintext, and
+//! that other spaces keep storing plaintext.
+//!
+//! Run with: cargo test --test lib documents::encryption_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::encryption::{is_encrypted, ContentEncryption, DerivedKeyProvider};
+use document_service::repository::DocumentRepository;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn repository(app: &TestApp) -> DocumentRepository {
+    let encryption = ContentEncryption::new(Arc::new(DerivedKeyProvider::new([42u8; 32])));
+    DocumentRepository::new(app.pool.clone()).with_encryption(Some(Arc::new(encryption)))
+}
+
+async fn set_space_encrypted(app: &TestApp, space_id: &Uuid) {
+    sqlx::query("UPDATE spaces SET content_encrypted = true WHERE id = $1")
+        .bind(space_id)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to flag space as encrypted");
+}
+
+async fn stored_content(app: &TestApp, document_id: &Uuid) -> serde_json::Value {
+    let row: (serde_json::Value,) = sqlx::query_as("SELECT content FROM documents WHERE id = $1")
+        .bind(document_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Failed to read stored content");
+    row.0
+}
+
+#[tokio::test]
+async fn test_encrypted_space_stores_ciphertext() {
+    let app = TestApp::create().await;
+    let repo = repository(&app);
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    set_space_encrypted(&app, &space.id).await;
+
+    let content = serde_json::json!({"text": "quarterly revenue forecast"});
+    let document = repo
+        .create(
+            &space.id.to_string(),
+            None,
+            "Encrypted doc",
+            None,
+            Some(content.clone()),
+            user.id,
+        )
+        .await
+        .expect("Failed to create document");
+    assert_eq!(document.content.0, content);
+
+    let stored = stored_content(&app, &document.id).await;
+    assert!(is_encrypted(&stored));
+    assert!(!stored.to_string().contains("quarterly revenue forecast"));
+
+    let fetched = repo
+        .get_by_id(&document.id.to_string())
+        .await
+        .unwrap()
+        .expect("Document should exist");
+    assert_eq!(fetched.content.0, content);
+
+    // Updates are sealed as well
+    let updated_content = serde_json::json!({"text": "revised forecast"});
+    let updated = repo
+        .update(
+            &document.id.to_string(),
+            None,
+            None,
+            Some(updated_content.clone()),
+            user.id,
+        )
+        .await
+        .unwrap()
+        .expect("Document should exist");
+    assert_eq!(updated.content.0, updated_content);
+    assert!(is_encrypted(&stored_content(&app, &document.id).await));
+}
+
+#[tokio::test]
+async fn test_plain_space_stores_plaintext() {
+    let app = TestApp::create().await;
+    let repo = repository(&app);
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let content = serde_json::json!({"text": "public notes"});
+    let document = repo
+        .create(
+            &space.id.to_string(),
+            None,
+            "Plain doc",
+            None,
+            Some(content.clone()),
+            user.id,
+        )
+        .await
+        .expect("Failed to create document");
+
+    assert_eq!(stored_content(&app, &document.id).await, content);
+}
+
+#[tokio::test]
+async fn test_encrypted_space_without_key_rejects_writes() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone()).with_encryption(None);
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    set_space_encrypted(&app, &space.id).await;
+
+    let result = repo
+        .create(
+            &space.id.to_string(),
+            None,
+            "Encrypted doc",
+            None,
+            Some(serde_json::json!({"text": "secret"})),
+            user.id,
+        )
+        .await;
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,148 @@
+//! Document ETag tests
+//!
+//! Checks that `GET /documents/{id}` and `GET /documents/{id}/versions/{n}`
+//! send an ETag that stays the same while the document is unchanged, answer
+//! `304 Not Modified` when `If-None-Match` names it, and send a new tag once
+//! the document is edited or the caller's requested favorite flag changes.
+//!
+//! Run with: cargo test --test lib documents::etag_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::dev::ServiceResponse;
+use actix_web::{test, web, App};
+use document_service::handlers::{get_document, get_version};
+use document_service::repository::DocumentRepository;
+use serde_json::{json, Value};
+
+fn request(uri: &str, user_id: &str, if_none_match: Option<&str>) -> test::TestRequest {
+    let req = test::TestRequest::get().uri(uri).insert_header(("X-User-Id", user_id));
+    match if_none_match {
+        Some(etag) => req.insert_header(("If-None-Match", etag)),
+        None => req,
+    }
+}
+
+fn etag(resp: &ServiceResponse) -> String {
+    resp.headers()
+        .get("ETag")
+        .expect("Response should carry an ETag")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[actix_rt::test]
+async fn test_document_etag_and_not_modified() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let user_id = owner.id.to_string();
+    let document = repo
+        .create(&space.id.to_string(), None, "Notes", None, Some(json!({"text": "v1"})), owner.id)
+        .await
+        .expect("Failed to create document");
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo.clone()))
+            .route("/documents/{documentId}", web::get().to(get_document)),
+    )
+    .await;
+    let uri = format!("/documents/{}", document.id);
+
+    let first = test::call_service(&service, request(&uri, &user_id, None).to_request()).await;
+    assert_eq!(first.status(), 200);
+    let tag = etag(&first);
+    assert!(tag.starts_with("W/"));
+
+    // Unchanged state, same tag
+    let second = test::call_service(&service, request(&uri, &user_id, None).to_request()).await;
+    assert_eq!(etag(&second), tag);
+
+    let not_modified = test::call_service(&service, request(&uri, &user_id, Some(&tag)).to_request()).await;
+    assert_eq!(not_modified.status(), 304);
+    assert_eq!(etag(&not_modified), tag);
+    assert!(test::read_body(not_modified).await.is_empty());
+
+    // Any edit gives a new tag and a full response
+    repo.update(&document.id.to_string(), Some("Renamed"), None, None, owner.id)
+        .await
+        .expect("Failed to update document");
+    let edited = test::call_service(&service, request(&uri, &user_id, Some(&tag)).to_request()).await;
+    assert_eq!(edited.status(), 200);
+    assert_ne!(etag(&edited), tag);
+}
+
+#[actix_rt::test]
+async fn test_document_etag_tracks_favorite_flag() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let user_id = owner.id.to_string();
+    let document = repo
+        .create(&space.id.to_string(), None, "Notes", None, Some(json!({"text": "v1"})), owner.id)
+        .await
+        .expect("Failed to create document");
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo.clone()))
+            .route("/documents/{documentId}", web::get().to(get_document)),
+    )
+    .await;
+    let uri = format!("/documents/{}?include=favorite", document.id);
+
+    let first = test::call_service(&service, request(&uri, &user_id, None).to_request()).await;
+    assert_eq!(first.status(), 200);
+    let tag = etag(&first);
+
+    // Favoriting changes the response without touching the document
+    repo.add_favorite(&document.id.to_string(), owner.id)
+        .await
+        .expect("Failed to add favorite");
+    let favorited = test::call_service(&service, request(&uri, &user_id, Some(&tag)).to_request()).await;
+    assert_eq!(favorited.status(), 200);
+    assert_ne!(etag(&favorited), tag);
+    let body: Value = test::read_body_json(favorited).await;
+    assert_eq!(body["data"]["is_favorite"], true);
+}
+
+#[actix_rt::test]
+async fn test_version_etag_and_not_modified() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let user_id = owner.id.to_string();
+    let document = repo
+        .create(&space.id.to_string(), None, "Notes", None, Some(json!({"text": "v1"})), owner.id)
+        .await
+        .expect("Failed to create document");
+    let version = repo
+        .create_version(&document.id.to_string(), json!({"text": "v1"}), "Notes", owner.id, None)
+        .await
+        .expect("Failed to create version");
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo))
+            .route("/documents/{documentId}/versions/{versionNumber}", web::get().to(get_version)),
+    )
+    .await;
+    let uri = format!("/documents/{}/versions/{}", document.id, version.version_number);
+
+    let first = test::call_service(&service, request(&uri, &user_id, None).to_request()).await;
+    assert_eq!(first.status(), 200);
+    let tag = etag(&first);
+    assert!(!tag.starts_with("W/"));
+    assert_eq!(etag(&test::call_service(&service, request(&uri, &user_id, None).to_request()).await), tag);
+
+    let not_modified = test::call_service(&service, request(&uri, &user_id, Some(&tag)).to_request()).await;
+    assert_eq!(not_modified.status(), 304);
+
+    let stale = test::call_service(&service, request(&uri, &user_id, Some("\"something-else\"")).to_request()).await;
+    assert_eq!(stale.status(), 200);
+}
@@ -0,0 +1,152 @@
+//! Favorite document tests
+//!
+//! Checks that favoriting a document requires access to it, that
+//! `GET /me/favorites` pages through the caller's favorites newest first,
+//! and that `?include=favorite` reports the flag on a fetched document.
+//!
+//! Run with: cargo test --test lib documents::favorites_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{create_document, TestApp};
+use actix_web::{test, web, App};
+use document_service::favorites::{add_favorite, list_favorites, remove_favorite};
+use document_service::handlers::get_document;
+use document_service::repository::DocumentRepository;
+use serde_json::Value;
+use uuid::Uuid;
+
+macro_rules! favorites_app {
+    ($app:expr) => {
+        test::init_service(
+            App::new()
+                .app_data(web::Data::new(DocumentRepository::new($app.pool.clone())))
+                .route("/documents/{documentId}", web::get().to(get_document))
+                .route("/documents/{documentId}/favorite", web::put().to(add_favorite))
+                .route("/documents/{documentId}/favorite", web::delete().to(remove_favorite))
+                .route("/me/favorites", web::get().to(list_favorites)),
+        )
+        .await
+    };
+}
+
+#[actix_rt::test]
+async fn test_favorite_requires_document_access() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = create_document(&repo, &space.id, &owner.id, "Private").await;
+    let service = favorites_app!(app);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/documents/{}/favorite", document))
+        .insert_header(("X-User-Id", outsider.id.to_string()))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 403);
+
+    let (favorites, total) = repo.list_favorites(outsider.id, 20, 0).await.unwrap();
+    assert!(favorites.is_empty());
+    assert_eq!(total, 0);
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/documents/{}/favorite", document))
+        .insert_header(("X-User-Id", owner.id.to_string()))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 204);
+
+    // Favoriting again is idempotent
+    let req = test::TestRequest::put()
+        .uri(&format!("/documents/{}/favorite", document))
+        .insert_header(("X-User-Id", owner.id.to_string()))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 204);
+}
+
+#[actix_rt::test]
+async fn test_list_favorites_pagination() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let id = create_document(&repo, &space.id, &user.id, &format!("Document {}", i)).await;
+        repo.add_favorite(&id.to_string(), user.id).await.unwrap();
+        // Spread favorite times so the order is deterministic
+        sqlx::query("UPDATE document_favorites SET created_at = NOW() - make_interval(mins => $2) WHERE document_id = $1")
+            .bind(id)
+            .bind(5 - i)
+            .execute(&app.pool)
+            .await
+            .expect("Failed to set created_at");
+        ids.push(id);
+    }
+    let service = favorites_app!(app);
+
+    let page = |offset: i32| {
+        test::TestRequest::get()
+            .uri(&format!("/me/favorites?limit=2&offset={}", offset))
+            .insert_header(("X-User-Id", user.id.to_string()))
+            .to_request()
+    };
+
+    let mut listed = Vec::new();
+    for offset in [0, 2, 4] {
+        let resp = test::call_service(&service, page(offset)).await;
+        assert_eq!(resp.status(), 200);
+        let body: Value = test::read_body_json(resp).await;
+        assert_eq!(body["data"]["total"], 5);
+        assert_eq!(body["data"]["limit"], 2);
+        for document in body["data"]["documents"].as_array().unwrap() {
+            assert_eq!(document["is_favorite"], true);
+            listed.push(Uuid::parse_str(document["id"].as_str().unwrap()).unwrap());
+        }
+    }
+
+    // Newest favorite first
+    ids.reverse();
+    assert_eq!(listed, ids);
+}
+
+#[actix_rt::test]
+async fn test_favorite_flag_and_removal() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let document = create_document(&repo, &space.id, &user.id, "Starred").await;
+    repo.add_favorite(&document.to_string(), user.id).await.unwrap();
+    let service = favorites_app!(app);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/documents/{}?include=favorite", document))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .to_request();
+    let body: Value = test::read_body_json(test::call_service(&service, req).await).await;
+    assert_eq!(body["data"]["is_favorite"], true);
+
+    // Not included unless requested
+    let req = test::TestRequest::get()
+        .uri(&format!("/documents/{}", document))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .to_request();
+    let body: Value = test::read_body_json(test::call_service(&service, req).await).await;
+    assert!(body["data"].get("is_favorite").is_none());
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/documents/{}/favorite", document))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .to_request();
+    assert_eq!(test::call_service(&service, req).await.status(), 204);
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/documents/{}/favorite", document))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .to_request();
+    assert_eq!(test::call_service(&service, req).await.status(), 404);
+}
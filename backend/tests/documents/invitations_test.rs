@@ -0,0 +1,181 @@
+//! Space invitation tests
+//!
+//! Checks that `POST /spaces/{spaceId}/invitations` stores a pending
+//! invitation for an address with no account yet, that the account later
+//! registered with that address can accept it and becomes a member, that a
+//! token can't be redeemed twice or by someone with a different email, and
+//! that only the space owner can invite someone as owner.
+//!
+//! Run with: cargo test --test lib documents::invitations_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::invitations::{accept_invitation, create_invitation};
+use document_service::repository::{DocumentRepository, InvitationError, INVITATION_TOKEN_LENGTH};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn call(app: &TestApp, req: test::TestRequest) -> (u16, Value) {
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/spaces/{spaceId}/invitations", web::post().to(create_invitation))
+            .route("/invitations/{token}/accept", web::post().to(accept_invitation)),
+    )
+    .await;
+
+    let resp = test::call_service(&service, req.to_request()).await;
+    let status = resp.status().as_u16();
+    let body: Value = test::read_body_json(resp).await;
+    (status, body)
+}
+
+#[actix_rt::test]
+async fn test_invite_then_accept_creates_membership() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let email = format!("invitee_{}@example.com", Uuid::new_v4().simple());
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/spaces/{}/invitations", space.id))
+        .insert_header(("X-User-Id", owner.id.to_string()))
+        .set_json(json!({ "email": email.to_uppercase(), "role": "editor" }));
+    let (status, body) = call(&app, req).await;
+
+    assert_eq!(status, 201, "{}", body);
+    assert_eq!(body["data"]["email"], email);
+    assert_eq!(body["data"]["role"], "editor");
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+    assert_eq!(token.len(), INVITATION_TOKEN_LENGTH);
+
+    // The invitee signs up with the invited address afterwards
+    let invitee = app.create_test_user().await;
+    sqlx::query("UPDATE users SET email = $1 WHERE id = $2")
+        .bind(&email)
+        .bind(invitee.id)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to set invitee email");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/invitations/{}/accept", token))
+        .insert_header(("X-User-Id", invitee.id.to_string()));
+    let (status, body) = call(&app, req).await;
+
+    assert_eq!(status, 201, "{}", body);
+    assert_eq!(body["data"]["user_id"], invitee.id.to_string());
+    assert_eq!(body["data"]["invited_by"], owner.id.to_string());
+    let role = repo
+        .get_user_space_role(&space.id.to_string(), invitee.id)
+        .await
+        .unwrap();
+    assert_eq!(role.as_deref(), Some("editor"));
+
+    // Used tokens can't be redeemed again
+    let err = repo
+        .accept_invitation(&token, invitee.id)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, InvitationError::NotFound));
+}
+
+#[actix_rt::test]
+async fn test_accept_requires_invited_email() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let other = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let invitation = repo
+        .invite_by_email(&space.id.to_string(), "someone.else@example.com", "viewer", owner.id)
+        .await
+        .expect("Failed to invite");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/invitations/{}/accept", invitation.token))
+        .insert_header(("X-User-Id", other.id.to_string()));
+    let (status, body) = call(&app, req).await;
+
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["error"], "INVITATION_EMAIL_MISMATCH");
+    let role = repo
+        .get_user_space_role(&space.id.to_string(), other.id)
+        .await
+        .unwrap();
+    assert!(role.is_none());
+}
+
+#[actix_rt::test]
+async fn test_invite_rejects_bad_email_and_non_managers() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let viewer = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    repo.add_space_member(&space.id.to_string(), &viewer.id.to_string(), "viewer", owner.id)
+        .await
+        .expect("Failed to add viewer");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/spaces/{}/invitations", space.id))
+        .insert_header(("X-User-Id", owner.id.to_string()))
+        .set_json(json!({ "email": "not-an-email", "role": "viewer" }));
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["error"], "VALIDATION_ERROR");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/spaces/{}/invitations", space.id))
+        .insert_header(("X-User-Id", viewer.id.to_string()))
+        .set_json(json!({ "email": "friend@example.com", "role": "viewer" }));
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["error"], "ACCESS_DENIED");
+}
+
+#[actix_rt::test]
+async fn test_only_owner_can_invite_as_owner() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let editor = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+    repo.add_space_member(&space_id, &editor.id.to_string(), "editor", owner.id)
+        .await
+        .expect("Failed to add editor");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/spaces/{}/invitations", space.id))
+        .insert_header(("X-User-Id", editor.id.to_string()))
+        .set_json(json!({ "email": "friend@example.com", "role": "owner" }));
+    let (status, body) = call(&app, req).await;
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["error"], "ACCESS_DENIED");
+
+    // An owner-role invitation that did not come from the owner can't be
+    // redeemed either
+    let email = format!("invitee_{}@example.com", Uuid::new_v4().simple());
+    let invitation = repo
+        .invite_by_email(&space_id, &email, "owner", editor.id)
+        .await
+        .expect("Failed to invite");
+    let invitee = app.create_test_user().await;
+    sqlx::query("UPDATE users SET email = $1 WHERE id = $2")
+        .bind(&email)
+        .bind(invitee.id)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to set invitee email");
+
+    let err = repo
+        .accept_invitation(&invitation.token, invitee.id)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, InvitationError::OwnerRoleNotAllowed));
+    let role = repo.get_user_space_role(&space_id, invitee.id).await.unwrap();
+    assert!(role.is_none());
+}
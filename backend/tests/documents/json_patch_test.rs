@@ -0,0 +1,101 @@
+//! Document JSON Patch tests
+//!
+//! Checks that `DocumentRepository::patch_content` applies RFC 6902
+//! patches to stored content, rejects patches that do not apply, and
+//! refuses patches computed against a stale document version.
+//!
+//! Run with: cargo test --test lib documents::json_patch_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::{DocumentRepository, DocumentRow, PatchContentError};
+use json_patch::PatchOperation;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn setup(app: &TestApp, repo: &DocumentRepository) -> (DocumentRow, Uuid) {
+    let content = json!({"title": "Notes", "blocks": ["intro"]});
+    let owned = app.create_owned_document(repo, "Notes", Some(content)).await;
+    (owned.document, owned.owner.id)
+}
+
+fn patch(ops: Value) -> Vec<PatchOperation> {
+    serde_json::from_value(ops).expect("Invalid patch fixture")
+}
+
+#[tokio::test]
+async fn test_valid_patch_updates_content() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (document, user_id) = setup(&app, &repo).await;
+
+    let ops = patch(json!([
+        {"op": "replace", "path": "/title", "value": "Meeting notes"},
+        {"op": "add", "path": "/blocks/-", "value": "agenda"}
+    ]));
+    let patched = repo
+        .patch_content(&document.id.to_string(), document.version, &ops, user_id)
+        .await
+        .expect("Patch should apply");
+
+    assert_eq!(patched.content.0, json!({"title": "Meeting notes", "blocks": ["intro", "agenda"]}));
+    assert_eq!(patched.version, document.version + 1);
+
+    let stored = repo.get_by_id(&document.id.to_string()).await.unwrap().unwrap();
+    assert_eq!(stored.content.0, patched.content.0);
+}
+
+#[tokio::test]
+async fn test_patch_with_bad_path_is_rejected() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (document, user_id) = setup(&app, &repo).await;
+
+    // The first operation is valid, but the patch applies all or nothing
+    let ops = patch(json!([
+        {"op": "replace", "path": "/title", "value": "Changed"},
+        {"op": "remove", "path": "/missing/field"}
+    ]));
+    let result = repo
+        .patch_content(&document.id.to_string(), document.version, &ops, user_id)
+        .await;
+    assert!(matches!(result, Err(PatchContentError::InvalidPatch(_))));
+
+    // Replacing the root with a non-object produces invalid content
+    let ops = patch(json!([{"op": "replace", "path": "", "value": [1, 2]}]));
+    let result = repo
+        .patch_content(&document.id.to_string(), document.version, &ops, user_id)
+        .await;
+    assert!(matches!(result, Err(PatchContentError::InvalidContent(_))));
+
+    let stored = repo.get_by_id(&document.id.to_string()).await.unwrap().unwrap();
+    assert_eq!(stored.content.0, json!({"title": "Notes", "blocks": ["intro"]}));
+    assert_eq!(stored.version, document.version);
+}
+
+#[tokio::test]
+async fn test_patch_against_stale_version_conflicts() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (document, user_id) = setup(&app, &repo).await;
+    let document_id = document.id.to_string();
+
+    let ops = patch(json!([{"op": "replace", "path": "/title", "value": "First"}]));
+    repo.patch_content(&document_id, document.version, &ops, user_id)
+        .await
+        .expect("First patch should apply");
+
+    // A second client still holding the original version loses the race
+    let ops = patch(json!([{"op": "replace", "path": "/title", "value": "Second"}]));
+    let result = repo.patch_content(&document_id, document.version, &ops, user_id).await;
+    match result {
+        Err(PatchContentError::VersionConflict { expected, current }) => {
+            assert_eq!(expected, document.version);
+            assert_eq!(current, document.version + 1);
+        },
+        other => panic!("Expected version conflict, got {:?}", other.map(|d| d.version)),
+    }
+
+    let stored = repo.get_by_id(&document_id).await.unwrap().unwrap();
+    assert_eq!(stored.content.0["title"], "First");
+}
@@ -0,0 +1,103 @@
+//! Space member cap tests
+//!
+//! Checks that `DocumentRepository::add_space_member` and the bulk
+//! `add_space_members` stop at the configured member cap, which counts the
+//! space owner.
+//!
+//! Run with: cargo test --test lib documents::member_limit_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{TestApp, TestSpace};
+use document_service::repository::{AddMemberError, DocumentRepository};
+use uuid::Uuid;
+
+// Owner plus two members
+const CAP: usize = 3;
+
+async fn capped_space(app: &TestApp) -> (DocumentRepository, TestSpace) {
+    let repo = DocumentRepository::new(app.pool.clone()).with_max_space_members(CAP);
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    (repo, space)
+}
+
+async fn add_new_member(app: &TestApp, repo: &DocumentRepository, space: &TestSpace) -> Result<Uuid, AddMemberError> {
+    let user = app.create_test_user().await;
+    repo.add_space_member(
+        &space.id.to_string(),
+        &user.id.to_string(),
+        "viewer",
+        space.owner_id,
+    )
+    .await
+    .map(|membership| membership.user_id)
+}
+
+#[tokio::test]
+async fn test_add_member_up_to_cap_then_rejected() {
+    let app = TestApp::create().await;
+    let (repo, space) = capped_space(&app).await;
+
+    let first = add_new_member(&app, &repo, &space).await.expect("First member fits");
+    add_new_member(&app, &repo, &space).await.expect("Second member fits");
+
+    let result = add_new_member(&app, &repo, &space).await;
+    assert!(matches!(
+        result,
+        Err(AddMemberError::LimitReached { limit: CAP, remaining: 0 })
+    ));
+    assert_eq!(repo.list_space_members(&space.id.to_string()).await.unwrap().len(), CAP);
+
+    // Changing an existing member's role does not need a free slot
+    repo.add_space_member(&space.id.to_string(), &first.to_string(), "editor", space.owner_id)
+        .await
+        .expect("Role change at the cap should succeed");
+}
+
+#[tokio::test]
+async fn test_removing_member_frees_slot() {
+    let app = TestApp::create().await;
+    let (repo, space) = capped_space(&app).await;
+
+    let first = add_new_member(&app, &repo, &space).await.unwrap();
+    add_new_member(&app, &repo, &space).await.unwrap();
+    assert!(add_new_member(&app, &repo, &space).await.is_err());
+
+    assert!(repo.remove_space_member(&space.id.to_string(), first, space.owner_id).await.unwrap());
+
+    add_new_member(&app, &repo, &space)
+        .await
+        .expect("Freed slot should be reusable");
+}
+
+#[tokio::test]
+async fn test_bulk_import_respects_remaining_capacity() {
+    let app = TestApp::create().await;
+    let (repo, space) = capped_space(&app).await;
+    let space_id = space.id.to_string();
+    let owner_id = space.owner_id.to_string();
+
+    let mut members = Vec::new();
+    for _ in 0..CAP {
+        let user = app.create_test_user().await;
+        members.push((user.id.to_string(), "viewer".to_string()));
+    }
+
+    // Three new members do not fit in the two free slots; nothing is added
+    let result = repo.add_space_members(&space_id, &members, space.owner_id).await;
+    assert!(matches!(
+        result,
+        Err(AddMemberError::LimitReached { remaining: 2, .. })
+    ));
+    assert_eq!(repo.list_space_members(&space_id).await.unwrap().len(), 1);
+
+    // Two fit exactly, together with the owner re-listed at the same role
+    let mut fitting = members[..2].to_vec();
+    fitting.push((owner_id.clone(), "owner".to_string()));
+    let added = repo
+        .add_space_members(&space_id, &fitting, space.owner_id)
+        .await
+        .expect("Import within capacity should succeed");
+    assert_eq!(added.len(), 3);
+    assert_eq!(repo.list_space_members(&space_id).await.unwrap().len(), CAP);
+}
@@ -0,0 +1,123 @@
+//! Member removal content policy tests
+//!
+//! Checks what `DocumentRepository::remove_space_member_with_policy` does to
+//! the documents a removed member created: left alone, reassigned to the
+//! space owner, or archived.
+//!
+//! Run with: cargo test --test lib documents::member_removal_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{TestApp, TestSpace, TestUser};
+use document_service::models::MemberContentPolicy;
+use document_service::repository::DocumentRepository;
+use uuid::Uuid;
+
+/// Create a space with an editor who has written one document in it
+async fn space_with_member_document(app: &TestApp, repo: &DocumentRepository) -> (TestSpace, TestUser, Uuid) {
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+
+    repo.add_space_member(
+        &space.id.to_string(),
+        &member.id.to_string(),
+        "editor",
+        owner.id,
+    )
+    .await
+    .expect("Failed to add member");
+
+    let document = repo
+        .create(
+            &space.id.to_string(),
+            None,
+            "Member notes",
+            None,
+            None,
+            member.id,
+        )
+        .await
+        .expect("Failed to create document");
+
+    (space, member, document.id)
+}
+
+async fn document_state(app: &TestApp, document_id: &Uuid) -> (Uuid, bool) {
+    sqlx::query_as("SELECT created_by, is_archived FROM documents WHERE id = $1")
+        .bind(document_id)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Failed to read document")
+}
+
+#[tokio::test]
+async fn test_keep_policy_leaves_documents_unchanged() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (space, member, document_id) = space_with_member_document(&app, &repo).await;
+
+    let removed = repo
+        .remove_space_member_with_policy(
+            &space.id.to_string(),
+            member.id,
+            space.owner_id,
+            MemberContentPolicy::Keep,
+        )
+        .await
+        .unwrap();
+
+    assert!(removed);
+    assert_eq!(document_state(&app, &document_id).await, (member.id, false));
+}
+
+#[tokio::test]
+async fn test_reassign_policy_moves_documents_to_owner() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (space, member, document_id) = space_with_member_document(&app, &repo).await;
+
+    let removed = repo
+        .remove_space_member_with_policy(
+            &space.id.to_string(),
+            member.id,
+            space.owner_id,
+            MemberContentPolicy::Reassign,
+        )
+        .await
+        .unwrap();
+
+    assert!(removed);
+    assert_eq!(document_state(&app, &document_id).await, (space.owner_id, false));
+}
+
+#[tokio::test]
+async fn test_archive_policy_archives_documents() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (space, member, document_id) = space_with_member_document(&app, &repo).await;
+
+    let removed = repo
+        .remove_space_member_with_policy(
+            &space.id.to_string(),
+            member.id,
+            space.owner_id,
+            MemberContentPolicy::Archive,
+        )
+        .await
+        .unwrap();
+
+    assert!(removed);
+    assert_eq!(document_state(&app, &document_id).await, (member.id, true));
+
+    // Removing someone who is not a member changes nothing
+    let removed = repo
+        .remove_space_member_with_policy(
+            &space.id.to_string(),
+            member.id,
+            space.owner_id,
+            MemberContentPolicy::Archive,
+        )
+        .await
+        .unwrap();
+    assert!(!removed);
+}
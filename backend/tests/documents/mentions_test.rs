@@ -0,0 +1,85 @@
+//! Comment @mention tests
+//!
+//! Checks that mentions in comment content resolve only to members of the
+//! document's space, by id or by username (the email's local part), and that
+//! resolved mentions are stored with the comment.
+//!
+//! Run with: cargo test --test lib documents::mentions_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{TestApp, TestUser};
+use document_service::mentions::resolve_content_mentions;
+use document_service::repository::DocumentRepository;
+use uuid::Uuid;
+
+fn username(user: &TestUser) -> &str {
+    user.email.split('@').next().unwrap()
+}
+
+/// Create a space with one member besides the owner, and a document in it
+async fn document_with_member(app: &TestApp, repo: &DocumentRepository) -> (TestUser, TestUser, String) {
+    let owned = app.create_owned_document(repo, "Mentions", None).await;
+    let member = app.create_space_member(&owned.space.id, "editor").await;
+    (owned.owner, member, owned.document.id.to_string())
+}
+
+#[tokio::test]
+async fn test_mentions_resolve_members_by_id_and_username() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (owner, member, document_id) = document_with_member(&app, &repo).await;
+
+    let content = format!("@{} please review, cc @{}", member.id, username(&owner).to_uppercase());
+    let mentioned = resolve_content_mentions(&repo, &document_id, &content).await.unwrap();
+
+    let ids: Vec<Uuid> = mentioned.iter().map(|u| u.id).collect();
+    assert_eq!(ids, vec![member.id, owner.id]);
+    assert_eq!(mentioned[1].username, username(&owner));
+}
+
+#[tokio::test]
+async fn test_mentions_of_non_members_are_ignored() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (_, member, document_id) = document_with_member(&app, &repo).await;
+    let outsider = app.create_test_user().await;
+
+    let content = format!(
+        "@{} @{} @{} @nobody-here and @{}",
+        outsider.id,
+        username(&outsider),
+        Uuid::new_v4(),
+        username(&member)
+    );
+    let mentioned = resolve_content_mentions(&repo, &document_id, &content).await.unwrap();
+
+    let ids: Vec<Uuid> = mentioned.iter().map(|u| u.id).collect();
+    assert_eq!(ids, vec![member.id], "Only the space member resolves");
+}
+
+#[tokio::test]
+async fn test_comment_stores_resolved_mentions() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (owner, member, document_id) = document_with_member(&app, &repo).await;
+
+    let content = format!("Thoughts, @{}?", username(&member));
+    let mentioned = resolve_content_mentions(&repo, &document_id, &content).await.unwrap();
+    let mention_ids: Vec<Uuid> = mentioned.iter().map(|u| u.id).collect();
+
+    let comment = repo
+        .create_comment(&document_id, owner.id, "Owner", &content, None, &mention_ids)
+        .await
+        .expect("Failed to create comment");
+    assert_eq!(comment.content, content, "Content is stored as written");
+
+    let other = repo
+        .create_comment(&document_id, owner.id, "Owner", "No mentions", None, &[])
+        .await
+        .unwrap();
+
+    let stored = repo.list_comment_mentions(&[comment.id, other.id]).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].comment_id, comment.id);
+    assert_eq!(stored[0].user_id, member.id);
+}
@@ -2,3 +2,30 @@ pub mod crud_test;
 pub mod versions_test;
 pub mod integration_test;
 pub mod e2e_document_flow_test;
+pub mod encryption_test;
+pub mod member_removal_test;
+pub mod pins_test;
+pub mod roles_test;
+pub mod comment_permissions_test;
+pub mod notifications_test;
+pub mod unread_test;
+pub mod member_limit_test;
+pub mod mentions_test;
+pub mod reactions_test;
+pub mod comment_counts_test;
+pub mod version_throttle_test;
+pub mod json_patch_test;
+pub mod tags_test;
+pub mod text_metrics_test;
+pub mod bulk_archive_test;
+pub mod favorites_test;
+pub mod templates_test;
+pub mod comment_authors_test;
+pub mod access_masking_test;
+pub mod etag_test;
+pub mod pagination_test;
+pub mod space_audit_test;
+pub mod ownership_transfer_test;
+pub mod invitations_test;
+pub mod bulk_members_test;
+pub mod content_size_test;
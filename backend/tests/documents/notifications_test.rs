@@ -0,0 +1,107 @@
+//! Notification read-marking tests
+//!
+//! Checks that `POST /notifications/read` marks only the requested
+//! notifications, handles `all: true`, and never touches notifications
+//! owned by another user.
+//!
+//! Run with: cargo test --test lib documents::notifications_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::notifications::mark_notifications_read;
+use document_service::repository::DocumentRepository;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Create `count` unread notifications for the user, returning their ids
+async fn seed_notifications(repo: &DocumentRepository, user_id: Uuid, count: usize) -> Vec<Uuid> {
+    let mut ids = Vec::with_capacity(count);
+    for i in 0..count {
+        let row = repo
+            .create_notification(&user_id.to_string(), "comment_reply", json!({ "index": i }))
+            .await
+            .expect("Failed to create notification");
+        ids.push(row.id);
+    }
+    ids
+}
+
+/// Call `POST /notifications/read` as `user_id`, returning the number updated
+async fn mark_read(app: &TestApp, user_id: Uuid, body: Value) -> u64 {
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/notifications/read", web::post().to(mark_notifications_read)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/notifications/read")
+        .insert_header(("X-User-Id", user_id.to_string()))
+        .set_json(body)
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    body["data"]["updated"].as_u64().expect("updated count")
+}
+
+async fn unread_ids(app: &TestApp, user_id: Uuid) -> Vec<Uuid> {
+    let rows: Vec<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM notifications WHERE user_id = $1 AND read_at IS NULL ORDER BY created_at")
+            .bind(user_id)
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    rows.into_iter().map(|(id,)| id).collect()
+}
+
+#[actix_rt::test]
+async fn test_mark_subset_read() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let ids = seed_notifications(&repo, user.id, 3).await;
+
+    let updated = mark_read(&app, user.id, json!({ "ids": [ids[0], ids[2]] })).await;
+    assert_eq!(updated, 2);
+
+    let unread = unread_ids(&app, user.id).await;
+    assert_eq!(unread, vec![ids[1]]);
+
+    // Already read notifications are not counted again
+    let updated = mark_read(&app, user.id, json!({ "ids": [ids[0]] })).await;
+    assert_eq!(updated, 0);
+}
+
+#[actix_rt::test]
+async fn test_mark_all_read() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let other_user = app.create_test_user().await;
+    seed_notifications(&repo, user.id, 4).await;
+    seed_notifications(&repo, other_user.id, 2).await;
+
+    let updated = mark_read(&app, user.id, json!({ "all": true })).await;
+    assert_eq!(updated, 4);
+
+    assert!(unread_ids(&app, user.id).await.is_empty());
+    assert_eq!(unread_ids(&app, other_user.id).await.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_marking_another_users_notification_is_noop() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let other_user = app.create_test_user().await;
+    let others = seed_notifications(&repo, other_user.id, 1).await;
+
+    let updated = mark_read(&app, user.id, json!({ "ids": others })).await;
+    assert_eq!(updated, 0);
+
+    assert_eq!(unread_ids(&app, other_user.id).await, others);
+}
@@ -0,0 +1,112 @@
+//! Space ownership transfer tests
+//!
+//! Checks that `DocumentRepository::transfer_ownership` moves `owner_id`,
+//! demotes the old owner and promotes the new one together, that a
+//! non-member can't be made owner, and that only the owner may call
+//! `POST /spaces/{spaceId}/transfer-ownership`.
+//!
+//! Run with: cargo test --test lib documents::ownership_transfer_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{role_of, TestApp};
+use actix_web::{test, web, App};
+use document_service::handlers::transfer_space_ownership;
+use document_service::repository::{DocumentRepository, TransferOwnershipError, PREVIOUS_OWNER_ROLE};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_transfer_swaps_owner_and_roles() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+    repo.add_space_member(&space_id, &member.id.to_string(), "editor", owner.id)
+        .await
+        .expect("Failed to add member");
+
+    repo.transfer_ownership(&space_id, owner.id, &member.id.to_string())
+        .await
+        .expect("Transfer should succeed");
+
+    assert!(repo.is_space_owner(&space_id, member.id).await.unwrap());
+    assert!(!repo.is_space_owner(&space_id, owner.id).await.unwrap());
+    assert_eq!(role_of(&repo, &space_id, &member.id).await.as_deref(), Some("owner"));
+    assert_eq!(role_of(&repo, &space_id, &owner.id).await.as_deref(), Some(PREVIOUS_OWNER_ROLE));
+
+    // Both role changes are audited
+    let (entries, _) = repo.list_space_audit(&space_id, 10, 0).await.unwrap();
+    assert!(entries
+        .iter()
+        .any(|e| e.target_user_id == Some(member.id) && e.new_role.as_deref() == Some("owner")));
+    assert!(entries
+        .iter()
+        .any(|e| e.target_user_id == Some(owner.id) && e.new_role.as_deref() == Some(PREVIOUS_OWNER_ROLE)));
+
+    // The old owner can't transfer it back
+    let err = repo
+        .transfer_ownership(&space_id, owner.id, &member.id.to_string())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TransferOwnershipError::NotOwner));
+}
+
+#[tokio::test]
+async fn test_transfer_to_non_member_changes_nothing() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let outsider = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+
+    let err = repo
+        .transfer_ownership(&space_id, owner.id, &outsider.id.to_string())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, TransferOwnershipError::NotMember));
+    assert!(repo.is_space_owner(&space_id, owner.id).await.unwrap());
+    assert_eq!(role_of(&repo, &space_id, &owner.id).await.as_deref(), Some("owner"));
+    assert_eq!(role_of(&repo, &space_id, &outsider.id).await, None);
+}
+
+#[tokio::test]
+async fn test_transfer_endpoint_is_owner_only() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    repo.add_space_member(&space.id.to_string(), &member.id.to_string(), "editor", owner.id)
+        .await
+        .expect("Failed to add member");
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo))
+            .route("/spaces/{spaceId}/transfer-ownership", web::post().to(transfer_space_ownership)),
+    )
+    .await;
+    let uri = format!("/spaces/{}/transfer-ownership", space.id);
+
+    // A member can't take the space for themselves
+    let req = test::TestRequest::post()
+        .uri(&uri)
+        .insert_header(("X-User-Id", member.id.to_string()))
+        .set_json(json!({ "new_owner_id": owner.id.to_string() }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 403);
+
+    let req = test::TestRequest::post()
+        .uri(&uri)
+        .insert_header(("X-User-Id", owner.id.to_string()))
+        .set_json(json!({ "new_owner_id": member.id.to_string() }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["owner_id"], member.id.to_string());
+}
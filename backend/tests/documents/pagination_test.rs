@@ -0,0 +1,84 @@
+//! List pagination metadata tests
+//!
+//! Checks that document and version listings report `has_more` and
+//! `next_offset` for every page but the last, and `false`/`null` on the
+//! last one.
+//!
+//! Run with: cargo test --test lib documents::pagination_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::handlers::{list_documents, list_versions};
+use document_service::repository::DocumentRepository;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// GET `uri` as `user_id`, returning the response data
+async fn get_page(app: &TestApp, user_id: Uuid, uri: &str) -> Value {
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/space-docs/{spaceId}/documents", web::get().to(list_documents))
+            .route("/documents/{documentId}/versions", web::get().to(list_versions)),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header(("X-User-Id", user_id.to_string()))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    body["data"].clone()
+}
+
+#[actix_rt::test]
+async fn test_document_list_has_more_until_last_page() {
+    let app = TestApp::create().await;
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    app.create_test_documents(&space.id, 3).await;
+    let uri = format!("/space-docs/{}/documents?limit=2", space.id);
+
+    let first = get_page(&app, owner.id, &uri).await;
+    assert_eq!(first["documents"].as_array().unwrap().len(), 2);
+    assert_eq!(first["has_more"], true);
+    assert_eq!(first["next_offset"], 2);
+
+    let last = get_page(&app, owner.id, &format!("{}&offset=2", uri)).await;
+    assert_eq!(last["documents"].as_array().unwrap().len(), 1);
+    assert_eq!(last["has_more"], false);
+    assert!(last["next_offset"].is_null());
+
+    // A page that ends exactly at the total is the last one too
+    let exact = get_page(&app, owner.id, &format!("/space-docs/{}/documents?limit=3", space.id)).await;
+    assert_eq!(exact["has_more"], false);
+    assert!(exact["next_offset"].is_null());
+}
+
+#[actix_rt::test]
+async fn test_version_list_has_more_until_last_page() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = app.create_test_document(&space.id, None).await;
+    for n in 1..=3 {
+        repo.create_version(&document.id.to_string(), json!({ "text": n }), "Notes", owner.id, None)
+            .await
+            .expect("Failed to create version");
+    }
+    let uri = format!("/documents/{}/versions?limit=2", document.id);
+
+    let first = get_page(&app, owner.id, &uri).await;
+    assert_eq!(first["has_more"], true);
+    assert_eq!(first["next_offset"], 2);
+
+    let last = get_page(&app, owner.id, &format!("{}&offset=2", uri)).await;
+    assert_eq!(last["versions"].as_array().unwrap().len(), 1);
+    assert_eq!(last["has_more"], false);
+    assert!(last["next_offset"].is_null());
+}
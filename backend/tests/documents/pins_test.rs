@@ -0,0 +1,101 @@
+//! Document pinning tests
+//!
+//! Checks that pinned documents are listed before unpinned ones in their
+//! space, ordered by `pin_order`, and that unpinning restores the normal
+//! newest-first order.
+//!
+//! Run with: cargo test --test lib documents::pins_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{create_document, TestApp};
+use chrono::{Duration, Utc};
+use document_service::repository::DocumentRepository;
+use uuid::Uuid;
+
+/// Create `count` root documents, the first one oldest, returning their ids
+async fn create_documents(
+    app: &TestApp,
+    repo: &DocumentRepository,
+    space_id: &Uuid,
+    user_id: &Uuid,
+    count: i64,
+) -> Vec<Uuid> {
+    let mut ids = Vec::new();
+    for i in 0..count {
+        let id = create_document(repo, space_id, user_id, &format!("Document {}", i)).await;
+
+        // Spread creation times so the default order is deterministic
+        sqlx::query("UPDATE documents SET created_at = $2 WHERE id = $1")
+            .bind(id)
+            .bind((Utc::now() - Duration::minutes(count - i)).naive_utc())
+            .execute(&app.pool)
+            .await
+            .expect("Failed to set created_at");
+        ids.push(id);
+    }
+    ids
+}
+
+async fn listed_ids(repo: &DocumentRepository, space_id: &Uuid) -> Vec<Uuid> {
+    let (documents, _) = repo
+        .list_in_space(&space_id.to_string(), None, 50, 0, false)
+        .await
+        .expect("Failed to list documents");
+    documents.into_iter().map(|d| d.id).collect()
+}
+
+#[tokio::test]
+async fn test_pinned_document_listed_first() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let ids = create_documents(&app, &repo, &space.id, &user.id, 3).await;
+    assert_eq!(listed_ids(&repo, &space.id).await, vec![ids[2], ids[1], ids[0]]);
+
+    // The oldest document jumps ahead of newer ones once pinned
+    let pinned = repo
+        .pin(&ids[0].to_string(), None)
+        .await
+        .unwrap()
+        .expect("Document should exist");
+    assert!(pinned.is_pinned);
+    assert_eq!(listed_ids(&repo, &space.id).await, vec![ids[0], ids[2], ids[1]]);
+}
+
+#[tokio::test]
+async fn test_unpin_restores_normal_order() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let ids = create_documents(&app, &repo, &space.id, &user.id, 3).await;
+    repo.pin(&ids[0].to_string(), None).await.unwrap();
+
+    let unpinned = repo.unpin(&ids[0].to_string()).await.unwrap().expect("Document should exist");
+    assert!(!unpinned.is_pinned);
+    assert!(unpinned.pin_order.is_none());
+    assert_eq!(listed_ids(&repo, &space.id).await, vec![ids[2], ids[1], ids[0]]);
+}
+
+#[tokio::test]
+async fn test_pins_respect_pin_order() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let ids = create_documents(&app, &repo, &space.id, &user.id, 4).await;
+
+    // Pins without an explicit order are appended after existing pins
+    let first = repo.pin(&ids[1].to_string(), None).await.unwrap().unwrap();
+    let second = repo.pin(&ids[3].to_string(), None).await.unwrap().unwrap();
+    assert!(second.pin_order > first.pin_order);
+    assert_eq!(listed_ids(&repo, &space.id).await, vec![ids[1], ids[3], ids[2], ids[0]]);
+
+    // An explicit order can move a pin ahead of the others
+    repo.pin(&ids[0].to_string(), Some(-1)).await.unwrap();
+    assert_eq!(listed_ids(&repo, &space.id).await, vec![ids[0], ids[1], ids[3], ids[2]]);
+}
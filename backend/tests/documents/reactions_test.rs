@@ -0,0 +1,86 @@
+//! Comment reaction tests
+//!
+//! Checks that adding and removing reactions is idempotent and that
+//! `list_comments` aggregates them per emoji for the caller.
+//!
+//! Run with: cargo test --test lib documents::reactions_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{TestApp, TestUser};
+use actix_web::{test, web, App};
+use document_service::comments::list_comments;
+use document_service::repository::{CommentRow, DocumentRepository};
+use serde_json::{json, Value};
+
+/// Create a space with an editor besides the owner, and a comment by the owner
+async fn commented_document(app: &TestApp, repo: &DocumentRepository) -> (TestUser, TestUser, CommentRow) {
+    let owned = app.create_owned_document(repo, "Reactions", None).await;
+    let member = app.create_space_member(&owned.space.id, "editor").await;
+    let comment = repo
+        .create_comment(
+            &owned.document.id.to_string(),
+            owned.owner.id,
+            "Owner",
+            "Ship it?",
+            None,
+            &[],
+        )
+        .await
+        .expect("Failed to create comment");
+
+    (owned.owner, member, comment)
+}
+
+#[tokio::test]
+async fn test_add_and_remove_reaction_are_idempotent() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let (_, member, comment) = commented_document(&app, &repo).await;
+    let comment_id = comment.id.to_string();
+    let user_id = member.id;
+
+    assert!(repo.add_reaction(&comment_id, user_id, "👍").await.unwrap());
+    assert!(!repo.add_reaction(&comment_id, user_id, "👍").await.unwrap(), "Second add is a no-op");
+
+    let rows = repo.list_comment_reactions(&[comment.id], user_id).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].count, 1);
+
+    assert!(repo.remove_reaction(&comment_id, user_id, "👍").await.unwrap());
+    assert!(!repo.remove_reaction(&comment_id, user_id, "👍").await.unwrap(), "Second remove is a no-op");
+    assert!(repo.list_comment_reactions(&[comment.id], user_id).await.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn test_list_comments_aggregates_reactions() {
+    let test_app = TestApp::create().await;
+    let repo = DocumentRepository::new(test_app.pool.clone());
+    let (owner, member, comment) = commented_document(&test_app, &repo).await;
+    let comment_id = comment.id.to_string();
+
+    repo.add_reaction(&comment_id, owner.id, "👍").await.unwrap();
+    repo.add_reaction(&comment_id, member.id, "👍").await.unwrap();
+    repo.add_reaction(&comment_id, owner.id, "🎉").await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(test_app.pool.clone())))
+            .route("/documents/{documentId}/comments", web::get().to(list_comments)),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/documents/{}/comments", comment.document_id))
+        .insert_header(("X-User-Id", member.id.to_string()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body["data"]["comments"][0]["reactions"],
+        json!([
+            { "emoji": "👍", "count": 2, "reacted_by_me": true },
+            { "emoji": "🎉", "count": 1, "reacted_by_me": false },
+        ])
+    );
+}
@@ -0,0 +1,86 @@
+//! Space role tests
+//!
+//! Checks that the built-in roles seeded into `space_roles` match
+//! `rbac::roles::builtin_roles`, and that members with a custom role get
+//! exactly that role's permissions.
+//!
+//! Run with: cargo test --test lib documents::roles_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use auth_service::permissions::Permission;
+use auth_service::rbac::roles::{builtin_roles, has_permission, PermissionSet, Role};
+use document_service::repository::DocumentRepository;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_seeded_builtin_roles_match_code() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    for role in builtin_roles() {
+        let seeded = repo
+            .find_space_role(&space.id.to_string(), &role.name)
+            .await
+            .unwrap()
+            .expect("Built-in role should be seeded");
+        assert_eq!(seeded, role);
+    }
+}
+
+#[tokio::test]
+async fn test_member_with_custom_role() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+
+    let reviewer = Role::new(
+        "reviewer",
+        PermissionSet::from_permissions(&[
+            Permission::ViewDocuments,
+            Permission::Comment,
+            Permission::EditDocuments,
+        ]),
+    );
+    repo.create_space_role(&space_id, &reviewer)
+        .await
+        .expect("Failed to create role");
+    repo.add_space_member(&space_id, &member.id.to_string(), "reviewer", owner.id)
+        .await
+        .expect("Failed to add member");
+
+    let role = repo
+        .get_member_role(&space_id, member.id)
+        .await
+        .unwrap()
+        .expect("Member should have a role");
+    assert_eq!(role, reviewer);
+    assert!(has_permission(&role, Permission::EditDocuments));
+    assert!(!has_permission(&role, Permission::CreateDocuments));
+
+    // The owner still resolves to the built-in role
+    let role = repo
+        .get_member_role(&space_id, owner.id)
+        .await
+        .unwrap()
+        .expect("Owner should have a role");
+    assert_eq!(Some(role), Role::builtin("owner"));
+
+    // Custom roles are scoped to the space that defined them
+    let other_space = app.create_test_space_for_user(&owner.id).await;
+    assert!(repo
+        .find_space_role(&other_space.id.to_string(), "reviewer")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(repo
+        .get_member_role(&space_id, Uuid::new_v4())
+        .await
+        .unwrap()
+        .is_none());
+}
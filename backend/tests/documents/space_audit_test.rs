@@ -0,0 +1,132 @@
+//! Space audit log tests
+//!
+//! Checks that adding a member, changing their role and removing them each
+//! write one audit entry with the actor, target and roles, that a no-op
+//! re-add writes nothing, and that only the space owner can read the log.
+//!
+//! Run with: cargo test --test lib documents::space_audit_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::audit::list_space_audit;
+use document_service::repository::DocumentRepository;
+use serde_json::Value;
+
+#[tokio::test]
+async fn test_role_update_writes_audit_entry() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+
+    repo.add_space_member(&space_id, &member.id.to_string(), "viewer", owner.id)
+        .await
+        .expect("Failed to add member");
+    repo.update_space_member(&space_id, member.id, "editor", owner.id)
+        .await
+        .expect("Failed to update member")
+        .expect("Member should exist");
+
+    let (entries, total) = repo.list_space_audit(&space_id, 10, 0).await.unwrap();
+    assert_eq!(total, 2);
+    let update = &entries[0];
+    assert_eq!(update.action, "role_changed");
+    assert_eq!(update.actor_id, Some(owner.id));
+    assert_eq!(update.target_user_id, Some(member.id));
+    assert_eq!(update.old_role.as_deref(), Some("viewer"));
+    assert_eq!(update.new_role.as_deref(), Some("editor"));
+
+    let add = &entries[1];
+    assert_eq!(add.action, "member_added");
+    assert_eq!(add.old_role, None);
+    assert_eq!(add.new_role.as_deref(), Some("viewer"));
+
+    // Setting the same role again is not a change
+    repo.update_space_member(&space_id, member.id, "editor", owner.id)
+        .await
+        .unwrap();
+    repo.add_space_member(&space_id, &member.id.to_string(), "editor", owner.id)
+        .await
+        .unwrap();
+    assert_eq!(repo.list_space_audit(&space_id, 10, 0).await.unwrap().1, 2);
+}
+
+#[tokio::test]
+async fn test_removal_writes_audit_entry() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let member = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+
+    repo.add_space_member(&space_id, &member.id.to_string(), "editor", owner.id)
+        .await
+        .expect("Failed to add member");
+    // Members may remove themselves
+    assert!(repo.remove_space_member(&space_id, member.id, member.id).await.unwrap());
+
+    let (entries, _) = repo.list_space_audit(&space_id, 10, 0).await.unwrap();
+    let removal = &entries[0];
+    assert_eq!(removal.action, "member_removed");
+    assert_eq!(removal.actor_id, Some(member.id));
+    assert_eq!(removal.target_user_id, Some(member.id));
+    assert_eq!(removal.old_role.as_deref(), Some("editor"));
+    assert_eq!(removal.new_role, None);
+
+    // Nothing to remove, nothing recorded
+    assert!(!repo.remove_space_member(&space_id, member.id, member.id).await.unwrap());
+    assert_eq!(repo.list_space_audit(&space_id, 10, 0).await.unwrap().1, 2);
+}
+
+#[tokio::test]
+async fn test_audit_endpoint_is_owner_only_and_paged() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let space_id = space.id.to_string();
+    let mut members = Vec::new();
+    for _ in 0..3 {
+        let member = app.create_test_user().await;
+        repo.add_space_member(&space_id, &member.id.to_string(), "viewer", owner.id)
+            .await
+            .expect("Failed to add member");
+        members.push(member);
+    }
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo))
+            .route("/spaces/{spaceId}/audit", web::get().to(list_space_audit)),
+    )
+    .await;
+    let get = |user_id: String, uri: String| {
+        test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("X-User-Id", user_id))
+            .to_request()
+    };
+
+    let resp = test::call_service(
+        &service,
+        get(owner.id.to_string(), format!("/spaces/{}/audit?limit=2", space_id)),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["data"]["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"]["total"], 3);
+    assert_eq!(body["data"]["has_more"], true);
+    assert_eq!(body["data"]["entries"][0]["action"], "member_added");
+
+    let resp = test::call_service(
+        &service,
+        get(members[0].id.to_string(), format!("/spaces/{}/audit", space_id)),
+    )
+    .await;
+    assert_eq!(resp.status(), 403);
+}
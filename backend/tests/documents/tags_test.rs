@@ -0,0 +1,79 @@
+//! Document tag tests
+//!
+//! Checks that tags are normalized before storage and that the per-document
+//! tag count and tag length caps are enforced when adding tags.
+//!
+//! Run with: cargo test --test lib documents::tags_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::DocumentRepository;
+use document_service::tags::{TagError, TagLimits};
+use uuid::Uuid;
+
+const LIMITS: TagLimits = TagLimits {
+    max_length: 10,
+    max_count: 3,
+};
+
+async fn setup(app: &TestApp) -> (DocumentRepository, String, Uuid) {
+    let repo = DocumentRepository::new(app.pool.clone()).with_tag_limits(LIMITS);
+    let owned = app.create_owned_document(&repo, "Tagged", None).await;
+    (repo, owned.document.id.to_string(), owned.owner.id)
+}
+
+fn tags(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_add_tags_within_limits() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    let stored = repo
+        .add_tags(&document_id, &tags(&["review", "design"]), user_id)
+        .await
+        .expect("Tags within limits should be added");
+    assert_eq!(stored, tags(&["design", "review"]));
+
+    // Re-adding an existing tag does not use up a slot
+    let stored = repo
+        .add_tags(&document_id, &tags(&["review", "q3"]), user_id)
+        .await
+        .expect("Third tag fits the cap");
+    assert_eq!(stored, tags(&["design", "q3", "review"]));
+}
+
+#[tokio::test]
+async fn test_exceeding_count_or_length_is_rejected() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    repo.add_tags(&document_id, &tags(&["a", "b"]), user_id).await.unwrap();
+
+    let result = repo.add_tags(&document_id, &tags(&["c", "d"]), user_id).await;
+    assert!(matches!(result, Err(TagError::LimitReached { limit: 3 })));
+
+    let result = repo.add_tags(&document_id, &tags(&["much-too-long"]), user_id).await;
+    assert!(matches!(result, Err(TagError::TooLong { max_length: 10, .. })));
+
+    // Rejected adds store nothing
+    assert_eq!(repo.list_tags(&document_id).await.unwrap(), tags(&["a", "b"]));
+}
+
+#[tokio::test]
+async fn test_tags_differing_in_case_normalize_to_one() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    repo.add_tags(&document_id, &tags(&["Draft"]), user_id).await.unwrap();
+    let stored = repo
+        .add_tags(&document_id, &tags(&[" draft ", "DRAFT"]), user_id)
+        .await
+        .unwrap();
+    assert_eq!(stored, tags(&["draft"]));
+
+    assert!(repo.remove_tag(&document_id, "draft").await.unwrap());
+    assert!(repo.list_tags(&document_id).await.unwrap().is_empty());
+}
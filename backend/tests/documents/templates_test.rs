@@ -0,0 +1,122 @@
+//! Document template tests
+//!
+//! Checks that instantiating a template creates a new document with the
+//! template's title and content under a fresh id, and that templates are
+//! listed per space.
+//!
+//! Run with: cargo test --test lib documents::templates_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::repository::DocumentRepository;
+use document_service::templates::{create_template, instantiate_template, list_templates};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_instantiate_copies_template_content() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let content = json!({ "type": "doc", "text": "## Agenda\n\n## Action items" });
+
+    let template = repo
+        .create_template(&space.id.to_string(), "Meeting notes", content.clone(), user.id)
+        .await
+        .expect("Failed to create template");
+
+    let first = repo
+        .instantiate_template(&template.id.to_string(), None, user.id)
+        .await
+        .unwrap()
+        .expect("Template should exist");
+    let second = repo
+        .instantiate_template(&template.id.to_string(), None, user.id)
+        .await
+        .unwrap()
+        .expect("Template should exist");
+
+    assert_ne!(first.id, template.id);
+    assert_ne!(first.id, second.id);
+    assert_eq!(first.space_id, space.id);
+    assert_eq!(first.title, "Meeting notes");
+    assert_eq!(first.content.0, content);
+    assert_eq!(first.created_by, user.id);
+    assert_eq!(second.content.0, content);
+}
+
+#[tokio::test]
+async fn test_instantiate_missing_template() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+
+    let document = repo
+        .instantiate_template(&Uuid::new_v4().to_string(), None, user.id)
+        .await
+        .unwrap();
+
+    assert!(document.is_none());
+}
+
+#[actix_rt::test]
+async fn test_template_endpoints() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let parent = repo
+        .create(&space.id.to_string(), None, "Meetings", None, None, user.id)
+        .await
+        .expect("Failed to create parent");
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/spaces/{spaceId}/templates", web::post().to(create_template))
+            .route("/spaces/{spaceId}/templates", web::get().to(list_templates))
+            .route("/templates/{templateId}/instantiate", web::post().to(instantiate_template)),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/spaces/{}/templates", space.id))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .set_json(json!({ "title": "Spec", "content": { "text": "## Goals" } }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 201);
+    let body: Value = test::read_body_json(resp).await;
+    let template_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/spaces/{}/templates", space.id))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .to_request();
+    let body: Value = test::read_body_json(test::call_service(&service, req).await).await;
+    assert_eq!(body["data"]["templates"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"]["templates"][0]["title"], "Spec");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/templates/{}/instantiate", template_id))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .set_json(json!({ "parent_id": parent.id.to_string() }))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 201);
+    let body: Value = test::read_body_json(resp).await;
+    assert_ne!(body["data"]["id"], template_id);
+    assert_eq!(body["data"]["document"]["title"], "Spec");
+    assert_eq!(body["data"]["document"]["content"], json!({ "text": "## Goals" }));
+    assert_eq!(body["data"]["document"]["parent_id"], parent.id.to_string());
+
+    // Outsiders can neither list nor use the space's templates
+    let outsider = app.create_test_user().await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/templates/{}/instantiate", template_id))
+        .insert_header(("X-User-Id", outsider.id.to_string()))
+        .to_request();
+    assert_eq!(test::call_service(&service, req).await.status(), 403);
+}
@@ -0,0 +1,63 @@
+//! Document text metrics tests
+//!
+//! Checks that `GET /documents/{id}/metrics` counts words from the document
+//! text, derives reading time from the configured reading speed, and reports
+//! zeros for an empty document.
+//!
+//! Run with: cargo test --test lib documents::text_metrics_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use actix_web::{test, web, App};
+use document_service::repository::DocumentRepository;
+use document_service::text_metrics::{get_document_metrics, ReadingSpeed};
+use serde_json::Value;
+
+/// Create a document with `content` and fetch its metrics at `wpm`
+async fn metrics_for(app: &TestApp, content: Option<Value>, wpm: u32) -> Value {
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owned = app.create_owned_document(&repo, "Essay", content).await;
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(repo))
+            .app_data(web::Data::new(ReadingSpeed { words_per_minute: wpm }))
+            .route("/documents/{documentId}/metrics", web::get().to(get_document_metrics)),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/documents/{}/metrics", owned.document.id))
+        .insert_header(("X-User-Id", owned.owner.id.to_string()))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    body["data"].clone()
+}
+
+#[actix_rt::test]
+async fn test_metrics_for_known_text() {
+    let app = TestApp::create().await;
+    let text = vec!["lorem"; 300].join(" ");
+
+    let metrics = metrics_for(&app, Some(serde_json::json!({ "text": text })), 100).await;
+
+    assert_eq!(metrics["word_count"], 300);
+    assert_eq!(metrics["character_count"], 1500);
+    assert_eq!(metrics["reading_time_minutes"], 3);
+    assert_eq!(metrics["reading_time_seconds"], 180);
+    assert_eq!(metrics["words_per_minute"], 100);
+}
+
+#[actix_rt::test]
+async fn test_metrics_for_empty_document() {
+    let app = TestApp::create().await;
+
+    let metrics = metrics_for(&app, Some(serde_json::json!({})), 200).await;
+
+    assert_eq!(metrics["word_count"], 0);
+    assert_eq!(metrics["character_count"], 0);
+    assert_eq!(metrics["reading_time_minutes"], 0);
+    assert_eq!(metrics["reading_time_seconds"], 0);
+}
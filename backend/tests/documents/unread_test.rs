@@ -0,0 +1,123 @@
+//! Document unread indicator tests
+//!
+//! Checks that documents report `unread` when someone else changed them
+//! after the caller's last view (or the caller never viewed them), and that
+//! viewing a document clears the flag.
+//!
+//! Run with: cargo test --test lib documents::unread_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{test_jwt_service, TestApp};
+use actix_web::{http::header, test, web, App};
+use document_service::handlers::{get_document, list_documents};
+use document_service::repository::DocumentRepository;
+use serde_json::Value;
+use uuid::Uuid;
+
+struct Fixture {
+    owner: Uuid,
+    member: Uuid,
+    space_id: Uuid,
+    document_id: Uuid,
+}
+
+/// A space with a document written by its owner and a second member
+async fn setup(app: &TestApp, repo: &DocumentRepository) -> Fixture {
+    let owned = app.create_owned_document(repo, "Roadmap", None).await;
+    let member = app.create_space_member(&owned.space.id, "editor").await;
+
+    Fixture {
+        owner: owned.owner.id,
+        member: member.id,
+        space_id: owned.space.id,
+        document_id: owned.document.id,
+    }
+}
+
+/// GET `uri` as `user_id`, returning the response data
+async fn get_as(app: &TestApp, user_id: Uuid, uri: &str) -> Value {
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(DocumentRepository::new(app.pool.clone())))
+            .route("/documents/{documentId}", web::get().to(get_document))
+            .route("/space-docs/{spaceId}/documents", web::get().to(list_documents)),
+    )
+    .await;
+
+    let token = test_jwt_service()
+        .generate_access_token(&user_id.to_string(), "reader@example.com", "user")
+        .expect("Failed to generate access token");
+    let req = test::TestRequest::get()
+        .uri(uri)
+        .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = test::read_body_json(resp).await;
+    body["data"].clone()
+}
+
+async fn listed_unread(app: &TestApp, fixture: &Fixture, user_id: Uuid) -> bool {
+    let data = get_as(app, user_id, &format!("/space-docs/{}/documents", fixture.space_id)).await;
+    let document = data["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == fixture.document_id.to_string())
+        .expect("Document should be listed")
+        .clone();
+    document["unread"].as_bool().unwrap()
+}
+
+#[actix_rt::test]
+async fn test_never_viewed_document_is_unread() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let fixture = setup(&app, &repo).await;
+
+    assert!(listed_unread(&app, &fixture, fixture.member).await);
+
+    // The author's own edits never count as unread
+    assert!(!listed_unread(&app, &fixture, fixture.owner).await);
+}
+
+#[actix_rt::test]
+async fn test_viewing_clears_unread() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let fixture = setup(&app, &repo).await;
+
+    // The response reports the state before this view
+    let data = get_as(&app, fixture.member, &format!("/documents/{}", fixture.document_id)).await;
+    assert_eq!(data["unread"], true);
+
+    assert!(!listed_unread(&app, &fixture, fixture.member).await);
+    let data = get_as(&app, fixture.member, &format!("/documents/{}", fixture.document_id)).await;
+    assert_eq!(data["unread"], false);
+}
+
+#[actix_rt::test]
+async fn test_update_after_last_view_is_unread() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let fixture = setup(&app, &repo).await;
+
+    get_as(&app, fixture.member, &format!("/documents/{}", fixture.document_id)).await;
+    assert!(!listed_unread(&app, &fixture, fixture.member).await);
+
+    repo.update(
+        &fixture.document_id.to_string(),
+        Some("Roadmap v2"),
+        None,
+        None,
+        fixture.owner,
+    )
+    .await
+    .expect("Failed to update document");
+
+    assert!(listed_unread(&app, &fixture, fixture.member).await);
+    let data = get_as(&app, fixture.member, &format!("/documents/{}", fixture.document_id)).await;
+    assert_eq!(data["unread"], true);
+    assert!(!listed_unread(&app, &fixture, fixture.member).await);
+}
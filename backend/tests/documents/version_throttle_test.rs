@@ -0,0 +1,160 @@
+//! Auto-save version throttle tests
+//!
+//! Checks that rapid auto-save versions by the same user coalesce into one
+//! version holding the latest content, while explicit saves always add a
+//! version, pinned versions are never overwritten and concurrent auto-saves
+//! still add only one version.
+//!
+//! Run with: cargo test --test lib documents::version_throttle_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::DocumentRepository;
+use serde_json::json;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// A repository with a window wide enough that test saves always fall inside it
+async fn setup(app: &TestApp) -> (DocumentRepository, String, Uuid) {
+    let repo = DocumentRepository::new(app.pool.clone()).with_version_throttle(Duration::from_secs(300));
+    let owned = app.create_owned_document(&repo, "Draft", None).await;
+    (repo, owned.document.id.to_string(), owned.owner.id)
+}
+
+#[tokio::test]
+async fn test_rapid_auto_saves_collapse_to_latest_content() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    let (first, coalesced) = repo
+        .create_auto_save_version(&document_id, json!({"text": "H"}), "Draft", user_id, None)
+        .await
+        .expect("First auto-save failed");
+    assert!(!coalesced);
+    assert!(first.is_auto_save);
+
+    for text in ["He", "Hel", "Hello"] {
+        let (version, coalesced) = repo
+            .create_auto_save_version(&document_id, json!({ "text": text }), "Draft", user_id, None)
+            .await
+            .expect("Auto-save failed");
+        assert!(coalesced);
+        assert_eq!(version.id, first.id);
+        assert_eq!(version.version_number, first.version_number);
+    }
+
+    // The initial version plus the single coalesced auto-save
+    let (versions, total) = repo.list_versions(&document_id, 10, 0).await.unwrap();
+    assert_eq!(total, 2);
+    assert_eq!(versions[0].id, first.id);
+    assert_eq!(versions[0].content.0, json!({"text": "Hello"}));
+}
+
+#[tokio::test]
+async fn test_explicit_save_always_creates_version() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    repo.create_auto_save_version(&document_id, json!({"text": "a"}), "Draft", user_id, None)
+        .await
+        .expect("Auto-save failed");
+    let explicit = repo
+        .create_version(&document_id, json!({"text": "ab"}), "Draft", user_id, Some("Checkpoint"))
+        .await
+        .expect("Explicit save failed");
+    let second_explicit = repo
+        .create_version(&document_id, json!({"text": "ab"}), "Draft", user_id, None)
+        .await
+        .expect("Explicit save failed");
+    assert!(!explicit.is_auto_save);
+    assert_eq!(second_explicit.version_number, explicit.version_number + 1);
+
+    // An explicit save breaks the run, so the next auto-save starts a new version
+    let (after, coalesced) = repo
+        .create_auto_save_version(&document_id, json!({"text": "abc"}), "Draft", user_id, None)
+        .await
+        .expect("Auto-save failed");
+    assert!(!coalesced);
+    assert_eq!(after.version_number, second_explicit.version_number + 1);
+
+    // Initial version, two auto-saves and two explicit saves
+    let (_, total) = repo.list_versions(&document_id, 10, 0).await.unwrap();
+    assert_eq!(total, 5);
+}
+
+#[tokio::test]
+async fn test_auto_saves_by_other_users_do_not_coalesce() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+    let other = app.create_test_user().await;
+
+    repo.create_auto_save_version(&document_id, json!({"text": "mine"}), "Draft", user_id, None)
+        .await
+        .expect("Auto-save failed");
+    let (_, coalesced) = repo
+        .create_auto_save_version(&document_id, json!({"text": "theirs"}), "Draft", other.id, None)
+        .await
+        .expect("Auto-save failed");
+    assert!(!coalesced);
+
+    // With the throttle disabled every auto-save is kept
+    let unthrottled = DocumentRepository::new(app.pool.clone()).with_version_throttle(Duration::ZERO);
+    let (_, coalesced) = unthrottled
+        .create_auto_save_version(&document_id, json!({"text": "theirs 2"}), "Draft", other.id, None)
+        .await
+        .expect("Auto-save failed");
+    assert!(!coalesced);
+
+    // Initial version plus three auto-saves
+    let (_, total) = repo.list_versions(&document_id, 10, 0).await.unwrap();
+    assert_eq!(total, 4);
+}
+
+#[tokio::test]
+async fn test_pinned_auto_save_is_not_overwritten() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    let (pinned, _) = repo
+        .create_auto_save_version(&document_id, json!({"text": "keep"}), "Draft", user_id, None)
+        .await
+        .expect("Auto-save failed");
+    sqlx::query("UPDATE document_versions SET is_pinned = true WHERE id = $1")
+        .bind(pinned.id)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to pin version");
+
+    let (after, coalesced) = repo
+        .create_auto_save_version(&document_id, json!({"text": "keep going"}), "Draft", user_id, None)
+        .await
+        .expect("Auto-save failed");
+    assert!(!coalesced);
+    assert_eq!(after.version_number, pinned.version_number + 1);
+
+    let (versions, _) = repo.list_versions(&document_id, 10, 0).await.unwrap();
+    let kept = versions.iter().find(|v| v.id == pinned.id).unwrap();
+    assert_eq!(kept.content.0, json!({"text": "keep"}));
+}
+
+#[tokio::test]
+async fn test_concurrent_auto_saves_add_one_version() {
+    let app = TestApp::create().await;
+    let (repo, document_id, user_id) = setup(&app).await;
+
+    let saves = (0..8).map(|i| {
+        repo.create_auto_save_version(&document_id, json!({ "text": i }), "Draft", user_id, None)
+    });
+    let results = futures_util::future::join_all(saves).await;
+
+    let created = results
+        .into_iter()
+        .map(|result| result.expect("Auto-save failed"))
+        .filter(|(_, coalesced)| !coalesced)
+        .count();
+    assert_eq!(created, 1);
+
+    // The initial version plus the single auto-save
+    let (_, total) = repo.list_versions(&document_id, 10, 0).await.unwrap();
+    assert_eq!(total, 2);
+}
@@ -230,6 +230,8 @@ mod file_service_test {
             is_deleted: false,
             deleted_at: None,
             created_at: chrono::Utc::now().naive_utc(),
+            version: 1,
+            previous_version_id: None,
         };
 
         let serialized = serde_json::to_string(&file).expect("Failed to serialize");
@@ -266,6 +268,8 @@ mod file_service_test {
                 is_deleted: false,
                 deleted_at: None,
                 created_at: chrono::Utc::now().naive_utc(),
+                version: 1,
+                previous_version_id: None,
             },
             uploaded_by: uploader_info,
         };
@@ -331,6 +335,8 @@ mod file_service_test {
             total: 1u64,
             limit: 100u64,
             offset: 0u64,
+            has_more: false,
+            next_offset: None,
         };
 
         let serialized = serde_json::to_string(&response).expect("Failed to serialize");
@@ -0,0 +1,62 @@
+//! File metadata uploader tests
+//!
+//! Checks that the metadata query joins the uploader's display name and
+//! avatar, and falls back to a placeholder when the user record is missing.
+//!
+//! Run with: cargo test --test lib files::metadata_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{new_text_file, TestApp};
+use file_service::models::{FileWithUploader, UNKNOWN_UPLOADER};
+use file_service::repository::{find_with_uploader, insert_file, NewFile};
+use uuid::Uuid;
+
+/// A file with a name of its own, so it starts a new version history
+fn new_file(space_id: Uuid, uploaded_by: Uuid) -> NewFile {
+    new_text_file(space_id, None, uploaded_by, &format!("{}.txt", Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn test_metadata_joins_uploader_display_fields() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    sqlx::query("UPDATE users SET avatar_url = $2 WHERE id = $1")
+        .bind(user.id)
+        .bind("https://example.com/uploader.png")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let file = insert_file(&app.pool, &new_file(space.id, user.id)).await.unwrap();
+
+    let row = find_with_uploader(&app.pool, file.id).await.unwrap().unwrap();
+    assert_eq!(row.file.id, file.id);
+
+    let uploader = row.uploader();
+    assert_eq!(uploader.id, user.id);
+    assert_eq!(uploader.display_name, user.display_name);
+    assert_eq!(uploader.avatar_url.as_deref(), Some("https://example.com/uploader.png"));
+
+    assert!(find_with_uploader(&app.pool, Uuid::new_v4()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_missing_uploader_uses_placeholder() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let file = insert_file(&app.pool, &new_file(space.id, user.id)).await.unwrap();
+
+    let row = FileWithUploader {
+        uploader_name: None,
+        uploader_avatar: None,
+        ..find_with_uploader(&app.pool, file.id).await.unwrap().unwrap()
+    };
+
+    let uploader = row.uploader();
+    assert_eq!(uploader.id, user.id);
+    assert_eq!(uploader.display_name, UNKNOWN_UPLOADER);
+    assert!(uploader.avatar_url.is_none());
+}
@@ -0,0 +1,4 @@
+pub mod versions_test;
+pub mod scanner_test;
+pub mod metadata_test;
+pub mod retention_test;
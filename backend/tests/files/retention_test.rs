@@ -0,0 +1,88 @@
+//! Deleted file retention tests
+//!
+//! Seeds soft-deleted files on either side of the retention cutoff and checks
+//! that the purge removes the expired row and its stored object while the
+//! recently deleted file is kept, and that the file handlers reach the same
+//! storage when the app is built the way main.rs builds it.
+//!
+//! Run with: cargo test --test lib files::retention_test
+//! Note: Requires a migrated database at DATABASE_URL and S3-compatible
+//! storage at S3_ENDPOINT (defaults to a local MinIO)
+
+use crate::helpers::{new_text_file, test_storage, TestApp};
+use actix_web::{test, web, App};
+use file_service::repository::{insert_file, NewFile};
+use file_service::retention::purge_deleted_files_older_than;
+use file_service::storage::S3Storage;
+use std::sync::Arc;
+use uuid::Uuid;
+
+async fn deleted_file(app: &TestApp, storage: &S3Storage, space_id: Uuid, uploaded_by: Uuid, days_ago: i64) -> NewFile {
+    let new_file = new_text_file(space_id, None, uploaded_by, &format!("deleted-{}.txt", days_ago));
+    storage.upload_file(&new_file.storage_path, b"deleted", "text/plain").await.unwrap();
+    insert_file(&app.pool, &new_file).await.unwrap();
+
+    sqlx::query("UPDATE files SET is_deleted = true, deleted_at = NOW() - make_interval(days => $2) WHERE id = $1")
+        .bind(new_file.id)
+        .bind(days_ago as i32)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    new_file
+}
+
+async fn row_exists(app: &TestApp, id: Uuid) -> bool {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM files WHERE id = $1)")
+        .bind(id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_purge_removes_only_files_past_retention() {
+    let app = TestApp::create().await;
+    let storage = test_storage().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let expired = deleted_file(&app, &storage, space.id, user.id, 40).await;
+    let recent = deleted_file(&app, &storage, space.id, user.id, 1).await;
+
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::days(30);
+    let purged = purge_deleted_files_older_than(&app.pool, &storage, cutoff).await.unwrap();
+
+    assert!(purged >= 1);
+    assert!(!row_exists(&app, expired.id).await);
+    assert!(!storage.file_exists(&expired.storage_path).await.unwrap());
+    assert!(row_exists(&app, recent.id).await);
+    assert!(storage.file_exists(&recent.storage_path).await.unwrap());
+
+    storage.delete_file(&recent.storage_path).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_file_handlers_reach_registered_storage() {
+    let test_app = TestApp::create().await;
+    let storage = Arc::new(test_storage().await);
+    let user = test_app.create_test_user().await;
+    let space = test_app.create_test_space_for_user(&user.id).await;
+    let deleted = deleted_file(&test_app, &storage, space.id, user.id, 1).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(test_app.pool.clone()))
+            .configure(miniwiki_backend::routes::storage_config(Some(storage.clone())))
+            .configure(miniwiki_backend::routes::config),
+    )
+    .await;
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/files/{}/permanent-delete", deleted.id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), 200);
+    assert!(!row_exists(&test_app, deleted.id).await);
+    assert!(!storage.file_exists(&deleted.storage_path).await.unwrap());
+}
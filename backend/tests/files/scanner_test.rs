@@ -0,0 +1,116 @@
+//! Upload malware scanning tests
+//!
+//! Uses a stub scanner that reports every file as infected, and checks that
+//! the upload is rejected with `MALWARE_DETECTED`, no file record is written
+//! and the stored object is removed again.
+//!
+//! Run with: cargo test --test lib files::scanner_test
+//! Note: Requires a migrated database at DATABASE_URL and S3-compatible
+//! storage at S3_ENDPOINT (defaults to a local MinIO)
+
+use crate::helpers::{test_storage, TestApp};
+use actix_web::{test, web, App};
+use async_trait::async_trait;
+use file_service::handlers::upload_file;
+use file_service::scanner::{scan_upload, FileScanner, ScanResult};
+use file_service::storage::S3Storage;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+struct InfectedScanner;
+
+#[async_trait]
+impl FileScanner for InfectedScanner {
+    async fn scan(&self, _bytes: &[u8]) -> ScanResult {
+        ScanResult::Infected("Eicar-Test-Signature".to_string())
+    }
+}
+
+fn infected_scanner() -> web::Data<Arc<dyn FileScanner>> {
+    web::Data::new(Arc::new(InfectedScanner) as Arc<dyn FileScanner>)
+}
+
+async fn storage() -> Arc<S3Storage> {
+    Arc::new(test_storage().await)
+}
+
+fn multipart_body(boundary: &str, space_id: &Uuid, file_name: &str, content: &str) -> String {
+    format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"space_id\"\r\n\r\n{space}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file_name\"\r\n\r\n{name}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{name}\"\r\n\
+         Content-Type: text/plain\r\n\r\n{content}\r\n--{b}--\r\n",
+        b = boundary,
+        space = space_id,
+        name = file_name,
+        content = content,
+    )
+}
+
+#[tokio::test]
+async fn test_infected_upload_is_deleted_from_storage() {
+    let storage = storage().await;
+    let path = format!("{}/{}/eicar.txt", Uuid::new_v4(), Uuid::new_v4());
+    storage.upload_file(&path, b"infected", "text/plain").await.unwrap();
+    assert!(storage.file_exists(&path).await.unwrap());
+
+    let scanner = infected_scanner();
+    let response = scan_upload(Some(&scanner), &storage, &path, b"infected")
+        .await
+        .expect_err("Infected upload should be rejected");
+
+    assert_eq!(response.status(), 422);
+    assert!(!storage.file_exists(&path).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_no_scanner_accepts_upload() {
+    let storage = storage().await;
+    let path = format!("{}/{}/notes.txt", Uuid::new_v4(), Uuid::new_v4());
+    storage.upload_file(&path, b"clean", "text/plain").await.unwrap();
+
+    assert!(scan_upload(None, &storage, &path, b"clean").await.is_ok());
+    assert!(storage.file_exists(&path).await.unwrap());
+
+    storage.delete_file(&path).await.unwrap();
+}
+
+#[actix_rt::test]
+async fn test_infected_upload_is_rejected() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let file_name = format!("eicar-{}.txt", Uuid::new_v4());
+
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app.pool.clone()))
+            .app_data(web::Data::new(storage().await))
+            .app_data(infected_scanner())
+            .route("/files/upload", web::post().to(upload_file)),
+    )
+    .await;
+
+    let boundary = "scannerTestBoundary";
+    let req = test::TestRequest::post()
+        .uri("/files/upload")
+        .insert_header(("Content-Type", format!("multipart/form-data; boundary={}", boundary)))
+        .insert_header(("X-User-Id", user.id.to_string()))
+        .set_payload(multipart_body(boundary, &space.id, &file_name, "X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR"))
+        .to_request();
+    let resp = test::call_service(&service, req).await;
+    assert_eq!(resp.status(), 422);
+
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "MALWARE_DETECTED");
+    assert_eq!(body["details"]["signature"], "Eicar-Test-Signature");
+
+    let records: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM files WHERE space_id = $1 AND file_name = $2")
+        .bind(space.id)
+        .bind(&file_name)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(records, 0);
+}
@@ -0,0 +1,136 @@
+//! File version history tests
+//!
+//! Checks that re-uploading a file name in the same space and document adds
+//! the next version linked to the previous one, that the history can be read
+//! from any live version, that downloads resolve to the latest version by
+//! default, and that concurrent re-uploads report a conflict instead of
+//! forking the history.
+//!
+//! Run with: cargo test --test lib files::versions_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{new_text_file, TestApp};
+use file_service::repository::{find_version, insert_file, list_versions, InsertFileError};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_reupload_increments_version() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let first = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "notes.txt")).await.unwrap();
+    let second = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "notes.txt")).await.unwrap();
+    let third = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "notes.txt")).await.unwrap();
+
+    assert_eq!(first.version, 1);
+    assert_eq!(first.previous_version_id, None);
+    assert_eq!(second.version, 2);
+    assert_eq!(second.previous_version_id, Some(first.id));
+    assert_eq!(third.version, 3);
+    assert_eq!(third.previous_version_id, Some(second.id));
+
+    // A different name starts its own history
+    let other = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "other.txt")).await.unwrap();
+    assert_eq!(other.version, 1);
+    assert_eq!(other.previous_version_id, None);
+}
+
+#[tokio::test]
+async fn test_same_name_in_another_space_is_not_a_version() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+    let other_space = app.create_test_space_for_user(&user.id).await;
+
+    insert_file(&app.pool, &new_text_file(space.id, None, user.id, "report.pdf")).await.unwrap();
+    let elsewhere = insert_file(&app.pool, &new_text_file(other_space.id, None, user.id, "report.pdf"))
+        .await
+        .unwrap();
+
+    assert_eq!(elsewhere.version, 1);
+    assert_eq!(elsewhere.previous_version_id, None);
+}
+
+#[tokio::test]
+async fn test_history_is_traversed_from_any_version() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let first = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "diagram.png")).await.unwrap();
+    let second = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "diagram.png")).await.unwrap();
+    let third = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "diagram.png")).await.unwrap();
+
+    for start in [first.id, second.id, third.id] {
+        let versions = list_versions(&app.pool, start).await.unwrap();
+        let ids: Vec<Uuid> = versions.iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![third.id, second.id, first.id]);
+    }
+
+    assert!(list_versions(&app.pool, Uuid::new_v4()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_find_version_defaults_to_latest() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let first = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "data.csv")).await.unwrap();
+    let second = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "data.csv")).await.unwrap();
+
+    let latest = find_version(&app.pool, first.id, None).await.unwrap().unwrap();
+    assert_eq!(latest.id, second.id);
+
+    let pinned = find_version(&app.pool, second.id, Some(1)).await.unwrap().unwrap();
+    assert_eq!(pinned.id, first.id);
+
+    assert!(find_version(&app.pool, first.id, Some(5)).await.unwrap().is_none());
+
+    // Deleted versions are skipped, but numbering continues past them
+    sqlx::query("UPDATE files SET is_deleted = true, deleted_at = NOW() WHERE id = $1")
+        .bind(second.id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // A deleted version's id no longer resolves to anything
+    assert!(find_version(&app.pool, second.id, None).await.unwrap().is_none());
+    assert!(list_versions(&app.pool, second.id).await.unwrap().is_empty());
+
+    let latest = find_version(&app.pool, first.id, None).await.unwrap().unwrap();
+    assert_eq!(latest.id, first.id);
+
+    let third = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "data.csv")).await.unwrap();
+    assert_eq!(third.version, 3);
+    assert_eq!(third.previous_version_id, Some(second.id));
+}
+
+#[tokio::test]
+async fn test_concurrent_reuploads_conflict_instead_of_forking() {
+    let app = TestApp::create().await;
+    let user = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&user.id).await;
+
+    let first = insert_file(&app.pool, &new_text_file(space.id, None, user.id, "race.txt")).await.unwrap();
+
+    let uploads: Vec<NewFile> = (0..4).map(|_| new_text_file(space.id, None, user.id, "race.txt")).collect();
+    let results = futures_util::future::join_all(uploads.iter().map(|f| insert_file(&app.pool, f))).await;
+
+    for result in &results {
+        assert!(
+            matches!(result, Ok(_) | Err(InsertFileError::ConcurrentVersion)),
+            "unexpected result: {:?}",
+            result
+        );
+    }
+
+    // Whatever won, the history is a single line
+    let versions = list_versions(&app.pool, first.id).await.unwrap();
+    let stored = results.iter().filter(|r| r.is_ok()).count();
+    assert_eq!(versions.len(), stored + 1);
+    for pair in versions.windows(2) {
+        assert_eq!(pair[0].previous_version_id, Some(pair[1].id));
+    }
+}
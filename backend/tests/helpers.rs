@@ -1,4 +1,7 @@
 use auth_service::jwt::{JwtConfig, JwtService};
+use document_service::repository::{DocumentRepository, DocumentRow};
+use file_service::repository::NewFile;
+use file_service::storage::{config_from_env_dev, S3Storage};
 use serde_json::json;
 use serde_json::Value;
 use sqlx::Error as SqlxError;
@@ -250,15 +253,16 @@ where
     unreachable!()
 }
 
+/// JWT service signing with the test secret
+pub fn test_jwt_service() -> JwtService {
+    JwtService::new(JwtConfig::new(TEST_JWT_SECRET.to_string(), 3600, 86400))
+}
+
 // JWT token helper function
 pub fn generate_test_jwt_token(user_id: Uuid, email: &str) -> String {
-    let config = JwtConfig {
-        secret: TEST_JWT_SECRET.to_string(),
-        access_expiry: 3600,
-        refresh_expiry: 86400,
-    };
-    let service = JwtService::new(config);
-    service.generate_access_token(&user_id.to_string(), email, "user").unwrap()
+    test_jwt_service()
+        .generate_access_token(&user_id.to_string(), email, "user")
+        .unwrap()
 }
 
 pub struct TestApp {
@@ -594,6 +598,30 @@ impl TestApp {
         }
     }
 
+    /// Create a user and add them to the space with `role`
+    pub async fn create_space_member(&self, space_id: &Uuid, role: &str) -> TestUser {
+        let member = self.create_test_user().await;
+        self.add_space_member(space_id, &member.id, role).await;
+        member
+    }
+
+    /// Create a user, a space they own and a document in it through `repo`
+    pub async fn create_owned_document(
+        &self,
+        repo: &DocumentRepository,
+        title: &str,
+        content: Option<Value>,
+    ) -> OwnedDocument {
+        let owner = self.create_test_user().await;
+        let space = self.create_test_space_for_user(&owner.id).await;
+        let document = repo
+            .create(&space.id.to_string(), None, title, None, content, owner.id)
+            .await
+            .expect("Failed to create document");
+
+        OwnedDocument { owner, space, document }
+    }
+
     pub async fn cleanup(&self) {
         sqlx::query(
             "DELETE FROM document_versions WHERE document_id IN (SELECT id FROM documents WHERE title LIKE 'Test%')",
@@ -684,6 +712,14 @@ pub struct TestDocument {
     pub title: String,
 }
 
+/// A user's space with one document in it, from `TestApp::create_owned_document`
+#[derive(Debug)]
+pub struct OwnedDocument {
+    pub owner: TestUser,
+    pub space: TestSpace,
+    pub document: DocumentRow,
+}
+
 /// Test document version for version-related tests
 #[derive(Debug)]
 pub struct TestVersion {
@@ -824,3 +860,46 @@ pub async fn cleanup_test_data(app: &TestApp) -> Result<(), SqlxError> {
         .await?;
     Ok(())
 }
+
+// ============================================================================
+// Service Fixtures
+// ============================================================================
+
+/// Create a document in the space through `repo`, returning its id
+pub async fn create_document(repo: &DocumentRepository, space_id: &Uuid, user_id: &Uuid, title: &str) -> Uuid {
+    repo.create(&space_id.to_string(), None, title, None, None, *user_id)
+        .await
+        .expect("Failed to create document")
+        .id
+}
+
+/// The user's role in the space, read through `repo`
+pub async fn role_of(repo: &DocumentRepository, space_id: &str, user_id: &Uuid) -> Option<String> {
+    repo.get_user_space_role(space_id, *user_id)
+        .await
+        .expect("Failed to read role")
+}
+
+/// A plain text file record ready for `insert_file`
+pub fn new_text_file(space_id: Uuid, document_id: Option<Uuid>, uploaded_by: Uuid, file_name: &str) -> NewFile {
+    let id = Uuid::new_v4();
+    NewFile {
+        id,
+        space_id,
+        document_id,
+        uploaded_by,
+        file_name: file_name.to_string(),
+        file_type: "text/plain".to_string(),
+        file_size: 12,
+        storage_path: format!("{}/{}/{}", space_id, id, file_name),
+        storage_bucket: "files".to_string(),
+        checksum: format!("{:x}", id.as_u128()),
+    }
+}
+
+/// Client for the S3-compatible test storage at S3_ENDPOINT
+pub async fn test_storage() -> S3Storage {
+    S3Storage::new(config_from_env_dev())
+        .await
+        .expect("Failed to connect to storage")
+}
@@ -5,6 +5,8 @@ pub mod macros;
 
 pub mod auth;
 pub mod documents;
+pub mod files;
+pub mod search;
 pub mod spaces;
 pub mod sync;
 
@@ -38,6 +38,15 @@ pub struct SpaceMembership {
     pub invited_by: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpaceMemberListResponse {
+    pub members: Vec<SpaceMembership>,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+    pub next_offset: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AddMemberRequest {
     pub user_id: String,
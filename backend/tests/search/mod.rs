@@ -0,0 +1 @@
+pub mod routes_test;
@@ -0,0 +1,75 @@
+//! Search routes as mounted by the server
+//!
+//! Builds the app through `routes::config`, registering the search
+//! repository the way main.rs does, so a route missing from `/api/v1` fails
+//! here.
+//!
+//! Run with: cargo test --test lib search::routes_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::{generate_test_jwt_token, TestApp};
+use actix_web::{test, web, App};
+use search_service::repository::SearchRepository;
+use serde_json::Value;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn test_search_is_mounted_under_api_v1() {
+    let test_app = TestApp::create().await;
+    let user = test_app.create_test_user().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(test_app.pool.clone()))
+            .app_data(web::Data::new(SearchRepository::new(Arc::new(test_app.pool.clone()))))
+            .configure(miniwiki_backend::routes::config),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/search?q=roadmap")
+        .insert_header(("Authorization", format!("Bearer {}", generate_test_jwt_token(user.id, &user.email))))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["success"], true);
+}
+
+#[actix_rt::test]
+async fn test_export_streams_every_matching_row() {
+    let test_app = TestApp::create().await;
+    let user = test_app.create_test_user().await;
+    let space = test_app.create_test_space_for_user(&user.id).await;
+    for i in 0..3 {
+        test_app.create_test_document_with_title(&space.id, None, &format!("Roadmap {}", i)).await;
+    }
+    test_app.create_test_document_with_title(&space.id, None, "Meeting notes").await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(test_app.pool.clone()))
+            .app_data(web::Data::new(SearchRepository::new(Arc::new(test_app.pool.clone()))))
+            .configure(miniwiki_backend::routes::config),
+    )
+    .await;
+    let export = |limit: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/search/export?format=csv&q=Roadmap&space_id={}{}", space.id, limit))
+            .insert_header(("Authorization", format!("Bearer {}", generate_test_jwt_token(user.id, &user.email))))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, export("")).await;
+    assert_eq!(resp.status(), 200);
+    let csv = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], search_service::export::CSV_HEADER);
+    assert_eq!(lines.len(), 4, "Header plus one row per matching document");
+    assert!(lines[1..].iter().all(|line| line.contains("Roadmap")));
+
+    // The limit caps the rows read from the query
+    let resp = test::call_service(&app, export("&limit=2")).await;
+    let csv = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert_eq!(csv.lines().count(), 3);
+}
@@ -462,4 +462,410 @@ mod search_tests {
             first_score, second_score
         );
     }
+
+    #[tokio::test]
+    async fn test_search_export_returns_csv_with_header_and_rows() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, space_id, _doc1_id) = create_test_data(&pool).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri(&format!("/search/export?format=csv&q=Rust&space_id={}", space_id))
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert!(resp.headers().get("content-type").unwrap().to_str().unwrap().starts_with("text/csv"));
+
+        let body = test::read_body(resp).await;
+        let csv = String::from_utf8(body.to_vec()).expect("CSV should be UTF-8");
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], search_service::export::CSV_HEADER);
+        assert_eq!(lines.len(), 3, "Header plus one row per matching document");
+        assert!(lines[1..].iter().all(|line| line.contains(&space_id.to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_search_export_rejects_unsupported_format() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, _space_id, _doc1_id) = create_test_data(&pool).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search/export?format=xlsx&q=Rust")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_search_empty_query_rejected_by_default() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, _space_id, _doc1_id) = create_test_data(&pool).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(search_service::models::EmptyQueryBehavior::Reject))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_search_empty_query_lists_recent_documents_when_enabled() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, space_id, _doc1_id) = create_test_data(&pool).await;
+        let (_other_user, other_space, _other_doc) = create_test_data(&pool).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(search_service::models::EmptyQueryBehavior::Recent))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        let results = json["data"]["results"].as_array().expect("Results should be array");
+
+        assert_eq!(results.len(), 2, "Both documents in the user's space are listed");
+        assert!(results.iter().all(|r| r["space_id"] == space_id.to_string()));
+        assert!(results.iter().all(|r| r["space_id"] != other_space.to_string()));
+        // Most recently created document comes first
+        assert_eq!(results[0]["title"], "Async Programming in Rust");
+    }
+
+    #[tokio::test]
+    async fn test_search_normal_query_unaffected_by_empty_query_setting() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, _space_id, _doc1_id) = create_test_data(&pool).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(search_service::models::EmptyQueryBehavior::Recent))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=Async")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        let results = json["data"]["results"].as_array().expect("Results should be array");
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["title"], "Async Programming in Rust");
+    }
+
+    // Archive a document and add an editor (not owner) to its space
+    async fn archive_and_add_editor(pool: &Pool<Postgres>, space_id: Uuid, document_id: Uuid) -> Uuid {
+        sqlx::query("UPDATE documents SET is_archived = true WHERE id = $1")
+            .bind(document_id)
+            .execute(pool)
+            .await
+            .expect("Failed to archive document");
+
+        let editor_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO users (id, email, password_hash, display_name, is_active)
+            VALUES ($1, $2, $3, $4, true)
+            "#
+        )
+        .bind(editor_id)
+        .bind(format!("editor_{}@example.com", editor_id))
+        .bind("$2b$12$LQv3c1yqBWVHxkd0LHAkCOYz6TtxMQJqhN8/X4aYJGYxMnC6C5.Oy")
+        .bind("Editor")
+        .execute(pool)
+        .await
+        .expect("Failed to create editor");
+
+        sqlx::query(
+            r#"
+            INSERT INTO space_memberships (id, space_id, user_id, role, invited_by)
+            VALUES ($1, $2, $3, 'editor', $3)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(space_id)
+        .bind(editor_id)
+        .execute(pool)
+        .await
+        .expect("Failed to add editor");
+
+        editor_id
+    }
+
+    #[tokio::test]
+    async fn test_search_excludes_archived_documents_by_default() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, space_id, doc1_id) = create_test_data(&pool).await;
+        archive_and_add_editor(&pool, space_id, doc1_id).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        let results = json["data"]["results"].as_array().expect("Results should be array");
+
+        assert!(results.iter().all(|r| r["document_id"] != doc1_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_search_owner_can_include_archived_documents() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, space_id, doc1_id) = create_test_data(&pool).await;
+        archive_and_add_editor(&pool, space_id, doc1_id).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust&include_archived=true")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let body = test::read_body(resp).await;
+        let json: serde_json::Value = serde_json::from_slice(&body).expect("Invalid JSON");
+        let results = json["data"]["results"].as_array().expect("Results should be array");
+
+        let archived = results
+            .iter()
+            .find(|r| r["document_id"] == doc1_id.to_string())
+            .expect("Archived document should be included");
+        assert_eq!(archived["is_archived"], true);
+        assert!(results
+            .iter()
+            .filter(|r| r["document_id"] != doc1_id.to_string())
+            .all(|r| r["is_archived"] == false));
+    }
+
+    #[tokio::test]
+    async fn test_search_include_archived_forbidden_for_non_owner() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (_user_id, space_id, doc1_id) = create_test_data(&pool).await;
+        let editor_id = archive_and_add_editor(&pool, space_id, doc1_id).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust&include_archived=true")
+            .header("X-User-Id", editor_id.to_string())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    // Tag documents for facet tests; `tags` pairs a document with one tag
+    async fn tag_documents(pool: &Pool<Postgres>, tags: &[(Uuid, &str)]) {
+        for (document_id, tag) in tags {
+            sqlx::query("INSERT INTO document_tags (document_id, tag) VALUES ($1, $2)")
+                .bind(document_id)
+                .bind(tag)
+                .execute(pool)
+                .await
+                .expect("Failed to tag document");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_facets_count_only_accessible_matches() {
+        use search_service::models::Facet;
+        use search_service::repository::SearchRepository;
+
+        let pool = setup_test_db().await;
+        let (user_id, space_id, doc1_id) = create_test_data(&pool).await;
+        let doc2_id: Uuid = sqlx::query_scalar("SELECT id FROM documents WHERE space_id = $1 AND id <> $2")
+            .bind(space_id)
+            .bind(doc1_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to find second document");
+        tag_documents(&pool, &[(doc1_id, "rust"), (doc1_id, "intro"), (doc2_id, "rust")]).await;
+
+        // A matching, tagged document in a space the user can't see
+        let (_other_user, other_space, other_doc) = create_test_data(&pool).await;
+        tag_documents(&pool, &[(other_doc, "rust")]).await;
+
+        let repo = SearchRepository::new(Arc::new(pool));
+        let counts = repo
+            .facets(user_id, Some("Rust"), None, false, &[Facet::Space, Facet::Tag])
+            .await
+            .expect("Facet query failed");
+
+        let spaces = counts.space.expect("Space facet requested");
+        assert_eq!(spaces.len(), 1);
+        assert_eq!(spaces[0].space_id, space_id);
+        assert_eq!(spaces[0].count, 2);
+        assert!(spaces.iter().all(|s| s.space_id != other_space));
+
+        let tags = counts.tag.expect("Tag facet requested");
+        let rust = tags.iter().find(|t| t.tag == "rust").expect("rust tag counted");
+        assert_eq!(rust.count, 2);
+        let intro = tags.iter().find(|t| t.tag == "intro").expect("intro tag counted");
+        assert_eq!(intro.count, 1);
+
+        // Only the requested facets are computed
+        let counts = repo
+            .facets(user_id, Some("Async"), None, false, &[Facet::Tag])
+            .await
+            .expect("Facet query failed");
+        assert!(counts.space.is_none());
+        let tags = counts.tag.expect("Tag facet requested");
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].tag, "rust");
+        assert_eq!(tags[0].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_skips_soft_deleted_spaces() {
+        use search_service::models::Facet;
+        use search_service::repository::{SearchRepository, SearchRepositoryTrait};
+
+        let pool = setup_test_db().await;
+        let (user_id, space_id, _doc1_id) = create_test_data(&pool).await;
+        sqlx::query("UPDATE spaces SET is_deleted = true, deleted_at = NOW() WHERE id = $1")
+            .bind(space_id)
+            .execute(&pool)
+            .await
+            .expect("Failed to delete space");
+
+        let repo = SearchRepository::new(Arc::new(pool));
+        let (results, total) = repo.search(user_id, "Rust", None, 10, 0, false).await.expect("Search failed");
+        assert!(results.is_empty());
+        assert_eq!(total, 0);
+
+        let (recent, total) = repo.recent(user_id, None, 10, 0).await.expect("Recent failed");
+        assert!(recent.is_empty());
+        assert_eq!(total, 0);
+
+        assert!(repo.suggest(user_id, "Rust", None).await.expect("Suggest failed").is_empty());
+        assert!(!repo.can_include_archived(user_id, Some(&space_id.to_string())).await.unwrap());
+
+        let counts = repo
+            .facets(user_id, Some("Rust"), None, false, &[Facet::Space])
+            .await
+            .expect("Facet query failed");
+        assert!(counts.space.expect("Space facet requested").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_facets_returned_only_when_requested() {
+        let pool = setup_test_db().await;
+        let pool = Arc::new(pool);
+
+        let (user_id, space_id, doc1_id) = create_test_data(&pool).await;
+        tag_documents(&pool, &[(doc1_id, "rust")]).await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .configure(search_service::config)
+        ).await;
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let json: serde_json::Value = test::read_body_json(resp).await;
+        assert!(json["data"].get("facets").is_none());
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust&limit=1&facets=space,tag")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let json: serde_json::Value = test::read_body_json(resp).await;
+
+        // Counts cover every match, not just the returned page
+        assert_eq!(json["data"]["results"].as_array().unwrap().len(), 1);
+        assert_eq!(json["data"]["facets"]["space"][0]["id"], space_id.to_string());
+        assert_eq!(json["data"]["facets"]["space"][0]["count"], 2);
+        assert_eq!(json["data"]["facets"]["tag"][0]["name"], "rust");
+        assert_eq!(json["data"]["facets"]["tag"][0]["count"], 1);
+
+        let req = TestRequest::get()
+            .uri("/search?q=Rust&facets=author")
+            .header("X-User-Id", user_id.to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
 }
@@ -1,4 +1,6 @@
-use crate::models::{CreateSpaceRequest, Space, SpaceMembership, AddMemberRequest, UpdateMemberRequest};
+use crate::models::{
+    AddMemberRequest, CreateSpaceRequest, Space, SpaceMemberListResponse, SpaceMembership, UpdateMemberRequest,
+};
 use crate::helpers::{TestApp, generate_test_jwt_token};
 use actix_web::{test, web};
 
@@ -53,10 +55,67 @@ async fn test_list_space_members() {
     assert_eq!(resp.status(), 200);
 
     let body = test::read_body(resp).await;
-    let members: Vec<SpaceMembership> = serde_json::from_slice(&body).unwrap();
-    assert_eq!(members.len(), 1);
-    assert_eq!(members[0].user_id, owner.id.to_string());
-    assert_eq!(members[0].role, "owner");
+    let page: SpaceMemberListResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(page.members.len(), 1);
+    assert_eq!(page.members[0].user_id, owner.id.to_string());
+    assert_eq!(page.members[0].role, "owner");
+    assert!(!page.has_more);
+    assert_eq!(page.next_offset, None);
+}
+
+#[actix_rt::test]
+async fn test_list_space_members_pages() {
+    let test_app = TestApp::create().await;
+    let app = test::init_service(
+        actix_web::App::new()
+            .app_data(web::Data::new(test_app.pool.clone()))
+            .configure(miniwiki_backend::routes::config)
+    ).await;
+
+    let owner = test_app.create_test_user().await;
+    let token = generate_test_jwt_token(owner.id, &owner.email);
+
+    let create_req = CreateSpaceRequest {
+        name: "Paged Space".to_string(),
+        icon: None,
+        description: None,
+        is_public: false,
+    };
+    let req = test::TestRequest::post()
+        .uri("/api/v1/spaces")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(&create_req)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let space: Space = test::read_body_json(resp).await;
+    let space_id = uuid::Uuid::parse_str(&space.id).unwrap();
+
+    for _ in 0..2 {
+        let member = test_app.create_test_user().await;
+        test_app.add_space_member(&space_id, &member.id, "viewer").await;
+    }
+
+    let list = |query: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/spaces/{}/members?{}", space.id, query))
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, list("limit=2")).await;
+    assert_eq!(resp.status(), 200);
+    let page: SpaceMemberListResponse = test::read_body_json(resp).await;
+    assert_eq!(page.members.len(), 2);
+    assert!(page.has_more);
+    assert_eq!(page.next_offset, Some(2));
+
+    // The last page holds exactly the remaining member
+    let resp = test::call_service(&app, list("limit=2&offset=2")).await;
+    let page: SpaceMemberListResponse = test::read_body_json(resp).await;
+    assert_eq!(page.members.len(), 1);
+    assert!(!page.has_more);
+    assert_eq!(page.next_offset, None);
 }
 
 #[actix_rt::test]
@@ -225,4 +284,72 @@ async fn test_update_member_role() {
 
     let updated: SpaceMembership = serde_json::from_slice(&body).unwrap();
     assert_eq!(updated.role, "viewer");
-}
\ No newline at end of file
+
+    // The add and the role change are both in the space audit log
+    let entries: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT action, old_role, new_role FROM space_audit_log WHERE space_id = $1 AND actor_id = $2 \
+         ORDER BY created_at, action",
+    )
+    .bind(space.id.parse::<uuid::Uuid>().unwrap())
+    .bind(owner.id)
+    .fetch_all(&test_app.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            ("member_added".to_string(), None, Some("editor".to_string())),
+            ("role_changed".to_string(), Some("editor".to_string()), Some("viewer".to_string())),
+        ]
+    );
+}
+
+#[actix_rt::test]
+async fn test_add_member_respects_member_limit() {
+    let test_app = TestApp::create().await;
+    let app = test::init_service(
+        actix_web::App::new()
+            .app_data(web::Data::new(test_app.pool.clone()))
+            .app_data(web::Data::new(space_service::repository::SpaceMemberLimit(2)))
+            .configure(miniwiki_backend::routes::config)
+    ).await;
+
+    let owner = test_app.create_test_user().await;
+    let token = generate_test_jwt_token(owner.id, &owner.email);
+
+    let create_req = CreateSpaceRequest {
+        name: "Capped Space".to_string(),
+        icon: None,
+        description: None,
+        is_public: false,
+    };
+
+    let create_resp = test::TestRequest::post()
+        .uri("/api/v1/spaces")
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(&create_req)
+        .to_request();
+    let resp = test::call_service(&app, create_resp).await;
+    assert_eq!(resp.status(), 201);
+
+    let body = test::read_body(resp).await;
+    let space: Space = serde_json::from_slice(&body).unwrap();
+
+    // The owner plus one member fill the space
+    for expected in [201, 409] {
+        let member = test_app.create_test_user().await;
+        let add_req = AddMemberRequest {
+            user_id: member.id.to_string(),
+            role: "viewer".to_string(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/v1/spaces/{}/members", space.id))
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .set_json(&add_req)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), expected);
+    }
+}
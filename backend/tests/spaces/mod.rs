@@ -1,3 +1,4 @@
 pub mod spaces_test;
 pub mod memberships_test;
 pub mod integration_test;
+pub mod soft_delete_test;
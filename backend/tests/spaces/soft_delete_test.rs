@@ -0,0 +1,97 @@
+//! Shared soft-delete lifecycle tests
+//!
+//! Runs the same delete → restore → purge sequence on documents and spaces
+//! through `shared_database::soft_delete`, and checks that a deleted space
+//! drops out of lookups until it is restored.
+//!
+//! Run with: cargo test --test lib spaces::soft_delete_test
+//! Note: Requires a migrated database at DATABASE_URL
+
+use crate::helpers::TestApp;
+use document_service::repository::{DocumentRepository, DocumentRow};
+use shared_database::soft_delete::{self, SoftDeletable};
+use space_service::models::Space;
+use space_service::repository::SpaceRepository;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Walk one live row through the whole lifecycle
+async fn assert_lifecycle<T: SoftDeletable>(pool: &PgPool, id: Uuid) {
+    let entity = T::TABLE;
+    assert_eq!(soft_delete::is_deleted::<T>(pool, id).await.unwrap(), Some(false), "{}", entity);
+    assert!(!soft_delete::purge::<T>(pool, id).await.unwrap(), "{}: live rows are not purged", entity);
+    assert!(!soft_delete::restore::<T>(pool, id).await.unwrap(), "{}: live rows are not restored", entity);
+
+    assert!(soft_delete::soft_delete::<T>(pool, id).await.unwrap(), "{}", entity);
+    assert!(!soft_delete::soft_delete::<T>(pool, id).await.unwrap(), "{}: second delete is a no-op", entity);
+    assert_eq!(soft_delete::is_deleted::<T>(pool, id).await.unwrap(), Some(true), "{}", entity);
+
+    assert!(soft_delete::restore::<T>(pool, id).await.unwrap(), "{}", entity);
+    assert!(!soft_delete::restore::<T>(pool, id).await.unwrap(), "{}: second restore is a no-op", entity);
+    assert_eq!(soft_delete::is_deleted::<T>(pool, id).await.unwrap(), Some(false), "{}", entity);
+
+    assert!(soft_delete::soft_delete::<T>(pool, id).await.unwrap(), "{}", entity);
+    assert!(soft_delete::purge::<T>(pool, id).await.unwrap(), "{}", entity);
+    assert_eq!(soft_delete::is_deleted::<T>(pool, id).await.unwrap(), None, "{}: purged rows are gone", entity);
+    assert!(!soft_delete::restore::<T>(pool, id).await.unwrap(), "{}: purged rows cannot be restored", entity);
+}
+
+#[tokio::test]
+async fn test_documents_and_spaces_share_lifecycle() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = repo
+        .create(&space.id.to_string(), None, "Lifecycle", None, None, owner.id)
+        .await
+        .expect("Failed to create document");
+
+    assert_lifecycle::<DocumentRow>(&app.pool, document.id).await;
+    assert_lifecycle::<Space>(&app.pool, space.id).await;
+}
+
+#[tokio::test]
+async fn test_deleted_space_is_hidden_until_restored() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+
+    assert!(SpaceRepository::delete(&app.pool, space.id).await.unwrap());
+    assert!(SpaceRepository::find_by_id(&app.pool, space.id).await.unwrap().is_none());
+    assert!(SpaceRepository::list_by_user(&app.pool, owner.id)
+        .await
+        .unwrap()
+        .iter()
+        .all(|s| s.id != space.id));
+    assert!(!repo.check_space_access(&space.id.to_string(), owner.id).await.unwrap());
+
+    assert!(soft_delete::restore::<Space>(&app.pool, space.id).await.unwrap());
+    assert!(SpaceRepository::find_by_id(&app.pool, space.id).await.unwrap().is_some());
+    assert!(repo.check_space_access(&space.id.to_string(), owner.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_documents_in_deleted_space_are_not_accessible() {
+    let app = TestApp::create().await;
+    let repo = DocumentRepository::new(app.pool.clone());
+    let owner = app.create_test_user().await;
+    let space = app.create_test_space_for_user(&owner.id).await;
+    let document = repo
+        .create(&space.id.to_string(), None, "Hidden", None, None, owner.id)
+        .await
+        .expect("Failed to create document");
+    let document_id = document.id.to_string();
+    repo.add_favorite(&document_id, owner.id).await.unwrap();
+
+    assert!(SpaceRepository::delete(&app.pool, space.id).await.unwrap());
+    assert!(!repo.check_document_access(&document_id, owner.id).await.unwrap());
+    assert!(repo.get_document_role(&document_id, owner.id).await.unwrap().is_none());
+    assert_eq!(repo.list_favorites(owner.id, 20, 0).await.unwrap().1, 0);
+
+    assert!(soft_delete::restore::<Space>(&app.pool, space.id).await.unwrap());
+    assert!(repo.check_document_access(&document_id, owner.id).await.unwrap());
+    assert!(repo.get_document_role(&document_id, owner.id).await.unwrap().is_some());
+    assert_eq!(repo.list_favorites(owner.id, 20, 0).await.unwrap().1, 1);
+}